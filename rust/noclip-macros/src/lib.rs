@@ -93,6 +93,64 @@ pub fn derive_from_enum(item: proc_macro::TokenStream) -> proc_macro::TokenStrea
     impls.into()
 }
 
+// Lists a deku struct's fields (with their types and any `cond`), so the
+// parser can be checked against the type trees some asset files carry
+#[proc_macro_derive(ParserSchema)]
+pub fn derive_parser_schema(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(item as syn::DeriveInput);
+
+    let struct_identifier = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let mut field_entries = proc_macro2::TokenStream::new();
+
+    match &input.data {
+        Data::Struct(syn::DataStruct { fields, .. }) => {
+            for field in fields {
+                let name = field.ident.as_ref().unwrap().to_string();
+                let ty = &field.ty;
+                let type_name = quote!(#ty).to_string().replace(' ', "");
+                let mut cond = None;
+                for attr in &field.attrs {
+                    if !attr.path().is_ident("deku") {
+                        continue;
+                    }
+                    let _ = attr.parse_nested_meta(|meta| {
+                        if meta.input.peek(syn::Token![=]) {
+                            let value: syn::Expr = meta.value()?.parse()?;
+                            if let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(value), .. }) = value {
+                                if meta.path.is_ident("cond") {
+                                    cond = Some(value.value());
+                                }
+                            }
+                        }
+                        Ok(())
+                    });
+                }
+                let cond = match cond {
+                    Some(cond) => quote!{ Some(#cond) },
+                    None => quote!{ None },
+                };
+                field_entries.extend(quote!{
+                    crate::unity::types::schema::ParserField {
+                        name: #name,
+                        type_name: #type_name,
+                        cond: #cond,
+                    },
+                });
+            }
+        },
+        _ => unimplemented!(),
+    }
+
+    quote!{
+        impl #impl_generics crate::unity::types::schema::ParserSchema for #struct_identifier #type_generics #where_clause {
+            fn parser_fields() -> &'static [crate::unity::types::schema::ParserField] {
+                &[#field_entries]
+            }
+        }
+    }.into()
+}
+
 #[proc_macro_attribute]
 pub fn from(attr: proc_macro::TokenStream, _: proc_macro::TokenStream) -> proc_macro::TokenStream {
     attr
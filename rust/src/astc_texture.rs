@@ -0,0 +1,833 @@
+use std::convert::TryInto;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::half::{f16_to_f32, f32_to_f16};
+
+// Software ASTC decoding, for GPUs without ASTC support. Handles 2D blocks in
+// both the LDR and HDR profiles; the LDR profile decodes to RGBA8, and the
+// HDR profile to half float RGBA, since HDR endpoints hold values far above
+// 1.0 (and half floats, unlike full ones, are filterable everywhere).
+// Blocks that fail to decode, or that use HDR endpoints in the LDR profile,
+// come out magenta.
+
+const BLOCK_MAX_TEXELS: usize = 144;
+const BLOCK_MAX_WEIGHTS: u32 = 64;
+const BLOCK_MIN_WEIGHT_BITS: u32 = 24;
+const BLOCK_MAX_WEIGHT_BITS: u32 = 96;
+const MAX_COLOR_VALUES: usize = 18;
+// color endpoints need at least 6 quantization levels
+const MIN_COLOR_QUANT: usize = 4;
+const VOID_EXTENT_MODE: u32 = 0x1FC;
+// 1.0 as an HDR endpoint value, for the HDR modes without alpha
+const HDR_ONE: i32 = 0x7800;
+const ERROR_COLOR: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Digits {
+    None,
+    Trit,
+    Quint,
+}
+
+// The integer sequence encoding of each quantization level, from 2 levels
+// up to 256: whether each value has a trit or quint, and its number of bits
+const QUANT_LEVELS: [(Digits, u32); 21] = [
+    (Digits::None, 1), (Digits::Trit, 0), (Digits::None, 2), (Digits::Quint, 0),
+    (Digits::Trit, 1), (Digits::None, 3), (Digits::Quint, 1), (Digits::Trit, 2),
+    (Digits::None, 4), (Digits::Quint, 2), (Digits::Trit, 3), (Digits::None, 5),
+    (Digits::Quint, 3), (Digits::Trit, 4), (Digits::None, 6), (Digits::Quint, 4),
+    (Digits::Trit, 5), (Digits::None, 7), (Digits::Quint, 5), (Digits::Trit, 6),
+    (Digits::None, 8),
+];
+
+fn read_bits(data: u128, start: u32, count: u32) -> u32 {
+    if start >= 128 || count == 0 {
+        return 0;
+    }
+    ((data >> start) & ((1 << count) - 1)) as u32
+}
+
+fn replicate(value: u32, from: u32, to: u32) -> u32 {
+    if from == 0 {
+        return 0;
+    }
+    let mut result = 0;
+    let mut shift = to as i32;
+    while shift > 0 {
+        shift -= from as i32;
+        result |= if shift >= 0 { value << shift } else { value >> -shift };
+    }
+    result
+}
+
+fn ise_bit_count(count: u32, quant: usize) -> u32 {
+    let (digits, bits) = QUANT_LEVELS[quant];
+    match digits {
+        Digits::None => count * bits,
+        Digits::Trit => count * bits + (8 * count).div_ceil(5),
+        Digits::Quint => count * bits + (7 * count).div_ceil(3),
+    }
+}
+
+// Five trits are packed into 8 bits
+fn decode_trits(t: u32) -> [u32; 5] {
+    let (c, t3, t4) = if (t >> 2) & 7 == 7 {
+        (((t >> 5) & 7) << 2 | (t & 3), 2, 2)
+    } else if (t >> 5) & 3 == 3 {
+        (t & 0x1F, (t >> 7) & 1, 2)
+    } else {
+        (t & 0x1F, (t >> 5) & 3, (t >> 7) & 1)
+    };
+    let (t0, t1, t2) = if c & 3 == 3 {
+        let c3 = (c >> 3) & 1;
+        ((c3 << 1) | ((c >> 2) & 1 & !c3), (c >> 4) & 1, 2)
+    } else if (c >> 2) & 3 == 3 {
+        (c & 3, 2, 2)
+    } else {
+        let c1 = (c >> 1) & 1;
+        ((c1 << 1) | (c & 1 & !c1), (c >> 2) & 3, (c >> 4) & 1)
+    };
+    [t0, t1, t2, t3, t4]
+}
+
+// Three quints are packed into 7 bits
+fn decode_quints(q: u32) -> [u32; 3] {
+    if (q >> 1) & 3 == 3 && (q >> 5) & 3 == 0 {
+        let q0 = q & 1;
+        let q2 = (q0 << 2) | (((q >> 4) & 1 & !q0) << 1) | ((q >> 3) & 1 & !q0);
+        return [4, 4, q2];
+    }
+    let (c, q2) = if (q >> 1) & 3 == 3 {
+        (((q >> 3) & 3) << 3 | ((!q >> 5) & 3) << 1 | (q & 1), 4)
+    } else {
+        (q & 0x1F, (q >> 5) & 3)
+    };
+    if c & 7 == 5 {
+        [(c >> 3) & 3, 4, q2]
+    } else {
+        [c & 7, (c >> 3) & 3, q2]
+    }
+}
+
+// Returns each value as its trit or quint above its bits. Anything past the
+// end of the sequence reads as zero, as the encoding requires.
+fn decode_ise(data: u128, start: u32, count: u32, quant: usize) -> Vec<u32> {
+    let (digits, bits) = QUANT_LEVELS[quant];
+    let end = start + ise_bit_count(count, quant);
+    let mut pos = start;
+    let mut read = |n: u32| {
+        let value = if pos < end { read_bits(data, pos, n.min(end - pos)) } else { 0 };
+        pos += n;
+        value
+    };
+    let mut values = Vec::with_capacity(count as usize + 4);
+    while (values.len() as u32) < count {
+        match digits {
+            Digits::None => values.push(read(bits)),
+            Digits::Trit => {
+                let mut m = [0; 5];
+                let mut t = 0;
+                // the trit bits are interleaved between the values' bits
+                for (i, &(shift, size)) in [(0, 2), (2, 2), (4, 1), (5, 2), (7, 1)].iter().enumerate() {
+                    m[i] = read(bits);
+                    t |= read(size) << shift;
+                }
+                values.extend(decode_trits(t).iter().zip(m).map(|(&trit, m)| (trit << bits) | m));
+            },
+            Digits::Quint => {
+                let mut m = [0; 3];
+                let mut q = 0;
+                for (i, &(shift, size)) in [(0, 3), (3, 2), (5, 2)].iter().enumerate() {
+                    m[i] = read(bits);
+                    q |= read(size) << shift;
+                }
+                values.extend(decode_quints(q).iter().zip(m).map(|(&quint, m)| (quint << bits) | m));
+            },
+        }
+    }
+    values.truncate(count as usize);
+    values
+}
+
+// Unquantizes to 0..=255, following the spec's bit-shuffling tables so the
+// trit and quint levels land where the encoder expects
+fn unquantize_color(value: u32, quant: usize) -> i32 {
+    let (digits, bits) = QUANT_LEVELS[quant];
+    if digits == Digits::None {
+        return replicate(value, bits, 8) as i32;
+    }
+    let d = value >> bits;
+    let bit = |i: u32| (value >> i) & 1;
+    let a = if bit(0) != 0 { 0x1FF } else { 0 };
+    let (b, c) = match (digits, bits) {
+        (Digits::Trit, 1) => (0, 204),
+        (Digits::Trit, 2) => (bit(1) << 8 | bit(1) << 4 | bit(1) << 2 | bit(1) << 1, 93),
+        (Digits::Trit, 3) => (bit(2) << 8 | bit(1) << 7 | bit(2) << 3 | bit(1) << 2 | bit(2) << 1 | bit(1), 44),
+        (Digits::Trit, 4) => (bit(3) << 8 | bit(2) << 7 | bit(1) << 6 | bit(3) << 2 | bit(2) << 1 | bit(1), 22),
+        (Digits::Trit, 5) => (bit(4) << 8 | bit(3) << 7 | bit(2) << 6 | bit(1) << 5 | bit(4) << 1 | bit(3), 11),
+        (Digits::Trit, 6) => (bit(5) << 8 | bit(4) << 7 | bit(3) << 6 | bit(2) << 5 | bit(1) << 4 | bit(5), 5),
+        (Digits::Quint, 1) => (0, 113),
+        (Digits::Quint, 2) => (bit(1) << 8 | bit(1) << 3 | bit(1) << 2, 54),
+        (Digits::Quint, 3) => (bit(2) << 8 | bit(1) << 7 | bit(2) << 2 | bit(1) << 1 | bit(2), 26),
+        (Digits::Quint, 4) => (bit(3) << 8 | bit(2) << 7 | bit(1) << 6 | bit(3) << 1 | bit(2), 13),
+        (Digits::Quint, 5) => (bit(4) << 8 | bit(3) << 7 | bit(2) << 6 | bit(1) << 5 | bit(4), 6),
+        _ => unreachable!(),
+    };
+    let t = (d * c + b) ^ a;
+    ((a & 0x80) | (t >> 2)) as i32
+}
+
+// Unquantizes to 0..=64
+fn unquantize_weight(value: u32, quant: usize) -> i32 {
+    let (digits, bits) = QUANT_LEVELS[quant];
+    let d = value >> bits;
+    let bit = |i: u32| (value >> i) & 1;
+    let a = if bit(0) != 0 { 0x7F } else { 0 };
+    let t = match (digits, bits) {
+        (Digits::None, _) => replicate(value, bits, 6),
+        (Digits::Trit, 0) => [0, 32, 63][d as usize],
+        (Digits::Quint, 0) => [0, 16, 32, 47, 63][d as usize],
+        _ => {
+            let (b, c) = match (digits, bits) {
+                (Digits::Trit, 1) => (0, 50),
+                (Digits::Trit, 2) => (bit(1) << 6 | bit(1) << 2 | bit(1), 23),
+                (Digits::Trit, 3) => (bit(2) << 6 | bit(1) << 5 | bit(2) << 1 | bit(1), 11),
+                (Digits::Quint, 1) => (0, 28),
+                (Digits::Quint, 2) => (bit(1) << 6 | bit(1) << 1, 13),
+                _ => unreachable!(),
+            };
+            let t = (d * c + b) ^ a;
+            (a & 0x20) | (t >> 2)
+        },
+    };
+    if t > 32 { t as i32 + 1 } else { t as i32 }
+}
+
+struct BlockMode {
+    weights_wide: u32,
+    weights_high: u32,
+    dual_plane: bool,
+    weight_quant: usize,
+    weight_bits: u32,
+}
+
+fn decode_block_mode(mode: u32) -> Option<BlockMode> {
+    let mut quant = (mode >> 4) & 1;
+    let mut high_precision = (mode >> 9) & 1 != 0;
+    let mut dual_plane = (mode >> 10) & 1 != 0;
+    let a = (mode >> 5) & 3;
+    let (weights_wide, weights_high) = if mode & 3 != 0 {
+        quant |= (mode & 3) << 1;
+        let b = (mode >> 7) & 3;
+        match (mode >> 2) & 3 {
+            0 => (b + 4, a + 2),
+            1 => (b + 8, a + 2),
+            2 => (a + 2, b + 8),
+            _ if mode & 0x100 != 0 => ((b & 1) + 2, a + 2),
+            _ => (a + 2, (b & 1) + 6),
+        }
+    } else {
+        quant |= ((mode >> 2) & 3) << 1;
+        if (mode >> 2) & 3 == 0 {
+            return None;
+        }
+        let b = (mode >> 9) & 3;
+        match (mode >> 7) & 3 {
+            0 => (12, a + 2),
+            1 => (a + 2, 12),
+            2 => {
+                high_precision = false;
+                dual_plane = false;
+                (a + 6, b + 6)
+            },
+            _ => match a {
+                0 => (6, 10),
+                1 => (10, 6),
+                _ => return None,
+            },
+        }
+    };
+    let weight_quant = (quant - 2 + if high_precision { 6 } else { 0 }) as usize;
+    let weight_count = weights_wide * weights_high * if dual_plane { 2 } else { 1 };
+    let weight_bits = ise_bit_count(weight_count, weight_quant);
+    if weight_count > BLOCK_MAX_WEIGHTS || !(BLOCK_MIN_WEIGHT_BITS..=BLOCK_MAX_WEIGHT_BITS).contains(&weight_bits) {
+        return None;
+    }
+    Some(BlockMode { weights_wide, weights_high, dual_plane, weight_quant, weight_bits })
+}
+
+fn hash52(seed: u32) -> u32 {
+    let mut p = seed;
+    p ^= p >> 15;
+    p = p.wrapping_sub(p << 17);
+    p = p.wrapping_add(p << 7);
+    p = p.wrapping_add(p << 4);
+    p ^= p >> 5;
+    p = p.wrapping_add(p << 16);
+    p ^= p >> 7;
+    p ^= p >> 3;
+    p ^= p << 6;
+    p ^= p >> 17;
+    p
+}
+
+// Partition assignments aren't stored; both sides derive them from the seed
+fn select_partition(seed: u32, x: u32, y: u32, partition_count: u32, small_block: bool) -> usize {
+    let (x, y) = if small_block { (x << 1, y << 1) } else { (x, y) };
+    let seed = seed + (partition_count - 1) * 1024;
+    let rnum = hash52(seed);
+    let mut seeds = [
+        rnum, rnum >> 4, rnum >> 8, rnum >> 12, rnum >> 16, rnum >> 20, rnum >> 24, rnum >> 28,
+        rnum >> 18, rnum >> 22, rnum >> 26, rnum.rotate_left(2),
+    ];
+    let (sh1, sh2) = if seed & 1 != 0 {
+        (if seed & 2 != 0 { 4 } else { 5 }, if partition_count == 3 { 6 } else { 5 })
+    } else {
+        (if partition_count == 3 { 6 } else { 5 }, if seed & 2 != 0 { 4 } else { 5 })
+    };
+    let sh3 = if seed & 0x10 != 0 { sh1 } else { sh2 };
+    for (i, s) in seeds.iter_mut().enumerate() {
+        let shift = if i >= 8 { sh3 } else if i % 2 == 0 { sh1 } else { sh2 };
+        *s = ((*s & 0xF) * (*s & 0xF)) >> shift;
+    }
+    // 2D, so the z terms drop out
+    let a = (seeds[0] * x + seeds[1] * y + (rnum >> 14)) & 0x3F;
+    let b = (seeds[2] * x + seeds[3] * y + (rnum >> 10)) & 0x3F;
+    let c = if partition_count < 3 { 0 } else { (seeds[4] * x + seeds[5] * y + (rnum >> 6)) & 0x3F };
+    let d = if partition_count < 4 { 0 } else { (seeds[6] * x + seeds[7] * y + (rnum >> 2)) & 0x3F };
+    if a >= b && a >= c && a >= d {
+        0
+    } else if b >= c && b >= d {
+        1
+    } else if c >= d {
+        2
+    } else {
+        3
+    }
+}
+
+// Endpoint components are 16 bits: LDR ones are unorm, HDR ones are in
+// ASTC's pseudo-logarithmic encoding
+struct Endpoints {
+    e0: [i32; 4],
+    e1: [i32; 4],
+    rgb_hdr: bool,
+    alpha_hdr: bool,
+}
+
+fn bit_transfer_signed(a: i32, b: i32) -> (i32, i32) {
+    let b = (b >> 1) | (a & 0x80);
+    let a = (a >> 1) & 0x3F;
+    (if a & 0x20 != 0 { a - 0x40 } else { a }, b)
+}
+
+fn blue_contract(r: i32, g: i32, b: i32, a: i32) -> [i32; 4] {
+    [(r + b) >> 1, (g + b) >> 1, b, a]
+}
+
+fn decode_ldr_endpoints(cem: u32, v: &[i32]) -> ([i32; 4], [i32; 4]) {
+    match cem {
+        // luminance
+        0 => ([v[0], v[0], v[0], 0xFF], [v[1], v[1], v[1], 0xFF]),
+        1 => {
+            let l0 = (v[0] >> 2) | (v[1] & 0xC0);
+            let l1 = (l0 + (v[1] & 0x3F)).min(0xFF);
+            ([l0, l0, l0, 0xFF], [l1, l1, l1, 0xFF])
+        },
+        // luminance and alpha
+        4 => ([v[0], v[0], v[0], v[2]], [v[1], v[1], v[1], v[3]]),
+        5 => {
+            let (d0, l) = bit_transfer_signed(v[1], v[0]);
+            let (d1, a) = bit_transfer_signed(v[3], v[2]);
+            ([l, l, l, a], [l + d0, l + d0, l + d0, a + d1])
+        },
+        // RGB and RGBA, base and scale
+        6 | 10 => {
+            let (a0, a1) = if cem == 10 { (v[4], v[5]) } else { (0xFF, 0xFF) };
+            ([(v[0] * v[3]) >> 8, (v[1] * v[3]) >> 8, (v[2] * v[3]) >> 8, a0], [v[0], v[1], v[2], a1])
+        },
+        // RGB and RGBA, direct
+        8 | 12 => {
+            let (a0, a1) = if cem == 12 { (v[6], v[7]) } else { (0xFF, 0xFF) };
+            if v[1] + v[3] + v[5] >= v[0] + v[2] + v[4] {
+                ([v[0], v[2], v[4], a0], [v[1], v[3], v[5], a1])
+            } else {
+                (blue_contract(v[1], v[3], v[5], a1), blue_contract(v[0], v[2], v[4], a0))
+            }
+        },
+        // RGB and RGBA, base and offset
+        _ => {
+            let (d0, r) = bit_transfer_signed(v[1], v[0]);
+            let (d1, g) = bit_transfer_signed(v[3], v[2]);
+            let (d2, b) = bit_transfer_signed(v[5], v[4]);
+            let (d3, a) = if cem == 13 { bit_transfer_signed(v[7], v[6]) } else { (0, 0xFF) };
+            if d0 + d1 + d2 >= 0 {
+                ([r, g, b, a], [r + d0, g + d1, b + d2, a + d3])
+            } else {
+                (blue_contract(r + d0, g + d1, b + d2, a + d3), blue_contract(r, g, b, a))
+            }
+        },
+    }
+}
+
+fn decode_hdr_luminance_large_range(v: &[i32]) -> ([i32; 4], [i32; 4]) {
+    let (y0, y1) = if v[1] >= v[0] {
+        (v[0] << 4, v[1] << 4)
+    } else {
+        ((v[1] << 4) + 8, (v[0] << 4) - 8)
+    };
+    ([y0 << 4, y0 << 4, y0 << 4, HDR_ONE], [y1 << 4, y1 << 4, y1 << 4, HDR_ONE])
+}
+
+fn decode_hdr_luminance_small_range(v: &[i32]) -> ([i32; 4], [i32; 4]) {
+    let (y0, d) = if v[0] & 0x80 != 0 {
+        (((v[1] & 0xE0) << 4) | ((v[0] & 0x7F) << 2), (v[1] & 0x1F) << 2)
+    } else {
+        (((v[1] & 0xF0) << 4) | ((v[0] & 0x7F) << 1), (v[1] & 0x0F) << 1)
+    };
+    let y1 = (y0 + d).min(0xFFF);
+    ([y0 << 4, y0 << 4, y0 << 4, HDR_ONE], [y1 << 4, y1 << 4, y1 << 4, HDR_ONE])
+}
+
+// HDR RGB as a base color and a scale. The top bits of each value select
+// how many bits go to each component, and which component is the largest.
+fn decode_hdr_rgb_scale(v: &[i32]) -> ([i32; 4], [i32; 4]) {
+    let mode_value = ((v[0] & 0xC0) >> 6) | ((v[1] & 0x80) >> 5) | ((v[2] & 0x80) >> 4);
+    let (major, mode) = if mode_value & 0xC != 0xC {
+        (mode_value >> 2, mode_value & 3)
+    } else if mode_value != 0xF {
+        (mode_value & 3, 4)
+    } else {
+        (0, 5)
+    };
+    let mut red = v[0] & 0x3F;
+    let mut green = v[1] & 0x1F;
+    let mut blue = v[2] & 0x1F;
+    let mut scale = v[3] & 0x1F;
+    let bits = [(v[1] >> 6) & 1, (v[1] >> 5) & 1, (v[2] >> 6) & 1, (v[2] >> 5) & 1, (v[3] >> 7) & 1, (v[3] >> 6) & 1, (v[3] >> 5) & 1];
+    // which of the extra bits each mode spends where
+    let modes = 1 << mode;
+    if modes & 0x30 != 0 { green |= bits[0] << 6; }
+    if modes & 0x3A != 0 { green |= bits[1] << 5; }
+    if modes & 0x30 != 0 { blue |= bits[2] << 6; }
+    if modes & 0x3A != 0 { blue |= bits[3] << 5; }
+    if modes & 0x3D != 0 { scale |= bits[6] << 5; }
+    if modes & 0x2D != 0 { scale |= bits[5] << 6; }
+    if modes & 0x04 != 0 { scale |= bits[4] << 7; }
+    if modes & 0x3B != 0 { red |= bits[4] << 6; }
+    if modes & 0x04 != 0 { red |= bits[3] << 6; }
+    if modes & 0x10 != 0 { red |= bits[5] << 7; }
+    if modes & 0x0F != 0 { red |= bits[2] << 7; }
+    if modes & 0x05 != 0 { red |= bits[1] << 8; }
+    if modes & 0x0A != 0 { red |= bits[0] << 8; }
+    if modes & 0x05 != 0 { red |= bits[0] << 9; }
+    if modes & 0x02 != 0 { red |= bits[6] << 9; }
+    if modes & 0x01 != 0 { red |= bits[3] << 10; }
+    if modes & 0x02 != 0 { red |= bits[5] << 10; }
+
+    let shift = [1, 1, 2, 3, 4, 5][mode as usize];
+    red <<= shift;
+    green <<= shift;
+    blue <<= shift;
+    scale <<= shift;
+    // all but the last mode store green and blue relative to red
+    if mode != 5 {
+        green = red - green;
+        blue = red - blue;
+    }
+    match major {
+        1 => std::mem::swap(&mut red, &mut green),
+        2 => std::mem::swap(&mut red, &mut blue),
+        _ => {},
+    }
+    let clamp = |c: i32| c.clamp(0, 0xFFF) << 4;
+    (
+        [clamp(red - scale), clamp(green - scale), clamp(blue - scale), HDR_ONE],
+        [clamp(red), clamp(green), clamp(blue), HDR_ONE],
+    )
+}
+
+// HDR RGB as two colors, stored as a base and differences from it
+fn decode_hdr_rgb(v: &[i32]) -> ([i32; 4], [i32; 4]) {
+    let mode_value = ((v[1] & 0x80) >> 7) | ((v[2] & 0x80) >> 6) | ((v[3] & 0x80) >> 5);
+    let major = ((v[4] & 0x80) >> 7) | ((v[5] & 0x80) >> 6);
+    if major == 3 {
+        return (
+            [v[0] << 8, v[2] << 8, (v[4] & 0x7F) << 9, HDR_ONE],
+            [v[1] << 8, v[3] << 8, (v[5] & 0x7F) << 9, HDR_ONE],
+        );
+    }
+
+    let mut a = v[0] | ((v[1] & 0x40) << 2);
+    let mut b0 = v[2] & 0x3F;
+    let mut b1 = v[3] & 0x3F;
+    let mut c = v[1] & 0x3F;
+    let mut d0 = v[4] & 0x7F;
+    let mut d1 = v[5] & 0x7F;
+    let d_bits = [7, 6, 7, 6, 5, 6, 5, 6][mode_value as usize];
+    let bits = [(v[2] >> 6) & 1, (v[3] >> 6) & 1, (v[4] >> 6) & 1, (v[5] >> 6) & 1, (v[4] >> 5) & 1, (v[5] >> 5) & 1];
+    let modes = 1 << mode_value;
+    if modes & 0xA4 != 0 { a |= bits[0] << 9; }
+    if modes & 0x08 != 0 { a |= bits[2] << 9; }
+    if modes & 0x50 != 0 { a |= bits[4] << 9; }
+    if modes & 0x50 != 0 { a |= bits[5] << 10; }
+    if modes & 0xA0 != 0 { a |= bits[1] << 10; }
+    if modes & 0xC0 != 0 { a |= bits[2] << 11; }
+    if modes & 0x04 != 0 { c |= bits[1] << 6; }
+    if modes & 0xE8 != 0 { c |= bits[3] << 6; }
+    if modes & 0x20 != 0 { c |= bits[2] << 7; }
+    if modes & 0x5B != 0 {
+        b0 |= bits[0] << 6;
+        b1 |= bits[1] << 6;
+    }
+    if modes & 0x12 != 0 {
+        b0 |= bits[2] << 7;
+        b1 |= bits[3] << 7;
+    }
+    if modes & 0xAF != 0 {
+        d0 |= bits[4] << 5;
+        d1 |= bits[5] << 5;
+    }
+    if modes & 0x05 != 0 {
+        d0 |= bits[2] << 6;
+        d1 |= bits[3] << 6;
+    }
+    // the differences are signed
+    let sign_extend = |d: i32| (d << (32 - d_bits)) >> (32 - d_bits);
+    let shift = (mode_value >> 1) ^ 3;
+    let (a, b0, b1, c) = (a << shift, b0 << shift, b1 << shift, c << shift);
+    let (d0, d1) = (sign_extend(d0) << shift, sign_extend(d1) << shift);
+
+    let mut e0 = [a - c, a - b0 - c - d0, a - b1 - c - d1];
+    let mut e1 = [a, a - b0, a - b1];
+    if major == 1 || major == 2 {
+        e0.swap(0, major as usize);
+        e1.swap(0, major as usize);
+    }
+    let clamp = |c: i32| c.clamp(0, 0xFFF) << 4;
+    (
+        [clamp(e0[0]), clamp(e0[1]), clamp(e0[2]), HDR_ONE],
+        [clamp(e1[0]), clamp(e1[1]), clamp(e1[2]), HDR_ONE],
+    )
+}
+
+fn decode_hdr_alpha(v6: i32, v7: i32) -> (i32, i32) {
+    let selector = ((v6 >> 7) & 1) | ((v7 >> 6) & 2);
+    let (v6, v7) = (v6 & 0x7F, v7 & 0x7F);
+    let (a0, a1) = if selector == 3 {
+        (v6 << 5, v7 << 5)
+    } else {
+        let v6 = (v6 | ((v7 << (selector + 1)) & 0x780)) << (4 - selector);
+        let v7 = (((v7 & (0x3F >> selector)) ^ (32 >> selector)) - (32 >> selector)) << (4 - selector);
+        (v6, (v6 + v7).clamp(0, 0xFFF))
+    };
+    (a0 << 4, a1 << 4)
+}
+
+fn decode_endpoints(cem: u32, v: &[i32]) -> Endpoints {
+    let (rgb_hdr, alpha_hdr) = match cem {
+        2 | 3 | 7 | 11 | 15 => (true, true),
+        14 => (true, false),
+        _ => (false, false),
+    };
+    let (mut e0, mut e1) = match cem {
+        2 => decode_hdr_luminance_large_range(v),
+        3 => decode_hdr_luminance_small_range(v),
+        7 => decode_hdr_rgb_scale(v),
+        11 | 14 | 15 => {
+            let (mut e0, mut e1) = decode_hdr_rgb(v);
+            if cem == 14 {
+                e0[3] = v[6];
+                e1[3] = v[7];
+            } else if cem == 15 {
+                let (a0, a1) = decode_hdr_alpha(v[6], v[7]);
+                e0[3] = a0;
+                e1[3] = a1;
+            }
+            (e0, e1)
+        },
+        _ => decode_ldr_endpoints(cem, v),
+    };
+    // expand LDR components to 16 bits
+    for c in 0..4 {
+        let hdr = if c == 3 { alpha_hdr } else { rgb_hdr };
+        if !hdr {
+            e0[c] = e0[c].clamp(0, 0xFF) * 257;
+            e1[c] = e1[c].clamp(0, 0xFF) * 257;
+        }
+    }
+    Endpoints { e0, e1, rgb_hdr, alpha_hdr }
+}
+
+fn lns_to_f16(lns: i32) -> u16 {
+    let mantissa = lns & 0x7FF;
+    let exponent = lns >> 11;
+    let mantissa = if mantissa < 512 {
+        3 * mantissa
+    } else if mantissa < 1536 {
+        4 * mantissa - 512
+    } else {
+        5 * mantissa - 2048
+    };
+    ((exponent << 10) | (mantissa >> 3)).min(0x7BFF) as u16
+}
+
+// Bilinearly upsamples the weight grid to the block's texels
+fn infill_weights(weights: &[i32], plane: usize, plane_count: usize, mode: &BlockMode, block_width: u32, block_height: u32, dst: &mut [i32]) {
+    let (n, m) = (mode.weights_wide, mode.weights_high);
+    let ds = (1024 + block_width / 2) / (block_width - 1);
+    let dt = (1024 + block_height / 2) / (block_height - 1);
+    let weight = |i: u32| weights.get(i as usize * plane_count + plane).copied().unwrap_or(0);
+    for t in 0..block_height {
+        for s in 0..block_width {
+            let gs = (ds * s * (n - 1) + 32) >> 6;
+            let gt = (dt * t * (m - 1) + 32) >> 6;
+            let (js, fs) = (gs >> 4, (gs & 0xF) as i32);
+            let (jt, ft) = (gt >> 4, (gt & 0xF) as i32);
+            let v0 = js + jt * n;
+            let w11 = (fs * ft + 8) >> 4;
+            let w10 = ft - w11;
+            let w01 = fs - w11;
+            let w00 = 16 - fs - ft + w11;
+            let p = weight(v0) * w00 + weight(v0 + 1) * w01 + weight(v0 + n) * w10 + weight(v0 + n + 1) * w11;
+            dst[(t * block_width + s) as usize] = (p + 8) >> 4;
+        }
+    }
+}
+
+fn decode_void_extent(block: u128, hdr_profile: bool, dst: &mut [[f32; 4]]) -> Option<()> {
+    let hdr = read_bits(block, 9, 1) != 0;
+    if read_bits(block, 10, 2) != 3 || (hdr && !hdr_profile) {
+        return None;
+    }
+    let mut color = [0.0; 4];
+    for (c, value) in color.iter_mut().enumerate() {
+        let bits = read_bits(block, 64 + 16 * c as u32, 16);
+        *value = if hdr { f16_to_f32(bits as u16) } else { bits as f32 / 65535.0 };
+    }
+    dst.fill(color);
+    Some(())
+}
+
+fn decode_block(block: u128, block_width: u32, block_height: u32, hdr_profile: bool, dst: &mut [[f32; 4]]) -> Option<()> {
+    if read_bits(block, 0, 9) == VOID_EXTENT_MODE {
+        return decode_void_extent(block, hdr_profile, dst);
+    }
+    let mode = decode_block_mode(read_bits(block, 0, 11))?;
+    if mode.weights_wide > block_width || mode.weights_high > block_height {
+        return None;
+    }
+    let partition_count = read_bits(block, 11, 2) + 1;
+    if mode.dual_plane && partition_count == 4 {
+        return None;
+    }
+
+    let mut below_weights = 128 - mode.weight_bits;
+    let mut cems = [0; 4];
+    let (color_start, partition_seed) = if partition_count == 1 {
+        cems[0] = read_bits(block, 13, 4);
+        (17, 0)
+    } else {
+        let low = read_bits(block, 23, 6);
+        if low & 3 == 0 {
+            cems.fill(low >> 2);
+        } else {
+            // the rest of the modes sit below the weights
+            let extra_bits = 3 * partition_count - 4;
+            below_weights -= extra_bits;
+            let encoded = low | (read_bits(block, below_weights, extra_bits) << 6);
+            let base_class = (encoded & 3) - 1;
+            for (i, cem) in cems.iter_mut().enumerate().take(partition_count as usize) {
+                let class = base_class + ((encoded >> (2 + i)) & 1);
+                *cem = (class << 2) | ((encoded >> (2 + partition_count as usize + 2 * i)) & 3);
+            }
+        }
+        (29, read_bits(block, 13, 10))
+    };
+    let plane2_component = if mode.dual_plane {
+        below_weights -= 2;
+        Some(read_bits(block, below_weights, 2) as usize)
+    } else {
+        None
+    };
+
+    let cems = &cems[..partition_count as usize];
+    let color_value_count: u32 = cems.iter().map(|cem| ((cem >> 2) + 1) * 2).sum();
+    if color_value_count as usize > MAX_COLOR_VALUES || below_weights < color_start {
+        return None;
+    }
+    let color_bits = below_weights - color_start;
+    let color_quant = (MIN_COLOR_QUANT..QUANT_LEVELS.len()).rev().find(|&q| ise_bit_count(color_value_count, q) <= color_bits)?;
+    let colors: Vec<i32> = decode_ise(block, color_start, color_value_count, color_quant).iter()
+        .map(|&v| unquantize_color(v, color_quant))
+        .collect();
+    let mut endpoints = Vec::with_capacity(cems.len());
+    let mut color_offset = 0;
+    for &cem in cems {
+        let count = (((cem >> 2) + 1) * 2) as usize;
+        let e = decode_endpoints(cem, &colors[color_offset..color_offset + count]);
+        if (e.rgb_hdr || e.alpha_hdr) && !hdr_profile {
+            return None;
+        }
+        endpoints.push(e);
+        color_offset += count;
+    }
+
+    // weights are stored backwards from the end of the block
+    let plane_count = if mode.dual_plane { 2 } else { 1 };
+    let weight_count = mode.weights_wide * mode.weights_high * plane_count as u32;
+    let weights: Vec<i32> = decode_ise(block.reverse_bits(), 0, weight_count, mode.weight_quant).iter()
+        .map(|&v| unquantize_weight(v, mode.weight_quant))
+        .collect();
+    let texel_count = (block_width * block_height) as usize;
+    let mut plane_weights = [[0; BLOCK_MAX_TEXELS]; 2];
+    for (plane, dst) in plane_weights.iter_mut().enumerate().take(plane_count) {
+        infill_weights(&weights, plane, plane_count, &mode, block_width, block_height, &mut dst[..texel_count]);
+    }
+
+    let small_block = texel_count < 31;
+    for y in 0..block_height {
+        for x in 0..block_width {
+            let i = (y * block_width + x) as usize;
+            let partition = if partition_count > 1 {
+                select_partition(partition_seed, x, y, partition_count, small_block)
+            } else {
+                0
+            };
+            let e = &endpoints[partition];
+            for c in 0..4 {
+                let w = plane_weights[if plane2_component == Some(c) { 1 } else { 0 }][i];
+                let value = (e.e0[c] * (64 - w) + e.e1[c] * w + 32) >> 6;
+                let hdr = if c == 3 { e.alpha_hdr } else { e.rgb_hdr };
+                dst[i][c] = if hdr { f16_to_f32(lns_to_f16(value)) } else { value as f32 / 65535.0 };
+            }
+        }
+    }
+    Some(())
+}
+
+fn decode_image(src: &[u8], w: usize, h: usize, block_width: usize, block_height: usize, hdr_profile: bool, mut write: impl FnMut(usize, [f32; 4])) {
+    if !(4..=12).contains(&block_width) || !(4..=12).contains(&block_height) {
+        return;
+    }
+    let blocks_wide = w.div_ceil(block_width);
+    let blocks_high = h.div_ceil(block_height);
+    let mut texels = [[0.0; 4]; BLOCK_MAX_TEXELS];
+    for by in 0..blocks_high {
+        for bx in 0..blocks_wide {
+            let src_offs = (by * blocks_wide + bx) * 16;
+            let Some(block) = src.get(src_offs..src_offs + 16) else {
+                return;
+            };
+            let block = u128::from_le_bytes(block.try_into().unwrap());
+            let texels = &mut texels[..block_width * block_height];
+            if decode_block(block, block_width as u32, block_height as u32, hdr_profile, texels).is_none() {
+                texels.fill(ERROR_COLOR);
+            }
+            for y in 0..block_height {
+                for x in 0..block_width {
+                    let (px, py) = (bx * block_width + x, by * block_height + y);
+                    if px < w && py < h {
+                        write(py * w + px, texels[y * block_width + x]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Decodes one mip level with the LDR profile to RGBA8
+#[wasm_bindgen]
+pub fn decode_astc(src: &[u8], w: usize, h: usize, block_width: usize, block_height: usize) -> Vec<u8> {
+    let mut dst = vec![0x00; w * h * 4];
+    decode_image(src, w, h, block_width, block_height, false, |i, texel| {
+        for c in 0..4 {
+            dst[i * 4 + c] = (texel[c] * 255.0 + 0.5) as u8;
+        }
+    });
+    dst
+}
+
+// Decodes one mip level with the HDR profile to RGBA16F
+#[wasm_bindgen]
+pub fn decode_astc_hdr(src: &[u8], w: usize, h: usize, block_width: usize, block_height: usize) -> Vec<u16> {
+    let mut dst = vec![0; w * h * 4];
+    decode_image(src, w, h, block_width, block_height, true, |i, texel| {
+        for c in 0..4 {
+            dst[i * 4 + c] = f32_to_f16(texel[c]);
+        }
+    });
+    dst
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 4x4 block with a 4x4 grid of 2-bit weights and one partition
+    fn make_block(cem: u128, v0: u128, v1: u128, weights: &[u128; 16]) -> [u8; 16] {
+        let mut block: u128 = 0x42 | (cem << 13) | (v0 << 17) | (v1 << 25);
+        for (i, &weight) in weights.iter().enumerate() {
+            // weights are stored bit-reversed from the top of the block
+            block |= (weight & 1) << (127 - 2 * i);
+            block |= (weight >> 1) << (126 - 2 * i);
+        }
+        block.to_le_bytes()
+    }
+
+    #[test]
+    fn test_ldr_block() {
+        let block = make_block(0, 0, 255, &[0, 1, 2, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3]);
+        let pixels = decode_astc(&block, 4, 4, 4, 4);
+        assert_eq!(&pixels[0..4], &[0, 0, 0, 255]);
+        // weight levels are 0, 21, 43 and 64 out of 64
+        assert_eq!(&pixels[4..8], &[84, 84, 84, 255]);
+        assert_eq!(&pixels[8..12], &[171, 171, 171, 255]);
+        assert_eq!(&pixels[60..64], &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_hdr_block() {
+        // HDR luminance, from 0 to 2.0
+        let block = make_block(2, 0, 0x80, &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3]);
+        let pixels = decode_astc_hdr(&block, 4, 4, 4, 4);
+        assert_eq!(&pixels[0..4], &[0x0000, 0x0000, 0x0000, 0x3C00]);
+        assert_eq!(&pixels[60..64], &[0x4000, 0x4000, 0x4000, 0x3C00]);
+        // HDR endpoints are an error in the LDR profile
+        assert_eq!(&decode_astc(&block, 4, 4, 4, 4)[0..4], &[255, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_void_extent() {
+        let mut block: u128 = 0xDFC | (0xFFFF << 64) | (0x8000 << 80) | (0xFFFF << 112);
+        assert_eq!(&decode_astc(&block.to_le_bytes(), 4, 4, 4, 4)[0..4], &[255, 128, 0, 255]);
+        // HDR void extents hold half floats
+        block = 0xFFC | (0x4000 << 64) | (0x3C00 << 80) | (0x3C00 << 112);
+        assert_eq!(&decode_astc_hdr(&block.to_le_bytes(), 4, 4, 4, 4)[0..4], &[0x4000, 0x3C00, 0x0000, 0x3C00]);
+    }
+
+    #[test]
+    fn test_integer_sequences() {
+        for t in 0..256 {
+            assert!(decode_trits(t).iter().all(|&d| d < 3));
+        }
+        for q in 0..128 {
+            assert!(decode_quints(q).iter().all(|&d| d < 5));
+        }
+        // each color level unquantizes to a distinct value spanning 0..=255
+        for quant in MIN_COLOR_QUANT..QUANT_LEVELS.len() {
+            let (digits, bits) = QUANT_LEVELS[quant];
+            let digit_count = match digits {
+                Digits::None => 1,
+                Digits::Trit => 3,
+                Digits::Quint => 5,
+            };
+            let mut values: Vec<i32> = (0..digit_count << bits).map(|v| unquantize_color(v, quant)).collect();
+            values.sort();
+            values.dedup();
+            assert_eq!(values.len() as u32, digit_count << bits);
+            assert_eq!((values[0], values[values.len() - 1]), (0, 255));
+        }
+    }
+}
@@ -0,0 +1,809 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+use std::convert::TryInto;
+
+use crate::profiling;
+
+// Transcodes Basis Universal ETC1S (BasisLZ-supercompressed KTX2) textures to
+// whichever format the GPU can sample. ETC1S blocks are ETC1 blocks with a
+// single color and intensity table, so ETC1 output is a straight copy and BC1
+// is a cheap re-fit of the same four colors. UASTC textures are a separate
+// follow-up: they need their own block decoder (19 BC7-like modes with
+// BISE-coded endpoints and weights) and are rejected for now.
+
+const KTX2_IDENTIFIER: [u8; 12] = [0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+const SUPERCOMPRESSION_BASISLZ: u32 = 1;
+const KHR_DF_MODEL_ETC1S: u8 = 163;
+const KHR_DF_MODEL_UASTC: u8 = 166;
+const IMAGE_FLAG_P_FRAME: u32 = 0x2;
+
+const MAX_HUFFMAN_SYMBOLS_LOG2: u32 = 14;
+const MAX_HUFFMAN_CODE_SIZE: usize = 16;
+const TOTAL_CODE_LENGTH_CODES: usize = 21;
+const SMALL_ZERO_RUN_CODE: u32 = 17;
+const BIG_ZERO_RUN_CODE: u32 = 18;
+const SMALL_REPEAT_CODE: u32 = 19;
+const BIG_REPEAT_CODE: u32 = 20;
+const SORTED_CODE_LENGTH_CODES: [usize; TOTAL_CODE_LENGTH_CODES] = [17, 18, 19, 20, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15, 16];
+
+const COLOR5_PAL0_PREV_HI: u8 = 9;
+const COLOR5_PAL1_PREV_HI: u8 = 21;
+
+const ENDPOINT_PRED_REPEAT_LAST_SYMBOL: u32 = 256;
+const ENDPOINT_PRED_MIN_REPEAT_COUNT: u32 = 3;
+const ENDPOINT_PRED_COUNT_VLC_BITS: u32 = 4;
+const SELECTOR_HISTORY_BUF_RLE_COUNT_THRESH: u32 = 3;
+const SELECTOR_HISTORY_BUF_RLE_COUNT_TOTAL: u32 = 64;
+
+const ETC1_INTEN_TABLES: [[i32; 4]; 8] = [
+    [-8, -2, 2, 8],
+    [-17, -5, 5, 17],
+    [-29, -9, 9, 29],
+    [-42, -13, 13, 42],
+    [-60, -18, 18, 60],
+    [-80, -24, 24, 80],
+    [-106, -33, 33, 106],
+    [-183, -47, 47, 183],
+];
+// ETC1S selectors go from darkest to brightest, ETC1's don't
+const SELECTOR_TO_ETC1: [u8; 4] = [3, 2, 0, 1];
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    bit_pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, bit_pos: 0 }
+    }
+
+    fn get_bit(&mut self) -> Result<u32, String> {
+        let byte = self.data.get(self.bit_pos / 8).ok_or("ran out of data")?;
+        let bit = (byte >> (self.bit_pos % 8)) & 1;
+        self.bit_pos += 1;
+        Ok(bit as u32)
+    }
+
+    // least significant bit first
+    fn get_bits(&mut self, count: u32) -> Result<u32, String> {
+        let mut result = 0;
+        for i in 0..count {
+            result |= self.get_bit()? << i;
+        }
+        Ok(result)
+    }
+
+    fn decode_vlc(&mut self, chunk_bits: u32) -> Result<u32, String> {
+        let chunk_size = 1 << chunk_bits;
+        let mut result = 0;
+        let mut shift = 0;
+        loop {
+            let chunk = self.get_bits(chunk_bits + 1)?;
+            result |= (chunk & (chunk_size - 1)) << shift;
+            shift += chunk_bits;
+            if chunk & chunk_size == 0 || shift >= 32 {
+                return Ok(result);
+            }
+        }
+    }
+
+    fn decode_huffman(&mut self, table: &HuffmanTable) -> Result<u32, String> {
+        // canonical codes come most significant bit first
+        let mut code = 0;
+        let mut first = 0;
+        let mut index = 0;
+        for &count in &table.counts[1..] {
+            code |= self.get_bit()? as usize;
+            if code < first + count {
+                return Ok(table.symbols[index + code - first]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+
+    fn read_huffman_table(&mut self) -> Result<HuffmanTable, String> {
+        let symbol_count = self.get_bits(MAX_HUFFMAN_SYMBOLS_LOG2)? as usize;
+        if symbol_count == 0 {
+            return Ok(HuffmanTable::new(&[]));
+        }
+        let code_length_code_count = self.get_bits(5)? as usize;
+        if code_length_code_count == 0 || code_length_code_count > TOTAL_CODE_LENGTH_CODES {
+            return Err(format!("invalid code length code count {}", code_length_code_count));
+        }
+        let mut code_length_code_sizes = [0; TOTAL_CODE_LENGTH_CODES];
+        for &code in &SORTED_CODE_LENGTH_CODES[..code_length_code_count] {
+            code_length_code_sizes[code] = self.get_bits(3)? as u8;
+        }
+        let code_length_table = HuffmanTable::new(&code_length_code_sizes);
+
+        let mut code_sizes = Vec::with_capacity(symbol_count);
+        while code_sizes.len() < symbol_count {
+            let code = self.decode_huffman(&code_length_table)?;
+            match code {
+                0..=16 => code_sizes.push(code as u8),
+                SMALL_ZERO_RUN_CODE | BIG_ZERO_RUN_CODE => {
+                    let run = if code == SMALL_ZERO_RUN_CODE {
+                        self.get_bits(3)? + 3
+                    } else {
+                        self.get_bits(7)? + 11
+                    };
+                    code_sizes.extend(std::iter::repeat_n(0, run as usize));
+                },
+                SMALL_REPEAT_CODE | BIG_REPEAT_CODE => {
+                    let run = if code == SMALL_REPEAT_CODE {
+                        self.get_bits(2)? + 3
+                    } else {
+                        self.get_bits(7)? + 7
+                    };
+                    let previous = match code_sizes.last() {
+                        Some(&size) if size > 0 => size,
+                        _ => return Err("code size repeat without a previous code size".to_string()),
+                    };
+                    code_sizes.extend(std::iter::repeat_n(previous, run as usize));
+                },
+                _ => return Err(format!("invalid code length code {}", code)),
+            }
+        }
+        if code_sizes.len() > symbol_count {
+            return Err("code size run past the last symbol".to_string());
+        }
+        Ok(HuffmanTable::new(&code_sizes))
+    }
+}
+
+struct HuffmanTable {
+    // how many codes there are of each length, and the symbols sorted by
+    // code length
+    counts: [usize; MAX_HUFFMAN_CODE_SIZE + 1],
+    symbols: Vec<u32>,
+}
+
+impl HuffmanTable {
+    fn new(code_sizes: &[u8]) -> Self {
+        let mut counts = [0; MAX_HUFFMAN_CODE_SIZE + 1];
+        let mut symbols = Vec::new();
+        for (length, count) in counts.iter_mut().enumerate().skip(1) {
+            for (symbol, &size) in code_sizes.iter().enumerate() {
+                if size as usize == length {
+                    *count += 1;
+                    symbols.push(symbol as u32);
+                }
+            }
+        }
+        Self { counts, symbols }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Endpoint {
+    color5: [u8; 3],
+    inten: u8,
+}
+
+// each row's 2-bit selectors, leftmost pixel in the low bits
+#[derive(Clone, Copy, Default)]
+struct Selector {
+    rows: [u8; 4],
+}
+
+impl Selector {
+    fn get(&self, x: usize, y: usize) -> usize {
+        ((self.rows[y] >> (x * 2)) & 3) as usize
+    }
+}
+
+// The selector history buffer: recently used selectors, with ones that get
+// used again slowly moving towards the front
+struct ApproxMoveToFront {
+    values: Vec<u32>,
+    rover: usize,
+}
+
+impl ApproxMoveToFront {
+    fn new(size: usize) -> Self {
+        Self { values: vec![0; size], rover: size / 2 }
+    }
+
+    fn add(&mut self, value: u32) {
+        self.values[self.rover] = value;
+        self.rover += 1;
+        if self.rover == self.values.len() {
+            self.rover = self.values.len() / 2;
+        }
+    }
+
+    fn use_index(&mut self, index: usize) {
+        self.values.swap(index / 2, index);
+    }
+}
+
+struct SliceTables {
+    endpoint_pred: HuffmanTable,
+    delta_endpoint: HuffmanTable,
+    selector: HuffmanTable,
+    selector_history_buf_rle: HuffmanTable,
+    selector_history_buf_size: usize,
+}
+
+fn decode_endpoints(data: &[u8], count: usize) -> Result<Vec<Endpoint>, String> {
+    let mut reader = BitReader::new(data);
+    let color5_delta_models = [reader.read_huffman_table()?, reader.read_huffman_table()?, reader.read_huffman_table()?];
+    let inten_delta_model = reader.read_huffman_table()?;
+    let grayscale = reader.get_bits(1)? == 1;
+
+    let mut endpoints = Vec::with_capacity(count);
+    let mut prev_color5 = [16u8; 3];
+    let mut prev_inten = 0u8;
+    for _ in 0..count {
+        let inten = (reader.decode_huffman(&inten_delta_model)? as u8).wrapping_add(prev_inten) & 7;
+        prev_inten = inten;
+        let channel_count = if grayscale { 1 } else { 3 };
+        for prev in &mut prev_color5[..channel_count] {
+            let model = if *prev <= COLOR5_PAL0_PREV_HI {
+                &color5_delta_models[0]
+            } else if *prev <= COLOR5_PAL1_PREV_HI {
+                &color5_delta_models[1]
+            } else {
+                &color5_delta_models[2]
+            };
+            *prev = (reader.decode_huffman(model)? as u8).wrapping_add(*prev) & 31;
+        }
+        let mut color5 = prev_color5;
+        if grayscale {
+            color5 = [color5[0]; 3];
+        }
+        endpoints.push(Endpoint { color5, inten });
+    }
+    Ok(endpoints)
+}
+
+fn decode_selectors(data: &[u8], count: usize) -> Result<Vec<Selector>, String> {
+    let mut reader = BitReader::new(data);
+    let uses_global_codebook = reader.get_bits(1)? == 1;
+    let uses_hybrid_codebook = reader.get_bits(1)? == 1;
+    if uses_global_codebook || uses_hybrid_codebook {
+        return Err("global selector codebooks aren't supported".to_string());
+    }
+    let raw = reader.get_bits(1)? == 1;
+    let delta_model = if raw { None } else { Some(reader.read_huffman_table()?) };
+
+    let mut selectors = Vec::with_capacity(count);
+    let mut prev_rows = [0u8; 4];
+    for i in 0..count {
+        let mut selector = Selector::default();
+        for (row, prev) in selector.rows.iter_mut().zip(&mut prev_rows) {
+            *row = match &delta_model {
+                Some(model) if i > 0 => reader.decode_huffman(model)? as u8 ^ *prev,
+                _ => reader.get_bits(8)? as u8,
+            };
+            *prev = *row;
+        }
+        selectors.push(selector);
+    }
+    Ok(selectors)
+}
+
+fn decode_tables(data: &[u8]) -> Result<SliceTables, String> {
+    let mut reader = BitReader::new(data);
+    let tables = SliceTables {
+        endpoint_pred: reader.read_huffman_table()?,
+        delta_endpoint: reader.read_huffman_table()?,
+        selector: reader.read_huffman_table()?,
+        selector_history_buf_rle: reader.read_huffman_table()?,
+        selector_history_buf_size: reader.get_bits(13)? as usize,
+    };
+    if tables.selector_history_buf_size == 0 {
+        return Err("empty selector history buffer".to_string());
+    }
+    Ok(tables)
+}
+
+#[derive(Clone, Copy, Default)]
+struct BlockPred {
+    endpoint_index: u32,
+    pred_bits: u32,
+}
+
+// Returns each block's (endpoint, selector) indices, in raster order
+fn decode_slice(data: &[u8], tables: &SliceTables, endpoint_count: usize, selector_count: usize, blocks_wide: usize, blocks_high: usize) -> Result<Vec<(u32, u32)>, String> {
+    let mut reader = BitReader::new(data);
+    let mut history = ApproxMoveToFront::new(tables.selector_history_buf_size);
+    let history_rle_symbol = (selector_count + tables.selector_history_buf_size) as u32;
+    // the predictions for this row and the row above, alternating
+    let mut block_preds = [vec![BlockPred::default(); blocks_wide], vec![BlockPred::default(); blocks_wide]];
+
+    let mut result = Vec::with_capacity(blocks_wide * blocks_high);
+    let mut selector_rle_count = 0;
+    let mut pred_bits = 0;
+    let mut prev_endpoint_pred = 0;
+    let mut endpoint_pred_repeat_count = 0;
+    let mut prev_endpoint_index = 0;
+    for block_y in 0..blocks_high {
+        let current = block_y & 1;
+        let above = current ^ 1;
+        for block_x in 0..blocks_wide {
+            // endpoint predictions come in 2x2 groups of blocks
+            if block_x & 1 == 0 {
+                if block_y & 1 == 0 {
+                    if endpoint_pred_repeat_count > 0 {
+                        endpoint_pred_repeat_count -= 1;
+                        pred_bits = prev_endpoint_pred;
+                    } else {
+                        pred_bits = reader.decode_huffman(&tables.endpoint_pred)?;
+                        if pred_bits == ENDPOINT_PRED_REPEAT_LAST_SYMBOL {
+                            endpoint_pred_repeat_count = reader.decode_vlc(ENDPOINT_PRED_COUNT_VLC_BITS)? + ENDPOINT_PRED_MIN_REPEAT_COUNT - 1;
+                            pred_bits = prev_endpoint_pred;
+                        } else {
+                            prev_endpoint_pred = pred_bits;
+                        }
+                    }
+                    block_preds[above][block_x].pred_bits = pred_bits >> 4;
+                } else {
+                    pred_bits = block_preds[current][block_x].pred_bits;
+                }
+            }
+
+            let pred = pred_bits & 3;
+            pred_bits >>= 2;
+            let endpoint_index = match pred {
+                0 if block_x > 0 => prev_endpoint_index,
+                1 if block_y > 0 => block_preds[above][block_x].endpoint_index,
+                2 if block_x > 0 && block_y > 0 => block_preds[above][block_x - 1].endpoint_index,
+                3 => {
+                    let index = reader.decode_huffman(&tables.delta_endpoint)? + prev_endpoint_index;
+                    if index as usize >= endpoint_count {
+                        index - endpoint_count as u32
+                    } else {
+                        index
+                    }
+                },
+                _ => return Err(format!("endpoint prediction {} is out of bounds at block ({}, {})", pred, block_x, block_y)),
+            };
+            block_preds[current][block_x].endpoint_index = endpoint_index;
+            prev_endpoint_index = endpoint_index;
+
+            let selector_symbol = if selector_rle_count > 0 {
+                selector_rle_count -= 1;
+                selector_count as u32
+            } else {
+                let symbol = reader.decode_huffman(&tables.selector)?;
+                if symbol == history_rle_symbol {
+                    let run_symbol = reader.decode_huffman(&tables.selector_history_buf_rle)?;
+                    selector_rle_count = if run_symbol == SELECTOR_HISTORY_BUF_RLE_COUNT_TOTAL - 1 {
+                        reader.decode_vlc(7)? + SELECTOR_HISTORY_BUF_RLE_COUNT_THRESH
+                    } else {
+                        run_symbol + SELECTOR_HISTORY_BUF_RLE_COUNT_THRESH
+                    };
+                    if selector_rle_count as usize > blocks_wide * blocks_high {
+                        return Err(format!("selector run of {} is longer than the slice", selector_rle_count));
+                    }
+                    selector_rle_count -= 1;
+                    selector_count as u32
+                } else {
+                    symbol
+                }
+            };
+            let selector_index = if selector_symbol as usize >= selector_count {
+                let history_index = selector_symbol as usize - selector_count;
+                let index = *history.values.get(history_index)
+                    .ok_or(format!("selector history index {} is out of bounds", history_index))?;
+                if history_index != 0 {
+                    history.use_index(history_index);
+                }
+                index
+            } else {
+                history.add(selector_symbol);
+                selector_symbol
+            };
+
+            if endpoint_index as usize >= endpoint_count || selector_index as usize >= selector_count {
+                return Err(format!("block ({}, {}) has invalid codebook indices", block_x, block_y));
+            }
+            result.push((endpoint_index, selector_index));
+        }
+    }
+    Ok(result)
+}
+
+fn expand5(value: u8) -> i32 {
+    ((value << 3) | (value >> 2)) as i32
+}
+
+// The block's four colors, darkest first
+fn get_block_colors(endpoint: &Endpoint) -> [[u8; 3]; 4] {
+    let mut result = [[0; 3]; 4];
+    for (color, modifier) in result.iter_mut().zip(ETC1_INTEN_TABLES[endpoint.inten as usize]) {
+        for (channel, &base) in color.iter_mut().zip(&endpoint.color5) {
+            *channel = (expand5(base) + modifier).clamp(0, 255) as u8;
+        }
+    }
+    result
+}
+
+fn write_etc1_block(output: &mut Vec<u8>, endpoint: &Endpoint, selector: &Selector) {
+    // differential mode with a zero delta, so both halves share one color
+    let [r, g, b] = endpoint.color5;
+    output.extend_from_slice(&[r << 3, g << 3, b << 3, endpoint.inten << 5 | endpoint.inten << 2 | 0x2]);
+    let mut msb = 0u16;
+    let mut lsb = 0u16;
+    for y in 0..4 {
+        for x in 0..4 {
+            let value = SELECTOR_TO_ETC1[selector.get(x, y)];
+            let bit = x * 4 + y;
+            msb |= ((value >> 1) as u16) << bit;
+            lsb |= ((value & 1) as u16) << bit;
+        }
+    }
+    output.extend_from_slice(&msb.to_be_bytes());
+    output.extend_from_slice(&lsb.to_be_bytes());
+}
+
+fn to_565(color: [u8; 3]) -> u16 {
+    ((color[0] as u16 >> 3) << 11) | ((color[1] as u16 >> 2) << 5) | (color[2] as u16 >> 3)
+}
+
+fn from_565(value: u16) -> [i32; 3] {
+    let r = (value >> 11) & 31;
+    let g = (value >> 5) & 63;
+    let b = value & 31;
+    [((r << 3) | (r >> 2)) as i32, ((g << 2) | (g >> 4)) as i32, ((b << 3) | (b >> 2)) as i32]
+}
+
+fn write_bc1_block(output: &mut Vec<u8>, endpoint: &Endpoint, selector: &Selector) {
+    // ETC1S colors all lie on one line, so use the darkest and brightest ones
+    // the block actually uses as the endpoints
+    let colors = get_block_colors(endpoint);
+    let mut low = 3;
+    let mut high = 0;
+    for y in 0..4 {
+        for x in 0..4 {
+            low = low.min(selector.get(x, y));
+            high = high.max(selector.get(x, y));
+        }
+    }
+    let mut color0 = to_565(colors[high]);
+    let mut color1 = to_565(colors[low]);
+    if color0 < color1 {
+        std::mem::swap(&mut color0, &mut color1);
+    }
+    let mut indices = 0u32;
+    if color0 != color1 {
+        let c0 = from_565(color0);
+        let c1 = from_565(color1);
+        let palette: [[i32; 3]; 4] = [
+            c0,
+            c1,
+            [0, 1, 2].map(|i| (2 * c0[i] + c1[i]) / 3),
+            [0, 1, 2].map(|i| (c0[i] + 2 * c1[i]) / 3),
+        ];
+        let mut nearest = [0u32; 4];
+        for (selector_value, color) in colors.iter().enumerate() {
+            let distance = |entry: &[i32; 3]| -> i32 {
+                (0..3).map(|i| (entry[i] - color[i] as i32).pow(2)).sum()
+            };
+            nearest[selector_value] = (0..4).min_by_key(|&i| distance(&palette[i])).unwrap() as u32;
+        }
+        for y in 0..4 {
+            for x in 0..4 {
+                indices |= nearest[selector.get(x, y)] << ((y * 4 + x) * 2);
+            }
+        }
+    }
+    output.extend_from_slice(&color0.to_le_bytes());
+    output.extend_from_slice(&color1.to_le_bytes());
+    output.extend_from_slice(&indices.to_le_bytes());
+}
+
+fn write_bc4_block(output: &mut Vec<u8>, values: &[u8; 16]) {
+    let max = *values.iter().max().unwrap();
+    let min = *values.iter().min().unwrap();
+    let mut indices = 0u64;
+    if max != min {
+        // eight-value mode, where index 0 is max and 1 is min
+        let mut palette = [max as i32, min as i32, 0, 0, 0, 0, 0, 0];
+        for (i, entry) in palette.iter_mut().enumerate().skip(2) {
+            *entry = ((8 - i as i32) * max as i32 + (i as i32 - 1) * min as i32) / 7;
+        }
+        for (pixel, &value) in values.iter().enumerate() {
+            let index = (0..8).min_by_key(|&i| (palette[i] - value as i32).abs()).unwrap() as u64;
+            indices |= index << (pixel * 3);
+        }
+    }
+    output.extend_from_slice(&[max, min]);
+    output.extend_from_slice(&indices.to_le_bytes()[..6]);
+}
+
+#[wasm_bindgen(js_name = "BasisTranscodeTarget")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TranscodeTarget {
+    RGBA32,
+    ETC1,
+    BC1,
+    // BC1 color with BC4 alpha
+    BC3,
+}
+
+struct ImageDesc {
+    flags: u32,
+    rgb_slice: (usize, usize),
+    alpha_slice: (usize, usize),
+}
+
+#[wasm_bindgen(js_name = "BasisTexture")]
+pub struct BasisTexture {
+    width: u32,
+    height: u32,
+    has_alpha: bool,
+    // (byte offset, byte length) of each level's data
+    levels: Vec<(usize, usize)>,
+    images: Vec<ImageDesc>,
+    endpoints: Vec<Endpoint>,
+    selectors: Vec<Selector>,
+    tables: SliceTables,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, String> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(format!("offset {:#x} is past the end of the file", offset))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<usize, String> {
+    let value = read_u32(data, offset)? as u64 | (read_u32(data, offset + 4)? as u64) << 32;
+    value.try_into().map_err(|_| format!("offset {:#x} is too large", value))
+}
+
+fn get_range(data: &[u8], offset: usize, length: usize) -> Result<&[u8], String> {
+    data.get(offset..offset + length)
+        .ok_or(format!("{} bytes at {:#x} is past the end of the file", length, offset))
+}
+
+#[wasm_bindgen(js_class = "BasisTexture")]
+impl BasisTexture {
+    // Parses a KTX2 file's header and codebooks; levels are transcoded later
+    pub fn new(data: &[u8]) -> Result<Self, String> {
+        if !data.starts_with(&KTX2_IDENTIFIER) {
+            return Err("not a KTX2 file".to_string());
+        }
+        let width = read_u32(data, 20)?;
+        let height = read_u32(data, 24)?;
+        let depth = read_u32(data, 28)?;
+        let layer_count = read_u32(data, 32)?;
+        let face_count = read_u32(data, 36)?;
+        let level_count = read_u32(data, 40)?.max(1);
+        let supercompression = read_u32(data, 44)?;
+        if depth > 1 || layer_count > 1 || face_count > 1 {
+            return Err("only single 2D textures are supported".to_string());
+        }
+
+        let dfd_offset = read_u32(data, 48)? as usize;
+        let color_model = *data.get(dfd_offset + 12).ok_or("missing data format descriptor")?;
+        let dfd_block_size = read_u32(data, dfd_offset + 8)? >> 16;
+        match color_model {
+            KHR_DF_MODEL_ETC1S if supercompression == SUPERCOMPRESSION_BASISLZ => {},
+            KHR_DF_MODEL_UASTC => return Err("UASTC textures aren't supported, only ETC1S".to_string()),
+            _ => return Err(format!("not a Basis texture (color model {}, supercompression {})", color_model, supercompression)),
+        }
+        // ETC1S textures with alpha have a second sample for the alpha slice
+        let has_alpha = dfd_block_size.saturating_sub(24) / 16 > 1;
+
+        let mut levels = Vec::with_capacity(level_count as usize);
+        for level in 0..level_count as usize {
+            let offset = read_u64(data, 80 + level * 24)?;
+            let length = read_u64(data, 80 + level * 24 + 8)?;
+            get_range(data, offset, length)?;
+            levels.push((offset, length));
+        }
+
+        let sgd_offset = read_u64(data, 64)?;
+        let sgd_length = read_u64(data, 72)?;
+        let sgd = get_range(data, sgd_offset, sgd_length)?;
+        let codebook_sizes = read_u32(sgd, 0)?;
+        let endpoint_count = (codebook_sizes & 0xFFFF) as usize;
+        let selector_count = (codebook_sizes >> 16) as usize;
+        let endpoints_length = read_u32(sgd, 4)? as usize;
+        let selectors_length = read_u32(sgd, 8)? as usize;
+        let tables_length = read_u32(sgd, 12)? as usize;
+
+        let mut images = Vec::with_capacity(level_count as usize);
+        for level in 0..level_count as usize {
+            let offset = 20 + level * 20;
+            images.push(ImageDesc {
+                flags: read_u32(sgd, offset)?,
+                rgb_slice: (read_u32(sgd, offset + 4)? as usize, read_u32(sgd, offset + 8)? as usize),
+                alpha_slice: (read_u32(sgd, offset + 12)? as usize, read_u32(sgd, offset + 16)? as usize),
+            });
+        }
+
+        let endpoints_offset = 20 + images.len() * 20;
+        let selectors_offset = endpoints_offset + endpoints_length;
+        let tables_offset = selectors_offset + selectors_length;
+        let (endpoints, selectors, tables) = profiling::time_stage("basis_codebooks", || -> Result<_, String> {
+            Ok((
+                decode_endpoints(get_range(sgd, endpoints_offset, endpoints_length)?, endpoint_count)?,
+                decode_selectors(get_range(sgd, selectors_offset, selectors_length)?, selector_count)?,
+                decode_tables(get_range(sgd, tables_offset, tables_length)?)?,
+            ))
+        })?;
+
+        Ok(Self {
+            width,
+            height,
+            has_alpha,
+            levels,
+            images,
+            endpoints,
+            selectors,
+            tables,
+        })
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_num_levels(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.has_alpha
+    }
+
+    // data is the whole KTX2 file, as passed to new()
+    pub fn transcode_level(&self, data: &[u8], level_index: u32, target: TranscodeTarget) -> Result<Vec<u8>, String> {
+        profiling::time_stage("basis_transcode", || self.transcode_level_inner(data, level_index as usize, target))
+    }
+}
+
+impl BasisTexture {
+    fn decode_slice(&self, level_data: &[u8], (offset, length): (usize, usize), blocks_wide: usize, blocks_high: usize) -> Result<Vec<(u32, u32)>, String> {
+        let slice = get_range(level_data, offset, length)?;
+        decode_slice(slice, &self.tables, self.endpoints.len(), self.selectors.len(), blocks_wide, blocks_high)
+    }
+
+    fn transcode_level_inner(&self, data: &[u8], level_index: usize, target: TranscodeTarget) -> Result<Vec<u8>, String> {
+        let &(offset, length) = self.levels.get(level_index)
+            .ok_or(format!("level {} is out of bounds", level_index))?;
+        let image = &self.images[level_index];
+        if image.flags & IMAGE_FLAG_P_FRAME != 0 {
+            return Err("video textures aren't supported".to_string());
+        }
+        let level_data = get_range(data, offset, length)?;
+        let width = (self.width >> level_index).max(1) as usize;
+        let height = (self.height >> level_index).max(1) as usize;
+        let blocks_wide = width.div_ceil(4);
+        let blocks_high = height.div_ceil(4);
+
+        let rgb_blocks = self.decode_slice(level_data, image.rgb_slice, blocks_wide, blocks_high)?;
+        let alpha_blocks = if self.has_alpha && matches!(target, TranscodeTarget::RGBA32 | TranscodeTarget::BC3) {
+            Some(self.decode_slice(level_data, image.alpha_slice, blocks_wide, blocks_high)?)
+        } else {
+            None
+        };
+        if target == TranscodeTarget::ETC1 && self.has_alpha {
+            return Err("ETC1 can't hold the texture's alpha".to_string());
+        }
+
+        // alpha comes from the alpha slice's green channel
+        let get_alpha = |block_index: usize| -> [u8; 16] {
+            let mut result = [255; 16];
+            if let Some(alpha_blocks) = &alpha_blocks {
+                let (endpoint_index, selector_index) = alpha_blocks[block_index];
+                let colors = get_block_colors(&self.endpoints[endpoint_index as usize]);
+                let selector = &self.selectors[selector_index as usize];
+                for (i, value) in result.iter_mut().enumerate() {
+                    *value = colors[selector.get(i % 4, i / 4)][1];
+                }
+            }
+            result
+        };
+
+        match target {
+            TranscodeTarget::RGBA32 => {
+                let mut output = vec![0; width * height * 4];
+                for (block_index, &(endpoint_index, selector_index)) in rgb_blocks.iter().enumerate() {
+                    let colors = get_block_colors(&self.endpoints[endpoint_index as usize]);
+                    let selector = &self.selectors[selector_index as usize];
+                    let alpha = get_alpha(block_index);
+                    let block_x = block_index % blocks_wide * 4;
+                    let block_y = block_index / blocks_wide * 4;
+                    for y in 0..4.min(height - block_y) {
+                        for x in 0..4.min(width - block_x) {
+                            let [r, g, b] = colors[selector.get(x, y)];
+                            let pixel = ((block_y + y) * width + block_x + x) * 4;
+                            output[pixel..pixel + 4].copy_from_slice(&[r, g, b, alpha[y * 4 + x]]);
+                        }
+                    }
+                }
+                Ok(output)
+            },
+            TranscodeTarget::ETC1 | TranscodeTarget::BC1 | TranscodeTarget::BC3 => {
+                let block_size = if target == TranscodeTarget::BC3 { 16 } else { 8 };
+                let mut output = Vec::with_capacity(rgb_blocks.len() * block_size);
+                for (block_index, &(endpoint_index, selector_index)) in rgb_blocks.iter().enumerate() {
+                    let endpoint = &self.endpoints[endpoint_index as usize];
+                    let selector = &self.selectors[selector_index as usize];
+                    match target {
+                        TranscodeTarget::ETC1 => write_etc1_block(&mut output, endpoint, selector),
+                        TranscodeTarget::BC3 => {
+                            write_bc4_block(&mut output, &get_alpha(block_index));
+                            write_bc1_block(&mut output, endpoint, selector);
+                        },
+                        _ => write_bc1_block(&mut output, endpoint, selector),
+                    }
+                }
+                Ok(output)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An 8x8 ETC1S texture with two levels, two endpoints and two selectors,
+    // assembled by hand following the KTX2 spec's BasisLZ layout. Level 0's
+    // blocks are (endpoint, selector) (0, 0), (1, 1), (0, 1), (0, 0), found
+    // with delta, above and above-left endpoint predictions and a selector
+    // history lookup; level 1 is (1, 1).
+    const ETC1S: &[u8] = include_bytes!("../test_data/basis/etc1s.ktx2");
+
+    // each endpoint's colors, darkest first
+    const COLORS: [[[u8; 3]; 4]; 2] = [
+        // color5 (10, 20, 25), intensity table 3
+        [[40, 123, 164], [69, 152, 193], [95, 178, 219], [124, 207, 248]],
+        // color5 (28, 4, 16), intensity table 6
+        [[125, 0, 26], [198, 0, 99], [255, 66, 165], [255, 139, 238]],
+    ];
+    const SELECTORS: [[[usize; 4]; 4]; 2] = [
+        [[0, 1, 2, 3], [1, 2, 3, 0], [2, 3, 0, 1], [3, 0, 1, 2]],
+        [[3, 3, 0, 0], [3, 3, 0, 0], [1, 1, 2, 2], [1, 1, 2, 2]],
+    ];
+
+    fn check_rgba(rgba: &[u8], width: usize, blocks: &[(usize, usize)]) {
+        assert_eq!(rgba.len(), width * width * 4);
+        for (block_index, &(endpoint, selector)) in blocks.iter().enumerate() {
+            let block_x = block_index % (width / 4) * 4;
+            let block_y = block_index / (width / 4) * 4;
+            for y in 0..4 {
+                for x in 0..4 {
+                    let [r, g, b] = COLORS[endpoint][SELECTORS[selector][y][x]];
+                    let pixel = ((block_y + y) * width + block_x + x) * 4;
+                    assert_eq!(rgba[pixel..pixel + 4], [r, g, b, 255], "block {} pixel ({}, {})", block_index, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_etc1s() {
+        let texture = BasisTexture::new(ETC1S).unwrap();
+        assert_eq!((texture.get_width(), texture.get_height(), texture.get_num_levels(), texture.has_alpha()), (8, 8, 2, false));
+
+        check_rgba(&texture.transcode_level(ETC1S, 0, TranscodeTarget::RGBA32).unwrap(), 8, &[(0, 0), (1, 1), (0, 1), (0, 0)]);
+        check_rgba(&texture.transcode_level(ETC1S, 1, TranscodeTarget::RGBA32).unwrap(), 4, &[(1, 1)]);
+
+        // differential mode with a zero delta, then the selectors remapped to
+        // ETC1's order, column by column
+        assert_eq!(texture.transcode_level(ETC1S, 1, TranscodeTarget::ETC1).unwrap(), [224, 32, 128, 218, 51, 204, 51, 51]);
+        assert_eq!(texture.transcode_level(ETC1S, 0, TranscodeTarget::BC1).unwrap().len(), 4 * 8);
+        assert!(texture.transcode_level(ETC1S, 2, TranscodeTarget::RGBA32).is_err());
+    }
+
+    #[test]
+    fn test_uastc_rejected() {
+        let mut uastc = ETC1S.to_vec();
+        let dfd_offset = read_u32(&uastc, 48).unwrap() as usize;
+        uastc[dfd_offset + 12] = KHR_DF_MODEL_UASTC;
+        assert_eq!(BasisTexture::new(&uastc).err().unwrap(), "UASTC textures aren't supported, only ETC1S");
+    }
+}
@@ -0,0 +1,254 @@
+use std::cmp::Ordering;
+
+use nalgebra_glm::{make_vec3, Vec3};
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::{Frustum, IntersectionState, AABB};
+
+// A bounding volume hierarchy over object bounding boxes, built with binned
+// SAH splits. Queries report objects by their index in the original list.
+
+const MAX_LEAF_SIZE: usize = 4;
+const SAH_BIN_COUNT: usize = 12;
+
+#[derive(Debug, Clone, Default)]
+struct BVHNode {
+    aabb: AABB,
+    // for leaves, where this node's objects start in indices; otherwise the
+    // first of the two children, which are stored next to each other
+    offset: u32,
+    // zero for inner nodes
+    count: u32,
+}
+
+#[wasm_bindgen(js_name = "BVH")]
+#[derive(Debug, Clone)]
+pub struct BVH {
+    nodes: Vec<BVHNode>,
+    indices: Vec<u32>,
+    bounds: Vec<AABB>,
+}
+
+fn find_split(items: &mut [u32], bounds: &[AABB], centers: &[Vec3], center_bounds: &AABB) -> usize {
+    let extents = center_bounds.max - center_bounds.min;
+    let axis = extents.imax();
+    let median_split = |items: &mut [u32]| {
+        let mid = items.len() / 2;
+        items.select_nth_unstable_by(mid, |&a, &b| {
+            centers[a as usize][axis].partial_cmp(&centers[b as usize][axis]).unwrap_or(Ordering::Equal)
+        });
+        mid
+    };
+    // everything is centered at the same spot, so any split is as good as another
+    if extents[axis].is_nan() || extents[axis] <= 0.0 {
+        return median_split(items);
+    }
+
+    let scale = SAH_BIN_COUNT as f32 / extents[axis];
+    let bin_of = |i: u32| (((centers[i as usize][axis] - center_bounds.min[axis]) * scale) as usize).min(SAH_BIN_COUNT - 1);
+    let mut bins = vec![(AABB::default(), 0usize); SAH_BIN_COUNT];
+    for &i in items.iter() {
+        let bin = &mut bins[bin_of(i)];
+        bin.0.union_aabb(&bounds[i as usize]);
+        bin.1 += 1;
+    }
+
+    let mut right_costs = [0.0; SAH_BIN_COUNT];
+    let mut aabb = AABB::default();
+    let mut count = 0;
+    for bin in (1..SAH_BIN_COUNT).rev() {
+        aabb.union_aabb(&bins[bin].0);
+        count += bins[bin].1;
+        if count > 0 {
+            right_costs[bin] = aabb.surface_area() * count as f32;
+        }
+    }
+    let mut best_cost = f32::INFINITY;
+    let mut best_bin = None;
+    let mut aabb = AABB::default();
+    let mut count = 0;
+    for bin in 0..SAH_BIN_COUNT - 1 {
+        aabb.union_aabb(&bins[bin].0);
+        count += bins[bin].1;
+        if count == 0 || count == items.len() {
+            continue;
+        }
+        let cost = aabb.surface_area() * count as f32 + right_costs[bin + 1];
+        if cost < best_cost {
+            best_cost = cost;
+            best_bin = Some(bin + 1);
+        }
+    }
+
+    match best_bin {
+        Some(split_bin) => {
+            let mut mid = 0;
+            for j in 0..items.len() {
+                if bin_of(items[j]) < split_bin {
+                    items.swap(j, mid);
+                    mid += 1;
+                }
+            }
+            mid
+        },
+        None => median_split(items),
+    }
+}
+
+impl BVH {
+    pub fn build(bounds: Vec<AABB>) -> Self {
+        let mut indices: Vec<u32> = (0..bounds.len() as u32).collect();
+        let centers: Vec<Vec3> = bounds.iter().map(AABB::center).collect();
+        let mut nodes = Vec::new();
+        if !bounds.is_empty() {
+            nodes.push(BVHNode::default());
+        }
+
+        let mut stack = vec![(0, 0, bounds.len())];
+        while let Some((node_index, start, end)) = stack.pop() {
+            if start == end {
+                continue;
+            }
+            let items = &mut indices[start..end];
+            let mut aabb = AABB::default();
+            let mut center_bounds = AABB::default();
+            for &i in items.iter() {
+                aabb.union_aabb(&bounds[i as usize]);
+                center_bounds.union_point(&centers[i as usize]);
+            }
+            nodes[node_index].aabb = aabb;
+
+            if items.len() <= MAX_LEAF_SIZE {
+                nodes[node_index].offset = start as u32;
+                nodes[node_index].count = items.len() as u32;
+                continue;
+            }
+            let mid = start + find_split(items, &bounds, &centers, &center_bounds);
+            let left = nodes.len();
+            nodes[node_index].offset = left as u32;
+            nodes.push(BVHNode::default());
+            nodes.push(BVHNode::default());
+            stack.push((left, start, mid));
+            stack.push((left + 1, mid, end));
+        }
+
+        BVH { nodes, indices, bounds }
+    }
+
+    fn leaf_indices(&self, node: &BVHNode) -> &[u32] {
+        let start = node.offset as usize;
+        &self.indices[start..start + node.count as usize]
+    }
+
+    // Calls visit for every object at least partly inside the frustum.
+    // Subtrees entirely inside it are accepted without testing their objects.
+    pub fn traverse_frustum(&self, frustum: &Frustum, mut visit: impl FnMut(u32)) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut stack = vec![(0, false)];
+        while let Some((node_index, parent_inside)) = stack.pop() {
+            let node = &self.nodes[node_index];
+            let inside = parent_inside || match frustum.intersect_aabb(&node.aabb) {
+                IntersectionState::Outside => continue,
+                IntersectionState::Inside => true,
+                IntersectionState::Intersection => false,
+            };
+            if node.count > 0 {
+                for &i in self.leaf_indices(node) {
+                    if inside || frustum.contains_aabb(&self.bounds[i as usize]) {
+                        visit(i);
+                    }
+                }
+            } else {
+                stack.push((node.offset as usize + 1, inside));
+                stack.push((node.offset as usize, inside));
+            }
+        }
+    }
+
+    // Calls hit for every object whose bounds the ray passes through within
+    // max_distance, along with the distance at which it enters them, roughly
+    // nearest first. hit can return a distance to stop looking beyond, e.g.
+    // once it has found an actual hit inside the object.
+    pub fn traverse_ray(&self, origin: &Vec3, dir: &Vec3, max_distance: f32, mut hit: impl FnMut(u32, f32) -> Option<f32>) {
+        let inv_dir = dir.map(|x| 1.0 / x);
+        let mut max_distance = max_distance;
+        let mut stack = Vec::new();
+        if let Some((t, _)) = self.nodes.first().and_then(|root| root.aabb.intersect_ray(origin, &inv_dir)) {
+            stack.push((0, t));
+        }
+        while let Some((node_index, t_enter)) = stack.pop() {
+            if t_enter > max_distance {
+                continue;
+            }
+            let node = &self.nodes[node_index];
+            if node.count > 0 {
+                for &i in self.leaf_indices(node) {
+                    if let Some((t, _)) = self.bounds[i as usize].intersect_ray(origin, &inv_dir) {
+                        if t <= max_distance {
+                            if let Some(distance) = hit(i, t) {
+                                max_distance = max_distance.min(distance);
+                            }
+                        }
+                    }
+                }
+            } else {
+                let left = node.offset as usize;
+                let enter = |child: usize| self.nodes[child].aabb.intersect_ray(origin, &inv_dir).map(|(t, _)| (child, t));
+                // push the farther child first so the nearer one gets visited first
+                match (enter(left), enter(left + 1)) {
+                    (Some(a), Some(b)) if a.1 < b.1 => stack.extend([b, a].iter()),
+                    (Some(a), Some(b)) => stack.extend([a, b].iter()),
+                    (Some(a), None) | (None, Some(a)) => stack.push(a),
+                    (None, None) => {},
+                }
+            }
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "BVH")]
+impl BVH {
+    // bounds holds six floats (min xyz, max xyz) per object
+    #[wasm_bindgen(constructor)]
+    pub fn new(bounds: &[f32]) -> Result<BVH, String> {
+        if !bounds.len().is_multiple_of(6) {
+            return Err(format!("expected six floats per object, got {} floats", bounds.len()));
+        }
+        Ok(BVH::build(bounds.chunks_exact(6).map(AABB::from_slice).collect()))
+    }
+
+    pub fn get_object_count(&self) -> usize {
+        self.bounds.len()
+    }
+
+    // Returns the indices of the objects at least partly inside the frustum
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<u32> {
+        let mut result = Vec::new();
+        self.traverse_frustum(frustum, |i| result.push(i));
+        result
+    }
+
+    // Same as query_frustum, but as a bitmask laid out like Frustum.cull_aabbs
+    pub fn cull_frustum(&self, frustum: &Frustum) -> Vec<u32> {
+        let mut result = vec![0u32; self.bounds.len().div_ceil(32)];
+        self.traverse_frustum(frustum, |i| result[i as usize / 32] |= 1 << (i % 32));
+        result
+    }
+
+    // Returns the indices of the objects whose bounds the ray passes through,
+    // nearest first
+    pub fn query_ray(&self, origin: &[f32], direction: &[f32], max_distance: f32) -> Result<Vec<u32>, String> {
+        if origin.len() != 3 || direction.len() != 3 {
+            return Err(format!("expected a 3D origin and direction, got {} and {} floats", origin.len(), direction.len()));
+        }
+        let mut hits = Vec::new();
+        self.traverse_ray(&make_vec3(origin), &make_vec3(direction), max_distance, |i, t| {
+            hits.push((t, i));
+            None
+        });
+        hits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        Ok(hits.into_iter().map(|(_, i)| i).collect())
+    }
+}
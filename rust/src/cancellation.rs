@@ -0,0 +1,31 @@
+use wasm_bindgen::JsValue;
+
+// Lets JS abort a long-running call by passing an AbortSignal (or any object
+// with an `aborted` property), which we check between objects or mip levels.
+// Since the call blocks the main thread, the signal will usually be aborted
+// from within a progress callback.
+pub struct Cancellation {
+    signal: Option<js_sys::Object>,
+}
+
+impl Cancellation {
+    pub fn new(signal: Option<js_sys::Object>) -> Self {
+        Self { signal }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        match &self.signal {
+            Some(signal) => js_sys::Reflect::get(signal, &JsValue::from_str("aborted"))
+                .map(|aborted| aborted.is_truthy())
+                .unwrap_or(false),
+            None => false,
+        }
+    }
+
+    pub fn check(&self, operation: &str) -> Result<(), String> {
+        if self.is_cancelled() {
+            return Err(format!("{} was cancelled", operation));
+        }
+        Ok(())
+    }
+}
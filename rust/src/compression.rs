@@ -1,9 +1,21 @@
 use wasm_bindgen::prelude::wasm_bindgen;
 use std::convert::TryInto;
 
+use crate::cancellation::Cancellation;
+use crate::hash::{adler32_update, crc32_update};
+use crate::profiling;
+use crate::unity::check_decompressed_size;
+
+mod deflate;
+mod zstd;
+
+use deflate::Inflater;
+
 #[wasm_bindgen]
-pub fn lz4_decompress(src: &[u8], uncompressed_size: usize) -> Vec<u8> {
-    lz4_flex::decompress(src, uncompressed_size).unwrap()
+pub fn lz4_decompress(src: &[u8], uncompressed_size: usize) -> Result<Vec<u8>, String> {
+    check_decompressed_size(uncompressed_size as u64)?;
+    profiling::time_decompression(|| lz4_flex::decompress(src, uncompressed_size))
+        .map_err(|err| format!("corrupt LZ4 data: {}", err))
 }
 
 #[wasm_bindgen]
@@ -14,7 +26,10 @@ pub fn lzma_decompress(
     pb: u32,
     dict_size: u32,
     unpacked_size: u64,
-) -> Vec<u8> {
+) -> Result<Vec<u8>, String> {
+    check_decompressed_size(unpacked_size)?;
+    let capacity: usize = unpacked_size.try_into()
+        .map_err(|_| format!("LZMA size {} is too large", unpacked_size))?;
     let properties = lzma_rs::decompress::raw::LzmaProperties {
         lc: lc,
         lp: lp,
@@ -22,20 +37,27 @@ pub fn lzma_decompress(
     };
     let params =
         lzma_rs::decompress::raw::LzmaParams::new(properties, dict_size, Some(unpacked_size));
-    let mut decoder = lzma_rs::decompress::raw::LzmaDecoder::new(params, None).unwrap();
-    let mut dst = Vec::<u8>::with_capacity(unpacked_size.try_into().unwrap());
-    decoder.decompress(&mut src, &mut dst).unwrap();
-    dst
+    let mut decoder = lzma_rs::decompress::raw::LzmaDecoder::new(params, None)
+        .map_err(|err| format!("invalid LZMA parameters: {}", err))?;
+    profiling::time_decompression(|| {
+        let mut dst = Vec::<u8>::with_capacity(capacity);
+        decoder.decompress(&mut src, &mut dst).map(|_| dst)
+    }).map_err(|err| format!("corrupt LZMA data: {}", err))
+}
+
+#[wasm_bindgen]
+pub fn deflate_decompress(src: &[u8]) -> Result<Vec<u8>, String> {
+    inflate_decompress(src, InflateFormat::Zlib)
 }
 
 #[wasm_bindgen]
-pub fn deflate_decompress(src: &[u8]) -> Vec<u8> {
-    inflate::inflate_bytes_zlib(src).unwrap()
+pub fn deflate_raw_decompress(src: &[u8]) -> Result<Vec<u8>, String> {
+    inflate_decompress(src, InflateFormat::Raw)
 }
 
 #[wasm_bindgen]
-pub fn deflate_raw_decompress(src: &[u8]) -> Vec<u8> {
-    inflate::inflate_bytes(src).unwrap()
+pub fn zstd_decompress(src: &[u8]) -> Result<Vec<u8>, String> {
+    profiling::time_decompression(|| zstd::decompress(src))
 }
 
 #[wasm_bindgen(js_name = "CrunchTexture")]
@@ -58,7 +80,344 @@ impl CrunchTexture {
     }
 
     pub fn decode_level(&self, data: &[u8], level_index: u32) -> Result<Vec<u8>, String> {
-        self.handle.unpack_level(data, level_index)
+        profiling::time_stage("crunch_decode", || self.handle.unpack_level(data, level_index))
             .map_err(|err| err.into())
     }
+
+    // Decodes levels first_level..first_level+level_count back to back,
+    // stopping between levels if signal is aborted
+    pub fn decode_levels(&self, data: &[u8], first_level: u32, level_count: u32, signal: Option<js_sys::Object>) -> Result<Vec<u8>, String> {
+        let cancellation = Cancellation::new(signal);
+        let mut result = Vec::new();
+        for level_index in first_level..first_level + level_count {
+            cancellation.check("crunch decode")?;
+            result.extend(self.decode_level(data, level_index)?);
+        }
+        Ok(result)
+    }
+}
+
+#[wasm_bindgen(js_name = "InflateFormat")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InflateFormat {
+    Raw,
+    Zlib,
+    Gzip,
+    // zlib or gzip if the header looks like one, raw deflate otherwise
+    Auto,
+}
+
+const GZIP_FLAG_HCRC: u8 = 0x02;
+const GZIP_FLAG_EXTRA: u8 = 0x04;
+const GZIP_FLAG_NAME: u8 = 0x08;
+const GZIP_FLAG_COMMENT: u8 = 0x10;
+
+fn detect_inflate_format(src: &[u8]) -> InflateFormat {
+    match src {
+        [0x1F, 0x8B, ..] => InflateFormat::Gzip,
+        [cmf, flg, ..] if cmf & 0x0F == 8 && (*cmf as u16 * 256 + *flg as u16).is_multiple_of(31) => InflateFormat::Zlib,
+        _ => InflateFormat::Raw,
+    }
+}
+
+// Returns None if src doesn't hold the whole header yet
+fn get_gzip_header_size(src: &[u8]) -> Result<Option<usize>, String> {
+    if src.len() < 10 {
+        return Ok(None);
+    }
+    if src[0] != 0x1F || src[1] != 0x8B || src[2] != 8 {
+        return Err("not a gzip stream".to_string());
+    }
+    let flags = src[3];
+    let mut size = 10;
+    if flags & GZIP_FLAG_EXTRA != 0 {
+        let Some(extra) = src.get(size..size + 2) else {
+            return Ok(None);
+        };
+        size += 2 + u16::from_le_bytes([extra[0], extra[1]]) as usize;
+    }
+    for flag in [GZIP_FLAG_NAME, GZIP_FLAG_COMMENT] {
+        if flags & flag != 0 {
+            let Some(end) = src.get(size..).and_then(|rest| rest.iter().position(|&b| b == 0)) else {
+                return Ok(None);
+            };
+            size += end + 1;
+        }
+    }
+    if flags & GZIP_FLAG_HCRC != 0 {
+        size += 2;
+    }
+    Ok(if size <= src.len() { Some(size) } else { None })
+}
+
+const GZIP_TRAILER_SIZE: usize = 8;
+
+// The trailer is the CRC-32 of the uncompressed data, then its size mod 2^32
+fn check_gzip_trailer(trailer: &[u8], crc: u32, size: u32) -> Result<(), String> {
+    if trailer.len() < GZIP_TRAILER_SIZE {
+        return Err("missing gzip trailer".to_string());
+    }
+    if u32::from_le_bytes(trailer[..4].try_into().unwrap()) != crc {
+        return Err("gzip CRC mismatch".to_string());
+    }
+    if u32::from_le_bytes(trailer[4..8].try_into().unwrap()) != size {
+        return Err("gzip size mismatch".to_string());
+    }
+    Ok(())
+}
+
+const ZLIB_HEADER_SIZE: usize = 2;
+const ZLIB_TRAILER_SIZE: usize = 4;
+const ZLIB_FLAG_DICT: u8 = 0x20;
+
+// Returns None if src doesn't hold the whole header yet
+fn get_zlib_header_size(src: &[u8]) -> Result<Option<usize>, String> {
+    if src.len() < ZLIB_HEADER_SIZE {
+        return Ok(None);
+    }
+    if detect_inflate_format(src) != InflateFormat::Zlib {
+        return Err("not a zlib stream".to_string());
+    }
+    if src[1] & ZLIB_FLAG_DICT != 0 {
+        return Err("zlib preset dictionaries aren't supported".to_string());
+    }
+    Ok(Some(ZLIB_HEADER_SIZE))
+}
+
+// The trailer is the Adler-32 of the uncompressed data, big-endian
+fn check_zlib_trailer(trailer: &[u8], adler: u32) -> Result<(), String> {
+    if trailer.len() < ZLIB_TRAILER_SIZE {
+        return Err("missing zlib trailer".to_string());
+    }
+    if u32::from_be_bytes(trailer[..4].try_into().unwrap()) != adler {
+        return Err("zlib checksum mismatch".to_string());
+    }
+    Ok(())
+}
+
+fn get_header_size(format: InflateFormat, src: &[u8]) -> Result<Option<usize>, String> {
+    match format {
+        InflateFormat::Gzip => get_gzip_header_size(src),
+        InflateFormat::Zlib => get_zlib_header_size(src),
+        _ => Ok(Some(0)),
+    }
+}
+
+// Whatever the format's trailer checks, accumulated as output is decoded
+struct Checksums {
+    format: InflateFormat,
+    crc: u32,
+    adler: u32,
+    size: u32,
+}
+
+impl Checksums {
+    fn new(format: InflateFormat) -> Self {
+        Self { format, crc: 0, adler: 1, size: 0 }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self.format {
+            InflateFormat::Gzip => {
+                self.crc = crc32_update(self.crc, data);
+                self.size = self.size.wrapping_add(data.len() as u32);
+            },
+            InflateFormat::Zlib => self.adler = adler32_update(self.adler, data),
+            _ => {},
+        }
+    }
+
+    // Fails if the stream was cut short, or its trailer doesn't match
+    fn check(&self, inflater: &Inflater) -> Result<(), String> {
+        if !inflater.is_done() {
+            return Err("truncated deflate stream".to_string());
+        }
+        let trailer = inflater.trailing_input();
+        match self.format {
+            InflateFormat::Gzip => check_gzip_trailer(&trailer, self.crc, self.size),
+            InflateFormat::Zlib => check_zlib_trailer(&trailer, self.adler),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn inflate_decompress(src: &[u8], format: InflateFormat) -> Result<Vec<u8>, String> {
+    let format = match format {
+        InflateFormat::Auto => detect_inflate_format(src),
+        format => format,
+    };
+    profiling::time_decompression(|| {
+        let header_size = get_header_size(format, src)?.ok_or("truncated header")?;
+        let mut inflater = Inflater::new();
+        inflater.push(&src[header_size..])?;
+        let mut checksums = Checksums::new(format);
+        checksums.update(inflater.output());
+        checksums.check(&inflater)?;
+        Ok(inflater.into_output())
+    })
+}
+
+// For data that arrives in pieces, like a download or a chunked archive
+#[wasm_bindgen(js_name = "InflateStream")]
+pub struct InflateStream {
+    format: InflateFormat,
+    inflater: Option<Inflater>,
+    // input held back until we have the whole header
+    pending: Vec<u8>,
+    checksums: Checksums,
+}
+
+#[wasm_bindgen(js_class = "InflateStream")]
+impl InflateStream {
+    pub fn new(format: InflateFormat) -> Self {
+        Self {
+            format,
+            inflater: None,
+            pending: Vec::new(),
+            checksums: Checksums::new(format),
+        }
+    }
+
+    // Returns whatever could be decoded so far
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<u8>, String> {
+        if let Some(inflater) = &mut self.inflater {
+            inflater.push(chunk)?;
+            let output = inflater.take_output();
+            self.checksums.update(&output);
+            return Ok(output);
+        }
+
+        self.pending.extend_from_slice(chunk);
+        if self.format == InflateFormat::Auto {
+            if self.pending.len() < 2 {
+                return Ok(Vec::new());
+            }
+            self.format = detect_inflate_format(&self.pending);
+        }
+        let Some(header_size) = get_header_size(self.format, &self.pending)? else {
+            return Ok(Vec::new());
+        };
+        let pending = std::mem::take(&mut self.pending);
+        let mut inflater = Inflater::new();
+        inflater.push(&pending[header_size..])?;
+        let output = inflater.take_output();
+        self.checksums = Checksums::new(self.format);
+        self.checksums.update(&output);
+        self.inflater = Some(inflater);
+        Ok(output)
+    }
+
+    // Checks that the stream ended, and its trailer if the format has one,
+    // once all the input has been pushed
+    pub fn finish(&self) -> Result<(), String> {
+        let inflater = self.inflater.as_ref().ok_or("truncated header")?;
+        self.checksums.check(inflater)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gzip_stored(data: &[u8]) -> Vec<u8> {
+        let mut result = vec![0x1F, 0x8B, 8, 0, 0, 0, 0, 0, 0, 0xFF];
+        // a single, final stored block
+        result.push(1);
+        result.extend((data.len() as u16).to_le_bytes());
+        result.extend((!(data.len() as u16)).to_le_bytes());
+        result.extend(data);
+        result.extend(crate::hash::crc32(data).to_le_bytes());
+        result.extend((data.len() as u32).to_le_bytes());
+        result
+    }
+
+    #[test]
+    fn test_gzip() {
+        let gzip = gzip_stored(b"hello");
+        assert_eq!(inflate_decompress(&gzip, InflateFormat::Auto).unwrap(), b"hello");
+
+        let mut stream = InflateStream::new(InflateFormat::Gzip);
+        let mut output = Vec::new();
+        for chunk in gzip.chunks(3) {
+            output.extend(stream.push(chunk).unwrap());
+        }
+        stream.finish().unwrap();
+        assert_eq!(output, b"hello");
+
+        let mut corrupt = gzip.clone();
+        let crc_offset = corrupt.len() - 8;
+        corrupt[crc_offset] ^= 1;
+        assert!(inflate_decompress(&corrupt, InflateFormat::Gzip).is_err());
+        let mut stream = InflateStream::new(InflateFormat::Gzip);
+        stream.push(&corrupt).unwrap();
+        assert!(stream.finish().is_err());
+    }
+
+    // zlib.compress(ZLIB_DATA, 9), which uses a dynamic Huffman block
+    const ZLIB_DATA: &[u8] = b"abcccaaaacaabacaaaadcaabccabaabcabadaaaabbadabaaba";
+    const ZLIB: [u8; 37] = [
+        0x78, 0xDA, 0x1D, 0x88, 0xC7, 0x11, 0x00, 0x00, 0x0C, 0x82, 0x66, 0xB5, 0xEC, 0x3F, 0x43, 0x24, 0x3E, 0x90, 0x43,
+        0x4E, 0xA2, 0x6D, 0xB0, 0xDE, 0x8A, 0x2E, 0x9A, 0x1B, 0x4B, 0x34, 0x4F, 0xD1, 0x01, 0xE7, 0x3F, 0x13, 0x18,
+    ];
+
+    // Raw deflate with fixed Huffman codes; "abc" and then a match that
+    // overlaps its own output
+    const RAW_DATA: &[u8] = b"abcabcabcabcabcabc!";
+    const RAW: [u8; 8] = [0x4B, 0x4C, 0x4A, 0x4E, 0x44, 0x45, 0x8A, 0x00];
+
+    fn inflate_chunked(src: &[u8], format: InflateFormat, chunk_size: usize) -> Result<Vec<u8>, String> {
+        let mut stream = InflateStream::new(format);
+        let mut output = Vec::new();
+        for chunk in src.chunks(chunk_size) {
+            output.extend(stream.push(chunk)?);
+        }
+        stream.finish()?;
+        Ok(output)
+    }
+
+    #[test]
+    fn test_zlib() {
+        assert_eq!(inflate_decompress(&ZLIB, InflateFormat::Zlib).unwrap(), ZLIB_DATA);
+        assert_eq!(deflate_decompress(&ZLIB).unwrap(), ZLIB_DATA);
+        for chunk_size in [1, 5, ZLIB.len()] {
+            assert_eq!(inflate_chunked(&ZLIB, InflateFormat::Zlib, chunk_size).unwrap(), ZLIB_DATA);
+        }
+
+        let mut corrupt = ZLIB;
+        corrupt[ZLIB.len() - 1] ^= 1;
+        assert!(inflate_decompress(&corrupt, InflateFormat::Zlib).is_err());
+        assert!(inflate_chunked(&corrupt, InflateFormat::Zlib, 1).is_err());
+    }
+
+    #[test]
+    fn test_raw() {
+        assert_eq!(inflate_decompress(&RAW, InflateFormat::Raw).unwrap(), RAW_DATA);
+        assert_eq!(deflate_raw_decompress(&RAW).unwrap(), RAW_DATA);
+        for chunk_size in [1, 3, RAW.len()] {
+            assert_eq!(inflate_chunked(&RAW, InflateFormat::Raw, chunk_size).unwrap(), RAW_DATA);
+        }
+    }
+
+    #[test]
+    fn test_auto() {
+        assert_eq!(inflate_decompress(&ZLIB, InflateFormat::Auto).unwrap(), ZLIB_DATA);
+        assert_eq!(inflate_decompress(&RAW, InflateFormat::Auto).unwrap(), RAW_DATA);
+        assert_eq!(inflate_chunked(&ZLIB, InflateFormat::Auto, 1).unwrap(), ZLIB_DATA);
+        assert_eq!(inflate_chunked(&RAW, InflateFormat::Auto, 1).unwrap(), RAW_DATA);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let gzip = gzip_stored(b"hello");
+        let streams: [(&[u8], InflateFormat); 3] = [(&ZLIB, InflateFormat::Zlib), (&RAW, InflateFormat::Raw), (&gzip, InflateFormat::Gzip)];
+        for (src, format) in streams {
+            for length in 0..src.len() {
+                for format in [format, InflateFormat::Auto] {
+                    assert!(inflate_decompress(&src[..length], format).is_err(), "{:?} cut to {} bytes", format, length);
+                    assert!(inflate_chunked(&src[..length], format, 1).is_err(), "{:?} cut to {} bytes", format, length);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,385 @@
+// DEFLATE decoding, following RFC 1951. Input can arrive in pieces: each
+// block header and each literal/match is decoded all at once or not at all,
+// so running out of input just leaves the decoder where it was. Unlike the
+// inflate crate, this tracks whether the final block has ended, so callers
+// can tell a truncated stream from a complete one.
+
+const WINDOW_SIZE: usize = 32768;
+const MAX_CODE_LENGTH: usize = 15;
+
+const BLOCK_TYPE_STORED: u32 = 0;
+const BLOCK_TYPE_FIXED: u32 = 1;
+const BLOCK_TYPE_DYNAMIC: u32 = 2;
+
+const END_OF_BLOCK: u16 = 256;
+
+// (base, extra bits) for length codes 257..=285
+const LENGTH_CODES: [(u16, u32); 29] = [
+    (3, 0), (4, 0), (5, 0), (6, 0), (7, 0), (8, 0), (9, 0), (10, 0), (11, 1), (13, 1), (15, 1), (17, 1), (19, 2), (23, 2), (27, 2),
+    (31, 2), (35, 3), (43, 3), (51, 3), (59, 3), (67, 4), (83, 4), (99, 4), (115, 4), (131, 5), (163, 5), (195, 5), (227, 5), (258, 0),
+];
+// (base, extra bits) for distance codes 0..=29
+const DISTANCE_CODES: [(u16, u32); 30] = [
+    (1, 0), (2, 0), (3, 0), (4, 0), (5, 1), (7, 1), (9, 2), (13, 2), (17, 3), (25, 3), (33, 4), (49, 4), (65, 5), (97, 5), (129, 6),
+    (193, 6), (257, 7), (385, 7), (513, 8), (769, 8), (1025, 9), (1537, 9), (2049, 10), (3073, 10), (4097, 11), (6145, 11),
+    (8193, 12), (12289, 12), (16385, 13), (24577, 13),
+];
+// The order code length code lengths are stored in
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+enum Stop {
+    NeedInput,
+    Corrupt(&'static str),
+}
+
+type Step<T> = Result<T, Stop>;
+
+// A canonical Huffman code as a table indexed by the next `bits` bits of
+// input, giving (symbol, code length); a length of 0 marks an unused code
+struct Huffman {
+    bits: u32,
+    entries: Vec<(u16, u32)>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u32]) -> Result<Self, &'static str> {
+        let mut counts = [0usize; MAX_CODE_LENGTH + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        // incomplete codes are fine (a single distance code is common), but
+        // over-subscribed ones can't be decoded
+        let mut left = 1isize;
+        for &count in &counts[1..] {
+            left = left * 2 - count as isize;
+            if left < 0 {
+                return Err("over-subscribed Huffman code");
+            }
+        }
+
+        let bits = lengths.iter().copied().max().unwrap_or(0);
+        let mut next_code = [0u32; MAX_CODE_LENGTH + 1];
+        let mut code = 0;
+        for length in 1..=MAX_CODE_LENGTH {
+            code = (code + counts[length - 1] as u32) << 1;
+            next_code[length] = code;
+        }
+
+        let mut entries = vec![(0, 0); 1 << bits];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length == 0 {
+                continue;
+            }
+            let code = next_code[length as usize];
+            next_code[length as usize] += 1;
+            // codes are packed most significant bit first
+            let reversed = (code as u16).reverse_bits() as usize >> (16 - length);
+            for index in (reversed..entries.len()).step_by(1 << length) {
+                entries[index] = (symbol as u16, length);
+            }
+        }
+        Ok(Self { bits, entries })
+    }
+
+    fn fixed_literals() -> Self {
+        let mut lengths = [8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        Self::new(&lengths).unwrap()
+    }
+
+    fn fixed_distances() -> Self {
+        Self::new(&[5; 30]).unwrap()
+    }
+}
+
+// Reads bits least significant first. Copy, so a step can be rolled back.
+#[derive(Clone, Copy, Default)]
+struct BitReader {
+    pos: usize,
+    buf: u64,
+    count: u32,
+}
+
+impl BitReader {
+    // Returns false if the input runs out first
+    fn fill(&mut self, input: &[u8], count: u32) -> bool {
+        while self.count < count {
+            let Some(&byte) = input.get(self.pos) else {
+                return false;
+            };
+            self.buf |= (byte as u64) << self.count;
+            self.count += 8;
+            self.pos += 1;
+        }
+        true
+    }
+
+    fn take(&mut self, input: &[u8], count: u32) -> Step<u32> {
+        if !self.fill(input, count) {
+            return Err(Stop::NeedInput);
+        }
+        let value = (self.buf & ((1 << count) - 1)) as u32;
+        self.buf >>= count;
+        self.count -= count;
+        Ok(value)
+    }
+
+    fn decode(&mut self, input: &[u8], code: &Huffman) -> Step<u16> {
+        // near the end of the input there may be fewer bits than the longest
+        // code, which is fine as long as the code we find fits in them
+        let complete = self.fill(input, code.bits);
+        let (symbol, length) = code.entries[(self.buf & ((1 << code.bits) - 1)) as usize];
+        if length == 0 {
+            return Err(if complete { Stop::Corrupt("invalid Huffman code") } else { Stop::NeedInput });
+        }
+        if length > self.count {
+            return Err(Stop::NeedInput);
+        }
+        self.buf >>= length;
+        self.count -= length;
+        Ok(symbol)
+    }
+
+    fn align(&mut self) {
+        let count = self.count % 8;
+        self.buf >>= count;
+        self.count -= count;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    BlockHeader,
+    Stored(usize),
+    Huffman,
+    Done,
+}
+
+enum Symbol {
+    Literal(u8),
+    EndOfBlock,
+    Match(usize, usize),
+}
+
+pub struct Inflater {
+    // input from the first byte not yet fully decoded
+    input: Vec<u8>,
+    bits: BitReader,
+    state: State,
+    final_block: bool,
+    literals: Huffman,
+    distances: Huffman,
+    // output not yet taken, plus the window before it
+    output: Vec<u8>,
+    taken: usize,
+}
+
+impl Inflater {
+    pub fn new() -> Self {
+        Self {
+            input: Vec::new(),
+            bits: BitReader::default(),
+            state: State::BlockHeader,
+            final_block: false,
+            literals: Huffman::fixed_literals(),
+            distances: Huffman::fixed_distances(),
+            output: Vec::new(),
+            taken: 0,
+        }
+    }
+
+    // True once the final block has ended
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    pub fn push(&mut self, data: &[u8]) -> Result<(), String> {
+        if self.state != State::Done {
+            self.input.drain(..self.bits.pos);
+            self.bits.pos = 0;
+        }
+        self.input.extend_from_slice(data);
+        loop {
+            let step = match self.state {
+                State::BlockHeader => self.atomic(Self::read_block_header),
+                State::Stored(remaining) => self.copy_stored(remaining),
+                State::Huffman => self.decode_huffman_block(),
+                State::Done => return Ok(()),
+            };
+            match step {
+                Ok(()) => {},
+                Err(Stop::NeedInput) => return Ok(()),
+                Err(Stop::Corrupt(what)) => return Err(format!("corrupt deflate data: {}", what)),
+            }
+        }
+    }
+
+    // The output decoded since take_output was last called
+    pub fn output(&self) -> &[u8] {
+        &self.output[self.taken..]
+    }
+
+    // Returns the output decoded since the last call
+    pub fn take_output(&mut self) -> Vec<u8> {
+        let output = self.output().to_vec();
+        if self.output.len() > 2 * WINDOW_SIZE {
+            self.output.drain(..self.output.len() - WINDOW_SIZE);
+        }
+        self.taken = self.output.len();
+        output
+    }
+
+    pub fn into_output(mut self) -> Vec<u8> {
+        self.output.drain(..self.taken);
+        self.output
+    }
+
+    // Once done, the input that followed the deflate stream, like a zlib or
+    // gzip trailer
+    pub fn trailing_input(&self) -> Vec<u8> {
+        let mut trailing: Vec<u8> = (0..self.bits.count / 8).map(|i| (self.bits.buf >> (8 * i)) as u8).collect();
+        trailing.extend_from_slice(&self.input[self.bits.pos..]);
+        trailing
+    }
+
+    // Runs step, rewinding the input if it runs out partway through
+    fn atomic<T>(&mut self, step: impl FnOnce(&mut Self) -> Step<T>) -> Step<T> {
+        let bits = self.bits;
+        let result = step(self);
+        if let Err(Stop::NeedInput) = result {
+            self.bits = bits;
+        }
+        result
+    }
+
+    fn end_block(&mut self) {
+        if self.final_block {
+            self.bits.align();
+            self.state = State::Done;
+        } else {
+            self.state = State::BlockHeader;
+        }
+    }
+
+    fn read_block_header(&mut self) -> Step<()> {
+        let input = &self.input[..];
+        let bits = &mut self.bits;
+        let final_block = bits.take(input, 1)? == 1;
+        match bits.take(input, 2)? {
+            BLOCK_TYPE_STORED => {
+                bits.align();
+                let length = bits.take(input, 16)?;
+                if bits.take(input, 16)? != !length & 0xFFFF {
+                    return Err(Stop::Corrupt("stored block length mismatch"));
+                }
+                self.state = State::Stored(length as usize);
+            },
+            BLOCK_TYPE_FIXED => {
+                self.literals = Huffman::fixed_literals();
+                self.distances = Huffman::fixed_distances();
+                self.state = State::Huffman;
+            },
+            BLOCK_TYPE_DYNAMIC => {
+                let literal_count = bits.take(input, 5)? as usize + 257;
+                let distance_count = bits.take(input, 5)? as usize + 1;
+                let code_length_count = bits.take(input, 4)? as usize + 4;
+                if literal_count > 286 || distance_count > 30 {
+                    return Err(Stop::Corrupt("too many length or distance codes"));
+                }
+                let mut code_lengths = [0; 19];
+                for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+                    code_lengths[symbol] = bits.take(input, 3)?;
+                }
+                let code_length_code = Huffman::new(&code_lengths).map_err(Stop::Corrupt)?;
+
+                let mut lengths = vec![0; literal_count + distance_count];
+                let mut i = 0;
+                while i < lengths.len() {
+                    let (length, repeat) = match bits.decode(input, &code_length_code)? {
+                        16 => {
+                            let previous = *lengths[..i].last().ok_or(Stop::Corrupt("repeat with no previous length"))?;
+                            (previous, 3 + bits.take(input, 2)? as usize)
+                        },
+                        17 => (0, 3 + bits.take(input, 3)? as usize),
+                        18 => (0, 11 + bits.take(input, 7)? as usize),
+                        length => (length as u32, 1),
+                    };
+                    let run = lengths.get_mut(i..i + repeat).ok_or(Stop::Corrupt("code lengths overrun"))?;
+                    run.fill(length);
+                    i += repeat;
+                }
+                if lengths[END_OF_BLOCK as usize] == 0 {
+                    return Err(Stop::Corrupt("no end of block code"));
+                }
+                self.literals = Huffman::new(&lengths[..literal_count]).map_err(Stop::Corrupt)?;
+                self.distances = Huffman::new(&lengths[literal_count..]).map_err(Stop::Corrupt)?;
+                self.state = State::Huffman;
+            },
+            _ => return Err(Stop::Corrupt("invalid block type")),
+        }
+        self.final_block = final_block;
+        Ok(())
+    }
+
+    fn copy_stored(&mut self, mut remaining: usize) -> Step<()> {
+        // whole bytes already read into the bit buffer come first
+        while remaining > 0 && self.bits.count >= 8 {
+            let byte = self.bits.take(&self.input, 8)?;
+            self.output.push(byte as u8);
+            remaining -= 1;
+        }
+        let count = remaining.min(self.input.len() - self.bits.pos);
+        self.output.extend_from_slice(&self.input[self.bits.pos..self.bits.pos + count]);
+        self.bits.pos += count;
+        remaining -= count;
+        if remaining > 0 {
+            self.state = State::Stored(remaining);
+            return Err(Stop::NeedInput);
+        }
+        self.end_block();
+        Ok(())
+    }
+
+    fn read_symbol(&mut self) -> Step<Symbol> {
+        let input = &self.input[..];
+        let bits = &mut self.bits;
+        let symbol = bits.decode(input, &self.literals)?;
+        if symbol < END_OF_BLOCK {
+            return Ok(Symbol::Literal(symbol as u8));
+        }
+        if symbol == END_OF_BLOCK {
+            return Ok(Symbol::EndOfBlock);
+        }
+        let &(base, extra) = LENGTH_CODES.get(symbol as usize - 257).ok_or(Stop::Corrupt("invalid length code"))?;
+        let length = (base as u32 + bits.take(input, extra)?) as usize;
+        let symbol = bits.decode(input, &self.distances)?;
+        let &(base, extra) = DISTANCE_CODES.get(symbol as usize).ok_or(Stop::Corrupt("invalid distance code"))?;
+        let distance = (base as u32 + bits.take(input, extra)?) as usize;
+        Ok(Symbol::Match(length, distance))
+    }
+
+    fn decode_huffman_block(&mut self) -> Step<()> {
+        loop {
+            match self.atomic(Self::read_symbol)? {
+                Symbol::Literal(byte) => self.output.push(byte),
+                Symbol::EndOfBlock => {
+                    self.end_block();
+                    return Ok(());
+                },
+                Symbol::Match(length, distance) => {
+                    let start = self.output.len().checked_sub(distance).ok_or(Stop::Corrupt("distance too far back"))?;
+                    if distance >= length {
+                        self.output.extend_from_within(start..start + length);
+                    } else {
+                        // the match overlaps its own output
+                        for i in start..start + length {
+                            self.output.push(self.output[i]);
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
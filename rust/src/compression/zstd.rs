@@ -0,0 +1,719 @@
+// Zstandard decoding, following RFC 8878. Dictionaries aren't supported.
+// This only uses Vec from the standard library, so it'd move to no_std
+// unchanged.
+
+use crate::hash::xxhash64;
+
+const ZSTD_MAGIC: u32 = 0xFD2FB528;
+const SKIPPABLE_MAGIC_MASK: u32 = 0xFFFFFFF0;
+const SKIPPABLE_MAGIC: u32 = 0x184D2A50;
+
+const BLOCK_TYPE_RAW: u32 = 0;
+const BLOCK_TYPE_RLE: u32 = 1;
+const BLOCK_TYPE_COMPRESSED: u32 = 2;
+
+const LITERALS_TYPE_RAW: u8 = 0;
+const LITERALS_TYPE_RLE: u8 = 1;
+const LITERALS_TYPE_COMPRESSED: u8 = 2;
+
+const MODE_PREDEFINED: u8 = 0;
+const MODE_RLE: u8 = 1;
+const MODE_FSE: u8 = 2;
+
+const MAX_HUFFMAN_BITS: u32 = 11;
+const MAX_HUFFMAN_WEIGHT_LOG: u32 = 6;
+const MAX_LITERAL_LENGTH_LOG: u32 = 9;
+const MAX_MATCH_LENGTH_LOG: u32 = 9;
+const MAX_OFFSET_LOG: u32 = 8;
+const MAX_LITERAL_LENGTH_CODE: usize = 35;
+const MAX_MATCH_LENGTH_CODE: usize = 52;
+const MAX_OFFSET_CODE: usize = 31;
+
+const LITERAL_LENGTH_DEFAULT_DISTRIBUTION: [i16; 36] = [
+    4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1, -1, -1, -1, -1,
+];
+const MATCH_LENGTH_DEFAULT_DISTRIBUTION: [i16; 53] = [
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1, -1, -1,
+];
+const OFFSET_DEFAULT_DISTRIBUTION: [i16; 29] = [
+    1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+];
+
+// (baseline, extra bits) for codes past the ones that stand for themselves
+const LITERAL_LENGTH_CODES: [(u32, u32); 20] = [
+    (16, 1), (18, 1), (20, 1), (22, 1), (24, 2), (28, 2), (32, 3), (40, 3), (48, 4), (64, 6),
+    (128, 7), (256, 8), (512, 9), (1024, 10), (2048, 11), (4096, 12), (8192, 13), (16384, 14), (32768, 15), (65536, 16),
+];
+const MATCH_LENGTH_CODES: [(u32, u32); 21] = [
+    (35, 1), (37, 1), (39, 1), (41, 1), (43, 2), (47, 2), (51, 3), (59, 3), (67, 4), (83, 4),
+    (99, 5), (131, 7), (259, 8), (515, 9), (1027, 10), (2051, 11), (4099, 12), (8195, 13), (16387, 14), (32771, 15), (65539, 16),
+];
+
+fn corrupt(what: &str) -> String {
+    format!("corrupt zstd data: {}", what)
+}
+
+struct ForwardReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ForwardReader<'a> {
+    fn u8(&mut self) -> Result<u8, String> {
+        let value = *self.data.get(self.pos).ok_or_else(|| corrupt("unexpected end of data"))?;
+        self.pos += 1;
+        Ok(value)
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let bytes = self.data.get(self.pos..self.pos + count).ok_or_else(|| corrupt("unexpected end of data"))?;
+        self.pos += count;
+        Ok(bytes)
+    }
+
+    fn le(&mut self, count: usize) -> Result<u64, String> {
+        Ok(self.bytes(count)?.iter().rev().fold(0, |value, &b| value << 8 | b as u64))
+    }
+}
+
+// Reads bits least significant first, for FSE table descriptions
+struct ForwardBitReader<'a> {
+    data: &'a [u8],
+    bit_pos: usize,
+}
+
+impl ForwardBitReader<'_> {
+    fn peek(&self, count: u32) -> u32 {
+        let mut value = 0u64;
+        for i in 0..5 {
+            let byte = self.data.get(self.bit_pos / 8 + i).copied().unwrap_or(0);
+            value |= (byte as u64) << (i * 8);
+        }
+        ((value >> (self.bit_pos % 8)) & ((1 << count) - 1)) as u32
+    }
+
+    fn consume(&mut self, count: u32) {
+        self.bit_pos += count as usize;
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        let value = self.peek(count);
+        self.consume(count);
+        value
+    }
+}
+
+// Reads bits most significant first, starting from the end of the data,
+// for Huffman and FSE coded streams. Reading past the start gives zeros and
+// marks the reader as overflowed.
+struct BackwardBitReader<'a> {
+    data: &'a [u8],
+    bits_left: isize,
+}
+
+impl<'a> BackwardBitReader<'a> {
+    fn new(data: &'a [u8]) -> Result<Self, String> {
+        // the last byte's highest set bit marks where the stream starts
+        let last = *data.last().ok_or_else(|| corrupt("empty bitstream"))?;
+        if last == 0 {
+            return Err(corrupt("bitstream is missing its start marker"));
+        }
+        let bits_left = data.len() as isize * 8 - last.leading_zeros() as isize - 1;
+        Ok(Self { data, bits_left })
+    }
+
+    fn load(&self, start: usize) -> u64 {
+        let mut value = 0u64;
+        for i in 0..8 {
+            let byte = self.data.get(start / 8 + i).copied().unwrap_or(0);
+            value |= (byte as u64) << (i * 8);
+        }
+        value >> (start % 8)
+    }
+
+    // count must be at most 56
+    fn peek(&self, count: u32) -> u64 {
+        if count == 0 {
+            return 0;
+        }
+        let mask = (1u64 << count) - 1;
+        if self.bits_left >= count as isize {
+            self.load((self.bits_left - count as isize) as usize) & mask
+        } else if self.bits_left > 0 {
+            let available = self.load(0) & ((1 << self.bits_left) - 1);
+            (available << (count as isize - self.bits_left)) & mask
+        } else {
+            0
+        }
+    }
+
+    fn consume(&mut self, count: u32) {
+        self.bits_left -= count as isize;
+    }
+
+    fn read(&mut self, count: u32) -> u64 {
+        let value = self.peek(count);
+        self.consume(count);
+        value
+    }
+
+    fn overflowed(&self) -> bool {
+        self.bits_left < 0
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct FseEntry {
+    symbol: u8,
+    bit_count: u8,
+    base: u16,
+}
+
+#[derive(Clone, Default)]
+struct FseTable {
+    accuracy_log: u32,
+    entries: Vec<FseEntry>,
+}
+
+impl FseTable {
+    fn from_distribution(accuracy_log: u32, distribution: &[i16]) -> Result<Self, String> {
+        let size = 1usize << accuracy_log;
+        let mut entries = vec![FseEntry::default(); size];
+        let mut next_state = vec![0u32; distribution.len()];
+
+        // "less than one" probabilities go at the end of the table
+        let mut high_threshold = size;
+        for (symbol, &probability) in distribution.iter().enumerate() {
+            if probability == -1 {
+                high_threshold -= 1;
+                entries[high_threshold].symbol = symbol as u8;
+                next_state[symbol] = 1;
+            } else {
+                next_state[symbol] = probability.max(0) as u32;
+            }
+        }
+
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mask = size - 1;
+        let mut position = 0;
+        for (symbol, &probability) in distribution.iter().enumerate() {
+            for _ in 0..probability.max(0) {
+                entries[position].symbol = symbol as u8;
+                loop {
+                    position = (position + step) & mask;
+                    if position < high_threshold {
+                        break;
+                    }
+                }
+            }
+        }
+        if position != 0 {
+            return Err(corrupt("FSE distribution doesn't fill its table"));
+        }
+
+        for entry in &mut entries {
+            let state = next_state[entry.symbol as usize];
+            next_state[entry.symbol as usize] += 1;
+            let bit_count = accuracy_log - (31 - state.leading_zeros());
+            entry.bit_count = bit_count as u8;
+            entry.base = ((state << bit_count) - size as u32) as u16;
+        }
+        Ok(Self { accuracy_log, entries })
+    }
+
+    fn rle(symbol: u8) -> Self {
+        Self {
+            accuracy_log: 0,
+            entries: vec![FseEntry { symbol, bit_count: 0, base: 0 }],
+        }
+    }
+
+    // Reads a table description, returning the table and its size in bytes
+    fn read(data: &[u8], max_accuracy_log: u32, max_symbol: usize) -> Result<(Self, usize), String> {
+        let mut reader = ForwardBitReader { data, bit_pos: 0 };
+        let accuracy_log = reader.read(4) + 5;
+        if accuracy_log > max_accuracy_log {
+            return Err(corrupt("FSE accuracy log is too large"));
+        }
+        let mut remaining = (1i32 << accuracy_log) + 1;
+        let mut threshold = 1i32 << accuracy_log;
+        let mut bit_count = accuracy_log + 1;
+        let mut distribution = Vec::new();
+        let mut previous_zero = false;
+        while remaining > 1 {
+            if previous_zero {
+                // 2-bit repeat counts of further zero probabilities
+                loop {
+                    let repeat = reader.read(2);
+                    distribution.extend(std::iter::repeat_n(0, repeat as usize));
+                    if repeat != 3 {
+                        break;
+                    }
+                }
+            }
+            if distribution.len() > max_symbol {
+                return Err(corrupt("too many FSE symbols"));
+            }
+            let max = 2 * threshold - 1 - remaining;
+            let value = reader.peek(bit_count) as i32;
+            let mut count = if (value & (threshold - 1)) < max {
+                reader.consume(bit_count - 1);
+                value & (threshold - 1)
+            } else {
+                reader.consume(bit_count);
+                let count = value & (2 * threshold - 1);
+                if count >= threshold { count - max } else { count }
+            };
+            count -= 1;
+            remaining -= count.abs();
+            distribution.push(count as i16);
+            previous_zero = count == 0;
+            while remaining < threshold {
+                bit_count -= 1;
+                threshold >>= 1;
+            }
+        }
+        if remaining != 1 || reader.bit_pos > data.len() * 8 {
+            return Err(corrupt("bad FSE table description"));
+        }
+        Ok((Self::from_distribution(accuracy_log, &distribution)?, reader.bit_pos.div_ceil(8)))
+    }
+}
+
+struct FseState<'t> {
+    table: &'t FseTable,
+    state: usize,
+}
+
+impl<'t> FseState<'t> {
+    fn new(table: &'t FseTable, reader: &mut BackwardBitReader) -> Self {
+        let state = reader.read(table.accuracy_log) as usize;
+        Self { table, state }
+    }
+
+    fn symbol(&self) -> u8 {
+        self.table.entries[self.state].symbol
+    }
+
+    fn update(&mut self, reader: &mut BackwardBitReader) {
+        let entry = self.table.entries[self.state];
+        self.state = entry.base as usize + reader.read(entry.bit_count as u32) as usize;
+    }
+}
+
+#[derive(Clone, Default)]
+struct HuffmanTable {
+    max_bits: u32,
+    // (symbol, bit count), indexed by the next max_bits bits
+    entries: Vec<(u8, u8)>,
+}
+
+impl HuffmanTable {
+    // Reads a tree description, returning the table and its size in bytes
+    fn read(data: &[u8]) -> Result<(Self, usize), String> {
+        let header = *data.first().ok_or_else(|| corrupt("missing Huffman tree description"))? as usize;
+        let (mut weights, size) = if header < 128 {
+            // FSE compressed, with two states taking turns
+            let compressed = data.get(1..1 + header).ok_or_else(|| corrupt("truncated Huffman weights"))?;
+            let (table, table_size) = FseTable::read(compressed, MAX_HUFFMAN_WEIGHT_LOG, 255)?;
+            let mut reader = BackwardBitReader::new(&compressed[table_size..])?;
+            let mut states = [FseState::new(&table, &mut reader), FseState::new(&table, &mut reader)];
+            let mut weights = Vec::new();
+            'decode: loop {
+                for i in 0..2 {
+                    weights.push(states[i].symbol());
+                    states[i].update(&mut reader);
+                    if reader.overflowed() {
+                        weights.push(states[1 - i].symbol());
+                        break 'decode;
+                    }
+                    if weights.len() > 255 {
+                        return Err(corrupt("too many Huffman weights"));
+                    }
+                }
+            }
+            (weights, 1 + header)
+        } else {
+            // 4 bits each, high nibble first
+            let count = header - 127;
+            let packed = data.get(1..1 + count.div_ceil(2)).ok_or_else(|| corrupt("truncated Huffman weights"))?;
+            let weights = (0..count).map(|i| if i % 2 == 0 { packed[i / 2] >> 4 } else { packed[i / 2] & 0xF }).collect();
+            (weights, 1 + count.div_ceil(2))
+        };
+
+        // the last weight is whatever brings the total to a power of two
+        let mut total = 0u32;
+        for &weight in &weights {
+            if weight as u32 > MAX_HUFFMAN_BITS {
+                return Err(corrupt("Huffman weight is too large"));
+            }
+            if weight > 0 {
+                total += 1 << (weight - 1);
+            }
+        }
+        if total == 0 {
+            return Err(corrupt("Huffman weights are all zero"));
+        }
+        let max_bits = 32 - total.leading_zeros();
+        let left_over = (1 << max_bits) - total;
+        if !left_over.is_power_of_two() || max_bits > MAX_HUFFMAN_BITS {
+            return Err(corrupt("Huffman weights don't sum to a power of two"));
+        }
+        weights.push(left_over.trailing_zeros() as u8 + 1);
+
+        // lowest weights (longest codes) get the lowest codes
+        let mut entries = vec![(0u8, 0u8); 1 << max_bits];
+        let mut position = 0;
+        for weight in 1..=max_bits as u8 {
+            for (symbol, _) in weights.iter().enumerate().filter(|(_, &w)| w == weight) {
+                let span = 1 << (weight - 1);
+                let bit_count = (max_bits + 1 - weight as u32) as u8;
+                entries[position..position + span].fill((symbol as u8, bit_count));
+                position += span;
+            }
+        }
+        Ok((Self { max_bits, entries }, size))
+    }
+
+    fn decode_stream(&self, data: &[u8], count: usize, output: &mut Vec<u8>) -> Result<(), String> {
+        let mut reader = BackwardBitReader::new(data)?;
+        for _ in 0..count {
+            let (symbol, bit_count) = self.entries[reader.peek(self.max_bits) as usize];
+            reader.consume(bit_count as u32);
+            output.push(symbol);
+        }
+        if reader.bits_left != 0 {
+            return Err(corrupt("Huffman stream length mismatch"));
+        }
+        Ok(())
+    }
+}
+
+// State carried from block to block within a frame
+struct FrameState {
+    huffman: Option<HuffmanTable>,
+    literal_lengths: Option<FseTable>,
+    offsets: Option<FseTable>,
+    match_lengths: Option<FseTable>,
+    repeat_offsets: [usize; 3],
+}
+
+fn read_literals(reader: &mut ForwardReader, state: &mut FrameState) -> Result<Vec<u8>, String> {
+    let byte0 = reader.u8()?;
+    let literals_type = byte0 & 3;
+    let size_format = (byte0 >> 2) & 3;
+    if literals_type == LITERALS_TYPE_RAW || literals_type == LITERALS_TYPE_RLE {
+        let size = match size_format {
+            0 | 2 => (byte0 >> 3) as usize,
+            1 => (byte0 >> 4) as usize + ((reader.u8()? as usize) << 4),
+            _ => (byte0 >> 4) as usize + ((reader.le(2)? as usize) << 4),
+        };
+        return if literals_type == LITERALS_TYPE_RAW {
+            Ok(reader.bytes(size)?.to_vec())
+        } else {
+            Ok(vec![reader.u8()?; size])
+        };
+    }
+
+    let (header_size, size_bits, stream_count) = match size_format {
+        0 => (3, 10, 1),
+        1 => (3, 10, 4),
+        2 => (4, 14, 4),
+        _ => (5, 18, 4),
+    };
+    let header = byte0 as u64 | reader.le(header_size - 1)? << 8;
+    let mask = (1 << size_bits) - 1;
+    let regenerated_size = ((header >> 4) & mask) as usize;
+    let compressed_size = ((header >> (4 + size_bits)) & mask) as usize;
+    let mut data = reader.bytes(compressed_size)?;
+
+    if literals_type == LITERALS_TYPE_COMPRESSED {
+        let (table, table_size) = HuffmanTable::read(data)?;
+        state.huffman = Some(table);
+        data = &data[table_size..];
+    }
+    let table = state.huffman.as_ref().ok_or_else(|| corrupt("treeless literals with no previous Huffman table"))?;
+
+    let mut literals = Vec::with_capacity(regenerated_size);
+    if stream_count == 1 {
+        table.decode_stream(data, regenerated_size, &mut literals)?;
+    } else {
+        let jump_table = data.get(..6).ok_or_else(|| corrupt("missing literal jump table"))?;
+        let mut sizes = [0; 4];
+        for i in 0..3 {
+            sizes[i] = u16::from_le_bytes([jump_table[i * 2], jump_table[i * 2 + 1]]) as usize;
+        }
+        sizes[3] = (data.len() - 6).checked_sub(sizes[0] + sizes[1] + sizes[2])
+            .ok_or_else(|| corrupt("literal streams are larger than their section"))?;
+        let stream_size = regenerated_size.div_ceil(4);
+        let mut offset = 6;
+        for (i, &size) in sizes.iter().enumerate() {
+            let count = if i < 3 { stream_size } else { regenerated_size.checked_sub(3 * stream_size).ok_or_else(|| corrupt("too few literals for 4 streams"))? };
+            table.decode_stream(&data[offset..offset + size], count, &mut literals)?;
+            offset += size;
+        }
+    }
+    Ok(literals)
+}
+
+fn read_sequence_table<'s>(reader: &mut ForwardReader, mode: u8, slot: &'s mut Option<FseTable>, default_log: u32, default_distribution: &[i16], max_log: u32, max_symbol: usize) -> Result<&'s FseTable, String> {
+    match mode {
+        MODE_PREDEFINED => {
+            *slot = Some(FseTable::from_distribution(default_log, default_distribution)?);
+        },
+        MODE_RLE => {
+            *slot = Some(FseTable::rle(reader.u8()?));
+        },
+        MODE_FSE => {
+            let (table, size) = FseTable::read(&reader.data[reader.pos..], max_log, max_symbol)?;
+            reader.pos += size;
+            *slot = Some(table);
+        },
+        _ => {},
+    }
+    slot.as_ref().ok_or_else(|| corrupt("repeated sequence table with no previous table"))
+}
+
+fn decode_block(data: &[u8], state: &mut FrameState, output: &mut Vec<u8>, frame_start: usize) -> Result<(), String> {
+    let mut reader = ForwardReader { data, pos: 0 };
+    let literals = read_literals(&mut reader, state)?;
+
+    let byte0 = reader.u8()? as usize;
+    let sequence_count = match byte0 {
+        0..=127 => byte0,
+        128..=254 => ((byte0 - 128) << 8) + reader.u8()? as usize,
+        _ => reader.le(2)? as usize + 0x7F00,
+    };
+    if sequence_count == 0 {
+        output.extend_from_slice(&literals);
+        return Ok(());
+    }
+
+    let modes = reader.u8()?;
+    if modes & 3 != 0 {
+        return Err(corrupt("reserved sequence mode bits are set"));
+    }
+    let literal_lengths = read_sequence_table(&mut reader, modes >> 6, &mut state.literal_lengths, 6, &LITERAL_LENGTH_DEFAULT_DISTRIBUTION, MAX_LITERAL_LENGTH_LOG, MAX_LITERAL_LENGTH_CODE)?;
+    let offsets = read_sequence_table(&mut reader, (modes >> 4) & 3, &mut state.offsets, 5, &OFFSET_DEFAULT_DISTRIBUTION, MAX_OFFSET_LOG, MAX_OFFSET_CODE)?;
+    let match_lengths = read_sequence_table(&mut reader, (modes >> 2) & 3, &mut state.match_lengths, 6, &MATCH_LENGTH_DEFAULT_DISTRIBUTION, MAX_MATCH_LENGTH_LOG, MAX_MATCH_LENGTH_CODE)?;
+
+    let mut bits = BackwardBitReader::new(&data[reader.pos..])?;
+    let mut literal_length_state = FseState::new(literal_lengths, &mut bits);
+    let mut offset_state = FseState::new(offsets, &mut bits);
+    let mut match_length_state = FseState::new(match_lengths, &mut bits);
+    let repeat_offsets = &mut state.repeat_offsets;
+
+    let mut literal_pos = 0;
+    for i in 0..sequence_count {
+        let offset_code = offset_state.symbol() as u32;
+        let match_length_code = match_length_state.symbol() as usize;
+        let literal_length_code = literal_length_state.symbol() as usize;
+        if offset_code as usize > MAX_OFFSET_CODE || match_length_code > MAX_MATCH_LENGTH_CODE || literal_length_code > MAX_LITERAL_LENGTH_CODE {
+            return Err(corrupt("sequence code out of range"));
+        }
+
+        let offset_value = (1u64 << offset_code) + bits.read(offset_code);
+        let match_length = if match_length_code < 32 {
+            match_length_code + 3
+        } else {
+            let (base, extra) = MATCH_LENGTH_CODES[match_length_code - 32];
+            (base as u64 + bits.read(extra)) as usize
+        };
+        let literal_length = if literal_length_code < 16 {
+            literal_length_code
+        } else {
+            let (base, extra) = LITERAL_LENGTH_CODES[literal_length_code - 16];
+            (base as u64 + bits.read(extra)) as usize
+        };
+        if i + 1 < sequence_count {
+            literal_length_state.update(&mut bits);
+            match_length_state.update(&mut bits);
+            offset_state.update(&mut bits);
+        }
+
+        // offset values 1-3 pick a recent offset, shifted by one if there are
+        // no literals
+        let offset = if offset_value > 3 {
+            let offset = offset_value as usize - 3;
+            *repeat_offsets = [offset, repeat_offsets[0], repeat_offsets[1]];
+            offset
+        } else {
+            let index = offset_value as usize - 1 + (literal_length == 0) as usize;
+            match index {
+                0 => repeat_offsets[0],
+                1 => {
+                    repeat_offsets.swap(0, 1);
+                    repeat_offsets[0]
+                },
+                2 => {
+                    *repeat_offsets = [repeat_offsets[2], repeat_offsets[0], repeat_offsets[1]];
+                    repeat_offsets[0]
+                },
+                _ => {
+                    let offset = repeat_offsets[0].checked_sub(1).filter(|&o| o > 0).ok_or_else(|| corrupt("zero offset"))?;
+                    *repeat_offsets = [offset, repeat_offsets[0], repeat_offsets[1]];
+                    offset
+                },
+            }
+        };
+
+        let new_literals = literals.get(literal_pos..literal_pos + literal_length).ok_or_else(|| corrupt("sequence uses more literals than there are"))?;
+        output.extend_from_slice(new_literals);
+        literal_pos += literal_length;
+
+        if offset > output.len() - frame_start {
+            return Err(corrupt("match offset is before the start of the frame"));
+        }
+        let copy_start = output.len() - offset;
+        for j in 0..match_length {
+            output.push(output[copy_start + j]);
+        }
+    }
+    if bits.bits_left != 0 {
+        return Err(corrupt("sequence bitstream length mismatch"));
+    }
+    output.extend_from_slice(&literals[literal_pos..]);
+    Ok(())
+}
+
+fn decode_frame(reader: &mut ForwardReader, output: &mut Vec<u8>) -> Result<(), String> {
+    let descriptor = reader.u8()?;
+    let content_size_flag = descriptor >> 6;
+    let single_segment = descriptor & 0x20 != 0;
+    let has_checksum = descriptor & 0x04 != 0;
+    let dictionary_id_size = [0, 1, 2, 4][(descriptor & 3) as usize];
+    if descriptor & 0x08 != 0 {
+        return Err(corrupt("reserved frame header bit is set"));
+    }
+    if !single_segment {
+        // window descriptor; we keep the whole output around anyway
+        reader.u8()?;
+    }
+    if reader.le(dictionary_id_size)? != 0 {
+        return Err("zstd dictionaries aren't supported".to_string());
+    }
+    let content_size_bytes = match content_size_flag {
+        0 => single_segment as usize,
+        1 => 2,
+        2 => 4,
+        _ => 8,
+    };
+    let mut content_size = reader.le(content_size_bytes)?;
+    if content_size_bytes == 2 {
+        content_size += 256;
+    }
+    if content_size_bytes > 0 {
+        output.reserve(content_size.min(1 << 30) as usize);
+    }
+
+    let frame_start = output.len();
+    let mut state = FrameState {
+        huffman: None,
+        literal_lengths: None,
+        offsets: None,
+        match_lengths: None,
+        repeat_offsets: [1, 4, 8],
+    };
+    loop {
+        let header = reader.le(3)? as u32;
+        let last = header & 1 != 0;
+        let block_type = (header >> 1) & 3;
+        let block_size = (header >> 3) as usize;
+        match block_type {
+            BLOCK_TYPE_RAW => output.extend_from_slice(reader.bytes(block_size)?),
+            BLOCK_TYPE_RLE => {
+                let byte = reader.u8()?;
+                output.resize(output.len() + block_size, byte);
+            },
+            BLOCK_TYPE_COMPRESSED => decode_block(reader.bytes(block_size)?, &mut state, output, frame_start)?,
+            _ => return Err(corrupt("reserved block type")),
+        }
+        if last {
+            break;
+        }
+    }
+    if content_size_bytes > 0 && (output.len() - frame_start) as u64 != content_size {
+        return Err(corrupt("frame content size mismatch"));
+    }
+    if has_checksum {
+        // the low 32 bits of the content's xxHash64
+        let checksum = reader.le(4)? as u32;
+        if checksum != xxhash64(&output[frame_start..], 0) as u32 {
+            return Err(corrupt("content checksum mismatch"));
+        }
+    }
+    Ok(())
+}
+
+// Decodes every frame in src, back to back
+pub fn decompress(src: &[u8]) -> Result<Vec<u8>, String> {
+    let mut reader = ForwardReader { data: src, pos: 0 };
+    let mut output = Vec::new();
+    while reader.pos < src.len() {
+        let magic = reader.le(4)? as u32;
+        if magic == ZSTD_MAGIC {
+            decode_frame(&mut reader, &mut output)?;
+        } else if magic & SKIPPABLE_MAGIC_MASK == SKIPPABLE_MAGIC {
+            let size = reader.le(4)? as usize;
+            reader.bytes(size)?;
+        } else {
+            return Err(format!("not zstd data (magic {:#x})", magic));
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::crc32;
+
+    // Made with the zstd 1.5.7 CLI:
+    // - hello.zst: `printf hello | zstd --no-check`, a single raw block
+    // - rle.zst: 131372 'a's at -3, a compressed block and then an RLE block
+    // - text.zst: text.txt at -19, Huffman literals and FSE tables
+    // - repeat.zst: two blocks of similar text at -6; the second has treeless
+    //   literals and repeats the first's offset table
+    const HELLO: &[u8] = include_bytes!("../../test_data/zstd/hello.zst");
+    const RLE: &[u8] = include_bytes!("../../test_data/zstd/rle.zst");
+    const TEXT: &[u8] = include_bytes!("../../test_data/zstd/text.zst");
+    const TEXT_DATA: &[u8] = include_bytes!("../../test_data/zstd/text.txt");
+    const REPEAT: &[u8] = include_bytes!("../../test_data/zstd/repeat.zst");
+
+    #[test]
+    fn test_blocks() {
+        assert_eq!(decompress(HELLO).unwrap(), b"hello");
+        assert_eq!(decompress(RLE).unwrap(), vec![b'a'; 131372]);
+        assert_eq!(decompress(TEXT).unwrap(), TEXT_DATA);
+        let repeat = decompress(REPEAT).unwrap();
+        assert_eq!((repeat.len(), crc32(&repeat)), (131668, 0xC4BA2A8B));
+    }
+
+    #[test]
+    fn test_frames() {
+        let mut src = HELLO.to_vec();
+        // a skippable frame holding 3 bytes
+        src.extend([0x50, 0x2A, 0x4D, 0x18, 3, 0, 0, 0, 1, 2, 3]);
+        src.extend(TEXT);
+        let mut expected = b"hello".to_vec();
+        expected.extend(TEXT_DATA);
+        assert_eq!(decompress(&src).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_checksum() {
+        let mut corrupt = TEXT.to_vec();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 1;
+        assert!(decompress(&corrupt).is_err());
+    }
+
+    #[test]
+    fn test_truncated() {
+        for src in [HELLO, RLE, TEXT, REPEAT] {
+            for length in 1..src.len() {
+                assert!(decompress(&src[..length]).is_err(), "cut to {} bytes", length);
+            }
+        }
+    }
+}
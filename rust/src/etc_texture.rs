@@ -0,0 +1,228 @@
+use std::convert::TryInto;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+// Software decoding for the 8-byte-per-block ETC formats, for GPUs (i.e.
+// most desktop ones) that can't sample them directly. Blocks are 64-bit
+// big-endian words; pixel indices are stored column-major, MSBs in the high
+// half of the low word and LSBs in the low half.
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EtcFormat {
+    Etc1RGB,
+    Etc2RGB,
+    // RGB with punch-through (1-bit) alpha
+    Etc2RGBA1,
+}
+
+const MODIFIER_TABLES: [[i32; 4]; 8] = [
+    [2, 8, -2, -8],
+    [5, 17, -5, -17],
+    [9, 29, -9, -29],
+    [13, 42, -13, -42],
+    [18, 60, -18, -60],
+    [24, 80, -24, -80],
+    [33, 106, -33, -106],
+    [47, 183, -47, -183],
+];
+
+const TH_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+// In punch-through blocks with the opaque bit clear, this index is
+// transparent black
+const TRANSPARENT_INDEX: usize = 2;
+
+fn bits(block: u64, high: u32, count: u32) -> i32 {
+    ((block >> (high + 1 - count)) & ((1 << count) - 1)) as i32
+}
+
+fn expand4(v: i32) -> i32 { (v << 4) | v }
+fn expand5(v: i32) -> i32 { (v << 3) | (v >> 2) }
+fn expand6(v: i32) -> i32 { (v << 2) | (v >> 4) }
+fn expand7(v: i32) -> i32 { (v << 1) | (v >> 6) }
+
+fn clamp(v: i32) -> u8 {
+    v.clamp(0, 255) as u8
+}
+
+fn offset_color(c: [i32; 3], d: i32) -> [u8; 4] {
+    [clamp(c[0] + d), clamp(c[1] + d), clamp(c[2] + d), 0xFF]
+}
+
+fn pixel_index(block: u64, x: usize, y: usize) -> usize {
+    let i = x * 4 + y;
+    let msb = (block >> (16 + i)) & 1;
+    let lsb = (block >> i) & 1;
+    ((msb << 1) | lsb) as usize
+}
+
+// T and H modes pick each pixel's color straight from a 4-color palette
+fn decode_paint_colors(block: u64, paint: [[u8; 4]; 4], punch_through: bool, dst: &mut [[u8; 4]; 16]) {
+    for x in 0..4 {
+        for y in 0..4 {
+            let index = pixel_index(block, x, y);
+            dst[y * 4 + x] = if punch_through && index == TRANSPARENT_INDEX { [0; 4] } else { paint[index] };
+        }
+    }
+}
+
+fn decode_t_mode(block: u64, punch_through: bool, dst: &mut [[u8; 4]; 16]) {
+    let c1 = [
+        expand4((bits(block, 60, 2) << 2) | bits(block, 57, 2)),
+        expand4(bits(block, 55, 4)),
+        expand4(bits(block, 51, 4)),
+    ];
+    let c2 = [expand4(bits(block, 47, 4)), expand4(bits(block, 43, 4)), expand4(bits(block, 39, 4))];
+    let d = TH_DISTANCES[((bits(block, 35, 2) << 1) | bits(block, 32, 1)) as usize];
+    let paint = [offset_color(c1, 0), offset_color(c2, d), offset_color(c2, 0), offset_color(c2, -d)];
+    decode_paint_colors(block, paint, punch_through, dst);
+}
+
+fn decode_h_mode(block: u64, punch_through: bool, dst: &mut [[u8; 4]; 16]) {
+    let r1 = bits(block, 62, 4);
+    let g1 = (bits(block, 58, 3) << 1) | bits(block, 52, 1);
+    let b1 = (bits(block, 51, 1) << 3) | bits(block, 49, 3);
+    let r2 = bits(block, 46, 4);
+    let g2 = bits(block, 42, 4);
+    let b2 = bits(block, 38, 4);
+    // the distance's lowest bit is whether the first color sorts first
+    let ordering = ((r1 << 8) | (g1 << 4) | b1) >= ((r2 << 8) | (g2 << 4) | b2);
+    let d = TH_DISTANCES[((bits(block, 34, 1) << 2) | (bits(block, 32, 1) << 1) | ordering as i32) as usize];
+    let c1 = [expand4(r1), expand4(g1), expand4(b1)];
+    let c2 = [expand4(r2), expand4(g2), expand4(b2)];
+    let paint = [offset_color(c1, d), offset_color(c1, -d), offset_color(c2, d), offset_color(c2, -d)];
+    decode_paint_colors(block, paint, punch_through, dst);
+}
+
+fn decode_planar_mode(block: u64, dst: &mut [[u8; 4]; 16]) {
+    let o = [
+        expand6(bits(block, 62, 6)),
+        expand7((bits(block, 56, 1) << 6) | bits(block, 54, 6)),
+        expand6((bits(block, 48, 1) << 5) | (bits(block, 44, 2) << 3) | bits(block, 41, 3)),
+    ];
+    let h = [
+        expand6((bits(block, 38, 5) << 1) | bits(block, 32, 1)),
+        expand7(bits(block, 31, 7)),
+        expand6(bits(block, 24, 6)),
+    ];
+    let v = [expand6(bits(block, 18, 6)), expand7(bits(block, 12, 7)), expand6(bits(block, 5, 6))];
+    for y in 0..4 {
+        for x in 0..4 {
+            let channel = |c: usize| clamp((x as i32 * (h[c] - o[c]) + y as i32 * (v[c] - o[c]) + 4 * o[c] + 2) >> 2);
+            dst[y * 4 + x] = [channel(0), channel(1), channel(2), 0xFF];
+        }
+    }
+}
+
+// Individual and differential modes, i.e. plain ETC1: two subblocks, each
+// with a base color and a modifier table
+fn decode_subblocks(block: u64, c1: [i32; 3], c2: [i32; 3], punch_through: bool, dst: &mut [[u8; 4]; 16]) {
+    let tables = [MODIFIER_TABLES[bits(block, 39, 3) as usize], MODIFIER_TABLES[bits(block, 36, 3) as usize]];
+    let flip = bits(block, 32, 1) != 0;
+    for x in 0..4 {
+        for y in 0..4 {
+            let subblock = (if flip { y >= 2 } else { x >= 2 }) as usize;
+            let base = if subblock == 0 { c1 } else { c2 };
+            let index = pixel_index(block, x, y);
+            dst[y * 4 + x] = if punch_through && index == TRANSPARENT_INDEX {
+                [0; 4]
+            } else if punch_through && index == 0 {
+                // the smaller modifiers are dropped to make room for transparency
+                offset_color(base, 0)
+            } else {
+                offset_color(base, tables[subblock][index])
+            };
+        }
+    }
+}
+
+fn decode_block(fmt: EtcFormat, block: u64, dst: &mut [[u8; 4]; 16]) {
+    // in punch-through blocks the diff bit becomes the opaque bit, and
+    // differential mode is always used
+    let (differential, punch_through) = match fmt {
+        EtcFormat::Etc2RGBA1 => (true, bits(block, 33, 1) == 0),
+        _ => (bits(block, 33, 1) != 0, false),
+    };
+
+    if !differential {
+        let c1 = [expand4(bits(block, 63, 4)), expand4(bits(block, 55, 4)), expand4(bits(block, 47, 4))];
+        let c2 = [expand4(bits(block, 59, 4)), expand4(bits(block, 51, 4)), expand4(bits(block, 43, 4))];
+        return decode_subblocks(block, c1, c2, false, dst);
+    }
+
+    let base = [bits(block, 63, 5), bits(block, 55, 5), bits(block, 47, 5)];
+    let delta = |high: u32| (bits(block, high, 3) << 29) >> 29;
+    let other = [base[0] + delta(58), base[1] + delta(50), base[2] + delta(42)];
+
+    // ETC2 fits its extra modes into differential blocks whose second color
+    // would overflow, which ETC1 encoders never produce
+    if fmt != EtcFormat::Etc1RGB {
+        if !(0..32).contains(&other[0]) {
+            return decode_t_mode(block, punch_through, dst);
+        } else if !(0..32).contains(&other[1]) {
+            return decode_h_mode(block, punch_through, dst);
+        } else if !(0..32).contains(&other[2]) {
+            return decode_planar_mode(block, dst);
+        }
+    }
+
+    let c1 = [expand5(base[0]), expand5(base[1]), expand5(base[2])];
+    let c2 = [expand5(other[0] & 0x1F), expand5(other[1] & 0x1F), expand5(other[2] & 0x1F)];
+    decode_subblocks(block, c1, c2, punch_through, dst);
+}
+
+// Decodes one mip level to RGBA8
+#[wasm_bindgen]
+pub fn decode_etc(fmt: EtcFormat, src: &[u8], w: usize, h: usize) -> Vec<u8> {
+    let mut dst = vec![0x00; w * h * 4];
+    let blocks_wide = w.div_ceil(4);
+    let blocks_high = h.div_ceil(4);
+    let mut pixels = [[0u8; 4]; 16];
+    for by in 0..blocks_high {
+        for bx in 0..blocks_wide {
+            let src_offs = (by * blocks_wide + bx) * 8;
+            let Some(block) = src.get(src_offs..src_offs + 8) else {
+                return dst;
+            };
+            decode_block(fmt, u64::from_be_bytes(block.try_into().unwrap()), &mut pixels);
+            for y in 0..4 {
+                for x in 0..4 {
+                    let (px, py) = (bx * 4 + x, by * 4 + y);
+                    if px >= w || py >= h {
+                        continue;
+                    }
+                    let dst_offs = (py * w + px) * 4;
+                    dst[dst_offs..dst_offs + 4].copy_from_slice(&pixels[y * 4 + x]);
+                }
+            }
+        }
+    }
+    dst
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_punch_through() {
+        // differential, opaque bit clear, base color 0x10 in each channel,
+        // no deltas, table 0; pixel (0, 0) uses index 2 and (1, 0) index 1
+        let block: u64 = (0x10 << 59) | (0x10 << 51) | (0x10 << 43) | (1 << 16) | (1 << 4);
+        let mut pixels = [[0u8; 4]; 16];
+        decode_block(EtcFormat::Etc2RGBA1, block, &mut pixels);
+        assert_eq!(pixels[0], [0, 0, 0, 0]);
+        assert_eq!(pixels[1], [0x84 + 8, 0x84 + 8, 0x84 + 8, 0xFF]);
+        // index 0 drops its modifier
+        assert_eq!(pixels[4], [0x84, 0x84, 0x84, 0xFF]);
+
+        // the same block with the opaque bit set decodes like ETC2 RGB
+        let opaque = block | (1 << 33);
+        decode_block(EtcFormat::Etc2RGBA1, opaque, &mut pixels);
+        let mut expected = [[0u8; 4]; 16];
+        decode_block(EtcFormat::Etc2RGB, opaque, &mut expected);
+        assert_eq!(pixels, expected);
+        assert_eq!(pixels[0], [0x84 - 2, 0x84 - 2, 0x84 - 2, 0xFF]);
+    }
+}
@@ -3,9 +3,12 @@ use core::f32;
 use nalgebra_glm::{make_mat4, make_vec3, triangle_normal, vec2, vec4, Mat4, Vec3, Vec2};
 use wasm_bindgen::prelude::*;
 
+#[wasm_bindgen(js_name = "Plane")]
 #[derive(Default, Debug, Clone)]
 pub struct Plane {
+    #[wasm_bindgen(skip)]
     pub d: f32,
+    #[wasm_bindgen(skip)]
     pub normal: Vec3,
 }
 
@@ -64,9 +67,48 @@ impl Plane {
     }
 }
 
+#[wasm_bindgen(js_class = "Plane")]
+impl Plane {
+    #[wasm_bindgen(constructor)]
+    pub fn js_new(x: f32, y: f32, z: f32, d: f32) -> Self {
+        Plane::new(Vec3::new(x, y, z), d)
+    }
+
+    pub fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    pub fn get_normal(&self) -> Vec<f32> {
+        self.normal.as_slice().to_vec()
+    }
+
+    pub fn get_d(&self) -> f32 {
+        self.d
+    }
+
+    pub fn normalize(&mut self) {
+        *self = self.normalized();
+    }
+
+    pub fn js_distance(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.distance(&Vec3::new(x, y, z))
+    }
+
+    // Takes the same matrix you'd transform points with
+    pub fn js_transform(&mut self, mat_slice: &[f32]) {
+        assert_eq!(mat_slice.len(), 16);
+        let mut inv_transpose_mat = make_mat4(mat_slice).try_inverse().unwrap();
+        inv_transpose_mat.transpose_mut();
+        self.transform(&inv_transpose_mat);
+    }
+}
+
+#[wasm_bindgen(js_name = "AABB")]
 #[derive(Debug, Clone)]
 pub struct AABB {
+    #[wasm_bindgen(skip)]
     pub min: Vec3,
+    #[wasm_bindgen(skip)]
     pub max: Vec3,
 }
 
@@ -132,6 +174,31 @@ impl AABB {
         self.max.z = self.max.z.max(p.z);
     }
 
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    // zero for points inside the box
+    pub fn distance_squared_to_point(&self, p: &Vec3) -> f32 {
+        let closest = p.sup(&self.min).inf(&self.max);
+        (closest - p).magnitude_squared()
+    }
+
+    // Returns the distances along the ray at which it enters and leaves the
+    // box, clamped to start at the origin. Takes 1 / direction so callers
+    // testing many boxes only divide once.
+    pub fn intersect_ray(&self, origin: &Vec3, inv_dir: &Vec3) -> Option<(f32, f32)> {
+        let mut t_min = 0.0f32;
+        let mut t_max = f32::INFINITY;
+        for i in 0..3 {
+            let t0 = (self.min[i] - origin[i]) * inv_dir[i];
+            let t1 = (self.max[i] - origin[i]) * inv_dir[i];
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+        }
+        if t_min <= t_max { Some((t_min, t_max)) } else { None }
+    }
+
     pub fn set_from_points(&mut self, points: &[Vec3]) {
         self.min = Vec3::from_element(f32::INFINITY);
         self.max = Vec3::from_element(f32::NEG_INFINITY);
@@ -159,6 +226,152 @@ impl AABB {
     }
 }
 
+#[wasm_bindgen(js_class = "AABB")]
+impl AABB {
+    #[wasm_bindgen(constructor)]
+    pub fn js_new(min_x: f32, min_y: f32, min_z: f32, max_x: f32, max_y: f32, max_z: f32) -> Self {
+        AABB::from_f32(min_x, min_y, min_z, max_x, max_y, max_z)
+    }
+
+    // A box containing nothing, to union things into
+    pub fn empty() -> Self {
+        AABB::default()
+    }
+
+    pub fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn get_min(&self) -> Vec<f32> {
+        self.min.as_slice().to_vec()
+    }
+
+    pub fn get_max(&self) -> Vec<f32> {
+        self.max.as_slice().to_vec()
+    }
+
+    pub fn get_center(&self) -> Vec<f32> {
+        self.center().as_slice().to_vec()
+    }
+
+    pub fn union_aabb(&mut self, other: &AABB) {
+        self.min = self.min.inf(&other.min);
+        self.max = self.max.sup(&other.max);
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let extents = self.max - self.min;
+        2.0 * (extents.x * extents.y + extents.y * extents.z + extents.z * extents.x)
+    }
+
+    pub fn intersects_aabb(&self, other: &AABB) -> bool {
+        !(other.min.x > self.max.x
+            || other.max.x < self.min.x
+            || other.min.y > self.max.y
+            || other.max.y < self.min.y
+            || other.min.z > self.max.z
+            || other.max.z < self.min.z)
+    }
+
+    pub fn contains_aabb(&self, other: &AABB) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    pub fn js_union_point(&mut self, x: f32, y: f32, z: f32) {
+        self.union_point(&Vec3::new(x, y, z));
+    }
+
+    pub fn js_contains_point(&self, x: f32, y: f32, z: f32) -> bool {
+        self.contains_point(&Vec3::new(x, y, z))
+    }
+
+    pub fn js_transform(&mut self, mat_slice: &[f32]) {
+        assert_eq!(mat_slice.len(), 16);
+        self.transform(&make_mat4(mat_slice));
+    }
+}
+
+#[wasm_bindgen(js_name = "Sphere")]
+#[derive(Debug, Clone)]
+pub struct Sphere {
+    #[wasm_bindgen(skip)]
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl Sphere {
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Sphere { center, radius }
+    }
+
+    pub fn contains_point(&self, p: &Vec3) -> bool {
+        (p - self.center).magnitude_squared() <= self.radius * self.radius
+    }
+
+    pub fn transform(&mut self, mat: &Mat4) {
+        self.center = (mat * vec4(self.center.x, self.center.y, self.center.z, 1.0)).xyz();
+        let scale = (0..3).map(|i| mat.column(i).xyz().magnitude()).fold(0.0, f32::max);
+        self.radius *= scale;
+    }
+}
+
+#[wasm_bindgen(js_class = "Sphere")]
+impl Sphere {
+    #[wasm_bindgen(constructor)]
+    pub fn js_new(x: f32, y: f32, z: f32, radius: f32) -> Self {
+        Sphere::new(Vec3::new(x, y, z), radius)
+    }
+
+    // The smallest sphere containing the box
+    pub fn from_aabb(aabb: &AABB) -> Self {
+        Sphere::new(aabb.center(), (aabb.max - aabb.min).magnitude() * 0.5)
+    }
+
+    pub fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    pub fn get_center(&self) -> Vec<f32> {
+        self.center.as_slice().to_vec()
+    }
+
+    // Grows this sphere to the smallest one containing both
+    pub fn union_sphere(&mut self, other: &Sphere) {
+        let offset = other.center - self.center;
+        let distance = offset.magnitude();
+        if distance + other.radius <= self.radius {
+            return;
+        }
+        if distance + self.radius <= other.radius {
+            *self = other.clone();
+            return;
+        }
+        let radius = (distance + self.radius + other.radius) * 0.5;
+        self.center += offset * ((radius - self.radius) / distance);
+        self.radius = radius;
+    }
+
+    pub fn intersects_sphere(&self, other: &Sphere) -> bool {
+        let radius = self.radius + other.radius;
+        (other.center - self.center).magnitude_squared() <= radius * radius
+    }
+
+    pub fn js_contains_point(&self, x: f32, y: f32, z: f32) -> bool {
+        self.contains_point(&Vec3::new(x, y, z))
+    }
+
+    // Scales the radius by the matrix's largest axis scale, so the result
+    // still contains everything it did
+    pub fn js_transform(&mut self, mat_slice: &[f32]) {
+        assert_eq!(mat_slice.len(), 16);
+        self.transform(&make_mat4(mat_slice));
+    }
+}
+
 #[wasm_bindgen(js_name = "IntersectionState")]
 pub enum IntersectionState {
     Inside,
@@ -293,6 +506,137 @@ impl ConvexHull {
     }
 }
 
+// A view frustum, built from a clip-from-world matrix, for culling lots of
+// bounding boxes at once
+#[wasm_bindgen(js_name = "Frustum")]
+#[derive(Debug, Clone)]
+pub struct Frustum {
+    hull: ConvexHull,
+}
+
+impl Frustum {
+    // clip_space_near_z_zero is true for APIs whose clip space depth range is
+    // [0, 1] (WebGPU), false for [-1, 1] (WebGL)
+    pub fn from_clip_from_world(mat: &Mat4, clip_space_near_z_zero: bool) -> Self {
+        let row = |i: usize| vec4(mat[i], mat[4 + i], mat[8 + i], mat[12 + i]);
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        let near = if clip_space_near_z_zero { z } else { w + z };
+        let mut hull = ConvexHull::new();
+        for plane in [w + x, w - x, w + y, w - y, near, w - z] {
+            // infinite far planes come out degenerate
+            if plane.xyz().magnitude_squared() > 0.0 {
+                hull.push_plane(plane.x, plane.y, plane.z, plane.w);
+            }
+        }
+        Frustum { hull }
+    }
+
+    pub fn intersect_aabb(&self, aabb: &AABB) -> IntersectionState {
+        self.hull.intersect_aabb(aabb)
+    }
+
+    pub fn contains_aabb(&self, aabb: &AABB) -> bool {
+        self.hull.contains_aabb(aabb)
+    }
+}
+
+#[wasm_bindgen(js_class = "Frustum")]
+impl Frustum {
+    #[wasm_bindgen(constructor)]
+    pub fn new(clip_from_world: &[f32], clip_space_near_z_zero: bool) -> Self {
+        assert_eq!(clip_from_world.len(), 16);
+        Frustum::from_clip_from_world(&make_mat4(clip_from_world), clip_space_near_z_zero)
+    }
+
+    // bounds holds six floats (min xyz, max xyz) per box. Returns a bitmask
+    // with bit i % 32 of word i / 32 set if box i is at least partly visible.
+    pub fn cull_aabbs(&self, bounds: &[f32]) -> Vec<u32> {
+        assert_eq!(bounds.len() % 6, 0);
+        let count = bounds.len() / 6;
+        let mut result = vec![0u32; count.div_ceil(32)];
+        for (i, aabb) in bounds.chunks_exact(6).enumerate() {
+            if self.contains_aabb(&AABB::from_slice(aabb)) {
+                result[i / 32] |= 1 << (i % 32);
+            }
+        }
+        result
+    }
+
+    pub fn js_contains_aabb(&self, min_x: f32, min_y: f32, min_z: f32, max_x: f32, max_y: f32, max_z: f32) -> bool {
+        self.contains_aabb(&AABB::from_f32(min_x, min_y, min_z, max_x, max_y, max_z))
+    }
+
+    pub fn js_intersect_aabb(&self, aabb: &AABB) -> IntersectionState {
+        self.intersect_aabb(aabb)
+    }
+
+    pub fn js_intersect_sphere(&self, sphere: &Sphere) -> IntersectionState {
+        self.hull.intersect_sphere(&sphere.center, sphere.radius)
+    }
+
+    pub fn js_contains_point(&self, x: f32, y: f32, z: f32) -> bool {
+        self.hull.contains_point(&Vec3::new(x, y, z))
+    }
+
+    // Moves the frustum along with the things it's tested against, e.g. to
+    // test in an object's local space, pass world_from_local's inverse
+    pub fn js_transform(&mut self, mat_slice: &[f32]) {
+        assert_eq!(mat_slice.len(), 16);
+        self.hull.transform(&make_mat4(mat_slice));
+    }
+}
+
+// Möller-Trumbore, hitting both sides of the triangle. Returns the distance
+// along the ray and the barycentric weights of p1 and p2.
+pub fn intersect_ray_triangle(origin: &Vec3, dir: &Vec3, p0: &Vec3, p1: &Vec3, p2: &Vec3) -> Option<(f32, f32, f32)> {
+    let edge1 = p1 - p0;
+    let edge2 = p2 - p0;
+    let p = dir.cross(&edge2);
+    let det = edge1.dot(&p);
+    if det == 0.0 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let s = origin - p0;
+    let u = s.dot(&p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = s.cross(&edge1);
+    let v = dir.dot(&q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = edge2.dot(&q) * inv_det;
+    if t >= 0.0 { Some((t, u, v)) } else { None }
+}
+
+// Composes translation * rotation * scale matrices, as Unity's Transforms do,
+// writing them column major into out, 16 floats each. Takes three floats per
+// position and scale and four per rotation quaternion (xyzw).
+#[wasm_bindgen]
+pub fn compose_trs_matrices(positions: &[f32], rotations: &[f32], scales: &[f32], out: &mut [f32]) {
+    let count = positions.len() / 3;
+    assert_eq!(positions.len(), count * 3);
+    assert_eq!(rotations.len(), count * 4);
+    assert_eq!(scales.len(), count * 3);
+    assert_eq!(out.len(), count * 16);
+    let inputs = positions.chunks_exact(3).zip(rotations.chunks_exact(4)).zip(scales.chunks_exact(3));
+    for (((p, q), s), m) in inputs.zip(out.chunks_exact_mut(16)) {
+        let (x, y, z, w) = (q[0], q[1], q[2], q[3]);
+        let (x2, y2, z2) = (x + x, y + y, z + z);
+        let (xx, xy, xz) = (x * x2, x * y2, x * z2);
+        let (yy, yz, zz) = (y * y2, y * z2, z * z2);
+        let (wx, wy, wz) = (w * x2, w * y2, w * z2);
+        m.copy_from_slice(&[
+            (1.0 - (yy + zz)) * s[0], (xy + wz) * s[0], (xz - wy) * s[0], 0.0,
+            (xy - wz) * s[1], (1.0 - (xx + zz)) * s[1], (yz + wx) * s[1], 0.0,
+            (xz + wy) * s[2], (yz - wx) * s[2], (1.0 - (xx + yy)) * s[2], 0.0,
+            p[0], p[1], p[2], 1.0,
+        ]);
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Axis {
     X,
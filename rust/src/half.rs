@@ -0,0 +1,130 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+// IEEE half precision conversions. Both directions are bit tricks with no
+// branches that the SIMD versions can't also do, so builds with simd128
+// enabled convert eight values at a time and the results match the scalar
+// versions exactly. The default build stays scalar, since not every browser
+// supports wasm SIMD; opt in with RUSTFLAGS="-C target-feature=+simd128".
+
+// 2^112, the difference between the two formats' exponent biases
+const EXPONENT_ADJUST: f32 = 5.192297e33;
+
+pub fn f16_to_f32(bits: u16) -> f32 {
+    let bits = bits as u32;
+    let sign = (bits & 0x8000) << 16;
+    let exponent_mantissa = bits & 0x7FFF;
+    // scaling the shifted bits handles normals and subnormals alike
+    let scaled = f32::from_bits(exponent_mantissa << 13) * EXPONENT_ADJUST;
+    let inf_nan = if exponent_mantissa >= 0x7C00 { 0x7F80_0000 } else { 0 };
+    f32::from_bits(scaled.to_bits() | inf_nan | sign)
+}
+
+// Rounds to nearest even; NaNs become quiet NaNs
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = bits & 0x8000_0000;
+    let abs = bits ^ sign;
+    let inf_nan = if abs > 0x7F80_0000 { 0x7E00 } else { 0x7C00 };
+    // adding 0.5 lines the subnormal half's bits up with the float's mantissa
+    let subnormal = (f32::from_bits(abs) + 0.5).to_bits().wrapping_sub(0x3F00_0000);
+    let normal = abs.wrapping_add(0xC800_0FFF).wrapping_add((abs >> 13) & 1) >> 13;
+    let result = if abs >= 0x4780_0000 {
+        inf_nan
+    } else if abs < 0x3880_0000 {
+        subnormal
+    } else {
+        normal
+    };
+    (result | (sign >> 16)) as u16
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod simd {
+    use core::arch::wasm32::*;
+
+    use super::EXPONENT_ADJUST;
+
+    fn f16_to_f32_x4(bits: v128) -> v128 {
+        let sign = u32x4_shl(v128_and(bits, u32x4_splat(0x8000)), 16);
+        let exponent_mantissa = v128_and(bits, u32x4_splat(0x7FFF));
+        let scaled = f32x4_mul(u32x4_shl(exponent_mantissa, 13), f32x4_splat(EXPONENT_ADJUST));
+        let inf_nan = v128_and(u32x4_ge(exponent_mantissa, u32x4_splat(0x7C00)), u32x4_splat(0x7F80_0000));
+        v128_or(v128_or(scaled, inf_nan), sign)
+    }
+
+    fn f32_to_f16_x4(value: v128) -> v128 {
+        let sign = v128_and(value, u32x4_splat(0x8000_0000));
+        let abs = v128_xor(value, sign);
+        let inf_nan = v128_bitselect(u32x4_splat(0x7E00), u32x4_splat(0x7C00), u32x4_gt(abs, u32x4_splat(0x7F80_0000)));
+        let subnormal = u32x4_sub(f32x4_add(abs, f32x4_splat(0.5)), u32x4_splat(0x3F00_0000));
+        let odd = v128_and(u32x4_shr(abs, 13), u32x4_splat(1));
+        let normal = u32x4_shr(u32x4_add(u32x4_add(abs, u32x4_splat(0xC800_0FFF)), odd), 13);
+        let finite = v128_bitselect(subnormal, normal, u32x4_lt(abs, u32x4_splat(0x3880_0000)));
+        let result = v128_bitselect(inf_nan, finite, u32x4_ge(abs, u32x4_splat(0x4780_0000)));
+        v128_or(result, u32x4_shr(sign, 16))
+    }
+
+    // Returns how many values were converted
+    pub fn f16_to_f32(input: &[u16], output: &mut [f32]) -> usize {
+        for (input, output) in input.chunks_exact(8).zip(output.chunks_exact_mut(8)) {
+            unsafe {
+                let bits = v128_load(input.as_ptr() as *const v128);
+                v128_store(output.as_mut_ptr() as *mut v128, f16_to_f32_x4(u32x4_extend_low_u16x8(bits)));
+                v128_store(output.as_mut_ptr().add(4) as *mut v128, f16_to_f32_x4(u32x4_extend_high_u16x8(bits)));
+            }
+        }
+        input.len() / 8 * 8
+    }
+
+    pub fn f32_to_f16(input: &[f32], output: &mut [u16]) -> usize {
+        for (input, output) in input.chunks_exact(8).zip(output.chunks_exact_mut(8)) {
+            unsafe {
+                let low = f32_to_f16_x4(v128_load(input.as_ptr() as *const v128));
+                let high = f32_to_f16_x4(v128_load(input.as_ptr().add(4) as *const v128));
+                v128_store(output.as_mut_ptr() as *mut v128, u16x8_narrow_i32x4(low, high));
+            }
+        }
+        input.len() / 8 * 8
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+mod simd {
+    pub fn f16_to_f32(_input: &[u16], _output: &mut [f32]) -> usize {
+        0
+    }
+
+    pub fn f32_to_f16(_input: &[f32], _output: &mut [u16]) -> usize {
+        0
+    }
+}
+
+pub fn f16_to_f32_slice(input: &[u16], output: &mut [f32]) {
+    assert_eq!(input.len(), output.len());
+    let start = simd::f16_to_f32(input, output);
+    for (bits, value) in input[start..].iter().zip(&mut output[start..]) {
+        *value = f16_to_f32(*bits);
+    }
+}
+
+pub fn f32_to_f16_slice(input: &[f32], output: &mut [u16]) {
+    assert_eq!(input.len(), output.len());
+    let start = simd::f32_to_f16(input, output);
+    for (value, bits) in input[start..].iter().zip(&mut output[start..]) {
+        *bits = f32_to_f16(*value);
+    }
+}
+
+#[wasm_bindgen]
+pub fn convert_f16_to_f32(data: &[u16]) -> Vec<f32> {
+    let mut result = vec![0.0; data.len()];
+    f16_to_f32_slice(data, &mut result);
+    result
+}
+
+#[wasm_bindgen]
+pub fn convert_f32_to_f16(data: &[f32]) -> Vec<u16> {
+    let mut result = vec![0; data.len()];
+    f32_to_f16_slice(data, &mut result);
+    result
+}
@@ -239,6 +239,10 @@ impl BitmapFormat {
             _ => 2,
         }
     }
+
+    pub fn is_compressed(&self) -> bool {
+        matches!(self, BitmapFormat::Dxt1 | BitmapFormat::Dxt3 | BitmapFormat::Dxt5)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -262,6 +266,23 @@ impl BitmapData {
     pub fn is_external(&self) -> bool {
         (self.flags & 0x100) > 0
     }
+
+    pub fn is_swizzled(&self) -> bool {
+        (self.flags & 0x8) > 0
+    }
+
+    pub fn get_face_count(&self) -> usize {
+        match self.bitmap_type {
+            BitmapDataType::CubeMap => 6,
+            _ => 1,
+        }
+    }
+
+    pub fn get_mip_dimensions(&self, level: u16) -> (usize, usize) {
+        let width = (self.width as usize >> level).max(1);
+        let height = (self.height as usize >> level).max(1);
+        (width, height)
+    }
 }
 
 impl Deserialize for BitmapData {
@@ -327,7 +348,10 @@ pub struct Bitmap {
 
 impl Bitmap {
     pub fn get_dimensions(&self) -> (u32, u32) {
-        todo!();
+        match self.data.items.as_ref().and_then(|items| items.first()) {
+            Some(data) => (data.width as u32, data.height as u32),
+            None => (0, 0),
+        }
     }
 }
 
@@ -1,5 +1,6 @@
 
 use crate::util;
+use crate::halo::bitmap::*;
 
 static P8_PALETTE: &[u8] = &[
     0xFF,0x7A,0x19,0xCC,0xFF,0x7E,0x19,0xCC,0xFF,0x80,0x19,0xCC,0xFF,0x81,0x19,0xCC,0xFF,0x85,0x19,0xCC,0xFF,0x74,0x2F,0xE2,0xFF,0x7A,0x2F,0xE2,0xFF,0x7E,0x2F,0xE2,0xFF,0x80,0x2F,0xE2,0xFF,0x81,0x2F,0xE2,0xFF,0x85,0x2F,0xE2,0xFF,0x8B,0x2F,0xE2,0xFF,0x6B,0x42,0xED,0xFF,0x74,0x42,0xEE,0xFF,0x7A,0x42,0xEF,0xFF,0x7E,0x42,0xEF,
@@ -65,6 +66,17 @@ pub fn convert_y8_data(input: &[u8]) -> Vec<u8> {
     result
 }
 
+pub fn convert_ay8_data(input: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(input.len() * 4);
+    for &x in input {
+        result.push(x);
+        result.push(x);
+        result.push(x);
+        result.push(x);
+    }
+    result
+}
+
 pub fn convert_x8r8g8b8_data(input: &[u8]) -> Vec<u8> {
     let mut result = Vec::with_capacity(input.len());
     for i in 0..input.len()/4 {
@@ -100,3 +112,142 @@ pub fn convert_r5g6b5_data(input: &[u8]) -> Vec<u8> {
     }
     result
 }
+
+pub fn convert_a1r5g5b5_data(input: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(input.len() * 2);
+    for i in (0..input.len()).step_by(2) {
+        let p = ((input[i + 1] as u16) << 8) | (input[i] as u16);
+        result.push(util::expand_n_to_8(5, ((p >> 10) & 0x1F) as u8));
+        result.push(util::expand_n_to_8(5, ((p >>  5) & 0x1F) as u8));
+        result.push(util::expand_n_to_8(5, (p & 0x1F) as u8));
+        result.push(if (p >> 15) != 0 { 0xFF } else { 0x00 });
+    }
+    result
+}
+
+pub fn convert_a4r4g4b4_data(input: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(input.len() * 2);
+    for i in (0..input.len()).step_by(2) {
+        let p = ((input[i + 1] as u16) << 8) | (input[i] as u16);
+        result.push(util::expand_n_to_8(4, ((p >>  8) & 0x0F) as u8));
+        result.push(util::expand_n_to_8(4, ((p >>  4) & 0x0F) as u8));
+        result.push(util::expand_n_to_8(4, (p & 0x0F) as u8));
+        result.push(util::expand_n_to_8(4, ((p >> 12) & 0x0F) as u8));
+    }
+    result
+}
+
+// Xbox swizzling interleaves the bits of x and y (x first), dropping bits from
+// whichever axis runs out first
+pub fn swizzle_offset(mut x: usize, mut y: usize, mut width: usize, mut height: usize) -> usize {
+    let mut offset = 0;
+    let mut shift = 0;
+    while width > 1 || height > 1 {
+        if width > 1 {
+            offset |= (x & 1) << shift;
+            x >>= 1;
+            width >>= 1;
+            shift += 1;
+        }
+        if height > 1 {
+            offset |= (y & 1) << shift;
+            y >>= 1;
+            height >>= 1;
+            shift += 1;
+        }
+    }
+    offset
+}
+
+pub fn deswizzle(input: &[u8], width: usize, height: usize, bytes_per_pixel: usize) -> Vec<u8> {
+    let mut result = vec![0; width * height * bytes_per_pixel];
+    for y in 0..height {
+        for x in 0..width {
+            let src = swizzle_offset(x, y, width, height) * bytes_per_pixel;
+            let dst = (y * width + x) * bytes_per_pixel;
+            if src + bytes_per_pixel <= input.len() {
+                result[dst..dst + bytes_per_pixel].copy_from_slice(&input[src..src + bytes_per_pixel]);
+            }
+        }
+    }
+    result
+}
+
+// Each face of a swizzled bitmap stores its own mip chain, and every mip level
+// is swizzled independently
+fn deswizzle_surfaces(input: &[u8], bitmap_data: &BitmapData) -> Vec<u8> {
+    let bytes_per_pixel = bitmap_data.format.pitch() as usize;
+    let mut result = Vec::with_capacity(input.len());
+    let mut offset = 0;
+    for _ in 0..bitmap_data.get_face_count() {
+        for level in 0..=bitmap_data.mipmap_count {
+            let (width, height) = bitmap_data.get_mip_dimensions(level);
+            let size = width * height * bytes_per_pixel;
+            let end = (offset + size).min(input.len());
+            result.extend(deswizzle(&input[offset.min(end)..end], width, height, bytes_per_pixel));
+            offset += size;
+        }
+    }
+    result
+}
+
+// DXT data is passed through untouched for GPU upload; everything else is
+// expanded to RGBA8
+pub fn convert_bitmap_data(input: &[u8], bitmap_data: &BitmapData) -> Vec<u8> {
+    if bitmap_data.format.is_compressed() {
+        return Vec::from(input);
+    }
+    let deswizzled;
+    let input = if bitmap_data.is_swizzled() && !matches!(bitmap_data.bitmap_type, BitmapDataType::Tex3D) {
+        deswizzled = deswizzle_surfaces(input, bitmap_data);
+        &deswizzled[..]
+    } else {
+        input
+    };
+    match bitmap_data.format {
+        BitmapFormat::P8 | BitmapFormat::P8Bump => convert_p8_data(input),
+        BitmapFormat::A8r8g8b8 => convert_a8r8g8b8_data(input),
+        BitmapFormat::X8r8g8b8 => convert_x8r8g8b8_data(input),
+        BitmapFormat::A8 => convert_a8_data(input),
+        BitmapFormat::Y8 => convert_y8_data(input),
+        BitmapFormat::Ay8 => convert_ay8_data(input),
+        BitmapFormat::A8y8 => convert_a8y8_data(input),
+        BitmapFormat::R5g6b5 => convert_r5g6b5_data(input),
+        BitmapFormat::A1r5g5b5 => convert_a1r5g5b5_data(input),
+        BitmapFormat::A4r4g4b4 => convert_a4r4g4b4_data(input),
+        _ => Vec::from(input),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swizzle_offset() {
+        assert_eq!(swizzle_offset(0, 0, 4, 4), 0);
+        assert_eq!(swizzle_offset(1, 0, 4, 4), 1);
+        assert_eq!(swizzle_offset(0, 1, 4, 4), 2);
+        assert_eq!(swizzle_offset(1, 1, 4, 4), 3);
+        assert_eq!(swizzle_offset(2, 0, 4, 4), 4);
+        assert_eq!(swizzle_offset(3, 3, 4, 4), 15);
+        // once y runs out, the remaining x bits are packed contiguously
+        assert_eq!(swizzle_offset(2, 0, 4, 1), 2);
+        assert_eq!(swizzle_offset(3, 1, 4, 2), 7);
+    }
+
+    #[test]
+    fn test_deswizzle() {
+        let swizzled: Vec<u8> = (0..16).collect();
+        let linear = deswizzle(&swizzled, 4, 4, 1);
+        assert_eq!(&linear[0..4], &[0, 1, 4, 5]);
+        assert_eq!(&linear[4..8], &[2, 3, 6, 7]);
+        assert_eq!(&linear[12..16], &[10, 11, 14, 15]);
+    }
+
+    #[test]
+    fn test_a1r5g5b5() {
+        assert_eq!(convert_a1r5g5b5_data(&[0x1F, 0xFC]), vec![0xFF, 0x00, 0xFF, 0xFF]);
+        assert_eq!(convert_a1r5g5b5_data(&[0x00, 0x00]), vec![0x00, 0x00, 0x00, 0x00]);
+    }
+}
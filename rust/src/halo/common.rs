@@ -55,9 +55,9 @@ impl<T: Deserialize + std::fmt::Debug> Block<T> {
         if self.count > 0 {
             let pointer = offset + self.base_pointer as i64;
             if pointer < 0 {
-                panic!("pointer underflow for offset {} and pointer {}", offset, self.base_pointer);
+                return Err(MapReaderError::InvalidTag(format!("pointer underflow for offset {} and pointer {}", offset, self.base_pointer)));
             }
-            data.seek(SeekFrom::Start((self.base_pointer as i64 + offset) as u64))?;
+            data.seek(SeekFrom::Start(pointer as u64))?;
             for _ in 0..self.count {
                 items.push(T::deserialize(data)?);
             }
@@ -220,6 +220,25 @@ impl Deserialize for Point3D {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct Quaternion {
+    pub i: f32,
+    pub j: f32,
+    pub k: f32,
+    pub w: f32,
+}
+
+impl Deserialize for Quaternion {
+    fn deserialize(data: &mut Cursor<Vec<u8>>) -> Result<Self> where Self: Sized {
+        Ok(Quaternion {
+            i: data.read_f32::<LittleEndian>()?,
+            j: data.read_f32::<LittleEndian>()?,
+            k: data.read_f32::<LittleEndian>()?,
+            w: data.read_f32::<LittleEndian>()?,
+        })
+    }
+}
+
 #[wasm_bindgen]
 #[derive(Debug, Clone, Copy)]
 pub struct Euler3D {
@@ -12,6 +12,18 @@ use crate::halo::shader::*;
 use byteorder::{LittleEndian, ReadBytesExt};
 
 const BASE_MEMORY_ADDRESS: Pointer = 0x50000000;
+const HEADER_MAGIC: u32 = 1751474532; // 'head'
+const FOOTER_MAGIC: u32 = 1718579060; // 'foot'
+const TAGS_MAGIC: u32 = 1952540531; // 'tags'
+const NULL_TAG_ID: u32 = 0xFFFFFFFF;
+
+#[derive(Debug, Copy, Clone, PartialEq, TryFromPrimitive)]
+#[repr(u32)]
+pub enum CacheVersion {
+    Retail = 0x7,
+    CustomEdition = 0x261,
+    MCC = 0xD,
+}
 
 pub struct MapManager {
     pub reader: MapReader,
@@ -63,7 +75,7 @@ impl MapManager {
     fn read_tag_at_offset(&mut self, tag_header: &TagHeader, offset: i64) -> Result<Tag> {
         let tag_pointer = offset + tag_header.tag_data as i64;
         if tag_pointer < 0 {
-            panic!("invalid tag pointer {} for header {:?}", tag_pointer, tag_header)
+            return Err(MapReaderError::InvalidTag(format!("invalid tag pointer {} for header {:?}", tag_pointer, tag_header)));
         }
         self.reader.data.seek(SeekFrom::Start(tag_pointer as u64))?;
         let data = match tag_header.primary_class {
@@ -85,8 +97,10 @@ impl MapManager {
                 let mut bsp = BSP::deserialize(&mut self.reader.data)?;
                 bsp.surfaces.read_items(&mut self.reader.data, offset)?;
                 bsp.lightmaps.read_items(&mut self.reader.data, offset)?;
-                for lightmap in bsp.lightmaps.items.as_mut().unwrap() {
-                    lightmap.materials.read_items(&mut self.reader.data, offset)?;
+                if let Some(lightmaps) = bsp.lightmaps.items.as_mut() {
+                    for lightmap in lightmaps {
+                        lightmap.materials.read_items(&mut self.reader.data, offset)?;
+                    }
                 }
                 TagData::BSP(bsp)
             },
@@ -162,9 +176,22 @@ impl MapManager {
                             geometry.parts.read_items(&mut self.reader.data, offset)?;
                         }
                     },
-                    None => panic!("failed to load geometries for {:?}", model),
+                    None => return Err(MapReaderError::InvalidTag(format!("failed to load geometries for {:?}", tag_header))),
                 }
                 model.shaders.read_items(&mut self.reader.data, offset)?;
+                model.nodes.read_items(&mut self.reader.data, offset)?;
+                model.markers.read_items(&mut self.reader.data, offset)?;
+                if let Some(markers) = model.markers.items.as_mut() {
+                    for marker in markers {
+                        marker.instances.read_items(&mut self.reader.data, offset)?;
+                    }
+                }
+                model.regions.read_items(&mut self.reader.data, offset)?;
+                if let Some(regions) = model.regions.items.as_mut() {
+                    for region in regions {
+                        region.permutations.read_items(&mut self.reader.data, offset)?;
+                    }
+                }
                 TagData::GbxModel(model)
             },
             _ => return Err(MapReaderError::UnimplementedTag(format!("can't yet read {:?}", tag_header))),
@@ -173,31 +200,69 @@ impl MapManager {
     }
 
     pub fn get_scenario(&mut self) -> Result<Tag> {
-        let header = self.tag_headers.iter()
-            .find(|header| matches!(header.primary_class, TagClass::Scenario))
-            .unwrap().clone();
+        let header = self.get_tag_header(self.tag_index_header.scenario_tag_id)
+            .or_else(|| self.tag_headers.iter()
+                .find(|header| matches!(header.primary_class, TagClass::Scenario))
+                .cloned())
+            .ok_or_else(|| MapReaderError::InvalidTag("map has no scenario tag".to_string()))?;
         self.read_tag(&header)
     }
 
+    // The low 16 bits of a tag ID are its index into the tag array, so try
+    // that first before falling back to a linear search
+    pub fn get_tag_header(&self, tag_id: u32) -> Option<TagHeader> {
+        if tag_id == NULL_TAG_ID {
+            return None;
+        }
+        let index = (tag_id & 0xFFFF) as usize;
+        match self.tag_headers.get(index) {
+            Some(header) if header.tag_id == tag_id => Some(header.clone()),
+            _ => self.tag_headers.iter()
+                .find(|header| header.tag_id == tag_id)
+                .cloned(),
+        }
+    }
+
     pub fn resolve_dependency(&self, dependency: &TagDependency) -> Option<TagHeader> {
+        self.get_tag_header(dependency.tag_id)
+    }
+
+    pub fn find_tag_header(&self, path: &str, class: TagClass) -> Option<TagHeader> {
         self.tag_headers.iter()
-            .find(|header| header.tag_id == dependency.tag_id)
+            .find(|header| header.primary_class == class && header.path == path)
             .cloned()
     }
 
+    pub fn read_tag_by_path(&mut self, path: &str, class: TagClass) -> Result<Tag> {
+        let header = self.find_tag_header(path, class)
+            .ok_or_else(|| MapReaderError::InvalidTag(format!("no {:?} tag at {}", class, path)))?;
+        self.read_tag(&header)
+    }
+
+    pub fn get_tags_of_class(&mut self, class: TagClass) -> Result<Vec<Tag>> {
+        let headers: Vec<TagHeader> = self.tag_headers.iter()
+            .filter(|header| header.primary_class == class)
+            .cloned()
+            .collect();
+        let mut result = Vec::with_capacity(headers.len());
+        for hdr in &headers {
+            result.push(self.read_tag(hdr)?);
+        }
+        Ok(result)
+    }
+
     pub fn get_scenario_bsps(&mut self, tag: &Tag) -> Result<Vec<Tag>> {
         if let TagData::Scenario(scenario) = &tag.data {
             if let Some(bsp_references) = &scenario.structure_bsp_references.items {
-                let bsp_refs_and_headers: Vec<(&ScenarioStructureBSPReference, TagHeader)> = bsp_references.iter()
-                    .map(|bsp_ref| (bsp_ref, self.resolve_dependency(&bsp_ref.structure_bsp).unwrap()))
-                    .collect();
                 let mut result = Vec::new();
-                for (bsp_ref, mut header) in bsp_refs_and_headers {
+                for bsp_ref in bsp_references {
+                    let mut header = self.resolve_dependency(&bsp_ref.structure_bsp)
+                        .ok_or_else(|| MapReaderError::InvalidTag(format!("couldn't resolve bsp {:?}", bsp_ref.structure_bsp)))?;
                     self.reader.data.seek(SeekFrom::Start(bsp_ref.start as u64))?;
                     let bsp_header = BSPHeader::deserialize(&mut self.reader.data)?;
                     let offset = bsp_ref.start as i64 - bsp_ref.address as i64;
                     header.tag_data = bsp_header.bsp_offset;
-                    let mut tag = self.read_tag_at_offset(&header, offset).unwrap();
+                    let mut tag = self.read_tag_at_offset(&header, offset)?;
                     if let TagData::BSP(bsp) = &mut tag.data {
                         bsp.header = Some(bsp_header);
                     }
@@ -211,15 +276,7 @@ impl MapManager {
     }
 
     pub fn get_bitmaps(&mut self) -> Result<Vec<Tag>> {
-        let bitmap_headers: Vec<TagHeader> = self.tag_headers.iter()
-            .filter(|header| matches!(header.primary_class, TagClass::Bitmap))
-            .cloned()
-            .collect();
-        let mut result = Vec::new();
-        for hdr in &bitmap_headers {
-            result.push(self.read_tag(hdr)?);
-        }
-        Ok(result)
+        self.get_tags_of_class(TagClass::Bitmap)
     }
 }
 
@@ -274,6 +331,7 @@ impl MapReader {
 
 #[derive(Debug)]
 pub struct Header {
+    pub version: CacheVersion,
     pub uncompressed_file_size: u32,
     pub tag_data_offset: Pointer,
     pub tag_data_size: u32,
@@ -341,8 +399,11 @@ pub enum ScenarioType {
 
 impl Deserialize for Header {
     fn deserialize(data: &mut Cursor<Vec<u8>>) -> Result<Self> where Self: Sized {
-        assert_eq!(data.read_u32::<LittleEndian>()?, 1751474532); // magic
-        assert_eq!(data.read_u32::<LittleEndian>()?, 0xD); // MCC
+        let magic = data.read_u32::<LittleEndian>()?;
+        if magic != HEADER_MAGIC {
+            return Err(MapReaderError::IO(format!("invalid cache header magic {:#x}", magic)));
+        }
+        let version = CacheVersion::try_from(data.read_u32::<LittleEndian>()?)?;
         let uncompressed_file_size = data.read_u32::<LittleEndian>()?;
         let _padding_length = data.read_u32::<LittleEndian>()?;
         let tag_data_offset = data.read_u32::<LittleEndian>()?;
@@ -354,8 +415,12 @@ impl Deserialize for Header {
         data.seek(SeekFrom::Current(0x2))?;
         let _checksum = data.read_u32::<LittleEndian>()?;
         data.seek(SeekFrom::Current(0x794))?;
-        assert_eq!(data.read_u32::<LittleEndian>()?, 1718579060);
+        let footer = data.read_u32::<LittleEndian>()?;
+        if footer != FOOTER_MAGIC {
+            return Err(MapReaderError::IO(format!("invalid cache footer magic {:#x}", footer)));
+        }
         Ok(Header{
+            version,
             uncompressed_file_size,
             tag_data_offset,
             tag_data_size,
@@ -387,7 +452,10 @@ impl Deserialize for TagIndexHeader {
         let _model_part_count_pc = data.read_u32::<LittleEndian>()?;
         let vertex_data_size = data.read_u32::<LittleEndian>()?;
         let model_data_size = data.read_u32::<LittleEndian>()?;
-        assert_eq!(data.read_u32::<LittleEndian>()?, 1952540531);
+        let magic = data.read_u32::<LittleEndian>()?;
+        if magic != TAGS_MAGIC {
+            return Err(MapReaderError::IO(format!("invalid tag index magic {:#x}", magic)));
+        }
         Ok(TagIndexHeader {
             tag_count,
             tag_array_pointer,
@@ -418,6 +486,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_invalid_header() {
+        let mut data = vec![0; 0x800];
+        assert!(MapManager::new(data.clone()).is_err());
+        data[0..4].copy_from_slice(&HEADER_MAGIC.to_le_bytes());
+        data[4..8].copy_from_slice(&0x3u32.to_le_bytes());
+        assert!(MapManager::new(data).is_err());
+    }
+
     #[test]
     fn test_waves() {
         let mut mgr = MapManager::new(read_map("b30.map")).unwrap();
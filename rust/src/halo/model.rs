@@ -1,6 +1,7 @@
-use std::io::{Cursor, Seek, SeekFrom};
+use std::io::{Cursor, Read, Seek, SeekFrom};
 use byteorder::{ReadBytesExt, LittleEndian};
 use crate::halo::common::*;
+use crate::halo::util::*;
 use crate::halo::tag::*;
 
 #[derive(Debug, Clone)]
@@ -68,14 +69,195 @@ impl Deserialize for Sky {
     }
 }
 
+pub const LOD_COUNT: usize = 5;
+const MODEL_FLAG_PARTS_HAVE_LOCAL_NODES: u32 = 0x2;
+
+// Detail levels, indexed from lowest (0) to highest (4)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ModelLod {
+    SuperLow = 0,
+    Low = 1,
+    Medium = 2,
+    High = 3,
+    SuperHigh = 4,
+}
+
 #[derive(Debug, Clone)]
 pub struct GbxModel {
+    pub flags: u32,
+    pub lod_cutoffs: [f32; LOD_COUNT],
+    pub lod_nodes: [i16; LOD_COUNT],
     pub base_bitmap_u_scale: f32,
     pub base_bitmap_v_scale: f32,
+    pub markers: Block<GbxModelMarker>,
+    pub nodes: Block<GbxModelNode>,
+    pub regions: Block<GbxModelRegion>,
     pub geometries: Block<GbxModelGeometry>,
     pub shaders: Block<GbxModelShader>,
 }
 
+impl GbxModel {
+    pub fn parts_have_local_nodes(&self) -> bool {
+        self.flags & MODEL_FLAG_PARTS_HAVE_LOCAL_NODES != 0
+    }
+
+    // Cutoffs are the projected size (in pixels) at which a LOD kicks in, so
+    // pick the most detailed LOD whose cutoff we've reached
+    pub fn get_lod_for_pixel_size(&self, pixels: f32) -> ModelLod {
+        use ModelLod::*;
+        let lods = [SuperLow, Low, Medium, High, SuperHigh];
+        for lod in lods.iter().rev() {
+            if pixels >= self.lod_cutoffs[*lod as usize] {
+                return *lod;
+            }
+        }
+        SuperLow
+    }
+
+    // Geometry indices for the given LOD of each region's first (default)
+    // permutation. Missing LODs fall back to the next more detailed one.
+    pub fn get_lod_geometry_indices(&self, lod: ModelLod) -> Vec<usize> {
+        let geometry_count = self.geometries.count;
+        let mut result = Vec::new();
+        for region in self.regions.items.iter().flatten() {
+            let permutation = match region.permutations.items.as_ref().and_then(|p| p.first()) {
+                Some(permutation) => permutation,
+                None => continue,
+            };
+            let index = permutation.lod_geometry_indices[lod as usize..].iter()
+                .find(|&&index| index >= 0 && (index as usize) < geometry_count);
+            if let Some(&index) = index {
+                if !result.contains(&(index as usize)) {
+                    result.push(index as usize);
+                }
+            }
+        }
+        result
+    }
+
+    pub fn get_lod_parts(&self, lod: ModelLod) -> Vec<&GbxModelPart> {
+        let geometries = match &self.geometries.items {
+            Some(geometries) => geometries,
+            None => return Vec::new(),
+        };
+        self.get_lod_geometry_indices(lod).iter()
+            .flat_map(|&index| geometries[index].parts.items.iter().flatten())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GbxModelMarker {
+    pub name: String,
+    pub magic_identifier: i16,
+    pub instances: Block<GbxModelMarkerInstance>,
+}
+
+impl Deserialize for GbxModelMarker {
+    fn deserialize(data: &mut Cursor<Vec<u8>>) -> Result<Self> where Self: Sized {
+        let start = data.position();
+        let name = read_null_terminated_string_with_size(data, 32)?;
+        let magic_identifier = data.read_i16::<LittleEndian>()?;
+        data.seek(SeekFrom::Start(start + 52))?;
+        let instances = Block::deserialize(data)?;
+        Ok(GbxModelMarker { name, magic_identifier, instances })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GbxModelMarkerInstance {
+    pub region_index: u8,
+    pub permutation_index: u8,
+    pub node_index: u8,
+    pub translation: Point3D,
+    pub rotation: Quaternion,
+}
+
+impl Deserialize for GbxModelMarkerInstance {
+    fn deserialize(data: &mut Cursor<Vec<u8>>) -> Result<Self> where Self: Sized {
+        let region_index = data.read_u8()?;
+        let permutation_index = data.read_u8()?;
+        let node_index = data.read_u8()?;
+        data.seek(SeekFrom::Current(1))?;
+        let translation = Point3D::deserialize(data)?;
+        let rotation = Quaternion::deserialize(data)?;
+        Ok(GbxModelMarkerInstance { region_index, permutation_index, node_index, translation, rotation })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GbxModelNode {
+    pub name: String,
+    pub next_sibling_node_index: i16,
+    pub first_child_node_index: i16,
+    pub parent_node_index: i16,
+    pub default_translation: Point3D,
+    pub default_rotation: Quaternion,
+    pub node_distance_from_parent: f32,
+}
+
+impl Deserialize for GbxModelNode {
+    fn deserialize(data: &mut Cursor<Vec<u8>>) -> Result<Self> where Self: Sized {
+        let start = data.position();
+        let name = read_null_terminated_string_with_size(data, 32)?;
+        let next_sibling_node_index = data.read_i16::<LittleEndian>()?;
+        let first_child_node_index = data.read_i16::<LittleEndian>()?;
+        let parent_node_index = data.read_i16::<LittleEndian>()?;
+        data.seek(SeekFrom::Current(2))?;
+        let default_translation = Point3D::deserialize(data)?;
+        let default_rotation = Quaternion::deserialize(data)?;
+        let node_distance_from_parent = data.read_f32::<LittleEndian>()?;
+        data.seek(SeekFrom::Start(start + 156))?;
+        Ok(GbxModelNode {
+            name,
+            next_sibling_node_index,
+            first_child_node_index,
+            parent_node_index,
+            default_translation,
+            default_rotation,
+            node_distance_from_parent,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GbxModelRegion {
+    pub name: String,
+    pub permutations: Block<GbxModelRegionPermutation>,
+}
+
+impl Deserialize for GbxModelRegion {
+    fn deserialize(data: &mut Cursor<Vec<u8>>) -> Result<Self> where Self: Sized {
+        let start = data.position();
+        let name = read_null_terminated_string_with_size(data, 32)?;
+        data.seek(SeekFrom::Start(start + 64))?;
+        let permutations = Block::deserialize(data)?;
+        Ok(GbxModelRegion { name, permutations })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GbxModelRegionPermutation {
+    pub name: String,
+    pub flags: u32,
+    pub lod_geometry_indices: [i16; LOD_COUNT], // super low to super high
+}
+
+impl Deserialize for GbxModelRegionPermutation {
+    fn deserialize(data: &mut Cursor<Vec<u8>>) -> Result<Self> where Self: Sized {
+        let start = data.position();
+        let name = read_null_terminated_string_with_size(data, 32)?;
+        let flags = data.read_u32::<LittleEndian>()?;
+        data.seek(SeekFrom::Start(start + 64))?;
+        let mut lod_geometry_indices = [0; LOD_COUNT];
+        for index in lod_geometry_indices.iter_mut() {
+            *index = data.read_i16::<LittleEndian>()?;
+        }
+        data.seek(SeekFrom::Start(start + 88))?;
+        Ok(GbxModelRegionPermutation { name, flags, lod_geometry_indices })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GbxModelGeometry {
     pub parts: Block<GbxModelPart>,
@@ -90,6 +272,9 @@ impl Deserialize for GbxModelGeometry {
     }
 }
 
+pub const MODEL_VERTEX_SIZE: usize = 68; // position + normal + binormal + tangent + uv + 2 node indices + 2 weights
+const MAX_LOCAL_NODES: usize = 22;
+
 #[derive(Debug, Clone)]
 pub struct GbxModelPart {
     pub shader_index: u16,
@@ -98,6 +283,9 @@ pub struct GbxModelPart {
     pub tri_offset: u32,
     pub vert_count: u32,
     pub vert_offset: u32,
+    // only meaningful if the model's parts_have_local_nodes() is set, in which
+    // case vertex node indices index into this list
+    pub local_node_indices: Vec<u8>,
 }
 
 impl Deserialize for GbxModelPart {
@@ -113,8 +301,12 @@ impl Deserialize for GbxModelPart {
         data.seek(SeekFrom::Start(start + 88))?;
         let vert_count = data.read_u32::<LittleEndian>()?;
         data.seek(SeekFrom::Start(start + 100))?;
-        dbg!(data.position());
         let vert_offset = data.read_u32::<LittleEndian>()?;
+        data.seek(SeekFrom::Start(start + 107))?;
+        let local_node_count = (data.read_u8()? as usize).min(MAX_LOCAL_NODES);
+        let mut local_node_indices = vec![0; MAX_LOCAL_NODES];
+        data.read_exact(&mut local_node_indices)?;
+        local_node_indices.truncate(local_node_count);
         data.seek(SeekFrom::Start(start + 132))?;
         Ok(GbxModelPart {
             shader_index,
@@ -123,37 +315,44 @@ impl Deserialize for GbxModelPart {
             tri_offset,
             vert_count,
             vert_offset,
+            local_node_indices,
         })
     }
 }
 
 impl Deserialize for GbxModel {
     fn deserialize(data: &mut Cursor<Vec<u8>>) -> Result<Self> where Self: Sized {
-        data.seek(SeekFrom::Current(4))?; // Bool32('flags',
-        data.seek(SeekFrom::Current(4))?; // SInt32('node_list_checksum'),
-        data.seek(SeekFrom::Current(4))?; // Float('superhigh_lod_cutoff', SIDETIP="pixels"),
-        data.seek(SeekFrom::Current(4))?; // Float('high_lod_cutoff', SIDETIP="pixels"),
-        data.seek(SeekFrom::Current(4))?; // Float('medium_lod_cutoff', SIDETIP="pixels"),
-        data.seek(SeekFrom::Current(4))?; // Float('low_lod_cutoff', SIDETIP="pixels"),
-        data.seek(SeekFrom::Current(4))?; // Float('superlow_lod_cutoff', SIDETIP="pixels"),
-        data.seek(SeekFrom::Current(2))?; // SInt16('superlow_lod_nodes', SIDETIP="nodes"),
-        data.seek(SeekFrom::Current(2))?; // SInt16('low_lod_nodes', SIDETIP="nodes"),
-        data.seek(SeekFrom::Current(2))?; // SInt16('medium_lod_nodes', SIDETIP="nodes"),
-        data.seek(SeekFrom::Current(2))?; // SInt16('high_lod_nodes', SIDETIP="nodes"),
-        data.seek(SeekFrom::Current(2))?; // SInt16('superhigh_lod_nodes', SIDETIP="nodes"),
-        data.seek(SeekFrom::Current(10))?; // Pad(10),
-        let base_bitmap_u_scale = data.read_f32::<LittleEndian>()?; // Float('base_map_u_scale'),
-        let base_bitmap_v_scale = data.read_f32::<LittleEndian>()?; // Float('base_map_v_scale'),
-        data.seek(SeekFrom::Current(116))?; // Pad(116),
-        data.seek(SeekFrom::Current(12))?; // reflexive("markers", marker, 256, DYN_NAME_PATH=".name"),
-        data.seek(SeekFrom::Current(12))?;// reflexive("nodes", node, 64, DYN_NAME_PATH=".name"),
-        data.seek(SeekFrom::Current(12))?;// reflexive("regions", region, 32, DYN_NAME_PATH=".name"),
+        let start = data.position();
+        let flags = data.read_u32::<LittleEndian>()?;
+        let _node_list_checksum = data.read_i32::<LittleEndian>()?;
+        // stored from super high to super low, but we index from low to high
+        let mut lod_cutoffs = [0.0; LOD_COUNT];
+        for i in (0..LOD_COUNT).rev() {
+            lod_cutoffs[i] = data.read_f32::<LittleEndian>()?;
+        }
+        let mut lod_nodes = [0; LOD_COUNT];
+        for nodes in lod_nodes.iter_mut() {
+            *nodes = data.read_i16::<LittleEndian>()?;
+        }
+        data.seek(SeekFrom::Start(start + 48))?;
+        let base_bitmap_u_scale = data.read_f32::<LittleEndian>()?;
+        let base_bitmap_v_scale = data.read_f32::<LittleEndian>()?;
+        data.seek(SeekFrom::Start(start + 172))?;
+        let markers: Block<GbxModelMarker> = Block::deserialize(data)?;
+        let nodes: Block<GbxModelNode> = Block::deserialize(data)?;
+        let regions: Block<GbxModelRegion> = Block::deserialize(data)?;
         let geometries: Block<GbxModelGeometry> = Block::deserialize(data)?;
         let shaders: Block<GbxModelShader> = Block::deserialize(data)?;
-        data.seek(SeekFrom::Start(232))?;// reflexive("regions", region, 32, DYN_NAME_PATH=".name"),
+        data.seek(SeekFrom::Start(start + 232))?;
         Ok(GbxModel {
+            flags,
+            lod_cutoffs,
+            lod_nodes,
             base_bitmap_u_scale,
             base_bitmap_v_scale,
+            markers,
+            nodes,
+            regions,
             geometries,
             shaders,
         })
@@ -161,7 +161,10 @@ impl Deserialize for BSPHeader {
         let rendered_vertices_offset = data.read_u32::<LittleEndian>()?;
         data.seek(SeekFrom::Current(4))?;
         let lightmap_vertices_offset = data.read_u32::<LittleEndian>()?;
-        assert_eq!(data.read_u32::<LittleEndian>()?, TagClass::ScenarioStructureBsp.into());
+        let class = data.read_u32::<LittleEndian>()?;
+        if class != u32::from(TagClass::ScenarioStructureBsp) {
+            return Err(MapReaderError::InvalidTag(format!("expected sbsp header, got {:#x}", class)));
+        }
         Ok(BSPHeader {
             bsp_offset,
             rendered_vertices_offset,
@@ -224,12 +227,32 @@ impl Deserialize for BSP {
     }
 }
 
+impl BSP {
+    pub fn get_indices(&self) -> Vec<u16> {
+        let mut indices = Vec::new();
+        if let Some(tris) = &self.surfaces.items {
+            indices.reserve(tris.len() * 3);
+            for tri in tris {
+                indices.extend_from_slice(&[tri.v0, tri.v1, tri.v2]);
+            }
+        }
+        indices
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct BSPLightmap {
     pub bitmap: u16,
     pub materials: Block<BSPMaterial>,
 }
 
+impl BSPLightmap {
+    // transparent and sky-only lightmaps don't reference a lightmap bitmap
+    pub fn has_bitmap(&self) -> bool {
+        self.bitmap != 0xFFFF
+    }
+}
+
 impl Deserialize for BSPLightmap {
     fn deserialize(data: &mut Cursor<Vec<u8>>) -> Result<Self> where Self: Sized {
         let bitmap = data.read_u16::<LittleEndian>()?;
@@ -270,7 +293,7 @@ pub struct BSPMaterial {
 impl Deserialize for BSPMaterial {
     fn deserialize(data: &mut Cursor<Vec<u8>>) -> Result<Self> where Self: Sized {
         let start = data.position();
-        let shader = TagDependency::deserialize(data).unwrap();
+        let shader = TagDependency::deserialize(data)?;
         let shader_permutation = data.read_u16::<LittleEndian>()?;
         let flags = data.read_u16::<LittleEndian>()?;
         let surfaces = data.read_i32::<LittleEndian>()?;
@@ -297,8 +320,9 @@ impl Deserialize for BSPMaterial {
         let lightmap_vertices: Block<LightmapVertex> = Block::deserialize(data)?;
         data.seek(SeekFrom::Start(start + 216))?;
         let uncompressed_vertices = TagDataOffset::deserialize(data)?;
-        assert_ne!(uncompressed_vertices.file_offset, 0);
-        assert_eq!(rendered_vertices_type, RenderedVerticesType::StructureBSPUncompressedRenderedVertices);
+        if rendered_vertices_type != RenderedVerticesType::StructureBSPUncompressedRenderedVertices {
+            return Err(MapReaderError::UnimplementedTag(format!("can't yet read {:?} BSP vertices", rendered_vertices_type)));
+        }
         data.seek(SeekFrom::Start(start + 236))?;
         let compressed_vertices = TagDataOffset::deserialize(data)?;
         Ok(BSPMaterial {
@@ -327,6 +351,19 @@ impl Deserialize for BSPMaterial {
     }
 }
 
+pub const RENDERED_VERTEX_SIZE: usize = 56; // position + normal + binormal + tangent + uv
+pub const LIGHTMAP_VERTEX_SIZE: usize = 20; // incident light normal + uv
+
+impl BSPMaterial {
+    pub fn get_vertex_count(&self) -> usize {
+        self.rendered_vertices.count
+    }
+
+    pub fn has_lightmap_vertices(&self) -> bool {
+        self.lightmap_vertices.count > 0
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RenderedVertex {
     pub position: Vector3D,
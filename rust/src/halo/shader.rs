@@ -757,6 +757,8 @@ pub struct ShaderEnvironment {
     pub material_color: ColorRGB,
     pub bump_map_scale: f32,
     pub bump_map: TagDependency,
+    pub u_animation: TextureAnimation,
+    pub v_animation: TextureAnimation,
     pub specular_flags: u16,
     pub brightness: f32,
     pub perpendicular_color: ColorRGB,
@@ -812,6 +814,9 @@ impl Deserialize for ShaderEnvironment {
         data.seek(SeekFrom::Start(start + 252))?;
         let bump_map_scale = data.read_f32::<LittleEndian>()?;
         let bump_map = TagDependency::deserialize(data)?;
+        data.seek(SeekFrom::Start(start + 296))?;
+        let u_animation = TextureAnimation::deserialize_scrolling(data)?;
+        let v_animation = TextureAnimation::deserialize_scrolling(data)?;
         data.seek(SeekFrom::Start(start + 596))?;
         let specular_flags = data.read_u16::<LittleEndian>()?;
         data.seek(SeekFrom::Start(start + 616))?;
@@ -851,6 +856,8 @@ impl Deserialize for ShaderEnvironment {
             material_color,
             bump_map_scale,
             bump_map,
+            u_animation,
+            v_animation,
             specular_flags,
             brightness,
             perpendicular_color,
@@ -865,6 +872,174 @@ impl Deserialize for ShaderEnvironment {
     }
 }
 
+// A single scrolling/rotating texture coordinate animation. Environment shaders
+// only have a function, period and scale per axis, so their source is always
+// None and their phase is always 0.
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone)]
+pub struct TextureAnimation {
+    pub source: FunctionSource,
+    pub function: AnimationFunction,
+    pub period: f32,
+    pub phase: f32,
+    pub scale: f32,
+}
+
+impl TextureAnimation {
+    fn deserialize_scrolling(data: &mut Cursor<Vec<u8>>) -> Result<Self> {
+        let function = AnimationFunction::try_from(data.read_u16::<LittleEndian>()?)?;
+        data.seek(SeekFrom::Current(2))?;
+        let period = data.read_f32::<LittleEndian>()?;
+        let scale = data.read_f32::<LittleEndian>()?;
+        Ok(TextureAnimation { source: FunctionSource::None, function, period, phase: 0.0, scale })
+    }
+
+    pub fn is_animated(&self) -> bool {
+        !matches!(self.function, AnimationFunction::Zero) && self.scale != 0.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DetailMap {
+    pub bitmap: TagDependency,
+    pub function: DetailBitmapFunction,
+    pub u_scale: f32,
+    pub v_scale: f32,
+}
+
+// The subset of shader_environment and shader_model that the renderer's
+// shader emulation cares about, normalized so both families look the same
+#[derive(Debug, Clone)]
+pub struct ShaderParameters {
+    pub base_map: Option<TagDependency>,
+    pub base_map_u_scale: f32,
+    pub base_map_v_scale: f32,
+    pub bump_map: Option<TagDependency>,
+    pub bump_map_scale: f32,
+    pub detail_maps: Vec<DetailMap>,
+    pub reflection_cube_map: Option<TagDependency>,
+    pub perpendicular_brightness: f32,
+    pub perpendicular_color: ColorRGB,
+    pub parallel_brightness: f32,
+    pub parallel_color: ColorRGB,
+    pub u_animation: TextureAnimation,
+    pub v_animation: TextureAnimation,
+    pub rotation_animation: Option<TextureAnimation>,
+    pub rotation_center: Point2D,
+}
+
+fn non_null(dependency: &TagDependency) -> Option<TagDependency> {
+    if dependency.is_null() { None } else { Some(dependency.clone()) }
+}
+
+impl From<&ShaderEnvironment> for ShaderParameters {
+    fn from(shader: &ShaderEnvironment) -> Self {
+        let mut detail_maps = Vec::new();
+        let details = [
+            (&shader.primary_detail_bitmap, shader.detail_bitmap_function, shader.primary_detail_bitmap_scale),
+            (&shader.secondary_detail_bitmap, shader.detail_bitmap_function, shader.secondary_detail_bitmap_scale),
+            (&shader.micro_detail_bitmap, shader.micro_detail_bitmap_function, shader.micro_detail_scale),
+        ];
+        for (bitmap, function, scale) in details.iter() {
+            if !bitmap.is_null() {
+                detail_maps.push(DetailMap { bitmap: (*bitmap).clone(), function: *function, u_scale: *scale, v_scale: *scale });
+            }
+        }
+        ShaderParameters {
+            base_map: non_null(&shader.base_bitmap),
+            base_map_u_scale: 1.0,
+            base_map_v_scale: 1.0,
+            bump_map: non_null(&shader.bump_map),
+            bump_map_scale: shader.bump_map_scale,
+            detail_maps,
+            reflection_cube_map: non_null(&shader.reflection_cube_map),
+            perpendicular_brightness: shader.perpendicular_brightness,
+            perpendicular_color: shader.perpendicular_color,
+            parallel_brightness: shader.parallel_brightness,
+            parallel_color: shader.parallel_color,
+            u_animation: shader.u_animation,
+            v_animation: shader.v_animation,
+            rotation_animation: None,
+            rotation_center: Point2D { x: 0.0, y: 0.0 },
+        }
+    }
+}
+
+impl From<&ShaderModel> for ShaderParameters {
+    fn from(shader: &ShaderModel) -> Self {
+        let mut detail_maps = Vec::new();
+        if !shader.detail_map.is_null() {
+            // a v scale of 0 means "same as u"
+            let v_scale = if shader.detail_map_v_scale == 0.0 { 1.0 } else { shader.detail_map_v_scale };
+            detail_maps.push(DetailMap {
+                bitmap: shader.detail_map.clone(),
+                function: shader.detail_function,
+                u_scale: shader.detail_map_scale,
+                v_scale: shader.detail_map_scale * v_scale,
+            });
+        }
+        ShaderParameters {
+            base_map: non_null(&shader.base_map),
+            base_map_u_scale: if shader.map_u_scale == 0.0 { 1.0 } else { shader.map_u_scale },
+            base_map_v_scale: if shader.map_v_scale == 0.0 { 1.0 } else { shader.map_v_scale },
+            bump_map: None,
+            bump_map_scale: 0.0,
+            detail_maps,
+            reflection_cube_map: non_null(&shader.reflection_cube_map),
+            perpendicular_brightness: shader.perpendicular_brightness,
+            perpendicular_color: shader.perpendicular_tint_color,
+            parallel_brightness: shader.parallel_brightness,
+            parallel_color: shader.parallel_tint_color,
+            u_animation: shader.get_u_animation(),
+            v_animation: shader.get_v_animation(),
+            rotation_animation: Some(shader.get_rotation_animation()),
+            rotation_center: shader.rotation_animation_center,
+        }
+    }
+}
+
+// shader_model and the transparent shaders' maps all have the same u, v and
+// rotation animation fields
+macro_rules! impl_texture_animations {
+    ($($shader:ty),*) => {
+        $(
+            impl $shader {
+                pub fn get_u_animation(&self) -> TextureAnimation {
+                    TextureAnimation {
+                        source: self.u_animation_source,
+                        function: self.u_animation_function,
+                        period: self.u_animation_period,
+                        phase: self.u_animation_phase,
+                        scale: self.u_animation_scale,
+                    }
+                }
+
+                pub fn get_v_animation(&self) -> TextureAnimation {
+                    TextureAnimation {
+                        source: self.v_animation_source,
+                        function: self.v_animation_function,
+                        period: self.v_animation_period,
+                        phase: self.v_animation_phase,
+                        scale: self.v_animation_scale,
+                    }
+                }
+
+                pub fn get_rotation_animation(&self) -> TextureAnimation {
+                    TextureAnimation {
+                        source: self.rotation_animation_source,
+                        function: self.rotation_animation_function,
+                        period: self.rotation_animation_period,
+                        phase: self.rotation_animation_phase,
+                        scale: self.rotation_animation_scale,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_texture_animations!(ShaderModel, ShaderTransparentChicagoMap, ShaderTransparentGenericMap);
+
 #[wasm_bindgen]
 #[derive(Debug, TryFromPrimitive, Copy, Clone)]
 #[repr(u16)]
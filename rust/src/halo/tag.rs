@@ -16,6 +16,12 @@ pub struct TagDependency {
     pub tag_id: u32,
 }
 
+impl TagDependency {
+    pub fn is_null(&self) -> bool {
+        self.tag_id == 0xFFFFFFFF
+    }
+}
+
 impl Deserialize for TagDependency {
     fn deserialize(data: &mut Cursor<Vec<u8>>) -> Result<Self> where Self: Sized {
         Ok(TagDependency {
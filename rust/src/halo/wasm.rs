@@ -37,16 +37,9 @@ impl HaloBitmapReader {
 fn get_and_convert_bitmap_data(bytes: &[u8], bitmap_data: &BitmapData) -> Vec<u8> {
     let offset = bitmap_data.pixel_data_offset as usize;
     let length = bitmap_data.pixel_data_size as usize;
-    let byte_range = &bytes[offset..offset+length];
-    match bitmap_data.format {
-        BitmapFormat::P8 | BitmapFormat::P8Bump => convert_p8_data(byte_range),
-        BitmapFormat::A8r8g8b8 => convert_a8r8g8b8_data(byte_range),
-        BitmapFormat::X8r8g8b8 => convert_x8r8g8b8_data(byte_range),
-        BitmapFormat::A8 => convert_a8_data(byte_range),
-        BitmapFormat::Y8 => convert_y8_data(byte_range),
-        BitmapFormat::A8y8 => convert_a8y8_data(byte_range),
-        BitmapFormat::R5g6b5 => convert_r5g6b5_data(byte_range),
-        _ => Vec::from(byte_range),
+    match bytes.get(offset..offset+length) {
+        Some(byte_range) => convert_bitmap_data(byte_range, bitmap_data),
+        None => Vec::new(),
     }
 }
 
@@ -93,6 +86,14 @@ impl HaloMaterial {
     pub fn get_index_offset(&self) -> i32 {
         self.inner.surfaces * 3
     }
+
+    pub fn get_vertex_count(&self) -> usize {
+        self.inner.get_vertex_count()
+    }
+
+    pub fn has_lightmap_vertices(&self) -> bool {
+        self.inner.has_lightmap_vertices()
+    }
 }
 
 #[wasm_bindgen]
@@ -110,6 +111,23 @@ impl HaloModel {
     pub fn get_base_bitmap_v_scale(&self) -> f32 {
         self.inner.base_bitmap_v_scale
     }
+
+    pub fn get_node_count(&self) -> usize {
+        self.inner.nodes.count
+    }
+
+    pub fn parts_have_local_nodes(&self) -> bool {
+        self.inner.parts_have_local_nodes()
+    }
+
+    // LOD indices run from 0 (super low) to 4 (super high)
+    pub fn get_lod_cutoff(&self, lod: usize) -> f32 {
+        self.inner.lod_cutoffs.get(lod).cloned().unwrap_or(0.0)
+    }
+
+    pub fn get_lod_for_pixel_size(&self, pixels: f32) -> usize {
+        self.inner.get_lod_for_pixel_size(pixels) as usize
+    }
 }
 
 #[wasm_bindgen]
@@ -158,6 +176,7 @@ impl HaloModelPart {
     #[wasm_bindgen(getter)] pub fn tri_offset(&self) -> u32 { self.inner.tri_offset }
     #[wasm_bindgen(getter)] pub fn vert_count(&self) -> u32 { self.inner.vert_count }
     #[wasm_bindgen(getter)] pub fn vert_offset(&self) -> u32 { self.inner.vert_offset }
+    #[wasm_bindgen(getter)] pub fn local_node_indices(&self) -> Vec<u8> { self.inner.local_node_indices.clone() }
 
     fn new(inner: &GbxModelPart) -> HaloModelPart {
         HaloModelPart { inner: inner.clone() }
@@ -201,6 +220,9 @@ impl HaloShaderTransparentChicagoMap {
     #[wasm_bindgen(getter)] pub fn rotation_animation_phase(&self) -> f32 { self.inner.rotation_animation_phase }
     #[wasm_bindgen(getter)] pub fn rotation_animation_scale(&self) -> f32 { self.inner.rotation_animation_scale }
     #[wasm_bindgen(getter)] pub fn rotation_animation_center(&self) -> Point2D { self.inner.rotation_animation_center }
+    #[wasm_bindgen(getter)] pub fn u_animation(&self) -> TextureAnimation { self.inner.get_u_animation() }
+    #[wasm_bindgen(getter)] pub fn v_animation(&self) -> TextureAnimation { self.inner.get_v_animation() }
+    #[wasm_bindgen(getter)] pub fn rotation_animation(&self) -> TextureAnimation { self.inner.get_rotation_animation() }
 }
 
 #[wasm_bindgen]
@@ -271,6 +293,9 @@ impl HaloShaderTransparentGenericMap {
     #[wasm_bindgen(getter)] pub fn rotation_animation_phase(&self) -> f32 { self.inner.rotation_animation_phase }
     #[wasm_bindgen(getter)] pub fn rotation_animation_scale(&self) -> f32 { self.inner.rotation_animation_scale }
     #[wasm_bindgen(getter)] pub fn rotation_animation_center(&self) -> Point2D { self.inner.rotation_animation_center }
+    #[wasm_bindgen(getter)] pub fn u_animation(&self) -> TextureAnimation { self.inner.get_u_animation() }
+    #[wasm_bindgen(getter)] pub fn v_animation(&self) -> TextureAnimation { self.inner.get_v_animation() }
+    #[wasm_bindgen(getter)] pub fn rotation_animation(&self) -> TextureAnimation { self.inner.get_rotation_animation() }
 }
 
 #[wasm_bindgen]
@@ -395,6 +420,9 @@ impl HaloShaderModel {
     #[wasm_bindgen(getter)] pub fn rotation_animation_phase(&self) -> f32 { self.inner.rotation_animation_phase }
     #[wasm_bindgen(getter)] pub fn rotation_animation_scale(&self) -> f32 { self.inner.rotation_animation_scale }
     #[wasm_bindgen(getter)] pub fn rotation_animation_center(&self) -> Point2D { self.inner.rotation_animation_center }
+    #[wasm_bindgen(getter)] pub fn u_animation(&self) -> TextureAnimation { self.inner.get_u_animation() }
+    #[wasm_bindgen(getter)] pub fn v_animation(&self) -> TextureAnimation { self.inner.get_v_animation() }
+    #[wasm_bindgen(getter)] pub fn rotation_animation(&self) -> TextureAnimation { self.inner.get_rotation_animation() }
     #[wasm_bindgen(getter)] pub fn reflection_falloff_distance(&self) -> f32 { self.inner.reflection_falloff_distance }
     #[wasm_bindgen(getter)] pub fn reflection_cutoff_distance(&self) -> f32 { self.inner.reflection_cutoff_distance }
     #[wasm_bindgen(getter)] pub fn perpendicular_brightness(&self) -> f32 { self.inner.perpendicular_brightness }
@@ -422,6 +450,18 @@ impl HaloShaderModel {
         self.reflection_cube_map.as_ref()
             .map(|map| HaloBitmap::new(map.clone()))
     }
+
+    pub fn get_parameters(&self) -> HaloShaderParameters {
+        let inner = ShaderParameters::from(&self.inner);
+        let detail_bitmaps = inner.detail_maps.iter().map(|_| self.detail_bitmap.clone()).collect();
+        HaloShaderParameters {
+            inner,
+            base_bitmap: self.base_bitmap.clone(),
+            bump_map: None,
+            detail_bitmaps,
+            reflection_cube_map: self.reflection_cube_map.clone(),
+        }
+    }
 }
 
 #[wasm_bindgen]
@@ -450,6 +490,8 @@ impl HaloShaderEnvironment {
     #[wasm_bindgen(getter)] pub fn micro_detail_bitmap_scale(&self) -> f32 { self.inner.micro_detail_scale }
     #[wasm_bindgen(getter)] pub fn micro_detail_bitmap_function(&self) -> DetailBitmapFunction { self.inner.micro_detail_bitmap_function }
     #[wasm_bindgen(getter)] pub fn bump_map_scale(&self) -> f32 { self.inner.bump_map_scale }
+    #[wasm_bindgen(getter)] pub fn u_animation(&self) -> TextureAnimation { self.inner.u_animation }
+    #[wasm_bindgen(getter)] pub fn v_animation(&self) -> TextureAnimation { self.inner.v_animation }
     #[wasm_bindgen(getter)] pub fn has_primary_detail_bitmap(&self) -> bool { self.primary_detail_bitmap.is_some() }
     #[wasm_bindgen(getter)] pub fn has_secondary_detail_bitmap(&self) -> bool { self.secondary_detail_bitmap.is_some() }
     #[wasm_bindgen(getter)] pub fn has_micro_detail_bitmap(&self) -> bool { self.micro_detail_bitmap.is_some() }
@@ -492,6 +534,86 @@ impl HaloShaderEnvironment {
         self.reflection_cube_map.as_ref()
             .map(|map| HaloBitmap::new(map.clone()))
     }
+
+    pub fn get_parameters(&self) -> HaloShaderParameters {
+        // ShaderParameters skips unset detail maps, so skip their bitmaps too
+        let details = [
+            (&self.inner.primary_detail_bitmap, &self.primary_detail_bitmap),
+            (&self.inner.secondary_detail_bitmap, &self.secondary_detail_bitmap),
+            (&self.inner.micro_detail_bitmap, &self.micro_detail_bitmap),
+        ];
+        let detail_bitmaps = details.iter()
+            .filter(|(dependency, _)| !dependency.is_null())
+            .map(|(_, bitmap)| (*bitmap).clone())
+            .collect();
+        HaloShaderParameters {
+            inner: ShaderParameters::from(&self.inner),
+            base_bitmap: Some(self.base_bitmap.clone()),
+            bump_map: self.bump_map.clone(),
+            detail_bitmaps,
+            reflection_cube_map: self.reflection_cube_map.clone(),
+        }
+    }
+}
+
+// The normalized parameters of a shader_environment or shader_model, along
+// with the bitmaps they reference
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct HaloShaderParameters {
+    inner: ShaderParameters,
+    base_bitmap: Option<Bitmap>,
+    bump_map: Option<Bitmap>,
+    detail_bitmaps: Vec<Option<Bitmap>>,
+    reflection_cube_map: Option<Bitmap>,
+}
+
+#[wasm_bindgen]
+impl HaloShaderParameters {
+    #[wasm_bindgen(getter)] pub fn base_map_u_scale(&self) -> f32 { self.inner.base_map_u_scale }
+    #[wasm_bindgen(getter)] pub fn base_map_v_scale(&self) -> f32 { self.inner.base_map_v_scale }
+    #[wasm_bindgen(getter)] pub fn bump_map_scale(&self) -> f32 { self.inner.bump_map_scale }
+    #[wasm_bindgen(getter)] pub fn perpendicular_brightness(&self) -> f32 { self.inner.perpendicular_brightness }
+    #[wasm_bindgen(getter)] pub fn perpendicular_color(&self) -> ColorRGB { self.inner.perpendicular_color }
+    #[wasm_bindgen(getter)] pub fn parallel_brightness(&self) -> f32 { self.inner.parallel_brightness }
+    #[wasm_bindgen(getter)] pub fn parallel_color(&self) -> ColorRGB { self.inner.parallel_color }
+    #[wasm_bindgen(getter)] pub fn u_animation(&self) -> TextureAnimation { self.inner.u_animation }
+    #[wasm_bindgen(getter)] pub fn v_animation(&self) -> TextureAnimation { self.inner.v_animation }
+    #[wasm_bindgen(getter)] pub fn rotation_animation(&self) -> Option<TextureAnimation> { self.inner.rotation_animation }
+    #[wasm_bindgen(getter)] pub fn rotation_center(&self) -> Point2D { self.inner.rotation_center }
+    #[wasm_bindgen(getter)] pub fn detail_map_count(&self) -> usize { self.inner.detail_maps.len() }
+
+    pub fn get_base_bitmap(&self) -> Option<HaloBitmap> {
+        self.base_bitmap.as_ref()
+            .map(|map| HaloBitmap::new(map.clone()))
+    }
+
+    pub fn get_bump_map(&self) -> Option<HaloBitmap> {
+        self.bump_map.as_ref()
+            .map(|map| HaloBitmap::new(map.clone()))
+    }
+
+    pub fn get_reflection_cube_map(&self) -> Option<HaloBitmap> {
+        self.reflection_cube_map.as_ref()
+            .map(|map| HaloBitmap::new(map.clone()))
+    }
+
+    pub fn get_detail_bitmap(&self, i: usize) -> Option<HaloBitmap> {
+        self.detail_bitmaps.get(i)?.as_ref()
+            .map(|map| HaloBitmap::new(map.clone()))
+    }
+
+    pub fn get_detail_map_function(&self, i: usize) -> Option<DetailBitmapFunction> {
+        self.inner.detail_maps.get(i).map(|detail| detail.function)
+    }
+
+    pub fn get_detail_map_u_scale(&self, i: usize) -> f32 {
+        self.inner.detail_maps.get(i).map_or(1.0, |detail| detail.u_scale)
+    }
+
+    pub fn get_detail_map_v_scale(&self, i: usize) -> f32 {
+        self.inner.detail_maps.get(i).map_or(1.0, |detail| detail.v_scale)
+    }
 }
 
 #[wasm_bindgen]
@@ -574,6 +696,10 @@ impl HaloLightmap {
     pub fn get_bitmap_index(&self) -> u16 {
         self.inner.bitmap
     }
+
+    pub fn has_bitmap(&self) -> bool {
+        self.inner.has_bitmap()
+    }
 }
 
 #[wasm_bindgen]
@@ -755,6 +881,19 @@ impl HaloSceneManager {
         result
     }
 
+    pub fn get_model_lod_parts(&mut self, model: &HaloModel, lod: usize) -> Array {
+        let lod = match lod {
+            0 => ModelLod::SuperLow,
+            1 => ModelLod::Low,
+            2 => ModelLod::Medium,
+            3 => ModelLod::High,
+            _ => ModelLod::SuperHigh,
+        };
+        model.inner.get_lod_parts(lod).into_iter()
+            .map(|part| JsValue::from(HaloModelPart::new(part)))
+            .collect()
+    }
+
     pub fn get_scenery_model(&mut self, scenery: &HaloScenery) -> Option<HaloModel> {
         self.resolve_model_dependency(&scenery.inner.model)
             .map(|model| HaloModel { inner: model })
@@ -826,13 +965,14 @@ impl HaloSceneManager {
     }
 
     pub fn get_bsp_lightmaps(&self, bsp: &HaloBSP) -> Array {
-        bsp.inner.lightmaps.items.as_ref().unwrap().iter()
+        bsp.inner.lightmaps.items.iter().flatten()
             .map(|lightmap| JsValue::from(HaloLightmap::new(lightmap)))
             .collect()
     }
 
     pub fn get_lightmap_materials(&self, lightmap: &HaloLightmap) -> Array {
-        lightmap.inner.materials.items.as_ref().unwrap().iter()
+        lightmap.inner.materials.items.iter().flatten()
+            .filter(|material| material.surface_count > 0)
             .map(|material| JsValue::from(HaloMaterial::new(material)))
             .collect()
     }
@@ -852,11 +992,7 @@ impl HaloSceneManager {
     }
 
     pub fn get_bsp_indices(&self, bsp: &HaloBSP) -> Vec<u16> {
-        let mut indices = Vec::new();
-        for tri in bsp.inner.surfaces.items.as_ref().unwrap() {
-            indices.extend_from_slice(&[tri.v0, tri.v1, tri.v2]);
-        }
-        indices
+        bsp.inner.get_indices()
     }
 
     fn resolve_model_dependency(&mut self, dependency: &TagDependency) -> Option<GbxModel> {
@@ -887,16 +1023,26 @@ impl HaloSceneManager {
     }
 
     pub fn get_material_vertex_data(&mut self, material: &HaloMaterial, bsp: &HaloBSP) -> Vec<u8> {
-        let offset = bsp.inner.header.as_ref().unwrap().rendered_vertices_offset + material.inner.rendered_vertices.base_pointer;
-        let count = material.inner.rendered_vertices.count;
-        let item_size = 56; // position + normal + binormal + tangent + uv
-        self.mgr.read_map_bytes(offset as u64, count * item_size).unwrap()
+        let count = material.inner.get_vertex_count();
+        let header = match bsp.inner.header.as_ref() {
+            Some(header) => header,
+            None => return vec![0; count * RENDERED_VERTEX_SIZE],
+        };
+        let offset = header.rendered_vertices_offset + material.inner.rendered_vertices.base_pointer;
+        self.mgr.read_map_bytes(offset as u64, count * RENDERED_VERTEX_SIZE)
+            .unwrap_or_else(|_| vec![0; count * RENDERED_VERTEX_SIZE])
     }
 
+    // Materials without lightmap vertices still need a buffer of the right
+    // size, so hand back zeroed incident light and lightmap UVs for them
     pub fn get_material_lightmap_data(&mut self, material: &HaloMaterial, bsp: &HaloBSP) -> Vec<u8> {
-        let offset = bsp.inner.header.as_ref().unwrap().rendered_vertices_offset + material.inner.lightmap_vertices.base_pointer;
-        let count = material.inner.rendered_vertices.count;
-        let item_size = 20; // normal + uv
-        self.mgr.read_map_bytes(offset as u64, count * item_size).unwrap()
+        let count = material.inner.get_vertex_count();
+        let header = match bsp.inner.header.as_ref() {
+            Some(header) if material.inner.has_lightmap_vertices() => header,
+            _ => return vec![0; count * LIGHTMAP_VERTEX_SIZE],
+        };
+        let offset = header.rendered_vertices_offset + material.inner.lightmap_vertices.base_pointer;
+        self.mgr.read_map_bytes(offset as u64, count * LIGHTMAP_VERTEX_SIZE)
+            .unwrap_or_else(|_| vec![0; count * LIGHTMAP_VERTEX_SIZE])
     }
 }
\ No newline at end of file
@@ -0,0 +1,223 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use std::convert::TryInto;
+
+// Hashes that engines use to refer to assets by name, plus the checksums
+// some of our formats carry. All take raw bytes; encode strings as UTF-8
+// first (which is what e.g. Unity does for bone path hashes).
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+// The zlib/PNG CRC-32
+#[wasm_bindgen]
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+// Continues a CRC-32 from the CRC of the data before this, for data that
+// arrives in pieces
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &byte in data {
+        crc = CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+// The zlib checksum
+pub fn adler32(data: &[u8]) -> u32 {
+    adler32_update(1, data)
+}
+
+pub fn adler32_update(adler: u32, data: &[u8]) -> u32 {
+    let mut a = adler & 0xFFFF;
+    let mut b = adler >> 16;
+    // 5552 bytes is as many as can be summed before b could overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+const XXH32_PRIME1: u32 = 2654435761;
+const XXH32_PRIME2: u32 = 2246822519;
+const XXH32_PRIME3: u32 = 3266489917;
+const XXH32_PRIME4: u32 = 668265263;
+const XXH32_PRIME5: u32 = 374761393;
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes(data[..4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data[..8].try_into().unwrap())
+}
+
+fn xxh32_round(acc: u32, lane: u32) -> u32 {
+    acc.wrapping_add(lane.wrapping_mul(XXH32_PRIME2)).rotate_left(13).wrapping_mul(XXH32_PRIME1)
+}
+
+#[wasm_bindgen]
+pub fn xxhash32(data: &[u8], seed: u32) -> u32 {
+    let mut rest = data;
+    let mut hash = if data.len() >= 16 {
+        let mut acc = [
+            seed.wrapping_add(XXH32_PRIME1).wrapping_add(XXH32_PRIME2),
+            seed.wrapping_add(XXH32_PRIME2),
+            seed,
+            seed.wrapping_sub(XXH32_PRIME1),
+        ];
+        while rest.len() >= 16 {
+            for (i, acc) in acc.iter_mut().enumerate() {
+                *acc = xxh32_round(*acc, read_u32(&rest[i * 4..]));
+            }
+            rest = &rest[16..];
+        }
+        acc[0].rotate_left(1)
+            .wrapping_add(acc[1].rotate_left(7))
+            .wrapping_add(acc[2].rotate_left(12))
+            .wrapping_add(acc[3].rotate_left(18))
+    } else {
+        seed.wrapping_add(XXH32_PRIME5)
+    };
+    hash = hash.wrapping_add(data.len() as u32);
+
+    while rest.len() >= 4 {
+        hash = hash.wrapping_add(read_u32(rest).wrapping_mul(XXH32_PRIME3)).rotate_left(17).wrapping_mul(XXH32_PRIME4);
+        rest = &rest[4..];
+    }
+    for &byte in rest {
+        hash = hash.wrapping_add((byte as u32).wrapping_mul(XXH32_PRIME5)).rotate_left(11).wrapping_mul(XXH32_PRIME1);
+    }
+
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(XXH32_PRIME2);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(XXH32_PRIME3);
+    hash ^ (hash >> 16)
+}
+
+const XXH64_PRIME1: u64 = 11400714785074694791;
+const XXH64_PRIME2: u64 = 14029467366897019727;
+const XXH64_PRIME3: u64 = 1609587929392839161;
+const XXH64_PRIME4: u64 = 9650029242287828579;
+const XXH64_PRIME5: u64 = 2870177450012600261;
+
+fn xxh64_round(acc: u64, lane: u64) -> u64 {
+    acc.wrapping_add(lane.wrapping_mul(XXH64_PRIME2)).rotate_left(31).wrapping_mul(XXH64_PRIME1)
+}
+
+fn xxh64_merge(hash: u64, acc: u64) -> u64 {
+    (hash ^ xxh64_round(0, acc)).wrapping_mul(XXH64_PRIME1).wrapping_add(XXH64_PRIME4)
+}
+
+#[wasm_bindgen]
+pub fn xxhash64(data: &[u8], seed: u64) -> u64 {
+    let mut rest = data;
+    let mut hash = if data.len() >= 32 {
+        let mut acc = [
+            seed.wrapping_add(XXH64_PRIME1).wrapping_add(XXH64_PRIME2),
+            seed.wrapping_add(XXH64_PRIME2),
+            seed,
+            seed.wrapping_sub(XXH64_PRIME1),
+        ];
+        while rest.len() >= 32 {
+            for (i, acc) in acc.iter_mut().enumerate() {
+                *acc = xxh64_round(*acc, read_u64(&rest[i * 8..]));
+            }
+            rest = &rest[32..];
+        }
+        let hash = acc[0].rotate_left(1)
+            .wrapping_add(acc[1].rotate_left(7))
+            .wrapping_add(acc[2].rotate_left(12))
+            .wrapping_add(acc[3].rotate_left(18));
+        acc.iter().fold(hash, |hash, &acc| xxh64_merge(hash, acc))
+    } else {
+        seed.wrapping_add(XXH64_PRIME5)
+    };
+    hash = hash.wrapping_add(data.len() as u64);
+
+    while rest.len() >= 8 {
+        hash ^= xxh64_round(0, read_u64(rest));
+        hash = hash.rotate_left(27).wrapping_mul(XXH64_PRIME1).wrapping_add(XXH64_PRIME4);
+        rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+        hash ^= (read_u32(rest) as u64).wrapping_mul(XXH64_PRIME1);
+        hash = hash.rotate_left(23).wrapping_mul(XXH64_PRIME2).wrapping_add(XXH64_PRIME3);
+        rest = &rest[4..];
+    }
+    for &byte in rest {
+        hash ^= (byte as u64).wrapping_mul(XXH64_PRIME5);
+        hash = hash.rotate_left(11).wrapping_mul(XXH64_PRIME1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(XXH64_PRIME2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(XXH64_PRIME3);
+    hash ^ (hash >> 32)
+}
+
+#[wasm_bindgen]
+pub fn fnv1a32(data: &[u8]) -> u32 {
+    data.iter().fold(0x811C9DC5, |hash, &byte| (hash ^ byte as u32).wrapping_mul(0x01000193))
+}
+
+#[wasm_bindgen]
+pub fn fnv1a64(data: &[u8]) -> u64 {
+    data.iter().fold(0xCBF29CE484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001B3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_answers() {
+        // 64 bytes covers the stripe loops as well as the tails
+        let long: Vec<u8> = (0..64).collect();
+
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+        assert_eq!(crc32_update(crc32(b"1234"), b"56789"), 0xCBF43926);
+
+        assert_eq!(xxhash32(b"", 0), 0x02CC5D05);
+        assert_eq!(xxhash32(b"a", 0), 0x550D7456);
+        assert_eq!(xxhash32(b"abc", 0), 0x32D153FF);
+        assert_eq!(xxhash32(b"abc", 1), 0xAA3DA8FF);
+        assert_eq!(xxhash32(&long, 0), 0x31120435);
+
+        assert_eq!(xxhash64(b"", 0), 0xEF46DB3751D8E999);
+        assert_eq!(xxhash64(b"a", 0), 0xD24EC4F1A98C6E5B);
+        assert_eq!(xxhash64(b"abc", 0), 0x44BC2CF5AD770999);
+        assert_eq!(xxhash64(b"abc", 1), 0xBEA9CA8199328908);
+        assert_eq!(xxhash64(&long, 0), 0xF7C67301DB6713F0);
+
+        assert_eq!(fnv1a32(b""), 0x811C9DC5);
+        assert_eq!(fnv1a32(b"a"), 0xE40C292C);
+        assert_eq!(fnv1a32(b"foobar"), 0xBF9CF968);
+        assert_eq!(fnv1a64(b""), 0xCBF29CE484222325);
+        assert_eq!(fnv1a64(b"a"), 0xAF63DC4C8601EC8C);
+        assert_eq!(fnv1a64(b"foobar"), 0x85944171F73967E8);
+    }
+}
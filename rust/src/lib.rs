@@ -1,8 +1,21 @@
 
+pub mod astc_texture;
+pub mod basis;
+pub mod bvh;
+pub mod cancellation;
 pub mod compression;
+pub mod etc_texture;
 pub mod glsl_compile;
 pub mod gx_texture;
+pub mod half;
 pub mod halo;
+pub mod hash;
+pub mod lz77;
+pub mod memory;
+pub mod octree;
+pub mod png;
+pub mod profiling;
+pub mod quantize;
 pub mod tegra_texture;
 pub mod unity;
 pub mod util;
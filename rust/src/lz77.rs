@@ -0,0 +1,199 @@
+
+// Nintendo LZ77 variants, from the GBA/DS BIOS and used on through the Wii.
+//
+// Header (4 bytes, little endian):
+//   Type: bits 0-7 (0x10 for LZ10, 0x11 for LZ11)
+//   Uncompressed size: bits 8-31
+//     If the size is 0, the real size follows as another 4 bytes.
+// Some Wii games put a "LZ77" magic in front of this.
+// Data:
+//   Flags (1 byte)
+//   For each bit in the flags byte, from MSB to LSB:
+//     If flag is 0:
+//       Literal: copy one byte from src to dest.
+//     If flag is 1:
+//       LZ10 (2 bytes, big endian):
+//         Length: bits 12-15, plus 3
+//         Offset: bits 0-11, plus 1
+//       LZ11: the top 4 bits of the first byte pick the encoding:
+//         0: length is the next 8 bits plus 0x11, then a 12-bit offset (3 bytes)
+//         1: length is the next 16 bits plus 0x111, then a 12-bit offset (4 bytes)
+//         otherwise: length is those 4 bits plus 1, then a 12-bit offset (2 bytes)
+//       Copy Length bytes from Offset back in the output buffer.
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use std::convert::TryInto;
+
+use crate::unity::check_decompressed_size;
+
+const TYPE_LZ10: u8 = 0x10;
+const TYPE_LZ11: u8 = 0x11;
+
+fn get_u32_le(src: &[u8], i: usize) -> Result<u32, String> {
+    src.get(i..i + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or("truncated header".to_string())
+}
+
+struct Reader<'a> {
+    src: &'a [u8],
+    offs: usize,
+}
+
+impl Reader<'_> {
+    fn next(&mut self) -> Result<usize, String> {
+        let byte = *self.src.get(self.offs)
+            .ok_or(format!("compressed data ends early, at {:#x}", self.offs))?;
+        self.offs += 1;
+        Ok(byte as usize)
+    }
+}
+
+fn decompress_body(src: &[u8], lz_type: u8) -> Result<Vec<u8>, String> {
+    let mut uncompressed_size = get_u32_le(src, 0)? as usize >> 8;
+    let mut reader = Reader { src, offs: 4 };
+    if uncompressed_size == 0 {
+        uncompressed_size = get_u32_le(src, 4)? as usize;
+        reader.offs = 8;
+    }
+    check_decompressed_size(uncompressed_size as u64)?;
+
+    let mut dst = Vec::with_capacity(uncompressed_size);
+    while dst.len() < uncompressed_size {
+        let command_byte = reader.next()?;
+        for i in (0..8).rev() {
+            if dst.len() >= uncompressed_size {
+                break;
+            }
+            if (command_byte & (1 << i)) == 0 {
+                // Literal.
+                let byte = reader.next()? as u8;
+                dst.push(byte);
+                continue;
+            }
+
+            let b0 = reader.next()?;
+            let (window_length, window_offset) = if lz_type == TYPE_LZ10 {
+                let b1 = reader.next()?;
+                ((b0 >> 4) + 3, ((b0 & 0x0F) << 8 | b1) + 1)
+            } else {
+                match b0 >> 4 {
+                    0 => {
+                        let b1 = reader.next()?;
+                        let b2 = reader.next()?;
+                        (((b0 & 0x0F) << 4 | b1 >> 4) + 0x11, ((b1 & 0x0F) << 8 | b2) + 1)
+                    },
+                    1 => {
+                        let b1 = reader.next()?;
+                        let b2 = reader.next()?;
+                        let b3 = reader.next()?;
+                        (((b0 & 0x0F) << 12 | b1 << 4 | b2 >> 4) + 0x111, ((b2 & 0x0F) << 8 | b3) + 1)
+                    },
+                    n => {
+                        let b1 = reader.next()?;
+                        (n + 1, ((b0 & 0x0F) << 8 | b1) + 1)
+                    },
+                }
+            };
+
+            if window_offset > dst.len() {
+                return Err(format!("back reference of {:#x} bytes at output offset {:#x}", window_offset, dst.len()));
+            }
+            let length = window_length.min(uncompressed_size - dst.len());
+            let copy_offs = dst.len() - window_offset;
+            for j in 0..length {
+                dst.push(dst[copy_offs + j]);
+            }
+        }
+    }
+    Ok(dst)
+}
+
+#[wasm_bindgen]
+pub fn lz10_decompress(src: &[u8]) -> Result<Vec<u8>, String> {
+    match src.first() {
+        Some(&TYPE_LZ10) => decompress_body(src, TYPE_LZ10),
+        _ => Err("not LZ10 data".to_string()),
+    }
+}
+
+#[wasm_bindgen]
+pub fn lz11_decompress(src: &[u8]) -> Result<Vec<u8>, String> {
+    match src.first() {
+        Some(&TYPE_LZ11) => decompress_body(src, TYPE_LZ11),
+        _ => Err("not LZ11 data".to_string()),
+    }
+}
+
+// Picks the variant from the header, skipping a "LZ77" magic if there is one
+#[wasm_bindgen]
+pub fn lz77_decompress(src: &[u8]) -> Result<Vec<u8>, String> {
+    let src = src.strip_prefix(b"LZ77").unwrap_or(src);
+    match src.first() {
+        Some(&TYPE_LZ10) => decompress_body(src, TYPE_LZ10),
+        Some(&TYPE_LZ11) => decompress_body(src, TYPE_LZ11),
+        Some(&other) => Err(format!("unknown LZ77 type {:#x}", other)),
+        None => Err("empty input".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // "ab", then copy 6 bytes from 2 back
+    const LZ10_OVERLAP: [u8; 9] = [0x10, 8, 0, 0, 0x20, b'a', b'b', 0x30, 0x01];
+    // "abc", then a 2-byte reference copying 6 bytes from 3 back
+    const LZ11_SHORT: [u8; 10] = [0x11, 9, 0, 0, 0x10, b'a', b'b', b'c', 0x50, 0x02];
+    // "z" with a 3-byte reference of 20 bytes, then "q" with a 4-byte reference of 273 bytes
+    const LZ11_LONG: [u8; 14] = [0x11, 0x27, 0x01, 0, 0x50, b'z', 0x00, 0x30, 0x00, b'q', 0x10, 0x00, 0x00, 0x00];
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(lz10_decompress(&[0x10, 5, 0, 0, 0x00, b'h', b'e', b'l', b'l', b'o']), Ok(b"hello".to_vec()));
+        assert_eq!(lz11_decompress(&[0x11, 3, 0, 0, 0x00, b'a', b'b', b'c']), Ok(b"abc".to_vec()));
+        // Size of 0 in the header means a 32-bit size follows
+        assert_eq!(lz11_decompress(&[0x11, 0, 0, 0, 3, 0, 0, 0, 0x00, b'a', b'b', b'c']), Ok(b"abc".to_vec()));
+        // Literals spanning more than one flags byte
+        let mut src = vec![0x10, 10, 0, 0, 0x00];
+        src.extend(b"01234567");
+        src.extend([0x00, b'8', b'9']);
+        assert_eq!(lz10_decompress(&src), Ok(b"0123456789".to_vec()));
+    }
+
+    #[test]
+    fn test_overlapping_references() {
+        assert_eq!(lz10_decompress(&LZ10_OVERLAP), Ok(b"abababab".to_vec()));
+        assert_eq!(lz10_decompress(&[0x10, 8, 0, 0, 0x40, b'x', 0x40, 0x00]), Ok(b"xxxxxxxx".to_vec()));
+        assert_eq!(lz11_decompress(&LZ11_SHORT), Ok(b"abcabcabc".to_vec()));
+
+        let mut expected = vec![b'z'; 21];
+        expected.extend([b'q'; 274]);
+        assert_eq!(lz11_decompress(&LZ11_LONG), Ok(expected));
+    }
+
+    #[test]
+    fn test_lz77_magic() {
+        let mut src = b"LZ77".to_vec();
+        src.extend(LZ10_OVERLAP);
+        assert_eq!(lz77_decompress(&src), Ok(b"abababab".to_vec()));
+        assert_eq!(lz77_decompress(&LZ11_SHORT), Ok(b"abcabcabc".to_vec()));
+        assert!(lz77_decompress(&[0x12, 0, 0, 0]).is_err());
+        assert!(lz10_decompress(&LZ11_SHORT).is_err());
+    }
+
+    #[test]
+    fn test_truncated() {
+        for src in [&LZ10_OVERLAP[..], &LZ11_SHORT, &LZ11_LONG] {
+            for len in 0..src.len() {
+                assert!(lz77_decompress(&src[..len]).is_err(), "prefix of {} bytes", len);
+            }
+        }
+        // Reference before the start of the output
+        assert_eq!(
+            lz10_decompress(&[0x10, 3, 0, 0, 0x80, 0x00, 0x00]),
+            Err("back reference of 0x1 bytes at output offset 0x0".to_string()),
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use wasm_bindgen::prelude::*;
+
+use crate::profiling;
+use crate::unity;
+
+// wasm memory only grows a page at a time as the allocator runs out, and each
+// memory.grow can stall (and detach every view into the old buffer). Loading a
+// scene in one big step is much cheaper, so these let the website reserve an
+// estimate up front.
+
+const WASM_PAGE_SIZE: usize = 65536;
+
+#[cfg(target_arch = "wasm32")]
+fn memory_size() -> usize {
+    core::arch::wasm32::memory_size(0) * WASM_PAGE_SIZE
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn memory_size() -> usize {
+    0
+}
+
+// Returns the current size of wasm memory in bytes
+#[wasm_bindgen]
+pub fn memory_get_size() -> usize {
+    memory_size()
+}
+
+// Grows wasm memory to at least total_bytes. Rather than calling memory.grow
+// ourselves (which the allocator wouldn't know about), we allocate and free
+// the whole shortfall at once, so memory grows in a single step and is left
+// in the allocator's free lists.
+#[wasm_bindgen]
+pub fn memory_reserve(total_bytes: usize) {
+    let current = memory_size();
+    if current >= total_bytes {
+        return;
+    }
+    let shortfall = (total_bytes - current).div_ceil(WASM_PAGE_SIZE) * WASM_PAGE_SIZE;
+    drop(Vec::<u8>::with_capacity(shortfall));
+}
+
+// Drops the per-load state we keep between calls (the profiling counters and
+// the Unity parser's budget and error path), to be called between scenes.
+// wasm memory can't shrink, but whatever this frees is reused by the next
+// scene.
+#[wasm_bindgen]
+pub fn memory_reset_load_state() {
+    profiling::profiling_reset_counters();
+    unity::reset_parse_state();
+}
@@ -0,0 +1,195 @@
+use std::cmp::Ordering;
+
+use nalgebra_glm::{make_vec3, Vec3};
+use wasm_bindgen::prelude::*;
+
+use crate::geometry::AABB;
+
+// An octree over object bounding boxes. Each object lives in the smallest
+// node that fully contains it, so objects straddling a split stay in the
+// parent. Queries report objects by their index in the original list.
+
+const MAX_DEPTH: u32 = 8;
+const MAX_NODE_OBJECTS: usize = 8;
+
+#[derive(Debug, Clone, Default)]
+struct OctreeNode {
+    aabb: AABB,
+    // the first of eight children stored next to each other, or zero for
+    // leaves (the root is never anybody's child)
+    first_child: u32,
+    objects: Vec<u32>,
+}
+
+#[wasm_bindgen(js_name = "Octree")]
+#[derive(Debug, Clone)]
+pub struct Octree {
+    nodes: Vec<OctreeNode>,
+    bounds: Vec<AABB>,
+}
+
+// Octants are numbered with bit 0 set for the high half in x, bit 1 for y,
+// and bit 2 for z
+fn get_octant(aabb: &AABB, center: &Vec3) -> Option<usize> {
+    let mut octant = 0;
+    for axis in 0..3 {
+        if aabb.min[axis] >= center[axis] {
+            octant |= 1 << axis;
+        } else if aabb.max[axis] > center[axis] {
+            return None;
+        }
+    }
+    Some(octant)
+}
+
+fn get_octant_aabb(aabb: &AABB, center: &Vec3, octant: usize) -> AABB {
+    let mut result = aabb.clone();
+    for axis in 0..3 {
+        if octant & (1 << axis) != 0 {
+            result.min[axis] = center[axis];
+        } else {
+            result.max[axis] = center[axis];
+        }
+    }
+    result
+}
+
+fn sort_by_distance(mut hits: Vec<(f32, u32)>) -> Vec<u32> {
+    hits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+    hits.into_iter().map(|(_, i)| i).collect()
+}
+
+impl Octree {
+    pub fn build(bounds: Vec<AABB>) -> Self {
+        let mut nodes = Vec::new();
+        if bounds.is_empty() {
+            return Octree { nodes, bounds };
+        }
+
+        // keep the nodes cubes, so deep nodes don't end up as thin slices
+        let mut scene_aabb = AABB::default();
+        for aabb in &bounds {
+            scene_aabb.union_aabb(aabb);
+        }
+        let center = scene_aabb.center();
+        let half_size = Vec3::from_element((scene_aabb.max - scene_aabb.min).max() * 0.5);
+        nodes.push(OctreeNode {
+            aabb: AABB { min: center - half_size, max: center + half_size },
+            ..OctreeNode::default()
+        });
+
+        let mut stack = vec![(0, (0..bounds.len() as u32).collect::<Vec<_>>(), 0)];
+        while let Some((node_index, objects, depth)) = stack.pop() {
+            if objects.len() <= MAX_NODE_OBJECTS || depth == MAX_DEPTH {
+                nodes[node_index].objects = objects;
+                continue;
+            }
+
+            let node_aabb = nodes[node_index].aabb.clone();
+            let center = node_aabb.center();
+            let object_count = objects.len();
+            let mut kept = Vec::new();
+            let mut octants = vec![Vec::new(); 8];
+            for i in objects {
+                match get_octant(&bounds[i as usize], &center) {
+                    Some(octant) => octants[octant].push(i),
+                    None => kept.push(i),
+                }
+            }
+            if kept.len() == object_count {
+                nodes[node_index].objects = kept;
+                continue;
+            }
+
+            let first_child = nodes.len();
+            for (octant, objects) in octants.into_iter().enumerate() {
+                nodes.push(OctreeNode {
+                    aabb: get_octant_aabb(&node_aabb, &center, octant),
+                    ..OctreeNode::default()
+                });
+                stack.push((first_child + octant, objects, depth + 1));
+            }
+            nodes[node_index].first_child = first_child as u32;
+            nodes[node_index].objects = kept;
+        }
+
+        Octree { nodes, bounds }
+    }
+
+    // Calls visit for every object whose bounds pass test. Nodes whose bounds
+    // fail it are skipped along with everything in them, so test must accept
+    // any box containing one it would accept.
+    pub fn query(&self, test: impl Fn(&AABB) -> bool, mut visit: impl FnMut(u32)) {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !test(&node.aabb) {
+                continue;
+            }
+            for &i in &node.objects {
+                if test(&self.bounds[i as usize]) {
+                    visit(i);
+                }
+            }
+            if node.first_child != 0 {
+                let first_child = node.first_child as usize;
+                stack.extend(first_child..first_child + 8);
+            }
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "Octree")]
+impl Octree {
+    // bounds holds six floats (min xyz, max xyz) per object
+    #[wasm_bindgen(constructor)]
+    pub fn new(bounds: &[f32]) -> Self {
+        assert_eq!(bounds.len() % 6, 0);
+        Octree::build(bounds.chunks_exact(6).map(AABB::from_slice).collect())
+    }
+
+    pub fn get_object_count(&self) -> usize {
+        self.bounds.len()
+    }
+
+    // Returns the indices of the objects whose bounds overlap the box given
+    // as six floats (min xyz, max xyz)
+    pub fn query_aabb(&self, aabb: &[f32]) -> Vec<u32> {
+        let aabb = AABB::from_slice(aabb);
+        let mut result = Vec::new();
+        self.query(|bounds| bounds.intersects_aabb(&aabb), |i| result.push(i));
+        result
+    }
+
+    // Returns the indices of the objects whose bounds come within radius of
+    // the point, nearest first
+    pub fn query_point(&self, point: &[f32], radius: f32) -> Vec<u32> {
+        assert_eq!(point.len(), 3);
+        let point = make_vec3(point);
+        let mut hits = Vec::new();
+        self.query(|bounds| bounds.distance_squared_to_point(&point) <= radius * radius, |i| {
+            hits.push((self.bounds[i as usize].distance_squared_to_point(&point), i));
+        });
+        sort_by_distance(hits)
+    }
+
+    // Returns the indices of the objects whose bounds the ray passes through
+    // within max_distance, nearest first
+    pub fn query_ray(&self, origin: &[f32], direction: &[f32], max_distance: f32) -> Vec<u32> {
+        assert_eq!(origin.len(), 3);
+        assert_eq!(direction.len(), 3);
+        let origin = make_vec3(origin);
+        let inv_dir = make_vec3(direction).map(|x| 1.0 / x);
+        let enter = |bounds: &AABB| bounds.intersect_ray(&origin, &inv_dir)
+            .map(|(t, _)| t)
+            .filter(|&t| t <= max_distance);
+        let mut hits = Vec::new();
+        self.query(|bounds| enter(bounds).is_some(), |i| {
+            hits.push((enter(&self.bounds[i as usize]).unwrap(), i));
+        });
+        sort_by_distance(hits)
+    }
+}
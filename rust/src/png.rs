@@ -0,0 +1,397 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::hash::{adler32, crc32};
+
+// A small PNG encoder, so decoded textures can be saved without going through
+// a canvas (which only does 8-bit, premultiplies, and color-manages). The
+// zlib stream uses deflate's fixed Huffman codes with a hash chain matcher,
+// which gets most of the way to a real encoder for a fraction of the code.
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+const COLOR_TYPE_RGBA: u8 = 6;
+
+struct BitWriter {
+    output: Vec<u8>,
+    bits: u32,
+    bit_count: u32,
+}
+
+impl BitWriter {
+    fn write_bits(&mut self, value: u32, count: u32) {
+        self.bits |= value << self.bit_count;
+        self.bit_count += count;
+        while self.bit_count >= 8 {
+            self.output.push(self.bits as u8);
+            self.bits >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    // Huffman codes go out most significant bit first
+    fn write_code(&mut self, code: u32, length: u32) {
+        self.write_bits(code.reverse_bits() >> (32 - length), length);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.output.push(self.bits as u8);
+        }
+        self.output
+    }
+}
+
+const LENGTH_BASES: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA_BITS: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASES: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+fn write_literal_or_length(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xC0 + symbol - 280, 8),
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let length_code = LENGTH_BASES.iter().rposition(|&base| base as usize <= length).unwrap();
+    write_literal_or_length(writer, 257 + length_code as u32);
+    writer.write_bits((length - LENGTH_BASES[length_code] as usize) as u32, LENGTH_EXTRA_BITS[length_code] as u32);
+    let distance_code = DISTANCE_BASES.iter().rposition(|&base| base as usize <= distance).unwrap();
+    writer.write_code(distance_code as u32, 5);
+    writer.write_bits((distance - DISTANCE_BASES[distance_code] as usize) as u32, DISTANCE_EXTRA_BITS[distance_code] as u32);
+}
+
+fn hash3(data: &[u8]) -> usize {
+    let value = (data[0] as u32) << 16 | (data[1] as u32) << 8 | data[2] as u32;
+    (value.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+}
+
+fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        output: vec![0x78, 0x01],
+        bits: 0,
+        bit_count: 0,
+    };
+    // a single final block with fixed Huffman codes
+    writer.write_bits(1, 1);
+    writer.write_bits(1, 2);
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+    let insert = |head: &mut Vec<usize>, prev: &mut Vec<usize>, pos: usize| {
+        if pos + MIN_MATCH <= data.len() {
+            let hash = hash3(&data[pos..]);
+            prev[pos % WINDOW_SIZE] = head[hash];
+            head[hash] = pos;
+        }
+    };
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best_length = 0;
+        let mut best_distance = 0;
+        if pos + MIN_MATCH <= data.len() {
+            let max_length = MAX_MATCH.min(data.len() - pos);
+            let mut candidate = head[hash3(&data[pos..])];
+            for _ in 0..MAX_CHAIN {
+                if candidate == usize::MAX || pos - candidate > WINDOW_SIZE - 1 {
+                    break;
+                }
+                let length = data[candidate..].iter().zip(&data[pos..pos + max_length])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best_length {
+                    best_length = length;
+                    best_distance = pos - candidate;
+                    if length == max_length {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW_SIZE];
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+            }
+        }
+
+        if best_length >= MIN_MATCH {
+            write_match(&mut writer, best_length, best_distance);
+            for i in pos..pos + best_length {
+                insert(&mut head, &mut prev, i);
+            }
+            pos += best_length;
+        } else {
+            write_literal_or_length(&mut writer, data[pos] as u32);
+            insert(&mut head, &mut prev, pos);
+            pos += 1;
+        }
+    }
+    write_literal_or_length(&mut writer, 256);
+
+    let mut output = writer.finish();
+    output.extend_from_slice(&adler32(data).to_be_bytes());
+    output
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// Filters each row with whichever filter gives the smallest sum of absolute
+// differences, the usual heuristic for picking filters
+fn filter_rows(data: &[u8], row_size: usize, bytes_per_pixel: usize) -> Vec<u8> {
+    let row_count = data.len() / row_size;
+    let mut result = Vec::with_capacity((row_size + 1) * row_count);
+    let zero_row = vec![0; row_size];
+    let mut candidate = vec![0; row_size];
+    let mut best = vec![0; row_size];
+    for y in 0..row_count {
+        let row = &data[y * row_size..(y + 1) * row_size];
+        let above = if y > 0 { &data[(y - 1) * row_size..y * row_size] } else { &zero_row[..] };
+        let mut best_filter = 0;
+        let mut best_score = u64::MAX;
+        for filter in 0..5u8 {
+            for x in 0..row_size {
+                let left = if x >= bytes_per_pixel { row[x - bytes_per_pixel] } else { 0 };
+                let upper_left = if x >= bytes_per_pixel { above[x - bytes_per_pixel] } else { 0 };
+                let predicted = match filter {
+                    0 => 0,
+                    1 => left,
+                    2 => above[x],
+                    3 => ((left as u16 + above[x] as u16) / 2) as u8,
+                    _ => paeth(left, above[x], upper_left),
+                };
+                candidate[x] = row[x].wrapping_sub(predicted);
+            }
+            let score: u64 = candidate.iter().map(|&b| (b as i8).unsigned_abs() as u64).sum();
+            if score < best_score {
+                best_score = score;
+                best_filter = filter;
+                best.copy_from_slice(&candidate);
+            }
+        }
+        result.push(best_filter);
+        result.extend_from_slice(&best);
+    }
+    result
+}
+
+fn write_chunk(output: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = output.len();
+    output.extend_from_slice(chunk_type);
+    output.extend_from_slice(data);
+    let crc = crc32(&output[start..]);
+    output.extend_from_slice(&crc.to_be_bytes());
+}
+
+// samples are big-endian, as PNG wants them
+fn encode_rgba(width: u32, height: u32, bit_depth: u8, samples: &[u8], linear: bool) -> Vec<u8> {
+    let bytes_per_pixel = 4 * bit_depth as usize / 8;
+    let filtered = filter_rows(samples, width as usize * bytes_per_pixel, bytes_per_pixel);
+
+    let mut output = PNG_SIGNATURE.to_vec();
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[bit_depth, COLOR_TYPE_RGBA, 0, 0, 0]);
+    write_chunk(&mut output, b"IHDR", &header);
+    if linear {
+        // a gamma of 1.0, so viewers don't treat linear data as sRGB
+        write_chunk(&mut output, b"gAMA", &100000u32.to_be_bytes());
+    } else {
+        // perceptual rendering intent
+        write_chunk(&mut output, b"sRGB", &[0]);
+    }
+    write_chunk(&mut output, b"IDAT", &zlib_compress(&filtered));
+    write_chunk(&mut output, b"IEND", &[]);
+    output
+}
+
+fn check_size(width: u32, height: u32, sample_count: usize) -> Result<(), String> {
+    let expected = width as usize * height as usize * 4;
+    if width == 0 || height == 0 || sample_count != expected {
+        return Err(format!("expected {} samples for a {}x{} RGBA image, got {}", expected, width, height, sample_count));
+    }
+    Ok(())
+}
+
+// Set linear for textures that aren't sRGB-encoded (normal maps, masks, etc.)
+#[wasm_bindgen]
+pub fn png_encode_rgba8(width: u32, height: u32, data: &[u8], linear: bool) -> Result<Vec<u8>, String> {
+    check_size(width, height, data.len())?;
+    Ok(encode_rgba(width, height, 8, data, linear))
+}
+
+// For HDR and other high precision textures, after conversion to 16-bit
+#[wasm_bindgen]
+pub fn png_encode_rgba16(width: u32, height: u32, data: &[u16], linear: bool) -> Result<Vec<u8>, String> {
+    check_size(width, height, data.len())?;
+    let samples: Vec<u8> = data.iter().flat_map(|sample| sample.to_be_bytes()).collect();
+    Ok(encode_rgba(width, height, 16, &samples, linear))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compression::{inflate_decompress, InflateFormat};
+
+    fn get_u32_be(data: &[u8], offs: usize) -> u32 {
+        u32::from_be_bytes([data[offs], data[offs + 1], data[offs + 2], data[offs + 3]])
+    }
+
+    // Undoes the row filters, as a decoder would
+    fn unfilter_rows(data: &[u8], row_size: usize, bytes_per_pixel: usize) -> Vec<u8> {
+        let mut result: Vec<u8> = Vec::with_capacity(data.len());
+        for (y, filtered) in data.chunks(row_size + 1).enumerate() {
+            assert_eq!(filtered.len(), row_size + 1);
+            let start = result.len();
+            for x in 0..row_size {
+                let left = if x >= bytes_per_pixel { result[start + x - bytes_per_pixel] } else { 0 };
+                let above = if y > 0 { result[start + x - row_size] } else { 0 };
+                let upper_left = if y > 0 && x >= bytes_per_pixel { result[start + x - row_size - bytes_per_pixel] } else { 0 };
+                let predicted = match filtered[0] {
+                    0 => 0,
+                    1 => left,
+                    2 => above,
+                    3 => ((left as u16 + above as u16) / 2) as u8,
+                    4 => paeth(left, above, upper_left),
+                    filter => panic!("bad filter type {}", filter),
+                };
+                result.push(filtered[x + 1].wrapping_add(predicted));
+            }
+        }
+        result
+    }
+
+    // Checks the chunk framing and returns the decoded samples
+    fn decode(png: &[u8], width: u32, height: u32, bit_depth: u8) -> Vec<u8> {
+        assert_eq!(png[..8], PNG_SIGNATURE);
+        let mut offs = 8;
+        let mut chunk_types = Vec::new();
+        let mut idat = Vec::new();
+        while offs < png.len() {
+            let length = get_u32_be(png, offs) as usize;
+            let chunk = &png[offs + 4..offs + 8 + length];
+            assert_eq!(get_u32_be(png, offs + 8 + length), crc32(chunk));
+            let (chunk_type, data) = chunk.split_at(4);
+            match chunk_type {
+                b"IHDR" => {
+                    assert_eq!(get_u32_be(data, 0), width);
+                    assert_eq!(get_u32_be(data, 4), height);
+                    assert_eq!(data[8..], [bit_depth, COLOR_TYPE_RGBA, 0, 0, 0]);
+                },
+                b"IDAT" => idat.extend_from_slice(data),
+                _ => {},
+            }
+            chunk_types.push(chunk_type.to_vec());
+            offs += 12 + length;
+        }
+        assert_eq!(offs, png.len());
+        assert_eq!(chunk_types.first().map(Vec::as_slice), Some(&b"IHDR"[..]));
+        assert_eq!(chunk_types.last().map(Vec::as_slice), Some(&b"IEND"[..]));
+
+        // zlib header: deflate with a 32K window, no preset dictionary, and a valid check value
+        assert_eq!(idat[0], 0x78);
+        assert_eq!(idat[1] & 0x20, 0);
+        assert_eq!(u16::from_be_bytes([idat[0], idat[1]]) % 31, 0);
+        let filtered = inflate_decompress(&idat, InflateFormat::Zlib).unwrap();
+        assert_eq!(get_u32_be(&idat, idat.len() - 4), adler32(&filtered));
+
+        let bytes_per_pixel = 4 * bit_depth as usize / 8;
+        let row_size = width as usize * bytes_per_pixel;
+        assert_eq!(filtered.len(), (row_size + 1) * height as usize);
+        let mut filters: Vec<u8> = filtered.chunks(row_size + 1).map(|row| row[0]).collect();
+        filters.sort_unstable();
+        filters.dedup();
+        assert_eq!(filters, [0, 1, 2, 3, 4]);
+        unfilter_rows(&filtered, row_size, bytes_per_pixel)
+    }
+
+    // Bands of rows that favor each filter type in turn, with repeated rows
+    // so the matcher finds long references as well as short ones
+    fn test_image(width: u32, height: u32) -> Vec<u16> {
+        let row_size = width as usize * 4;
+        let mut seed = 0x1234_5678u32;
+        let mut samples: Vec<u16> = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                let noise = (seed >> 16) as u16;
+                let column = (x.wrapping_mul(2654435761) >> 16) as u16;
+                let diagonal = 0x8000u16.wrapping_add((x as u16).wrapping_sub(y as u16).wrapping_mul(600));
+                // -3 to 3 in both bytes, so each byte is closer to zero than to its neighbors
+                let small = |n: u16| {
+                    let byte = (n & 3) as u8;
+                    (if n & 4 != 0 { byte.wrapping_neg() } else { byte }) as u16 * 0x101
+                };
+                let pixel = match (y / 4) % 5 {
+                    // Up
+                    0 => [column, column.rotate_left(3), column ^ 0x5555, 0xFFFF],
+                    // Average: each byte is the mean of the ones to the left and above, give or take one
+                    1 => {
+                        let i = samples.len();
+                        let mut pixel = [0; 4];
+                        for (c, sample) in pixel.iter_mut().enumerate() {
+                            let left = if x > 0 { samples[i + c - 4] & 0xFF } else { 0 };
+                            let above = samples[i + c - row_size] & 0xFF;
+                            *sample = ((left + above) / 2 + (noise >> c & 1)).min(0xFF) * 0x101;
+                        }
+                        pixel
+                    },
+                    // Sub, and repeated rows
+                    2 => [x as u16 * 1500, 0x1234, 0xFFFF - x as u16 * 900, 0xFFFF],
+                    // None
+                    3 => [small(noise), small(noise >> 3), small(noise >> 6), small(noise >> 9)],
+                    // Paeth
+                    _ => [diagonal, diagonal, diagonal ^ 0xFF00, 0xFFFF],
+                };
+                samples.extend(pixel);
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn test_round_trip_rgba8() {
+        let (width, height) = (37, 23);
+        let samples: Vec<u8> = test_image(width, height).iter().map(|&s| (s >> 8) as u8).collect();
+        let png = png_encode_rgba8(width, height, &samples, false).unwrap();
+        assert_eq!(decode(&png, width, height, 8), samples);
+    }
+
+    #[test]
+    fn test_round_trip_rgba16() {
+        let (width, height) = (37, 23);
+        let samples = test_image(width, height);
+        let png = png_encode_rgba16(width, height, &samples, true).unwrap();
+        let decoded = decode(&png, width, height, 16);
+        let decoded: Vec<u16> = decoded.chunks(2).map(|s| u16::from_be_bytes([s[0], s[1]])).collect();
+        assert_eq!(decoded, samples);
+    }
+
+    #[test]
+    fn test_bad_size() {
+        assert!(png_encode_rgba8(2, 2, &[0; 15], false).is_err());
+        assert!(png_encode_rgba16(0, 1, &[], false).is_err());
+    }
+}
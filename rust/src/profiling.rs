@@ -0,0 +1,93 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+// Load-time counters for the website's debug overlay. wasm is single-threaded,
+// so these just live in a thread local and get snapshotted on request.
+#[derive(Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Counters {
+    bytes_read: u64,
+    objects_parsed: HashMap<String, u32>,
+    stage_time_ms: HashMap<String, f64>,
+    decompressed_bytes: u64,
+    decompression_time_ms: f64,
+}
+
+thread_local! {
+    static COUNTERS: RefCell<Counters> = RefCell::new(Counters::default());
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> f64 {
+    // performance.now() exists on both windows and workers, unlike window.performance
+    let global = js_sys::global();
+    let performance = js_sys::Reflect::get(&global, &JsValue::from_str("performance")).ok();
+    let now = performance.as_ref()
+        .and_then(|performance| js_sys::Reflect::get(performance, &JsValue::from_str("now")).ok())
+        .and_then(|now| now.dyn_into::<js_sys::Function>().ok());
+    match (performance, now) {
+        (Some(performance), Some(now)) => now.call0(&performance).ok().and_then(|v| v.as_f64()).unwrap_or(0.0),
+        _ => js_sys::Date::now(),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    let since_epoch = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    since_epoch.as_secs_f64() * 1000.0
+}
+
+pub fn record_object_parsed(class_name: &str, byte_size: usize) {
+    COUNTERS.with(|counters| {
+        let mut counters = counters.borrow_mut();
+        counters.bytes_read += byte_size as u64;
+        *counters.objects_parsed.entry(class_name.to_string()).or_insert(0) += 1;
+    });
+}
+
+// Runs f, adding its duration to the named stage
+pub fn time_stage<T, F: FnOnce() -> T>(stage: &str, f: F) -> T {
+    let start = now_ms();
+    let result = f();
+    let elapsed = now_ms() - start;
+    COUNTERS.with(|counters| {
+        *counters.borrow_mut().stage_time_ms.entry(stage.to_string()).or_insert(0.0) += elapsed;
+    });
+    result
+}
+
+// Runs a decompressor, counting its time and (on success) output size
+pub fn time_decompression<T: AsRef<[u8]>, E, F: FnOnce() -> Result<T, E>>(f: F) -> Result<T, E> {
+    let start = now_ms();
+    let result = f();
+    let elapsed = now_ms() - start;
+    COUNTERS.with(|counters| {
+        let mut counters = counters.borrow_mut();
+        counters.decompression_time_ms += elapsed;
+        if let Ok(data) = &result {
+            counters.decompressed_bytes += data.as_ref().len() as u64;
+        }
+    });
+    result
+}
+
+// Returns { bytesRead, objectsParsed: { [class]: count }, stageTimeMs: { [stage]: ms },
+// decompressedBytes, decompressionTimeMs }
+#[wasm_bindgen]
+pub fn profiling_get_counters() -> Result<JsValue, String> {
+    let counters = COUNTERS.with(|counters| counters.borrow().clone());
+    let serializer = serde_wasm_bindgen::Serializer::new()
+        .serialize_maps_as_objects(true);
+    counters.serialize(&serializer)
+        .map_err(|err| format!("Couldn't convert profiling counters: {:?}", err))
+}
+
+#[wasm_bindgen]
+pub fn profiling_reset_counters() {
+    COUNTERS.with(|counters| *counters.borrow_mut() = Counters::default());
+}
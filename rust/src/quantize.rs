@@ -0,0 +1,135 @@
+use wasm_bindgen::prelude::*;
+
+// Packs float vertex attributes into smaller normalized integer formats.
+// Each component is mapped from its range onto [0, 1], so the renderer can
+// use unorm vertex formats and get the original value back as
+// offset + scale * value (or by folding get_dequantize_matrix into the model
+// matrix, for positions).
+
+#[wasm_bindgen(js_name = "QuantizedAttribute", getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct QuantizedAttribute {
+    // little endian, ready to upload
+    pub data: Vec<u8>,
+    // bytes per vertex
+    pub stride: u32,
+    // one of each per input component
+    pub scale: Vec<f32>,
+    pub offset: Vec<f32>,
+}
+
+#[wasm_bindgen(js_class = "QuantizedAttribute")]
+impl QuantizedAttribute {
+    // A column major matrix taking quantized xyz back to the original space
+    pub fn get_dequantize_matrix(&self) -> Vec<f32> {
+        let mut result = vec![0.0; 16];
+        for i in 0..3 {
+            result[i * 5] = self.scale.get(i).copied().unwrap_or(1.0);
+            result[12 + i] = self.offset.get(i).copied().unwrap_or(0.0);
+        }
+        result[15] = 1.0;
+        result
+    }
+}
+
+// Returns the offset and scale mapping each component's range onto [0, 1]
+fn get_ranges(data: &[f32], components: usize) -> (Vec<f32>, Vec<f32>) {
+    let mut min = vec![f32::INFINITY; components];
+    let mut max = vec![f32::NEG_INFINITY; components];
+    for vertex in data.chunks_exact(components) {
+        for (i, &value) in vertex.iter().enumerate() {
+            min[i] = min[i].min(value);
+            max[i] = max[i].max(value);
+        }
+    }
+    let mut offset = Vec::with_capacity(components);
+    let mut scale = Vec::with_capacity(components);
+    for (min, max) in min.into_iter().zip(max) {
+        if max > min {
+            offset.push(min);
+            scale.push(max - min);
+        } else {
+            // constant (or no) data
+            offset.push(if min.is_finite() { min } else { 0.0 });
+            scale.push(0.0);
+        }
+    }
+    (offset, scale)
+}
+
+fn quantize(value: f32, offset: f32, scale: f32, max_value: u32) -> u32 {
+    if scale == 0.0 {
+        return 0;
+    }
+    // NaNs end up as 0
+    (((value - offset) / scale).clamp(0.0, 1.0) * max_value as f32).round() as u32
+}
+
+fn check_components(data: &[f32], components: usize) -> Result<(), String> {
+    if components == 0 || components > 4 {
+        return Err(format!("can't quantize {} components", components));
+    }
+    if !data.len().is_multiple_of(components) {
+        return Err(format!("{} floats isn't a whole number of {} component vertices", data.len(), components));
+    }
+    Ok(())
+}
+
+// 16 bits per component, padded out to two or four components since vertex
+// formats don't come in other sizes. Suits positions and UVs.
+#[wasm_bindgen]
+pub fn quantize_unorm16(data: &[f32], components: usize) -> Result<QuantizedAttribute, String> {
+    check_components(data, components)?;
+    let (offset, scale) = get_ranges(data, components);
+    let padded_components = if components <= 2 { 2 } else { 4 };
+    let mut result = Vec::with_capacity(data.len() / components * padded_components * 2);
+    for vertex in data.chunks_exact(components) {
+        for i in 0..padded_components {
+            let value = match vertex.get(i) {
+                Some(&value) => quantize(value, offset[i], scale[i], 0xFFFF) as u16,
+                None => 0,
+            };
+            result.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    Ok(QuantizedAttribute {
+        data: result,
+        stride: padded_components as u32 * 2,
+        scale,
+        offset,
+    })
+}
+
+fn pack_10_10_10_2(data: &[f32], components: usize, offset: Vec<f32>, scale: Vec<f32>) -> QuantizedAttribute {
+    let mut result = Vec::with_capacity(data.len() / components * 4);
+    for vertex in data.chunks_exact(components) {
+        let mut packed = 0;
+        for (i, &value) in vertex.iter().enumerate() {
+            let max_value = if i < 3 { 0x3FF } else { 0x3 };
+            packed |= quantize(value, offset[i], scale[i], max_value) << (i * 10);
+        }
+        result.extend_from_slice(&packed.to_le_bytes());
+    }
+    QuantizedAttribute {
+        data: result,
+        stride: 4,
+        scale,
+        offset,
+    }
+}
+
+// 10 bits for each of xyz and 2 for w, over each component's own range
+#[wasm_bindgen]
+pub fn quantize_unorm10_10_10_2(data: &[f32], components: usize) -> Result<QuantizedAttribute, String> {
+    check_components(data, components)?;
+    let (offset, scale) = get_ranges(data, components);
+    Ok(pack_10_10_10_2(data, components, offset, scale))
+}
+
+// 10-10-10-2 over a fixed [-1, 1] range, so every mesh's normals (or tangents,
+// with their handedness in w) dequantize the same way
+#[wasm_bindgen]
+pub fn quantize_normals(data: &[f32], components: usize) -> Result<QuantizedAttribute, String> {
+    check_components(data, components)?;
+    Ok(pack_10_10_10_2(data, components, vec![-1.0; components], vec![2.0; components]))
+}
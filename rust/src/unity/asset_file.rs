@@ -1,18 +1,30 @@
-use std::io::Cursor;
+use std::convert::{TryFrom, TryInto};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek};
 
 use deku::reader::Reader;
 use deku::{DekuContainerRead, DekuReader};
+use serde::Serialize;
+use tsify::Tsify;
 use wasm_bindgen::prelude::*;
 
-use crate::unity::types::wasm::WasmFriendlyPPtr;
+use crate::unity::types::common::UnityVersion;
+use crate::unity::version::ParsedUnityVersion;
+use crate::unity::types::schema::{diff_type_tree, SchemaMismatch};
+use crate::unity::types::wasm::{validate_object, MonoBehavior, MonoScript, WasmFriendlyPPtr};
 use crate::unity::types::class_id::ClassID;
-use crate::unity::types::serialized_file::{SerializedFileHeader, SerializedFileMetadata};
+use crate::unity::types::serialized_file::{class_id_from_raw, SerializedFileHeader, SerializedFileMetadata, TypeTreeField, BUILTIN_EXTRA_GUID, DEFAULT_RESOURCES_GUID};
+use crate::unity::type_tree::read_type_tree;
+use crate::unity::external::{external_path_matches, normalize_external_path};
+use crate::unity::segmented::SegmentedBuffer;
 
 #[wasm_bindgen(js_name = "UnityAssetFile")]
 pub struct AssetFile {
     header: SerializedFileHeader,
     metadata_offset: usize,
     metadata: Option<SerializedFileMetadata>,
+    // each serialized type's type tree, empty if stripped
+    type_fields: Vec<Vec<TypeTreeField>>,
 }
 
 #[wasm_bindgen(js_class = "UnityAssetFile")]
@@ -26,6 +38,7 @@ impl AssetFile {
                     header,
                     metadata_offset: header_size,
                     metadata: None,
+                    type_fields: Vec::new(),
                 })
             },
             Err(err) => Err(format!("failed to parse header: {:?}", err)),
@@ -41,21 +54,25 @@ impl AssetFile {
 
     pub fn append_metadata_chunk(&mut self, data: &[u8]) -> Result<(), String> {
         // data will be the file from bytes 0..data_offset, so skip to where the metadata starts
-        let mut cursor = Cursor::new(data);
-        let mut reader = Reader::new(&mut cursor);
-        let _header = SerializedFileHeader::from_reader_with_ctx(&mut reader, ())
-            .map_err(|err| format!("failed to parse metadata file header: {:?}", err))?;
-        match SerializedFileMetadata::from_reader_with_ctx(&mut reader, self.header.version) {
-            Ok(metadata) => self.metadata = Some(metadata),
-            Err(err) => return Err(format!("failed to parse metadata: {:?}", err)),
-        }
-        Ok(())
+        self.read_metadata(&mut Cursor::new(data))
+    }
+
+    // Like append_metadata_chunk, for when the file's start arrived in pieces
+    pub fn append_metadata_segments(&mut self, buffer: &SegmentedBuffer) -> Result<(), String> {
+        self.read_metadata(&mut buffer.cursor())
     }
 
     pub fn get_version_string(&self) -> String {
         self.get_metadata().version_ascii.clone().into()
     }
 
+    // Which parser layout to use for this file's objects, if we have one
+    pub fn get_unity_version(&self) -> Option<UnityVersion> {
+        let version_string = self.get_version_string();
+        ParsedUnityVersion::try_from(version_string.as_str()).ok()?
+            .to_supported_version()
+    }
+
     fn get_metadata(&self) -> &SerializedFileMetadata {
         self.metadata.as_ref().expect("must call AssetFile.append_metadata_chunk()")
     }
@@ -65,7 +82,10 @@ impl AssetFile {
         let mut result = Vec::new();
         for obj in &metadata.objects {
             let byte_start = self.get_data_offset() as i64 + obj.get_byte_start();
-            let class_id = if obj.serialized_type_index >= 0 {
+            let class_id = if let Some(class_id) = obj.class_id {
+                // before v16, objects name their class directly
+                class_id_from_raw(class_id as i32)
+            } else if obj.serialized_type_index >= 0 {
                 match metadata.type_tree.get(obj.serialized_type_index as usize) {
                     Some(obj_type) => {
                         // println!("{}: got actual type {:?}", obj.file_id, obj_type.header.raw_type_id);
@@ -90,6 +110,170 @@ impl AssetFile {
         result
     }
 
+    // Tries parsing every object in the file (data being the whole file) with
+    // whichever parser matches its class, to see how much of a new game we
+    // can already read
+    pub fn validate(&self, version: UnityVersion, data: &[u8]) -> ValidationReport {
+        let mut report = ValidationReport {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+            unknown: Vec::new(),
+        };
+        for obj in self.get_objects() {
+            let class_name = format!("{:?}", obj.class_id);
+            let start = obj.byte_start as usize;
+            let Some(object_data) = data.get(start..start + obj.byte_size) else {
+                report.failed.push(ValidationFailure {
+                    file_id: JsValue::from(obj.file_id),
+                    class_name,
+                    byte_start: obj.byte_start as f64,
+                    error_offset: obj.byte_start as f64,
+                    error: format!("object extends past the end of the file ({} bytes)", data.len()),
+                });
+                continue;
+            };
+            match validate_object(obj.class_id, version, object_data) {
+                Some(Ok(())) => ValidationReport::count(&mut report.succeeded, class_name),
+                Some(Err((error, bytes_read))) => report.failed.push(ValidationFailure {
+                    file_id: JsValue::from(obj.file_id),
+                    class_name,
+                    byte_start: obj.byte_start as f64,
+                    error_offset: (obj.byte_start as u64 + bytes_read) as f64,
+                    error,
+                }),
+                None => ValidationReport::count(&mut report.unknown, class_name),
+            }
+        }
+        report
+    }
+
+    // Compares each type tree in the file against our parser for that type.
+    // Player builds usually strip type trees, so this needs an editor build or
+    // asset bundle.
+    pub fn diff_schemas(&self) -> Result<SchemaDiffReport, String> {
+        if self.get_metadata().enable_type_tree == 0 {
+            return Err("file has no type trees".to_string());
+        }
+        let mut mismatches = Vec::new();
+        for fields in &self.type_fields {
+            mismatches.extend(diff_type_tree(fields));
+        }
+        Ok(SchemaDiffReport { mismatches })
+    }
+
+    // Finds the MonoBehaviors running the given script class (data being the
+    // whole file), e.g. to locate a game's streaming or terrain managers.
+    // Only scripts defined in this file are matched.
+    pub fn find_mono_behaviors(&self, version: UnityVersion, data: &[u8], script_class_name: &str) -> Vec<AssetFileObject> {
+        let objects = self.get_objects();
+        let get_object_data = |obj: &AssetFileObject| {
+            let start = obj.byte_start as usize;
+            data.get(start..start + obj.byte_size)
+        };
+        let mut script_matches: HashMap<i64, bool> = HashMap::new();
+        let mut result = Vec::new();
+        for obj in objects.iter().filter(|obj| obj.class_id == ClassID::MonoBehavior) {
+            let Some(behavior) = get_object_data(obj).and_then(|d| MonoBehavior::create(version, d).ok()) else {
+                continue;
+            };
+            if behavior.script.file_index != 0 {
+                continue;
+            }
+            let path_id = behavior.script.path_id;
+            let is_match = *script_matches.entry(path_id).or_insert_with(|| {
+                objects.iter()
+                    .find(|script| script.file_id == path_id && script.class_id == ClassID::MonoScript)
+                    .and_then(get_object_data)
+                    .and_then(|d| MonoScript::create(version, d).ok())
+                    .is_some_and(|script| script.class_name == script_class_name)
+            });
+            if is_match {
+                result.push(AssetFileObject {
+                    file_id: obj.file_id,
+                    byte_start: obj.byte_start,
+                    byte_size: obj.byte_size,
+                    class_id: obj.class_id,
+                });
+            }
+        }
+        result
+    }
+
+    // Reads just the name of every named object (data being the whole file),
+    // without parsing the rest of it, for listing a file's assets. Objects
+    // without a name, or whose class we don't know the name position of, are
+    // left out.
+    pub fn get_object_names(&self, version: UnityVersion, data: &[u8]) -> ObjectNameList {
+        let mut objects = Vec::new();
+        for obj in self.get_objects() {
+            let start = obj.byte_start as usize;
+            let Some(object_data) = data.get(start..start + obj.byte_size) else {
+                continue;
+            };
+            let Some(name) = read_object_name(obj.class_id, version, object_data) else {
+                continue;
+            };
+            if name.is_empty() {
+                continue;
+            }
+            objects.push(ObjectName {
+                path_id: JsValue::from(obj.file_id),
+                class_name: format!("{:?}", obj.class_id),
+                name,
+            });
+        }
+        ObjectNameList { objects }
+    }
+
+    // The object's type tree, if the file has them (editor builds and asset
+    // bundles usually do, players usually don't)
+    pub fn get_type_tree(&self, path_id: i64) -> Option<TypeTree> {
+        self.get_object_type_fields(path_id)
+            .map(|fields| TypeTree { fields: fields.to_vec() })
+    }
+
+    // Reads an object (data being the whole file) using its own type tree,
+    // for classes we have no parser for
+    pub fn read_object_fields(&self, data: &[u8], path_id: i64) -> Result<JsValue, String> {
+        let obj = self.get_objects().into_iter()
+            .find(|obj| obj.file_id == path_id)
+            .ok_or_else(|| format!("no object with path ID {}", path_id))?;
+        let fields = self.get_object_type_fields(path_id)
+            .ok_or_else(|| format!("object {} has no type tree", path_id))?;
+        let start = obj.byte_start as usize;
+        let object_data = data.get(start..start + obj.byte_size)
+            .ok_or_else(|| format!("object {} extends past the end of the file ({} bytes)", path_id, data.len()))?;
+        read_type_tree(fields, object_data)?.to_js()
+    }
+
+    // Checks that each object's type tree accounts for exactly its bytes
+    // (data being the whole file), which catches misread type trees before
+    // anything depends on them
+    pub fn validate_type_trees(&self, data: &[u8]) -> TypeTreeValidationReport {
+        let mut report = TypeTreeValidationReport { checked: 0, failed: Vec::new() };
+        for obj in self.get_objects() {
+            let Some(fields) = self.get_object_type_fields(obj.file_id) else {
+                continue;
+            };
+            report.checked += 1;
+            let start = obj.byte_start as usize;
+            let result = match data.get(start..start + obj.byte_size) {
+                Some(object_data) => read_type_tree(fields, object_data).map(|_| ()),
+                None => Err(format!("object extends past the end of the file ({} bytes)", data.len())),
+            };
+            if let Err(error) = result {
+                report.failed.push(ValidationFailure {
+                    file_id: JsValue::from(obj.file_id),
+                    class_name: format!("{:?}", obj.class_id),
+                    byte_start: obj.byte_start as f64,
+                    error_offset: obj.byte_start as f64,
+                    error,
+                });
+            }
+        }
+        report
+    }
+
     pub fn get_external_path(&self, pptr: &WasmFriendlyPPtr) -> Option<String> {
         let idx = pptr.file_index as usize - 1;
         let metadata = self.get_metadata();
@@ -97,6 +281,188 @@ impl AssetFile {
             .get(idx)
             .map(|external_file| (&external_file.path_name_ascii).into())
     }
+
+    pub fn get_external_guid(&self, pptr: &WasmFriendlyPPtr) -> Option<String> {
+        let idx = (pptr.file_index as usize).checked_sub(1)?;
+        self.get_metadata().externals.values
+            .get(idx)
+            .map(|external_file| external_file.guid.to_hex_string())
+    }
+
+    // The PPtr file index that refers to the given file, if this file depends
+    // on it. Matches by GUID for the built-in resources, and by path otherwise.
+    pub fn find_external_file_index(&self, file_path: &str) -> Option<u32> {
+        let builtin_guid = match normalize_external_path(file_path).as_str() {
+            "resources/unity default resources" => Some(DEFAULT_RESOURCES_GUID),
+            "resources/unity_builtin_extra" => Some(BUILTIN_EXTRA_GUID),
+            _ => None,
+        };
+        self.get_metadata().externals.values.iter()
+            .position(|external_file| {
+                if builtin_guid.as_ref() == Some(&external_file.guid) {
+                    return true;
+                }
+                let path: String = (&external_file.path_name_ascii).into();
+                external_path_matches(&path, file_path)
+            })
+            .map(|idx| idx as u32 + 1)
+    }
+}
+
+impl AssetFile {
+    fn read_metadata<R: Read + Seek>(&mut self, source: &mut R) -> Result<(), String> {
+        let mut reader = Reader::new(source);
+        let _header = SerializedFileHeader::from_reader_with_ctx(&mut reader, ())
+            .map_err(|err| format!("failed to parse metadata file header: {:?}", err))?;
+        match SerializedFileMetadata::from_reader_with_ctx(&mut reader, self.header.version) {
+            Ok(metadata) => {
+                self.type_fields = metadata.type_tree.iter()
+                    .map(|serialized_type| serialized_type.header.get_fields().unwrap_or_default())
+                    .collect();
+                self.metadata = Some(metadata);
+            },
+            Err(err) => return Err(format!("failed to parse metadata: {:?}", err)),
+        }
+        Ok(())
+    }
+
+    pub fn get_object_type_fields(&self, path_id: i64) -> Option<&[TypeTreeField]> {
+        let obj = self.get_metadata().objects.iter().find(|obj| obj.file_id == path_id)?;
+        let fields = self.type_fields.get(self.get_metadata().get_type_index(obj)?)?;
+        if fields.is_empty() {
+            None
+        } else {
+            Some(fields)
+        }
+    }
+}
+
+// Holds a whole serialized file and hands out the raw bytes of its objects
+// by path ID, so parsers for game-specific classes can be prototyped on the
+// JS side before being ported here. Classes we do have parsers for can be
+// read in place with their create_from_reader.
+#[wasm_bindgen(js_name = "UnityAssetReader")]
+pub struct AssetReader {
+    data: Vec<u8>,
+    asset_file: AssetFile,
+    objects: HashMap<i64, AssetFileObject>,
+}
+
+#[wasm_bindgen(js_class = "UnityAssetReader")]
+impl AssetReader {
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: Vec<u8>) -> Result<AssetReader, String> {
+        let mut asset_file = AssetFile::initialize_with_header_chunk(&data)?;
+        asset_file.append_metadata_chunk(&data)?;
+        let objects = asset_file.get_objects().into_iter()
+            .map(|obj| (obj.file_id, obj))
+            .collect();
+        Ok(Self { data, asset_file, objects })
+    }
+
+    pub fn get_version_string(&self) -> String {
+        self.asset_file.get_version_string()
+    }
+
+    pub fn get_info(&self, path_id: i64) -> Option<AssetInfo> {
+        let obj = self.objects.get(&path_id)?;
+        Some(AssetInfo {
+            path_id: JsValue::from(path_id),
+            class_name: format!("{:?}", obj.class_id),
+            byte_start: obj.byte_start as f64,
+            byte_size: obj.byte_size as u32,
+        })
+    }
+
+    pub fn get_object_names(&self, version: UnityVersion) -> ObjectNameList {
+        self.asset_file.get_object_names(version, &self.data)
+    }
+
+    pub fn read_object_fields(&self, path_id: i64) -> Result<JsValue, String> {
+        self.asset_file.read_object_fields(&self.data, path_id)
+    }
+
+    // The object's bytes, starting after the file's data offset. Errors if
+    // there's no such object or it runs past the end of the file.
+    pub fn read_object(&self, path_id: i64) -> Result<Vec<u8>, String> {
+        self.get_object_data(path_id).map(|data| data.to_vec())
+    }
+}
+
+impl AssetReader {
+    pub fn get_object_data(&self, path_id: i64) -> Result<&[u8], String> {
+        let obj = self.objects.get(&path_id)
+            .ok_or_else(|| format!("no object with path ID {}", path_id))?;
+        let start = obj.byte_start as usize;
+        self.data.get(start..start + obj.byte_size)
+            .ok_or_else(|| format!("object {} extends past the end of the file ({} bytes)", path_id, self.data.len()))
+    }
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct AssetInfo {
+    #[tsify(type = "bigint")]
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    pub path_id: JsValue,
+    pub class_name: String,
+    // from the start of the file, i.e. data offset included
+    pub byte_start: f64,
+    pub byte_size: u32,
+}
+
+// Where m_Name is in an object, if we know. Anything deriving from
+// NamedObject starts with it; GameObjects and MonoBehaviors have it after a
+// few fixed fields.
+fn name_offset(class_id: ClassID, version: UnityVersion, data: &[u8]) -> Option<usize> {
+    // path IDs were 32 bits before 5.x
+    let pptr_size = if version < UnityVersion::V5_4_6f1 { 8 } else { 12 };
+    match class_id {
+        ClassID::GameObject => {
+            // before 5.5, each component was stored along with its class ID
+            let component_size = if version < UnityVersion::V2017_3_0f3 { pptr_size + 4 } else { pptr_size };
+            let count = i32::from_le_bytes(data.get(0..4)?.try_into().ok()?);
+            let count = usize::try_from(count).ok()?;
+            // components, then the layer
+            Some(4 + count.checked_mul(component_size)? + 4)
+        },
+        // game object, enabled (aligned), script
+        ClassID::MonoBehavior => Some(pptr_size + 4 + pptr_size),
+        ClassID::Material | ClassID::Mesh | ClassID::Texture2D | ClassID::Texture3D | ClassID::Cubemap |
+        ClassID::Texture2DArray | ClassID::CubemapArray | ClassID::RenderTexture | ClassID::Shader |
+        ClassID::TextAsset | ClassID::AnimationClip | ClassID::AudioClip | ClassID::Avatar |
+        ClassID::AnimatorController | ClassID::AnimatorOverrideController | ClassID::AvatarMask |
+        ClassID::MonoScript | ClassID::Font | ClassID::Sprite | ClassID::SpriteAtlas |
+        ClassID::TerrainData | ClassID::NavMeshData | ClassID::PhysicMaterial | ClassID::PhysicsMaterial2D |
+        ClassID::LightingDataAsset | ClassID::LightProbes | ClassID::VideoClip | ClassID::ComputeShader |
+        ClassID::ShaderVariantCollection | ClassID::AssetBundle | ClassID::AudioMixer |
+        ClassID::AudioMixerGroup | ClassID::AudioMixerSnapshot | ClassID::TerrainLayer |
+        ClassID::OcclusionCullingData => Some(0),
+        _ => None,
+    }
+}
+
+fn read_object_name(class_id: ClassID, version: UnityVersion, data: &[u8]) -> Option<String> {
+    let offset = name_offset(class_id, version, data)?;
+    let len = i32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?);
+    let len = usize::try_from(len).ok()?;
+    let bytes = data.get(offset + 4..offset + 4 + len)?;
+    std::str::from_utf8(bytes).ok().map(|name| name.to_string())
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct ObjectNameList {
+    pub objects: Vec<ObjectName>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct ObjectName {
+    #[tsify(type = "bigint")]
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    pub path_id: JsValue,
+    pub class_name: String,
+    pub name: String,
 }
 
 #[wasm_bindgen(js_name = "UnityAssetFileObject")]
@@ -107,13 +473,68 @@ pub struct AssetFileObject {
     pub class_id: ClassID,
 }
 
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct ValidationReport {
+    pub succeeded: Vec<ClassCount>,
+    pub failed: Vec<ValidationFailure>,
+    // objects whose class we have no parser for
+    pub unknown: Vec<ClassCount>,
+}
+
+impl ValidationReport {
+    fn count(counts: &mut Vec<ClassCount>, class_name: String) {
+        match counts.iter_mut().find(|count| count.class_name == class_name) {
+            Some(count) => count.count += 1,
+            None => counts.push(ClassCount { class_name, count: 1 }),
+        }
+    }
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct TypeTree {
+    pub fields: Vec<TypeTreeField>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct TypeTreeValidationReport {
+    pub checked: u32,
+    pub failed: Vec<ValidationFailure>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct SchemaDiffReport {
+    pub mismatches: Vec<SchemaMismatch>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct ClassCount {
+    pub class_name: String,
+    pub count: u32,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct ValidationFailure {
+    #[tsify(type = "bigint")]
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    pub file_id: JsValue,
+    pub class_name: String,
+    pub byte_start: f64,
+    // roughly where in the file the parser gave up
+    pub error_offset: f64,
+    pub error: String,
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
     use std::str::FromStr;
 
     use crate::unity::types::common::UnityVersion;
-    use crate::unity::types::wasm::{Texture2D, Mesh, MeshFilter, MeshRenderer, Transform, Material};
+    use crate::unity::types::wasm::{Texture2D, Mesh, MeshFilter, MeshRenderer, Transform, Material, Animator, AnimatorController, AnimatorOverrideController, Avatar, BoxCollider, SphereCollider, CapsuleCollider, MeshCollider, NavMeshData, OcclusionCullingSettings, OcclusionCullingData, ResourceManager, Canvas, CanvasRenderer, MonoScript, MonoBehavior, SpriteRenderer, Sprite, LineRenderer, TrailRenderer, WindZone, Shader};
 
     use super::*;
 
@@ -168,6 +589,72 @@ mod tests {
                     println!("parsing Texture2D {}", obj.file_id);
                     Texture2D::create(version, data).unwrap();
                 },
+                ClassID::Animator => {
+                    Animator::create(version, data).unwrap();
+                },
+                ClassID::AnimatorController => {
+                    AnimatorController::create(version, data).unwrap();
+                },
+                ClassID::AnimatorOverrideController => {
+                    AnimatorOverrideController::create(version, data).unwrap();
+                },
+                ClassID::Avatar => {
+                    Avatar::create(version, data).unwrap();
+                },
+                ClassID::BoxCollider => {
+                    BoxCollider::create(version, data).unwrap();
+                },
+                ClassID::SphereCollider => {
+                    SphereCollider::create(version, data).unwrap();
+                },
+                ClassID::CapsuleCollider => {
+                    CapsuleCollider::create(version, data).unwrap();
+                },
+                ClassID::MeshCollider => {
+                    MeshCollider::create(version, data).unwrap();
+                },
+                ClassID::NavMeshData => {
+                    NavMeshData::create(version, data).unwrap();
+                },
+                ClassID::OcclusionCullingSettings => {
+                    OcclusionCullingSettings::create(version, data).unwrap();
+                },
+                ClassID::OcclusionCullingData => {
+                    OcclusionCullingData::create(version, data).unwrap();
+                },
+                ClassID::ResourceManager => {
+                    ResourceManager::create(version, data).unwrap();
+                },
+                ClassID::Canvas => {
+                    Canvas::create(version, data).unwrap();
+                },
+                ClassID::CanvasRenderer => {
+                    CanvasRenderer::create(version, data).unwrap();
+                },
+                ClassID::MonoScript => {
+                    MonoScript::create(version, data).unwrap();
+                },
+                ClassID::MonoBehavior => {
+                    MonoBehavior::create(version, data).unwrap();
+                },
+                ClassID::SpriteRenderer => {
+                    SpriteRenderer::create(version, data).unwrap();
+                },
+                ClassID::Sprite => {
+                    Sprite::create(version, data).unwrap();
+                },
+                ClassID::LineRenderer => {
+                    LineRenderer::create(version, data).unwrap();
+                },
+                ClassID::TrailRenderer => {
+                    TrailRenderer::create(version, data).unwrap();
+                },
+                ClassID::WindZone => {
+                    WindZone::create(version, data).unwrap();
+                },
+                ClassID::Shader => {
+                    Shader::create(version, data).unwrap();
+                },
                 _ => {},
             }
         }
@@ -0,0 +1,180 @@
+use serde::Serialize;
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+// Packs lots of small decoded textures (sprites, UI icons, terrain details)
+// into one, so 2D-heavy scenes can bind a single texture. Images are packed
+// onto shelves, tallest first, in the smallest power-of-two atlas they fit.
+// Each image gets a border of its own edge pixels so bilinear filtering
+// doesn't bleed in its neighbors.
+
+struct AtlasImage {
+    width: u32,
+    height: u32,
+    // RGBA8
+    pixels: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Placement {
+    x: u32,
+    y: u32,
+}
+
+#[wasm_bindgen(js_name = "UnityAtlasBuilder")]
+#[derive(Default)]
+pub struct AtlasBuilder {
+    padding: u32,
+    images: Vec<AtlasImage>,
+    atlas: Vec<u8>,
+}
+
+#[wasm_bindgen(js_class = "UnityAtlasBuilder")]
+impl AtlasBuilder {
+    pub fn new(padding: u32) -> Self {
+        Self {
+            padding,
+            ..Default::default()
+        }
+    }
+
+    // Returns the index the layout refers to it by
+    pub fn add_image(&mut self, width: u32, height: u32, pixels: Vec<u8>) -> Result<usize, String> {
+        if pixels.len() != (width * height * 4) as usize {
+            return Err(format!("expected {}x{} RGBA8 pixels, got {} bytes", width, height, pixels.len()));
+        }
+        self.images.push(AtlasImage { width, height, pixels });
+        Ok(self.images.len() - 1)
+    }
+
+    // Lays out and draws the atlas; get_atlas_pixels then returns it
+    pub fn pack(&mut self, max_size: u32) -> Result<AtlasLayout, String> {
+        let (width, height, placements) = self.find_layout(max_size)
+            .ok_or_else(|| format!("{} images don't fit in a {}x{} atlas", self.images.len(), max_size, max_size))?;
+
+        self.atlas = vec![0; (width * height * 4) as usize];
+        let mut scale_offsets = Vec::with_capacity(self.images.len() * 4);
+        for (image, placement) in self.images.iter().zip(&placements) {
+            draw_padded(&mut self.atlas, width, image, placement.x, placement.y, self.padding);
+            scale_offsets.extend([
+                image.width as f32 / width as f32,
+                image.height as f32 / height as f32,
+                (placement.x + self.padding) as f32 / width as f32,
+                (placement.y + self.padding) as f32 / height as f32,
+            ]);
+        }
+        Ok(AtlasLayout {
+            width,
+            height,
+            scale_offsets,
+        })
+    }
+
+    pub fn get_atlas_pixels(&self) -> Vec<u8> {
+        self.atlas.clone()
+    }
+}
+
+impl AtlasBuilder {
+    fn find_layout(&self, max_size: u32) -> Option<(u32, u32, Vec<Placement>)> {
+        let padded = |image: &AtlasImage| (image.width + 2 * self.padding, image.height + 2 * self.padding);
+        let area: u64 = self.images.iter().map(|image| {
+            let (w, h) = padded(image);
+            w as u64 * h as u64
+        }).sum();
+        let (mut width, mut height) = (1, 1);
+        while (width as u64 * height as u64) < area {
+            if width <= height { width *= 2 } else { height *= 2 }
+        }
+
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(padded(&self.images[i]).1));
+        while width <= max_size && height <= max_size {
+            if let Some(placements) = pack_shelves(&order, |i| padded(&self.images[i]), width, height) {
+                return Some((width, height, placements));
+            }
+            if width <= height { width *= 2 } else { height *= 2 }
+        }
+        None
+    }
+}
+
+// Places the images in the given order, left to right along each shelf
+fn pack_shelves(order: &[usize], size: impl Fn(usize) -> (u32, u32), width: u32, height: u32) -> Option<Vec<Placement>> {
+    let mut placements = vec![Placement { x: 0, y: 0 }; order.len()];
+    let (mut x, mut shelf_y, mut shelf_height) = (0, 0, 0);
+    for &i in order {
+        let (w, h) = size(i);
+        if w > width {
+            return None;
+        }
+        if x + w > width {
+            x = 0;
+            shelf_y += shelf_height;
+            shelf_height = 0;
+        }
+        if shelf_y + h > height {
+            return None;
+        }
+        placements[i] = Placement { x, y: shelf_y };
+        x += w;
+        shelf_height = shelf_height.max(h);
+    }
+    Some(placements)
+}
+
+// Copies the image in at (x + padding, y + padding), extending its edges out
+// through the padding
+fn draw_padded(atlas: &mut [u8], atlas_width: u32, image: &AtlasImage, x: u32, y: u32, padding: u32) {
+    let padded_width = image.width + 2 * padding;
+    let padded_height = image.height + 2 * padding;
+    for py in 0..padded_height {
+        let src_y = py.saturating_sub(padding).min(image.height.saturating_sub(1));
+        for px in 0..padded_width {
+            let src_x = px.saturating_sub(padding).min(image.width.saturating_sub(1));
+            let src = ((src_y * image.width + src_x) * 4) as usize;
+            let dst = (((y + py) * atlas_width + x + px) * 4) as usize;
+            if let Some(pixel) = image.pixels.get(src..src + 4) {
+                atlas[dst..dst + 4].copy_from_slice(pixel);
+            }
+        }
+    }
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct AtlasLayout {
+    pub width: u32,
+    pub height: u32,
+    // (scale x, scale y, offset x, offset y) per image, in the order they
+    // were added, mapping its UVs into its region of the atlas
+    pub scale_offsets: Vec<f32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack() {
+        let mut builder = AtlasBuilder::new(1);
+        builder.add_image(6, 6, vec![0xFF; 6 * 6 * 4]).unwrap();
+        builder.add_image(2, 2, vec![0x80; 2 * 2 * 4]).unwrap();
+        builder.add_image(14, 2, vec![0x40; 14 * 2 * 4]).unwrap();
+        assert!(builder.add_image(2, 2, vec![0; 4]).is_err());
+
+        let layout = builder.pack(64).unwrap();
+        assert_eq!((layout.width, layout.height), (16, 16));
+        // the 6x6 image is tallest, so goes first, with its padding
+        assert_eq!(&layout.scale_offsets[0..4], &[6.0 / 16.0, 6.0 / 16.0, 1.0 / 16.0, 1.0 / 16.0]);
+        assert_eq!(&layout.scale_offsets[4..8], &[2.0 / 16.0, 2.0 / 16.0, 9.0 / 16.0, 1.0 / 16.0]);
+        assert_eq!(&layout.scale_offsets[8..12], &[14.0 / 16.0, 2.0 / 16.0, 1.0 / 16.0, 9.0 / 16.0]);
+
+        // padding repeats the edge pixels
+        let pixels = builder.get_atlas_pixels();
+        assert_eq!(pixels[0], 0xFF);
+        assert_eq!(pixels[(8 * 4) as usize], 0x80);
+
+        assert!(builder.pack(8).is_err());
+    }
+}
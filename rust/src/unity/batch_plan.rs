@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use crate::unity::types::wasm::{MeshFilter, MeshRenderer, PPtrInfo, WasmFriendlyPPtr, RENDER_QUEUE_GEOMETRY, RENDER_QUEUE_GEOMETRY_LAST};
+
+// Unity scenes can have thousands of renderers, each drawing a submesh per
+// material. This plans fewer, larger draws:
+// - statically batched renderers sharing a combined mesh, material and
+//   lightmap draw adjacent submeshes in one call, since a combined mesh's
+//   submeshes are laid out one after another in its index buffer
+// - other opaque renderers of the same mesh, submesh, material and lightmap
+//   become one instanced draw
+// - transparent renderers are left alone, since they need sorting
+// Opaque batches are ordered by shader, then material, then mesh, to keep
+// state changes down.
+
+#[derive(Clone, Copy)]
+struct MaterialInfo {
+    shader: WasmFriendlyPPtr,
+    render_queue: i32,
+}
+
+struct PlannedDraw {
+    game_object: WasmFriendlyPPtr,
+    mesh: WasmFriendlyPPtr,
+    material: WasmFriendlyPPtr,
+    submesh: u32,
+    lightmap_index: u16,
+    statically_batched: bool,
+}
+
+#[wasm_bindgen(js_name = "UnityDrawBatchPlanner")]
+#[derive(Default)]
+pub struct DrawBatchPlanner {
+    meshes: HashMap<WasmFriendlyPPtr, WasmFriendlyPPtr>,
+    materials: HashMap<WasmFriendlyPPtr, MaterialInfo>,
+    draws: Vec<PlannedDraw>,
+    renderer_count: u32,
+}
+
+#[wasm_bindgen(js_class = "UnityDrawBatchPlanner")]
+impl DrawBatchPlanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_mesh_filter(&mut self, mesh_filter: &MeshFilter) {
+        self.meshes.insert(mesh_filter.game_object, mesh_filter.mesh);
+    }
+
+    // render_queue as from Material.get_render_queue(); materials never
+    // added here are treated as opaque with an unknown shader
+    pub fn add_material(&mut self, material: &WasmFriendlyPPtr, shader: &WasmFriendlyPPtr, render_queue: i32) {
+        self.materials.insert(*material, MaterialInfo { shader: *shader, render_queue });
+    }
+
+    // Mesh filters must be added first
+    pub fn add_mesh_renderer(&mut self, mesh_renderer: &MeshRenderer) {
+        if mesh_renderer.enabled == 0 {
+            return;
+        }
+        let Some(&mesh) = self.meshes.get(&mesh_renderer.game_object) else {
+            return;
+        };
+        if mesh.path_id == 0 {
+            return;
+        }
+        self.renderer_count += 1;
+        let batch_info = &mesh_renderer.static_batch_info;
+        let statically_batched = batch_info.submesh_count > 0;
+        for (i, &material) in mesh_renderer.materials.iter().enumerate() {
+            if material.path_id == 0 {
+                continue;
+            }
+            // extra materials draw the last submesh again
+            let submesh = if statically_batched {
+                batch_info.first_submesh as u32 + (i as u32).min(batch_info.submesh_count as u32 - 1)
+            } else {
+                i as u32
+            };
+            self.draws.push(PlannedDraw {
+                game_object: mesh_renderer.game_object,
+                mesh,
+                material,
+                submesh,
+                lightmap_index: mesh_renderer.lightmap_index,
+                statically_batched,
+            });
+        }
+    }
+
+    pub fn compute_plan(&self) -> DrawBatchPlan {
+        let mut opaque: Vec<FoundBatch> = Vec::new();
+        let mut transparent: Vec<FoundBatch> = Vec::new();
+        // (mesh, material, lightmap index, submesh or None for static batches)
+        let mut batch_indices: HashMap<(WasmFriendlyPPtr, WasmFriendlyPPtr, u16, Option<u32>), usize> = HashMap::new();
+        for (i, draw) in self.draws.iter().enumerate() {
+            let info = self.get_material_info(&draw.material);
+            if info.render_queue > RENDER_QUEUE_GEOMETRY_LAST {
+                transparent.push(FoundBatch::new(draw, info, vec![i]));
+                continue;
+            }
+            let key = (draw.mesh, draw.material, draw.lightmap_index, if draw.statically_batched { None } else { Some(draw.submesh) });
+            match batch_indices.get(&key) {
+                Some(&index) => opaque[index].draws.push(i),
+                None => {
+                    batch_indices.insert(key, opaque.len());
+                    opaque.push(FoundBatch::new(draw, info, vec![i]));
+                },
+            }
+        }
+
+        let mut batches = Vec::new();
+        opaque.sort_by_key(|batch| (
+            batch.shader.file_index, batch.shader.path_id,
+            batch.material.file_index, batch.material.path_id,
+            batch.mesh.file_index, batch.mesh.path_id,
+        ));
+        for batch in opaque {
+            if self.draws[batch.draws[0]].statically_batched {
+                batches.extend(self.merge_submesh_ranges(&batch));
+            } else {
+                batches.push(self.to_draw_batch(&batch, &batch.draws, batch.draws.len() > 1));
+            }
+        }
+        batches.extend(transparent.iter().map(|batch| self.to_draw_batch(batch, &batch.draws, false)));
+
+        DrawBatchPlan {
+            renderer_count: self.renderer_count,
+            unbatched_draw_count: self.draws.len() as u32,
+            batches,
+        }
+    }
+}
+
+struct FoundBatch {
+    shader: WasmFriendlyPPtr,
+    material: WasmFriendlyPPtr,
+    mesh: WasmFriendlyPPtr,
+    lightmap_index: u16,
+    // indices into DrawBatchPlanner.draws
+    draws: Vec<usize>,
+}
+
+impl FoundBatch {
+    fn new(draw: &PlannedDraw, info: MaterialInfo, draws: Vec<usize>) -> Self {
+        Self {
+            shader: info.shader,
+            material: draw.material,
+            mesh: draw.mesh,
+            lightmap_index: draw.lightmap_index,
+            draws,
+        }
+    }
+}
+
+impl DrawBatchPlanner {
+    fn get_material_info(&self, material: &WasmFriendlyPPtr) -> MaterialInfo {
+        self.materials.get(material).copied().unwrap_or(MaterialInfo {
+            shader: WasmFriendlyPPtr { file_index: 0, path_id: 0 },
+            render_queue: RENDER_QUEUE_GEOMETRY,
+        })
+    }
+
+    // Splits a static batch's draws into runs of consecutive submeshes
+    fn merge_submesh_ranges(&self, batch: &FoundBatch) -> Vec<DrawBatch> {
+        let mut draws = batch.draws.clone();
+        draws.sort_by_key(|&i| self.draws[i].submesh);
+        draws.dedup_by_key(|i| self.draws[*i].submesh);
+        let mut result = Vec::new();
+        let mut run_start = 0;
+        for i in 1..=draws.len() {
+            let ends_run = i == draws.len() || self.draws[draws[i]].submesh != self.draws[draws[i - 1]].submesh + 1;
+            if ends_run {
+                result.push(self.to_draw_batch(batch, &draws[run_start..i], false));
+                run_start = i;
+            }
+        }
+        result
+    }
+
+    fn to_draw_batch(&self, batch: &FoundBatch, draws: &[usize], instanced: bool) -> DrawBatch {
+        let first_submesh = draws.iter().map(|&i| self.draws[i].submesh).min().unwrap_or(0);
+        let last_submesh = draws.iter().map(|&i| self.draws[i].submesh).max().unwrap_or(0);
+        DrawBatch {
+            shader: batch.shader.into(),
+            material: batch.material.into(),
+            mesh: batch.mesh.into(),
+            first_submesh,
+            submesh_count: if instanced { 1 } else { last_submesh - first_submesh + 1 },
+            lightmap_index: batch.lightmap_index,
+            instanced,
+            game_objects: draws.iter().map(|&i| self.draws[i].game_object.into()).collect(),
+        }
+    }
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct DrawBatch {
+    pub shader: PPtrInfo,
+    pub material: PPtrInfo,
+    pub mesh: PPtrInfo,
+    // draw the index ranges of these submeshes as one
+    pub first_submesh: u32,
+    pub submesh_count: u32,
+    // 0xFFFF when not lightmapped
+    pub lightmap_index: u16,
+    // once per game object, rather than the game objects sharing one draw
+    pub instanced: bool,
+    pub game_objects: Vec<PPtrInfo>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct DrawBatchPlan {
+    pub renderer_count: u32,
+    // draws needed without batching, i.e. one per renderer per material
+    pub unbatched_draw_count: u32,
+    pub batches: Vec<DrawBatch>,
+}
@@ -0,0 +1,246 @@
+use deku::prelude::*;
+use wasm_bindgen::prelude::*;
+
+use crate::compression::{inflate_decompress, lz4_decompress, lzma_decompress, InflateFormat};
+use crate::unity::segmented::SegmentedBuffer;
+use crate::unity::types::common::NullTerminatedAsciiString;
+
+// UnityFS asset bundles, which wrap a scene's or an AssetBundle's serialized
+// files (and their .resS/.resource streaming data) in LZ4- or LZMA-compressed
+// blocks. The block and directory info is its own compressed blob, either
+// right after the header or at the very end of the file.
+
+const FLAG_COMPRESSION_MASK: u32 = 0x3f;
+const FLAG_BLOCKS_INFO_AT_END: u32 = 0x80;
+// 2019.4 and up
+const FLAG_BLOCKS_INFO_NEEDS_PADDING: u32 = 0x200;
+
+const NODE_FLAG_SERIALIZED_FILE: u32 = 0x4;
+
+const COMPRESSION_NONE: u32 = 0;
+const COMPRESSION_LZMA: u32 = 1;
+const COMPRESSION_LZ4: u32 = 2;
+const COMPRESSION_LZ4HC: u32 = 3;
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(magic = b"UnityFS\0")]
+pub struct BundleHeader {
+    #[deku(endian = "big")]
+    pub format_version: u32,
+    pub unity_version: NullTerminatedAsciiString,
+    pub unity_revision: NullTerminatedAsciiString,
+    #[deku(endian = "big")]
+    pub size: i64,
+    #[deku(endian = "big")]
+    pub compressed_blocks_info_size: u32,
+    #[deku(endian = "big")]
+    pub uncompressed_blocks_info_size: u32,
+    #[deku(endian = "big")]
+    pub flags: u32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct BundleBlocksInfo {
+    pub uncompressed_data_hash: [u8; 16],
+    #[deku(endian = "big")]
+    block_count: i32,
+    #[deku(count = "*block_count")]
+    pub blocks: Vec<BundleBlock>,
+    #[deku(endian = "big")]
+    node_count: i32,
+    #[deku(count = "*node_count")]
+    pub nodes: Vec<BundleNode>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct BundleBlock {
+    #[deku(endian = "big")]
+    pub uncompressed_size: u32,
+    #[deku(endian = "big")]
+    pub compressed_size: u32,
+    #[deku(endian = "big")]
+    pub flags: u16,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct BundleNode {
+    #[deku(endian = "big")]
+    pub offset: i64,
+    #[deku(endian = "big")]
+    pub size: i64,
+    #[deku(endian = "big")]
+    pub flags: u32,
+    pub path: NullTerminatedAsciiString,
+}
+
+fn decompress(data: &[u8], flags: u32, uncompressed_size: usize) -> Result<Vec<u8>, String> {
+    match flags & FLAG_COMPRESSION_MASK {
+        COMPRESSION_NONE => Ok(data.to_vec()),
+        COMPRESSION_LZMA => {
+            // 5 bytes of properties: lc/lp/pb packed into one byte, then
+            // the dictionary size
+            if data.len() < 5 {
+                return Err("LZMA block is too short".to_string());
+            }
+            let mut props = data[0] as u32;
+            let lc = props % 9;
+            props /= 9;
+            let lp = props % 5;
+            let pb = props / 5;
+            let dict_size = u32::from_le_bytes([data[1], data[2], data[3], data[4]]);
+            lzma_decompress(&data[5..], lc, lp, pb, dict_size, uncompressed_size as u64)
+        },
+        COMPRESSION_LZ4 | COMPRESSION_LZ4HC => lz4_decompress(data, uncompressed_size),
+        v => Err(format!("unsupported bundle compression type {}", v)),
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityBundleFile")]
+pub struct BundleFile {
+    header: BundleHeader,
+    nodes: Vec<BundleNode>,
+    // every block, decompressed, as one segment each
+    data: SegmentedBuffer,
+}
+
+#[wasm_bindgen(js_class = "UnityBundleFile")]
+impl BundleFile {
+    pub fn new(data: &[u8]) -> Result<BundleFile, String> {
+        // WebGL builds can gzip whole bundles (.unityweb), for servers that
+        // won't send them with a Content-Encoding
+        if data.starts_with(&[0x1F, 0x8B]) {
+            let data = inflate_decompress(data, InflateFormat::Gzip)?;
+            return BundleFile::new(&data);
+        }
+        let ((rest, _), header) = BundleHeader::from_bytes((data, 0))
+            .map_err(|err| format!("failed to parse bundle header: {:?}", err))?;
+        let mut offset = data.len() - rest.len();
+        if header.format_version >= 7 {
+            offset = (offset + 15) & !15;
+        }
+
+        let compressed_size = header.compressed_blocks_info_size as usize;
+        let blocks_info_start = if header.flags & FLAG_BLOCKS_INFO_AT_END != 0 {
+            data.len().checked_sub(compressed_size)
+                .ok_or("blocks info is larger than the bundle".to_string())?
+        } else {
+            offset
+        };
+        let blocks_info_data = data.get(blocks_info_start..blocks_info_start + compressed_size)
+            .ok_or("blocks info is out of bounds".to_string())?;
+        let blocks_info_data = decompress(blocks_info_data, header.flags, header.uncompressed_blocks_info_size as usize)?;
+        let (_, blocks_info) = BundleBlocksInfo::from_bytes((&blocks_info_data, 0))
+            .map_err(|err| format!("failed to parse bundle blocks info: {:?}", err))?;
+
+        if header.flags & FLAG_BLOCKS_INFO_AT_END == 0 {
+            offset += compressed_size;
+        }
+        if header.flags & FLAG_BLOCKS_INFO_NEEDS_PADDING != 0 {
+            offset = (offset + 15) & !15;
+        }
+
+        let mut uncompressed = SegmentedBuffer::new();
+        for (i, block) in blocks_info.blocks.iter().enumerate() {
+            let end = offset + block.compressed_size as usize;
+            let block_data = data.get(offset..end)
+                .ok_or(format!("block {} ({}..{}) is out of bounds", i, offset, end))?;
+            uncompressed.push_segment(decompress(block_data, block.flags as u32, block.uncompressed_size as usize)?);
+            offset = end;
+        }
+
+        Ok(BundleFile {
+            header,
+            nodes: blocks_info.nodes,
+            data: uncompressed,
+        })
+    }
+
+    // unity_version is just the format family (e.g. "5.x.x"); the revision
+    // is the actual engine version
+    pub fn get_version_string(&self) -> String {
+        (&self.header.unity_revision).into()
+    }
+
+    pub fn get_file_names(&self) -> Vec<String> {
+        self.nodes.iter().map(|node| (&node.path).into()).collect()
+    }
+
+    // Just the serialized files, leaving out .resS/.resource streaming data.
+    // Scene bundles (e.g. from Addressables) have a "BuildPlayer-<scene>"
+    // file per scene, plus a "BuildPlayer-<scene>.sharedAssets" for the
+    // assets only it uses.
+    pub fn get_serialized_file_names(&self) -> Vec<String> {
+        self.nodes.iter()
+            .filter(|node| node.flags & NODE_FLAG_SERIALIZED_FILE != 0)
+            .map(|node| (&node.path).into())
+            .collect()
+    }
+
+    pub fn get_scene_file_names(&self) -> Vec<String> {
+        self.get_serialized_file_names().into_iter()
+            .filter(|name| name.starts_with("BuildPlayer-") && !name.ends_with(".sharedAssets"))
+            .collect()
+    }
+
+    // Also accepts streaming info paths, like "archive:/CAB-1234/CAB-1234.resS"
+    pub fn get_file(&self, path: &str) -> Option<Vec<u8>> {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let node = self.nodes.iter().find(|node| String::from(&node.path) == name)?;
+        let start = node.offset as usize;
+        self.data.get_range(start, node.size as usize).map(|data| data.into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unity::types::common::{set_parse_limits, ParseLimits};
+
+    #[test]
+    fn test_uncompressed_bundle() {
+        let file_data = b"hello";
+        let mut blocks_info = vec![0; 16];
+        blocks_info.extend(1i32.to_be_bytes());
+        blocks_info.extend((file_data.len() as u32).to_be_bytes());
+        blocks_info.extend((file_data.len() as u32).to_be_bytes());
+        blocks_info.extend(0u16.to_be_bytes());
+        blocks_info.extend(1i32.to_be_bytes());
+        blocks_info.extend(0i64.to_be_bytes());
+        blocks_info.extend((file_data.len() as i64).to_be_bytes());
+        blocks_info.extend(4u32.to_be_bytes());
+        blocks_info.extend(b"CAB-test\0");
+
+        let mut data = b"UnityFS\0".to_vec();
+        data.extend(6u32.to_be_bytes());
+        data.extend(b"5.x.x\0");
+        data.extend(b"2017.3.0f3\0");
+        data.extend(0i64.to_be_bytes());
+        data.extend((blocks_info.len() as u32).to_be_bytes());
+        data.extend((blocks_info.len() as u32).to_be_bytes());
+        data.extend(0u32.to_be_bytes());
+        data.extend(&blocks_info);
+        data.extend(file_data);
+
+        let bundle = BundleFile::new(&data).unwrap();
+        assert_eq!(bundle.get_version_string(), "2017.3.0f3");
+        assert_eq!(bundle.get_file_names(), vec!["CAB-test".to_string()]);
+        assert_eq!(bundle.get_serialized_file_names(), vec!["CAB-test".to_string()]);
+        assert!(bundle.get_scene_file_names().is_empty());
+        assert_eq!(bundle.get_file("archive:/CAB-test/CAB-test").unwrap(), file_data.to_vec());
+        assert!(bundle.get_file("CAB-missing").is_none());
+    }
+
+    #[test]
+    fn test_decompress_errors() {
+        // a literal run of 4 bytes, claiming to be more
+        let lz4 = [0x40, b't', b'e', b's', b't'];
+        assert_eq!(decompress(&lz4, COMPRESSION_LZ4, 4).unwrap(), b"test");
+        assert!(decompress(&lz4, COMPRESSION_LZ4, 8).is_err());
+        assert!(decompress(&[0x5D, 0, 0, 1, 0], COMPRESSION_LZMA, 16).is_err());
+
+        set_parse_limits(Some(ParseLimits::hardened()));
+        let result = decompress(&lz4, COMPRESSION_LZ4, 1 << 30);
+        set_parse_limits(None);
+        assert_eq!(result.unwrap_err(), "decompressed size 1073741824 is over the allocation budget of 536870912");
+    }
+}
@@ -0,0 +1,162 @@
+use wasm_bindgen::prelude::*;
+
+use crate::unity::types::wasm::Keyframe;
+
+// Evaluating AnimationCurves the way Unity does: cubic Hermite segments
+// between keyframes, which become Bezier segments when a keyframe's tangents
+// are weighted, and stepped when a tangent is infinite. Outside the first and
+// last keyframes the curve wraps according to its pre- and post-infinity
+// modes.
+
+// How a curve continues before its first keyframe or after its last, with
+// the values Unity serializes for m_PreInfinity and m_PostInfinity
+#[wasm_bindgen(js_name = "UnityCurveWrapMode")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CurveWrapMode {
+    PingPong = 0,
+    Repeat = 1,
+    Clamp = 2,
+}
+
+impl CurveWrapMode {
+    pub fn from_raw(value: i32) -> Self {
+        match value {
+            0 => CurveWrapMode::PingPong,
+            1 => CurveWrapMode::Repeat,
+            _ => CurveWrapMode::Clamp,
+        }
+    }
+}
+
+const WEIGHTED_IN: i32 = 1;
+const WEIGHTED_OUT: i32 = 2;
+// the weight that makes a Bezier segment the same as a Hermite one
+const DEFAULT_WEIGHT: f32 = 1.0 / 3.0;
+
+pub fn evaluate(keyframes: &[Keyframe], pre_infinity: CurveWrapMode, post_infinity: CurveWrapMode, time: f32) -> f32 {
+    let (Some(first), Some(last)) = (keyframes.first(), keyframes.last()) else {
+        return 0.0;
+    };
+    let duration = last.time - first.time;
+    let time = if time < first.time {
+        wrap_time(time, first.time, duration, pre_infinity)
+    } else if time > last.time {
+        wrap_time(time, first.time, duration, post_infinity)
+    } else {
+        time
+    };
+    if time <= first.time {
+        return first.value;
+    }
+    if time >= last.time {
+        return last.value;
+    }
+    let i = keyframes.iter().position(|k| k.time > time).unwrap();
+    evaluate_segment(&keyframes[i - 1], &keyframes[i], time)
+}
+
+fn wrap_time(time: f32, start: f32, duration: f32, mode: CurveWrapMode) -> f32 {
+    if duration <= 0.0 {
+        return start;
+    }
+    match mode {
+        CurveWrapMode::Clamp => time.clamp(start, start + duration),
+        CurveWrapMode::Repeat => start + (time - start).rem_euclid(duration),
+        CurveWrapMode::PingPong => {
+            let t = (time - start).rem_euclid(2.0 * duration);
+            start + if t > duration { 2.0 * duration - t } else { t }
+        },
+    }
+}
+
+fn evaluate_segment(k0: &Keyframe, k1: &Keyframe, time: f32) -> f32 {
+    let dt = k1.time - k0.time;
+    // infinite tangents mean the curve is stepped
+    if dt <= 0.0 || !k0.out_slope.is_finite() || !k1.in_slope.is_finite() {
+        return k0.value;
+    }
+    let t = (time - k0.time) / dt;
+    let m0 = k0.out_slope * dt;
+    let m1 = k1.in_slope * dt;
+
+    let out_weighted = k0.weighted_mode & WEIGHTED_OUT != 0;
+    let in_weighted = k1.weighted_mode & WEIGHTED_IN != 0;
+    if out_weighted || in_weighted {
+        let w0 = if out_weighted { k0.out_weight.clamp(0.0, 1.0) } else { DEFAULT_WEIGHT };
+        let w1 = if in_weighted { k1.in_weight.clamp(0.0, 1.0) } else { DEFAULT_WEIGHT };
+        return evaluate_weighted(k0.value, m0, w0, k1.value, m1, w1, t);
+    }
+
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (2.0 * t3 - 3.0 * t2 + 1.0) * k0.value
+        + (t3 - 2.0 * t2 + t) * m0
+        + (-2.0 * t3 + 3.0 * t2) * k1.value
+        + (t3 - t2) * m1
+}
+
+fn bezier(p0: f32, p1: f32, p2: f32, p3: f32, u: f32) -> f32 {
+    let v = 1.0 - u;
+    v * v * v * p0 + 3.0 * v * v * u * p1 + 3.0 * v * u * u * p2 + u * u * u * p3
+}
+
+// A Bezier segment over normalized time, whose control points are the
+// weights' fractions of the way along each tangent. Time isn't linear in the
+// Bezier parameter then, so that's found first, by bisection (the weights
+// being clamped to 0..1 keeps time monotonic).
+fn evaluate_weighted(v0: f32, m0: f32, w0: f32, v1: f32, m1: f32, w1: f32, t: f32) -> f32 {
+    let (mut lo, mut hi) = (0.0, 1.0);
+    for _ in 0..24 {
+        let mid = (lo + hi) * 0.5;
+        if bezier(0.0, w0, 1.0 - w1, 1.0, mid) < t {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    let u = (lo + hi) * 0.5;
+    bezier(v0, v0 + w0 * m0, v1 - w1 * m1, v1, u)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(time: f32, value: f32, slope: f32) -> Keyframe {
+        Keyframe {
+            time,
+            value,
+            in_slope: slope,
+            out_slope: slope,
+            weighted_mode: 0,
+            in_weight: DEFAULT_WEIGHT,
+            out_weight: DEFAULT_WEIGHT,
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let keys = [key(0.0, 0.0, 1.0), key(1.0, 1.0, 1.0)];
+        let clamp = CurveWrapMode::Clamp;
+        assert!((evaluate(&keys, clamp, clamp, 0.25) - 0.25).abs() < 1e-6);
+        assert_eq!(evaluate(&keys, clamp, clamp, 2.0), 1.0);
+        assert!((evaluate(&keys, clamp, CurveWrapMode::Repeat, 1.25) - 0.25).abs() < 1e-6);
+        assert!((evaluate(&keys, CurveWrapMode::PingPong, clamp, -0.25) - 0.25).abs() < 1e-6);
+        assert!((evaluate(&keys, clamp, CurveWrapMode::PingPong, 1.75) - 0.25).abs() < 1e-6);
+
+        // weights of a third match the unweighted curve
+        let mut weighted = keys;
+        weighted[0].weighted_mode = WEIGHTED_OUT;
+        weighted[1].weighted_mode = WEIGHTED_IN;
+        weighted[1].in_slope = 0.0;
+        let mut unweighted = keys;
+        unweighted[1].in_slope = 0.0;
+        for &t in &[0.1, 0.5, 0.9] {
+            assert!((evaluate(&weighted, clamp, clamp, t) - evaluate(&unweighted, clamp, clamp, t)).abs() < 1e-4);
+        }
+
+        let mut stepped = keys;
+        stepped[0].out_slope = f32::INFINITY;
+        assert_eq!(evaluate(&stepped, clamp, clamp, 0.9), 0.0);
+    }
+}
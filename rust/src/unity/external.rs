@@ -0,0 +1,68 @@
+use wasm_bindgen::prelude::*;
+
+// Externals name their files inconsistently depending on the Unity version,
+// platform and whether they came from a bundle, e.g.
+//   "archive:/CAB-0123abcd/CAB-0123abcd"
+//   "Library/unity default resources"
+//   "resources/unity_builtin_extra"
+//   "C:\Build\Game_Data\sharedassets0.assets"
+// Normalizing both sides before comparing lets us match them against the
+// files we actually have.
+
+// Lowercases, uses forward slashes, reduces bundle paths to the bare
+// CAB name, and maps Library/ to Resources/ where the built-in resources
+// moved to
+#[wasm_bindgen(js_name = "unity_normalize_external_path")]
+pub fn normalize_external_path(path: &str) -> String {
+    let path = path.replace('\\', "/").to_lowercase();
+    if let Some(archive_path) = path.strip_prefix("archive:/") {
+        return archive_path.rsplit('/').next().unwrap_or(archive_path).to_string();
+    }
+    let path = path.trim_start_matches("./");
+    match path.strip_prefix("library/") {
+        Some(rest) => format!("resources/{}", rest),
+        None => path.to_string(),
+    }
+}
+
+// Whether an external's path refers to the given file. If either side is a
+// bare file name, only the file names are compared, since players store
+// some externals with absolute build machine paths.
+#[wasm_bindgen(js_name = "unity_external_path_matches")]
+pub fn external_path_matches(external_path: &str, file_path: &str) -> bool {
+    let external_path = normalize_external_path(external_path);
+    let file_path = normalize_external_path(file_path);
+    if external_path == file_path {
+        return true;
+    }
+    if !external_path.contains('/') || !file_path.contains('/') {
+        let file_name = |path: &str| path.rsplit('/').next().unwrap_or("").to_string();
+        return file_name(&external_path) == file_name(&file_path);
+    }
+    external_path.ends_with(&format!("/{}", file_path)) || file_path.ends_with(&format!("/{}", external_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unity::types::serialized_file::{Guid, BUILTIN_EXTRA_GUID};
+
+    #[test]
+    fn test_external_paths() {
+        assert_eq!(normalize_external_path("archive:/CAB-0123abcd/CAB-0123abcd"), "cab-0123abcd");
+        assert_eq!(normalize_external_path("Library/unity default resources"), "resources/unity default resources");
+        assert!(external_path_matches("Library/unity default resources", "Resources/unity default resources"));
+        assert!(external_path_matches("resources/unity_builtin_extra", "Resources/unity_builtin_extra"));
+        assert!(external_path_matches("C:\\Build\\Game_Data\\sharedassets0.assets", "sharedassets0.assets"));
+        assert!(external_path_matches("archive:/CAB-0123abcd/CAB-0123abcd", "CAB-0123ABCD"));
+        assert!(!external_path_matches("sharedassets1.assets", "sharedassets0.assets"));
+    }
+
+    #[test]
+    fn test_guid_hex() {
+        let guid = Guid::from_hex_string("0000000000000000f000000000000000").unwrap();
+        assert_eq!(guid, BUILTIN_EXTRA_GUID);
+        assert_eq!(guid.to_hex_string(), "0000000000000000f000000000000000");
+        assert!(Guid::from_hex_string("not a guid").is_none());
+    }
+}
@@ -0,0 +1,100 @@
+use std::convert::TryInto;
+
+use wasm_bindgen::prelude::*;
+
+// Sniffs what sort of Unity file a buffer is, so dropped or extracted files
+// can be routed to the right loader without trusting their names
+
+#[wasm_bindgen(js_name = "UnityFileKind")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileKind {
+    // UnityFS asset bundle
+    Bundle,
+    // a level, sharedassets, resources.assets, CAB-..., etc.
+    SerializedFile,
+    // WebGL builds' .data files, which concatenate the whole data folder
+    WebData,
+    // .resS and .resource files, which are raw texture, mesh and audio data
+    StreamingData,
+    Unknown,
+}
+
+const BUNDLE_MAGIC: &[u8] = b"UnityFS\0";
+const WEB_DATA_MAGIC: &[u8] = b"UnityWebData1.0\0";
+
+fn read_u32_be(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as u64)
+}
+
+fn read_u64_be(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+// Serialized files have no magic, so check that the header's sizes are
+// consistent with the buffer instead
+fn is_serialized_file(data: &[u8]) -> bool {
+    let Some(version) = read_u32_be(data, 8) else {
+        return false;
+    };
+    if !(9..=30).contains(&version) {
+        return false;
+    }
+    // from v22, the sizes moved after the endianness and became 64 bits
+    let sizes = if version >= 22 {
+        read_u32_be(data, 20).zip(read_u64_be(data, 24)).zip(read_u64_be(data, 32))
+    } else {
+        read_u32_be(data, 0).zip(read_u32_be(data, 4)).zip(read_u32_be(data, 12))
+    };
+    let Some(((metadata_size, file_size), data_offset)) = sizes else {
+        return false;
+    };
+    file_size == data.len() as u64 && metadata_size < file_size && data_offset <= file_size
+}
+
+#[wasm_bindgen(js_name = "unity_detect_file_kind")]
+pub fn detect(data: &[u8]) -> FileKind {
+    if data.starts_with(BUNDLE_MAGIC) {
+        FileKind::Bundle
+    } else if data.starts_with(WEB_DATA_MAGIC) {
+        FileKind::WebData
+    } else if is_serialized_file(data) {
+        FileKind::SerializedFile
+    } else {
+        FileKind::Unknown
+    }
+}
+
+// Like detect, but falls back to the file name for streaming data, which is
+// headerless
+#[wasm_bindgen(js_name = "unity_detect_file_kind_with_name")]
+pub fn detect_with_name(file_name: &str, data: &[u8]) -> FileKind {
+    match detect(data) {
+        FileKind::Unknown if file_name.ends_with(".resS") || file_name.ends_with(".resource") => FileKind::StreamingData,
+        kind => kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect(b"UnityFS\0\0\0\0\x08"), FileKind::Bundle);
+        assert_eq!(detect(b"UnityWebData1.0\0\0\0\0\0"), FileKind::WebData);
+
+        // v17 header: metadata size, file size, version, data offset
+        let mut data = Vec::new();
+        data.extend(8u32.to_be_bytes());
+        data.extend(64u32.to_be_bytes());
+        data.extend(17u32.to_be_bytes());
+        data.extend(32u32.to_be_bytes());
+        data.resize(64, 0);
+        assert_eq!(detect(&data), FileKind::SerializedFile);
+        data.push(0);
+        assert_eq!(detect(&data), FileKind::Unknown);
+
+        assert_eq!(detect_with_name("sharedassets0.assets.resS", &[0; 16]), FileKind::StreamingData);
+        assert_eq!(detect_with_name("notes.txt", &[0; 16]), FileKind::Unknown);
+    }
+}
@@ -0,0 +1,116 @@
+use serde::Serialize;
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use crate::unity::asset_file::AssetFile;
+use crate::unity::types::class_id::ClassID;
+use crate::unity::types::wasm::BuildSettings;
+
+// Finds the scenes and shared asset files in a player build's data folder
+// (e.g. "GettingOverIt_Data"), for games that don't use asset bundles and
+// instead keep everything in levelN, sharedassetsN.assets and
+// resources.assets. The scene list comes from BuildSettings in
+// globalgamemanagers (or mainData before 5.x).
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct GameDescription {
+    pub unity_version: String,
+    pub scenes: Vec<GameScene>,
+    // files any scene may reference, e.g. resources.assets
+    pub shared_files: Vec<String>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct GameScene {
+    // e.g. "Assets/Scenes/Mountain.unity"
+    pub path: String,
+    // e.g. "Mountain"
+    pub name: String,
+    // e.g. "level1"
+    pub level_file: String,
+    // e.g. "sharedassets1.assets", if the build has one for this scene
+    pub shared_assets_file: Option<String>,
+}
+
+const SHARED_FILES: [&str; 4] = [
+    "globalgamemanagers.assets",
+    "resources.assets",
+    "Resources/unity_builtin_extra",
+    "Resources/unity default resources",
+];
+
+fn scene_name(path: &str) -> String {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    file_name.strip_suffix(".unity").unwrap_or(file_name).to_string()
+}
+
+// Pairs each scene in build order with its level file, skipping any the
+// listing doesn't have (e.g. scenes stripped from a demo build)
+fn describe_scenes(scene_paths: &[String], dir_listing: &[String]) -> Vec<GameScene> {
+    let has_file = |name: &str| dir_listing.iter().any(|file| file == name);
+    scene_paths.iter().enumerate()
+        .filter_map(|(i, path)| {
+            let level_file = format!("level{}", i);
+            if !has_file(&level_file) {
+                return None;
+            }
+            let shared_assets_file = format!("sharedassets{}.assets", i);
+            Some(GameScene {
+                path: path.clone(),
+                name: scene_name(path),
+                level_file,
+                shared_assets_file: has_file(&shared_assets_file).then_some(shared_assets_file),
+            })
+        })
+        .collect()
+}
+
+// dir_listing holds paths relative to the data folder, using forward slashes
+#[wasm_bindgen(js_name = "unity_load_game")]
+pub fn load_game(dir_listing: Vec<String>, global_game_managers: &[u8]) -> Result<GameDescription, String> {
+    let mut asset_file = AssetFile::initialize_with_header_chunk(global_game_managers)?;
+    asset_file.append_metadata_chunk(global_game_managers)?;
+    let unity_version = asset_file.get_version_string();
+    let version = asset_file.get_unity_version()
+        .ok_or_else(|| format!("unsupported Unity version {}", unity_version))?;
+
+    let obj = asset_file.get_objects().into_iter()
+        .find(|obj| obj.class_id == ClassID::BuildSettings)
+        .ok_or("no BuildSettings found")?;
+    let start = obj.byte_start as usize;
+    let data = global_game_managers.get(start..start + obj.byte_size)
+        .ok_or("BuildSettings is out of bounds")?;
+    let build_settings = BuildSettings::create(version, data)?;
+
+    let shared_files = SHARED_FILES.iter()
+        .filter(|name| dir_listing.iter().any(|file| file == *name))
+        .map(|name| name.to_string())
+        .collect();
+
+    Ok(GameDescription {
+        unity_version,
+        scenes: describe_scenes(&build_settings.scenes, &dir_listing),
+        shared_files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_scenes() {
+        let scene_paths: Vec<String> = ["Assets/Loader.unity", "Assets/Scenes/Mountain.unity", "Assets/Credits.unity"]
+            .iter().map(|s| s.to_string()).collect();
+        let dir_listing: Vec<String> = ["globalgamemanagers", "level0", "level1", "sharedassets1.assets", "resources.assets"]
+            .iter().map(|s| s.to_string()).collect();
+        let scenes = describe_scenes(&scene_paths, &dir_listing);
+        assert_eq!(scenes.len(), 2);
+        assert_eq!(scenes[0].name, "Loader");
+        assert_eq!(scenes[0].shared_assets_file, None);
+        assert_eq!(scenes[1].name, "Mountain");
+        assert_eq!(scenes[1].level_file, "level1");
+        assert_eq!(scenes[1].shared_assets_file.as_deref(), Some("sharedassets1.assets"));
+    }
+}
@@ -0,0 +1,323 @@
+use wasm_bindgen::prelude::*;
+
+// Builds a binary glTF (.glb) out of scene geometry so users can download it.
+// Everything passed in is in Unity's left-handed space with V pointing down
+// the texture; we mirror X (flipping winding and rotations to match) and flip
+// V on the way out, since glTF is right-handed.
+
+const GLB_MAGIC: u32 = 0x46546C67;
+const GLB_CHUNK_JSON: u32 = 0x4E4F534A;
+const GLB_CHUNK_BIN: u32 = 0x004E4942;
+
+const COMPONENT_TYPE_FLOAT: u32 = 5126;
+const COMPONENT_TYPE_UNSIGNED_INT: u32 = 5125;
+const TARGET_ARRAY_BUFFER: u32 = 34962;
+const TARGET_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+fn json_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn json_f32(value: f32) -> String {
+    if value.is_finite() {
+        format!("{}", value)
+    } else {
+        "0".to_string()
+    }
+}
+
+fn json_f32_array(values: &[f32]) -> String {
+    let values: Vec<String> = values.iter().map(|&v| json_f32(v)).collect();
+    format!("[{}]", values.join(","))
+}
+
+struct Node {
+    name: String,
+    mesh: Option<u32>,
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    scale: [f32; 3],
+    children: Vec<u32>,
+    has_parent: bool,
+}
+
+#[wasm_bindgen(js_name = "UnityGlbExporter")]
+#[derive(Default)]
+pub struct GlbExporter {
+    bin: Vec<u8>,
+    buffer_views: Vec<String>,
+    accessors: Vec<String>,
+    images: Vec<String>,
+    textures: Vec<String>,
+    materials: Vec<String>,
+    meshes: Vec<(String, Vec<String>)>,
+    nodes: Vec<Node>,
+}
+
+#[wasm_bindgen(js_class = "UnityGlbExporter")]
+impl GlbExporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // png_data must already be PNG-encoded
+    pub fn add_texture(&mut self, name: String, png_data: &[u8]) -> u32 {
+        let buffer_view = self.add_buffer_view(png_data, None);
+        self.images.push(format!("{{\"name\":{},\"mimeType\":\"image/png\",\"bufferView\":{}}}", json_string(&name), buffer_view));
+        self.textures.push(format!("{{\"source\":{}}}", self.images.len() - 1));
+        self.textures.len() as u32 - 1
+    }
+
+    pub fn add_material(&mut self, name: String, base_color: &[f32], base_color_texture: Option<u32>, alpha_cutoff: Option<f32>) -> Result<u32, String> {
+        if base_color.len() != 4 {
+            return Err(format!("expected an RGBA base color, got {} components", base_color.len()));
+        }
+        let mut pbr = format!("\"baseColorFactor\":{},\"metallicFactor\":0", json_f32_array(base_color));
+        if let Some(texture) = base_color_texture {
+            if texture as usize >= self.textures.len() {
+                return Err(format!("invalid texture index {}", texture));
+            }
+            pbr.push_str(&format!(",\"baseColorTexture\":{{\"index\":{}}}", texture));
+        }
+        let mut material = format!("{{\"name\":{},\"pbrMetallicRoughness\":{{{}}}", json_string(&name), pbr);
+        if let Some(cutoff) = alpha_cutoff {
+            material.push_str(&format!(",\"alphaMode\":\"MASK\",\"alphaCutoff\":{}", json_f32(cutoff)));
+        }
+        material.push('}');
+        self.materials.push(material);
+        Ok(self.materials.len() as u32 - 1)
+    }
+
+    pub fn add_mesh(&mut self, name: String) -> u32 {
+        self.meshes.push((name, Vec::new()));
+        self.meshes.len() as u32 - 1
+    }
+
+    // Adds a triangle list (e.g. one submesh) to a mesh. normals and uvs are
+    // optional, but must have an entry per position if given.
+    pub fn add_primitive(&mut self, mesh: u32, positions: &[f32], normals: Option<Vec<f32>>, uvs: Option<Vec<f32>>, indices: &[u32], material: Option<u32>) -> Result<(), String> {
+        if mesh as usize >= self.meshes.len() {
+            return Err(format!("invalid mesh index {}", mesh));
+        }
+        if let Some(material) = material {
+            if material as usize >= self.materials.len() {
+                return Err(format!("invalid material index {}", material));
+            }
+        }
+        if !positions.len().is_multiple_of(3) || !indices.len().is_multiple_of(3) {
+            return Err("positions and indices must come in threes".to_string());
+        }
+        let vertex_count = positions.len() / 3;
+        if let Some(index) = indices.iter().find(|&&index| index as usize >= vertex_count) {
+            return Err(format!("index {} is out of range for {} vertices", index, vertex_count));
+        }
+
+        let positions = mirror_x(positions);
+        let mut attributes = vec![format!("\"POSITION\":{}", self.add_vec3_accessor(&positions, true))];
+        if let Some(normals) = normals {
+            if normals.len() != positions.len() {
+                return Err(format!("expected {} normal components, got {}", positions.len(), normals.len()));
+            }
+            attributes.push(format!("\"NORMAL\":{}", self.add_vec3_accessor(&mirror_x(&normals), false)));
+        }
+        if let Some(uvs) = uvs {
+            if uvs.len() != vertex_count * 2 {
+                return Err(format!("expected {} UV components, got {}", vertex_count * 2, uvs.len()));
+            }
+            let uvs: Vec<f32> = uvs.chunks_exact(2).flat_map(|uv| [uv[0], 1.0 - uv[1]]).collect();
+            attributes.push(format!("\"TEXCOORD_0\":{}", self.add_float_accessor(&uvs, "VEC2", None)));
+        }
+
+        // mirroring flips the winding, so flip it back
+        let indices: Vec<u32> = indices.chunks_exact(3).flat_map(|tri| [tri[0], tri[2], tri[1]]).collect();
+        let indices_accessor = self.add_index_accessor(&indices);
+
+        let mut primitive = format!("{{\"attributes\":{{{}}},\"indices\":{}", attributes.join(","), indices_accessor);
+        if let Some(material) = material {
+            primitive.push_str(&format!(",\"material\":{}", material));
+        }
+        primitive.push('}');
+        self.meshes[mesh as usize].1.push(primitive);
+        Ok(())
+    }
+
+    // translation, rotation (as a quaternion) and scale are local to parent
+    pub fn add_node(&mut self, name: String, mesh: Option<u32>, parent: Option<u32>, translation: &[f32], rotation: &[f32], scale: &[f32]) -> Result<u32, String> {
+        if let Some(mesh) = mesh {
+            if mesh as usize >= self.meshes.len() {
+                return Err(format!("invalid mesh index {}", mesh));
+            }
+        }
+        let (&[tx, ty, tz], &[rx, ry, rz, rw], &[sx, sy, sz]) = (translation, rotation, scale) else {
+            return Err("expected a 3-component translation and scale and a 4-component rotation".to_string());
+        };
+        let index = self.nodes.len() as u32;
+        if let Some(parent) = parent {
+            let parent_node = self.nodes.get_mut(parent as usize)
+                .ok_or(format!("invalid parent node index {}", parent))?;
+            parent_node.children.push(index);
+        }
+        self.nodes.push(Node {
+            name,
+            mesh,
+            translation: [-tx, ty, tz],
+            rotation: [rx, -ry, -rz, rw],
+            scale: [sx, sy, sz],
+            children: Vec::new(),
+            has_parent: parent.is_some(),
+        });
+        Ok(index)
+    }
+
+    pub fn finish(&self) -> Vec<u8> {
+        let json = self.build_json();
+        let mut json_chunk = json.into_bytes();
+        while !json_chunk.len().is_multiple_of(4) {
+            json_chunk.push(b' ');
+        }
+        let total_length = 12 + 8 + json_chunk.len() + 8 + self.bin.len();
+        let mut result = Vec::with_capacity(total_length);
+        result.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+        result.extend_from_slice(&2u32.to_le_bytes());
+        result.extend_from_slice(&(total_length as u32).to_le_bytes());
+        result.extend_from_slice(&(json_chunk.len() as u32).to_le_bytes());
+        result.extend_from_slice(&GLB_CHUNK_JSON.to_le_bytes());
+        result.extend_from_slice(&json_chunk);
+        result.extend_from_slice(&(self.bin.len() as u32).to_le_bytes());
+        result.extend_from_slice(&GLB_CHUNK_BIN.to_le_bytes());
+        result.extend_from_slice(&self.bin);
+        result
+    }
+}
+
+fn mirror_x(values: &[f32]) -> Vec<f32> {
+    values.chunks_exact(3).flat_map(|v| [-v[0], v[1], v[2]]).collect()
+}
+
+impl GlbExporter {
+    fn add_buffer_view(&mut self, data: &[u8], target: Option<u32>) -> usize {
+        let offset = self.bin.len();
+        self.bin.extend_from_slice(data);
+        while !self.bin.len().is_multiple_of(4) {
+            self.bin.push(0);
+        }
+        let mut buffer_view = format!("{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{}", offset, data.len());
+        if let Some(target) = target {
+            buffer_view.push_str(&format!(",\"target\":{}", target));
+        }
+        buffer_view.push('}');
+        self.buffer_views.push(buffer_view);
+        self.buffer_views.len() - 1
+    }
+
+    fn add_float_accessor(&mut self, values: &[f32], accessor_type: &str, bounds: Option<(Vec<f32>, Vec<f32>)>) -> usize {
+        let components = match accessor_type {
+            "VEC2" => 2,
+            "VEC3" => 3,
+            _ => 1,
+        };
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let buffer_view = self.add_buffer_view(&bytes, Some(TARGET_ARRAY_BUFFER));
+        let mut accessor = format!(
+            "{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"{}\"",
+            buffer_view, COMPONENT_TYPE_FLOAT, values.len() / components, accessor_type,
+        );
+        if let Some((min, max)) = bounds {
+            accessor.push_str(&format!(",\"min\":{},\"max\":{}", json_f32_array(&min), json_f32_array(&max)));
+        }
+        accessor.push('}');
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    // glTF requires bounds on positions
+    fn add_vec3_accessor(&mut self, values: &[f32], with_bounds: bool) -> usize {
+        let bounds = if with_bounds && !values.is_empty() {
+            let mut min = vec![f32::INFINITY; 3];
+            let mut max = vec![f32::NEG_INFINITY; 3];
+            for v in values.chunks_exact(3) {
+                for i in 0..3 {
+                    min[i] = min[i].min(v[i]);
+                    max[i] = max[i].max(v[i]);
+                }
+            }
+            Some((min, max))
+        } else {
+            None
+        };
+        self.add_float_accessor(values, "VEC3", bounds)
+    }
+
+    fn add_index_accessor(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let buffer_view = self.add_buffer_view(&bytes, Some(TARGET_ELEMENT_ARRAY_BUFFER));
+        self.accessors.push(format!(
+            "{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"SCALAR\"}}",
+            buffer_view, COMPONENT_TYPE_UNSIGNED_INT, indices.len(),
+        ));
+        self.accessors.len() - 1
+    }
+
+    fn build_json(&self) -> String {
+        let meshes: Vec<String> = self.meshes.iter()
+            .map(|(name, primitives)| format!("{{\"name\":{},\"primitives\":[{}]}}", json_string(name), primitives.join(",")))
+            .collect();
+        let nodes: Vec<String> = self.nodes.iter()
+            .map(|node| {
+                let mut result = format!(
+                    "{{\"name\":{},\"translation\":{},\"rotation\":{},\"scale\":{}",
+                    json_string(&node.name), json_f32_array(&node.translation), json_f32_array(&node.rotation), json_f32_array(&node.scale),
+                );
+                if let Some(mesh) = node.mesh {
+                    result.push_str(&format!(",\"mesh\":{}", mesh));
+                }
+                if !node.children.is_empty() {
+                    let children: Vec<String> = node.children.iter().map(|c| c.to_string()).collect();
+                    result.push_str(&format!(",\"children\":[{}]", children.join(",")));
+                }
+                result.push('}');
+                result
+            })
+            .collect();
+        let roots: Vec<String> = self.nodes.iter().enumerate()
+            .filter(|(_, node)| !node.has_parent)
+            .map(|(i, _)| i.to_string())
+            .collect();
+
+        let mut sections = vec![
+            "\"asset\":{\"version\":\"2.0\",\"generator\":\"noclip.website\"}".to_string(),
+            "\"scene\":0".to_string(),
+            format!("\"scenes\":[{{\"nodes\":[{}]}}]", roots.join(",")),
+            format!("\"nodes\":[{}]", nodes.join(",")),
+        ];
+        let arrays = [
+            ("meshes", &meshes),
+            ("materials", &self.materials),
+            ("textures", &self.textures),
+            ("images", &self.images),
+            ("accessors", &self.accessors),
+            ("bufferViews", &self.buffer_views),
+        ];
+        for (key, values) in arrays {
+            if !values.is_empty() {
+                sections.push(format!("\"{}\":[{}]", key, values.join(",")));
+            }
+        }
+        if !self.bin.is_empty() {
+            sections.push(format!("\"buffers\":[{{\"byteLength\":{}}}]", self.bin.len()));
+        }
+        format!("{{{}}}", sections.join(","))
+    }
+}
@@ -0,0 +1,231 @@
+use std::convert::TryFrom;
+
+use nalgebra_glm as glm;
+use serde::Serialize;
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use crate::unity::types::binary;
+use crate::unity::types::common::{Quaternion, Vec3, Vec4};
+use crate::unity::types::wasm::Avatar;
+
+// Humanoid AnimationClips don't animate transforms, but "muscles": one value
+// per degree of freedom of each human bone, from -1 to 1, plus the body's
+// position and rotation. Turning those back into transforms takes the
+// avatar's human axes, which hold each bone's limits (from the
+// HumanDescription) and the rotations between its transform and its muscle
+// space.
+
+pub const HUMAN_BONE_COUNT: usize = 55;
+pub const MUSCLE_COUNT: usize = 95;
+
+// The muscle driving each human bone's (twist, y, z) axes, or -1, like
+// HumanTrait.MuscleFromBone. Bones are in HumanBodyBones order.
+const BONE_MUSCLES: [[i8; 3]; HUMAN_BONE_COUNT] = [
+    [-1, -1, -1], // Hips
+    [23, 22, 21], [31, 30, 29], // upper legs
+    [25, -1, 24], [33, -1, 32], // lower legs
+    [-1, 27, 26], [-1, 35, 34], // feet
+    [2, 1, 0], // Spine
+    [5, 4, 3], // Chest
+    [11, 10, 9], // Neck
+    [14, 13, 12], // Head
+    [-1, 38, 37], [-1, 47, 46], // shoulders
+    [41, 40, 39], [50, 49, 48], // upper arms
+    [43, -1, 42], [52, -1, 51], // lower arms
+    [-1, 45, 44], [-1, 54, 53], // hands
+    [-1, -1, 28], [-1, -1, 36], // toes
+    [-1, 16, 15], [-1, 18, 17], // eyes
+    [-1, 20, 19], // Jaw
+    // left thumb, index, middle, ring and little fingers, proximal first
+    [-1, 56, 55], [-1, -1, 57], [-1, -1, 58],
+    [-1, 60, 59], [-1, -1, 61], [-1, -1, 62],
+    [-1, 64, 63], [-1, -1, 65], [-1, -1, 66],
+    [-1, 68, 67], [-1, -1, 69], [-1, -1, 70],
+    [-1, 72, 71], [-1, -1, 73], [-1, -1, 74],
+    // and the right hand's
+    [-1, 76, 75], [-1, -1, 77], [-1, -1, 78],
+    [-1, 80, 79], [-1, -1, 81], [-1, -1, 82],
+    [-1, 84, 83], [-1, -1, 85], [-1, -1, 86],
+    [-1, 88, 87], [-1, -1, 89], [-1, -1, 90],
+    [-1, 92, 91], [-1, -1, 93], [-1, -1, 94],
+    [8, 7, 6], // UpperChest
+];
+
+const HIPS: usize = 0;
+
+#[derive(Clone, Debug)]
+pub struct HumanBoneRig {
+    // index into the avatar's skeleton
+    pub node: usize,
+    pre_q: glm::Quat,
+    post_q: glm::Quat,
+    sgn: glm::Vec3,
+    // in radians
+    limit_min: glm::Vec3,
+    limit_max: glm::Vec3,
+}
+
+fn to_quat(v: &Vec4) -> glm::Quat {
+    glm::quat(v.x, v.y, v.z, v.w)
+}
+
+fn to_vec3(v: &Vec3) -> glm::Vec3 {
+    glm::vec3(v.x, v.y, v.z)
+}
+
+// Each human bone's rig, if the avatar maps it to a transform
+pub fn build_human_rig(constant: &binary::AvatarConstant) -> Vec<Option<HumanBoneRig>> {
+    let human = &constant.human;
+    human.human_bone_indices.values.iter()
+        .map(|&human_node| {
+            let human_node = usize::try_from(human_node).ok()?;
+            let node = *constant.human_skeleton_indices.values.get(human_node)?;
+            let axes_id = human.skeleton.nodes.values.get(human_node)?.axes_id;
+            let axes = human.skeleton.axes.values.get(usize::try_from(axes_id).ok()?)?;
+            Some(HumanBoneRig {
+                node: usize::try_from(node).ok()?,
+                pre_q: to_quat(&axes.pre_q),
+                post_q: to_quat(&axes.post_q),
+                sgn: to_vec3(&axes.sgn),
+                limit_min: to_vec3(&axes.limit_min),
+                limit_max: to_vec3(&axes.limit_max),
+            })
+        })
+        .collect()
+}
+
+impl HumanBoneRig {
+    // The bone's local rotation for the given muscle values (0 for axes
+    // without a muscle)
+    fn local_rotation(&self, muscles: glm::Vec3) -> glm::Quat {
+        let angles = glm::vec3(
+            muscle_angle(muscles.x, self.limit_min.x, self.limit_max.x),
+            muscle_angle(muscles.y, self.limit_min.y, self.limit_max.y),
+            muscle_angle(muscles.z, self.limit_min.z, self.limit_max.z),
+        ).component_mul(&self.sgn);
+        // a twist around the bone, then a swing of it towards y and z
+        let twist = glm::quat_angle_axis(angles.x, &glm::vec3(1.0, 0.0, 0.0));
+        let swing_angle = (angles.y * angles.y + angles.z * angles.z).sqrt();
+        let swing = if swing_angle > 0.0 {
+            glm::quat_angle_axis(swing_angle, &glm::vec3(0.0, angles.y, angles.z))
+        } else {
+            glm::quat_identity()
+        };
+        self.pre_q * swing * twist * glm::quat_inverse(&self.post_q)
+    }
+}
+
+// Muscles reach the lower limit at -1 and the upper at 1
+fn muscle_angle(muscle: f32, limit_min: f32, limit_max: f32) -> f32 {
+    if muscle >= 0.0 {
+        muscle * limit_max
+    } else {
+        -muscle * limit_min
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityAvatar")]
+impl Avatar {
+    pub fn is_human(&self) -> bool {
+        self.human_rig.len() == HUMAN_BONE_COUNT
+    }
+
+    // Poses the skeleton from a humanoid clip's body position and rotation
+    // (its RootT and RootQ curves) and muscle values (in HumanTrait order).
+    // Transforms not driven by a muscle keep their default pose. The body
+    // position is used as the hips' position, which ignores the difference
+    // between the hips and the body's center of mass.
+    pub fn compute_human_pose(&self, body_position: &Vec3, body_rotation: &Quaternion, muscles: &[f32]) -> Result<HumanPose, String> {
+        if !self.is_human() {
+            return Err(format!("avatar {} isn't humanoid", self.name));
+        }
+        if muscles.len() != MUSCLE_COUNT {
+            return Err(format!("expected {} muscle values, got {}", MUSCLE_COUNT, muscles.len()));
+        }
+
+        let mut pose = HumanPose {
+            translations: Vec::with_capacity(self.default_pose.len() * 3),
+            rotations: Vec::with_capacity(self.default_pose.len() * 4),
+        };
+        for x in &self.default_pose {
+            pose.translations.extend([x.t.x, x.t.y, x.t.z]);
+            pose.rotations.extend([x.q.x, x.q.y, x.q.z, x.q.w]);
+        }
+
+        for (bone, rig) in self.human_rig.iter().enumerate() {
+            let Some(rig) = rig else {
+                continue;
+            };
+            if rig.node >= self.default_pose.len() {
+                continue;
+            }
+            let rotation = if bone == HIPS {
+                let body = glm::quat(body_rotation.x, body_rotation.y, body_rotation.z, body_rotation.w);
+                let t = to_vec3(body_position) * self.human_scale;
+                pose.translations[rig.node * 3..rig.node * 3 + 3].copy_from_slice(t.as_slice());
+                body * glm::quat_inverse(&rig.post_q)
+            } else {
+                let axis_muscle = |axis: usize| match BONE_MUSCLES[bone][axis] {
+                    -1 => 0.0,
+                    i => muscles[i as usize],
+                };
+                rig.local_rotation(glm::vec3(axis_muscle(0), axis_muscle(1), axis_muscle(2)))
+            };
+            pose.rotations[rig.node * 4..rig.node * 4 + 4].copy_from_slice(rotation.coords.as_slice());
+        }
+        Ok(pose)
+    }
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct HumanPose {
+    // local (x, y, z) per avatar skeleton node, as in get_skeleton_node_paths
+    pub translations: Vec<f32>,
+    // local (x, y, z, w) per avatar skeleton node
+    pub rotations: Vec<f32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_muscle_table() {
+        // every muscle drives exactly one axis of one bone
+        let mut seen = [0; MUSCLE_COUNT];
+        for axes in &BONE_MUSCLES {
+            for &muscle in axes.iter().filter(|&&m| m >= 0) {
+                seen[muscle as usize] += 1;
+            }
+        }
+        assert!(seen.iter().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn test_local_rotation() {
+        let limit = 0.5;
+        let rig = HumanBoneRig {
+            node: 0,
+            pre_q: glm::quat_identity(),
+            post_q: glm::quat_identity(),
+            sgn: glm::vec3(1.0, 1.0, -1.0),
+            limit_min: glm::vec3(-limit, -limit, -limit),
+            limit_max: glm::vec3(limit, limit, limit * 2.0),
+        };
+        // no muscle movement leaves the bone as is
+        let rest = rig.local_rotation(glm::vec3(0.0, 0.0, 0.0));
+        assert!((rest.w - 1.0).abs() < 1e-6);
+
+        // a full z muscle swings by the upper limit, flipped by sgn
+        let q = rig.local_rotation(glm::vec3(0.0, 0.0, 1.0));
+        let expected = glm::quat_angle_axis(-limit * 2.0, &glm::vec3(0.0, 0.0, 1.0));
+        assert!((q.coords - expected.coords).norm() < 1e-6);
+
+        // and a negative twist by the lower limit
+        let q = rig.local_rotation(glm::vec3(-1.0, 0.0, 0.0));
+        let expected = glm::quat_angle_axis(-limit, &glm::vec3(1.0, 0.0, 0.0));
+        assert!((q.coords - expected.coords).norm() < 1e-6);
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use nalgebra_glm as glm;
+use serde::Serialize;
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use crate::unity::types::wasm::{MeshFilter, MeshRenderer, PPtrInfo, Transform, WasmFriendlyPPtr};
+
+// Scenes tend to repeat the same few props (rocks, trees, fences) hundreds of
+// times, each with its own MeshRenderer. This groups renderers that draw the
+// same mesh with the same materials, so each group can be drawn instanced.
+
+const MAX_HIERARCHY_DEPTH: usize = 256;
+
+struct TransformNode {
+    local_matrix: glm::Mat4,
+    parent_path_id: i64,
+}
+
+#[wasm_bindgen(js_name = "UnityInstanceGrouper")]
+#[derive(Default)]
+pub struct InstanceGrouper {
+    transforms: HashMap<i64, TransformNode>,
+    transform_path_ids: HashMap<WasmFriendlyPPtr, i64>,
+    meshes: HashMap<WasmFriendlyPPtr, WasmFriendlyPPtr>,
+    renderers: Vec<GroupedRenderer>,
+}
+
+struct GroupedRenderer {
+    game_object: WasmFriendlyPPtr,
+    materials: Vec<WasmFriendlyPPtr>,
+    lightmap_index: u16,
+    lightmap_scale_offset: [f32; 4],
+}
+
+#[wasm_bindgen(js_class = "UnityInstanceGrouper")]
+impl InstanceGrouper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // path_id is the Transform's own, which its children refer to it by
+    pub fn add_transform(&mut self, path_id: i64, transform: &Transform) {
+        let rotation = &transform.local_rotation;
+        let position = &transform.local_position;
+        let scale = &transform.local_scale;
+        let local_matrix = glm::translation(&glm::vec3(position.x, position.y, position.z))
+            * glm::quat_to_mat4(&glm::quat(rotation.x, rotation.y, rotation.z, rotation.w))
+            * glm::scaling(&glm::vec3(scale.x, scale.y, scale.z));
+        // we only follow parents within the same file
+        let parent_path_id = if transform.parent.file_index == 0 {
+            transform.parent.path_id
+        } else {
+            0
+        };
+        self.transforms.insert(path_id, TransformNode {
+            local_matrix,
+            parent_path_id,
+        });
+        self.transform_path_ids.insert(transform.game_object, path_id);
+    }
+
+    pub fn add_mesh_filter(&mut self, mesh_filter: &MeshFilter) {
+        self.meshes.insert(mesh_filter.game_object, mesh_filter.mesh);
+    }
+
+    pub fn add_mesh_renderer(&mut self, mesh_renderer: &MeshRenderer) {
+        // static batched renderers already share one combined mesh
+        if mesh_renderer.enabled == 0 || mesh_renderer.static_batch_info.submesh_count > 0 {
+            return;
+        }
+        let offset = &mesh_renderer.lightmap_tiling_offset;
+        self.renderers.push(GroupedRenderer {
+            game_object: mesh_renderer.game_object,
+            materials: mesh_renderer.materials.clone(),
+            lightmap_index: mesh_renderer.lightmap_index,
+            lightmap_scale_offset: [offset.x, offset.y, offset.z, offset.w],
+        });
+    }
+
+    // Returns each mesh, material and lightmap combination drawn by at least
+    // min_instance_count renderers, with their Unity-space world matrices and
+    // lightmap scale/offsets
+    pub fn compute_groups(&self, min_instance_count: usize) -> InstanceGroupList {
+        let groups = self.find_groups(min_instance_count).into_iter()
+            .map(|group| InstanceGroup {
+                mesh: group.mesh.into(),
+                materials: group.materials.into_iter().map(PPtrInfo::from).collect(),
+                game_objects: group.game_objects.into_iter().map(PPtrInfo::from).collect(),
+                matrices: group.matrices,
+                lightmap_index: group.lightmap_index,
+                lightmap_scale_offsets: group.lightmap_scale_offsets,
+            })
+            .collect();
+        InstanceGroupList { groups }
+    }
+}
+
+struct FoundGroup {
+    mesh: WasmFriendlyPPtr,
+    materials: Vec<WasmFriendlyPPtr>,
+    game_objects: Vec<WasmFriendlyPPtr>,
+    matrices: Vec<f32>,
+    lightmap_index: u16,
+    lightmap_scale_offsets: Vec<f32>,
+}
+
+impl InstanceGrouper {
+    fn get_world_matrix(&self, transform_path_id: i64) -> glm::Mat4 {
+        let mut result = glm::Mat4::identity();
+        let mut path_id = transform_path_id;
+        for _ in 0..MAX_HIERARCHY_DEPTH {
+            let Some(node) = self.transforms.get(&path_id) else {
+                break;
+            };
+            result = node.local_matrix * result;
+            path_id = node.parent_path_id;
+        }
+        result
+    }
+
+    fn find_groups(&self, min_instance_count: usize) -> Vec<FoundGroup> {
+        let mut groups: Vec<FoundGroup> = Vec::new();
+        // renderers in different lightmaps can't share a draw
+        let mut group_indices: HashMap<(WasmFriendlyPPtr, &[WasmFriendlyPPtr], u16), usize> = HashMap::new();
+        for renderer in &self.renderers {
+            let game_object = &renderer.game_object;
+            let materials = &renderer.materials;
+            let Some(&mesh) = self.meshes.get(game_object) else {
+                continue;
+            };
+            if mesh.path_id == 0 {
+                continue;
+            }
+            let Some(&transform_path_id) = self.transform_path_ids.get(game_object) else {
+                continue;
+            };
+            let index = *group_indices.entry((mesh, materials.as_slice(), renderer.lightmap_index)).or_insert_with(|| {
+                groups.push(FoundGroup {
+                    mesh,
+                    materials: materials.clone(),
+                    game_objects: Vec::new(),
+                    matrices: Vec::new(),
+                    lightmap_index: renderer.lightmap_index,
+                    lightmap_scale_offsets: Vec::new(),
+                });
+                groups.len() - 1
+            });
+            let group = &mut groups[index];
+            group.game_objects.push(*game_object);
+            group.matrices.extend_from_slice(self.get_world_matrix(transform_path_id).as_slice());
+            group.lightmap_scale_offsets.extend_from_slice(&renderer.lightmap_scale_offset);
+        }
+        groups.retain(|group| group.game_objects.len() >= min_instance_count.max(1));
+        groups
+    }
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct InstanceGroup {
+    pub mesh: PPtrInfo,
+    pub materials: Vec<PPtrInfo>,
+    pub game_objects: Vec<PPtrInfo>,
+    // a column-major 4x4 matrix per instance, in the same order as game_objects
+    pub matrices: Vec<f32>,
+    // 0xFFFF when not lightmapped
+    pub lightmap_index: u16,
+    // (scale x, scale y, offset x, offset y) per instance, mapping its
+    // lightmap UVs into its region of the lightmap
+    pub lightmap_scale_offsets: Vec<f32>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct InstanceGroupList {
+    pub groups: Vec<InstanceGroup>,
+}
@@ -2,5 +2,28 @@
 
 mod version;
 mod asset_file;
+mod bundle;
 mod types;
 mod util;
+mod smolv;
+mod progress;
+mod instancing;
+mod gltf;
+mod obj;
+mod picking;
+mod texture_container;
+mod octree_batch;
+mod scene;
+mod game;
+mod external;
+mod type_tree;
+mod file_kind;
+mod render_order;
+mod batch_plan;
+mod atlas;
+mod segmented;
+mod humanoid;
+mod curve;
+mod muscle_clip;
+
+pub(crate) use types::common::{check_decompressed_size, reset_parse_state};
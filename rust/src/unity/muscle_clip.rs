@@ -0,0 +1,235 @@
+use std::convert::TryFrom;
+
+use wasm_bindgen::prelude::*;
+
+use crate::unity::curve::CurveWrapMode;
+use crate::unity::types::binary;
+use crate::unity::types::class_id::ClassID;
+use crate::unity::types::serialized_file::class_id_from_raw;
+use crate::unity::types::wasm::{AnimationCurve, Keyframe};
+
+// Unpacking an AnimationClip's muscle clip into plain curves, and working out
+// which curves each of its bindings animates. Curves are numbered through the
+// streamed clip, then the dense clip, then the constant clip, and bindings
+// take consecutive curves in the same order, one per component.
+
+// GenericBinding.custom_type for humanoid muscles, whose attribute then
+// indexes Unity's humanoid values: motion T and Q, root (body) T and Q, the
+// four IK goals' T and Q, then the muscles in HumanTrait order
+pub const BINDING_CUSTOM_TYPE_MUSCLE: u8 = 8;
+pub const HUMANOID_ROOT_ATTRIBUTE: u32 = 7;
+pub const HUMANOID_MUSCLE_ATTRIBUTE: u32 = 42;
+
+// Attributes of transform bindings
+const TRANSFORM_POSITION: u32 = 1;
+const TRANSFORM_ROTATION: u32 = 2;
+const TRANSFORM_SCALE: u32 = 3;
+const TRANSFORM_EULER: u32 = 4;
+
+#[wasm_bindgen(js_name = "UnityCurveBinding")]
+#[derive(Clone, Copy, Debug)]
+pub struct CurveBinding {
+    // CRC32 of the transform path, which the Avatar (or the Animator's
+    // transform hierarchy) resolves
+    pub path_hash: u32,
+    pub attribute: u32,
+    pub class_id: ClassID,
+    pub custom_type: u8,
+    // the curves animating this binding's components
+    pub first_curve: u32,
+    pub curve_count: u32,
+}
+
+pub fn resolve_bindings(bindings: &[binary::GenericBinding]) -> Vec<CurveBinding> {
+    let mut next_curve = 0;
+    let mut result = Vec::with_capacity(bindings.len());
+    // PPtr curves index pptr_curve_mapping rather than the muscle clip
+    for binding in bindings.iter().filter(|binding| binding.is_pptr_curve == 0) {
+        let class_id = class_id_from_raw(binding.class_id);
+        let curve_count = match (class_id, binding.attribute) {
+            (ClassID::Transform, TRANSFORM_POSITION | TRANSFORM_SCALE | TRANSFORM_EULER) => 3,
+            (ClassID::Transform, TRANSFORM_ROTATION) => 4,
+            _ => 1,
+        };
+        result.push(CurveBinding {
+            path_hash: binding.path,
+            attribute: binding.attribute,
+            class_id,
+            custom_type: binding.custom_type,
+            first_curve: next_curve,
+            curve_count,
+        });
+        next_curve += curve_count;
+    }
+    result
+}
+
+pub fn decode_curves(clip: &binary::Clip) -> Vec<AnimationCurve> {
+    let mut curves = decode_streamed(&clip.streamed_clip);
+    curves.extend(decode_dense(&clip.dense_clip));
+    curves.extend(clip.constant_clip.data.values.iter().map(|&value| vec![Keyframe {
+        time: 0.0,
+        value,
+        in_slope: 0.0,
+        out_slope: 0.0,
+        weighted_mode: 0,
+        in_weight: 0.0,
+        out_weight: 0.0,
+    }]));
+    curves.into_iter()
+        .map(|keyframes| AnimationCurve {
+            keyframes,
+            pre_infinity: CurveWrapMode::Clamp,
+            post_infinity: CurveWrapMode::Clamp,
+        })
+        .collect()
+}
+
+struct StreamedKey {
+    curve: usize,
+    // the cubic's coefficients, highest power first, over time since the key
+    coeffs: [f32; 4],
+}
+
+fn read_streamed_frames(data: &[u32]) -> Vec<(f32, Vec<StreamedKey>)> {
+    let mut frames = Vec::new();
+    let mut words = data.iter().copied();
+    while let (Some(time), Some(key_count)) = (words.next(), words.next()) {
+        let mut keys = Vec::new();
+        for _ in 0..key_count {
+            let (Some(curve), Some(a), Some(b), Some(c), Some(d)) = (words.next(), words.next(), words.next(), words.next(), words.next()) else {
+                break;
+            };
+            keys.push(StreamedKey {
+                curve: curve as usize,
+                coeffs: [f32::from_bits(a), f32::from_bits(b), f32::from_bits(c), f32::from_bits(d)],
+            });
+        }
+        frames.push((f32::from_bits(time), keys));
+    }
+    frames
+}
+
+fn decode_streamed(clip: &binary::StreamedClip) -> Vec<Vec<Keyframe>> {
+    let mut curves: Vec<Vec<Keyframe>> = vec![Vec::new(); clip.curve_count as usize];
+    // the segment each curve is currently in, as (start time, coefficients)
+    let mut segments: Vec<Option<(f32, [f32; 4])>> = vec![None; curves.len()];
+    let frames = read_streamed_frames(&clip.data.values);
+    // the first and last frames are only there for the slopes of the real
+    // keys next to them
+    let last_frame = frames.len().saturating_sub(1);
+    for (i, (time, keys)) in frames.iter().enumerate() {
+        for key in keys {
+            let Some(keyframes) = curves.get_mut(key.curve) else {
+                continue;
+            };
+            let [_, _, c, d] = key.coeffs;
+            let mut in_slope = 0.0;
+            if let Some((start, [pa, pb, pc, _])) = segments[key.curve] {
+                if pa == 0.0 && pb == 0.0 && pc == 0.0 {
+                    // a flat segment, which the previous key steps through
+                    in_slope = f32::INFINITY;
+                    if let Some(previous) = keyframes.last_mut() {
+                        previous.out_slope = f32::INFINITY;
+                    }
+                } else {
+                    let dt = time - start;
+                    in_slope = (3.0 * pa * dt + 2.0 * pb) * dt + pc;
+                }
+            }
+            segments[key.curve] = Some((*time, key.coeffs));
+            if i > 0 && i < last_frame {
+                keyframes.push(Keyframe {
+                    time: *time,
+                    value: d,
+                    in_slope,
+                    out_slope: c,
+                    weighted_mode: 0,
+                    in_weight: 0.0,
+                    out_weight: 0.0,
+                });
+            }
+        }
+    }
+    curves
+}
+
+// Dense clips interpolate linearly between samples, which is what Hermite
+// segments with both slopes on the line between them do
+fn decode_dense(clip: &binary::DenseClip) -> Vec<Vec<Keyframe>> {
+    let curve_count = clip.curve_count as usize;
+    let frame_count = usize::try_from(clip.frame_count).unwrap_or(0);
+    let samples = &clip.sample_array.values;
+    let frame_time = |frame: usize| clip.begin_time + frame as f32 / clip.sample_rate;
+    let sample = |frame: usize, curve: usize| samples.get(frame * curve_count + curve).copied().unwrap_or(0.0);
+    let slope = |frame: usize, curve: usize| {
+        (sample(frame + 1, curve) - sample(frame, curve)) * clip.sample_rate
+    };
+    (0..curve_count)
+        .map(|curve| {
+            (0..frame_count)
+                .map(|frame| Keyframe {
+                    time: frame_time(frame),
+                    value: sample(frame, curve),
+                    in_slope: if frame > 0 { slope(frame - 1, curve) } else { 0.0 },
+                    out_slope: if frame + 1 < frame_count { slope(frame, curve) } else { 0.0 },
+                    weighted_mode: 0,
+                    in_weight: 0.0,
+                    out_weight: 0.0,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unity::curve;
+    use crate::unity::types::common::UnityArray;
+
+    fn evaluate(curve: &AnimationCurve, time: f32) -> f32 {
+        curve::evaluate(&curve.keyframes, curve.pre_infinity, curve.post_infinity, time)
+    }
+
+    #[test]
+    fn test_decode_curves() {
+        let mut streamed = Vec::new();
+        let mut frame = |time: f32, keys: &[(u32, [f32; 4])]| {
+            streamed.extend([time.to_bits(), keys.len() as u32]);
+            for (curve, coeffs) in keys {
+                streamed.push(*curve);
+                streamed.extend(coeffs.iter().map(|c| c.to_bits()));
+            }
+        };
+        // curve 0 goes 0 -> 1 along t^2 over the first second, then holds
+        frame(f32::MIN, &[(0, [0.0, 0.0, 0.0, 0.0])]);
+        frame(0.0, &[(0, [0.0, 1.0, 0.0, 0.0])]);
+        frame(1.0, &[(0, [0.0, 0.0, 0.0, 1.0])]);
+        frame(f32::MAX, &[(0, [0.0, 0.0, 0.0, 1.0])]);
+
+        let clip = binary::Clip {
+            streamed_clip: binary::StreamedClip { data: UnityArray { values: streamed }, curve_count: 1 },
+            dense_clip: binary::DenseClip {
+                frame_count: 3,
+                curve_count: 1,
+                sample_rate: 2.0,
+                begin_time: 0.0,
+                sample_array: UnityArray { values: vec![0.0, 2.0, 1.0] },
+            },
+            constant_clip: binary::ConstantClip { data: UnityArray { values: vec![5.0] } },
+        };
+        let curves = decode_curves(&clip);
+        assert_eq!(curves.len(), 3);
+
+        assert_eq!(curves[0].keyframes.len(), 2);
+        assert!((evaluate(&curves[0], 0.5) - 0.25).abs() < 1e-5);
+        assert_eq!(curves[0].keyframes[1].in_slope, 2.0);
+        assert_eq!(evaluate(&curves[0], 3.0), 1.0);
+
+        assert!((evaluate(&curves[1], 0.25) - 1.0).abs() < 1e-5);
+        assert!((evaluate(&curves[1], 0.75) - 1.5).abs() < 1e-5);
+
+        assert_eq!(evaluate(&curves[2], 10.0), 5.0);
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+use tsify::Tsify;
+
+// Writes a single mesh as Wavefront OBJ, with a group and MTL material per
+// submesh. Like the glTF exporter, we mirror X and flip V to get from Unity's
+// conventions to the right-handed ones other tools expect.
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct ObjExport {
+    pub obj: String,
+    pub mtl: String,
+}
+
+pub struct ObjGroup {
+    pub name: String,
+    // triangle list indices into the mesh's vertices
+    pub indices: Vec<u32>,
+}
+
+fn sanitize_name(name: &str) -> String {
+    let result: String = name.chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .collect();
+    if result.is_empty() {
+        "unnamed".to_string()
+    } else {
+        result
+    }
+}
+
+pub fn write_obj(name: &str, positions: &[f32], normals: Option<&[f32]>, uvs: Option<&[f32]>, groups: &[ObjGroup]) -> ObjExport {
+    let name = sanitize_name(name);
+    let mut obj = String::new();
+    let mut mtl = String::new();
+    // writing to a String can't fail
+    let _ = writeln!(obj, "# exported from noclip.website");
+    let _ = writeln!(obj, "mtllib {}.mtl", name);
+    let _ = writeln!(obj, "o {}", name);
+    for v in positions.chunks_exact(3) {
+        let _ = writeln!(obj, "v {} {} {}", -v[0], v[1], v[2]);
+    }
+    if let Some(uvs) = uvs {
+        for uv in uvs.chunks_exact(2) {
+            let _ = writeln!(obj, "vt {} {}", uv[0], 1.0 - uv[1]);
+        }
+    }
+    if let Some(normals) = normals {
+        for n in normals.chunks_exact(3) {
+            let _ = writeln!(obj, "vn {} {} {}", -n[0], n[1], n[2]);
+        }
+    }
+
+    let vertex = |index: u32| {
+        let index = index + 1;
+        match (uvs.is_some(), normals.is_some()) {
+            (true, true) => format!("{}/{}/{}", index, index, index),
+            (true, false) => format!("{}/{}", index, index),
+            (false, true) => format!("{}//{}", index, index),
+            (false, false) => format!("{}", index),
+        }
+    };
+    for group in groups {
+        let group_name = sanitize_name(&group.name);
+        let _ = writeln!(obj, "g {}", group_name);
+        let _ = writeln!(obj, "usemtl {}", group_name);
+        // mirroring flips the winding, so flip it back
+        for tri in group.indices.chunks_exact(3) {
+            let _ = writeln!(obj, "f {} {} {}", vertex(tri[0]), vertex(tri[2]), vertex(tri[1]));
+        }
+        let _ = writeln!(mtl, "newmtl {}", group_name);
+        let _ = writeln!(mtl, "Kd 1 1 1");
+        let _ = writeln!(mtl);
+    }
+    ObjExport { obj, mtl }
+}
@@ -0,0 +1,215 @@
+use deku::prelude::*;
+use wasm_bindgen::prelude::*;
+
+// Subnautica doesn't store its terrain as Meshes; instead it's a voxel grid,
+// split into batches of 5x5x5 octrees which are 32 voxels on a side, and
+// meshed at runtime. Each batch lives in its own file under
+// StreamingAssets/SNUnmanagedData/Build18/CompiledOctreesCache.
+
+pub const OCTREE_SIZE: usize = 32;
+pub const BATCH_OCTREES: usize = 5;
+pub const BATCH_SIZE: usize = OCTREE_SIZE * BATCH_OCTREES;
+const OCTREE_BATCH_VERSION: i32 = 4;
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+pub struct OctreeNode {
+    // 0 is empty space, anything else indexes into the terrain's materials
+    pub node_type: u8,
+    pub density: u8,
+    // index of the first of 8 consecutive children, or 0 for a leaf
+    pub first_child: u16,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct Octree {
+    node_count: u16,
+    #[deku(count = "*node_count")]
+    pub nodes: Vec<OctreeNode>,
+}
+
+impl Octree {
+    // Returns the leaf containing the voxel at (x, y, z), relative to this
+    // octree's corner. Children are ordered with x in the high bit and z in
+    // the low bit.
+    pub fn get_leaf(&self, x: usize, y: usize, z: usize) -> Option<&OctreeNode> {
+        let mut node = self.nodes.first()?;
+        let mut size = OCTREE_SIZE;
+        let (mut x, mut y, mut z) = (x, y, z);
+        while node.first_child != 0 {
+            size /= 2;
+            let mut child = 0;
+            if x >= size { child |= 4; x -= size; }
+            if y >= size { child |= 2; y -= size; }
+            if z >= size { child |= 1; z -= size; }
+            node = self.nodes.get(node.first_child as usize + child)?;
+        }
+        Some(node)
+    }
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct OctreeBatchFile {
+    pub version: i32,
+    // ordered x-major, i.e. x * 25 + y * 5 + z
+    #[deku(count = "BATCH_OCTREES * BATCH_OCTREES * BATCH_OCTREES")]
+    pub octrees: Vec<Octree>,
+}
+
+#[wasm_bindgen(js_name = "UnityOctreeBatch")]
+pub struct OctreeBatch {
+    octrees: Vec<Octree>,
+}
+
+#[wasm_bindgen(js_name = "UnityOctreeBatchMesh", getter_with_clone)]
+pub struct OctreeBatchMesh {
+    // in voxels, relative to the batch's corner
+    pub positions: Vec<f32>,
+    pub normals: Vec<f32>,
+    // the node type per vertex, for picking the terrain material
+    pub types: Vec<u8>,
+    pub indices: Vec<u32>,
+}
+
+// (axis, direction) per face, and its corners as offsets from the voxel's
+// minimum corner, wound counter-clockwise when seen from outside
+const FACES: [([i32; 3], [[f32; 3]; 4]); 6] = [
+    ([1, 0, 0], [[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [1.0, 1.0, 1.0], [1.0, 0.0, 1.0]]),
+    ([-1, 0, 0], [[0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 1.0], [0.0, 1.0, 0.0]]),
+    ([0, 1, 0], [[0.0, 1.0, 0.0], [0.0, 1.0, 1.0], [1.0, 1.0, 1.0], [1.0, 1.0, 0.0]]),
+    ([0, -1, 0], [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 0.0, 1.0], [0.0, 0.0, 1.0]]),
+    ([0, 0, 1], [[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 1.0, 1.0]]),
+    ([0, 0, -1], [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0], [1.0, 0.0, 0.0]]),
+];
+
+#[wasm_bindgen(js_class = "UnityOctreeBatch")]
+impl OctreeBatch {
+    pub fn new(data: &[u8]) -> Result<OctreeBatch, String> {
+        let (_, file) = OctreeBatchFile::from_bytes((data, 0))
+            .map_err(|err| format!("failed to parse octree batch: {:?}", err))?;
+        if file.version != OCTREE_BATCH_VERSION {
+            return Err(format!("unsupported octree batch version {}", file.version));
+        }
+        Ok(OctreeBatch {
+            octrees: file.octrees,
+        })
+    }
+
+    // The file holding the batch at the given batch coordinates
+    pub fn get_file_name(x: i32, y: i32, z: i32) -> String {
+        format!("compiled-batch-{}-{}-{}.optoctrees", x, y, z)
+    }
+
+    // The node type of the voxel at (x, y, z) relative to the batch's corner,
+    // or 0 if it's empty or out of bounds
+    pub fn get_voxel_type(&self, x: usize, y: usize, z: usize) -> u8 {
+        if x >= BATCH_SIZE || y >= BATCH_SIZE || z >= BATCH_SIZE {
+            return 0;
+        }
+        let index = (x / OCTREE_SIZE) * BATCH_OCTREES * BATCH_OCTREES
+            + (y / OCTREE_SIZE) * BATCH_OCTREES
+            + (z / OCTREE_SIZE);
+        self.octrees.get(index)
+            .and_then(|octree| octree.get_leaf(x % OCTREE_SIZE, y % OCTREE_SIZE, z % OCTREE_SIZE))
+            .map_or(0, |leaf| leaf.node_type)
+    }
+
+    // Builds a blocky mesh of the batch's solid voxels, sampling every
+    // step voxels (so 1 is full detail). Faces on the batch's edges are
+    // skipped, since the neighboring batch is usually solid there too.
+    pub fn build_mesh(&self, step: usize) -> OctreeBatchMesh {
+        let step = step.clamp(1, OCTREE_SIZE);
+        let dim = BATCH_SIZE / step;
+        let mut grid = vec![0u8; dim * dim * dim];
+        for x in 0..dim {
+            for y in 0..dim {
+                for z in 0..dim {
+                    grid[(x * dim + y) * dim + z] = self.get_voxel_type(x * step, y * step, z * step);
+                }
+            }
+        }
+        let is_open = |x: i32, y: i32, z: i32| {
+            let d = dim as i32;
+            if x < 0 || y < 0 || z < 0 || x >= d || y >= d || z >= d {
+                return false;
+            }
+            grid[((x * d + y) * d + z) as usize] == 0
+        };
+
+        let mut mesh = OctreeBatchMesh {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            types: Vec::new(),
+            indices: Vec::new(),
+        };
+        for x in 0..dim {
+            for y in 0..dim {
+                for z in 0..dim {
+                    let node_type = grid[(x * dim + y) * dim + z];
+                    if node_type == 0 {
+                        continue;
+                    }
+                    for (normal, corners) in FACES.iter() {
+                        if !is_open(x as i32 + normal[0], y as i32 + normal[1], z as i32 + normal[2]) {
+                            continue;
+                        }
+                        let base = mesh.types.len() as u32;
+                        for corner in corners {
+                            mesh.positions.push((x as f32 + corner[0]) * step as f32);
+                            mesh.positions.push((y as f32 + corner[1]) * step as f32);
+                            mesh.positions.push((z as f32 + corner[2]) * step as f32);
+                            mesh.normals.extend(normal.iter().map(|&n| n as f32));
+                            mesh.types.push(node_type);
+                        }
+                        mesh.indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+                    }
+                }
+            }
+        }
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_batch(octrees: &[Vec<[u8; 4]>]) -> Vec<u8> {
+        let mut data = OCTREE_BATCH_VERSION.to_le_bytes().to_vec();
+        for i in 0..BATCH_OCTREES * BATCH_OCTREES * BATCH_OCTREES {
+            let nodes = octrees.get(i).cloned().unwrap_or_default();
+            data.extend((nodes.len() as u16).to_le_bytes());
+            for node in nodes {
+                data.extend(node);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_octree_lookup() {
+        // root with 8 leaf children, only the +x+y+z one solid
+        let mut nodes = vec![[0, 0, 1, 0]];
+        for child in 0..8 {
+            nodes.push(if child == 7 { [3, 0, 0, 0] } else { [0, 0, 0, 0] });
+        }
+        let batch = OctreeBatch::new(&make_batch(&[nodes])).unwrap();
+        assert_eq!(batch.get_voxel_type(0, 0, 0), 0);
+        assert_eq!(batch.get_voxel_type(16, 16, 16), 3);
+        assert_eq!(batch.get_voxel_type(31, 31, 31), 3);
+        assert_eq!(batch.get_voxel_type(16, 0, 16), 0);
+        // the other octrees are empty
+        assert_eq!(batch.get_voxel_type(40, 40, 40), 0);
+
+        // one 16^3 cube, seen from all six sides
+        let mesh = batch.build_mesh(16);
+        assert_eq!(mesh.types.len(), 6 * 4);
+        assert_eq!(mesh.indices.len(), 6 * 6);
+    }
+
+    #[test]
+    fn test_bad_version() {
+        let mut data = make_batch(&[]);
+        data[0] = 3;
+        assert!(OctreeBatch::new(&data).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use nalgebra_glm as glm;
+use wasm_bindgen::prelude::*;
+
+use crate::bvh::BVH;
+use crate::geometry::{intersect_ray_triangle, AABB};
+use crate::unity::types::wasm::Mesh;
+
+// Casts rays against the triangles of placed meshes, for working out what's
+// under the cursor. Each mesh gets a BVH over its triangles, and the meshes
+// themselves go in another BVH over their world space bounds.
+
+struct PickObject {
+    // world space
+    positions: Vec<glm::Vec3>,
+    triangles: Vec<[u32; 3]>,
+    // the submesh each triangle came from, and its index within it
+    triangle_ids: Vec<(u32, u32)>,
+    bvh: BVH,
+    aabb: AABB,
+}
+
+#[wasm_bindgen(js_name = "UnityPickHit", getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct PickHit {
+    pub object: u32,
+    pub submesh: u32,
+    // quads count as two triangles
+    pub triangle: u32,
+    pub distance: f32,
+    // weights of the triangle's three vertices at the hit
+    pub barycentrics: Vec<f32>,
+    pub position: Vec<f32>,
+}
+
+#[wasm_bindgen(js_name = "UnityMeshPicker")]
+#[derive(Default)]
+pub struct MeshPicker {
+    objects: Vec<PickObject>,
+    // rebuilt on the next pick after objects are added
+    bvh: Option<BVH>,
+}
+
+#[wasm_bindgen(js_class = "UnityMeshPicker")]
+impl MeshPicker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds an instance of mesh placed by world_from_local (16 floats, column
+    // major), returning the object index hits will report. Streamed vertex
+    // data must have been set first.
+    pub fn add_mesh(&mut self, mesh: &Mesh, world_from_local: &[f32]) -> Result<u32, String> {
+        assert_eq!(world_from_local.len(), 16);
+        let world_from_local = glm::make_mat4(world_from_local);
+        let (positions, submeshes) = mesh.get_triangles()?;
+        let positions: Vec<glm::Vec3> = positions.chunks_exact(3)
+            .map(|p| (world_from_local * glm::vec4(p[0], p[1], p[2], 1.0)).xyz())
+            .collect();
+
+        let mut triangles = Vec::new();
+        let mut triangle_ids = Vec::new();
+        for (submesh, indices) in submeshes {
+            for (i, triangle) in indices.chunks_exact(3).enumerate() {
+                triangles.push([triangle[0], triangle[1], triangle[2]]);
+                triangle_ids.push((submesh as u32, i as u32));
+            }
+        }
+        let triangle_bounds: Vec<AABB> = triangles.iter()
+            .map(|triangle| {
+                let mut aabb = AABB::default();
+                for &index in triangle {
+                    aabb.union_point(&positions[index as usize]);
+                }
+                aabb
+            })
+            .collect();
+        let mut aabb = AABB::default();
+        for triangle_aabb in &triangle_bounds {
+            aabb.union_aabb(triangle_aabb);
+        }
+
+        self.objects.push(PickObject {
+            positions,
+            triangles,
+            triangle_ids,
+            bvh: BVH::build(triangle_bounds),
+            aabb,
+        });
+        self.bvh = None;
+        Ok(self.objects.len() as u32 - 1)
+    }
+
+    pub fn get_object_count(&self) -> usize {
+        self.objects.len()
+    }
+
+    // Returns the nearest triangle the ray hits within max_distance
+    pub fn pick(&mut self, origin: &[f32], direction: &[f32], max_distance: f32) -> Option<PickHit> {
+        assert_eq!(origin.len(), 3);
+        assert_eq!(direction.len(), 3);
+        let origin = glm::make_vec3(origin);
+        let dir = glm::make_vec3(direction);
+        let objects = &self.objects;
+        let bvh = self.bvh.get_or_insert_with(|| BVH::build(objects.iter().map(|object| object.aabb.clone()).collect()));
+
+        let mut best_distance = max_distance;
+        let mut best = None;
+        bvh.traverse_ray(&origin, &dir, max_distance, |object_index, _| {
+            let object = &objects[object_index as usize];
+            object.bvh.traverse_ray(&origin, &dir, best_distance, |triangle_index, _| {
+                let [i0, i1, i2] = object.triangles[triangle_index as usize];
+                let p = &object.positions;
+                let (t, u, v) = intersect_ray_triangle(&origin, &dir, &p[i0 as usize], &p[i1 as usize], &p[i2 as usize])?;
+                if t < best_distance {
+                    best_distance = t;
+                    best = Some((object_index, triangle_index as usize, u, v));
+                }
+                Some(t)
+            });
+            Some(best_distance)
+        });
+
+        let (object_index, triangle_index, u, v) = best?;
+        let (submesh, triangle) = objects[object_index as usize].triangle_ids[triangle_index];
+        let position = origin + dir * best_distance;
+        Some(PickHit {
+            object: object_index,
+            submesh,
+            triangle,
+            distance: best_distance,
+            barycentrics: vec![1.0 - u - v, u, v],
+            position: vec![position.x, position.y, position.z],
+        })
+    }
+}
@@ -0,0 +1,27 @@
+use wasm_bindgen::JsValue;
+
+// Reports progress on long-running calls to an optional JS callback, which is
+// called as callback(done, total) after each object or block
+pub struct Progress {
+    callback: Option<js_sys::Function>,
+    done: usize,
+    total: usize,
+}
+
+impl Progress {
+    pub fn new(callback: Option<js_sys::Function>, total: usize) -> Self {
+        Self {
+            callback,
+            done: 0,
+            total,
+        }
+    }
+
+    pub fn step(&mut self) {
+        self.done += 1;
+        if let Some(callback) = &self.callback {
+            // the callback is only informational, so ignore anything it throws
+            let _ = callback.call2(&JsValue::NULL, &JsValue::from(self.done as u32), &JsValue::from(self.total as u32));
+        }
+    }
+}
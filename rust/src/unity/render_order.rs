@@ -0,0 +1,98 @@
+use std::cmp::Ordering;
+
+use wasm_bindgen::prelude::*;
+
+use crate::unity::types::wasm::{Material, MeshRenderer, RENDER_QUEUE_GEOMETRY_LAST};
+
+// Works out the order Unity would draw a set of renderers in. Opaque queues
+// (up to 2500) come first, then transparent ones; within each, renderers
+// sort by sorting layer, order in layer and render queue, then by distance
+// (front to back when opaque, back to front when transparent). Anything
+// still tied keeps the order it was added in.
+
+struct RenderOrderItem {
+    sorting_layer: i16,
+    sorting_order: i16,
+    render_queue: i32,
+    view_distance: f32,
+}
+
+impl RenderOrderItem {
+    fn is_transparent(&self) -> bool {
+        self.render_queue > RENDER_QUEUE_GEOMETRY_LAST
+    }
+
+    fn compare(&self, other: &RenderOrderItem) -> Ordering {
+        let transparent = self.is_transparent();
+        transparent.cmp(&other.is_transparent())
+            .then(self.sorting_layer.cmp(&other.sorting_layer))
+            .then(self.sorting_order.cmp(&other.sorting_order))
+            .then(self.render_queue.cmp(&other.render_queue))
+            .then_with(|| {
+                let by_distance = self.view_distance.partial_cmp(&other.view_distance).unwrap_or(Ordering::Equal);
+                if transparent { by_distance.reverse() } else { by_distance }
+            })
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityRenderOrder")]
+#[derive(Default)]
+pub struct RenderOrder {
+    items: Vec<RenderOrderItem>,
+}
+
+#[wasm_bindgen(js_class = "UnityRenderOrder")]
+impl RenderOrder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // shader_queue is the Queue tag of the subshader the material is drawn
+    // with, and view_distance how far the renderer's bounds are from the
+    // camera. Returns the index compute_order refers to it by.
+    pub fn add_renderer(&mut self, renderer: &MeshRenderer, material: &Material, shader_queue: Option<i32>, view_distance: f32) -> usize {
+        self.add(renderer.sorting_layer, renderer.sorting_order, material.get_render_queue(shader_queue), view_distance)
+    }
+
+    // For sprites, terrains and anything else that isn't a MeshRenderer
+    pub fn add(&mut self, sorting_layer: i16, sorting_order: i16, render_queue: i32, view_distance: f32) -> usize {
+        self.items.push(RenderOrderItem {
+            sorting_layer,
+            sorting_order,
+            render_queue,
+            view_distance,
+        });
+        self.items.len() - 1
+    }
+
+    // Indices of the added renderers, in the order they should be drawn
+    pub fn compute_order(&self) -> Vec<u32> {
+        let mut order: Vec<u32> = (0..self.items.len() as u32).collect();
+        // sort_by is stable, so ties keep the order they were added in
+        order.sort_by(|&a, &b| self.items[a as usize].compare(&self.items[b as usize]));
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_order() {
+        let mut order = RenderOrder::new();
+        // transparent, near
+        order.add(0, 0, 3000, 1.0);
+        // opaque, far
+        order.add(0, 0, 2000, 10.0);
+        // transparent, far
+        order.add(0, 0, 3000, 10.0);
+        // opaque, near
+        order.add(0, 0, 2000, 1.0);
+        // transparent, in front by order in layer
+        order.add(0, 1, 3000, 20.0);
+        // opaque skybox-ish queue
+        order.add(0, 0, 1000, 5.0);
+        assert_eq!(order.compute_order(), vec![5, 3, 1, 2, 0, 4]);
+    }
+}
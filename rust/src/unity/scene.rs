@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+use nalgebra_glm as glm;
+use serde::Serialize;
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use crate::unity::asset_file::AssetFile;
+use crate::unity::types::class_id::ClassID;
+use crate::unity::types::common::{Quaternion, UnityVersion, Vec3};
+use crate::unity::types::wasm::{GameObject, LightmapSettings, MeshFilter, MeshRenderer, PPtrInfo, SkinnedMeshRenderer, Terrain, Transform, WasmFriendlyPPtr};
+
+// Reads a whole level file (e.g. "level1") into everything needed to draw
+// it: the GameObject hierarchy with world transforms, and the mesh
+// renderers, terrains and lightmaps attached. Objects that fail to parse are
+// skipped and reported in errors rather than failing the scene.
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct SceneDescription {
+    pub game_objects: Vec<SceneGameObject>,
+    pub mesh_renderers: Vec<SceneMeshRenderer>,
+    pub terrains: Vec<SceneTerrain>,
+    pub lightmaps: Vec<SceneLightmap>,
+    // 0 for non-directional, 1 for directional (with direction lightmaps)
+    pub lightmaps_mode: i32,
+    pub use_shadowmask: bool,
+    pub errors: Vec<String>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct SceneGameObject {
+    #[tsify(type = "bigint")]
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    pub path_id: JsValue,
+    pub name: String,
+    pub layer: u32,
+    // whether it and all its parents are active
+    pub active: bool,
+    // column-major
+    pub world_from_local: Vec<f32>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct SceneMeshRenderer {
+    pub game_object: PPtrInfo,
+    pub mesh: PPtrInfo,
+    pub materials: Vec<PPtrInfo>,
+    pub enabled: bool,
+    // 0xFFFF when not lightmapped
+    pub lightmap_index: u16,
+    pub lightmap_scale_offset: [f32; 4],
+    pub static_batch_first_submesh: u16,
+    pub static_batch_submesh_count: u16,
+    // drawn in its bind pose, since we don't animate
+    pub skinned: bool,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct SceneTerrain {
+    pub game_object: PPtrInfo,
+    pub terrain_data: PPtrInfo,
+    pub material: PPtrInfo,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct SceneLightmap {
+    pub color: PPtrInfo,
+    pub direction: PPtrInfo,
+    pub shadow_mask: PPtrInfo,
+}
+
+pub fn local_from_trs(position: &Vec3, rotation: &Quaternion, scale: &Vec3) -> glm::Mat4 {
+    let translation = glm::translation(&glm::vec3(position.x, position.y, position.z));
+    let rotation = glm::quat_to_mat4(&glm::quat(rotation.x, rotation.y, rotation.z, rotation.w));
+    let scale = glm::scaling(&glm::vec3(scale.x, scale.y, scale.z));
+    translation * rotation * scale
+}
+
+struct SceneNode {
+    transform: Transform,
+    world_from_local: Option<glm::Mat4>,
+}
+
+fn compute_world_from_local(nodes: &mut HashMap<i64, SceneNode>, path_id: i64, depth: usize) -> glm::Mat4 {
+    let Some(node) = nodes.get(&path_id) else {
+        return glm::identity();
+    };
+    if let Some(world_from_local) = node.world_from_local {
+        return world_from_local;
+    }
+    let t = &node.transform;
+    let local_from_parent = local_from_trs(&t.local_position, &t.local_rotation, &t.local_scale);
+    let parent = t.parent;
+    // guard against cycles in broken files
+    let world_from_parent = if parent.path_id != 0 && parent.file_index == 0 && depth < 256 {
+        compute_world_from_local(nodes, parent.path_id, depth + 1)
+    } else {
+        glm::identity()
+    };
+    let world_from_local = world_from_parent * local_from_parent;
+    if let Some(node) = nodes.get_mut(&path_id) {
+        node.world_from_local = Some(world_from_local);
+    }
+    world_from_local
+}
+
+#[wasm_bindgen(js_name = "unity_load_scene")]
+pub fn load_scene(version: UnityVersion, level_file: &[u8]) -> Result<SceneDescription, String> {
+    let mut asset_file = AssetFile::initialize_with_header_chunk(level_file)?;
+    asset_file.append_metadata_chunk(level_file)?;
+
+    let mut errors = Vec::new();
+    let mut game_objects: Vec<(i64, GameObject)> = Vec::new();
+    let mut nodes: HashMap<i64, SceneNode> = HashMap::new();
+    // GameObject path ID -> Transform path ID
+    let mut transform_ids: HashMap<i64, i64> = HashMap::new();
+    let mut mesh_filters: HashMap<i64, WasmFriendlyPPtr> = HashMap::new();
+    let mut mesh_renderers = Vec::new();
+    let mut skinned_mesh_renderers: Vec<SkinnedMeshRenderer> = Vec::new();
+    let mut terrains = Vec::new();
+    let mut lightmaps = Vec::new();
+    let mut lightmaps_mode = 0;
+    let mut use_shadowmask = false;
+
+    for obj in asset_file.get_objects() {
+        let start = obj.byte_start as usize;
+        let Some(data) = level_file.get(start..start + obj.byte_size) else {
+            errors.push(format!("object {} is out of bounds", obj.file_id));
+            continue;
+        };
+        let result = match obj.class_id {
+            ClassID::GameObject => GameObject::create(version, data)
+                .map(|game_object| game_objects.push((obj.file_id, game_object))),
+            ClassID::Transform | ClassID::RectTransform => Transform::create(version, data)
+                .map(|transform| {
+                    transform_ids.insert(transform.game_object.path_id, obj.file_id);
+                    nodes.insert(obj.file_id, SceneNode { transform, world_from_local: None });
+                }),
+            ClassID::MeshFilter => MeshFilter::create(version, data)
+                .map(|filter| { mesh_filters.insert(filter.game_object.path_id, filter.mesh); }),
+            ClassID::MeshRenderer => MeshRenderer::create(version, data)
+                .map(|renderer| mesh_renderers.push(renderer)),
+            ClassID::SkinnedMeshRenderer => SkinnedMeshRenderer::create(version, data)
+                .map(|renderer| skinned_mesh_renderers.push(renderer)),
+            ClassID::Terrain => Terrain::create(version, data)
+                .map(|terrain| terrains.push(SceneTerrain {
+                    game_object: terrain.game_object.into(),
+                    terrain_data: terrain.terrain_data.into(),
+                    material: terrain.material_template.into(),
+                })),
+            ClassID::LightmapSettings => LightmapSettings::create(version, data)
+                .map(|settings| {
+                    lightmaps_mode = settings.lightmaps_mode;
+                    use_shadowmask = settings.use_shadowmask != 0;
+                    lightmaps.extend(settings.lightmaps.iter().map(|lightmap| SceneLightmap {
+                        color: lightmap.lightmap.into(),
+                        direction: lightmap.dir_lightmap.into(),
+                        shadow_mask: lightmap.shadow_mask.into(),
+                    }));
+                }),
+            _ => Ok(()),
+        };
+        if let Err(err) = result {
+            errors.push(format!("object {}: {}", obj.file_id, err));
+        }
+    }
+
+    let active_by_id: HashMap<i64, bool> = game_objects.iter()
+        .map(|(path_id, game_object)| (*path_id, game_object.is_active != 0))
+        .collect();
+    let mut scene_game_objects = Vec::with_capacity(game_objects.len());
+    for (path_id, game_object) in game_objects {
+        let (world_from_local, active) = match transform_ids.get(&path_id) {
+            Some(&transform_id) => {
+                let world_from_local = compute_world_from_local(&mut nodes, transform_id, 0);
+                // walk up to check the parents are active too
+                let mut active = game_object.is_active != 0;
+                let mut parent = nodes.get(&transform_id).map(|node| node.transform.parent);
+                let mut depth = 0;
+                while let Some(p) = parent {
+                    if !active || p.path_id == 0 || depth >= 256 {
+                        break;
+                    }
+                    let Some(node) = nodes.get(&p.path_id) else { break };
+                    active = *active_by_id.get(&node.transform.game_object.path_id).unwrap_or(&true);
+                    parent = Some(node.transform.parent);
+                    depth += 1;
+                }
+                (world_from_local, active)
+            },
+            None => (glm::identity(), game_object.is_active != 0),
+        };
+        scene_game_objects.push(SceneGameObject {
+            path_id: JsValue::from(path_id),
+            name: game_object.name,
+            layer: game_object.layer,
+            active,
+            world_from_local: world_from_local.as_slice().to_vec(),
+        });
+    }
+
+    let mut mesh_renderers: Vec<SceneMeshRenderer> = mesh_renderers.into_iter()
+        .map(|renderer| {
+            let mesh = mesh_filters.get(&renderer.game_object.path_id).copied()
+                .unwrap_or(WasmFriendlyPPtr { file_index: 0, path_id: 0 });
+            let offset = renderer.lightmap_tiling_offset;
+            SceneMeshRenderer {
+                game_object: renderer.game_object.into(),
+                mesh: mesh.into(),
+                materials: renderer.materials.iter().map(|&material| material.into()).collect(),
+                enabled: renderer.enabled != 0,
+                lightmap_index: renderer.lightmap_index,
+                lightmap_scale_offset: [offset.x, offset.y, offset.z, offset.w],
+                static_batch_first_submesh: renderer.static_batch_info.first_submesh,
+                static_batch_submesh_count: renderer.static_batch_info.submesh_count,
+                skinned: false,
+            }
+        })
+        .collect();
+    mesh_renderers.extend(skinned_mesh_renderers.into_iter().map(|renderer| {
+        let offset = renderer.lightmap_tiling_offset;
+        SceneMeshRenderer {
+            game_object: renderer.game_object.into(),
+            mesh: renderer.mesh.into(),
+            materials: renderer.materials.iter().map(|&material| material.into()).collect(),
+            enabled: renderer.enabled != 0,
+            lightmap_index: renderer.lightmap_index,
+            lightmap_scale_offset: [offset.x, offset.y, offset.z, offset.w],
+            static_batch_first_submesh: 0,
+            static_batch_submesh_count: 0,
+            skinned: true,
+        }
+    }));
+
+    Ok(SceneDescription {
+        game_objects: scene_game_objects,
+        mesh_renderers,
+        terrains,
+        lightmaps,
+        lightmaps_mode,
+        use_shadowmask,
+        errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_from_trs() {
+        let position = Vec3 { x: 1.0, y: 2.0, z: 3.0 };
+        // 90 degrees around y
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        let rotation = Quaternion { x: 0.0, y: half, z: 0.0, w: half };
+        let scale = Vec3 { x: 2.0, y: 2.0, z: 2.0 };
+        let m = local_from_trs(&position, &rotation, &scale);
+        let p = m * glm::vec4(1.0, 0.0, 0.0, 1.0);
+        assert!((p.x - 1.0).abs() < 1e-5);
+        assert!((p.y - 2.0).abs() < 1e-5);
+        assert!((p.z - 1.0).abs() < 1e-5);
+    }
+}
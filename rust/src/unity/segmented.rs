@@ -0,0 +1,162 @@
+use std::borrow::Cow;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use wasm_bindgen::prelude::*;
+
+// Data that arrives in pieces, like split downloads or separately
+// decompressed bundle blocks, read as if it were one contiguous buffer. deku
+// parsers read from it through a SegmentedCursor, so nothing has to be
+// concatenated into one giant Vec first.
+
+#[wasm_bindgen(js_name = "UnitySegmentedBuffer")]
+#[derive(Default, Debug, Clone)]
+pub struct SegmentedBuffer {
+    segments: Vec<Vec<u8>>,
+    // offset of each segment within the whole
+    starts: Vec<usize>,
+    len: usize,
+}
+
+#[wasm_bindgen(js_class = "UnitySegmentedBuffer")]
+impl SegmentedBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Appends data after everything pushed so far
+    pub fn push_segment(&mut self, data: Vec<u8>) {
+        if data.is_empty() {
+            return;
+        }
+        self.starts.push(self.len);
+        self.len += data.len();
+        self.segments.push(data);
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn read_range(&self, start: usize, size: usize) -> Result<Vec<u8>, String> {
+        self.get_range(start, size)
+            .map(|data| data.into_owned())
+            .ok_or_else(|| format!("range {}..{} is past the end of the {} bytes of data", start, start + size, self.len))
+    }
+}
+
+impl SegmentedBuffer {
+    // Which segment holds the byte at offset, and where it is in that segment
+    fn locate(&self, offset: usize) -> Option<(usize, usize)> {
+        if offset >= self.len {
+            return None;
+        }
+        let index = match self.starts.binary_search(&offset) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        Some((index, offset - self.starts[index]))
+    }
+
+    // Borrows when the range is within one segment, and copies otherwise
+    pub fn get_range(&self, start: usize, size: usize) -> Option<Cow<'_, [u8]>> {
+        if start.checked_add(size)? > self.len {
+            return None;
+        }
+        if size == 0 {
+            return Some(Cow::Borrowed(&[]));
+        }
+        let (index, offset) = self.locate(start)?;
+        if let Some(data) = self.segments[index].get(offset..offset + size) {
+            return Some(Cow::Borrowed(data));
+        }
+        let mut result = vec![0; size];
+        let mut cursor = self.cursor();
+        cursor.seek(SeekFrom::Start(start as u64)).ok()?;
+        cursor.read_exact(&mut result).ok()?;
+        Some(Cow::Owned(result))
+    }
+
+    pub fn cursor(&self) -> SegmentedCursor<'_> {
+        SegmentedCursor {
+            buffer: self,
+            position: 0,
+        }
+    }
+}
+
+impl From<Vec<u8>> for SegmentedBuffer {
+    fn from(data: Vec<u8>) -> Self {
+        let mut buffer = Self::new();
+        buffer.push_segment(data);
+        buffer
+    }
+}
+
+pub struct SegmentedCursor<'a> {
+    buffer: &'a SegmentedBuffer,
+    position: u64,
+}
+
+impl Read for SegmentedCursor<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        while read < buf.len() {
+            let Some((index, offset)) = self.buffer.locate(self.position as usize) else {
+                break;
+            };
+            let data = &self.buffer.segments[index][offset..];
+            let count = data.len().min(buf.len() - read);
+            buf[read..read + count].copy_from_slice(&data[..count]);
+            read += count;
+            self.position += count as u64;
+        }
+        Ok(read)
+    }
+}
+
+impl Seek for SegmentedCursor<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => (self.buffer.len as u64).checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        match position {
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            },
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "seek to a negative position")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segmented_reads() {
+        let mut buffer = SegmentedBuffer::new();
+        buffer.push_segment(vec![0, 1, 2]);
+        buffer.push_segment(vec![]);
+        buffer.push_segment(vec![3, 4]);
+        buffer.push_segment(vec![5, 6, 7, 8]);
+        assert_eq!(buffer.len(), 9);
+
+        assert!(matches!(buffer.get_range(3, 2), Some(Cow::Borrowed(&[3, 4]))));
+        assert_eq!(buffer.read_range(1, 6).unwrap(), vec![1, 2, 3, 4, 5, 6]);
+        assert!(buffer.read_range(8, 2).is_err());
+
+        let mut cursor = buffer.cursor();
+        cursor.seek(SeekFrom::End(-3)).unwrap();
+        let mut rest = Vec::new();
+        cursor.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, vec![6, 7, 8]);
+        assert!(cursor.seek(SeekFrom::Current(-10)).is_err());
+    }
+}
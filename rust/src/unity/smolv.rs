@@ -0,0 +1,685 @@
+// SMOL-V, a compressed SPIR-V encoding (https://github.com/aras-p/smol-v),
+// which Unity uses for its Vulkan sub-programs.
+//
+// Header (24 bytes, little endian):
+//   Magic: "SMOL" (4 bytes)
+//   Version: SPIR-V version in the low 24 bits, SMOL-V version in the high 8
+//   Generator, bound, schema: as in SPIR-V
+//   Decoded size in bytes
+// Instructions:
+//   Length and opcode packed into a varint, with common opcodes remapped to
+//   small values and lengths stored minus their usual minimum
+//   Type ID as a varint, result ID as a zigzag delta from the previous result
+//   Some leading operand IDs as zigzag deltas relative to the result ID
+//   Remaining operands either as varints or as raw words, depending on the op
+
+use std::convert::TryInto;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::profiling;
+
+const SPIRV_MAGIC: u32 = 0x07230203;
+const SMOLV_MAGIC: u32 = 0x534D4F4C;
+const SMOLV_HEADER_SIZE: usize = 24;
+
+const OP_NOP: u32 = 0;
+const OP_UNDEF: u32 = 1;
+const OP_SOURCE_CONTINUED: u32 = 2;
+const OP_SOURCE: u32 = 3;
+const OP_SOURCE_EXTENSION: u32 = 4;
+const OP_STRING: u32 = 7;
+const OP_LINE: u32 = 8;
+const OP_EXTENSION: u32 = 10;
+const OP_EXT_INST_IMPORT: u32 = 11;
+// not a real SPIR-V op; a VectorShuffle with its components packed in a byte
+const OP_VECTOR_SHUFFLE_COMPACT: u32 = 13;
+const OP_MEMORY_MODEL: u32 = 14;
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_VARIABLE: u32 = 59;
+const OP_LOAD: u32 = 61;
+const OP_STORE: u32 = 62;
+const OP_ACCESS_CHAIN: u32 = 65;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+const OP_VECTOR_SHUFFLE: u32 = 79;
+const OP_FNEGATE: u32 = 127;
+const OP_FADD: u32 = 129;
+const OP_FMUL: u32 = 133;
+const OP_LABEL: u32 = 248;
+
+const DECORATION_OFFSET: u32 = 35;
+
+// Per-opcode encoding info: (has result, has type, number of leading operand
+// IDs stored relative to the result, whether the remaining operands are
+// varints). A relative count of 9 means "all of them". This has to match
+// smol-v's table exactly, including its entries for unassigned opcodes.
+#[rustfmt::skip]
+const OP_DATA: [(u8, u8, u8, u8); 367] = [
+    (0, 0, 0, 0), // Nop
+    (1, 1, 0, 0), // Undef
+    (0, 0, 0, 0), // SourceContinued
+    (0, 0, 0, 1), // Source
+    (0, 0, 0, 0), // SourceExtension
+    (0, 0, 0, 0), // Name
+    (0, 0, 0, 0), // MemberName
+    (0, 0, 0, 0), // String
+    (0, 0, 0, 1), // Line
+    (1, 1, 0, 0), // #9
+    (0, 0, 0, 0), // Extension
+    (1, 0, 0, 0), // ExtInstImport
+    (1, 1, 0, 1), // ExtInst
+    (1, 1, 2, 1), // VectorShuffleCompact
+    (0, 0, 0, 1), // MemoryModel
+    (0, 0, 0, 1), // EntryPoint
+    (0, 0, 0, 1), // ExecutionMode
+    (0, 0, 0, 1), // Capability
+    (1, 1, 0, 0), // #18
+    (1, 0, 0, 1), // TypeVoid
+    (1, 0, 0, 1), // TypeBool
+    (1, 0, 0, 1), // TypeInt
+    (1, 0, 0, 1), // TypeFloat
+    (1, 0, 0, 1), // TypeVector
+    (1, 0, 0, 1), // TypeMatrix
+    (1, 0, 0, 1), // TypeImage
+    (1, 0, 0, 1), // TypeSampler
+    (1, 0, 0, 1), // TypeSampledImage
+    (1, 0, 0, 1), // TypeArray
+    (1, 0, 0, 1), // TypeRuntimeArray
+    (1, 0, 0, 1), // TypeStruct
+    (1, 0, 0, 0), // TypeOpaque
+    (1, 0, 0, 1), // TypePointer
+    (1, 0, 0, 1), // TypeFunction
+    (1, 0, 0, 1), // TypeEvent
+    (1, 0, 0, 1), // TypeDeviceEvent
+    (1, 0, 0, 1), // TypeReserveId
+    (1, 0, 0, 1), // TypeQueue
+    (1, 0, 0, 1), // TypePipe
+    (0, 0, 0, 1), // TypeForwardPointer
+    (1, 1, 0, 0), // #40
+    (1, 1, 0, 0), // ConstantTrue
+    (1, 1, 0, 0), // ConstantFalse
+    (1, 1, 0, 0), // Constant
+    (1, 1, 9, 0), // ConstantComposite
+    (1, 1, 0, 1), // ConstantSampler
+    (1, 1, 0, 0), // ConstantNull
+    (1, 1, 0, 0), // #47
+    (1, 1, 0, 0), // SpecConstantTrue
+    (1, 1, 0, 0), // SpecConstantFalse
+    (1, 1, 0, 0), // SpecConstant
+    (1, 1, 9, 0), // SpecConstantComposite
+    (1, 1, 0, 0), // SpecConstantOp
+    (1, 1, 0, 0), // #53
+    (1, 1, 0, 1), // Function
+    (1, 1, 0, 0), // FunctionParameter
+    (0, 0, 0, 0), // FunctionEnd
+    (1, 1, 9, 0), // FunctionCall
+    (1, 1, 0, 0), // #58
+    (1, 1, 0, 1), // Variable
+    (1, 1, 0, 0), // ImageTexelPointer
+    (1, 1, 1, 1), // Load
+    (0, 0, 2, 1), // Store
+    (0, 0, 0, 0), // CopyMemory
+    (0, 0, 0, 0), // CopyMemorySized
+    (1, 1, 0, 1), // AccessChain
+    (1, 1, 0, 0), // InBoundsAccessChain
+    (1, 1, 0, 0), // PtrAccessChain
+    (1, 1, 0, 0), // ArrayLength
+    (1, 1, 0, 0), // GenericPtrMemSemantics
+    (1, 1, 0, 0), // InBoundsPtrAccessChain
+    (0, 0, 0, 1), // Decorate
+    (0, 0, 0, 1), // MemberDecorate
+    (1, 0, 0, 0), // DecorationGroup
+    (0, 0, 0, 0), // GroupDecorate
+    (0, 0, 0, 0), // GroupMemberDecorate
+    (1, 1, 0, 0), // #76
+    (1, 1, 1, 1), // VectorExtractDynamic
+    (1, 1, 2, 1), // VectorInsertDynamic
+    (1, 1, 2, 1), // VectorShuffle
+    (1, 1, 9, 0), // CompositeConstruct
+    (1, 1, 1, 1), // CompositeExtract
+    (1, 1, 2, 1), // CompositeInsert
+    (1, 1, 1, 0), // CopyObject
+    (1, 1, 0, 0), // Transpose
+    (1, 1, 0, 0), // #85
+    (1, 1, 0, 0), // SampledImage
+    (1, 1, 2, 1), // ImageSampleImplicitLod
+    (1, 1, 2, 1), // ImageSampleExplicitLod
+    (1, 1, 3, 1), // ImageSampleDrefImplicitLod
+    (1, 1, 3, 1), // ImageSampleDrefExplicitLod
+    (1, 1, 2, 1), // ImageSampleProjImplicitLod
+    (1, 1, 2, 1), // ImageSampleProjExplicitLod
+    (1, 1, 3, 1), // ImageSampleProjDrefImplicitLod
+    (1, 1, 3, 1), // ImageSampleProjDrefExplicitLod
+    (1, 1, 2, 1), // ImageFetch
+    (1, 1, 3, 1), // ImageGather
+    (1, 1, 3, 1), // ImageDrefGather
+    (1, 1, 2, 1), // ImageRead
+    (0, 0, 3, 1), // ImageWrite
+    (1, 1, 1, 0), // Image
+    (1, 1, 1, 0), // ImageQueryFormat
+    (1, 1, 1, 0), // ImageQueryOrder
+    (1, 1, 2, 0), // ImageQuerySizeLod
+    (1, 1, 1, 0), // ImageQuerySize
+    (1, 1, 2, 0), // ImageQueryLod
+    (1, 1, 1, 0), // ImageQueryLevels
+    (1, 1, 1, 0), // ImageQuerySamples
+    (1, 1, 0, 0), // #108
+    (1, 1, 1, 0), // ConvertFToU
+    (1, 1, 1, 0), // ConvertFToS
+    (1, 1, 1, 0), // ConvertSToF
+    (1, 1, 1, 0), // ConvertUToF
+    (1, 1, 1, 0), // UConvert
+    (1, 1, 1, 0), // SConvert
+    (1, 1, 1, 0), // FConvert
+    (1, 1, 1, 0), // QuantizeToF16
+    (1, 1, 1, 0), // ConvertPtrToU
+    (1, 1, 1, 0), // SatConvertSToU
+    (1, 1, 1, 0), // SatConvertUToS
+    (1, 1, 1, 0), // ConvertUToPtr
+    (1, 1, 1, 0), // PtrCastToGeneric
+    (1, 1, 1, 0), // GenericCastToPtr
+    (1, 1, 1, 1), // GenericCastToPtrExplicit
+    (1, 1, 1, 0), // Bitcast
+    (1, 1, 0, 0), // #125
+    (1, 1, 1, 0), // SNegate
+    (1, 1, 1, 0), // FNegate
+    (1, 1, 2, 0), // IAdd
+    (1, 1, 2, 0), // FAdd
+    (1, 1, 2, 0), // ISub
+    (1, 1, 2, 0), // FSub
+    (1, 1, 2, 0), // IMul
+    (1, 1, 2, 0), // FMul
+    (1, 1, 2, 0), // UDiv
+    (1, 1, 2, 0), // SDiv
+    (1, 1, 2, 0), // FDiv
+    (1, 1, 2, 0), // UMod
+    (1, 1, 2, 0), // SRem
+    (1, 1, 2, 0), // SMod
+    (1, 1, 2, 0), // FRem
+    (1, 1, 2, 0), // FMod
+    (1, 1, 2, 0), // VectorTimesScalar
+    (1, 1, 2, 0), // MatrixTimesScalar
+    (1, 1, 2, 0), // VectorTimesMatrix
+    (1, 1, 2, 0), // MatrixTimesVector
+    (1, 1, 2, 0), // MatrixTimesMatrix
+    (1, 1, 2, 0), // OuterProduct
+    (1, 1, 2, 0), // Dot
+    (1, 1, 2, 0), // IAddCarry
+    (1, 1, 2, 0), // ISubBorrow
+    (1, 1, 2, 0), // UMulExtended
+    (1, 1, 2, 0), // SMulExtended
+    (1, 1, 0, 0), // #153
+    (1, 1, 1, 0), // Any
+    (1, 1, 1, 0), // All
+    (1, 1, 1, 0), // IsNan
+    (1, 1, 1, 0), // IsInf
+    (1, 1, 1, 0), // IsFinite
+    (1, 1, 1, 0), // IsNormal
+    (1, 1, 1, 0), // SignBitSet
+    (1, 1, 2, 0), // LessOrGreater
+    (1, 1, 2, 0), // Ordered
+    (1, 1, 2, 0), // Unordered
+    (1, 1, 2, 0), // LogicalEqual
+    (1, 1, 2, 0), // LogicalNotEqual
+    (1, 1, 2, 0), // LogicalOr
+    (1, 1, 2, 0), // LogicalAnd
+    (1, 1, 1, 0), // LogicalNot
+    (1, 1, 3, 0), // Select
+    (1, 1, 2, 0), // IEqual
+    (1, 1, 2, 0), // INotEqual
+    (1, 1, 2, 0), // UGreaterThan
+    (1, 1, 2, 0), // SGreaterThan
+    (1, 1, 2, 0), // UGreaterThanEqual
+    (1, 1, 2, 0), // SGreaterThanEqual
+    (1, 1, 2, 0), // ULessThan
+    (1, 1, 2, 0), // SLessThan
+    (1, 1, 2, 0), // ULessThanEqual
+    (1, 1, 2, 0), // SLessThanEqual
+    (1, 1, 2, 0), // FOrdEqual
+    (1, 1, 2, 0), // FUnordEqual
+    (1, 1, 2, 0), // FOrdNotEqual
+    (1, 1, 2, 0), // FUnordNotEqual
+    (1, 1, 2, 0), // FOrdLessThan
+    (1, 1, 2, 0), // FUnordLessThan
+    (1, 1, 2, 0), // FOrdGreaterThan
+    (1, 1, 2, 0), // FUnordGreaterThan
+    (1, 1, 2, 0), // FOrdLessThanEqual
+    (1, 1, 2, 0), // FUnordLessThanEqual
+    (1, 1, 2, 0), // FOrdGreaterThanEqual
+    (1, 1, 2, 0), // FUnordGreaterThanEqual
+    (1, 1, 0, 0), // #192
+    (1, 1, 0, 0), // #193
+    (1, 1, 2, 0), // ShiftRightLogical
+    (1, 1, 2, 0), // ShiftRightArithmetic
+    (1, 1, 2, 0), // ShiftLeftLogical
+    (1, 1, 2, 0), // BitwiseOr
+    (1, 1, 2, 0), // BitwiseXor
+    (1, 1, 2, 0), // BitwiseAnd
+    (1, 1, 1, 0), // Not
+    (1, 1, 4, 0), // BitFieldInsert
+    (1, 1, 3, 0), // BitFieldSExtract
+    (1, 1, 3, 0), // BitFieldUExtract
+    (1, 1, 1, 0), // BitReverse
+    (1, 1, 1, 0), // BitCount
+    (1, 1, 0, 0), // #206
+    (1, 1, 0, 0), // DPdx
+    (1, 1, 0, 0), // DPdy
+    (1, 1, 0, 0), // Fwidth
+    (1, 1, 0, 0), // DPdxFine
+    (1, 1, 0, 0), // DPdyFine
+    (1, 1, 0, 0), // FwidthFine
+    (1, 1, 0, 0), // DPdxCoarse
+    (1, 1, 0, 0), // DPdyCoarse
+    (1, 1, 0, 0), // FwidthCoarse
+    (1, 1, 0, 0), // #216
+    (1, 1, 0, 0), // #217
+    (0, 0, 0, 0), // EmitVertex
+    (0, 0, 0, 0), // EndPrimitive
+    (0, 0, 0, 0), // EmitStreamVertex
+    (0, 0, 0, 0), // EndStreamPrimitive
+    (1, 1, 0, 0), // #222
+    (1, 1, 0, 0), // #223
+    (0, 0, 3, 0), // ControlBarrier
+    (0, 0, 2, 0), // MemoryBarrier
+    (1, 1, 0, 0), // #226
+    (1, 1, 0, 0), // AtomicLoad
+    (0, 0, 0, 0), // AtomicStore
+    (1, 1, 0, 0), // AtomicExchange
+    (1, 1, 0, 0), // AtomicCompareExchange
+    (1, 1, 0, 0), // AtomicCompareExchangeWeak
+    (1, 1, 0, 0), // AtomicIIncrement
+    (1, 1, 0, 0), // AtomicIDecrement
+    (1, 1, 0, 0), // AtomicIAdd
+    (1, 1, 0, 0), // AtomicISub
+    (1, 1, 0, 0), // AtomicSMin
+    (1, 1, 0, 0), // AtomicUMin
+    (1, 1, 0, 0), // AtomicSMax
+    (1, 1, 0, 0), // AtomicUMax
+    (1, 1, 0, 0), // AtomicAnd
+    (1, 1, 0, 0), // AtomicOr
+    (1, 1, 0, 0), // AtomicXor
+    (1, 1, 0, 0), // #243
+    (1, 1, 0, 0), // #244
+    (1, 1, 0, 0), // Phi
+    (0, 0, 2, 1), // LoopMerge
+    (0, 0, 1, 1), // SelectionMerge
+    (1, 0, 0, 0), // Label
+    (0, 0, 1, 0), // Branch
+    (0, 0, 3, 1), // BranchConditional
+    (0, 0, 0, 0), // Switch
+    (0, 0, 0, 0), // Kill
+    (0, 0, 0, 0), // Return
+    (0, 0, 0, 0), // ReturnValue
+    (0, 0, 0, 0), // Unreachable
+    (0, 0, 0, 0), // LifetimeStart
+    (0, 0, 0, 0), // LifetimeStop
+    (1, 1, 0, 0), // #258
+    (1, 1, 0, 0), // GroupAsyncCopy
+    (0, 0, 0, 0), // GroupWaitEvents
+    (1, 1, 0, 0), // GroupAll
+    (1, 1, 0, 0), // GroupAny
+    (1, 1, 0, 0), // GroupBroadcast
+    (1, 1, 0, 0), // GroupIAdd
+    (1, 1, 0, 0), // GroupFAdd
+    (1, 1, 0, 0), // GroupFMin
+    (1, 1, 0, 0), // GroupUMin
+    (1, 1, 0, 0), // GroupSMin
+    (1, 1, 0, 0), // GroupFMax
+    (1, 1, 0, 0), // GroupUMax
+    (1, 1, 0, 0), // GroupSMax
+    (1, 1, 0, 0), // #272
+    (1, 1, 0, 0), // #273
+    (1, 1, 0, 0), // ReadPipe
+    (1, 1, 0, 0), // WritePipe
+    (1, 1, 0, 0), // ReservedReadPipe
+    (1, 1, 0, 0), // ReservedWritePipe
+    (1, 1, 0, 0), // ReserveReadPipePackets
+    (1, 1, 0, 0), // ReserveWritePipePackets
+    (0, 0, 0, 0), // CommitReadPipe
+    (0, 0, 0, 0), // CommitWritePipe
+    (1, 1, 0, 0), // IsValidReserveId
+    (1, 1, 0, 0), // GetNumPipePackets
+    (1, 1, 0, 0), // GetMaxPipePackets
+    (1, 1, 0, 0), // GroupReserveReadPipePackets
+    (1, 1, 0, 0), // GroupReserveWritePipePackets
+    (0, 0, 0, 0), // GroupCommitReadPipe
+    (0, 0, 0, 0), // GroupCommitWritePipe
+    (1, 1, 0, 0), // #289
+    (1, 1, 0, 0), // #290
+    (1, 1, 0, 0), // EnqueueMarker
+    (1, 1, 0, 0), // EnqueueKernel
+    (1, 1, 0, 0), // GetKernelNDrangeSubGroupCount
+    (1, 1, 0, 0), // GetKernelNDrangeMaxSubGroupSize
+    (1, 1, 0, 0), // GetKernelWorkGroupSize
+    (1, 1, 0, 0), // GetKernelPreferredWorkGroupSizeMultiple
+    (0, 0, 0, 0), // RetainEvent
+    (0, 0, 0, 0), // ReleaseEvent
+    (1, 1, 0, 0), // CreateUserEvent
+    (1, 1, 0, 0), // IsValidEvent
+    (0, 0, 0, 0), // SetUserEventStatus
+    (0, 0, 0, 0), // CaptureEventProfilingInfo
+    (1, 1, 0, 0), // GetDefaultQueue
+    (1, 1, 0, 0), // BuildNDRange
+    (1, 1, 2, 1), // ImageSparseSampleImplicitLod
+    (1, 1, 2, 1), // ImageSparseSampleExplicitLod
+    (1, 1, 3, 1), // ImageSparseSampleDrefImplicitLod
+    (1, 1, 3, 1), // ImageSparseSampleDrefExplicitLod
+    (1, 1, 2, 1), // ImageSparseSampleProjImplicitLod
+    (1, 1, 2, 1), // ImageSparseSampleProjExplicitLod
+    (1, 1, 3, 1), // ImageSparseSampleProjDrefImplicitLod
+    (1, 1, 3, 1), // ImageSparseSampleProjDrefExplicitLod
+    (1, 1, 2, 1), // ImageSparseFetch
+    (1, 1, 3, 1), // ImageSparseGather
+    (1, 1, 3, 1), // ImageSparseDrefGather
+    (1, 1, 1, 0), // ImageSparseTexelsResident
+    (0, 0, 0, 0), // NoLine
+    (1, 1, 0, 0), // AtomicFlagTestAndSet
+    (0, 0, 0, 0), // AtomicFlagClear
+    (1, 1, 0, 0), // ImageSparseRead
+    (1, 1, 0, 0), // SizeOf
+    (1, 1, 0, 0), // TypePipeStorage
+    (1, 1, 0, 0), // ConstantPipeStorage
+    (1, 1, 0, 0), // CreatePipeFromPipeStorage
+    (1, 1, 0, 0), // GetKernelLocalSizeForSubgroupCount
+    (1, 1, 0, 0), // GetKernelMaxNumSubgroups
+    (1, 1, 0, 0), // TypeNamedBarrier
+    (1, 1, 0, 1), // NamedBarrierInitialize
+    (0, 0, 2, 1), // MemoryNamedBarrier
+    (1, 1, 0, 0), // ModuleProcessed
+    // SMOL-V version 1 and up
+    (0, 0, 0, 1), // ExecutionModeId
+    (0, 0, 0, 1), // DecorateId
+    (1, 1, 1, 1), // GroupNonUniformElect
+    (1, 1, 1, 1), // GroupNonUniformAll
+    (1, 1, 1, 1), // GroupNonUniformAny
+    (1, 1, 1, 1), // GroupNonUniformAllEqual
+    (1, 1, 1, 1), // GroupNonUniformBroadcast
+    (1, 1, 1, 1), // GroupNonUniformBroadcastFirst
+    (1, 1, 1, 1), // GroupNonUniformBallot
+    (1, 1, 1, 1), // GroupNonUniformInverseBallot
+    (1, 1, 1, 1), // GroupNonUniformBallotBitExtract
+    (1, 1, 1, 1), // GroupNonUniformBallotBitCount
+    (1, 1, 1, 1), // GroupNonUniformBallotFindLSB
+    (1, 1, 1, 1), // GroupNonUniformBallotFindMSB
+    (1, 1, 1, 1), // GroupNonUniformShuffle
+    (1, 1, 1, 1), // GroupNonUniformShuffleXor
+    (1, 1, 1, 1), // GroupNonUniformShuffleUp
+    (1, 1, 1, 1), // GroupNonUniformShuffleDown
+    (1, 1, 1, 1), // GroupNonUniformIAdd
+    (1, 1, 1, 1), // GroupNonUniformFAdd
+    (1, 1, 1, 1), // GroupNonUniformIMul
+    (1, 1, 1, 1), // GroupNonUniformFMul
+    (1, 1, 1, 1), // GroupNonUniformSMin
+    (1, 1, 1, 1), // GroupNonUniformUMin
+    (1, 1, 1, 1), // GroupNonUniformFMin
+    (1, 1, 1, 1), // GroupNonUniformSMax
+    (1, 1, 1, 1), // GroupNonUniformUMax
+    (1, 1, 1, 1), // GroupNonUniformFMax
+    (1, 1, 1, 1), // GroupNonUniformBitwiseAnd
+    (1, 1, 1, 1), // GroupNonUniformBitwiseOr
+    (1, 1, 1, 1), // GroupNonUniformBitwiseXor
+    (1, 1, 1, 1), // GroupNonUniformLogicalAnd
+    (1, 1, 1, 1), // GroupNonUniformLogicalOr
+    (1, 1, 1, 1), // GroupNonUniformLogicalXor
+    (1, 1, 1, 1), // GroupNonUniformQuadBroadcast
+    (1, 1, 1, 1), // GroupNonUniformQuadSwap
+];
+
+// ModuleProcessed + 1 for version 0, GroupNonUniformQuadSwap + 1 for version 1
+fn known_ops_count(smolv_version: u32) -> Result<usize, String> {
+    match smolv_version {
+        0 => Ok(331),
+        1 => Ok(367),
+        _ => Err(format!("unsupported SMOL-V version {}", smolv_version)),
+    }
+}
+
+fn remap_op(op: u32) -> u32 {
+    const SWAPS: [(u32, u32); 12] = [
+        (OP_DECORATE, OP_NOP),
+        (OP_LOAD, OP_UNDEF),
+        (OP_STORE, OP_SOURCE_CONTINUED),
+        (OP_ACCESS_CHAIN, OP_SOURCE),
+        (OP_VECTOR_SHUFFLE, OP_SOURCE_EXTENSION),
+        (OP_MEMBER_DECORATE, OP_STRING),
+        (OP_LABEL, OP_LINE),
+        (OP_VARIABLE, 9),
+        (OP_FMUL, OP_EXTENSION),
+        (OP_FADD, OP_EXT_INST_IMPORT),
+        (OP_TYPE_POINTER, OP_MEMORY_MODEL),
+        (OP_FNEGATE, OP_ENTRY_POINT),
+    ];
+    for (a, b) in SWAPS {
+        if op == a {
+            return b;
+        } else if op == b {
+            return a;
+        }
+    }
+    op
+}
+
+fn decode_len(op: u32, len: u32) -> u32 {
+    let len = len + 1;
+    match op {
+        OP_VECTOR_SHUFFLE | OP_VECTOR_SHUFFLE_COMPACT => len + 4,
+        OP_DECORATE => len + 2,
+        OP_LOAD | OP_ACCESS_CHAIN => len + 3,
+        _ => len,
+    }
+}
+
+fn zig_decode(value: u32) -> u32 {
+    if value & 1 != 0 {
+        !(value >> 1)
+    } else {
+        value >> 1
+    }
+}
+
+// MemberDecorate operand counts for decorations smol-v knows about, or None
+// if the count is stored explicitly
+fn decoration_extra_ops(decoration: u32) -> Option<u32> {
+    match decoration {
+        // RelaxedPrecision, Block, BufferBlock, RowMajor, ColMajor
+        0 | 2..=5 => Some(0),
+        // Stream through XfbStride
+        29..=37 => Some(1),
+        _ => None,
+    }
+}
+
+struct SmolvReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> SmolvReader<'a> {
+    fn at_end(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        let value = *self.data.get(self.offset)
+            .ok_or(format!("unexpected end of SMOL-V data at {}", self.offset))?;
+        self.offset += 1;
+        Ok(value)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        let bytes = self.data.get(self.offset..self.offset + 4)
+            .ok_or(format!("unexpected end of SMOL-V data at {}", self.offset))?;
+        self.offset += 4;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_varint(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        let mut shift = 0;
+        loop {
+            let b = self.read_u8()?;
+            if shift < 32 {
+                value |= ((b & 0x7F) as u32) << shift;
+            }
+            shift += 7;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn read_length_op(&mut self) -> Result<(u32, u32), String> {
+        let value = self.read_varint()?;
+        let len = ((value >> 20) << 4) | ((value >> 4) & 0xF);
+        let op = remap_op(((value >> 4) & 0xFFF0) | (value & 0xF));
+        Ok((decode_len(op, len), op))
+    }
+}
+
+pub fn decode(data: &[u8]) -> Result<Vec<u32>, String> {
+    let mut reader = SmolvReader { data, offset: 0 };
+    if reader.read_u32()? != SMOLV_MAGIC {
+        return Err("bad SMOL-V magic".to_string());
+    }
+    let version = reader.read_u32()?;
+    let known_ops = known_ops_count(version >> 24)?;
+    let generator = reader.read_u32()?;
+    let bound = reader.read_u32()?;
+    let schema = reader.read_u32()?;
+    let decoded_size = reader.read_u32()? as usize;
+    debug_assert_eq!(reader.offset, SMOLV_HEADER_SIZE);
+
+    let mut out = Vec::with_capacity(decoded_size / 4);
+    out.extend([SPIRV_MAGIC, version & 0x00FFFFFF, generator, bound, schema]);
+
+    let op_data = |op: u32| OP_DATA[..known_ops].get(op as usize).copied().unwrap_or((0, 0, 0, 0));
+    let mut prev_result = 0u32;
+    let mut prev_decorate = 0u32;
+    while !reader.at_end() {
+        let (len, mut op) = reader.read_length_op()?;
+        let was_swizzle = op == OP_VECTOR_SHUFFLE_COMPACT;
+        if was_swizzle {
+            op = OP_VECTOR_SHUFFLE;
+        }
+        out.push((len << 16) | op);
+        let (has_result, has_type, delta_from_result, var_rest) = op_data(op);
+
+        let mut ioffs = 1;
+        if has_type != 0 {
+            out.push(reader.read_varint()?);
+            ioffs += 1;
+        }
+        if has_result != 0 {
+            let value = prev_result.wrapping_add(zig_decode(reader.read_varint()?));
+            out.push(value);
+            prev_result = value;
+            ioffs += 1;
+        }
+
+        // decoration targets are relative to the previous decoration's
+        if op == OP_DECORATE || op == OP_MEMBER_DECORATE {
+            let value = prev_decorate.wrapping_add(zig_decode(reader.read_varint()?));
+            out.push(value);
+            prev_decorate = value;
+            ioffs += 1;
+        }
+
+        // consecutive MemberDecorates of one struct are packed together
+        if op == OP_MEMBER_DECORATE {
+            let count = reader.read_u8()?;
+            let mut prev_index = 0u32;
+            let mut prev_offset = 0u32;
+            for m in 0..count {
+                let member_index = reader.read_varint()?.wrapping_add(prev_index);
+                prev_index = member_index;
+                let decoration = reader.read_varint()?;
+                let member_len = match decoration_extra_ops(decoration) {
+                    Some(extra) => 4 + extra,
+                    None => 4 + reader.read_varint()?,
+                };
+                // the first member's op and target were written above
+                if m != 0 {
+                    out.push((member_len << 16) | op);
+                    out.push(prev_decorate);
+                }
+                out.push(member_index);
+                out.push(decoration);
+                if decoration == DECORATION_OFFSET {
+                    let value = reader.read_varint()?.wrapping_add(prev_offset);
+                    out.push(value);
+                    prev_offset = value;
+                } else {
+                    for _ in 4..member_len {
+                        out.push(reader.read_varint()?);
+                    }
+                }
+            }
+            continue;
+        }
+
+        for _ in 0..delta_from_result {
+            if ioffs >= len {
+                break;
+            }
+            let delta = zig_decode(reader.read_varint()?);
+            out.push(prev_result.wrapping_sub(delta));
+            ioffs += 1;
+        }
+
+        if was_swizzle && len <= 9 {
+            let swizzle = reader.read_u8()? as u32;
+            for (i, shift) in [6, 4, 2, 0].iter().enumerate() {
+                if len > 5 + i as u32 {
+                    out.push((swizzle >> shift) & 3);
+                }
+            }
+        } else if var_rest != 0 {
+            while ioffs < len {
+                out.push(reader.read_varint()?);
+                ioffs += 1;
+            }
+        } else {
+            while ioffs < len {
+                out.push(reader.read_u32()?);
+                ioffs += 1;
+            }
+        }
+    }
+
+    if out.len() * 4 != decoded_size {
+        return Err(format!("decoded {} bytes of SPIR-V, expected {}", out.len() * 4, decoded_size));
+    }
+    Ok(out)
+}
+
+#[wasm_bindgen(js_name = "unity_smolv_decode")]
+pub fn decode_js(data: &[u8]) -> Result<Vec<u32>, String> {
+    profiling::time_stage("smolv_decode", || decode(data))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let mut data = Vec::new();
+        for word in [SMOLV_MAGIC, 0x00010000, 0, 5, 0, 13 * 4] {
+            data.extend(u32::to_le_bytes(word));
+        }
+        // OpCapability Shader
+        data.extend([0x91, 0x02, 0x01]);
+        // %1 = OpTypeVoid
+        data.extend([0x93, 0x02, 0x02]);
+        // %3 = OpLoad %1 %2
+        data.extend([0x01, 0x01, 0x04, 0x02]);
+        assert_eq!(decode(&data), Ok(vec![
+            SPIRV_MAGIC, 0x00010000, 0, 5, 0,
+            0x00020011, 1,
+            0x00020013, 1,
+            0x0004003D, 1, 3, 2,
+        ]));
+    }
+}
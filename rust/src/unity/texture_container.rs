@@ -0,0 +1,270 @@
+use crate::unity::types::wasm::TextureFormat;
+
+// Wraps a texture's compressed blocks in a DDS or KTX2 file as-is, so it can
+// be saved without decoding (and losing quality) first. DDS can only hold the
+// BCn formats; KTX2 also covers ETC, EAC and ASTC.
+
+pub struct BlockFormat {
+    block_width: u32,
+    block_height: u32,
+    block_size: u32,
+    dxgi_format: Option<u32>,
+    vk_format: u32,
+    // KTX2 data format descriptor info
+    color_model: u8,
+    // (bit offset, channel ID, qualifiers) per 64- or 128-bit sample
+    samples: &'static [(u16, u8, u8)],
+    sample_bits: u8,
+}
+
+const KHR_DF_MODEL_BC1A: u8 = 128;
+const KHR_DF_MODEL_BC3: u8 = 130;
+const KHR_DF_MODEL_BC4: u8 = 131;
+const KHR_DF_MODEL_BC5: u8 = 132;
+const KHR_DF_MODEL_BC6H: u8 = 133;
+const KHR_DF_MODEL_BC7: u8 = 134;
+const KHR_DF_MODEL_ETC2: u8 = 161;
+const KHR_DF_MODEL_ASTC: u8 = 162;
+
+const KHR_DF_SAMPLE_SIGNED: u8 = 0x4;
+const KHR_DF_SAMPLE_FLOAT: u8 = 0x8;
+
+const VK_FORMAT_ASTC_4X4_SFLOAT_BLOCK: u32 = 1000066000;
+
+fn bc(block_size: u32, dxgi_format: u32, vk_format: u32, color_model: u8, samples: &'static [(u16, u8, u8)]) -> BlockFormat {
+    BlockFormat {
+        block_width: 4,
+        block_height: 4,
+        block_size,
+        dxgi_format: Some(dxgi_format),
+        vk_format,
+        color_model,
+        samples,
+        sample_bits: 64,
+    }
+}
+
+fn etc(block_size: u32, vk_format: u32, samples: &'static [(u16, u8, u8)]) -> BlockFormat {
+    BlockFormat {
+        block_width: 4,
+        block_height: 4,
+        block_size,
+        dxgi_format: None,
+        vk_format,
+        color_model: KHR_DF_MODEL_ETC2,
+        samples,
+        sample_bits: 64,
+    }
+}
+
+// ASTC block sizes in the order Vulkan numbers them
+const ASTC_BLOCK_SIZES: [(u32, u32); 14] = [(4, 4), (5, 4), (5, 5), (6, 5), (6, 6), (8, 5), (8, 6), (8, 8), (10, 5), (10, 6), (10, 8), (10, 10), (12, 10), (12, 12)];
+
+fn astc(block_width: u32, block_height: u32, srgb: bool, hdr: bool) -> BlockFormat {
+    let index = ASTC_BLOCK_SIZES.iter().position(|&size| size == (block_width, block_height)).unwrap() as u32;
+    let vk_format = if hdr {
+        VK_FORMAT_ASTC_4X4_SFLOAT_BLOCK + index
+    } else {
+        157 + index * 2 + srgb as u32
+    };
+    let samples: &'static [(u16, u8, u8)] = if hdr {
+        &[(0, 0, KHR_DF_SAMPLE_FLOAT | KHR_DF_SAMPLE_SIGNED)]
+    } else {
+        &[(0, 0, 0)]
+    };
+    BlockFormat {
+        block_width,
+        block_height,
+        block_size: 16,
+        dxgi_format: None,
+        vk_format,
+        color_model: KHR_DF_MODEL_ASTC,
+        samples,
+        sample_bits: 128,
+    }
+}
+
+pub fn get_block_format(format: TextureFormat, srgb: bool) -> Option<BlockFormat> {
+    let s = srgb as u32;
+    let mut result = match format {
+        TextureFormat::DXT1 => bc(8, 71 + s, 133 + s, KHR_DF_MODEL_BC1A, &[(0, 1, 0)]),
+        TextureFormat::DXT5 => bc(16, 77 + s, 137 + s, KHR_DF_MODEL_BC3, &[(0, 15, 0), (64, 0, 0)]),
+        TextureFormat::BC4 => bc(8, 80, 139, KHR_DF_MODEL_BC4, &[(0, 0, 0)]),
+        TextureFormat::BC5 => bc(16, 83, 141, KHR_DF_MODEL_BC5, &[(0, 0, 0), (64, 1, 0)]),
+        TextureFormat::BC6H => bc(16, 95, 143, KHR_DF_MODEL_BC6H, &[(0, 0, KHR_DF_SAMPLE_FLOAT)]),
+        TextureFormat::BC7 => bc(16, 98 + s, 145 + s, KHR_DF_MODEL_BC7, &[(0, 0, 0)]),
+        // ETC1 is a subset of ETC2 RGB
+        TextureFormat::EtcRGB4 | TextureFormat::Etc2RGB4 => etc(8, 147 + s, &[(0, 2, 0)]),
+        TextureFormat::Etc2RGB4PunchthroughAlpha => etc(8, 149 + s, &[(0, 2, 0)]),
+        TextureFormat::Etc2RGBA8 => etc(16, 151 + s, &[(0, 15, 0), (64, 2, 0)]),
+        TextureFormat::EacR => etc(8, 153, &[(0, 0, 0)]),
+        TextureFormat::EacRSigned => etc(8, 154, &[(0, 0, KHR_DF_SAMPLE_SIGNED)]),
+        TextureFormat::EacRG => etc(16, 155, &[(0, 0, 0), (64, 1, 0)]),
+        TextureFormat::EacRGSigned => etc(16, 156, &[(0, 0, KHR_DF_SAMPLE_SIGNED), (64, 1, KHR_DF_SAMPLE_SIGNED)]),
+        TextureFormat::Astc4x4 => astc(4, 4, srgb, false),
+        TextureFormat::Astc5x5 => astc(5, 5, srgb, false),
+        TextureFormat::Astc6x6 => astc(6, 6, srgb, false),
+        TextureFormat::Astc8x8 => astc(8, 8, srgb, false),
+        TextureFormat::Astc10x10 => astc(10, 10, srgb, false),
+        TextureFormat::Astc12x12 => astc(12, 12, srgb, false),
+        TextureFormat::AstcHdr4x4 => astc(4, 4, false, true),
+        TextureFormat::AstcHdr5x5 => astc(5, 5, false, true),
+        TextureFormat::AstcHdr6x6 => astc(6, 6, false, true),
+        TextureFormat::AstcHdr8x8 => astc(8, 8, false, true),
+        TextureFormat::AstcHdr10x10 => astc(10, 10, false, true),
+        TextureFormat::AstcHdr12x12 => astc(12, 12, false, true),
+        _ => return None,
+    };
+    // 16-byte blocks split into two 64-bit samples, except where a single
+    // sample covers the whole block
+    if result.block_size == 16 && result.samples.len() == 1 {
+        result.sample_bits = 128;
+    }
+    Some(result)
+}
+
+impl BlockFormat {
+    fn level_size(&self, width: u32, height: u32, level: u32) -> usize {
+        let width = (width >> level).max(1);
+        let height = (height >> level).max(1);
+        let blocks_wide = width.div_ceil(self.block_width) as usize;
+        let blocks_high = height.div_ceil(self.block_height) as usize;
+        blocks_wide * blocks_high * self.block_size as usize
+    }
+
+    // Splits data into each mip level's blocks, largest first
+    fn split_levels<'a>(&self, width: u32, height: u32, level_count: u32, data: &'a [u8]) -> Result<Vec<&'a [u8]>, String> {
+        let mut result = Vec::with_capacity(level_count as usize);
+        let mut offset = 0;
+        for level in 0..level_count {
+            let size = self.level_size(width, height, level);
+            let level_data = data.get(offset..offset + size)
+                .ok_or(format!("mip level {} ({} bytes at {}) is past the end of the {} bytes of data", level, size, offset, data.len()))?;
+            result.push(level_data);
+            offset += size;
+        }
+        Ok(result)
+    }
+}
+
+const DDS_MAGIC: &[u8; 4] = b"DDS ";
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_HEIGHT: u32 = 0x2;
+const DDSD_WIDTH: u32 = 0x4;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDSD_LINEARSIZE: u32 = 0x80000;
+const DDPF_FOURCC: u32 = 0x4;
+const DDSCAPS_COMPLEX: u32 = 0x8;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const DDSCAPS_MIPMAP: u32 = 0x400000;
+const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+
+fn push_u32s(output: &mut Vec<u8>, values: &[u32]) {
+    for value in values {
+        output.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+pub fn write_dds(format: &BlockFormat, width: u32, height: u32, level_count: u32, data: &[u8]) -> Result<Vec<u8>, String> {
+    let dxgi_format = format.dxgi_format.ok_or("DDS only supports BC formats; use KTX2 instead")?;
+    let levels = format.split_levels(width, height, level_count, data)?;
+
+    let mut flags = DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT | DDSD_LINEARSIZE;
+    let mut caps = DDSCAPS_TEXTURE;
+    if level_count > 1 {
+        flags |= DDSD_MIPMAPCOUNT;
+        caps |= DDSCAPS_COMPLEX | DDSCAPS_MIPMAP;
+    }
+    let mut output = DDS_MAGIC.to_vec();
+    push_u32s(&mut output, &[124, flags, height, width, levels[0].len() as u32, 0, level_count]);
+    push_u32s(&mut output, &[0; 11]);
+    // the pixel format just points at the DX10 header that follows
+    push_u32s(&mut output, &[32, DDPF_FOURCC, u32::from_le_bytes(*b"DX10"), 0, 0, 0, 0, 0]);
+    push_u32s(&mut output, &[caps, 0, 0, 0, 0]);
+    push_u32s(&mut output, &[dxgi_format, D3D10_RESOURCE_DIMENSION_TEXTURE2D, 0, 1, 0]);
+    for level in levels {
+        output.extend_from_slice(level);
+    }
+    Ok(output)
+}
+
+const KTX2_IDENTIFIER: [u8; 12] = [0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+const KHR_DF_PRIMARIES_BT709: u32 = 1;
+const KHR_DF_TRANSFER_LINEAR: u32 = 1;
+const KHR_DF_TRANSFER_SRGB: u32 = 2;
+
+fn build_dfd(format: &BlockFormat, srgb: bool) -> Vec<u8> {
+    let block_size = 24 + 16 * format.samples.len() as u32;
+    let transfer = if srgb { KHR_DF_TRANSFER_SRGB } else { KHR_DF_TRANSFER_LINEAR };
+    let mut result = Vec::new();
+    push_u32s(&mut result, &[
+        4 + block_size,
+        // descriptor type and vendor are both 0 (basic, Khronos)
+        0,
+        block_size << 16 | 2,
+        format.color_model as u32 | KHR_DF_PRIMARIES_BT709 << 8 | transfer << 16,
+        (format.block_width - 1) | (format.block_height - 1) << 8,
+        format.block_size,
+        0,
+    ]);
+    for &(bit_offset, channel, qualifiers) in format.samples {
+        let (lower, upper) = if qualifiers & KHR_DF_SAMPLE_FLOAT != 0 {
+            ((-1.0f32).to_bits(), 1.0f32.to_bits())
+        } else if qualifiers & KHR_DF_SAMPLE_SIGNED != 0 {
+            (i32::MIN as u32, i32::MAX as u32)
+        } else {
+            (0, u32::MAX)
+        };
+        push_u32s(&mut result, &[
+            bit_offset as u32 | (format.sample_bits as u32 - 1) << 16 | ((channel | qualifiers << 4) as u32) << 24,
+            0,
+            lower,
+            upper,
+        ]);
+    }
+    result
+}
+
+fn align_to(output: &mut Vec<u8>, alignment: usize) {
+    while !output.len().is_multiple_of(alignment) {
+        output.push(0);
+    }
+}
+
+pub fn write_ktx2(format: &BlockFormat, srgb: bool, width: u32, height: u32, level_count: u32, data: &[u8]) -> Result<Vec<u8>, String> {
+    let levels = format.split_levels(width, height, level_count, data)?;
+    let dfd = build_dfd(format, srgb);
+
+    let header_size = 12 + 4 * 9 + 4 * 4 + 8 * 2;
+    let level_index_size = 24 * level_count as usize;
+    let dfd_offset = header_size + level_index_size;
+
+    let mut output = KTX2_IDENTIFIER.to_vec();
+    // vkFormat, typeSize, pixelWidth/Height/Depth, layerCount, faceCount,
+    // levelCount, supercompressionScheme
+    push_u32s(&mut output, &[format.vk_format, 1, width, height, 0, 0, 1, level_count, 0]);
+    // DFD offset and length, then empty key/value and supercompression data
+    push_u32s(&mut output, &[dfd_offset as u32, dfd.len() as u32, 0, 0]);
+    output.extend_from_slice(&[0; 16]);
+
+    // level data goes smallest first, each aligned to the block size
+    let mut data_offset = dfd_offset + dfd.len();
+    let mut level_offsets = vec![0; levels.len()];
+    for (level, level_data) in levels.iter().enumerate().rev() {
+        data_offset = data_offset.div_ceil(format.block_size as usize) * format.block_size as usize;
+        level_offsets[level] = data_offset;
+        data_offset += level_data.len();
+    }
+    for (level_data, offset) in levels.iter().zip(&level_offsets) {
+        output.extend_from_slice(&(*offset as u64).to_le_bytes());
+        output.extend_from_slice(&(level_data.len() as u64).to_le_bytes());
+        output.extend_from_slice(&(level_data.len() as u64).to_le_bytes());
+    }
+    output.extend_from_slice(&dfd);
+    for level_data in levels.iter().rev() {
+        align_to(&mut output, format.block_size as usize);
+        output.extend_from_slice(level_data);
+    }
+    Ok(output)
+}
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+
+use serde::{Deserialize, Serialize, Serializer};
+use serde::ser::SerializeMap;
+use wasm_bindgen::prelude::*;
+
+use crate::unity::types::schema::children;
+use crate::unity::types::serialized_file::TypeTreeField;
+
+// Reads objects structurally by walking a type tree, for classes we have no
+// parser for (mostly MonoBehaviours, whose fields are defined by the game's
+// scripts). The result is a plain JS object keyed by the type tree's field
+// names, e.g. { m_GameObject: { m_FileID, m_PathID }, m_Enabled, ... }.
+
+// kAlignBytes: the field is followed by padding to 4 bytes
+const META_FLAG_ALIGN: u32 = 0x4000;
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone)]
+pub enum TypeTreeValue {
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    I64(i64),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    // UInt8 arrays and TypelessData
+    Bytes(Vec<u8>),
+    Array(Vec<TypeTreeValue>),
+    Object(Vec<(String, TypeTreeValue)>),
+}
+
+impl Serialize for TypeTreeValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TypeTreeValue::Bool(v) => serializer.serialize_bool(*v),
+            TypeTreeValue::I32(v) => serializer.serialize_i32(*v),
+            TypeTreeValue::U32(v) => serializer.serialize_u32(*v),
+            TypeTreeValue::I64(v) => serializer.serialize_i64(*v),
+            TypeTreeValue::U64(v) => serializer.serialize_u64(*v),
+            TypeTreeValue::F32(v) => serializer.serialize_f32(*v),
+            TypeTreeValue::F64(v) => serializer.serialize_f64(*v),
+            TypeTreeValue::String(v) => serializer.serialize_str(v),
+            TypeTreeValue::Bytes(v) => serializer.serialize_bytes(v),
+            TypeTreeValue::Array(v) => v.serialize(serializer),
+            TypeTreeValue::Object(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (name, value) in fields {
+                    map.serialize_entry(name, value)?;
+                }
+                map.end()
+            },
+        }
+    }
+}
+
+impl TypeTreeValue {
+    // 64-bit integers (e.g. path IDs) become BigInts, like elsewhere
+    pub fn to_js(&self) -> Result<JsValue, String> {
+        let serializer = serde_wasm_bindgen::Serializer::new()
+            .serialize_maps_as_objects(true)
+            .serialize_large_number_types_as_bigints(true);
+        self.serialize(&serializer)
+            .map_err(|err| format!("Couldn't convert type tree value: {:?}", err))
+    }
+}
+
+struct TypeTreeReader<'a> {
+    fields: &'a [TypeTreeField],
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> TypeTreeReader<'a> {
+    fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.offset.checked_add(count)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| format!("read of {} bytes at {} runs past the end ({} bytes)", count, self.offset, self.data.len()))?;
+        let result = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(result)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    fn read_count(&mut self) -> Result<usize, String> {
+        let count = i32::from_le_bytes(self.read_array()?);
+        let remaining = self.data.len() - self.offset;
+        match usize::try_from(count) {
+            Ok(count) if count <= remaining => Ok(count),
+            _ => Err(format!("bad array length {} at {}", count, self.offset - 4)),
+        }
+    }
+
+    fn align(&mut self) {
+        self.offset = (self.offset + 3) & !3;
+    }
+
+    fn read_node(&mut self, index: usize, depth: usize) -> Result<TypeTreeValue, String> {
+        if depth > MAX_DEPTH {
+            return Err("type tree is nested too deeply".to_string());
+        }
+        let field = &self.fields[index];
+        let child_indices = children(self.fields, index);
+        let value = match field.type_name.as_str() {
+            "bool" => TypeTreeValue::Bool(self.read_array::<1>()?[0] != 0),
+            "SInt8" => TypeTreeValue::I32(i8::from_le_bytes(self.read_array()?) as i32),
+            "UInt8" | "char" => TypeTreeValue::U32(self.read_array::<1>()?[0] as u32),
+            "SInt16" | "short" => TypeTreeValue::I32(i16::from_le_bytes(self.read_array()?) as i32),
+            "UInt16" | "unsigned short" => TypeTreeValue::U32(u16::from_le_bytes(self.read_array()?) as u32),
+            "SInt32" | "int" => TypeTreeValue::I32(i32::from_le_bytes(self.read_array()?)),
+            "UInt32" | "unsigned int" | "Type*" => TypeTreeValue::U32(u32::from_le_bytes(self.read_array()?)),
+            "SInt64" | "long long" => TypeTreeValue::I64(i64::from_le_bytes(self.read_array()?)),
+            "UInt64" | "unsigned long long" | "FileSize" => TypeTreeValue::U64(u64::from_le_bytes(self.read_array()?)),
+            "float" => TypeTreeValue::F32(f32::from_le_bytes(self.read_array()?)),
+            "double" => TypeTreeValue::F64(f64::from_le_bytes(self.read_array()?)),
+            "string" => {
+                let len = self.read_count()?;
+                let value = String::from_utf8_lossy(self.read_bytes(len)?).into_owned();
+                // the alignment is usually on the inner char array
+                if child_indices.iter().any(|&i| self.fields[i].meta_flags & META_FLAG_ALIGN != 0) {
+                    self.align();
+                }
+                TypeTreeValue::String(value)
+            },
+            "TypelessData" => {
+                let len = self.read_count()?;
+                TypeTreeValue::Bytes(self.read_bytes(len)?.to_vec())
+            },
+            "Array" => self.read_array_node(&child_indices, depth)?,
+            // e.g. vector, map and staticvector, which just wrap an Array
+            _ if child_indices.len() == 1 && self.fields[child_indices[0]].type_name == "Array" => {
+                self.read_node(child_indices[0], depth + 1)?
+            },
+            _ => {
+                let mut object = Vec::with_capacity(child_indices.len());
+                for &child_index in &child_indices {
+                    let value = self.read_node(child_index, depth + 1)?;
+                    object.push((self.fields[child_index].name.clone(), value));
+                }
+                TypeTreeValue::Object(object)
+            },
+        };
+        if field.meta_flags & META_FLAG_ALIGN != 0 {
+            self.align();
+        }
+        Ok(value)
+    }
+
+    // Arrays have two children: the size, then the element type
+    fn read_array_node(&mut self, child_indices: &[usize], depth: usize) -> Result<TypeTreeValue, String> {
+        let &[_size, data] = child_indices else {
+            return Err("malformed Array node".to_string());
+        };
+        let count = self.read_count()?;
+        if matches!(self.fields[data].type_name.as_str(), "UInt8" | "SInt8" | "char") {
+            return Ok(TypeTreeValue::Bytes(self.read_bytes(count)?.to_vec()));
+        }
+        let mut values = Vec::with_capacity(count);
+        for _ in 0..count {
+            values.push(self.read_node(data, depth + 1)?);
+        }
+        Ok(TypeTreeValue::Array(values))
+    }
+}
+
+// Reads an object using the type tree rooted at the first field. Errors if
+// the type tree doesn't account for all of the object's bytes, since that
+// usually means it's for a different version of the script.
+pub fn read_type_tree(fields: &[TypeTreeField], data: &[u8]) -> Result<TypeTreeValue, String> {
+    if fields.is_empty() {
+        return Err("empty type tree".to_string());
+    }
+    let mut reader = TypeTreeReader { fields, data, offset: 0 };
+    let value = reader.read_node(0, 0)?;
+    if reader.offset != data.len() {
+        return Err(format!("type tree read {} bytes, but the object is {} bytes", reader.offset, data.len()));
+    }
+    Ok(value)
+}
+
+// One node of a type tree dump, as produced by type tree generators for a
+// game's script assemblies. Accepts both our field names and Unity's.
+#[derive(Deserialize, Debug, Clone)]
+struct ScriptTypeNode {
+    #[serde(alias = "m_Level")]
+    level: u8,
+    #[serde(alias = "m_Type")]
+    type_name: String,
+    #[serde(alias = "m_Name")]
+    name: String,
+    #[serde(alias = "m_ByteSize", default)]
+    byte_size: i32,
+    #[serde(alias = "m_MetaFlag", default)]
+    meta_flags: u32,
+}
+
+// Player builds usually strip type trees, so MonoBehaviours can only be read
+// structurally with the script types supplied from elsewhere. Keys are class
+// names, with or without their namespace (e.g. "Game.Level.Spawner").
+#[wasm_bindgen(js_name = "UnityScriptTypeDatabase")]
+pub struct ScriptTypeDatabase {
+    types: HashMap<String, Vec<TypeTreeField>>,
+}
+
+#[wasm_bindgen(js_class = "UnityScriptTypeDatabase")]
+impl ScriptTypeDatabase {
+    // Takes a parsed JSON dump, mapping each class name to its flattened
+    // nodes: { "Spawner": [{ level, type_name, name, byte_size, meta_flags }, ...] }
+    #[wasm_bindgen(constructor)]
+    pub fn new(dump: JsValue) -> Result<ScriptTypeDatabase, String> {
+        let dump: HashMap<String, Vec<ScriptTypeNode>> = serde_wasm_bindgen::from_value(dump)
+            .map_err(|err| format!("Couldn't read script type database: {:?}", err))?;
+        let types = dump.into_iter()
+            .map(|(class_name, nodes)| {
+                let fields = nodes.into_iter()
+                    .map(|node| TypeTreeField {
+                        level: node.level,
+                        type_name: node.type_name,
+                        name: node.name,
+                        byte_size: node.byte_size,
+                        meta_flags: node.meta_flags,
+                    })
+                    .collect();
+                (class_name, fields)
+            })
+            .collect();
+        Ok(Self { types })
+    }
+
+    pub fn has_type(&self, namespace: &str, class_name: &str) -> bool {
+        self.get_fields(namespace, class_name).is_some()
+    }
+
+    // Reads a whole MonoBehaviour, using its MonoScript's namespace and class
+    // name to find the type
+    pub fn read_mono_behavior(&self, namespace: &str, class_name: &str, data: &[u8]) -> Result<JsValue, String> {
+        let fields = self.get_fields(namespace, class_name)
+            .ok_or_else(|| format!("no type for {}", class_name))?;
+        read_type_tree(fields, data)?.to_js()
+    }
+}
+
+impl ScriptTypeDatabase {
+    pub fn get_fields(&self, namespace: &str, class_name: &str) -> Option<&[TypeTreeField]> {
+        let full_name = if namespace.is_empty() {
+            class_name.to_string()
+        } else {
+            format!("{}.{}", namespace, class_name)
+        };
+        self.types.get(&full_name)
+            .or_else(|| self.types.get(class_name))
+            .map(|fields| fields.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(level: u8, type_name: &str, name: &str, meta_flags: u32) -> TypeTreeField {
+        TypeTreeField { level, type_name: type_name.to_string(), name: name.to_string(), byte_size: -1, meta_flags }
+    }
+
+    #[test]
+    fn test_read_type_tree() {
+        let fields = vec![
+            field(0, "MonoBehaviour", "Base", 0),
+            field(1, "UInt8", "m_Enabled", META_FLAG_ALIGN),
+            field(1, "string", "m_Name", 0),
+            field(2, "Array", "Array", META_FLAG_ALIGN),
+            field(3, "int", "size", 0),
+            field(3, "char", "data", 0),
+            field(1, "vector", "points", 0),
+            field(2, "Array", "Array", 0),
+            field(3, "int", "size", 0),
+            field(3, "float", "data", 0),
+        ];
+        let mut data = vec![1, 0, 0, 0];
+        data.extend(3i32.to_le_bytes());
+        data.extend(b"abc\0");
+        data.extend(2i32.to_le_bytes());
+        data.extend(1.0f32.to_le_bytes());
+        data.extend(2.0f32.to_le_bytes());
+        let TypeTreeValue::Object(object) = read_type_tree(&fields, &data).unwrap() else {
+            panic!("expected an object");
+        };
+        assert_eq!(object.len(), 3);
+        assert!(matches!(&object[1].1, TypeTreeValue::String(s) if s == "abc"));
+        assert!(matches!(&object[2].1, TypeTreeValue::Array(points) if points.len() == 2));
+
+        // trailing bytes mean the type tree is wrong
+        data.push(0);
+        assert!(read_type_tree(&fields, &data).is_err());
+    }
+}
@@ -1,14 +1,18 @@
 
 use deku::prelude::*;
+use noclip_macros::ParserSchema;
 
 // https://github.com/AssetRipper/TypeTreeDumps/blob/main/StructsDump/release/2019.4.39f1.dump
-// e.g. Outer Wilds
+// e.g. Outer Wilds, with fields gated on UnityVersion back to 4.x (e.g.
+// Gone Home) and forward to 2022.3
 
-use super::common::{CharArray, ColorRGBA, Map, Matrix4x4, PPtr, Packedf32Vec, Packedi32Vec, Quaternion, UnityArray, Vec2, Vec3, Vec4, AABB, UnityVersion};
+use super::serialized_file::Guid;
+use super::common::{CharArray, ColorRGBA, Map, Matrix4x4, PPtr, Packedf32Vec, Packedi32Vec, Quaternion, Rect, UnityArray, Vec2, Vec3, Vec4, AABB, UnityVersion, check_array_length};
 
-#[derive(DekuRead, Clone, Debug)]
-#[deku(ctx = "_version: UnityVersion")]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
 pub struct GameObject {
+    #[deku(reader = "GameObject::read_components(deku::reader, version)")]
     pub components: UnityArray<PPtr<Component>>,
     pub layer: u32,
     pub name: CharArray,
@@ -16,12 +20,31 @@ pub struct GameObject {
     pub is_active: u8,
 }
 
+impl GameObject {
+    // Before 5.5, each component was stored along with its class ID
+    fn read_components<R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>, version: UnityVersion) -> Result<UnityArray<PPtr<Component>>, DekuError> {
+        if version >= UnityVersion::V2017_3_0f3 {
+            return UnityArray::from_reader_with_ctx(reader, ());
+        }
+        let pairs = UnityArray::<ComponentPair>::from_reader_with_ctx(reader, ())?;
+        Ok(UnityArray {
+            values: pairs.values.into_iter().map(|pair| pair.component).collect(),
+        })
+    }
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct ComponentPair {
+    pub class_id: i32,
+    pub component: PPtr<Component>,
+}
+
 #[derive(DekuRead, Clone, Debug)]
 pub struct Component {
     pub game_object: PPtr<GameObject>,
 }
 
-#[derive(DekuRead, Clone, Debug)]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
 #[deku(ctx = "_version: UnityVersion")]
 pub struct Transform {
     pub game_object: PPtr<GameObject>,
@@ -32,13 +55,21 @@ pub struct Transform {
     pub parent: PPtr<Transform>,
 }
 
-#[derive(DekuRead, Clone, Debug)]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
 #[deku(ctx = "version: UnityVersion")]
 pub struct Material {
     pub name: CharArray,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
     pub shader: PPtr<()>,
-    #[deku(cond = "version < UnityVersion::V2021_3_27f1")]
+    // material variants
+    #[deku(cond = "version >= UnityVersion::V2022_3_0f1")]
+    pub parent: Option<PPtr<Material>>,
+    #[deku(cond = "version >= UnityVersion::V2022_3_0f1")]
+    pub modified_serialized_properties: Option<i32>,
+    // a list until 5.0, then a space-separated string until 2021.2
+    #[deku(cond = "version < UnityVersion::V5_4_6f1")]
+    pub legacy_shader_keywords: Option<UnityArray<CharArray>>,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1 && version < UnityVersion::V2021_3_27f1")]
     pub shader_keywords: Option<CharArray>,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
     #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
@@ -47,14 +78,26 @@ pub struct Material {
     #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
     pub invalid_keywords: Option<UnityArray<CharArray>>,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment3: Vec<u8>,
+    // 5.0 and up
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "0")]
     pub lightmap_flags: u32,
+    // 5.6 and up
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "0")]
     pub enable_instancing_variants: u8,
+    // 2017.1 and up
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "0")]
     pub double_sided_gi: u8,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment4: Vec<u8>,
     pub custom_render_queue: u32,
+    // 5.1 and up
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "Map::default()")]
     pub string_tag_map: Map<CharArray, CharArray>,
+    // 5.6 and up
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "UnityArray::default()")]
     pub disabled_shader_passes: UnityArray<CharArray>,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment5: Vec<u8>,
+    #[deku(cond = "version >= UnityVersion::V2022_3_0f1")]
+    pub locked_properties: Option<CharArray>,
     pub tex_envs: Map<CharArray, TexEnv>,
     #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
     pub ints: Option<Map<CharArray, i32>>,
@@ -62,6 +105,8 @@ pub struct Material {
     pub colors: Map<CharArray, ColorRGBA>,
     #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
     pub build_texture_stacks: Option<UnityArray<BuildTextureStackReference>>,
+    #[deku(cond = "version >= UnityVersion::V2022_3_0f1")]
+    pub allow_locking: Option<u8>,
 }
 
 #[derive(DekuRead, Clone, Debug)]
@@ -70,7 +115,7 @@ pub struct BuildTextureStackReference {
     pub item_name: CharArray,
 }
 
-#[derive(DekuRead, Clone, Debug)]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
 pub struct TexEnv {
     pub texture: PPtr<Texture>,
     pub scale: Vec2,
@@ -84,75 +129,132 @@ pub struct Texture {
     pub downscale_fallback: u8,
 }
 
-#[derive(DekuRead, Clone, Debug)]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
 #[deku(ctx = "version: UnityVersion")]
 pub struct MeshRenderer {
     pub game_object: PPtr<GameObject>,
     pub enabled: u8,
     pub cast_shadows: u8,
     pub receive_shadows: u8,
+    // before 5.0 this was a byte, and came first
+    #[deku(cond = "version < UnityVersion::V5_4_6f1")]
+    pub legacy_lightmap_index: Option<u8>,
+    // 2017.2 and up
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "1")]
     pub dynamic_occludee: u8,
     #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
     pub static_shadow_caster: Option<u8>,
+    // motion vectors and probe usage are 5.4 and up
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "1")]
     pub motion_vectors: u8,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "0")]
     pub light_probe_usage: u8,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "0")]
     pub reflection_probe_usage: u8,
+    // 2019.3 and up
+    #[deku(cond = "version >= UnityVersion::V2019_4_39f1", default = "0")]
     pub ray_tracing_mode: u8,
     #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
     pub ray_trace_procedural: Option<u8>,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    // 2018.1 and up; before that everything was on the default layer
+    #[deku(cond = "version >= UnityVersion::V2018_1_9f2", default = "1")]
     pub rendering_layer_mask: u32,
+    // 2018.3 and up
+    #[deku(cond = "version >= UnityVersion::V2018_4_36f1", default = "0")]
     pub renderer_priority: i32,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "widen_lightmap_index(*legacy_lightmap_index)")]
     pub lightmap_index: u16,
+    // dynamic (realtime GI) lightmaps are 5.0 and up
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "0xFFFF")]
     pub lightmap_index_dynamic: u16,
     pub lightmap_tiling_offset: Vec4,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "Vec4 { x: 1.0, y: 1.0, z: 0.0, w: 0.0 }")]
     pub lightmap_tiling_offset_dynamic: Vec4,
     pub materials: UnityArray<PPtr<Material>>,
+    // before 5.5, static batches were described by the submeshes they used
+    #[deku(cond = "version < UnityVersion::V2017_3_0f3", default = "UnityArray::default()")]
+    pub subset_indices: UnityArray<u32>,
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "StaticBatchInfo::default()")]
     pub static_batch_info: StaticBatchInfo,
     pub static_batch_root: PPtr<Transform>,
+    // replaced by light_probe_usage in 5.4
+    #[deku(cond = "version < UnityVersion::V5_4_6f1", default = "0")]
+    pub use_light_probes: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment2: Vec<u8>,
     pub probe_anchor: PPtr<Transform>,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "PPtr::default()")]
     pub light_probe_volume_override: PPtr<GameObject>,
     pub sorting_layer_id: i32,
+    // 5.6 and up
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "0")]
     pub sorting_layer: i16,
     pub sorting_order: i16,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    // 5.0 and up
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "PPtr::default()")]
     pub additional_vertex_streams: PPtr<Mesh>,
     #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
     pub enlighten_vertex_streams: Option<PPtr<Mesh>>,
 }
 
-#[derive(DekuRead, Clone, Debug)]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
 #[deku(ctx = "version: UnityVersion")]
 pub struct Mesh {
     pub name: CharArray,
+    #[deku(ctx = "version")]
     pub submeshes: UnityArray<SubMesh>,
+    // the blend shape layout here is from 4.3 and up
     pub shapes: BlendShapeData,
     pub bind_pose: UnityArray<Matrix4x4>,
     pub bone_name_hashes: UnityArray<u32>,
     pub root_bone_name_hash: u32,
+    // 2019.1 and up
+    #[deku(cond = "version >= UnityVersion::V2019_4_39f1", default = "UnityArray::default()")]
     pub bones_aabb: UnityArray<AABB>,
+    #[deku(cond = "version >= UnityVersion::V2019_4_39f1", default = "UnityArray::default()")]
     pub variable_bone_count_weights: UnityArray<u32>,
     pub mesh_compression: MeshCompression,
     pub is_readable: u8,
     pub keep_vertices: u8,
     pub keep_indices: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    // 2017.3 and up; before that indices were always 16-bit
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "IndexFormat::UInt16")]
     pub index_format: IndexFormat,
     pub index_buffer: ByteArray,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment2: Vec<u8>,
+    // before 2018.2, skinning weights were stored apart from the vertex data
+    #[deku(cond = "version < UnityVersion::V2018_2_21f1", default = "UnityArray::default()")]
+    pub skin: UnityArray<BoneWeights4>,
     #[deku(ctx = "version")]
     pub vertex_data: VertexData,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment3: Vec<u8>,
+    #[deku(ctx = "version")]
     pub compressed_mesh: CompressedMesh,
     pub local_aabb: AABB,
     pub mesh_usage_flags: i32,
+    // collision meshes were cooked at runtime before 5.0
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "ByteArray::default()")]
     pub baked_convex_collision_mesh: ByteArray,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment4: Vec<u8>,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "ByteArray::default()")]
     pub baked_triangle_collision_mesh: ByteArray,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment5: Vec<u8>,
+    // 2018.2 and up
+    #[deku(cond = "version >= UnityVersion::V2018_2_21f1", default = "[1.0, 1.0]")]
     pub mesh_metrics: [f32; 2],
-    #[deku(ctx = "version")]
+    // 2018.3 and up; older meshes always keep their vertices inline
+    #[deku(ctx = "version", cond = "version >= UnityVersion::V2018_4_36f1", default = "StreamingInfo::default()")]
     pub streaming_info: StreamingInfo,
 }
 
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct BoneWeights4 {
+    pub weight: [f32; 4],
+    pub bone_index: [i32; 4],
+}
+
 #[derive(DekuRead, Clone, Copy, Debug)]
 #[deku(id_type = "i32")]
 pub enum IndexFormat {
@@ -169,7 +271,7 @@ pub enum MeshCompression {
     High = 3,
 }
 
-#[derive(DekuRead, Clone, Debug)]
+#[derive(DekuRead, ParserSchema, Clone, Debug, Default)]
 #[deku(ctx = "version: UnityVersion")]
 pub struct StreamingInfo {
     #[deku(ctx = "version")]
@@ -181,12 +283,18 @@ pub struct StreamingInfo {
 #[derive(DekuRead, Clone, Debug)]
 #[deku(ctx = "version: UnityVersion", id = "version")]
 pub enum StreamingInfoOffset {
-    #[deku(id_pat = "UnityVersion::V2019_4_39f1")]
+    #[deku(id_pat = "UnityVersion::V4_7_2f1 | UnityVersion::V5_4_6f1 | UnityVersion::V2017_3_0f3 | UnityVersion::V2018_1_9f2 | UnityVersion::V2018_2_21f1 | UnityVersion::V2018_4_36f1 | UnityVersion::V2019_4_39f1")]
     Small(u32),
     #[deku(id_pat = "_")]
     Big(u64),
 }
 
+impl Default for StreamingInfoOffset {
+    fn default() -> Self {
+        StreamingInfoOffset::Small(0)
+    }
+}
+
 impl From<StreamingInfoOffset> for u64 {
     fn from(value: StreamingInfoOffset) -> Self {
         match value {
@@ -196,26 +304,45 @@ impl From<StreamingInfoOffset> for u64 {
     }
 }
 
-#[derive(DekuRead, Clone, Debug)]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
 pub struct SubMesh {
     pub first_byte: u32,
     pub index_count: u32,
     pub topology: i32,
+    // 2017.3 and up
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "0")]
     pub base_vertex: u32,
     pub first_vertex: u32,
     pub vertex_count: u32,
     pub local_aabb: AABB,
 }
 
-#[derive(DekuRead, Clone, Debug)]
-#[deku(ctx = "_version: UnityVersion")]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
 pub struct VertexData {
+    // removed in 2018.1
+    #[deku(cond = "version < UnityVersion::V2018_1_9f2", default = "0")]
+    pub current_channels: u32,
     pub vertex_count: u32,
+    #[deku(ctx = "version")]
     pub channels: UnityArray<ChannelInfo>,
+    // since 5.0 the stream layout is derived from the channels instead
+    #[deku(cond = "version < UnityVersion::V5_4_6f1", default = "UnityArray::default()")]
+    pub streams: UnityArray<StreamInfo>,
     pub data: ByteArray,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
 }
 
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct StreamInfo {
+    pub channel_mask: u32,
+    pub offset: u32,
+    pub stride: u8,
+    pub divider_op: u8,
+    pub frequency: u16,
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct ByteArray {
     pub data: Vec<u8>,
@@ -224,6 +351,7 @@ pub struct ByteArray {
 impl<'a, Ctx> DekuReader<'a, Ctx> for ByteArray where Ctx: Copy {
     fn from_reader_with_ctx<R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>, _ctx: Ctx) -> Result<Self, DekuError> {
         let count = i32::from_reader_with_ctx(reader, ())? as usize;
+        check_array_length(count, 1)?;
         let mut buf = vec![0x00; count];
         reader.read_bytes(count, &mut buf)?;
         Ok(ByteArray{ data: buf })
@@ -236,25 +364,37 @@ impl From<ByteArray> for Vec<u8> {
     }
 }
 
-#[derive(DekuRead, Clone, Debug)]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
 pub struct CompressedMesh {
     pub vertices: Packedf32Vec,
     pub uv: Packedf32Vec,
+    // removed in 5.0
+    #[deku(cond = "version < UnityVersion::V5_4_6f1")]
+    pub bind_poses: Option<Packedf32Vec>,
     pub normals: Packedf32Vec,
     pub tangents: Packedf32Vec,
     pub weights: Packedi32Vec,
     pub normal_signs: Packedi32Vec,
     pub tangent_signs: Packedi32Vec,
+    // before 5.0, colors were packed as bytes after the triangles
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "Packedf32Vec::default()")]
     pub float_colors: Packedf32Vec,
     pub bone_indices: Packedi32Vec,
     pub triangles: Packedi32Vec,
+    #[deku(cond = "version < UnityVersion::V5_4_6f1")]
+    pub colors: Option<Packedi32Vec>,
+    // 5.0 and up
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "0")]
     pub uv_info: u32,
 }
 
-#[derive(DekuRead, Clone, Debug)]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
 pub struct ChannelInfo {
     pub stream: u8,
     pub offset: u8,
+    #[deku(reader = "VertexFormat::read_versioned(deku::reader, version)")]
     pub format: VertexFormat,
     #[deku(bits = "1", pad_bits_after = "4")]
     pub instance_data: u8,
@@ -279,6 +419,23 @@ pub enum VertexFormat {
     #[deku(id = "11")] SInt32,
 }
 
+impl VertexFormat {
+    // Before 2019.1, channels used a shorter list of formats
+    fn read_versioned<R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>, version: UnityVersion) -> Result<Self, DekuError> {
+        if version >= UnityVersion::V2019_4_39f1 {
+            return VertexFormat::from_reader_with_ctx(reader, ());
+        }
+        match u8::from_reader_with_ctx(reader, ())? {
+            0 => Ok(VertexFormat::Float),
+            1 => Ok(VertexFormat::Float16),
+            2 => Ok(VertexFormat::UNorm8), // "Color"
+            3 => Ok(VertexFormat::UInt8),
+            4 => Ok(VertexFormat::UInt32),
+            v => Err(DekuError::Parse(format!("invalid legacy vertex format {}", v).into())),
+        }
+    }
+}
+
 #[derive(DekuRead, Clone, Debug)]
 pub struct BlendShapeData {
     pub vertices: UnityArray<BlendShapeVertex>,
@@ -312,18 +469,29 @@ pub struct BlendShapeVertex {
     pub index: u32,
 }
 
-#[derive(DekuRead, Clone, Debug)]
+// 255 meant no lightmap when the index was a byte
+fn widen_lightmap_index(index: Option<u8>) -> u16 {
+    match index {
+        Some(0xFF) | None => 0xFFFF,
+        Some(index) => index as u16,
+    }
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug, Default)]
 pub struct StaticBatchInfo {
     pub first_submesh: u16,
     pub submesh_count: u16,
 }
 
-#[derive(DekuRead, Clone, Debug)]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
 #[deku(ctx = "version: UnityVersion")]
 pub struct Texture2D {
     pub name: CharArray,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    // 2017.3 and up
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "0")]
     pub forced_fallback_format: i32,
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "0")]
     pub downscale_fallback: u8,
     #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
     pub is_alpha_channel_optional: u8,
@@ -334,12 +502,17 @@ pub struct Texture2D {
     #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
     pub mips_stripped: Option<i32>,
     pub texture_format: TextureFormat,
+    // 5.2 and up; before that there was only a flag, in the boolean settings
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "1")]
     pub mip_count: i32,
     #[deku(ctx = "version")]
     pub settings: TextureBooleanSettings,
+    // 2018.2 and up
+    #[deku(cond = "version >= UnityVersion::V2018_2_21f1", default = "0")]
     pub streaming_mipmaps_priority: i32,
     pub image_count: i32,
     pub texture_dimension: i32,
+    #[deku(ctx = "version")]
     pub texture_settings: GLTextureSettings,
     pub lightmap_format: i32,
     pub color_space: ColorSpace,
@@ -348,13 +521,41 @@ pub struct Texture2D {
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment2: Vec<u8>,
     pub data: ByteArray,
     #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment3: Vec<u8>,
-    #[deku(ctx = "version")]
+    // 5.3 and up
+    #[deku(ctx = "version", cond = "version >= UnityVersion::V5_4_6f1", default = "StreamingInfo::default()")]
     pub streaming_info: StreamingInfo,
 }
 
 #[derive(DekuRead, Debug, Clone)]
 #[deku(ctx = "version: UnityVersion", id = "version")]
 pub enum TextureBooleanSettings {
+    #[deku(id_pat = "UnityVersion::V4_7_2f1")]
+    V4 {
+        mip_map: u8,
+        is_readable: u8,
+        read_allowed: u8,
+        _alignment: u8,
+    },
+    // read_allowed was removed in 5.5
+    #[deku(id_pat = "UnityVersion::V5_4_6f1")]
+    V5 {
+        is_readable: u8,
+        read_allowed: u8,
+        _alignment: [u8; 2],
+    },
+    // no streaming mipmaps before 2018.2
+    #[deku(id_pat = "UnityVersion::V2017_3_0f3 | UnityVersion::V2018_1_9f2")]
+    V2017 {
+        is_readable: u8,
+        _alignment: [u8; 3],
+    },
+    #[deku(id_pat = "UnityVersion::V2018_2_21f1 | UnityVersion::V2018_4_36f1")]
+    V2018 {
+        is_readable: u8,
+        streaming_mipmaps: u8,
+        _alignment: [u8; 2],
+    },
+    // ignore_master_texture_limit is 2019.3 and up
     #[deku(id_pat = "UnityVersion::V2019_4_39f1")]
     V2019 {
         is_readable: u8,
@@ -371,13 +572,17 @@ pub enum TextureBooleanSettings {
     }
 }
 
-#[derive(DekuRead, Clone, Debug)]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
 pub struct GLTextureSettings {
     pub filter_mode: TextureFilterMode,
     pub aniso: i32,
     pub mip_bias: f32,
+    // a single wrap mode for all axes before 2017.1
     pub wrap_u: TextureWrapMode,
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "wrap_u.clone()")]
     pub wrap_v: TextureWrapMode,
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "wrap_u.clone()")]
     pub wrap_w: TextureWrapMode,
 }
 
@@ -521,7 +726,7 @@ pub enum ColorSpace {
     SRGB   = 0x01,
 }
 
-#[derive(DekuRead, Clone, Debug)]
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
 #[deku(ctx = "_version: UnityVersion")]
 pub struct MeshFilter {
     pub game_object: PPtr<GameObject>,
@@ -534,3 +739,2200 @@ pub struct ScriptMapper {
     pub shader_to_name_map: Map<PPtr<()>, CharArray>,
     pub preload_shaders: bool,
 }
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct Animator {
+    pub game_object: PPtr<GameObject>,
+    pub enabled: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub avatar: PPtr<Avatar>,
+    pub controller: PPtr<()>,
+    pub culling_mode: AnimatorCullingMode,
+    pub update_mode: AnimatorUpdateMode,
+    pub apply_root_motion: u8,
+    pub linear_velocity_blending: u8,
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub stabilize_feet: Option<u8>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    pub has_transform_hierarchy: u8,
+    pub allow_constant_clip_sampling_optimization: u8,
+    pub keep_animator_controller_state_on_disable: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment2: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+#[deku(id_type = "i32")]
+pub enum AnimatorCullingMode {
+    AlwaysAnimate = 0,
+    CullUpdateTransforms = 1,
+    CullCompletely = 2,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+#[deku(id_type = "i32")]
+pub enum AnimatorUpdateMode {
+    Normal = 0,
+    AnimatePhysics = 1,
+    UnscaledTime = 2,
+}
+
+// The controller itself is stored as a baked "constant" blob (see
+// https://github.com/Perfare/AssetStudio/blob/master/AssetStudio/Classes/AnimatorController.cs),
+// which is mostly state machine evaluation data. We parse all of it so we can
+// walk from each layer's default state down to the clips it plays.
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct AnimatorController {
+    pub name: CharArray,
+    pub controller_size: u32,
+    pub controller: ControllerConstant,
+    pub tos: Map<u32, CharArray>,
+    pub animation_clips: UnityArray<PPtr<()>>,
+    pub state_machine_behaviour_vector_description: StateMachineBehaviourVectorDescription,
+    pub state_machine_behaviours: UnityArray<PPtr<()>>,
+    pub multithreaded_state_machine: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct ControllerConstant {
+    pub layers: UnityArray<LayerConstant>,
+    pub state_machines: UnityArray<StateMachineConstant>,
+    pub values: UnityArray<ValueConstant>,
+    pub default_values: ValueArray,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct LayerConstant {
+    pub state_machine_index: u32,
+    pub state_machine_motion_set_index: u32,
+    pub body_mask: HumanPoseMask,
+    pub skeleton_mask: UnityArray<SkeletonMaskElement>,
+    pub binding: u32,
+    pub layer_blending_mode: i32,
+    pub default_weight: f32,
+    pub ik_pass: u8,
+    pub synced_layer_affects_timing: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct HumanPoseMask {
+    pub word0: u32,
+    pub word1: u32,
+    pub word2: u32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct SkeletonMaskElement {
+    pub path_hash: u32,
+    pub weight: f32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct StateMachineConstant {
+    pub states: UnityArray<StateConstant>,
+    pub any_state_transitions: UnityArray<TransitionConstant>,
+    pub selector_states: UnityArray<SelectorStateConstant>,
+    pub default_state: u32,
+    pub motion_set_count: u32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct StateConstant {
+    pub transitions: UnityArray<TransitionConstant>,
+    pub blend_tree_constant_indices: UnityArray<i32>,
+    pub blend_trees: UnityArray<BlendTreeConstant>,
+    pub name_id: u32,
+    pub path_id: u32,
+    pub full_path_id: u32,
+    pub tag_id: u32,
+    pub speed_param_id: u32,
+    pub mirror_param_id: u32,
+    pub cycle_offset_param_id: u32,
+    pub time_param_id: u32,
+    pub speed: f32,
+    pub cycle_offset: f32,
+    pub ik_on_feet: u8,
+    pub write_default_values: u8,
+    pub looping: u8,
+    pub mirror: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct TransitionConstant {
+    pub conditions: UnityArray<ConditionConstant>,
+    pub destination_state: u32,
+    pub full_path_id: u32,
+    pub id: u32,
+    pub user_id: u32,
+    pub transition_duration: f32,
+    pub transition_offset: f32,
+    pub exit_time: f32,
+    pub has_exit_time: u8,
+    pub has_fixed_duration: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub interruption_source: i32,
+    pub ordered_interruption: u8,
+    pub can_transition_to_self: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct ConditionConstant {
+    pub condition_mode: u32,
+    pub event_id: u32,
+    pub event_threshold: f32,
+    pub exit_time: f32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct SelectorStateConstant {
+    pub transitions: UnityArray<SelectorTransitionConstant>,
+    pub full_path_id: u32,
+    pub is_entry: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct SelectorTransitionConstant {
+    pub destination: u32,
+    pub conditions: UnityArray<ConditionConstant>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct BlendTreeConstant {
+    pub nodes: UnityArray<BlendTreeNodeConstant>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct BlendTreeNodeConstant {
+    pub blend_type: u32,
+    pub blend_event_id: u32,
+    pub blend_event_y_id: u32,
+    pub child_indices: UnityArray<u32>,
+    pub blend_1d_data: Blend1dDataConstant,
+    pub blend_2d_data: Blend2dDataConstant,
+    pub blend_direct_data: BlendDirectDataConstant,
+    pub clip_id: u32,
+    pub duration: f32,
+    pub cycle_offset: f32,
+    pub mirror: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+impl BlendTreeNodeConstant {
+    // Nodes which blend between children rather than play a clip use this
+    // sentinel for clip_id
+    pub const NO_CLIP: u32 = 0xFFFFFFFF;
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct Blend1dDataConstant {
+    pub child_thresholds: UnityArray<f32>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct Blend2dDataConstant {
+    pub child_positions: UnityArray<Vec2>,
+    pub child_magnitudes: UnityArray<f32>,
+    pub child_pair_vectors: UnityArray<Vec2>,
+    pub child_pair_avg_mag_invs: UnityArray<f32>,
+    pub child_neighbor_lists: UnityArray<UnityArray<u32>>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct BlendDirectDataConstant {
+    pub child_blend_event_ids: UnityArray<u32>,
+    pub normalized_blend_values: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct ValueConstant {
+    pub id: u32,
+    pub value_type: u32,
+    pub index: u32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct ValueArray {
+    pub position_values: UnityArray<Vec3>,
+    pub quaternion_values: UnityArray<Vec4>,
+    pub scale_values: UnityArray<Vec3>,
+    pub float_values: UnityArray<f32>,
+    pub int_values: UnityArray<i32>,
+    pub bool_values: UnityArray<u8>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct StateMachineBehaviourVectorDescription {
+    pub state_machine_behaviour_ranges: Map<StateKey, StateRange>,
+    pub state_machine_behaviour_indices: UnityArray<u32>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct StateKey {
+    pub state_id: u32,
+    pub layer_index: i32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct StateRange {
+    pub start_index: u32,
+    pub count: u32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct AnimatorOverrideController {
+    pub name: CharArray,
+    pub controller: PPtr<()>,
+    pub clips: UnityArray<AnimationClipOverride>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct AnimationClipOverride {
+    pub original_clip: PPtr<()>,
+    pub override_clip: PPtr<()>,
+}
+
+// The 2018.3+ layout. Clips imported for Mecanim keep their data in the
+// muscle clip rather than the plain curves, which are then empty.
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct AnimationClip {
+    pub name: CharArray,
+    pub legacy: u8,
+    pub compressed: u8,
+    pub use_high_quality_curve: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub rotation_curves: UnityArray<QuaternionCurve>,
+    pub compressed_rotation_curves: UnityArray<CompressedAnimationCurve>,
+    pub euler_curves: UnityArray<Vec3Curve>,
+    pub position_curves: UnityArray<Vec3Curve>,
+    pub scale_curves: UnityArray<Vec3Curve>,
+    #[deku(ctx = "version")]
+    pub float_curves: UnityArray<FloatCurve>,
+    #[deku(ctx = "version")]
+    pub pptr_curves: UnityArray<PPtrCurve>,
+    pub sample_rate: f32,
+    pub wrap_mode: i32,
+    pub bounds: AABB,
+    pub muscle_clip_size: u32,
+    pub muscle_clip: ClipMuscleConstant,
+    #[deku(ctx = "version")]
+    pub clip_binding_constant: AnimationClipBindingConstant,
+    pub has_generic_root_transform: u8,
+    pub has_motion_float_curves: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    pub events: UnityArray<AnimationEvent>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct QuaternionCurve {
+    pub curve: QuaternionAnimationCurve,
+    pub path: CharArray,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct QuaternionAnimationCurve {
+    pub curve: UnityArray<QuaternionKeyframe>,
+    pub pre_infinity: i32,
+    pub post_infinity: i32,
+    pub rotation_order: i32,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+pub struct QuaternionKeyframe {
+    pub time: f32,
+    pub value: Quaternion,
+    pub in_slope: Quaternion,
+    pub out_slope: Quaternion,
+    pub weighted_mode: i32,
+    pub in_weight: Quaternion,
+    pub out_weight: Quaternion,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct Vec3Curve {
+    pub curve: Vec3AnimationCurve,
+    pub path: CharArray,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct Vec3AnimationCurve {
+    pub curve: UnityArray<Vec3Keyframe>,
+    pub pre_infinity: i32,
+    pub post_infinity: i32,
+    pub rotation_order: i32,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+pub struct Vec3Keyframe {
+    pub time: f32,
+    pub value: Vec3,
+    pub in_slope: Vec3,
+    pub out_slope: Vec3,
+    pub weighted_mode: i32,
+    pub in_weight: Vec3,
+    pub out_weight: Vec3,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct CompressedAnimationCurve {
+    pub path: CharArray,
+    pub times: Packedi32Vec,
+    pub values: PackedQuatVector,
+    pub slopes: Packedf32Vec,
+    pub pre_infinity: i32,
+    pub post_infinity: i32,
+}
+
+// Kept packed, since only legacy clips use these
+#[derive(DekuRead, Clone, Debug)]
+pub struct PackedQuatVector {
+    pub num_items: u32,
+    pub data: UnityArray<u8>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct FloatCurve {
+    pub curve: AnimationCurve,
+    pub attribute: CharArray,
+    pub path: CharArray,
+    pub class_id: i32,
+    pub script: PPtr<()>,
+    #[deku(cond = "version >= UnityVersion::V2022_3_0f1")]
+    pub flags: Option<i32>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct PPtrCurve {
+    pub curve: UnityArray<PPtrKeyframe>,
+    pub attribute: CharArray,
+    pub path: CharArray,
+    pub class_id: i32,
+    pub script: PPtr<()>,
+    #[deku(cond = "version >= UnityVersion::V2022_3_0f1")]
+    pub flags: Option<i32>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct PPtrKeyframe {
+    pub time: f32,
+    pub value: PPtr<()>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct ClipMuscleConstant {
+    pub delta_pose: HumanPose,
+    pub start_x: XForm,
+    pub stop_x: XForm,
+    pub left_foot_start_x: XForm,
+    pub right_foot_start_x: XForm,
+    pub average_speed: Vec3,
+    pub clip: Clip,
+    pub start_time: f32,
+    pub stop_time: f32,
+    pub orientation_offset_y: f32,
+    pub level: f32,
+    pub cycle_offset: f32,
+    pub average_angular_speed: f32,
+    pub index_array: UnityArray<i32>,
+    pub value_array_delta: UnityArray<ValueDelta>,
+    pub value_array_reference_pose: UnityArray<f32>,
+    pub mirror: u8,
+    pub loop_time: u8,
+    pub loop_blend: u8,
+    pub loop_blend_orientation: u8,
+    pub loop_blend_position_y: u8,
+    pub loop_blend_position_xz: u8,
+    pub start_at_origin: u8,
+    pub keep_original_orientation: u8,
+    pub keep_original_position_y: u8,
+    pub keep_original_position_xz: u8,
+    pub height_from_feet: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct HumanPose {
+    pub root_x: XForm,
+    pub look_at_position: Vec3,
+    pub look_at_weight: Vec4,
+    pub goal_array: UnityArray<HumanGoal>,
+    pub left_hand_pose: HandPose,
+    pub right_hand_pose: HandPose,
+    pub dof_array: UnityArray<f32>,
+    pub t_dof_array: UnityArray<Vec3>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct HumanGoal {
+    pub x: XForm,
+    pub weight_t: f32,
+    pub weight_r: f32,
+    pub hint_t: Vec3,
+    pub hint_weight_t: f32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct HandPose {
+    pub grab_x: XForm,
+    pub dof_array: UnityArray<f32>,
+    pub override_: f32,
+    pub close_open: f32,
+    pub in_out: f32,
+    pub grab: f32,
+}
+
+// A clip's curves, in three packings. Curve indices run through the streamed
+// curves, then the dense ones, then the constant ones.
+#[derive(DekuRead, Clone, Debug)]
+pub struct Clip {
+    pub streamed_clip: StreamedClip,
+    pub dense_clip: DenseClip,
+    pub constant_clip: ConstantClip,
+}
+
+// Keyframes as a stream of frames, each a time followed by the keys starting
+// there: a curve index and the cubic's coefficients for the segment. All of
+// it is stored as u32s.
+#[derive(DekuRead, Clone, Debug)]
+pub struct StreamedClip {
+    pub data: UnityArray<u32>,
+    pub curve_count: u32,
+}
+
+// Every curve sampled at a fixed rate, frame by frame
+#[derive(DekuRead, Clone, Debug)]
+pub struct DenseClip {
+    pub frame_count: i32,
+    pub curve_count: u32,
+    pub sample_rate: f32,
+    pub begin_time: f32,
+    pub sample_array: UnityArray<f32>,
+}
+
+// One value per curve
+#[derive(DekuRead, Clone, Debug)]
+pub struct ConstantClip {
+    pub data: UnityArray<f32>,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+pub struct ValueDelta {
+    pub start: f32,
+    pub stop: f32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct AnimationClipBindingConstant {
+    #[deku(ctx = "version")]
+    pub generic_bindings: UnityArray<GenericBinding>,
+    pub pptr_curve_mapping: UnityArray<PPtr<()>>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct GenericBinding {
+    // CRC32 of the target's transform path
+    pub path: u32,
+    // for transforms, which of position/rotation/scale/euler angles; for
+    // humanoid clips, which muscle; otherwise a CRC32 of the property name
+    pub attribute: u32,
+    pub script: PPtr<()>,
+    pub class_id: i32,
+    pub custom_type: u8,
+    pub is_pptr_curve: u8,
+    #[deku(cond = "version >= UnityVersion::V2022_3_0f1")]
+    pub is_int_curve: Option<u8>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct AnimationEvent {
+    pub time: f32,
+    pub function_name: CharArray,
+    pub data: CharArray,
+    pub object_reference_parameter: PPtr<()>,
+    pub float_parameter: f32,
+    pub int_parameter: i32,
+    pub message_options: i32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct Avatar {
+    pub name: CharArray,
+    pub avatar_size: u32,
+    pub avatar: AvatarConstant,
+    pub tos: Map<u32, CharArray>,
+    pub human_description: HumanDescription,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct AvatarConstant {
+    pub avatar_skeleton: Skeleton,
+    pub avatar_skeleton_pose: SkeletonPose,
+    pub default_pose: SkeletonPose,
+    pub skeleton_name_ids: UnityArray<u32>,
+    pub human: Human,
+    pub human_skeleton_indices: UnityArray<i32>,
+    pub human_skeleton_reverse_indices: UnityArray<i32>,
+    pub root_motion_bone_index: i32,
+    pub root_motion_bone_x: XForm,
+    pub root_motion_skeleton: Skeleton,
+    pub root_motion_skeleton_pose: SkeletonPose,
+    pub root_motion_skeleton_indices: UnityArray<i32>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct Skeleton {
+    pub nodes: UnityArray<SkeletonNode>,
+    pub ids: UnityArray<u32>,
+    pub axes: UnityArray<Axes>,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+pub struct SkeletonNode {
+    pub parent_id: i32,
+    pub axes_id: i32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct Axes {
+    pub pre_q: Vec4,
+    pub post_q: Vec4,
+    pub sgn: Vec3,
+    pub limit_min: Vec3,
+    pub limit_max: Vec3,
+    pub length: f32,
+    pub axes_type: u32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct SkeletonPose {
+    pub x: UnityArray<XForm>,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+pub struct XForm {
+    pub t: Vec3,
+    pub q: Quaternion,
+    pub s: Vec3,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct Human {
+    pub root_x: XForm,
+    pub skeleton: Skeleton,
+    pub skeleton_pose: SkeletonPose,
+    pub left_hand: UnityArray<i32>,
+    pub right_hand: UnityArray<i32>,
+    pub human_bone_indices: UnityArray<i32>,
+    pub human_bone_masses: UnityArray<f32>,
+    pub scale: f32,
+    pub arm_twist: f32,
+    pub fore_arm_twist: f32,
+    pub upper_leg_twist: f32,
+    pub leg_twist: f32,
+    pub arm_stretch: f32,
+    pub leg_stretch: f32,
+    pub feet_spacing: f32,
+    pub has_left_hand: u8,
+    pub has_right_hand: u8,
+    pub has_t_dof: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct HumanDescription {
+    pub human: UnityArray<HumanBone>,
+    pub skeleton: UnityArray<SkeletonBone>,
+    pub arm_twist: f32,
+    pub fore_arm_twist: f32,
+    pub upper_leg_twist: f32,
+    pub leg_twist: f32,
+    pub arm_stretch: f32,
+    pub leg_stretch: f32,
+    pub feet_spacing: f32,
+    pub global_scale: f32,
+    pub root_motion_bone_name: CharArray,
+    pub has_translation_dof: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub has_extra_root: u8,
+    pub skeleton_has_parents: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct HumanBone {
+    pub bone_name: CharArray,
+    pub human_name: CharArray,
+    pub limit: SkeletonBoneLimit,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct SkeletonBoneLimit {
+    pub min: Vec3,
+    pub max: Vec3,
+    pub value: Vec3,
+    pub length: f32,
+    pub modified: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct SkeletonBone {
+    pub name: CharArray,
+    pub parent_name: CharArray,
+    pub position: Vec3,
+    pub rotation: Quaternion,
+    pub scale: Vec3,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct BoxCollider {
+    pub game_object: PPtr<GameObject>,
+    pub material: PPtr<()>,
+    pub is_trigger: u8,
+    pub enabled: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub size: Vec3,
+    pub center: Vec3,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct SphereCollider {
+    pub game_object: PPtr<GameObject>,
+    pub material: PPtr<()>,
+    pub is_trigger: u8,
+    pub enabled: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub radius: f32,
+    pub center: Vec3,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct CapsuleCollider {
+    pub game_object: PPtr<GameObject>,
+    pub material: PPtr<()>,
+    pub is_trigger: u8,
+    pub enabled: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub radius: f32,
+    pub height: f32,
+    pub direction: CapsuleDirection,
+    pub center: Vec3,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+#[deku(id_type = "i32")]
+pub enum CapsuleDirection {
+    X = 0,
+    Y = 1,
+    Z = 2,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct MeshCollider {
+    pub game_object: PPtr<GameObject>,
+    pub material: PPtr<()>,
+    pub is_trigger: u8,
+    pub enabled: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub convex: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    pub cooking_options: i32,
+    pub mesh: PPtr<Mesh>,
+}
+
+// The fields shared by every Collider2D subclass
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct Collider2D {
+    pub game_object: PPtr<GameObject>,
+    pub enabled: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub density: f32,
+    pub material: PPtr<()>,
+    pub is_trigger: u8,
+    pub used_by_effector: u8,
+    pub used_by_composite: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    pub offset: Vec2,
+}
+
+// How a collider follows its SpriteRenderer's size in sliced/tiled mode
+#[derive(DekuRead, Clone, Debug)]
+pub struct SpriteTilingProperty {
+    pub border: Vec4,
+    pub pivot: Vec2,
+    pub old_size: Vec2,
+    pub new_size: Vec2,
+    pub adaptive_tiling_threshold: f32,
+    pub draw_mode: i32,
+    pub adaptive_tiling: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct BoxCollider2D {
+    #[deku(ctx = "version")]
+    pub collider: Collider2D,
+    pub sprite_tiling_property: SpriteTilingProperty,
+    pub auto_tiling: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub size: Vec2,
+    pub edge_radius: f32,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct CircleCollider2D {
+    #[deku(ctx = "version")]
+    pub collider: Collider2D,
+    pub radius: f32,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct PolygonCollider2D {
+    #[deku(ctx = "version")]
+    pub collider: Collider2D,
+    pub sprite_tiling_property: SpriteTilingProperty,
+    pub auto_tiling: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub paths: UnityArray<UnityArray<Vec2>>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct EdgeCollider2D {
+    #[deku(ctx = "version")]
+    pub collider: Collider2D,
+    pub edge_radius: f32,
+    // 2020.1 and up
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub use_adjacent_start_point: Option<u8>,
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub use_adjacent_end_point: Option<u8>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub adjacent_start_point: Option<Vec2>,
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub adjacent_end_point: Option<Vec2>,
+    pub points: UnityArray<Vec2>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct NavMeshData {
+    pub name: CharArray,
+    pub tiles: UnityArray<NavMeshTileData>,
+    pub build_settings: NavMeshBuildSettings,
+    pub heightmaps: UnityArray<HeightmapData>,
+    pub height_meshes: UnityArray<HeightMeshData>,
+    pub off_mesh_links: UnityArray<AutoOffMeshLinkData>,
+    pub source_bounds: AABB,
+    pub rotation: Quaternion,
+    pub position: Vec3,
+    pub agent_type_id: i32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct NavMeshTileData {
+    pub mesh_data: ByteArray,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub hash: [u8; 16],
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct NavMeshBuildSettings {
+    pub agent_type_id: i32,
+    pub agent_radius: f32,
+    pub agent_height: f32,
+    pub agent_slope: f32,
+    pub agent_climb: f32,
+    pub ledge_drop_height: f32,
+    pub max_jump_across_distance: f32,
+    pub min_region_area: f32,
+    pub manual_cell_size: i32,
+    pub cell_size: f32,
+    pub manual_tile_size: i32,
+    pub tile_size: i32,
+    pub accurate_placement: i32,
+    pub max_job_workers: u32,
+    pub preserve_tiles_outside_bounds: i32,
+    pub debug_flags: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct HeightmapData {
+    pub position: Vec3,
+    pub terrain_data: PPtr<()>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct HeightMeshData {
+    pub vertices: UnityArray<Vec3>,
+    pub indices: UnityArray<i32>,
+    pub bounds: AABB,
+    pub nodes: UnityArray<HeightMeshBVNode>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct HeightMeshBVNode {
+    pub min: Vec3,
+    pub max: Vec3,
+    pub i: i32,
+    pub n: i32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct AutoOffMeshLinkData {
+    pub start: Vec3,
+    pub end: Vec3,
+    pub radius: f32,
+    pub link_type: u16,
+    pub area: u8,
+    pub link_direction: u8,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct OcclusionCullingSettings {
+    pub pvs_data: ByteArray,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub static_renderers: UnityArray<PPtr<()>>,
+    pub portals: UnityArray<PPtr<()>>,
+    pub scene_guid: Guid,
+    pub occlusion_culling_data: PPtr<OcclusionCullingData>,
+}
+
+// When scenes are baked together, their PVS data is shared in this asset
+// rather than stored on each scene's OcclusionCullingSettings
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct OcclusionCullingData {
+    pub name: CharArray,
+    pub pvs_data: ByteArray,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub scenes: UnityArray<OcclusionScene>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct OcclusionScene {
+    pub index_renderers: i32,
+    pub size_renderers: i32,
+    pub index_portals: i32,
+    pub size_portals: i32,
+    pub scene: Guid,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct ResourceManager {
+    pub container: Map<CharArray, PPtr<()>>,
+    pub dependent_assets: UnityArray<ResourceManagerDependency>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct ResourceManagerDependency {
+    pub object: PPtr<()>,
+    pub dependencies: UnityArray<PPtr<()>>,
+}
+
+// Lists the objects in the sharedassets file (or a scene bundle's
+// .sharedAssets) that a scene needs loaded before it
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct PreloadData {
+    pub name: CharArray,
+    pub assets: UnityArray<PPtr<()>>,
+    // 2018.2 and up
+    #[deku(cond = "version >= UnityVersion::V2018_2_21f1", default = "UnityArray::default()")]
+    pub dependencies: UnityArray<CharArray>,
+    // 2019.1 and up
+    #[deku(cond = "version >= UnityVersion::V2019_4_39f1", default = "0")]
+    pub explicit_data_layout: u8,
+}
+
+// Only the leading fields, up to the container; the rest are the main asset,
+// bundle name and dependencies
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct AssetBundle {
+    pub name: CharArray,
+    // each container entry's objects, along with everything they depend on
+    pub preload_table: UnityArray<PPtr<()>>,
+    pub container: Map<CharArray, AssetBundleAssetInfo>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct AssetBundleAssetInfo {
+    pub preload_index: i32,
+    pub preload_size: i32,
+    pub asset: PPtr<()>,
+}
+
+// Only the leading field; the rest are build flags and VR devices. Lives in
+// globalgamemanagers (mainData before 5.x), and scene N is stored as "levelN"
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct BuildSettings {
+    pub scenes: UnityArray<CharArray>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct Canvas {
+    pub game_object: PPtr<GameObject>,
+    pub enabled: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub render_mode: CanvasRenderMode,
+    pub camera: PPtr<()>,
+    pub plane_distance: f32,
+    pub pixel_perfect: u8,
+    pub receives_events: u8,
+    pub override_sorting: u8,
+    pub override_pixel_perfect: u8,
+    pub sorting_bucket_normalized_size: f32,
+    pub additional_shader_channels_flag: i32,
+    pub sorting_layer_id: i32,
+    pub sorting_order: i16,
+    pub target_display: i8,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+#[deku(id_type = "i32")]
+pub enum CanvasRenderMode {
+    ScreenSpaceOverlay = 0,
+    ScreenSpaceCamera = 1,
+    WorldSpace = 2,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct CanvasRenderer {
+    pub game_object: PPtr<GameObject>,
+    pub cull_transparent_mesh: u8,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct MonoScript {
+    pub name: CharArray,
+    pub execution_order: i32,
+    pub properties_hash: [u8; 16],
+    pub class_name: CharArray,
+    pub namespace: CharArray,
+    pub assembly_name: CharArray,
+}
+
+// The fields every MonoBehavior starts with. Whatever follows is defined by
+// the script, so it's up to the caller to know which script it's reading.
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct MonoBehavior {
+    pub game_object: PPtr<GameObject>,
+    pub enabled: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub script: PPtr<MonoScript>,
+    pub name: CharArray,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct UnityEvent {
+    #[deku(ctx = "version")]
+    pub persistent_calls: UnityArray<PersistentCall>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct PersistentCall {
+    pub target: PPtr<()>,
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub target_assembly_type_name: Option<CharArray>,
+    pub method_name: CharArray,
+    pub mode: i32,
+    pub arguments: ArgumentCache,
+    pub call_state: i32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct ArgumentCache {
+    pub object_argument: PPtr<()>,
+    pub object_argument_assembly_type_name: CharArray,
+    pub int_argument: i32,
+    pub float_argument: f32,
+    pub string_argument: CharArray,
+    pub bool_argument: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+// UnityEngine.UI.MaskableGraphic, which Image and RawImage derive from
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct MaskableGraphic {
+    pub material: PPtr<Material>,
+    pub color: ColorRGBA,
+    pub raycast_target: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub raycast_padding: Option<Vec4>,
+    pub maskable: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    #[deku(ctx = "version")]
+    pub on_cull_state_changed: UnityEvent,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct Image {
+    #[deku(ctx = "version")]
+    pub behavior: MonoBehavior,
+    #[deku(ctx = "version")]
+    pub graphic: MaskableGraphic,
+    pub sprite: PPtr<()>,
+    pub image_type: ImageType,
+    pub preserve_aspect: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub fill_center: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    pub fill_method: i32,
+    pub fill_amount: f32,
+    pub fill_clockwise: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment2: Vec<u8>,
+    pub fill_origin: i32,
+    pub use_sprite_mesh: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment3: Vec<u8>,
+    pub pixels_per_unit_multiplier: f32,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+#[deku(id_type = "i32")]
+pub enum ImageType {
+    Simple = 0,
+    Sliced = 1,
+    Tiled = 2,
+    Filled = 3,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct RawImage {
+    #[deku(ctx = "version")]
+    pub behavior: MonoBehavior,
+    #[deku(ctx = "version")]
+    pub graphic: MaskableGraphic,
+    pub texture: PPtr<Texture>,
+    pub uv_rect: Rect,
+}
+
+// 2D Toolkit's tk2dSprite (via tk2dBaseSprite), as used by e.g. Hollow
+// Knight. Only the fields needed to draw it; the collider settings after
+// them are left unread.
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct Tk2dSprite {
+    #[deku(ctx = "version")]
+    pub behavior: MonoBehavior,
+    pub collection: PPtr<()>,
+    pub color: ColorRGBA,
+    pub scale: Vec3,
+    pub sprite_id: i32,
+}
+
+// tk2dSpriteCollectionData, following 2D Toolkit 2.5's field order
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct Tk2dSpriteCollectionData {
+    #[deku(ctx = "version")]
+    pub behavior: MonoBehavior,
+    pub collection_version: i32,
+    pub material_ids_valid: u8,
+    pub need_material_instance: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub sprite_definitions: UnityArray<Tk2dSpriteDefinition>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct Tk2dSpriteDefinition {
+    pub name: CharArray,
+    pub bounds_data: UnityArray<Vec3>,
+    pub untrimmed_bounds_data: UnityArray<Vec3>,
+    pub texel_size: Vec2,
+    pub positions: UnityArray<Vec3>,
+    pub normals: UnityArray<Vec3>,
+    pub tangents: UnityArray<Vec4>,
+    pub uvs: UnityArray<Vec2>,
+    pub normalized_uvs: UnityArray<Vec2>,
+    pub indices: UnityArray<i32>,
+    pub material: PPtr<Material>,
+    pub material_id: i32,
+    pub source_texture_guid: CharArray,
+    pub extract_region: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub region_x: i32,
+    pub region_y: i32,
+    pub region_w: i32,
+    pub region_h: i32,
+    pub flipped: i32,
+    pub complex_geometry: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    pub physics_engine: i32,
+    pub collider_type: i32,
+    pub collider_vertices: UnityArray<Vec3>,
+    pub collider_indices_fwd: UnityArray<i32>,
+    pub collider_indices_back: UnityArray<i32>,
+    pub collider_convex: u8,
+    pub collider_smooth_sphere_collisions: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment2: Vec<u8>,
+    pub polygon_collider_2d: UnityArray<Tk2dCollider2DData>,
+    pub edge_collider_2d: UnityArray<Tk2dCollider2DData>,
+    pub attach_points: UnityArray<Tk2dAttachPoint>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct Tk2dCollider2DData {
+    pub points: UnityArray<Vec2>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct Tk2dAttachPoint {
+    pub name: CharArray,
+    pub position: Vec3,
+    pub angle: f32,
+}
+
+// The fields shared by every Renderer subclass
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct Renderer {
+    pub game_object: PPtr<GameObject>,
+    pub enabled: u8,
+    pub cast_shadows: u8,
+    pub receive_shadows: u8,
+    // before 5.0 this was a byte, and came first
+    #[deku(cond = "version < UnityVersion::V5_4_6f1")]
+    pub legacy_lightmap_index: Option<u8>,
+    // 2017.2 and up
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "1")]
+    pub dynamic_occludee: u8,
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub static_shadow_caster: Option<u8>,
+    // motion vectors and probe usage are 5.4 and up
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "1")]
+    pub motion_vectors: u8,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "0")]
+    pub light_probe_usage: u8,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "0")]
+    pub reflection_probe_usage: u8,
+    // 2019.3 and up
+    #[deku(cond = "version >= UnityVersion::V2019_4_39f1", default = "0")]
+    pub ray_tracing_mode: u8,
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub ray_trace_procedural: Option<u8>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    // 2018.1 and up; before that everything was on the default layer
+    #[deku(cond = "version >= UnityVersion::V2018_1_9f2", default = "1")]
+    pub rendering_layer_mask: u32,
+    // 2018.3 and up
+    #[deku(cond = "version >= UnityVersion::V2018_4_36f1", default = "0")]
+    pub renderer_priority: i32,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "widen_lightmap_index(*legacy_lightmap_index)")]
+    pub lightmap_index: u16,
+    // dynamic (realtime GI) lightmaps are 5.0 and up
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "0xFFFF")]
+    pub lightmap_index_dynamic: u16,
+    pub lightmap_tiling_offset: Vec4,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "Vec4 { x: 1.0, y: 1.0, z: 0.0, w: 0.0 }")]
+    pub lightmap_tiling_offset_dynamic: Vec4,
+    pub materials: UnityArray<PPtr<Material>>,
+    // before 5.5, static batches were described by the submeshes they used
+    #[deku(cond = "version < UnityVersion::V2017_3_0f3", default = "UnityArray::default()")]
+    pub subset_indices: UnityArray<u32>,
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "StaticBatchInfo::default()")]
+    pub static_batch_info: StaticBatchInfo,
+    pub static_batch_root: PPtr<Transform>,
+    // replaced by light_probe_usage in 5.4
+    #[deku(cond = "version < UnityVersion::V5_4_6f1", default = "0")]
+    pub use_light_probes: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment2: Vec<u8>,
+    pub probe_anchor: PPtr<Transform>,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "PPtr::default()")]
+    pub light_probe_volume_override: PPtr<GameObject>,
+    pub sorting_layer_id: i32,
+    // 5.6 and up
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3", default = "0")]
+    pub sorting_layer: i16,
+    pub sorting_order: i16,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct SkinnedMeshRenderer {
+    #[deku(ctx = "version")]
+    pub renderer: Renderer,
+    pub quality: i32,
+    pub update_when_offscreen: u8,
+    // 5.4 and up
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "0")]
+    pub skinned_motion_vectors: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub mesh: PPtr<Mesh>,
+    pub bones: UnityArray<PPtr<Transform>>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    pub blend_shape_weights: UnityArray<f32>,
+    pub root_bone: PPtr<Transform>,
+    pub aabb: AABB,
+    pub dirty_aabb: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment2: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct SpriteRenderer {
+    #[deku(ctx = "version")]
+    pub renderer: Renderer,
+    pub sprite: PPtr<Sprite>,
+    pub color: ColorRGBA,
+    pub flip_x: u8,
+    pub flip_y: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub draw_mode: SpriteDrawMode,
+    pub size: Vec2,
+    pub adaptive_mode_threshold: f32,
+    pub sprite_tile_mode: i32,
+    pub was_sprite_assigned: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    pub mask_interaction: i32,
+    // 2018.2 and up
+    #[deku(cond = "version >= UnityVersion::V2018_2_21f1", default = "0")]
+    pub sprite_sort_point: i32,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+#[deku(id_type = "i32")]
+pub enum SpriteDrawMode {
+    Simple = 0,
+    Sliced = 1,
+    Tiled = 2,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct Sprite {
+    pub name: CharArray,
+    pub rect: Rect,
+    pub offset: Vec2,
+    pub border: Vec4,
+    pub pixels_to_units: f32,
+    pub pivot: Vec2,
+    pub extrude: u32,
+    pub is_polygon: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub render_data_key_guid: Guid,
+    pub render_data_key_id: i64,
+    pub atlas_tags: UnityArray<CharArray>,
+    pub sprite_atlas: PPtr<()>,
+    #[deku(ctx = "version")]
+    pub render_data: SpriteRenderData,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct SpriteRenderData {
+    pub texture: PPtr<Texture2D>,
+    pub alpha_texture: PPtr<Texture2D>,
+    // 2019.1 and up
+    #[deku(cond = "version >= UnityVersion::V2019_4_39f1", default = "UnityArray::default()")]
+    pub secondary_textures: UnityArray<SecondarySpriteTexture>,
+    #[deku(ctx = "version")]
+    pub submeshes: UnityArray<SubMesh>,
+    pub index_buffer: ByteArray,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    #[deku(ctx = "version")]
+    pub vertex_data: VertexData,
+    // 2018.1 and up
+    #[deku(cond = "version >= UnityVersion::V2018_1_9f2", default = "UnityArray::default()")]
+    pub bind_pose: UnityArray<Matrix4x4>,
+    pub texture_rect: Rect,
+    pub texture_rect_offset: Vec2,
+    pub atlas_rect_offset: Vec2,
+    pub settings_raw: u32,
+    pub uv_transform: Vec4,
+    pub downscale_multiplier: f32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct SecondarySpriteTexture {
+    pub texture: PPtr<Texture2D>,
+    pub name: CharArray,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct SpriteAtlas {
+    pub name: CharArray,
+    pub packed_sprites: UnityArray<PPtr<Sprite>>,
+    pub packed_sprite_names_to_index: UnityArray<CharArray>,
+    #[deku(ctx = "version")]
+    pub render_data_map: Map<SpriteRenderDataKey, SpriteAtlasData>,
+    pub tag: CharArray,
+    pub is_variant: u8,
+}
+
+// Matches Sprite.render_data_key_guid and render_data_key_id
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct SpriteRenderDataKey {
+    pub guid: Guid,
+    pub id: i64,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct SpriteAtlasData {
+    pub texture: PPtr<Texture2D>,
+    pub alpha_texture: PPtr<Texture2D>,
+    pub texture_rect: Rect,
+    pub texture_rect_offset: Vec2,
+    pub atlas_rect_offset: Vec2,
+    pub uv_transform: Vec4,
+    pub downscale_multiplier: f32,
+    pub settings_raw: u32,
+    // 2020.2 and up
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub secondary_textures: Option<UnityArray<SecondarySpriteTexture>>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct LineRenderer {
+    #[deku(ctx = "version")]
+    pub renderer: Renderer,
+    pub positions: UnityArray<Vec3>,
+    pub parameters: LineParameters,
+    pub use_world_space: u8,
+    pub looping: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct TrailRenderer {
+    #[deku(ctx = "version")]
+    pub renderer: Renderer,
+    pub time: f32,
+    pub parameters: LineParameters,
+    pub min_vertex_distance: f32,
+    pub autodestruct: u8,
+    pub emitting: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct LineParameters {
+    pub width_multiplier: f32,
+    pub width_curve: AnimationCurve,
+    pub color_gradient: Gradient,
+    pub num_corner_vertices: i32,
+    pub num_cap_vertices: i32,
+    pub alignment: LineAlignment,
+    pub texture_mode: LineTextureMode,
+    pub shadow_bias: f32,
+    pub generate_lighting_data: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+#[deku(id_type = "i32")]
+pub enum LineAlignment {
+    View = 0,
+    TransformZ = 1,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+#[deku(id_type = "i32")]
+pub enum LineTextureMode {
+    Stretch = 0,
+    Tile = 1,
+    DistributePerSegment = 2,
+    RepeatPerSegment = 3,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct AnimationCurve {
+    pub curve: UnityArray<Keyframe>,
+    pub pre_infinity: i32,
+    pub post_infinity: i32,
+    pub rotation_order: i32,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub time: f32,
+    pub value: f32,
+    pub in_slope: f32,
+    pub out_slope: f32,
+    pub weighted_mode: i32,
+    pub in_weight: f32,
+    pub out_weight: f32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct Gradient {
+    pub color_keys: [ColorRGBA; 8],
+    pub color_times: [u16; 8],
+    pub alpha_times: [u16; 8],
+    pub mode: GradientMode,
+    pub num_color_keys: u8,
+    pub num_alpha_keys: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+#[deku(id_type = "i32")]
+pub enum GradientMode {
+    Blend = 0,
+    Fixed = 1,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct WindZone {
+    pub game_object: PPtr<GameObject>,
+    pub enabled: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub mode: WindZoneMode,
+    pub radius: f32,
+    pub wind_main: f32,
+    pub wind_turbulence: f32,
+    pub wind_pulse_magnitude: f32,
+    pub wind_pulse_frequency: f32,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+#[deku(id_type = "i32")]
+pub enum WindZoneMode {
+    Directional = 0,
+    Spherical = 1,
+}
+
+// Only the leading fields, up to the material; the rest are tree and detail
+// rendering settings we don't use
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct Terrain {
+    pub game_object: PPtr<GameObject>,
+    pub enabled: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub terrain_data: PPtr<TerrainData>,
+    pub tree_distance: f32,
+    pub tree_billboard_distance: f32,
+    pub tree_cross_fade_length: f32,
+    pub tree_maximum_full_lod_count: i32,
+    pub detail_object_distance: f32,
+    pub detail_object_density: f32,
+    pub heightmap_pixel_error: f32,
+    pub splat_map_distance: f32,
+    pub heightmap_maximum_lod: i32,
+    pub shadow_casting_mode: i32,
+    pub draw_heightmap: u8,
+    pub draw_instanced: u8,
+    pub draw_trees_and_foliage: u8,
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub static_shadow_caster: Option<u8>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    pub reflection_probe_usage: i32,
+    // the built-in material types were removed in 2019.2
+    #[deku(cond = "version < UnityVersion::V2019_4_39f1")]
+    pub material_type: Option<i32>,
+    #[deku(cond = "version < UnityVersion::V2019_4_39f1")]
+    pub legacy_specular: Option<ColorRGBA>,
+    #[deku(cond = "version < UnityVersion::V2019_4_39f1")]
+    pub legacy_shininess: Option<f32>,
+    pub material_template: PPtr<Material>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct TerrainData {
+    pub name: CharArray,
+    pub splat_database: SplatDatabase,
+    #[deku(ctx = "version")]
+    pub detail_database: DetailDatabase,
+    #[deku(ctx = "version")]
+    pub heightmap: Heightmap,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct SplatDatabase {
+    pub terrain_layers: UnityArray<PPtr<()>>,
+    pub alpha_textures: UnityArray<PPtr<Texture2D>>,
+    pub alphamap_resolution: i32,
+    pub base_map_resolution: i32,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct DetailDatabase {
+    pub patches: UnityArray<DetailPatch>,
+    #[deku(ctx = "version")]
+    pub detail_prototypes: UnityArray<DetailPrototype>,
+    pub patch_count: i32,
+    pub patch_samples: i32,
+    pub random_rotations: UnityArray<Vec3>,
+    pub waving_grass_tint: ColorRGBA,
+    pub waving_grass_strength: f32,
+    pub waving_grass_amount: f32,
+    pub waving_grass_speed: f32,
+    pub tree_instances: UnityArray<TreeInstance>,
+    pub tree_prototypes: UnityArray<TreePrototype>,
+    pub preload_texture_atlas_data: UnityArray<PPtr<Texture2D>>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct DetailPatch {
+    pub bounds: AABB,
+    pub layer_indices: ByteArray,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub number_of_objects: ByteArray,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct DetailPrototype {
+    pub prototype: PPtr<GameObject>,
+    pub prototype_texture: PPtr<Texture2D>,
+    pub min_width: f32,
+    pub max_width: f32,
+    pub min_height: f32,
+    pub max_height: f32,
+    // 2021.2 and up
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub noise_seed: Option<i32>,
+    pub noise_spread: f32,
+    #[deku(cond = "version < UnityVersion::V2021_3_27f1")]
+    pub bend_factor: Option<f32>,
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub hole_edge_padding: Option<f32>,
+    pub healthy_color: ColorRGBA,
+    pub dry_color: ColorRGBA,
+    pub render_mode: i32,
+    pub use_prototype_mesh: i32,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct TreeInstance {
+    pub position: Vec3,
+    pub width_scale: f32,
+    pub height_scale: f32,
+    pub rotation: f32,
+    pub color: u32,
+    pub lightmap_color: u32,
+    pub index: i32,
+    pub temporary_distance: f32,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct TreePrototype {
+    pub prefab: PPtr<GameObject>,
+    pub bend_factor: f32,
+    pub nav_mesh_lod: i32,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct Heightmap {
+    // 0..32766 maps to 0..1 of the terrain's height
+    pub heights: UnityArray<i16>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    // 2019.3 and up
+    #[deku(cond = "version >= UnityVersion::V2019_4_39f1")]
+    pub holes: Option<ByteArray>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    #[deku(cond = "version >= UnityVersion::V2019_4_39f1")]
+    pub holes_lod: Option<ByteArray>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment2: Vec<u8>,
+    #[deku(cond = "version >= UnityVersion::V2019_4_39f1")]
+    pub enable_holes_texture_compression: Option<u8>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment3: Vec<u8>,
+    pub precomputed_error: UnityArray<f32>,
+    pub min_max_patch_heights: UnityArray<f32>,
+    // before 2019.3 this was separate width and height, which were always equal
+    #[deku(cond = "version < UnityVersion::V2019_4_39f1")]
+    pub width: Option<i32>,
+    #[deku(cond = "version < UnityVersion::V2019_4_39f1")]
+    pub height: Option<i32>,
+    #[deku(cond = "version >= UnityVersion::V2019_4_39f1")]
+    pub resolution: Option<i32>,
+    pub levels: i32,
+    // the size of one heightmap sample, with y being the terrain's full height
+    pub scale: Vec3,
+}
+
+// Only the leading fields, up to the skybox; the rest are halos, flares and
+// reflection settings
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct RenderSettings {
+    pub fog: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub fog_color: ColorRGBA,
+    // 1 for linear, 2 for exponential, 3 for exponential squared
+    pub fog_mode: i32,
+    pub fog_density: f32,
+    pub linear_fog_start: f32,
+    pub linear_fog_end: f32,
+    // before 5.0, there was just the one flat ambient color
+    #[deku(cond = "version < UnityVersion::V5_4_6f1")]
+    pub ambient_light: Option<ColorRGBA>,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1")]
+    pub ambient_sky_color: Option<ColorRGBA>,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1")]
+    pub ambient_equator_color: Option<ColorRGBA>,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1")]
+    pub ambient_ground_color: Option<ColorRGBA>,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "1.0")]
+    pub ambient_intensity: f32,
+    // 0 for skybox, 1 for trilight, 3 for flat
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "3")]
+    pub ambient_mode: i32,
+    // 5.6 and up
+    #[deku(cond = "version >= UnityVersion::V2017_3_0f3")]
+    pub subtractive_shadow_color: Option<ColorRGBA>,
+    pub skybox_material: PPtr<Material>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "_version: UnityVersion")]
+pub struct LightmapSettings {
+    pub enlighten_scene_mapping: EnlightenSceneMapping,
+    pub light_probes: PPtr<()>,
+    pub lightmaps: UnityArray<LightmapData>,
+    pub lightmaps_mode: i32,
+    // SphericalHarmonicsL2, as 9 coefficients for each of r, g and b
+    pub baked_ambient_probe_in_linear: [f32; 27],
+    pub use_shadowmask: u8,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct EnlightenSceneMapping {
+    pub renderers: UnityArray<EnlightenRendererInformation>,
+    pub systems: UnityArray<EnlightenSystemInformation>,
+    pub probesets: UnityArray<[u8; 16]>,
+    pub system_atlases: UnityArray<EnlightenSystemAtlasInformation>,
+    pub terrain_chunks: UnityArray<EnlightenTerrainChunksInformation>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct EnlightenRendererInformation {
+    pub renderer: PPtr<()>,
+    pub dynamic_lightmap_st_in_system: Vec4,
+    pub system_id: i32,
+    pub instance_hash: [u8; 16],
+    pub geometry_hash: [u8; 16],
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct EnlightenSystemInformation {
+    pub renderer_index: u32,
+    pub renderer_size: u32,
+    pub atlas_index: i32,
+    pub atlas_offset_x: i32,
+    pub atlas_offset_y: i32,
+    pub input_system_hash: [u8; 16],
+    pub radiosity_system_hash: [u8; 16],
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct EnlightenSystemAtlasInformation {
+    pub atlas_id: i32,
+    pub atlas_hash: [u8; 16],
+    pub first_system_id: i32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct EnlightenTerrainChunksInformation {
+    pub first_system_id: i32,
+    pub num_chunks_in_x: i32,
+    pub num_chunks_in_y: i32,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct LightmapData {
+    pub lightmap: PPtr<Texture2D>,
+    pub dir_lightmap: PPtr<Texture2D>,
+    pub shadow_mask: PPtr<Texture2D>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct Shader {
+    pub name: CharArray,
+    #[deku(ctx = "version")]
+    pub parsed_form: SerializedShader,
+    pub platforms: UnityArray<u32>,
+    #[deku(ctx = "version")]
+    pub offsets: ShaderBlobTable,
+    #[deku(ctx = "version")]
+    pub compressed_lengths: ShaderBlobTable,
+    #[deku(ctx = "version")]
+    pub decompressed_lengths: ShaderBlobTable,
+    pub compressed_blob: ByteArray,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub dependencies: UnityArray<PPtr<Shader>>,
+    // 2018.1 and up
+    #[deku(cond = "version >= UnityVersion::V2018_1_9f2")]
+    pub non_modifiable_textures: Option<Map<CharArray, PPtr<Texture>>>,
+    pub shader_is_baked: u8,
+}
+
+// Before 5.5 shaders were stored as their ShaderLab source, with the
+// compiled programs in a single LZ4-compressed blob rather than parsed out
+// into a SerializedShader
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct LegacyShader {
+    pub name: CharArray,
+    pub script: ByteArray,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub path_name: CharArray,
+    // 5.3 and up; before that the compiled programs were inlined in the script
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "0")]
+    pub decompressed_size: u32,
+    #[deku(cond = "version >= UnityVersion::V5_4_6f1", default = "ByteArray::default()")]
+    pub sub_program_blob: ByteArray,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    pub dependencies: UnityArray<PPtr<Shader>>,
+    pub shader_is_baked: u8,
+}
+
+// Per-platform blob offsets and lengths. Since 2019.3 each platform's blob
+// may be split into several segments; before that there's exactly one.
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion", id = "version")]
+pub enum ShaderBlobTable {
+    #[deku(id_pat = "UnityVersion::V2017_3_0f3 | UnityVersion::V2018_1_9f2 | UnityVersion::V2018_2_21f1 | UnityVersion::V2018_4_36f1")]
+    Single(UnityArray<u32>),
+    #[deku(id_pat = "_")]
+    Segmented(UnityArray<UnityArray<u32>>),
+}
+
+impl ShaderBlobTable {
+    pub fn is_segmented(&self) -> bool {
+        matches!(self, ShaderBlobTable::Segmented(_))
+    }
+}
+
+impl From<ShaderBlobTable> for Vec<Vec<u32>> {
+    fn from(value: ShaderBlobTable) -> Self {
+        match value {
+            ShaderBlobTable::Single(v) => v.values.into_iter().map(|v| vec![v]).collect(),
+            ShaderBlobTable::Segmented(v) => v.into(),
+        }
+    }
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct SerializedShader {
+    pub prop_info: UnityArray<SerializedProperty>,
+    #[deku(ctx = "version")]
+    pub sub_shaders: UnityArray<SerializedSubShader>,
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub keyword_names: Option<UnityArray<CharArray>>,
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub keyword_flags: Option<ByteArray>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub name: CharArray,
+    pub custom_editor_name: CharArray,
+    pub fallback_name: CharArray,
+    pub dependencies: UnityArray<SerializedShaderDependency>,
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub custom_editor_for_render_pipelines: Option<UnityArray<SerializedCustomEditorForRenderPipeline>>,
+    pub disable_no_subshaders_message: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct SerializedShaderDependency {
+    pub from: CharArray,
+    pub to: CharArray,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct SerializedCustomEditorForRenderPipeline {
+    pub custom_editor_name: CharArray,
+    pub render_pipeline_type: CharArray,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct SerializedProperty {
+    pub name: CharArray,
+    pub description: CharArray,
+    pub attributes: UnityArray<CharArray>,
+    pub prop_type: SerializedPropertyType,
+    pub flags: u32,
+    pub def_value: [f32; 4],
+    pub def_texture: SerializedTextureProperty,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+#[deku(id_type = "i32")]
+pub enum SerializedPropertyType {
+    Color = 0,
+    Vector = 1,
+    Float = 2,
+    Range = 3,
+    Texture = 4,
+    Int = 5,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct SerializedTextureProperty {
+    pub default_name: CharArray,
+    pub tex_dim: i32,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct SerializedSubShader {
+    #[deku(ctx = "version")]
+    pub passes: UnityArray<SerializedPass>,
+    pub tags: Map<CharArray, CharArray>,
+    pub lod: i32,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct SerializedPass {
+    // 2020.2 and up
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub editor_data_hash: Option<UnityArray<[u8; 16]>>,
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub platforms: Option<ByteArray>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    // until 2021.2, when keywords were merged into a single list
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1 && version < UnityVersion::V2021_3_27f1")]
+    pub local_keyword_mask: Option<UnityArray<u16>>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1 && version < UnityVersion::V2021_3_27f1")]
+    pub global_keyword_mask: Option<UnityArray<u16>>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment2: Vec<u8>,
+    pub name_indices: Map<CharArray, i32>,
+    pub pass_type: PassType,
+    #[deku(ctx = "version")]
+    pub state: SerializedShaderState,
+    pub program_mask: u32,
+    #[deku(ctx = "version")]
+    pub prog_vertex: SerializedProgram,
+    #[deku(ctx = "version")]
+    pub prog_fragment: SerializedProgram,
+    #[deku(ctx = "version")]
+    pub prog_geometry: SerializedProgram,
+    #[deku(ctx = "version")]
+    pub prog_hull: SerializedProgram,
+    #[deku(ctx = "version")]
+    pub prog_domain: SerializedProgram,
+    // 2019.3 and up
+    #[deku(ctx = "version", cond = "version >= UnityVersion::V2019_4_39f1", default = "SerializedProgram::default()")]
+    pub prog_ray_tracing: SerializedProgram,
+    pub has_instancing_variant: u8,
+    // 2018.1 and up
+    #[deku(cond = "version >= UnityVersion::V2018_1_9f2", default = "0")]
+    pub has_procedural_instancing_variant: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment3: Vec<u8>,
+    pub use_name: CharArray,
+    pub name: CharArray,
+    pub texture_name: CharArray,
+    pub tags: Map<CharArray, CharArray>,
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub serialized_keyword_state_mask: Option<UnityArray<u16>>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment4: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Copy, Debug)]
+#[deku(id_type = "i32")]
+pub enum PassType {
+    Normal = 0,
+    Use = 1,
+    Grab = 2,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct SerializedShaderState {
+    pub name: CharArray,
+    pub rt_blend: [SerializedShaderRTBlendState; 8],
+    pub rt_separate_blend: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub z_clip: SerializedShaderFloatValue,
+    pub z_test: SerializedShaderFloatValue,
+    pub z_write: SerializedShaderFloatValue,
+    pub culling: SerializedShaderFloatValue,
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub conservative: Option<SerializedShaderFloatValue>,
+    pub offset_factor: SerializedShaderFloatValue,
+    pub offset_units: SerializedShaderFloatValue,
+    pub alpha_to_mask: SerializedShaderFloatValue,
+    pub stencil_op: SerializedStencilOp,
+    pub stencil_op_front: SerializedStencilOp,
+    pub stencil_op_back: SerializedStencilOp,
+    pub stencil_read_mask: SerializedShaderFloatValue,
+    pub stencil_write_mask: SerializedShaderFloatValue,
+    pub stencil_ref: SerializedShaderFloatValue,
+    pub fog_start: SerializedShaderFloatValue,
+    pub fog_end: SerializedShaderFloatValue,
+    pub fog_density: SerializedShaderFloatValue,
+    pub fog_color: SerializedShaderVectorValue,
+    pub fog_mode: i32,
+    pub gpu_program_id: i32,
+    pub tags: Map<CharArray, CharArray>,
+    pub lod: i32,
+    pub lighting: u8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+}
+
+// A fixed-function state value; when name is non-empty, the value is taken
+// from the material property of that name instead (e.g. "Blend [_SrcBlend] [_DstBlend]")
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct SerializedShaderFloatValue {
+    pub val: f32,
+    pub name: CharArray,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct SerializedShaderVectorValue {
+    pub x: SerializedShaderFloatValue,
+    pub y: SerializedShaderFloatValue,
+    pub z: SerializedShaderFloatValue,
+    pub w: SerializedShaderFloatValue,
+    pub name: CharArray,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct SerializedShaderRTBlendState {
+    pub src_blend: SerializedShaderFloatValue,
+    pub dest_blend: SerializedShaderFloatValue,
+    pub src_blend_alpha: SerializedShaderFloatValue,
+    pub dest_blend_alpha: SerializedShaderFloatValue,
+    pub blend_op: SerializedShaderFloatValue,
+    pub blend_op_alpha: SerializedShaderFloatValue,
+    pub col_mask: SerializedShaderFloatValue,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct SerializedStencilOp {
+    pub pass: SerializedShaderFloatValue,
+    pub fail: SerializedShaderFloatValue,
+    pub z_fail: SerializedShaderFloatValue,
+    pub comp: SerializedShaderFloatValue,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug, Default)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct SerializedProgram {
+    #[deku(ctx = "version")]
+    pub sub_programs: UnityArray<SerializedSubProgram>,
+    // 2021.3.10f1 and up
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub player_sub_programs: Option<UnityArray<UnityArray<SerializedPlayerSubProgram>>>,
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub parameter_blob_indices: Option<UnityArray<UnityArray<u32>>>,
+    // 2020.3.2f1 and up
+    #[deku(ctx = "version", cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub common_parameters: Option<SerializedProgramParameters>,
+    #[deku(cond = "version >= UnityVersion::V2022_3_0f1")]
+    pub serialized_keyword_state_mask: Option<UnityArray<u16>>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct SerializedSubProgram {
+    pub blob_index: u32,
+    pub channels: ParserBindChannels,
+    // local keywords were split out from 2019.1 until 2021.2, when they
+    // were merged back into a single list
+    #[deku(cond = "version >= UnityVersion::V2019_4_39f1 && version < UnityVersion::V2021_3_27f1")]
+    pub global_keyword_indices: Option<UnityArray<u16>>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    #[deku(cond = "version >= UnityVersion::V2019_4_39f1 && version < UnityVersion::V2021_3_27f1")]
+    pub local_keyword_indices: Option<UnityArray<u16>>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+    #[deku(cond = "version < UnityVersion::V2019_4_39f1 || version >= UnityVersion::V2021_3_27f1")]
+    pub keyword_indices: Option<UnityArray<u16>>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment2: Vec<u8>,
+    pub shader_hardware_tier: i8,
+    pub gpu_program_type: i8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment3: Vec<u8>,
+    // before 2020.3.2f1 these were stored inline, but with the same layout
+    #[deku(ctx = "version")]
+    pub parameters: SerializedProgramParameters,
+    #[deku(cond = "version < UnityVersion::V2021_3_27f1")]
+    pub shader_requirements_32: Option<i32>,
+    #[deku(cond = "version >= UnityVersion::V2021_3_27f1")]
+    pub shader_requirements_64: Option<i64>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct SerializedPlayerSubProgram {
+    pub blob_index: u32,
+    pub keyword_mask: UnityArray<u16>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment0: Vec<u8>,
+    pub shader_requirements: i64,
+    pub gpu_program_type: i8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment1: Vec<u8>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct ParserBindChannels {
+    pub channels: UnityArray<ShaderBindChannel>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+    pub source_map: u32,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct ShaderBindChannel {
+    pub source: i8,
+    pub target: i8,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct SerializedProgramParameters {
+    pub vector_params: UnityArray<VectorParameter>,
+    pub matrix_params: UnityArray<MatrixParameter>,
+    pub texture_params: UnityArray<TextureParameter>,
+    #[deku(ctx = "version")]
+    pub buffer_params: UnityArray<BufferBinding>,
+    #[deku(ctx = "version")]
+    pub constant_buffers: UnityArray<ConstantBuffer>,
+    #[deku(ctx = "version")]
+    pub constant_buffer_bindings: UnityArray<BufferBinding>,
+    pub uav_params: UnityArray<UAVParameter>,
+    pub samplers: UnityArray<SamplerParameter>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct VectorParameter {
+    pub name_index: i32,
+    pub index: i32,
+    pub array_size: i32,
+    pub param_type: i8,
+    pub dim: i8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct MatrixParameter {
+    pub name_index: i32,
+    pub index: i32,
+    pub array_size: i32,
+    pub param_type: i8,
+    pub row_count: i8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct TextureParameter {
+    pub name_index: i32,
+    pub index: i32,
+    pub sampler_index: i32,
+    pub multi_sampled: u8,
+    pub dim: i8,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct BufferBinding {
+    pub name_index: i32,
+    pub index: i32,
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub array_size: Option<i32>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+#[deku(ctx = "version: UnityVersion")]
+pub struct ConstantBuffer {
+    pub name_index: i32,
+    pub matrix_params: UnityArray<MatrixParameter>,
+    pub vector_params: UnityArray<VectorParameter>,
+    pub struct_params: UnityArray<StructParameter>,
+    pub size: i32,
+    #[deku(cond = "version >= UnityVersion::V2020_3_16f1")]
+    pub is_partial_cb: Option<u8>,
+    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct StructParameter {
+    pub name_index: i32,
+    pub index: i32,
+    pub array_size: i32,
+    pub struct_size: i32,
+    pub vector_params: UnityArray<VectorParameter>,
+    pub matrix_params: UnityArray<MatrixParameter>,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct UAVParameter {
+    pub name_index: i32,
+    pub index: i32,
+    pub original_index: i32,
+}
+
+#[derive(DekuRead, ParserSchema, Clone, Debug)]
+pub struct SamplerParameter {
+    pub sampler: u32,
+    pub bind_point: i32,
+}
+
+// Each platform's decompressed blob starts with a table locating its
+// sub-programs, which SerializedSubProgram.blob_index indexes into
+#[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "segmented: bool")]
+pub struct ShaderSubProgramEntry {
+    pub offset: u32,
+    pub length: u32,
+    #[deku(cond = "segmented", default = "0")]
+    pub segment: u32,
+}
+
+// The payload of one decompressed sub-program; for GL platforms the program
+// code is GLSL text, while other platforms store compiled bytecode
+#[derive(DekuRead, Clone, Debug)]
+pub struct ShaderSubProgram {
+    pub version: i32,
+    pub program_type: i32,
+    _unknown0: [u32; 3],
+    #[deku(cond = "*version >= 201608170")]
+    _unknown1: Option<u32>,
+    pub keywords: UnityArray<CharArray>,
+    #[deku(cond = "*version >= 201806140 && *version < 202012090")]
+    pub local_keywords: Option<UnityArray<CharArray>>,
+    pub program_code: ByteArray,
+}
@@ -2,25 +2,41 @@ use std::borrow::Cow;
 use std::io::{Seek, SeekFrom};
 use std::{collections::HashMap, fmt::Debug, hash::Hash, marker::PhantomData};
 use std::clone::Clone;
+use std::cell::{Cell, RefCell};
 
 use wasm_bindgen::prelude::*;
 use deku::{ctx::BitSize, prelude::*};
+use serde::Serialize;
 
 // Important: these must be ordered by chronological release date, so
 // PartialOrd can correctly compare them.
 #[wasm_bindgen(js_name = "UnityVersion")]
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub enum UnityVersion {
+    V4_7_2f1,
+    V5_4_6f1,
+    V2017_3_0f3,
+    V2018_1_9f2,
+    V2018_2_21f1,
+    V2018_4_36f1,
     V2019_4_39f1,
     V2020_3_16f1,
     V2021_3_27f1,
+    V2022_3_0f1,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 pub struct UnityArray<T> {
     pub values: Vec<T>,
 }
 
+// Written out rather than derived, which would needlessly require T: Default
+impl<T> Default for UnityArray<T> {
+    fn default() -> Self {
+        UnityArray { values: Vec::new() }
+    }
+}
+
 fn check_count(count: usize, limit: usize) -> Result<(), DekuError> {
     if count > limit {
         return Err(DekuError::Assertion(Cow::from(format!("Got unreasonably large count: {} > {}", count, limit))));
@@ -28,12 +44,287 @@ fn check_count(count: usize, limit: usize) -> Result<(), DekuError> {
     Ok(())
 }
 
+// Asset files can come from arbitrary user-provided archives, so in hardened
+// mode we cap sizes up front rather than letting a bogus count OOM the tab
+#[wasm_bindgen(js_name = "UnityParseLimits")]
+#[derive(Debug, Clone, Copy)]
+pub struct ParseLimits {
+    pub max_array_length: usize,
+    pub max_string_length: usize,
+    pub max_depth: usize,
+    // total bytes of arrays and strings per object
+    pub allocation_budget: usize,
+}
+
+#[wasm_bindgen(js_class = "UnityParseLimits")]
+impl ParseLimits {
+    pub fn hardened() -> ParseLimits {
+        ParseLimits {
+            max_array_length: 1 << 24,
+            max_string_length: 1 << 20,
+            max_depth: 32,
+            allocation_budget: 512 << 20,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct ParseBudget {
+    depth: usize,
+    allocated: usize,
+}
+
+thread_local! {
+    static PARSE_LIMITS: Cell<Option<ParseLimits>> = const { Cell::new(None) };
+    static PARSE_BUDGET: Cell<ParseBudget> = Cell::new(ParseBudget::default());
+    // the array elements we were inside when parsing failed, innermost first
+    static ERROR_PATH: RefCell<Vec<ErrorPathElement>> = const { RefCell::new(Vec::new()) };
+    // whether deku's field trace logs are being followed, and the last field
+    // they said was being read, as "Type.field"
+    static FIELD_TRACING: Cell<bool> = const { Cell::new(false) };
+    static CURRENT_FIELD: RefCell<Option<String>> = const { RefCell::new(None) };
+    // PPtrs don't get a version ctx, since they're in nearly every type
+    static OBJECT_VERSION: Cell<Option<UnityVersion>> = const { Cell::new(None) };
+}
+
+// Passing None turns hardened mode back off
+#[wasm_bindgen(js_name = "unity_set_parse_limits")]
+pub fn set_parse_limits(limits: Option<ParseLimits>) {
+    PARSE_LIMITS.with(|v| v.set(limits));
+}
+
+// Decompressed sizes come from headers too, so in hardened mode nothing
+// bigger than the whole allocation budget gets allocated for one
+pub fn check_decompressed_size(size: u64) -> Result<(), String> {
+    match PARSE_LIMITS.with(|v| v.get()) {
+        Some(limits) if size > limits.allocation_budget as u64 => {
+            Err(format!("decompressed size {} is over the allocation budget of {}", size, limits.allocation_budget))
+        },
+        _ => Ok(()),
+    }
+}
+
+// Called before parsing each top-level object
+pub fn reset_parse_budget() {
+    PARSE_BUDGET.with(|v| v.set(ParseBudget::default()));
+    ERROR_PATH.with(|v| v.borrow_mut().clear());
+}
+
+// Forgets everything left over from the last parse, freeing the error path.
+// Parse limits are a setting rather than state, so they're kept.
+pub fn reset_parse_state() {
+    PARSE_BUDGET.with(|v| v.set(ParseBudget::default()));
+    ERROR_PATH.with(|v| *v.borrow_mut() = Vec::new());
+    CURRENT_FIELD.with(|v| v.borrow_mut().take());
+    OBJECT_VERSION.with(|v| v.set(None));
+}
+
+// Called before parsing each top-level object, so that PPtrs within it can
+// tell how wide their path IDs are
+pub fn set_object_version(version: UnityVersion) {
+    OBJECT_VERSION.with(|v| v.set(Some(version)));
+}
+
+// Strips module paths from a type name, so a::b::Foo<c::Bar> becomes Foo<Bar>
+fn short_type_name<T>() -> String {
+    let mut result = String::new();
+    let mut ident = String::new();
+    for c in std::any::type_name::<T>().chars() {
+        if c.is_alphanumeric() || c == '_' {
+            ident.push(c);
+        } else if c == ':' {
+            ident.clear();
+        } else {
+            result.push_str(&ident);
+            ident.clear();
+            result.push(c);
+        }
+    }
+    result.push_str(&ident);
+    result
+}
+
+enum ErrorPathElement {
+    // the field that was being read where the parse failed, as "Type.field"
+    Field(String),
+    Element {
+        // the "Type.field" holding the array, if fields were being traced
+        field: Option<String>,
+        type_name: String,
+        index: usize,
+    },
+}
+
+fn field_name(field: &str) -> &str {
+    field.rsplit('.').next().unwrap_or(field)
+}
+
+// The field an array or map is about to be read into, if fields are being
+// traced. Cheap when they aren't, since it's called for every array.
+fn current_field() -> Option<String> {
+    if !FIELD_TRACING.with(|v| v.get()) {
+        return None;
+    }
+    CURRENT_FIELD.with(|v| v.borrow().clone())
+}
+
+fn note_error_element<T>(field: &Option<String>, index: usize) {
+    ERROR_PATH.with(|v| {
+        let mut path = v.borrow_mut();
+        // the innermost array also records which of its element's fields
+        // failed, unless the element has no fields of its own
+        if path.is_empty() {
+            if let Some(current) = current_field().filter(|current| Some(current) != field.as_ref()) {
+                path.push(ErrorPathElement::Field(current));
+            }
+        }
+        path.push(ErrorPathElement::Element { field: field.clone(), type_name: short_type_name::<T>(), index });
+    });
+}
+
+// Describes where the last parse failed, like "sub_meshes[3].first_byte", or
+// with type names, like "SubShader[0].Pass[2]", where fields weren't traced
+pub fn take_error_path() -> String {
+    let path = ERROR_PATH.with(|v| std::mem::take(&mut *v.borrow_mut()));
+    if path.is_empty() {
+        return current_field().map(|field| field_name(&field).to_string()).unwrap_or_default();
+    }
+    let mut result = String::new();
+    let mut outer_field = None;
+    for element in path.iter().rev() {
+        match element {
+            ErrorPathElement::Field(field) => {
+                result.push('.');
+                result.push_str(field_name(field));
+            },
+            // nested arrays in the same field read as field[i][j]
+            ErrorPathElement::Element { field: Some(field), index, .. } if outer_field == Some(field) => {
+                result.push_str(&format!("[{}]", index));
+            },
+            ErrorPathElement::Element { field, type_name, index } => {
+                let name = field.as_deref().map_or(type_name.as_str(), field_name);
+                result.push_str(&format!(".{}[{}]", name, index));
+                outer_field = field.as_ref();
+            },
+        }
+    }
+    result.split_off(1)
+}
+
+// Follows deku's "Reading: Type.field" trace logs, but only while
+// trace_error_path has turned them on
+struct FieldTracer;
+
+impl log::Log for FieldTracer {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() == log::Level::Trace && FIELD_TRACING.with(|v| v.get())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        if let Some(field) = message.strip_prefix("Reading: ") {
+            CURRENT_FIELD.with(|v| *v.borrow_mut() = Some(field.to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static FIELD_TRACER: FieldTracer = FieldTracer;
+
+// Runs a parse that's already failed once again, with field tracing on, and
+// returns where it failed with the names of the fields it was in. Tracing
+// every field read is slow, so it's only done after the fact.
+pub fn trace_error_path(parse: impl FnOnce()) -> String {
+    // if something else installed a logger first, this fails and the path
+    // falls back to type names
+    let _ = log::set_logger(&FIELD_TRACER);
+    let max_level = log::max_level();
+    log::set_max_level(log::LevelFilter::Trace);
+    FIELD_TRACING.with(|v| v.set(true));
+    parse();
+    let path = take_error_path();
+    FIELD_TRACING.with(|v| v.set(false));
+    CURRENT_FIELD.with(|v| v.borrow_mut().take());
+    log::set_max_level(max_level);
+    path
+}
+
+fn charge_allocation(limits: &ParseLimits, byte_size: usize) -> Result<(), DekuError> {
+    PARSE_BUDGET.with(|v| {
+        let mut budget = v.get();
+        budget.allocated = budget.allocated.saturating_add(byte_size);
+        v.set(budget);
+        if budget.allocated > limits.allocation_budget {
+            return Err(DekuError::Assertion(Cow::from(format!("Exceeded allocation budget: {} > {}", budget.allocated, limits.allocation_budget))));
+        }
+        Ok(())
+    })
+}
+
+pub fn check_array_length(count: usize, element_size: usize) -> Result<(), DekuError> {
+    match PARSE_LIMITS.with(|v| v.get()) {
+        Some(limits) => {
+            check_count(count, limits.max_array_length)?;
+            charge_allocation(&limits, count.saturating_mul(element_size))
+        },
+        None => Ok(()),
+    }
+}
+
+pub fn check_string_length(length: u32) -> Result<u32, DekuError> {
+    if let Some(limits) = PARSE_LIMITS.with(|v| v.get()) {
+        check_count(length as usize, limits.max_string_length)?;
+        charge_allocation(&limits, length as usize)?;
+    }
+    Ok(length)
+}
+
+// Tracks how deeply arrays are nested while it's alive
+struct DepthGuard;
+
+impl DepthGuard {
+    fn enter() -> Result<Self, DekuError> {
+        let depth = PARSE_BUDGET.with(|v| {
+            let mut budget = v.get();
+            budget.depth += 1;
+            v.set(budget);
+            budget.depth
+        });
+        let guard = DepthGuard;
+        if let Some(limits) = PARSE_LIMITS.with(|v| v.get()) {
+            if depth > limits.max_depth {
+                return Err(DekuError::Assertion(Cow::from(format!("Exceeded max nesting depth {}", limits.max_depth))));
+            }
+        }
+        Ok(guard)
+    }
+}
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        PARSE_BUDGET.with(|v| {
+            let mut budget = v.get();
+            budget.depth = budget.depth.saturating_sub(1);
+            v.set(budget);
+        });
+    }
+}
+
 impl<'a, T, Ctx> DekuReader<'a, Ctx> for UnityArray<T> where T: DekuReader<'a, Ctx>, Ctx: Clone {
     fn from_reader_with_ctx<R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>, ctx: Ctx) -> Result<Self, DekuError> {
         let count = i32::from_reader_with_ctx(reader, ())? as usize;
+        check_array_length(count, std::mem::size_of::<T>())?;
+        let _guard = DepthGuard::enter()?;
+        let field = current_field();
         let mut values = Vec::new();
-        for _ in 0..count {
-            values.push(T::from_reader_with_ctx(reader, ctx.clone())?);
+        for i in 0..count {
+            let value = T::from_reader_with_ctx(reader, ctx.clone())
+                .inspect_err(|_| note_error_element::<T>(&field, i))?;
+            values.push(value);
         }
         Ok(UnityArray {
             values,
@@ -47,7 +338,7 @@ impl<PreT, ResT> From<UnityArray<PreT>> for Vec<ResT> where ResT: From<PreT> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Map<K, V> {
     pub keys: Vec<K>,
     pub values: Vec<V>,
@@ -58,11 +349,18 @@ impl<'a, K, V, Ctx> DekuReader<'a, Ctx> for Map<K, V>
 {
     fn from_reader_with_ctx<R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>, ctx: Ctx) -> Result<Self, DekuError> {
         let count = i32::from_reader_with_ctx(reader, ())?;
+        check_array_length(count as usize, std::mem::size_of::<K>() + std::mem::size_of::<V>())?;
+        let _guard = DepthGuard::enter()?;
+        let field = current_field();
         let mut keys = Vec::new();
         let mut values = Vec::new();
-        for _ in 0..count {
-            keys.push(K::from_reader_with_ctx(reader, ctx.clone())?);
-            values.push(V::from_reader_with_ctx(reader, ctx.clone())?);
+        for i in 0..count {
+            let key = K::from_reader_with_ctx(reader, ctx.clone())
+                .inspect_err(|_| note_error_element::<K>(&field, i as usize))?;
+            let value = V::from_reader_with_ctx(reader, ctx.clone())
+                .inspect_err(|_| note_error_element::<V>(&field, i as usize))?;
+            keys.push(key);
+            values.push(value);
         }
         Ok(Map {
             keys,
@@ -88,6 +386,7 @@ impl<PreK, PreV, ResK, ResV> From<Map<PreK, PreV>> for HashMap<ResK, ResV>
 
 #[derive(DekuRead, Clone, Default)]
 pub struct CharArray {
+    #[deku(map = "check_string_length")]
     count: u32,
     #[deku(count = "*count")]
     bytes: Vec<u8>,
@@ -133,14 +432,40 @@ impl Debug for NullTerminatedAsciiString {
     }
 }
 
-#[derive(DekuRead, Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct PPtr<T> {
     pub file_index: u32,
     pub path_id: i64,
-    #[deku(skip)]
     _foo: PhantomData<T>,
 }
 
+impl<'a, T, Ctx> DekuReader<'a, Ctx> for PPtr<T> where Ctx: Copy {
+    fn from_reader_with_ctx<R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>, _ctx: Ctx) -> Result<Self, DekuError> {
+        let file_index = u32::from_reader_with_ctx(reader, ())?;
+        // path IDs were 32-bit before 5.0
+        let path_id = match OBJECT_VERSION.with(|v| v.get()) {
+            Some(version) if version < UnityVersion::V5_4_6f1 => i32::from_reader_with_ctx(reader, ())? as i64,
+            _ => i64::from_reader_with_ctx(reader, ())?,
+        };
+        Ok(PPtr {
+            file_index,
+            path_id,
+            _foo: PhantomData,
+        })
+    }
+}
+
+// The null pointer, for fields that older versions don't have
+impl<T> Default for PPtr<T> {
+    fn default() -> Self {
+        PPtr {
+            file_index: 0,
+            path_id: 0,
+            _foo: PhantomData,
+        }
+    }
+}
+
 #[wasm_bindgen(js_name = "UnityVec4")]
 #[derive(DekuRead, Debug, Copy, Clone)]
 pub struct Vec4 {
@@ -159,14 +484,23 @@ pub struct Vec3 {
 }
 
 #[wasm_bindgen(js_name = "UnityVec2")]
-#[derive(DekuRead, Debug, Copy, Clone)]
+#[derive(DekuRead, Serialize, Debug, Copy, Clone)]
 pub struct Vec2 {
     pub x: f32,
     pub y: f32,
 }
 
-#[wasm_bindgen(js_name = "UnityColorRGBA")]
+#[wasm_bindgen(js_name = "UnityRect")]
 #[derive(DekuRead, Debug, Copy, Clone)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[wasm_bindgen(js_name = "UnityColorRGBA")]
+#[derive(DekuRead, Serialize, Debug, Copy, Clone)]
 pub struct ColorRGBA {
     pub r: f32,
     pub g: f32,
@@ -208,7 +542,7 @@ fn unpack_i32s<R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>, num_ite
     Ok(result)
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Packedi32Vec {
     pub data: Vec<i32>,
 }
@@ -222,6 +556,7 @@ impl From<Packedi32Vec> for Vec<i32> {
 impl<'a, Ctx> DekuReader<'a, Ctx> for Packedi32Vec where Ctx: Clone {
     fn from_reader_with_ctx<R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>, _ctx: Ctx) -> Result<Self, DekuError> {
         let num_items = u32::from_reader_with_ctx(reader, ())? as usize;
+        check_array_length(num_items, std::mem::size_of::<i32>())?;
         let byte_array_count = u32::from_reader_with_ctx(reader, ())? as usize;
         reader.seek(SeekFrom::Current(byte_array_count as i64)).unwrap();
         let bit_size: u8 = u8::from_reader_with_ctx(reader, ())?;
@@ -236,7 +571,7 @@ impl<'a, Ctx> DekuReader<'a, Ctx> for Packedi32Vec where Ctx: Clone {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Packedf32Vec {
     pub data: Vec<f32>,
 }
@@ -250,6 +585,7 @@ impl From<Packedf32Vec> for Vec<f32> {
 impl<'a, Ctx> DekuReader<'a, Ctx> for Packedf32Vec where Ctx: Clone {
     fn from_reader_with_ctx<R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>, _ctx: Ctx) -> Result<Self, DekuError> {
         let num_items = u32::from_reader_with_ctx(reader, ())?;
+        check_array_length(num_items as usize, std::mem::size_of::<f32>())?;
         let scale = f32::from_reader_with_ctx(reader, ())?;
         let start = f32::from_reader_with_ctx(reader, ())?;
         let byte_array_count = u32::from_reader_with_ctx(reader, ())? as usize;
@@ -272,3 +608,34 @@ impl<'a, Ctx> DekuReader<'a, Ctx> for Packedf32Vec where Ctx: Clone {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(DekuRead)]
+    struct SubMesh {
+        first_byte: u32,
+        index_count: u32,
+    }
+
+    #[derive(DekuRead)]
+    struct Mesh {
+        flags: u32,
+        sub_meshes: UnityArray<SubMesh>,
+    }
+
+    #[test]
+    fn test_error_path() {
+        // the second submesh is cut off after first_byte
+        let data: Vec<u8> = [0u32, 2, 0, 6, 6].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let parse = || {
+            reset_parse_budget();
+            let mut cursor = std::io::Cursor::new(&data);
+            assert!(Mesh::from_reader_with_ctx(&mut Reader::new(&mut cursor), ()).is_err());
+        };
+        parse();
+        assert_eq!(take_error_path(), "SubMesh[1]");
+        assert_eq!(trace_error_path(parse), "sub_meshes[1].index_count");
+    }
+}
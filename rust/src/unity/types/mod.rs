@@ -3,3 +3,6 @@ pub mod wasm;
 pub mod common;
 pub mod class_id;
 pub mod serialized_file;
+pub mod physx;
+pub mod navmesh;
+pub mod schema;
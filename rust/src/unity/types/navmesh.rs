@@ -0,0 +1,87 @@
+use deku::prelude::*;
+
+// Each NavMeshData tile stores its polygons in a Recast/Detour tile blob
+// (see dtMeshHeader and dtCreateNavMeshData in recastnavigation's
+// DetourNavMesh.h/DetourNavMeshBuilder.cpp). We read the header, vertices,
+// and polygons, then skip the links, detail meshes, and BV tree.
+
+const NAVMESH_MAGIC: i32 = (b'D' as i32) << 24 | (b'N' as i32) << 16 | (b'A' as i32) << 8 | (b'V' as i32);
+const VERTS_PER_POLYGON: usize = 6;
+const POLYTYPE_OFFMESH_CONNECTION: u8 = 1;
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct NavMeshTileHeader {
+    #[deku(assert_eq = "NAVMESH_MAGIC")]
+    pub magic: i32,
+    pub version: i32,
+    pub x: i32,
+    pub y: i32,
+    pub layer: i32,
+    pub user_id: u32,
+    pub poly_count: i32,
+    pub vert_count: i32,
+    pub max_link_count: i32,
+    pub detail_mesh_count: i32,
+    pub detail_vert_count: i32,
+    pub detail_tri_count: i32,
+    pub bv_node_count: i32,
+    pub off_mesh_con_count: i32,
+    pub off_mesh_base: i32,
+    pub walkable_height: f32,
+    pub walkable_radius: f32,
+    pub walkable_climb: f32,
+    pub bmin: [f32; 3],
+    pub bmax: [f32; 3],
+    pub bv_quant_factor: f32,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct NavMeshTile {
+    pub header: NavMeshTileHeader,
+    #[deku(count = "header.vert_count * 3")]
+    pub vertices: Vec<f32>,
+    #[deku(count = "header.poly_count")]
+    pub polys: Vec<NavMeshPoly>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct NavMeshPoly {
+    pub first_link: u32,
+    pub verts: [u16; VERTS_PER_POLYGON],
+    pub neighbors: [u16; VERTS_PER_POLYGON],
+    pub flags: u16,
+    pub vert_count: u8,
+    pub area_and_type: u8,
+}
+
+impl NavMeshPoly {
+    pub fn area(&self) -> u8 {
+        self.area_and_type & 0x3f
+    }
+
+    pub fn is_off_mesh_connection(&self) -> bool {
+        self.area_and_type >> 6 == POLYTYPE_OFFMESH_CONNECTION
+    }
+}
+
+impl NavMeshTile {
+    // Fan-triangulates the tile's ground polygons, returning triangle indices
+    // into the tile's vertices along with each triangle's area type
+    pub fn triangulate(&self) -> (Vec<u32>, Vec<u8>) {
+        let mut indices = Vec::new();
+        let mut areas = Vec::new();
+        for poly in &self.polys {
+            if poly.is_off_mesh_connection() {
+                continue;
+            }
+            let vert_count = (poly.vert_count as usize).min(VERTS_PER_POLYGON);
+            for i in 2..vert_count {
+                indices.push(poly.verts[0] as u32);
+                indices.push(poly.verts[i - 1] as u32);
+                indices.push(poly.verts[i] as u32);
+                areas.push(poly.area());
+            }
+        }
+        (indices, areas)
+    }
+}
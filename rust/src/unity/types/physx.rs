@@ -0,0 +1,119 @@
+use deku::prelude::*;
+
+// Meshes with collision enabled carry PhysX "cooked" versions of themselves in
+// m_BakedTriangleCollisionMesh and m_BakedConvexCollisionMesh, which survive
+// even when the render data has been stripped. The formats below follow
+// PhysX 4.1's GuSerialize/TriangleMeshBuilder/ConvexHullBuilder, which is what
+// Unity 2019.3+ ships with. We only read as far as the geometry; the midphase
+// acceleration structures and GPU data that follow are ignored.
+
+const SERIAL_FLAG_MATERIALS: u32 = 1 << 0;
+const SERIAL_FLAG_FACE_REMAP: u32 = 1 << 1;
+const SERIAL_FLAG_8BIT_INDICES: u32 = 1 << 2;
+const SERIAL_FLAG_16BIT_INDICES: u32 = 1 << 3;
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(magic = b"NXS\x01MESH")]
+pub struct CookedTriangleMesh {
+    pub version: u32,
+    #[deku(cond = "*version >= 14", default = "0")]
+    pub midphase_id: u32,
+    pub serial_flags: u32,
+    #[deku(cond = "*version <= 12")]
+    _convex_edge_threshold: Option<f32>,
+    pub vertex_count: u32,
+    pub triangle_count: u32,
+    #[deku(count = "*vertex_count * 3")]
+    pub vertices: Vec<f32>,
+    #[deku(reader = "read_triangle_indices(deku::reader, *serial_flags, *triangle_count as usize * 3)")]
+    pub indices: Vec<u32>,
+    #[deku(cond = "*serial_flags & SERIAL_FLAG_MATERIALS != 0", count = "*triangle_count")]
+    pub material_indices: Vec<u16>,
+    #[deku(cond = "*serial_flags & SERIAL_FLAG_FACE_REMAP != 0", default = "0")]
+    face_remap_max_index: u32,
+    #[deku(
+        cond = "*serial_flags & SERIAL_FLAG_FACE_REMAP != 0",
+        reader = "read_indices(deku::reader, *face_remap_max_index, *triangle_count as usize)",
+    )]
+    pub face_remap: Vec<u32>,
+}
+
+fn read_triangle_indices<R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>, serial_flags: u32, count: usize) -> Result<Vec<u32>, DekuError> {
+    let mut result = Vec::with_capacity(count);
+    for _ in 0..count {
+        let index = if serial_flags & SERIAL_FLAG_8BIT_INDICES != 0 {
+            u8::from_reader_with_ctx(reader, ())? as u32
+        } else if serial_flags & SERIAL_FLAG_16BIT_INDICES != 0 {
+            u16::from_reader_with_ctx(reader, ())? as u32
+        } else {
+            u32::from_reader_with_ctx(reader, ())?
+        };
+        result.push(index);
+    }
+    Ok(result)
+}
+
+// same as above, but the index size is picked from the largest stored value
+fn read_indices<R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>, max_index: u32, count: usize) -> Result<Vec<u32>, DekuError> {
+    let serial_flags = if max_index <= 0xFF {
+        SERIAL_FLAG_8BIT_INDICES
+    } else if max_index <= 0xFFFF {
+        SERIAL_FLAG_16BIT_INDICES
+    } else {
+        0
+    };
+    read_triangle_indices(reader, serial_flags, count)
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(magic = b"NXS\x01CVXM")]
+pub struct CookedConvexMesh {
+    pub version: u32,
+    pub serial_flags: u32,
+    pub hull: ConvexHull,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+#[deku(magic = b"NXS\x01CLHL")]
+pub struct ConvexHull {
+    pub version: u32,
+    pub vertex_count: u32,
+    // the top bit flags whether GPU data follows the hull
+    pub edge_data: u32,
+    pub polygon_count: u32,
+    pub polygon_vertex_ref_count: u32,
+    #[deku(count = "*vertex_count * 3")]
+    pub vertices: Vec<f32>,
+    #[deku(count = "*polygon_count")]
+    pub polygons: Vec<HullPolygon>,
+    #[deku(count = "*polygon_vertex_ref_count")]
+    pub polygon_vertex_refs: Vec<u8>,
+}
+
+#[derive(DekuRead, Clone, Debug)]
+pub struct HullPolygon {
+    pub plane: [f32; 4],
+    pub vertex_ref_offset: u16,
+    pub vertex_count: u8,
+    pub min_index: u8,
+}
+
+impl ConvexHull {
+    // Fan-triangulates the hull's polygons, which PhysX guarantees are convex
+    pub fn triangulate(&self) -> Vec<u32> {
+        let mut result = Vec::new();
+        for polygon in &self.polygons {
+            let start = polygon.vertex_ref_offset as usize;
+            let end = start + polygon.vertex_count as usize;
+            let Some(refs) = self.polygon_vertex_refs.get(start..end) else {
+                continue;
+            };
+            for i in 2..refs.len() {
+                result.push(refs[0] as u32);
+                result.push(refs[i - 1] as u32);
+                result.push(refs[i] as u32);
+            }
+        }
+        result
+    }
+}
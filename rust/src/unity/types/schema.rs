@@ -0,0 +1,231 @@
+use serde::Serialize;
+use tsify::Tsify;
+
+use crate::unity::types::binary;
+use crate::unity::types::serialized_file::TypeTreeField;
+
+// Compares our hardcoded parsers against the type trees that editor builds
+// and asset bundles embed, to find fields we're missing or gating on the
+// wrong version. Field names are matched loosely, so m_IsPartialCB matches
+// is_partial_cb.
+
+pub struct ParserField {
+    pub name: &'static str,
+    pub type_name: &'static str,
+    pub cond: Option<&'static str>,
+}
+
+pub trait ParserSchema {
+    fn parser_fields() -> &'static [ParserField];
+}
+
+macro_rules! define_schemas {
+    ($($unity_name:literal => $t:ident),* $(,)?) => {
+        fn parser_fields_for_unity_type(unity_name: &str) -> Option<&'static [ParserField]> {
+            match unity_name {
+                $($unity_name => Some(<binary::$t as ParserSchema>::parser_fields()),)*
+                _ => None,
+            }
+        }
+
+        fn parser_fields_for_rust_type(type_name: &str) -> Option<&'static [ParserField]> {
+            match type_name {
+                $(stringify!($t) => Some(<binary::$t as ParserSchema>::parser_fields()),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+define_schemas! {
+    "GameObject" => GameObject,
+    "Transform" => Transform,
+    "Material" => Material,
+    "UnityTexEnv" => TexEnv,
+    "MeshRenderer" => MeshRenderer,
+    "Renderer" => Renderer,
+    "SkinnedMeshRenderer" => SkinnedMeshRenderer,
+    "StaticBatchInfo" => StaticBatchInfo,
+    "Mesh" => Mesh,
+    "SubMesh" => SubMesh,
+    "VertexData" => VertexData,
+    "ChannelInfo" => ChannelInfo,
+    "CompressedMesh" => CompressedMesh,
+    "StreamingInfo" => StreamingInfo,
+    "MeshFilter" => MeshFilter,
+    "Collider2D" => Collider2D,
+    "BoxCollider2D" => BoxCollider2D,
+    "CircleCollider2D" => CircleCollider2D,
+    "PolygonCollider2D" => PolygonCollider2D,
+    "EdgeCollider2D" => EdgeCollider2D,
+    "SpriteAtlas" => SpriteAtlas,
+    "SpriteAtlasData" => SpriteAtlasData,
+    "Terrain" => Terrain,
+    "TerrainData" => TerrainData,
+    "SplatDatabase" => SplatDatabase,
+    "DetailDatabase" => DetailDatabase,
+    "Heightmap" => Heightmap,
+    "RenderSettings" => RenderSettings,
+    "LightmapSettings" => LightmapSettings,
+    "LightmapData" => LightmapData,
+    "BuildSettings" => BuildSettings,
+    "PreloadData" => PreloadData,
+    "AssetBundle" => AssetBundle,
+    "AssetInfo" => AssetBundleAssetInfo,
+    "Texture2D" => Texture2D,
+    "GLTextureSettings" => GLTextureSettings,
+    "Shader" => Shader,
+    "SerializedShader" => SerializedShader,
+    "SerializedShaderDependency" => SerializedShaderDependency,
+    "SerializedProperty" => SerializedProperty,
+    "SerializedTextureProperty" => SerializedTextureProperty,
+    "SerializedSubShader" => SerializedSubShader,
+    "SerializedPass" => SerializedPass,
+    "SerializedShaderState" => SerializedShaderState,
+    "SerializedShaderFloatValue" => SerializedShaderFloatValue,
+    "SerializedShaderVectorValue" => SerializedShaderVectorValue,
+    "SerializedShaderRTBlendState" => SerializedShaderRTBlendState,
+    "SerializedStencilOp" => SerializedStencilOp,
+    "SerializedProgram" => SerializedProgram,
+    "SerializedSubProgram" => SerializedSubProgram,
+    "SerializedPlayerSubProgram" => SerializedPlayerSubProgram,
+    "ParserBindChannels" => ParserBindChannels,
+    "ShaderBindChannel" => ShaderBindChannel,
+    "SerializedProgramParameters" => SerializedProgramParameters,
+    "VectorParameter" => VectorParameter,
+    "MatrixParameter" => MatrixParameter,
+    "TextureParameter" => TextureParameter,
+    "BufferBinding" => BufferBinding,
+    "ConstantBuffer" => ConstantBuffer,
+    "StructParameter" => StructParameter,
+    "UAVParameter" => UAVParameter,
+    "SamplerParameter" => SamplerParameter,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct SchemaMismatch {
+    // e.g. Shader.m_ParsedForm.m_SubShaders.Array.data.m_Passes
+    pub path: String,
+    pub message: String,
+}
+
+fn normalize_name(name: &str) -> String {
+    name.strip_prefix("m_").unwrap_or(name)
+        .chars()
+        .filter(|c| *c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn strip_option(type_name: &str) -> &str {
+    type_name.strip_prefix("Option<")
+        .and_then(|inner| inner.strip_suffix('>'))
+        .unwrap_or(type_name)
+}
+
+fn primitive_size(type_name: &str) -> Option<i32> {
+    match strip_option(type_name) {
+        "u8" | "i8" => Some(1),
+        "u16" | "i16" => Some(2),
+        "u32" | "i32" | "f32" => Some(4),
+        "u64" | "i64" | "f64" => Some(8),
+        _ => None,
+    }
+}
+
+pub fn children(fields: &[TypeTreeField], index: usize) -> Vec<usize> {
+    let level = fields[index].level;
+    let mut result = Vec::new();
+    for (i, field) in fields.iter().enumerate().skip(index + 1) {
+        if field.level <= level {
+            break;
+        }
+        if field.level == level + 1 {
+            result.push(i);
+        }
+    }
+    result
+}
+
+// Parser fields, with any nested parser struct that doesn't line up with a
+// type tree field expanded in place (e.g. MeshRenderer's Renderer, which
+// Unity stores as the subclass's own fields)
+fn flatten_parser_fields(fields: &'static [ParserField], type_tree_names: &[String]) -> Vec<&'static ParserField> {
+    let mut result = Vec::new();
+    for field in fields {
+        let inner = if type_tree_names.contains(&normalize_name(field.name)) {
+            None
+        } else {
+            parser_fields_for_rust_type(strip_option(field.type_name))
+        };
+        match inner {
+            Some(inner_fields) => result.extend(flatten_parser_fields(inner_fields, type_tree_names)),
+            None => result.push(field),
+        }
+    }
+    result
+}
+
+fn diff_struct(fields: &[TypeTreeField], index: usize, parser_fields: &'static [ParserField], path: &str, result: &mut Vec<SchemaMismatch>) {
+    let child_indices = children(fields, index);
+    let type_tree_names: Vec<String> = child_indices.iter()
+        .map(|&i| normalize_name(&fields[i].name))
+        .collect();
+    let parser_fields = flatten_parser_fields(parser_fields, &type_tree_names);
+    let mut matched = vec![false; parser_fields.len()];
+    for (&child_index, child_name) in child_indices.iter().zip(&type_tree_names) {
+        let child = &fields[child_index];
+        let child_path = format!("{}.{}", path, child.name);
+        let Some(parser_index) = parser_fields.iter().position(|field| normalize_name(field.name) == *child_name) else {
+            result.push(SchemaMismatch {
+                path: child_path,
+                message: format!("{} ({} bytes) isn't read by the parser", child.type_name, child.byte_size),
+            });
+            continue;
+        };
+        matched[parser_index] = true;
+        let parser_field = parser_fields[parser_index];
+        if let Some(size) = primitive_size(parser_field.type_name) {
+            if child.byte_size >= 0 && child.byte_size != size {
+                result.push(SchemaMismatch {
+                    path: child_path.clone(),
+                    message: format!("{} is {} bytes, but the parser reads {} as {}", child.type_name, child.byte_size, parser_field.name, parser_field.type_name),
+                });
+            }
+        }
+        walk(fields, child_index, &child_path, result);
+    }
+    for (field, matched) in parser_fields.iter().zip(matched) {
+        // underscored fields are padding and the like
+        if matched || field.name.starts_with('_') {
+            continue;
+        }
+        let cond = match field.cond {
+            Some(cond) => format!(" (when {})", cond),
+            None => String::new(),
+        };
+        result.push(SchemaMismatch {
+            path: format!("{}.{}", path, field.name),
+            message: format!("the parser reads {}{}, which isn't in the type tree", field.type_name, cond),
+        });
+    }
+}
+
+fn walk(fields: &[TypeTreeField], index: usize, path: &str, result: &mut Vec<SchemaMismatch>) {
+    if let Some(parser_fields) = parser_fields_for_unity_type(&fields[index].type_name) {
+        return diff_struct(fields, index, parser_fields, path, result);
+    }
+    for child_index in children(fields, index) {
+        walk(fields, child_index, &format!("{}.{}", path, fields[child_index].name), result);
+    }
+}
+
+// Diffs a type tree against our parsers, returning nothing if its class has
+// no parser or it matches
+pub fn diff_type_tree(fields: &[TypeTreeField]) -> Vec<SchemaMismatch> {
+    let mut result = Vec::new();
+    if let Some(root) = fields.first() {
+        walk(fields, 0, &root.type_name, &mut result);
+    }
+    result
+}
@@ -1,10 +1,13 @@
+use std::convert::TryFrom;
 use std::fmt::Debug;
 use deku::prelude::*;
+use serde::Serialize;
+use tsify::Tsify;
 
 use crate::unity::types::common::{NullTerminatedAsciiString, UnityArray};
 use crate::unity::types::class_id::ClassID;
 
-// Supports v21, v22, and above
+// Supports v9 (3.5 through 4.x), v15 (5.0 through 5.4) through v21, v22, and above
 
 #[derive(DekuRead, Clone, Debug)]
 #[deku(endian = "big")]
@@ -30,32 +33,66 @@ pub struct SerializedFileHeader {
 pub struct SerializedFileMetadata {
     pub version_ascii: NullTerminatedAsciiString,
     pub target_platform: u32,
+    // before v13, type trees were always included
+    #[deku(cond = "version >= 13", default = "1")]
     pub enable_type_tree: u8,
     type_tree_count: i32,
-    #[deku(count = "*type_tree_count", ctx = "*enable_type_tree > 0")]
+    #[deku(count = "*type_tree_count", ctx = "*enable_type_tree > 0, version")]
     pub type_tree: Vec<SerializedType>,
+    #[deku(cond = "(7..14).contains(&version)", default = "0")]
+    big_id_enabled: i32,
     object_count: i32,
-    #[deku(count = "(4 - deku::byte_offset % 4) % 4")] _alignment: Vec<u8>,
-    #[deku(ctx = "version", count = "*object_count")]
+    #[deku(ctx = "version, *big_id_enabled != 0", count = "*object_count")]
     pub objects: Vec<ObjectInfo>,
+    // added in v11 (3.5)
+    #[deku(ctx = "version", cond = "version >= 11", default = "UnityArray::default()")]
     pub script_types: UnityArray<LocalSerializedObjectIdentifier>,
     pub externals: UnityArray<FileIdentifier>,
+    // added in v20 (2019.2)
+    #[deku(cond = "version >= 20", default = "0")]
     ref_types_count: i32,
-    #[deku(count = "*ref_types_count", ctx = "*enable_type_tree > 0")]
+    #[deku(count = "*ref_types_count", ctx = "*enable_type_tree > 0, version")]
     pub ref_types: Vec<SerializedTypeReference>,
     pub user_information: NullTerminatedAsciiString,
 }
 
+impl SerializedFileMetadata {
+    // Which entry of type_tree describes the object. From v16 objects store
+    // the index directly, but before that they store the type's ID (the
+    // class ID, or a negative script type ID for MonoBehaviours).
+    pub fn get_type_index(&self, obj: &ObjectInfo) -> Option<usize> {
+        if obj.class_id.is_some() {
+            self.type_tree.iter().position(|serialized_type| serialized_type.header.type_id == obj.serialized_type_index)
+        } else {
+            usize::try_from(obj.serialized_type_index).ok()
+                .filter(|&index| index < self.type_tree.len())
+        }
+    }
+}
+
 #[derive(DekuRead, Clone, Debug)]
-#[deku(ctx = "version: i32")]
+#[deku(ctx = "version: i32, big_id_enabled: bool")]
 pub struct ObjectInfo {
+    // from v14 each entry is aligned, which only matters before v17 since
+    // the entries weren't a multiple of 4 bytes long
+    #[deku(count = "if version >= 14 { (4 - deku::byte_offset % 4) % 4 } else { 0 }")] _alignment: Vec<u8>,
+    #[deku(reader = "read_path_id(deku::reader, version >= 14 || big_id_enabled)")]
     pub file_id: i64,
     #[deku(cond = "version <= 21")]
     pub small_file_byte_start: Option<u32>,
     #[deku(cond = "version >= 22")]
     pub large_file_byte_start: Option<i64>,
     pub byte_size: i32,
+    // before v16, this is the class ID rather than an index into the type tree
     pub serialized_type_index: i32,
+    #[deku(cond = "version < 16")]
+    pub class_id: Option<u16>,
+    #[deku(cond = "version < 11", default = "0")]
+    pub is_destroyed: u16,
+    #[deku(cond = "(11..17).contains(&version)", default = "-1")]
+    pub script_type_index: i16,
+    #[deku(cond = "version == 15 || version == 16", default = "0")]
+    pub is_stripped: u8,
 }
 
 impl ObjectInfo {
@@ -68,41 +105,86 @@ impl ObjectInfo {
 }
 
 #[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: i32")]
 pub struct LocalSerializedObjectIdentifier {
     pub local_serialized_file_index: i32,
+    // only does anything before v17, when the object table could end unaligned
+    #[deku(count = "if version >= 14 { (4 - deku::byte_offset % 4) % 4 } else { 0 }")] _alignment: Vec<u8>,
+    #[deku(reader = "read_path_id(deku::reader, version >= 14)")]
     pub local_identifier_in_file: i64,
 }
 
+// Path IDs were 32-bit before v14, unless the file opted into 64-bit ones
+fn read_path_id<R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>, wide: bool) -> Result<i64, DekuError> {
+    if wide {
+        i64::from_reader_with_ctx(reader, ())
+    } else {
+        Ok(i32::from_reader_with_ctx(reader, ())? as i64)
+    }
+}
+
 #[derive(DekuRead, Clone, Debug)]
-#[deku(ctx = "has_type_tree: bool")]
+#[deku(ctx = "has_type_tree: bool, version: i32")]
 pub struct SerializedTypeHeader {
+    // before v16, MonoBehaviours get one type per script, numbered down from -1
+    pub type_id: i32,
+    #[deku(skip, default = "class_id_from_raw(*type_id)")]
     pub raw_type_id: ClassID,
+    #[deku(cond = "version >= 16", default = "0")]
     pub is_stripped_type: u8,
+    #[deku(cond = "version >= 17", default = "-1")]
     pub script_type_index: i16,
-    #[deku(cond = "*raw_type_id == ClassID::MonoBehavior")]
+    #[deku(cond = "version >= 13 && if version >= 16 { *raw_type_id == ClassID::MonoBehavior } else { *type_id < 0 }")]
     pub script_id: Option<[u8; 16]>,
+    // added in v13 (5.0)
+    #[deku(cond = "version >= 13", default = "[0; 16]")]
     pub old_type_hash: [u8; 16],
-    #[deku(cond = "has_type_tree")]
+    #[deku(cond = "has_type_tree && (version >= 12 || version == 10)", ctx = "version")]
     pub old_type: Option<OldSerializedType>,
+    #[deku(cond = "has_type_tree && version < 12 && version != 10")]
+    pub legacy_type: Option<LegacyTypeTreeNode>,
+}
+
+impl SerializedTypeHeader {
+    // The type tree in whichever form the file stored it, if it has one
+    pub fn get_fields(&self) -> Option<Vec<TypeTreeField>> {
+        if let Some(old_type) = &self.old_type {
+            Some(old_type.get_fields())
+        } else {
+            self.legacy_type.as_ref().map(|legacy_type| legacy_type.get_fields())
+        }
+    }
+}
+
+// Unknown classes are kept as UnknownType rather than failing the whole file
+pub fn class_id_from_raw(raw: i32) -> ClassID {
+    if raw < 0 {
+        return ClassID::MonoBehavior;
+    }
+    ClassID::from_bytes((&raw.to_le_bytes(), 0))
+        .map(|(_, class_id)| class_id)
+        .unwrap_or(ClassID::UnknownType)
 }
 
 #[derive(DekuRead, Clone, Debug)]
-#[deku(ctx = "has_type_tree: bool")]
+#[deku(ctx = "has_type_tree: bool, version: i32")]
 pub struct SerializedType {
-    #[deku(ctx = "has_type_tree")]
+    #[deku(ctx = "has_type_tree, version")]
     pub header: SerializedTypeHeader,
-    #[deku(cond = "has_type_tree", default = "0")]
+    // type dependencies were added in v21 (2019.3)
+    #[deku(cond = "has_type_tree && version >= 21", default = "0")]
     type_dependencies_count: i32,
     #[deku(count = "*type_dependencies_count")]
     pub type_dependencies: Vec<i32>,
 }
 
 #[derive(DekuRead, Clone, Debug)]
-#[deku(ctx = "has_type_tree: bool")]
+#[deku(ctx = "has_type_tree: bool, version: i32")]
 pub struct SerializedTypeReference {
-    #[deku(ctx = "has_type_tree")]
+    #[deku(ctx = "has_type_tree, version")]
     pub header: SerializedTypeHeader,
-    #[deku(cond = "has_type_tree", default = "0")]
+    // type dependencies were added in v21 (2019.3)
+    #[deku(cond = "has_type_tree && version >= 21", default = "0")]
     type_dependencies_count: i32,
     #[deku(count = "*type_dependencies_count")]
     pub type_dependencies: Vec<i32>,
@@ -116,16 +198,18 @@ pub struct SerializedTypeReferenceDependency {
 }
 
 #[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "version: i32")]
 pub struct OldSerializedType {
     nodes_count: i32,
     string_buffer_size: i32,
-    #[deku(count = "*nodes_count")]
+    #[deku(count = "*nodes_count", ctx = "version")]
     nodes: Vec<TreeTypeNode>,
     #[deku(count = "*string_buffer_size")]
     string_buffer: Vec<u8>,
 }
 
 #[derive(DekuRead, Clone, Debug)]
+#[deku(ctx = "file_version: i32")]
 pub struct TreeTypeNode {
     version: u16,
     level: u8,
@@ -135,10 +219,92 @@ pub struct TreeTypeNode {
     byte_size: i32,
     index: i32,
     meta_flags: u32,
+    #[deku(cond = "file_version >= 19", default = "0")]
     ref_type_hash: u64,
 }
 
+// Before v12, type trees were stored as nested nodes, each with its own
+// strings, rather than as a flat list with a string buffer
 #[derive(DekuRead, Clone, Debug)]
+pub struct LegacyTypeTreeNode {
+    type_name: NullTerminatedAsciiString,
+    name: NullTerminatedAsciiString,
+    byte_size: i32,
+    index: i32,
+    is_array: i32,
+    node_version: i32,
+    meta_flags: u32,
+    children_count: i32,
+    #[deku(count = "*children_count")]
+    children: Vec<LegacyTypeTreeNode>,
+}
+
+impl LegacyTypeTreeNode {
+    // Flattens the tree into the same form as OldSerializedType's fields
+    pub fn get_fields(&self) -> Vec<TypeTreeField> {
+        let mut result = Vec::new();
+        self.append_fields(0, &mut result);
+        result
+    }
+
+    fn append_fields(&self, level: u8, result: &mut Vec<TypeTreeField>) {
+        result.push(TypeTreeField {
+            level,
+            type_name: (&self.type_name).into(),
+            name: (&self.name).into(),
+            byte_size: self.byte_size,
+            meta_flags: self.meta_flags,
+        });
+        for child in &self.children {
+            child.append_fields(level + 1, result);
+        }
+    }
+}
+
+// Type tree strings with the top bit set in their offset point into this
+// table built into the engine, rather than the type's own string buffer
+const COMMON_STRINGS: &str = "AABB\0AnimationClip\0AnimationCurve\0AnimationState\0Array\0Base\0BitField\0bitset\0bool\0char\0ColorRGBA\0Component\0data\0deque\0double\0dynamic_array\0FastPropertyName\0first\0float\0Font\0GameObject\0Generic Mono\0GradientNEW\0GUID\0GUIStyle\0int\0list\0long long\0map\0Matrix4x4f\0MdFour\0MonoBehaviour\0MonoScript\0m_ByteSize\0m_Curve\0m_EditorClassIdentifier\0m_EditorHideFlags\0m_Enabled\0m_ExtensionPtr\0m_GameObject\0m_Index\0m_IsArray\0m_IsStatic\0m_MetaFlag\0m_Name\0m_ObjectHideFlags\0m_PrefabInternal\0m_PrefabParentObject\0m_Script\0m_StaticEditorFlags\0m_Type\0m_Version\0Object\0pair\0PPtr<Component>\0PPtr<GameObject>\0PPtr<Material>\0PPtr<MonoBehaviour>\0PPtr<MonoScript>\0PPtr<Object>\0PPtr<Prefab>\0PPtr<Sprite>\0PPtr<TextAsset>\0PPtr<Texture>\0PPtr<Texture2D>\0PPtr<Transform>\0Prefab\0Quaternionf\0Rectf\0RectInt\0RectOffset\0second\0set\0short\0size\0SInt16\0SInt32\0SInt64\0SInt8\0staticvector\0string\0TextAsset\0TextMesh\0Texture\0Texture2D\0Transform\0TypelessData\0UInt16\0UInt32\0UInt64\0UInt8\0unsigned int\0unsigned long long\0unsigned short\0vector\0Vector2f\0Vector3f\0Vector4f\0m_ScriptingClassIdentifier\0Gradient\0Type*\0int2_storage\0int3_storage\0BoundsInt\0m_CorrespondingSourceObject\0m_PrefabInstance\0m_PrefabAsset\0FileSize\0Hash128\0";
+
+fn read_string_at(buffer: &[u8], offset: usize) -> Option<String> {
+    let rest = buffer.get(offset..)?;
+    let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+    Some(String::from_utf8_lossy(&rest[..end]).into_owned())
+}
+
+// A type tree node with its strings looked up
+#[derive(Tsify, Serialize, Clone, Debug)]
+pub struct TypeTreeField {
+    pub level: u8,
+    pub type_name: String,
+    pub name: String,
+    pub byte_size: i32,
+    pub meta_flags: u32,
+}
+
+impl OldSerializedType {
+    pub fn get_fields(&self) -> Vec<TypeTreeField> {
+        self.nodes.iter()
+            .map(|node| TypeTreeField {
+                level: node.level,
+                type_name: self.get_string(node.type_string_offset),
+                name: self.get_string(node.name_string_offset),
+                byte_size: node.byte_size,
+                meta_flags: node.meta_flags,
+            })
+            .collect()
+    }
+
+    fn get_string(&self, offset: u32) -> String {
+        let result = if offset & 0x80000000 != 0 {
+            read_string_at(COMMON_STRINGS.as_bytes(), (offset & 0x7fffffff) as usize)
+        } else {
+            read_string_at(&self.string_buffer, offset as usize)
+        };
+        result.unwrap_or_else(|| format!("<bad string offset {:#x}>", offset))
+    }
+}
+
+#[derive(DekuRead, Clone, Debug, PartialEq, Eq)]
 pub struct Guid {
     pub data0: u32,
     pub data1: u32,
@@ -146,6 +312,44 @@ pub struct Guid {
     pub data3: u32,
 }
 
+impl Guid {
+    // Formats the GUID the way Unity does in .meta files, i.e. each word's
+    // nibbles from least to most significant
+    pub fn to_hex_string(&self) -> String {
+        let mut result = String::with_capacity(32);
+        for word in [self.data0, self.data1, self.data2, self.data3] {
+            for i in 0..8 {
+                let nibble = (word >> (i * 4)) & 0xF;
+                result.push(std::char::from_digit(nibble, 16).unwrap());
+            }
+        }
+        result
+    }
+
+    // The inverse of to_hex_string
+    pub fn from_hex_string(hex: &str) -> Option<Guid> {
+        if hex.len() != 32 || !hex.is_ascii() {
+            return None;
+        }
+        let mut words = [0u32; 4];
+        for (i, c) in hex.chars().enumerate() {
+            let nibble = c.to_digit(16)?;
+            words[i / 8] |= nibble << ((i % 8) * 4);
+        }
+        Some(Guid { data0: words[0], data1: words[1], data2: words[2], data3: words[3] })
+    }
+
+    // Externals that aren't asset files (e.g. bundles) have an all-zero GUID
+    pub fn is_zero(&self) -> bool {
+        self.data0 == 0 && self.data1 == 0 && self.data2 == 0 && self.data3 == 0
+    }
+}
+
+// Resources/unity default resources (Library/ in older versions)
+pub const DEFAULT_RESOURCES_GUID: Guid = Guid { data0: 0, data1: 0, data2: 0xe, data3: 0 };
+// Resources/unity_builtin_extra
+pub const BUILTIN_EXTRA_GUID: Guid = Guid { data0: 0, data1: 0, data2: 0xf, data3: 0 };
+
 #[derive(DekuRead, Clone, Debug)]
 pub struct FileIdentifier {
     pub asset_path_ascii: NullTerminatedAsciiString,
@@ -1,34 +1,98 @@
 use std::collections::HashMap;
 use std::io::Cursor;
+use std::rc::Rc;
 
 use deku::reader::Reader;
 use deku::DekuContainerRead;
 use noclip_macros::{FromStructPerField, FromEnumPerVariant, from};
 use wasm_bindgen::prelude::*;
-use deku::DekuReader;
+use deku::{DekuError, DekuReader};
+use serde::Serialize;
+use tsify::Tsify;
 
-use crate::unity::types::common::CharArray;
-use super::common::{ColorRGBA, Matrix4x4, PPtr, Quaternion, Vec2, Vec3, Vec4, AABB, UnityVersion};
+use crate::unity::types::common::UnityArray;
+use super::common::{CharArray, ColorRGBA, Matrix4x4, PPtr, Quaternion, Rect, Vec2, Vec3, Vec4, AABB, UnityVersion, reset_parse_budget, set_object_version, trace_error_path};
 use super::binary;
+use super::serialized_file::Guid;
+use super::class_id::ClassID;
+use super::physx;
+use super::navmesh;
+use crate::cancellation::Cancellation;
+use crate::half::f16_to_f32;
+use crate::profiling;
+use crate::unity::progress::Progress;
+use crate::unity::asset_file::AssetReader;
+use crate::unity::humanoid::{build_human_rig, HumanBoneRig, HumanPose, MUSCLE_COUNT};
+use crate::unity::muscle_clip::{decode_curves, resolve_bindings, CurveBinding, BINDING_CUSTOM_TYPE_MUSCLE, HUMANOID_MUSCLE_ATTRIBUTE, HUMANOID_ROOT_ATTRIBUTE};
+use crate::unity::curve::{self, CurveWrapMode};
+use crate::unity::util::hex_dump_around;
+use crate::unity::obj::{write_obj, ObjExport, ObjGroup};
+use crate::unity::texture_container::{get_block_format, write_dds, write_ktx2, BlockFormat};
 
 macro_rules! define_create {
     ($t:ident, $u:expr) => {
+        define_create!($t, $u, None);
+    };
+    // fallback, if given, is tried when the full parse fails
+    ($t:ident, $u:expr, $fallback:expr) => {
         #[wasm_bindgen(js_class = $u)]
         impl $t {
             pub fn create(version: UnityVersion, data: &[u8]) -> Result<$t, String> {
+                profiling::record_object_parsed($u, data.len());
+                reset_parse_budget();
+                set_object_version(version);
                 let mut cursor = Cursor::new(data);
                 let mut reader = Reader::new(&mut cursor);
-                match binary::$t::from_reader_with_ctx(&mut reader, version) {
+                let result = profiling::time_stage("parse", || binary::$t::from_reader_with_ctx(&mut reader, version));
+                match result {
                     Ok(value) => Ok(value.into()),
-                    Err(err) => return Err(format!("Couldn't create {}: {:?}", $u, err)),
+                    Err(err) => {
+                        let offset = reader.bits_read / 8;
+                        let message = format!("Couldn't create {}: {}", $u, describe_parse_error::<binary::$t>(&err, version, data, offset));
+                        let fallback: Option<fn(UnityVersion, &[u8], String) -> Result<$t, String>> = $fallback;
+                        match fallback {
+                            Some(fallback) => fallback(version, data, message),
+                            None => Err(message),
+                        }
+                    },
+                }
+            }
+
+            // Like create, but reading the object's bytes in place from a
+            // reader that already holds its file, rather than having JS copy
+            // them out with read_object and back in again
+            pub fn create_from_reader(version: UnityVersion, reader: &AssetReader, path_id: i64) -> Result<$t, String> {
+                let data = reader.get_object_data(path_id)
+                    .map_err(|err| format!("Couldn't create {}: {}", $u, err))?;
+                $t::create(version, data)
+            }
+
+            // Parses several objects in one call, where ranges holds a
+            // (start, size) pair into data per object, calling
+            // on_progress(done, total) after each one. If signal is aborted,
+            // parsing stops at the next object.
+            pub fn create_many(version: UnityVersion, data: &[u8], ranges: &[u32], on_progress: Option<js_sys::Function>, signal: Option<js_sys::Object>) -> Result<Vec<$t>, String> {
+                let count = ranges.len() / 2;
+                let mut progress = Progress::new(on_progress, count);
+                let cancellation = Cancellation::new(signal);
+                let mut result = Vec::with_capacity(count);
+                for range in ranges.chunks_exact(2) {
+                    cancellation.check(concat!("Creating ", $u))?;
+                    let start = range[0] as usize;
+                    let end = start + range[1] as usize;
+                    let object_data = data.get(start..end)
+                        .ok_or(format!("Couldn't create {}: range {}..{} is out of bounds", $u, start, end))?;
+                    result.push($t::create(version, object_data)?);
+                    progress.step();
                 }
+                Ok(result)
             }
         }
     };
 }
 
 #[wasm_bindgen(js_name = "UnityPPtr")]
-#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone, Serialize)]
 pub struct WasmFriendlyPPtr {
     pub file_index: u32,
     pub path_id: i64,
@@ -73,11 +137,59 @@ pub struct Transform {
     pub parent: WasmFriendlyPPtr,
 }
 
+#[wasm_bindgen(js_class = "UnityTransform")]
+impl Transform {
+    pub fn get_info(&self) -> TransformInfo {
+        TransformInfo {
+            game_object: self.game_object.into(),
+            local_position: [self.local_position.x, self.local_position.y, self.local_position.z],
+            local_rotation: [self.local_rotation.x, self.local_rotation.y, self.local_rotation.z, self.local_rotation.w],
+            local_scale: [self.local_scale.x, self.local_scale.y, self.local_scale.z],
+            parent: self.parent.into(),
+            children: self.children.iter().map(|&child| child.into()).collect(),
+        }
+    }
+}
+
+// The read-only results below are plain data, which tsify hands to JS as
+// typed objects in one go rather than through per-field getters
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct PPtrInfo {
+    pub file_index: u32,
+    #[tsify(type = "bigint")]
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    pub path_id: JsValue,
+}
+
+impl From<WasmFriendlyPPtr> for PPtrInfo {
+    fn from(value: WasmFriendlyPPtr) -> Self {
+        Self {
+            file_index: value.file_index,
+            path_id: JsValue::from(value.path_id),
+        }
+    }
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct TransformInfo {
+    pub game_object: PPtrInfo,
+    pub local_position: [f32; 3],
+    pub local_rotation: [f32; 4],
+    pub local_scale: [f32; 3],
+    pub parent: PPtrInfo,
+    pub children: Vec<PPtrInfo>,
+}
+
 #[wasm_bindgen(js_name = "UnityMaterial", getter_with_clone)]
 #[derive(Debug, Clone)]
 pub struct Material {
     pub name: String,
     pub shader: WasmFriendlyPPtr,
+    pub parent: Option<WasmFriendlyPPtr>,
+    pub modified_serialized_properties: Option<i32>,
+    pub locked_properties: Option<String>,
     pub shader_keywords: Option<String>,
     pub valid_keywords: Option<Vec<String>>,
     pub invalid_keywords: Option<Vec<String>>,
@@ -92,14 +204,42 @@ pub struct Material {
     colors: HashMap<String, ColorRGBA>,
 }
 
+// Colors are stored as RGBA, and tex envs' scales and offsets as
+// (scale x, scale y, offset x, offset y)
+#[wasm_bindgen(js_name = "UnityPropertySheetArrays", getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct PropertySheetArrays {
+    pub float_names: String,
+    pub float_values: Vec<f32>,
+    pub color_names: String,
+    pub color_values: Vec<f32>,
+    pub tex_env_names: String,
+    pub tex_env_scale_offsets: Vec<f32>,
+    pub tex_env_file_indices: Vec<u32>,
+    pub tex_env_path_ids: Vec<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PropertySheetJs<'a> {
+    floats: &'a HashMap<String, f32>,
+    colors: &'a HashMap<String, ColorRGBA>,
+    tex_envs: &'a HashMap<String, TexEnv>,
+}
+
 impl From<binary::Material> for Material {
     fn from(value: binary::Material) -> Self {
         Self {
             name: value.name.into(),
             shader: value.shader.into(),
-            shader_keywords: match value.shader_keywords {
-                Some(v) => Some(v.into()),
-                None => None,
+            parent: value.parent.map(|v| v.into()),
+            modified_serialized_properties: value.modified_serialized_properties,
+            locked_properties: value.locked_properties.map(|v| v.into()),
+            shader_keywords: match (value.shader_keywords, value.legacy_shader_keywords) {
+                (Some(v), _) => Some(v.into()),
+                // keep pre-5.0 keyword lists in the later space-separated form
+                (None, Some(v)) => Some(Vec::<String>::from(v).join(" ")),
+                (None, None) => None,
             },
             valid_keywords: match value.valid_keywords {
                 Some(v) => Some(v.into()),
@@ -147,578 +287,4285 @@ impl Material {
     pub fn get_color_by_key(&self, key: &str) -> Option<ColorRGBA> {
         self.colors.get(key).cloned()
     }
-}
-
-// Note: the actual Shader type is insanely complicated, so we don't want to
-// actually implement a binary reader for it unless we're trying to actually
-// recreate Unity's shader compilation pipeline. Instead, let's just read in the
-// name to be identifiable in noclip.
-#[wasm_bindgen(js_name = "UnityShader", getter_with_clone)]
-#[derive(Debug, Clone)]
-pub struct Shader {
-    pub name: String,
-}
 
-#[wasm_bindgen(js_class = "UnityShader")]
-impl Shader {
-    pub fn create(_version: UnityVersion, data: &[u8]) -> Result<Self, String> {
-        let (_, name) = CharArray::from_bytes((data, 0))
-            .map_err(|err| format!("{:?}", err))?;
-        Ok(Shader {
-            name: name.into(),
-        })
+    // The whole property sheet as a handful of flat arrays, with names
+    // newline-separated and sorted
+    pub fn get_property_arrays(&self) -> PropertySheetArrays {
+        let mut float_names: Vec<&String> = self.floats.keys().collect();
+        float_names.sort();
+        let mut color_names: Vec<&String> = self.colors.keys().collect();
+        color_names.sort();
+        let mut tex_env_names: Vec<&String> = self.tex_envs.keys().collect();
+        tex_env_names.sort();
+        let join = |names: &[&String]| names.iter().map(|n| n.as_str()).collect::<Vec<&str>>().join("\n");
+        PropertySheetArrays {
+            float_names: join(&float_names),
+            float_values: float_names.iter().map(|n| self.floats[*n]).collect(),
+            color_names: join(&color_names),
+            color_values: color_names.iter()
+                .flat_map(|n| {
+                    let c = self.colors[*n];
+                    vec![c.r, c.g, c.b, c.a]
+                })
+                .collect(),
+            tex_env_names: join(&tex_env_names),
+            tex_env_scale_offsets: tex_env_names.iter()
+                .flat_map(|n| {
+                    let t = &self.tex_envs[*n];
+                    vec![t.scale.x, t.scale.y, t.offset.x, t.offset.y]
+                })
+                .collect(),
+            tex_env_file_indices: tex_env_names.iter().map(|n| self.tex_envs[*n].texture.file_index).collect(),
+            tex_env_path_ids: tex_env_names.iter().map(|n| self.tex_envs[*n].texture.path_id).collect(),
+        }
     }
-}
 
-#[wasm_bindgen(js_name = "UnityTexEnv")]
-#[derive(FromStructPerField, Debug, Clone)]
-#[from(binary::TexEnv)]
-pub struct TexEnv {
-    pub texture: WasmFriendlyPPtr,
-    pub scale: Vec2,
-    pub offset: Vec2,
-}
+    // Returns the whole property sheet as a plain
+    // { floats, colors, texEnvs } object in one go
+    pub fn to_js(&self) -> Result<JsValue, String> {
+        let sheet = PropertySheetJs {
+            floats: &self.floats,
+            colors: &self.colors,
+            tex_envs: &self.tex_envs,
+        };
+        let serializer = serde_wasm_bindgen::Serializer::new()
+            .serialize_maps_as_objects(true)
+            .serialize_large_number_types_as_bigints(true);
+        sheet.serialize(&serializer)
+            .map_err(|err| format!("Couldn't convert UnityMaterial properties: {:?}", err))
+    }
 
-#[wasm_bindgen(js_name = "UnityMesh", getter_with_clone)]
-#[derive(Debug, Clone, FromStructPerField)]
-#[from(binary::Mesh)]
-pub struct Mesh {
-    pub name: String,
-    pub submeshes: Vec<SubMesh>,
-    // pub shapes: Vec<BlendShapeData>,
-    pub bind_pose: Vec<Matrix4x4>,
-    pub bone_name_hashes: Vec<u32>,
-    pub root_bone_name_hash: u32,
-    pub bones_aabb: Vec<AABB>,
-    pub variable_bone_count_weights: Vec<u32>,
-    pub mesh_compression: MeshCompression,
-    pub is_readable: u8,
-    pub keep_vertices: u8,
-    pub keep_indices: u8,
-    pub index_format: IndexFormat,
-    pub index_buffer: Vec<u8>,
-    pub vertex_data: VertexData,
-    pub compressed_mesh: CompressedMesh,
-    pub local_aabb: AABB,
-    pub mesh_usage_flags: i32,
-    pub baked_convex_collision_mesh: Vec<u8>,
-    pub baked_triangle_collision_mesh: Vec<u8>,
-    pub streaming_info: StreamingInfo,
-}
+    // Enabled shader keywords, regardless of which version of the format they
+    // were stored in
+    pub fn get_keywords(&self) -> Vec<String> {
+        let mut result: Vec<String> = Vec::new();
+        if let Some(keywords) = &self.shader_keywords {
+            result.extend(keywords.split_whitespace().map(|k| k.to_string()));
+        }
+        if let Some(keywords) = &self.valid_keywords {
+            result.extend(keywords.iter().cloned());
+        }
+        result
+    }
 
-#[wasm_bindgen(js_class = "UnityMesh")]
-impl Mesh {
-    pub fn set_vertex_data(&mut self, data: Vec<u8>) {
-        self.vertex_data.data = data;
+    pub fn has_keyword(&self, keyword: &str) -> bool {
+        self.get_keywords().iter().any(|k| k == keyword)
     }
 
-    pub fn unpack_vertices(&self) -> Option<Vec<f32>> {
-        match self.mesh_compression {
-            MeshCompression::Off => None,
-            _ => Some(self.compressed_mesh.vertices.clone())
+    // The material's own queue if it overrides it, otherwise the shader's
+    // (from the Queue tag of the subshader it's drawn with)
+    pub fn get_render_queue(&self, shader_queue: Option<i32>) -> i32 {
+        // -1 means "from shader"
+        match self.custom_render_queue as i32 {
+            -1 => shader_queue.unwrap_or(RENDER_QUEUE_GEOMETRY),
+            queue => queue,
         }
     }
 
-    pub fn unpack_normals(&self) -> Option<Vec<f32>> {
-        match self.mesh_compression {
-            MeshCompression::Off => None,
-            _ => Some(self.compressed_mesh.unpack_normals())
-        }
+    pub fn is_variant(&self) -> bool {
+        matches!(self.parent, Some(parent) if parent.path_id != 0)
     }
 
-    pub fn unpack_indices(&self) -> Option<Vec<i32>> {
-        match self.mesh_compression {
-            MeshCompression::Off => None,
-            _ => Some(self.compressed_mesh.triangles.clone())
+    // Material variants only store the properties they override, so this
+    // fills in the rest from the parent, which should already have been
+    // flattened if it's a variant itself
+    pub fn flatten_variant(&self, parent: &Material) -> Material {
+        let mut result = parent.clone();
+        result.name = self.name.clone();
+        if self.shader.path_id != 0 {
+            result.shader = self.shader;
+        }
+        result.parent = None;
+        result.modified_serialized_properties = None;
+        result.locked_properties = self.locked_properties.clone();
+        if !self.get_keywords().is_empty() {
+            result.shader_keywords = self.shader_keywords.clone();
+            result.valid_keywords = self.valid_keywords.clone();
+            result.invalid_keywords = self.invalid_keywords.clone();
+        }
+        result.lightmap_flags = self.lightmap_flags;
+        result.enable_instancing_variants = self.enable_instancing_variants;
+        result.double_sided_gi = self.double_sided_gi;
+        result.custom_render_queue = self.custom_render_queue;
+        if !self.disabled_shader_passes.is_empty() {
+            result.disabled_shader_passes = self.disabled_shader_passes.clone();
         }
+        result.string_tag_map.extend(self.string_tag_map.iter().map(|(k, v)| (k.clone(), v.clone())));
+        result.tex_envs.extend(self.tex_envs.iter().map(|(k, v)| (k.clone(), v.clone())));
+        result.floats.extend(self.floats.iter().map(|(k, v)| (k.clone(), *v)));
+        result.colors.extend(self.colors.iter().map(|(k, v)| (k.clone(), *v)));
+        result
     }
 
-    pub fn get_vertex_data(&self) -> Vec<u8> {
-        self.vertex_data.data.clone()
+    // Maps this material's properties, as used by the given built-in shader,
+    // to a common set of PBR parameters
+    pub fn get_pbr_params(&self, shader_kind: BuiltinShaderKind) -> PBRMaterialParams {
+        use BuiltinShaderKind::*;
+        let white = ColorRGBA { r: 1.0, g: 1.0, b: 1.0, a: 1.0 };
+        let black = ColorRGBA { r: 0.0, g: 0.0, b: 0.0, a: 1.0 };
+        let is_scriptable_pipeline = matches!(shader_kind, UniversalLit | UniversalSimpleLit | UniversalUnlit | UniversalBakedLit);
+        // LWRP before 5.x (so everything on 2018.x) still used the built-in
+        // pipeline's property names
+        let (base_color, base_color_texture, smoothness) = if is_scriptable_pipeline {
+            (
+                self.colors.get("_BaseColor").or_else(|| self.colors.get("_Color")).copied(),
+                self.get_assigned_tex_env("_BaseMap").or_else(|| self.get_assigned_tex_env("_MainTex")),
+                self.floats.get("_Smoothness").or_else(|| self.floats.get("_Glossiness")).copied(),
+            )
+        } else {
+            (
+                self.colors.get("_Color").copied(),
+                self.get_assigned_tex_env("_MainTex"),
+                self.floats.get("_Glossiness").copied(),
+            )
+        };
+        let render_mode = match shader_kind {
+            Standard | StandardSpecular => match self.get_float_or("_Mode", 0.0) as i32 {
+                1 => PBRRenderMode::Cutout,
+                2 => PBRRenderMode::Fade,
+                3 => PBRRenderMode::Transparent,
+                _ => PBRRenderMode::Opaque,
+            },
+            UniversalLit | UniversalSimpleLit | UniversalUnlit | UniversalBakedLit => if self.get_float_or("_Surface", 0.0) != 0.0 {
+                if self.get_float_or("_Blend", 0.0) as i32 == 1 {
+                    PBRRenderMode::Transparent
+                } else {
+                    PBRRenderMode::Fade
+                }
+            } else if self.get_float_or("_AlphaClip", 0.0) != 0.0 {
+                PBRRenderMode::Cutout
+            } else {
+                PBRRenderMode::Opaque
+            },
+            LegacyDiffuse => PBRRenderMode::Opaque,
+        };
+        let is_pbr = !matches!(shader_kind, LegacyDiffuse | UniversalUnlit | UniversalBakedLit);
+        // URP/LWRP Lit picks its workflow with _WorkflowMode: 0 is specular, 1 metallic
+        let is_specular = match shader_kind {
+            StandardSpecular | UniversalSimpleLit => true,
+            UniversalLit => self.get_float_or("_WorkflowMode", 1.0) == 0.0,
+            _ => false,
+        };
+        let specular_color = if is_specular {
+            let default = if shader_kind == UniversalSimpleLit { 0.5 } else { 0.2 };
+            Some(self.colors.get("_SpecColor").copied().unwrap_or(ColorRGBA { r: default, g: default, b: default, a: 1.0 }))
+        } else {
+            None
+        };
+        let metallic_gloss_texture = if !is_pbr {
+            None
+        } else if is_specular {
+            self.get_assigned_tex_env("_SpecGlossMap")
+        } else {
+            self.get_assigned_tex_env("_MetallicGlossMap")
+        };
+        // the Standard shader only applies emission with _EMISSION enabled,
+        // and so do LWRP and URP
+        let emission_enabled = is_pbr && self.has_keyword("_EMISSION");
+        let smoothness = match shader_kind {
+            // LWRP's Simple Lit called it shininess
+            UniversalSimpleLit => smoothness.or_else(|| self.floats.get("_Shininess").copied()),
+            _ => smoothness,
+        };
+        PBRMaterialParams {
+            shader_kind,
+            render_mode,
+            alpha_cutoff: self.get_float_or("_Cutoff", 0.5),
+            base_color: base_color.unwrap_or(white),
+            base_color_texture,
+            metallic: match shader_kind {
+                Standard | UniversalLit if !is_specular => self.get_float_or("_Metallic", 0.0),
+                _ => 0.0,
+            },
+            specular_color,
+            metallic_gloss_texture,
+            smoothness: if is_pbr { smoothness.unwrap_or(0.5) } else { 0.0 },
+            smoothness_from_albedo_alpha: is_pbr && self.get_float_or("_SmoothnessTextureChannel", 0.0) as i32 == 1,
+            normal_texture: if is_pbr || shader_kind == UniversalBakedLit { self.get_assigned_tex_env("_BumpMap") } else { None },
+            normal_scale: self.get_float_or("_BumpScale", 1.0),
+            occlusion_texture: if is_pbr && shader_kind != UniversalSimpleLit { self.get_assigned_tex_env("_OcclusionMap") } else { None },
+            occlusion_strength: self.get_float_or("_OcclusionStrength", 1.0),
+            emission_color: if emission_enabled { self.colors.get("_EmissionColor").copied().unwrap_or(black) } else { black },
+            emission_texture: if emission_enabled { self.get_assigned_tex_env("_EmissionMap") } else { None },
+        }
     }
 
-    pub fn get_index_data(&self) -> Vec<u8> {
-        self.index_buffer.clone()
-    }
+    // Returns the SpeedTree-specific settings of this material, or None if it
+    // doesn't look like it's using one of the SpeedTree shaders
+    pub fn get_speed_tree_info(&self) -> Option<SpeedTreeInfo> {
+        let keywords = self.get_keywords();
+        let has = |keyword: &str| keywords.iter().any(|k| k == keyword);
+        let geometry_type = if has("GEOM_TYPE_BRANCH") {
+            SpeedTreeGeometryType::Branch
+        } else if has("GEOM_TYPE_BRANCH_DETAIL") {
+            SpeedTreeGeometryType::BranchDetail
+        } else if has("GEOM_TYPE_FROND") {
+            SpeedTreeGeometryType::Frond
+        } else if has("GEOM_TYPE_LEAF") {
+            SpeedTreeGeometryType::Leaf
+        } else if has("GEOM_TYPE_MESH") {
+            SpeedTreeGeometryType::Mesh
+        } else if has("EFFECT_BILLBOARD") {
+            SpeedTreeGeometryType::Billboard
+        } else if self.floats.contains_key("_WindQuality") || keywords.iter().any(|k| k.starts_with("_WINDQUALITY_")) {
+            // SpeedTree 8 doesn't tag geometry types, so fall back to the
+            // wind settings every SpeedTree shader has
+            SpeedTreeGeometryType::Unknown
+        } else {
+            return None;
+        };
 
-    pub fn get_channel_count(&self) -> usize {
-        self.vertex_data.channels.len()
+        // SpeedTree 7 calls this _HueVariation, SpeedTree 8 _HueVariationColor;
+        // either way, the alpha channel is the strength
+        let hue_variation = self.colors.get("_HueVariationColor")
+            .or_else(|| self.colors.get("_HueVariation"))
+            .cloned()
+            .unwrap_or(ColorRGBA { r: 1.0, g: 0.5, b: 0.0, a: 0.1 });
+        let wind_quality = match self.floats.get("_WindQuality") {
+            Some(quality) => *quality as i32,
+            None => ["NONE", "FASTEST", "FAST", "BETTER", "BEST", "PALM"].iter()
+                .position(|quality| has(&format!("_WINDQUALITY_{}", quality)))
+                .unwrap_or(0) as i32,
+        };
+        Some(SpeedTreeInfo {
+            geometry_type,
+            hue_variation_enabled: has("EFFECT_HUE_VARIATION"),
+            hue_variation,
+            wind_enabled: wind_quality > 0 || has("ENABLE_WIND"),
+            wind_quality,
+        })
     }
+}
 
-    pub fn get_channels(&self) -> Vec<ChannelInfo> {
-        self.vertex_data.channels.clone()
+impl Material {
+    fn get_float_or(&self, key: &str, default: f32) -> f32 {
+        self.floats.get(key).copied().unwrap_or(default)
     }
 
-    pub fn get_streams(&self) -> Vec<VertexStreamInfo> {
-        VertexStreamInfo::from_channels(&self.vertex_data.channels, self.vertex_data.vertex_count as usize)
+    fn get_assigned_tex_env(&self, key: &str) -> Option<TexEnv> {
+        self.tex_envs.get(key)
+            .filter(|tex_env| tex_env.texture.path_id != 0)
+            .cloned()
     }
 }
 
-#[wasm_bindgen(js_name = "UnityIndexFormat")]
-#[derive(FromEnumPerVariant, Debug, Clone, Copy)]
-#[from(binary::IndexFormat)]
-pub enum IndexFormat {
-    UInt16,
-    UInt32,
+// SpeedTree meshes pack their wind data into the texcoord channels (see
+// SpeedTreeVertex.cginc):
+//
+//      BRANCHES                        FRONDS              LEAVES
+// 0    diffuse uv, branch wind xy      "                   "
+// 1    lod xyz, 0                      lod xyz, 0          anchor xyz, lod scalar
+// 2    detail/seam uv, seam amount, 0  frond wind xyz, 0   leaf wind xyz, leaf group
+#[wasm_bindgen(js_name = "UnitySpeedTreeInfo")]
+#[derive(Debug, Clone)]
+pub struct SpeedTreeInfo {
+    pub geometry_type: SpeedTreeGeometryType,
+    pub hue_variation_enabled: bool,
+    pub hue_variation: ColorRGBA,
+    pub wind_enabled: bool,
+    pub wind_quality: i32,
 }
 
-#[wasm_bindgen(js_name = "UnityMeshCompression")]
-#[derive(FromEnumPerVariant, Debug, Clone, Copy)]
-#[from(binary::MeshCompression)]
-pub enum MeshCompression {
-    Off = 0,
-    Low = 1,
-    Medium = 2,
-    High = 3,
+#[wasm_bindgen(js_name = "UnitySpeedTreeGeometryType")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpeedTreeGeometryType {
+    Unknown,
+    Branch,
+    BranchDetail,
+    Frond,
+    Leaf,
+    Mesh,
+    Billboard,
 }
 
-#[wasm_bindgen(js_name = "UnityCompressedMesh", getter_with_clone)]
-#[derive(Clone, Debug, FromStructPerField)]
-#[from(binary::CompressedMesh)]
-pub struct CompressedMesh {
-    pub vertices: Vec<f32>,
-    pub uv: Vec<f32>,
-    pub normals: Vec<f32>,
-    pub tangents: Vec<f32>,
-    pub weights: Vec<i32>,
-    pub normal_signs: Vec<i32>,
-    pub tangent_signs: Vec<i32>,
-    pub float_colors: Vec<f32>,
-    pub bone_indices: Vec<i32>,
-    pub triangles: Vec<i32>,
-    pub uv_info: u32,
+// Unity's own shaders, which cover most materials in most games
+#[wasm_bindgen(js_name = "UnityBuiltinShaderKind")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BuiltinShaderKind {
+    Standard,
+    StandardSpecular,
+    LegacyDiffuse,
+    UniversalLit,
+    UniversalSimpleLit,
+    UniversalUnlit,
+    // unlit apart from lightmaps and light probes, and normal mapped
+    UniversalBakedLit,
 }
 
-impl CompressedMesh {
-    pub fn unpack_normals(&self) -> Vec<f32> {
-        let n = self.normals.len() / 2;
-        let mut result = vec![0.0; 3 * n];
-        for i in 0..n {
-            let x = self.normals[2*i];
-            let y = self.normals[2*i + 1];
-            result[3*i + 0] = x;
-            result[3*i + 1] = y;
-            let zsqr = 1.0 - x*x - y*y;
-            if zsqr >= 0.0 {
-                result[3*i + 2] = zsqr.sqrt();
-            } else {
-                result[3*i + 2] = 0.0;
-            }
-
-            if self.normal_signs.len() > 0 && self.normal_signs[i] == 0 {
-                result[3*i + 2] *= -1.0;
-            }
+impl BuiltinShaderKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Standard" => Some(BuiltinShaderKind::Standard),
+            "Standard (Specular setup)" => Some(BuiltinShaderKind::StandardSpecular),
+            "Legacy Shaders/Diffuse" | "Diffuse" => Some(BuiltinShaderKind::LegacyDiffuse),
+            // LWRP was renamed to URP in 2019.3, and its shaders with it
+            "Universal Render Pipeline/Lit"
+                | "Lightweight Render Pipeline/Lit"
+                | "LightweightPipeline/Standard (Physically Based)" => Some(BuiltinShaderKind::UniversalLit),
+            "Universal Render Pipeline/Simple Lit"
+                | "Lightweight Render Pipeline/Simple Lit"
+                | "LightweightPipeline/Standard (Simple Lighting)" => Some(BuiltinShaderKind::UniversalSimpleLit),
+            "Universal Render Pipeline/Unlit"
+                | "Lightweight Render Pipeline/Unlit"
+                | "LightweightPipeline/Standard Unlit" => Some(BuiltinShaderKind::UniversalUnlit),
+            "Universal Render Pipeline/Baked Lit"
+                | "Lightweight Render Pipeline/Baked Lit" => Some(BuiltinShaderKind::UniversalBakedLit),
+            _ => None,
         }
-        result
     }
 }
 
-#[wasm_bindgen(js_name = "UnityVertexData", getter_with_clone)]
-#[derive(Clone, Debug, FromStructPerField)]
-#[from(binary::VertexData)]
-pub struct VertexData {
-    pub vertex_count: u32,
-    pub channels: Vec<ChannelInfo>,
-    pub data: Vec<u8>,
+// Games don't always ship the built-in shaders themselves, in which case
+// materials point at the placeholder objects in Resources/unity_builtin_extra
+// (guid 0000000000000000f000000000000000)
+#[wasm_bindgen(js_name = "unity_get_builtin_extra_shader_kind")]
+pub fn get_builtin_extra_shader_kind(path_id: i64) -> Option<BuiltinShaderKind> {
+    match path_id {
+        7 => Some(BuiltinShaderKind::LegacyDiffuse),
+        45 => Some(BuiltinShaderKind::StandardSpecular),
+        46 => Some(BuiltinShaderKind::Standard),
+        _ => None,
+    }
 }
 
-#[wasm_bindgen]
+#[wasm_bindgen(js_name = "UnityPBRRenderMode")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PBRRenderMode {
+    Opaque,
+    Cutout,
+    // alpha-blended, including the specular highlights
+    Fade,
+    // premultiplied alpha
+    Transparent,
+}
+
+// A material's properties mapped to metallic/roughness-style parameters,
+// independent of which built-in shader it uses. Unset textures are None.
+#[wasm_bindgen(js_name = "UnityPBRMaterialParams", getter_with_clone)]
 #[derive(Debug, Clone)]
-pub struct VertexStreamInfo {
-    pub channel_mask: u32,
-    pub offset: u32,
-    pub stride: u32,
-    pub divider_op: u8,
-    pub frequency: u32,
+pub struct PBRMaterialParams {
+    pub shader_kind: BuiltinShaderKind,
+    pub render_mode: PBRRenderMode,
+    pub alpha_cutoff: f32,
+    pub base_color: ColorRGBA,
+    pub base_color_texture: Option<TexEnv>,
+    pub metallic: f32,
+    // only for the specular setup; metallic is 0 there
+    pub specular_color: Option<ColorRGBA>,
+    // metallic (or specular) in RGB, smoothness in A
+    pub metallic_gloss_texture: Option<TexEnv>,
+    pub smoothness: f32,
+    pub smoothness_from_albedo_alpha: bool,
+    pub normal_texture: Option<TexEnv>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<TexEnv>,
+    pub occlusion_strength: f32,
+    pub emission_color: ColorRGBA,
+    pub emission_texture: Option<TexEnv>,
 }
 
-impl VertexStreamInfo {
-    pub fn from_channels(channels: &[ChannelInfo], vertex_count: usize) -> Vec<VertexStreamInfo> {
-        let mut n_streams = 0;
-        for c in channels {
-            if c.stream > n_streams {
-                n_streams = c.stream;
-            }
+#[wasm_bindgen(js_name = "UnityShader", getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct Shader {
+    pub name: String,
+    // None if only the name was read, either through create_name_only or
+    // because the full parse failed (see parse_error). Shared rather than
+    // exposed directly, so passing shaders around doesn't copy it; JS reads
+    // it through the accessors below.
+    parsed_form: Option<Rc<SerializedShader>>,
+    pub parse_error: Option<String>,
+    pub platforms: Vec<u32>,
+    offsets: Vec<Vec<u32>>,
+    compressed_lengths: Vec<Vec<u32>>,
+    decompressed_lengths: Vec<Vec<u32>>,
+    compressed_blob: Rc<Vec<u8>>,
+    // whether the sub-program table records which segment each entry is in
+    segmented: bool,
+    pub dependencies: Vec<WasmFriendlyPPtr>,
+}
+
+impl From<binary::Shader> for Shader {
+    fn from(value: binary::Shader) -> Self {
+        let parsed_form: SerializedShader = value.parsed_form.into();
+        // built shaders usually leave the object name empty
+        let mut name: String = value.name.into();
+        if name.is_empty() {
+            name = parsed_form.name.clone();
         }
-        n_streams += 1;
-        let mut result = Vec::with_capacity(n_streams as usize);
-        let mut offset = 0;
-        for s in 0..n_streams {
-            let mut channel_mask = 0;
-            let mut stride = 0;
-            for chn in 0..channels.len() {
-                let channel = &channels[chn];
-                if channel.stream == s {
-                    if channel.dimension > 0 {
-                        channel_mask |= 1 << chn;
-                        stride += channel.dimension as usize * channel.get_format_size();
-                    }
-                }
-            }
+        let segmented = value.offsets.is_segmented();
+        Self {
+            name,
+            parsed_form: Some(Rc::new(parsed_form)),
+            parse_error: None,
+            platforms: value.platforms.into(),
+            offsets: value.offsets.into(),
+            compressed_lengths: value.compressed_lengths.into(),
+            decompressed_lengths: value.decompressed_lengths.into(),
+            compressed_blob: Rc::new(value.compressed_blob.into()),
+            segmented,
+            dependencies: value.dependencies.into(),
+        }
+    }
+}
 
-            result.push(VertexStreamInfo {
-                channel_mask,
-                offset,
-                stride: stride as u32,
-                divider_op: 0,
-                frequency: 0,
-            });
-            offset += (vertex_count * stride) as u32;
-            offset = (offset + 0x0F) & !0x0F;
+impl Shader {
+    fn name_only(name: String, parse_error: Option<String>) -> Self {
+        Shader {
+            name,
+            parsed_form: None,
+            parse_error,
+            platforms: Vec::new(),
+            offsets: Vec::new(),
+            compressed_lengths: Vec::new(),
+            decompressed_lengths: Vec::new(),
+            compressed_blob: Rc::new(Vec::new()),
+            segmented: false,
+            dependencies: Vec::new(),
         }
-        return result;
+    }
+
+    fn create_fallback(version: UnityVersion, data: &[u8], parse_error: String) -> Result<Self, String> {
+        let mut shader = Shader::create_name_only(version, data)
+            .map_err(|_| parse_error.clone())?;
+        shader.parse_error = Some(parse_error);
+        Ok(shader)
     }
 }
 
-#[wasm_bindgen(js_name = "UnityChannelInfo")]
-#[derive(Clone, Debug, FromStructPerField)]
-#[from(binary::ChannelInfo)]
-pub struct ChannelInfo {
-    pub stream: u8,
-    pub offset: u8,
-    pub format: VertexFormat,
-    pub dimension: u8,
+#[wasm_bindgen(js_name = "UnityLegacyShader", getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct LegacyShader {
+    pub name: String,
+    pub path_name: String,
+    script: Vec<u8>,
+    decompressed_size: u32,
+    sub_program_blob: Vec<u8>,
+    pub dependencies: Vec<WasmFriendlyPPtr>,
 }
 
-impl ChannelInfo {
-    pub fn get_format_size(&self) -> usize {
-        match self.format {
-            VertexFormat::Float |
-            VertexFormat::UInt32 |
-            VertexFormat::SInt32 => 4,
+impl From<binary::LegacyShader> for LegacyShader {
+    fn from(value: binary::LegacyShader) -> Self {
+        Self {
+            name: value.name.into(),
+            path_name: value.path_name.into(),
+            script: value.script.into(),
+            decompressed_size: value.decompressed_size,
+            sub_program_blob: value.sub_program_blob.into(),
+            dependencies: value.dependencies.into(),
+        }
+    }
+}
 
-            VertexFormat::Float16 |
-            VertexFormat::UNorm16 |
-            VertexFormat::SNorm16 |
+#[wasm_bindgen(js_class = "UnityLegacyShader")]
+impl LegacyShader {
+    // The ShaderLab source, with each program's compiled variants inlined
+    pub fn get_script(&self) -> String {
+        String::from_utf8_lossy(&self.script).into_owned()
+    }
+
+    pub fn get_sub_program_blob(&self) -> Result<Vec<u8>, String> {
+        if self.sub_program_blob.is_empty() {
+            return Ok(Vec::new());
+        }
+        profiling::time_decompression(|| lz4_flex::decompress(&self.sub_program_blob, self.decompressed_size as usize))
+            .map_err(|err| format!("failed to decompress sub-program blob: {:?}", err))
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityShader")]
+impl Shader {
+    // Only reads the object name, for when that's all that's needed. Built
+    // shaders usually leave it empty, in which case the full parse is needed
+    // to get the name from the SerializedShader.
+    pub fn create_name_only(_version: UnityVersion, data: &[u8]) -> Result<Shader, String> {
+        let (_, name) = CharArray::from_bytes((data, 0))
+            .map_err(|err| format!("{:?}", err))?;
+        Ok(Shader::name_only(name.into(), None))
+    }
+
+    pub fn is_fully_parsed(&self) -> bool {
+        self.parsed_form.is_some()
+    }
+
+    pub fn get_sub_shader_count(&self) -> usize {
+        self.parsed_form.as_ref().map_or(0, |form| form.sub_shaders.len())
+    }
+
+    pub fn get_sub_shader_lod(&self, sub_shader_index: usize) -> Option<i32> {
+        Some(self.parsed_form.as_ref()?.sub_shaders.get(sub_shader_index)?.lod)
+    }
+
+    pub fn get_pass_count(&self, sub_shader_index: usize) -> usize {
+        self.parsed_form.as_ref()
+            .and_then(|form| form.sub_shaders.get(sub_shader_index))
+            .map_or(0, |sub_shader| sub_shader.passes.len())
+    }
+
+    pub fn get_pass(&self, sub_shader_index: usize, pass_index: usize) -> Option<SerializedPass> {
+        self.parsed_form.as_ref()?.sub_shaders.get(sub_shader_index)?.passes.get(pass_index).cloned()
+    }
+
+    pub fn get_property_count(&self) -> usize {
+        self.parsed_form.as_ref().map_or(0, |form| form.prop_info.len())
+    }
+
+    pub fn get_property(&self, index: usize) -> Option<SerializedProperty> {
+        self.parsed_form.as_ref()?.prop_info.get(index).cloned()
+    }
+
+    pub fn get_keyword_name(&self, index: usize) -> Option<String> {
+        self.parsed_form.as_ref()?.keyword_names.get(index).cloned()
+    }
+
+    pub fn get_custom_editor_name(&self) -> Option<String> {
+        Some(self.parsed_form.as_ref()?.custom_editor_name.clone())
+    }
+
+    // The shader Unity falls back to when none of the subshaders (or passes)
+    // can run. It's referenced by name, but will be among the dependencies.
+    pub fn get_fallback_name(&self) -> Option<String> {
+        let name = &self.parsed_form.as_ref()?.fallback_name;
+        if name.is_empty() || name.eq_ignore_ascii_case("Off") {
+            None
+        } else {
+            Some(name.clone())
+        }
+    }
+
+    pub fn get_default_texture(&self, property_name: &str) -> Option<ShaderDefaultTexture> {
+        self.parsed_form.as_ref()?.prop_info.iter()
+            .find(|prop| prop.prop_type == SerializedPropertyType::Texture && prop.name == property_name)
+            .map(|prop| prop.def_texture.get_default_texture())
+    }
+
+    // Finds the pass a UsePass refers to, given the shader named by its
+    // get_use_pass_shader_name(), so it can be used in the UsePass's place
+    pub fn resolve_use_pass(&self, use_pass: &SerializedPass) -> Option<SerializedPass> {
+        if use_pass.pass_type != PassType::Use {
+            return None;
+        }
+        let (shader_name, pass_name) = use_pass.use_name.rsplit_once('/')?;
+        if shader_name != self.name {
+            return None;
+        }
+        self.parsed_form.as_ref()?.sub_shaders.iter()
+            .flat_map(|sub_shader| sub_shader.passes.iter())
+            .find(|pass| pass.pass_type == PassType::Normal && pass.state.name.eq_ignore_ascii_case(pass_name))
+            .cloned()
+    }
+
+    pub fn get_builtin_kind(&self) -> Option<BuiltinShaderKind> {
+        BuiltinShaderKind::from_name(&self.name)
+    }
+
+    // e.g. "BaseMapShader" for terrain shaders
+    pub fn get_dependency(&self, from: &str) -> Option<String> {
+        self.parsed_form.as_ref()?.dependencies.iter()
+            .find(|dependency| dependency.from == from)
+            .map(|dependency| dependency.to.clone())
+    }
+
+    // Decompresses all of a platform's segments, which together hold the
+    // sub-programs referenced by each SerializedSubProgram's blob_index
+    pub fn get_platform_blob(&self, platform_index: usize) -> Result<ShaderPlatformBlob, String> {
+        self.get_platform_blob_with_progress(platform_index, None)
+    }
+
+    // Same as get_platform_blob, calling on_progress(done, total) as each
+    // segment is decompressed
+    pub fn get_platform_blob_with_progress(&self, platform_index: usize, on_progress: Option<js_sys::Function>) -> Result<ShaderPlatformBlob, String> {
+        let platform = *self.platforms.get(platform_index)
+            .ok_or(format!("invalid platform index {}", platform_index))?;
+        let offsets = &self.offsets[platform_index];
+        let compressed_lengths = &self.compressed_lengths[platform_index];
+        let decompressed_lengths = &self.decompressed_lengths[platform_index];
+        let mut segments = Vec::with_capacity(offsets.len());
+        let mut progress = Progress::new(on_progress, offsets.len());
+        for i in 0..offsets.len() {
+            let start = offsets[i] as usize;
+            let end = start + compressed_lengths[i] as usize;
+            let compressed = self.compressed_blob.get(start..end)
+                .ok_or(format!("segment {} ({}..{}) is out of bounds", i, start, end))?;
+            let segment = profiling::time_decompression(|| lz4_flex::decompress(compressed, decompressed_lengths[i] as usize))
+                .map_err(|err| format!("failed to decompress segment {}: {:?}", i, err))?;
+            segments.push(segment);
+            progress.step();
+        }
+        let header = segments.first()
+            .ok_or(format!("platform {} has no segments", platform_index))?;
+        let mut cursor = Cursor::new(header);
+        let mut reader = Reader::new(&mut cursor);
+        let entries = UnityArray::<binary::ShaderSubProgramEntry>::from_reader_with_ctx(&mut reader, self.segmented)
+            .map_err(|err| format!("failed to read sub-program table: {:?}", err))?;
+        Ok(ShaderPlatformBlob {
+            platform,
+            segments,
+            entries: entries.values,
+        })
+    }
+
+    // Picks the subshader and pass Unity would render with: the first
+    // subshader within max_lod (and matching render_type, as with replacement
+    // shaders), then the first pass in it whose LightMode matches. Like Unity,
+    // we don't look at later subshaders if the chosen one has no such pass.
+    pub fn select_pass(&self, max_lod: i32, light_mode: Option<String>, render_type: Option<String>) -> Option<ShaderPassSelection> {
+        let parsed_form = self.parsed_form.as_ref()?;
+        let sub_shader_index = parsed_form.select_sub_shader(max_lod, render_type.as_deref())?;
+        let sub_shader = &parsed_form.sub_shaders[sub_shader_index];
+        let pass_index = sub_shader.select_pass(light_mode.as_deref())?;
+        Some(ShaderPassSelection {
+            sub_shader_index,
+            pass_index,
+        })
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityShaderPassSelection")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShaderPassSelection {
+    pub sub_shader_index: usize,
+    pub pass_index: usize,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderPlatformBlob")]
+#[derive(Debug, Clone)]
+pub struct ShaderPlatformBlob {
+    pub platform: u32,
+    segments: Vec<Vec<u8>>,
+    entries: Vec<binary::ShaderSubProgramEntry>,
+}
+
+#[wasm_bindgen(js_class = "UnityShaderPlatformBlob")]
+impl ShaderPlatformBlob {
+    pub fn get_sub_program_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get_sub_program(&self, blob_index: u32) -> Option<Vec<u8>> {
+        self.get_sub_program_data(blob_index).map(|data| data.to_vec())
+    }
+
+    pub fn parse_sub_program(&self, blob_index: u32) -> Result<ShaderSubProgram, String> {
+        let data = self.get_sub_program_data(blob_index)
+            .ok_or(format!("invalid blob index {}", blob_index))?;
+        let (_, sub_program) = binary::ShaderSubProgram::from_bytes((data, 0))
+            .map_err(|err| format!("failed to read sub-program {}: {:?}", blob_index, err))?;
+        Ok(sub_program.into())
+    }
+}
+
+impl ShaderPlatformBlob {
+    pub fn get_sub_program_data(&self, blob_index: u32) -> Option<&[u8]> {
+        let entry = self.entries.get(blob_index as usize)?;
+        let segment = self.segments.get(entry.segment as usize)?;
+        let start = entry.offset as usize;
+        segment.get(start..start + entry.length as usize)
+    }
+}
+
+// ShaderGpuProgramType values from GLLegacy through GLCore43
+const GPU_PROGRAM_TYPE_GL_FIRST: i32 = 1;
+const GPU_PROGRAM_TYPE_GL_LAST: i32 = 8;
+
+const GLSL_STAGES: [&str; 5] = ["VERTEX", "FRAGMENT", "GEOMETRY", "HULL", "DOMAIN"];
+
+#[wasm_bindgen(js_name = "UnityShaderSubProgram", getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct ShaderSubProgram {
+    pub version: i32,
+    pub program_type: i32,
+    pub keywords: Vec<String>,
+    pub local_keywords: Vec<String>,
+    program_code: Vec<u8>,
+}
+
+impl From<binary::ShaderSubProgram> for ShaderSubProgram {
+    fn from(value: binary::ShaderSubProgram) -> Self {
+        Self {
+            version: value.version,
+            program_type: value.program_type,
+            keywords: value.keywords.into(),
+            local_keywords: value.local_keywords.map(|v| v.into()).unwrap_or_default(),
+            program_code: value.program_code.into(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityShaderSubProgram")]
+impl ShaderSubProgram {
+    pub fn get_program_code(&self) -> Vec<u8> {
+        self.program_code.clone()
+    }
+
+    pub fn is_glsl(&self) -> bool {
+        (GPU_PROGRAM_TYPE_GL_FIRST..=GPU_PROGRAM_TYPE_GL_LAST).contains(&self.program_type)
+    }
+
+    // Unity compiles all of a GL program's stages into one source, with each
+    // stage's code wrapped in an "#ifdef VERTEX"/"#ifdef FRAGMENT"/etc. block
+    pub fn get_glsl_source(&self) -> Result<ShaderGLSLSource, String> {
+        if !self.is_glsl() {
+            return Err(format!("program type {} isn't GLSL", self.program_type));
+        }
+        // skip any binary header preceding the text
+        let start = self.program_code.iter().position(|b| *b == b'#')
+            .ok_or("couldn't find the start of the GLSL source")?;
+        let text = String::from_utf8_lossy(&self.program_code[start..]);
+        let text = text.trim_end_matches('\0');
+
+        let mut common = String::new();
+        let mut stages: Vec<Option<String>> = vec![None; GLSL_STAGES.len()];
+        let mut current: Option<usize> = None;
+        let mut depth = 0;
+        for line in text.lines() {
+            let directive = line.trim_start();
+            if let Some(stage) = current {
+                if directive.starts_with("#if") {
+                    depth += 1;
+                } else if directive.starts_with("#endif") {
+                    if depth == 0 {
+                        current = None;
+                        continue;
+                    }
+                    depth -= 1;
+                }
+                let source = stages[stage].get_or_insert_with(String::new);
+                source.push_str(line);
+                source.push('\n');
+            } else if let Some(stage) = directive.strip_prefix("#ifdef ").and_then(|name| GLSL_STAGES.iter().position(|s| *s == name.trim())) {
+                current = Some(stage);
+                stages[stage].get_or_insert_with(String::new);
+            } else {
+                common.push_str(line);
+                common.push('\n');
+            }
+        }
+
+        // code outside the stage blocks applies to every stage, but has to
+        // come after the stage's #version directive
+        let mut stages = stages.into_iter().map(|stage| stage.map(|source| {
+            if common.trim().is_empty() {
+                return source;
+            }
+            match source.split_once('\n') {
+                Some((first, rest)) if first.trim_start().starts_with("#version") => format!("{}\n{}{}", first, common, rest),
+                _ => format!("{}{}", common, source),
+            }
+        }));
+        Ok(ShaderGLSLSource {
+            vertex: stages.next().unwrap(),
+            fragment: stages.next().unwrap(),
+            geometry: stages.next().unwrap(),
+            hull: stages.next().unwrap(),
+            domain: stages.next().unwrap(),
+        })
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityShaderGLSLSource", getter_with_clone)]
+#[derive(Debug, Clone, Default)]
+pub struct ShaderGLSLSource {
+    pub vertex: Option<String>,
+    pub fragment: Option<String>,
+    pub geometry: Option<String>,
+    pub hull: Option<String>,
+    pub domain: Option<String>,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedShader", getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct SerializedShader {
+    pub prop_info: Vec<SerializedProperty>,
+    pub sub_shaders: Vec<SerializedSubShader>,
+    // what sub-programs' keyword indices refer to from 2021.2; empty before
+    // that, when they index into each pass's name table instead
+    pub keyword_names: Vec<String>,
+    pub name: String,
+    pub custom_editor_name: String,
+    pub fallback_name: String,
+    pub dependencies: Vec<SerializedShaderDependency>,
+}
+
+impl From<binary::SerializedShader> for SerializedShader {
+    fn from(value: binary::SerializedShader) -> Self {
+        Self {
+            prop_info: value.prop_info.into(),
+            sub_shaders: value.sub_shaders.into(),
+            keyword_names: value.keyword_names.map(|v| v.into()).unwrap_or_default(),
+            name: value.name.into(),
+            custom_editor_name: value.custom_editor_name.into(),
+            fallback_name: value.fallback_name.into(),
+            dependencies: value.dependencies.into(),
+        }
+    }
+}
+
+impl SerializedShader {
+    fn select_sub_shader(&self, max_lod: i32, render_type: Option<&str>) -> Option<usize> {
+        self.sub_shaders.iter().position(|sub_shader| {
+            if sub_shader.lod > max_lod {
+                return false;
+            }
+            match render_type {
+                Some(render_type) => sub_shader.find_tag("RenderType") == Some(render_type),
+                None => true,
+            }
+        })
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedShaderDependency", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::SerializedShaderDependency)]
+pub struct SerializedShaderDependency {
+    pub from: String,
+    pub to: String,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedProperty", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::SerializedProperty)]
+pub struct SerializedProperty {
+    pub name: String,
+    pub description: String,
+    pub attributes: Vec<String>,
+    pub prop_type: SerializedPropertyType,
+    pub flags: u32,
+    pub def_value: Vec<f32>,
+    pub def_texture: SerializedTextureProperty,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedTextureProperty", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::SerializedTextureProperty)]
+pub struct SerializedTextureProperty {
+    pub default_name: String,
+    pub tex_dim: i32,
+}
+
+#[wasm_bindgen(js_class = "UnityShaderSerializedTextureProperty")]
+impl SerializedTextureProperty {
+    // the builtin texture Unity binds when a material leaves this slot empty;
+    // empty or unrecognized names get gray
+    pub fn get_default_texture(&self) -> ShaderDefaultTexture {
+        match self.default_name.to_ascii_lowercase().as_str() {
+            "white" => ShaderDefaultTexture::White,
+            "black" => ShaderDefaultTexture::Black,
+            "bump" => ShaderDefaultTexture::Bump,
+            "red" => ShaderDefaultTexture::Red,
+            "lineargray" | "lineargrey" => ShaderDefaultTexture::LinearGray,
+            _ => ShaderDefaultTexture::Gray,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityShaderDefaultTexture")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShaderDefaultTexture {
+    White,
+    Black,
+    Gray,
+    Bump,
+    Red,
+    LinearGray,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedPropertyType")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug, PartialEq)]
+#[from(binary::SerializedPropertyType)]
+pub enum SerializedPropertyType {
+    Color = 0,
+    Vector = 1,
+    Float = 2,
+    Range = 3,
+    Texture = 4,
+    Int = 5,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedSubShader", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::SerializedSubShader)]
+pub struct SerializedSubShader {
+    pub passes: Vec<SerializedPass>,
+    tags: HashMap<String, String>,
+    pub lod: i32,
+}
+
+// Unity upper-cases the names (and sometimes values) of the tags it knows
+// about, e.g. LightMode becomes LIGHTMODE
+fn find_tag<'a>(tags: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    tags.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+pub const RENDER_QUEUE_GEOMETRY: i32 = 2000;
+// queues above this are drawn back to front, after everything else
+pub const RENDER_QUEUE_GEOMETRY_LAST: i32 = 2500;
+
+// Converts a Queue tag like "Transparent" or "Geometry+1" to its numeric value
+fn parse_render_queue(queue: &str) -> Option<i32> {
+    let (base, offset) = match queue.find(['+', '-']) {
+        Some(i) => (&queue[..i], queue[i..].trim_start_matches('+').trim().parse::<i32>().ok()?),
+        None => (queue, 0),
+    };
+    let base = match base.trim().to_ascii_lowercase().as_str() {
+        "background" => 1000,
+        "geometry" => 2000,
+        "alphatest" => 2450,
+        "transparent" => 3000,
+        "overlay" => 4000,
+        other => other.parse::<i32>().ok()?,
+    };
+    Some(base + offset)
+}
+
+#[wasm_bindgen(js_class = "UnityShaderSerializedSubShader")]
+impl SerializedSubShader {
+    pub fn get_tag_names(&self) -> Vec<String> {
+        self.tags.keys().cloned().collect()
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<String> {
+        self.find_tag(name).map(|v| v.to_string())
+    }
+
+    pub fn get_render_type(&self) -> Option<String> {
+        self.get_tag("RenderType")
+    }
+
+    pub fn get_queue(&self) -> Option<i32> {
+        self.find_tag("Queue").and_then(parse_render_queue)
+    }
+}
+
+impl SerializedSubShader {
+    fn find_tag(&self, name: &str) -> Option<&str> {
+        find_tag(&self.tags, name)
+    }
+
+    // Passes without a LightMode tag are "Always" passes. UsePass/GrabPass
+    // entries aren't real passes, so they're never picked.
+    fn select_pass(&self, light_mode: Option<&str>) -> Option<usize> {
+        self.passes.iter().position(|pass| {
+            if pass.pass_type != PassType::Normal {
+                return false;
+            }
+            match light_mode {
+                Some(light_mode) => pass.find_tag("LightMode")
+                    .unwrap_or("Always")
+                    .eq_ignore_ascii_case(light_mode),
+                None => true,
+            }
+        })
+    }
+}
+
+const INSTANCING_KEYWORD: &str = "INSTANCING_ON";
+const PROCEDURAL_INSTANCING_KEYWORD: &str = "PROCEDURAL_INSTANCING_ON";
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedPass", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::SerializedPass)]
+pub struct SerializedPass {
+    name_indices: HashMap<String, i32>,
+    pub pass_type: PassType,
+    pub state: SerializedShaderState,
+    pub program_mask: u32,
+    pub prog_vertex: SerializedProgram,
+    pub prog_fragment: SerializedProgram,
+    pub prog_geometry: SerializedProgram,
+    pub prog_hull: SerializedProgram,
+    pub prog_domain: SerializedProgram,
+    pub prog_ray_tracing: SerializedProgram,
+    pub has_instancing_variant: u8,
+    pub has_procedural_instancing_variant: u8,
+    pub use_name: String,
+    pub name: String,
+    pub texture_name: String,
+    tags: HashMap<String, String>,
+}
+
+#[wasm_bindgen(js_class = "UnityShaderSerializedPass")]
+impl SerializedPass {
+    pub fn get_name_index(&self, name: &str) -> Option<i32> {
+        self.name_indices.get(name).copied()
+    }
+
+    // The whole name table in two calls: newline-separated names, and their
+    // indices in the same order
+    pub fn get_name_table_names(&self) -> String {
+        self.get_sorted_name_table().iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+
+    pub fn get_name_table_indices(&self) -> Vec<i32> {
+        self.get_sorted_name_table().iter()
+            .map(|(_, index)| *index)
+            .collect()
+    }
+
+    // includes tags inherited from the subshader
+    pub fn get_tag_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.state.tags.keys().cloned().collect();
+        for name in self.tags.keys() {
+            if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+                names.push(name.clone());
+            }
+        }
+        names
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<String> {
+        self.find_tag(name).map(|v| v.to_string())
+    }
+
+    pub fn get_light_mode(&self) -> Option<String> {
+        self.get_tag("LightMode")
+    }
+
+    pub fn get_render_type(&self) -> Option<String> {
+        self.get_tag("RenderType")
+    }
+
+    pub fn get_queue(&self) -> Option<i32> {
+        self.find_tag("Queue").and_then(parse_render_queue)
+    }
+
+    // UsePass names look like "Shader/Name/PASSNAME"
+    pub fn get_use_pass_shader_name(&self) -> Option<String> {
+        if self.pass_type != PassType::Use {
+            return None;
+        }
+        self.use_name.rsplit_once('/').map(|(shader_name, _)| shader_name.to_string())
+    }
+
+    // The texture a GrabPass copies the screen into. Unnamed GrabPasses use
+    // _GrabTexture, but grab the screen again for every object.
+    pub fn get_grab_texture_name(&self) -> Option<String> {
+        if self.pass_type != PassType::Grab {
+            return None;
+        }
+        if self.texture_name.is_empty() {
+            Some("_GrabTexture".to_string())
+        } else {
+            Some(self.texture_name.clone())
+        }
+    }
+
+    pub fn get_render_state(&self) -> ShaderRenderState {
+        self.state.get_render_state()
+    }
+
+    pub fn get_material_render_state(&self, material: &Material) -> ShaderRenderState {
+        self.state.get_material_render_state(material)
+    }
+
+    pub fn get_fog(&self, render_settings: &RenderSettings) -> FogParameters {
+        self.state.get_fog(render_settings)
+    }
+
+    pub fn get_material_fog(&self, render_settings: &RenderSettings, material: &Material) -> FogParameters {
+        self.state.get_material_fog(render_settings, material)
+    }
+
+    // Whether a draw with these keywords enabled should use the pass's
+    // instanced sub-programs rather than the regular ones
+    pub fn uses_instancing_variant(&self, keywords: Vec<String>) -> bool {
+        let has = |keyword: &str| keywords.iter().any(|k| k == keyword);
+        (self.has_instancing_variant != 0 && has(INSTANCING_KEYWORD))
+            || (self.has_procedural_instancing_variant != 0 && has(PROCEDURAL_INSTANCING_KEYWORD))
+    }
+
+    // The keywords to select this pass's sub-programs with when drawing the
+    // material, instanced or not. Like Unity, INSTANCING_ON is only enabled
+    // for materials with "Enable GPU Instancing" checked, and only if the
+    // pass was compiled with an instanced variant.
+    pub fn get_draw_keywords(&self, material: &Material, instanced: bool) -> Vec<String> {
+        let mut keywords = material.get_keywords();
+        if instanced && material.enable_instancing_variants != 0 && self.has_instancing_variant != 0 {
+            keywords.push(INSTANCING_KEYWORD.to_string());
+        }
+        keywords
+    }
+
+    // Picks the sub-program Unity would use with these keywords enabled: the
+    // one whose keywords are all enabled, with the most of them. Instanced
+    // sub-programs require INSTANCING_ON, so they're only picked once it's
+    // enabled. keyword_names is the shader's, for 2021.2 and up.
+    pub fn select_sub_program(&self, program: &SerializedProgram, keyword_names: Vec<String>, keywords: Vec<String>, gpu_program_type: Option<i8>) -> Option<usize> {
+        let names_by_index = self.get_names_by_index();
+        let resolve = |index: u16| -> Option<&str> {
+            if keyword_names.is_empty() {
+                names_by_index.get(&(index as i32)).map(|name| name.as_str())
+            } else {
+                keyword_names.get(index as usize).map(|name| name.as_str())
+            }
+        };
+        let mut best: Option<(usize, usize)> = None;
+        for (i, sub_program) in program.sub_programs.iter().enumerate() {
+            if gpu_program_type.is_some_and(|t| t != sub_program.gpu_program_type) {
+                continue;
+            }
+            let all_enabled = sub_program.keyword_indices.iter()
+                .all(|&index| resolve(index).is_some_and(|name| keywords.iter().any(|k| k == name)));
+            if !all_enabled {
+                continue;
+            }
+            let score = sub_program.keyword_indices.len();
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((i, score));
+            }
+        }
+        best.map(|(i, _)| i)
+    }
+
+    // the reverse of name_indices, for resolving parameters' name_index
+    pub fn get_name(&self, name_index: i32) -> Option<String> {
+        self.name_indices.iter()
+            .find(|(_, index)| **index == name_index)
+            .map(|(name, _)| name.clone())
+    }
+
+    // Resolves each parameter's name_index, returning names in the same order
+    // as the parameter lists (or empty strings for unknown indices)
+    pub fn get_parameter_names(&self, parameters: &SerializedProgramParameters) -> ShaderParameterNames {
+        let names = self.get_names_by_index();
+        let resolve = |name_index: i32| names.get(&name_index).cloned().unwrap_or_default();
+        ShaderParameterNames {
+            vector_params: parameters.vector_params.iter().map(|p| resolve(p.name_index)).collect(),
+            matrix_params: parameters.matrix_params.iter().map(|p| resolve(p.name_index)).collect(),
+            texture_params: parameters.texture_params.iter().map(|p| resolve(p.name_index)).collect(),
+            buffer_params: parameters.buffer_params.iter().map(|p| resolve(p.name_index)).collect(),
+            constant_buffers: parameters.constant_buffers.iter().map(|p| resolve(p.name_index)).collect(),
+            constant_buffer_bindings: parameters.constant_buffer_bindings.iter().map(|p| resolve(p.name_index)).collect(),
+            uav_params: parameters.uav_params.iter().map(|p| resolve(p.name_index)).collect(),
+        }
+    }
+
+    // Everything in the parameter lists, with names resolved, as one object
+    pub fn get_parameter_info(&self, parameters: &SerializedProgramParameters) -> ShaderParameterInfo {
+        let names = self.get_names_by_index();
+        let resolve = |name_index: i32| names.get(&name_index).cloned().unwrap_or_default();
+        let vector_info = |p: &VectorParameter| VectorParameterInfo {
+            name: resolve(p.name_index),
+            index: p.index,
+            array_size: p.array_size,
+            param_type: p.param_type,
+            dim: p.dim,
+        };
+        let matrix_info = |p: &MatrixParameter| MatrixParameterInfo {
+            name: resolve(p.name_index),
+            index: p.index,
+            array_size: p.array_size,
+            param_type: p.param_type,
+            row_count: p.row_count,
+        };
+        let buffer_info = |p: &BufferBinding| BufferBindingInfo {
+            name: resolve(p.name_index),
+            index: p.index,
+            array_size: p.array_size,
+        };
+        ShaderParameterInfo {
+            vector_params: parameters.vector_params.iter().map(vector_info).collect(),
+            matrix_params: parameters.matrix_params.iter().map(matrix_info).collect(),
+            texture_params: parameters.texture_params.iter().map(|p| TextureParameterInfo {
+                name: resolve(p.name_index),
+                index: p.index,
+                sampler_index: p.sampler_index,
+                multi_sampled: p.multi_sampled != 0,
+                dim: p.dim,
+            }).collect(),
+            buffer_params: parameters.buffer_params.iter().map(buffer_info).collect(),
+            constant_buffers: parameters.constant_buffers.iter().map(|cb| ConstantBufferInfo {
+                name: resolve(cb.name_index),
+                size: cb.size,
+                vector_params: cb.vector_params.iter().map(vector_info).collect(),
+                matrix_params: cb.matrix_params.iter().map(matrix_info).collect(),
+            }).collect(),
+            constant_buffer_bindings: parameters.constant_buffer_bindings.iter().map(buffer_info).collect(),
+            uav_params: parameters.uav_params.iter().map(|p| UAVParameterInfo {
+                name: resolve(p.name_index),
+                index: p.index,
+                original_index: p.original_index,
+            }).collect(),
+            samplers: parameters.samplers.iter().map(|p| SamplerParameterInfo {
+                sampler: p.sampler,
+                bind_point: p.bind_point,
+            }).collect(),
+        }
+    }
+
+    pub fn get_constant_buffer_names(&self, constant_buffer: &ConstantBuffer) -> ShaderConstantBufferNames {
+        let names = self.get_names_by_index();
+        let resolve = |name_index: i32| names.get(&name_index).cloned().unwrap_or_default();
+        ShaderConstantBufferNames {
+            name: resolve(constant_buffer.name_index),
+            vector_params: constant_buffer.vector_params.iter().map(|p| resolve(p.name_index)).collect(),
+            matrix_params: constant_buffer.matrix_params.iter().map(|p| resolve(p.name_index)).collect(),
+            struct_params: constant_buffer.struct_params.iter().map(|p| resolve(p.name_index)).collect(),
+        }
+    }
+}
+
+impl SerializedPass {
+    // a pass's own Tags block ends up in its state
+    fn find_tag(&self, name: &str) -> Option<&str> {
+        find_tag(&self.state.tags, name).or_else(|| find_tag(&self.tags, name))
+    }
+
+    fn get_sorted_name_table(&self) -> Vec<(&String, i32)> {
+        let mut result: Vec<(&String, i32)> = self.name_indices.iter()
+            .map(|(name, index)| (name, *index))
+            .collect();
+        result.sort();
+        result
+    }
+
+    fn get_names_by_index(&self) -> HashMap<i32, String> {
+        self.name_indices.iter()
+            .map(|(name, index)| (*index, name.clone()))
+            .collect()
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityShaderParameterNames", getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct ShaderParameterNames {
+    pub vector_params: Vec<String>,
+    pub matrix_params: Vec<String>,
+    pub texture_params: Vec<String>,
+    pub buffer_params: Vec<String>,
+    pub constant_buffers: Vec<String>,
+    pub constant_buffer_bindings: Vec<String>,
+    pub uav_params: Vec<String>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct ShaderParameterInfo {
+    pub vector_params: Vec<VectorParameterInfo>,
+    pub matrix_params: Vec<MatrixParameterInfo>,
+    pub texture_params: Vec<TextureParameterInfo>,
+    pub buffer_params: Vec<BufferBindingInfo>,
+    pub constant_buffers: Vec<ConstantBufferInfo>,
+    pub constant_buffer_bindings: Vec<BufferBindingInfo>,
+    pub uav_params: Vec<UAVParameterInfo>,
+    pub samplers: Vec<SamplerParameterInfo>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct VectorParameterInfo {
+    pub name: String,
+    pub index: i32,
+    pub array_size: i32,
+    pub param_type: i8,
+    pub dim: i8,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct MatrixParameterInfo {
+    pub name: String,
+    pub index: i32,
+    pub array_size: i32,
+    pub param_type: i8,
+    pub row_count: i8,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct TextureParameterInfo {
+    pub name: String,
+    pub index: i32,
+    pub sampler_index: i32,
+    pub multi_sampled: bool,
+    pub dim: i8,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct BufferBindingInfo {
+    pub name: String,
+    pub index: i32,
+    pub array_size: Option<i32>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct ConstantBufferInfo {
+    pub name: String,
+    pub size: i32,
+    pub vector_params: Vec<VectorParameterInfo>,
+    pub matrix_params: Vec<MatrixParameterInfo>,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct UAVParameterInfo {
+    pub name: String,
+    pub index: i32,
+    pub original_index: i32,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct SamplerParameterInfo {
+    pub sampler: u32,
+    pub bind_point: i32,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderConstantBufferNames", getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct ShaderConstantBufferNames {
+    pub name: String,
+    pub vector_params: Vec<String>,
+    pub matrix_params: Vec<String>,
+    pub struct_params: Vec<String>,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderPassType")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug, PartialEq)]
+#[from(binary::PassType)]
+pub enum PassType {
+    Normal = 0,
+    Use = 1,
+    Grab = 2,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedShaderState", getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct SerializedShaderState {
+    pub name: String,
+    pub rt_blend: Vec<SerializedShaderRTBlendState>,
+    pub rt_separate_blend: u8,
+    pub z_clip: SerializedShaderFloatValue,
+    pub z_test: SerializedShaderFloatValue,
+    pub z_write: SerializedShaderFloatValue,
+    pub culling: SerializedShaderFloatValue,
+    pub conservative: Option<SerializedShaderFloatValue>,
+    pub offset_factor: SerializedShaderFloatValue,
+    pub offset_units: SerializedShaderFloatValue,
+    pub alpha_to_mask: SerializedShaderFloatValue,
+    pub stencil_op: SerializedStencilOp,
+    pub stencil_op_front: SerializedStencilOp,
+    pub stencil_op_back: SerializedStencilOp,
+    pub stencil_read_mask: SerializedShaderFloatValue,
+    pub stencil_write_mask: SerializedShaderFloatValue,
+    pub stencil_ref: SerializedShaderFloatValue,
+    pub fog_start: SerializedShaderFloatValue,
+    pub fog_end: SerializedShaderFloatValue,
+    pub fog_density: SerializedShaderFloatValue,
+    pub fog_color: SerializedShaderVectorValue,
+    pub fog_mode: i32,
+    pub gpu_program_id: i32,
+    tags: HashMap<String, String>,
+    pub lod: i32,
+    pub lighting: u8,
+}
+
+impl From<binary::SerializedShaderState> for SerializedShaderState {
+    fn from(value: binary::SerializedShaderState) -> Self {
+        Self {
+            name: value.name.into(),
+            rt_blend: Vec::from(value.rt_blend).into_iter().map(|v| v.into()).collect(),
+            rt_separate_blend: value.rt_separate_blend,
+            z_clip: value.z_clip.into(),
+            z_test: value.z_test.into(),
+            z_write: value.z_write.into(),
+            culling: value.culling.into(),
+            conservative: value.conservative.map(|v| v.into()),
+            offset_factor: value.offset_factor.into(),
+            offset_units: value.offset_units.into(),
+            alpha_to_mask: value.alpha_to_mask.into(),
+            stencil_op: value.stencil_op.into(),
+            stencil_op_front: value.stencil_op_front.into(),
+            stencil_op_back: value.stencil_op_back.into(),
+            stencil_read_mask: value.stencil_read_mask.into(),
+            stencil_write_mask: value.stencil_write_mask.into(),
+            stencil_ref: value.stencil_ref.into(),
+            fog_start: value.fog_start.into(),
+            fog_end: value.fog_end.into(),
+            fog_density: value.fog_density.into(),
+            fog_color: value.fog_color.into(),
+            fog_mode: value.fog_mode,
+            gpu_program_id: value.gpu_program_id,
+            tags: value.tags.into(),
+            lod: value.lod,
+            lighting: value.lighting,
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityShaderSerializedShaderState")]
+impl SerializedShaderState {
+    // Evaluates the state using its default values, ignoring any material
+    // property bindings
+    pub fn get_render_state(&self) -> ShaderRenderState {
+        self.evaluate_render_state(|value| value.val)
+    }
+
+    // The fog to draw the pass with in a scene, using its default values
+    pub fn get_fog(&self, render_settings: &RenderSettings) -> FogParameters {
+        self.evaluate_fog(render_settings, |value| value.val, |value| {
+            let color = ColorRGBA { r: value.x.val, g: value.y.val, b: value.z.val, a: value.w.val };
+            if color.r == 0.0 && color.g == 0.0 && color.b == 0.0 && color.a == 0.0 { None } else { Some(color) }
+        })
+    }
+
+    // Like get_fog, looking up values bound to material properties
+    pub fn get_material_fog(&self, render_settings: &RenderSettings, material: &Material) -> FogParameters {
+        let fog = self.get_fog(render_settings);
+        self.evaluate_fog(render_settings, |value| {
+            if value.name.is_empty() {
+                return value.val;
+            }
+            material.floats.get(&value.name).copied().unwrap_or(value.val)
+        }, |value| {
+            if value.name.is_empty() {
+                return Some(fog.color);
+            }
+            material.colors.get(&value.name).copied().or(Some(fog.color))
+        })
+    }
+
+    // Values bound to a material property (e.g. "Blend [_SrcBlend] [_DstBlend]")
+    // are looked up in the material, falling back to the default when the
+    // material doesn't set them
+    pub fn get_material_render_state(&self, material: &Material) -> ShaderRenderState {
+        self.evaluate_render_state(|value| {
+            if value.name.is_empty() {
+                return value.val;
+            }
+            material.floats.get(&value.name).copied().unwrap_or(value.val)
+        })
+    }
+}
+
+impl SerializedShaderState {
+    // Fog blocks (e.g. "Fog { Mode Off }" or "Fog { Color [_FogColor] }")
+    // override the scene's fog for the pass. Values the pass doesn't set are
+    // stored unnamed and zero, so those fall back to the scene's.
+    fn evaluate_fog<F, G>(&self, render_settings: &RenderSettings, eval: F, eval_color: G) -> FogParameters
+        where F: Fn(&SerializedShaderFloatValue) -> f32, G: Fn(&SerializedShaderVectorValue) -> Option<ColorRGBA>
+    {
+        let scene_mode = if render_settings.fog != 0 { render_settings.fog_mode } else { FOG_MODE_DISABLED };
+        let mode = match self.fog_mode {
+            FOG_MODE_UNKNOWN => scene_mode,
+            _ if scene_mode == FOG_MODE_DISABLED => FOG_MODE_DISABLED,
+            mode => mode,
+        };
+        let eval_or = |value: &SerializedShaderFloatValue, default: f32| {
+            let v = eval(value);
+            if value.name.is_empty() && v == 0.0 { default } else { v }
+        };
+        FogParameters {
+            enabled: (FOG_MODE_LINEAR..=FOG_MODE_EXP2).contains(&mode),
+            mode,
+            color: eval_color(&self.fog_color).unwrap_or(render_settings.fog_color),
+            start: eval_or(&self.fog_start, render_settings.linear_fog_start),
+            end: eval_or(&self.fog_end, render_settings.linear_fog_end),
+            density: eval_or(&self.fog_density, render_settings.fog_density),
+        }
+    }
+
+    fn evaluate_render_state<F>(&self, eval: F) -> ShaderRenderState
+        where F: Fn(&SerializedShaderFloatValue) -> f32
+    {
+        let eval_i32 = |value: &SerializedShaderFloatValue| eval(value) as i32;
+        let blend = &self.rt_blend[0];
+        let stencil = &self.stencil_op;
+        let offset_factor = eval(&self.offset_factor);
+        let offset_units = eval(&self.offset_units);
+        ShaderRenderState {
+            channel_write_mask: gfx_channel_write_mask(eval_i32(&blend.col_mask)),
+            rgb_blend_mode: gfx_blend_mode(eval_i32(&blend.blend_op)),
+            rgb_blend_src_factor: gfx_blend_factor(eval_i32(&blend.src_blend)),
+            rgb_blend_dst_factor: gfx_blend_factor(eval_i32(&blend.dest_blend)),
+            alpha_blend_mode: gfx_blend_mode(eval_i32(&blend.blend_op_alpha)),
+            alpha_blend_src_factor: gfx_blend_factor(eval_i32(&blend.src_blend_alpha)),
+            alpha_blend_dst_factor: gfx_blend_factor(eval_i32(&blend.dest_blend_alpha)),
+            depth_compare: gfx_compare_mode(eval_i32(&self.z_test)),
+            depth_write: eval_i32(&self.z_write) != 0,
+            stencil_compare: gfx_compare_mode(eval_i32(&stencil.comp)),
+            stencil_write: [&stencil.pass, &stencil.fail, &stencil.z_fail].iter()
+                .any(|op| eval_i32(op) != UNITY_STENCIL_OP_KEEP),
+            stencil_pass_op: gfx_stencil_op(eval_i32(&stencil.pass)),
+            stencil_ref: eval_i32(&self.stencil_ref) as u32 & 0xFF,
+            stencil_read_mask: eval_i32(&self.stencil_read_mask) as u32 & 0xFF,
+            stencil_write_mask: eval_i32(&self.stencil_write_mask) as u32 & 0xFF,
+            cull_mode: eval_i32(&self.culling).clamp(0, 2) as u32,
+            front_face: GL_CW,
+            polygon_offset: offset_factor != 0.0 || offset_units != 0.0,
+            offset_factor,
+            offset_units,
+        }
+    }
+}
+
+// The state of a pass's first render target, with enum values matching
+// noclip's GfxMegaStateDescriptor (GfxBlendFactor, GfxCompareMode, etc. are
+// WebGL constants). Depth compares aren't reversed, so pass them through
+// reverseDepthForCompareMode like any other.
+#[wasm_bindgen(js_name = "UnityShaderRenderState")]
+#[derive(Debug, Clone, Copy)]
+pub struct ShaderRenderState {
+    pub channel_write_mask: u32,
+    pub rgb_blend_mode: u32,
+    pub rgb_blend_src_factor: u32,
+    pub rgb_blend_dst_factor: u32,
+    pub alpha_blend_mode: u32,
+    pub alpha_blend_src_factor: u32,
+    pub alpha_blend_dst_factor: u32,
+    pub depth_compare: u32,
+    pub depth_write: bool,
+    pub stencil_compare: u32,
+    pub stencil_write: bool,
+    pub stencil_pass_op: u32,
+    pub stencil_ref: u32,
+    pub stencil_read_mask: u32,
+    pub stencil_write_mask: u32,
+    pub cull_mode: u32,
+    pub front_face: u32,
+    pub polygon_offset: bool,
+    pub offset_factor: f32,
+    pub offset_units: f32,
+}
+
+// UnityEngine.FogMode, plus the shader-only Unknown (use the scene's) and
+// Disabled
+const FOG_MODE_UNKNOWN: i32 = -1;
+const FOG_MODE_DISABLED: i32 = 0;
+const FOG_MODE_LINEAR: i32 = 1;
+const FOG_MODE_EXP: i32 = 2;
+const FOG_MODE_EXP2: i32 = 3;
+
+// Everything needed to draw a pass's fog, with the scene's settings and the
+// pass's overrides combined
+#[wasm_bindgen(js_name = "UnityFogParameters")]
+#[derive(Debug, Clone, Copy)]
+pub struct FogParameters {
+    pub enabled: bool,
+    pub mode: i32,
+    pub color: ColorRGBA,
+    pub start: f32,
+    pub end: f32,
+    pub density: f32,
+}
+
+#[wasm_bindgen(js_class = "UnityFogParameters")]
+impl FogParameters {
+    // The global keyword Unity enables for the mode, which selects the
+    // sub-program variant that computes fog
+    pub fn get_keyword(&self) -> Option<String> {
+        match self.mode {
+            FOG_MODE_LINEAR => Some("FOG_LINEAR".to_string()),
+            FOG_MODE_EXP => Some("FOG_EXP".to_string()),
+            FOG_MODE_EXP2 => Some("FOG_EXP2".to_string()),
+            _ => None,
+        }
+    }
+}
+
+const UNITY_STENCIL_OP_KEEP: i32 = 0;
+
+const GL_ZERO: u32 = 0;
+const GL_ONE: u32 = 1;
+const GL_SRC_COLOR: u32 = 0x0300;
+const GL_ONE_MINUS_SRC_COLOR: u32 = 0x0301;
+const GL_SRC_ALPHA: u32 = 0x0302;
+const GL_ONE_MINUS_SRC_ALPHA: u32 = 0x0303;
+const GL_DST_ALPHA: u32 = 0x0304;
+const GL_ONE_MINUS_DST_ALPHA: u32 = 0x0305;
+const GL_DST_COLOR: u32 = 0x0306;
+const GL_ONE_MINUS_DST_COLOR: u32 = 0x0307;
+const GL_FUNC_ADD: u32 = 0x8006;
+const GL_FUNC_SUBTRACT: u32 = 0x800A;
+const GL_FUNC_REVERSE_SUBTRACT: u32 = 0x800B;
+const GL_NEVER: u32 = 0x0200;
+const GL_ALWAYS: u32 = 0x0207;
+const GL_KEEP: u32 = 0x1E00;
+const GL_REPLACE: u32 = 0x1E01;
+const GL_INCR: u32 = 0x1E02;
+const GL_DECR: u32 = 0x1E03;
+const GL_INVERT: u32 = 0x150A;
+const GL_INCR_WRAP: u32 = 0x8507;
+const GL_DECR_WRAP: u32 = 0x8508;
+const GL_CW: u32 = 0x0900;
+
+// UnityEngine.Rendering.BlendMode
+fn gfx_blend_factor(value: i32) -> u32 {
+    match value {
+        0 => GL_ZERO,
+        2 => GL_DST_COLOR,
+        3 => GL_SRC_COLOR,
+        4 => GL_ONE_MINUS_DST_COLOR,
+        // SrcAlphaSaturate has no Gfx equivalent
+        5 | 9 => GL_SRC_ALPHA,
+        6 => GL_ONE_MINUS_SRC_COLOR,
+        7 => GL_DST_ALPHA,
+        8 => GL_ONE_MINUS_DST_ALPHA,
+        10 => GL_ONE_MINUS_SRC_ALPHA,
+        _ => GL_ONE,
+    }
+}
+
+// UnityEngine.Rendering.BlendOp; Min, Max and the logical ops aren't supported
+fn gfx_blend_mode(value: i32) -> u32 {
+    match value {
+        1 => GL_FUNC_SUBTRACT,
+        2 => GL_FUNC_REVERSE_SUBTRACT,
+        _ => GL_FUNC_ADD,
+    }
+}
+
+// UnityEngine.Rendering.CompareFunction, which is GL's order offset by one
+// with 0 meaning disabled
+fn gfx_compare_mode(value: i32) -> u32 {
+    match value {
+        1..=8 => GL_NEVER + (value - 1) as u32,
+        _ => GL_ALWAYS,
+    }
+}
+
+// UnityEngine.Rendering.StencilOp
+fn gfx_stencil_op(value: i32) -> u32 {
+    match value {
+        1 => GL_ZERO,
+        2 => GL_REPLACE,
+        3 => GL_INCR,
+        4 => GL_DECR,
+        5 => GL_INVERT,
+        6 => GL_INCR_WRAP,
+        7 => GL_DECR_WRAP,
+        _ => GL_KEEP,
+    }
+}
+
+// Unity's ColorWriteMask has the channel bits in the opposite order
+fn gfx_channel_write_mask(value: i32) -> u32 {
+    let value = value as u32;
+    ((value & 8) >> 3) | ((value & 4) >> 1) | ((value & 2) << 1) | ((value & 1) << 3)
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedShaderFloatValue", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::SerializedShaderFloatValue)]
+pub struct SerializedShaderFloatValue {
+    pub val: f32,
+    pub name: String,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedShaderVectorValue", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::SerializedShaderVectorValue)]
+pub struct SerializedShaderVectorValue {
+    pub x: SerializedShaderFloatValue,
+    pub y: SerializedShaderFloatValue,
+    pub z: SerializedShaderFloatValue,
+    pub w: SerializedShaderFloatValue,
+    pub name: String,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedShaderRTBlendState", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::SerializedShaderRTBlendState)]
+pub struct SerializedShaderRTBlendState {
+    pub src_blend: SerializedShaderFloatValue,
+    pub dest_blend: SerializedShaderFloatValue,
+    pub src_blend_alpha: SerializedShaderFloatValue,
+    pub dest_blend_alpha: SerializedShaderFloatValue,
+    pub blend_op: SerializedShaderFloatValue,
+    pub blend_op_alpha: SerializedShaderFloatValue,
+    pub col_mask: SerializedShaderFloatValue,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedStencilOp", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::SerializedStencilOp)]
+pub struct SerializedStencilOp {
+    pub pass: SerializedShaderFloatValue,
+    pub fail: SerializedShaderFloatValue,
+    pub z_fail: SerializedShaderFloatValue,
+    pub comp: SerializedShaderFloatValue,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedProgram", getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct SerializedProgram {
+    pub sub_programs: Vec<SerializedSubProgram>,
+    pub common_parameters: Option<SerializedProgramParameters>,
+}
+
+impl From<binary::SerializedProgram> for SerializedProgram {
+    fn from(value: binary::SerializedProgram) -> Self {
+        Self {
+            sub_programs: value.sub_programs.into(),
+            common_parameters: value.common_parameters.map(|v| v.into()),
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedSubProgram", getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct SerializedSubProgram {
+    pub blob_index: u32,
+    pub keyword_indices: Vec<u16>,
+    pub shader_hardware_tier: i8,
+    pub gpu_program_type: i8,
+    pub parameters: SerializedProgramParameters,
+    pub shader_requirements: i64,
+}
+
+impl From<binary::SerializedSubProgram> for SerializedSubProgram {
+    fn from(value: binary::SerializedSubProgram) -> Self {
+        // before 2021.2, keywords were split into global and local sets
+        let mut keyword_indices: Vec<u16> = Vec::new();
+        for indices in vec![value.global_keyword_indices, value.local_keyword_indices, value.keyword_indices].into_iter().flatten() {
+            keyword_indices.extend(indices.values);
+        }
+        let shader_requirements = match value.shader_requirements_64 {
+            Some(v) => v,
+            None => value.shader_requirements_32.unwrap_or(0) as i64,
+        };
+        Self {
+            blob_index: value.blob_index,
+            keyword_indices,
+            shader_hardware_tier: value.shader_hardware_tier,
+            gpu_program_type: value.gpu_program_type,
+            parameters: value.parameters.into(),
+            shader_requirements,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSerializedProgramParameters", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::SerializedProgramParameters)]
+pub struct SerializedProgramParameters {
+    pub vector_params: Vec<VectorParameter>,
+    pub matrix_params: Vec<MatrixParameter>,
+    pub texture_params: Vec<TextureParameter>,
+    pub buffer_params: Vec<BufferBinding>,
+    pub constant_buffers: Vec<ConstantBuffer>,
+    pub constant_buffer_bindings: Vec<BufferBinding>,
+    pub uav_params: Vec<UAVParameter>,
+    pub samplers: Vec<SamplerParameter>,
+}
+
+// Flattened versions of the parameter lists, for reading in bulk. Each
+// parameter becomes a fixed number of consecutive values, in the same order
+// as the struct's fields; missing optional values are -1.
+#[wasm_bindgen(js_class = "UnityShaderSerializedProgramParameters")]
+impl SerializedProgramParameters {
+    pub fn get_vector_param_data(&self) -> Vec<i32> {
+        vector_param_data(&self.vector_params)
+    }
+
+    pub fn get_matrix_param_data(&self) -> Vec<i32> {
+        matrix_param_data(&self.matrix_params)
+    }
+
+    pub fn get_texture_param_data(&self) -> Vec<i32> {
+        self.texture_params.iter()
+            .flat_map(|p| vec![p.name_index, p.index, p.sampler_index, p.multi_sampled as i32, p.dim as i32])
+            .collect()
+    }
+
+    pub fn get_buffer_param_data(&self) -> Vec<i32> {
+        buffer_binding_data(&self.buffer_params)
+    }
+
+    pub fn get_constant_buffer_binding_data(&self) -> Vec<i32> {
+        buffer_binding_data(&self.constant_buffer_bindings)
+    }
+
+    pub fn get_uav_param_data(&self) -> Vec<i32> {
+        self.uav_params.iter()
+            .flat_map(|p| vec![p.name_index, p.index, p.original_index])
+            .collect()
+    }
+
+    pub fn get_sampler_data(&self) -> Vec<i32> {
+        self.samplers.iter()
+            .flat_map(|p| vec![p.sampler as i32, p.bind_point])
+            .collect()
+    }
+}
+
+fn vector_param_data(params: &[VectorParameter]) -> Vec<i32> {
+    params.iter()
+        .flat_map(|p| vec![p.name_index, p.index, p.array_size, p.param_type as i32, p.dim as i32])
+        .collect()
+}
+
+fn matrix_param_data(params: &[MatrixParameter]) -> Vec<i32> {
+    params.iter()
+        .flat_map(|p| vec![p.name_index, p.index, p.array_size, p.param_type as i32, p.row_count as i32])
+        .collect()
+}
+
+fn buffer_binding_data(params: &[BufferBinding]) -> Vec<i32> {
+    params.iter()
+        .flat_map(|p| vec![p.name_index, p.index, p.array_size.unwrap_or(-1)])
+        .collect()
+}
+
+#[wasm_bindgen(js_name = "UnityShaderVectorParameter")]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::VectorParameter)]
+pub struct VectorParameter {
+    pub name_index: i32,
+    pub index: i32,
+    pub array_size: i32,
+    pub param_type: i8,
+    pub dim: i8,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderMatrixParameter")]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::MatrixParameter)]
+pub struct MatrixParameter {
+    pub name_index: i32,
+    pub index: i32,
+    pub array_size: i32,
+    pub param_type: i8,
+    pub row_count: i8,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderTextureParameter")]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::TextureParameter)]
+pub struct TextureParameter {
+    pub name_index: i32,
+    pub index: i32,
+    pub sampler_index: i32,
+    pub multi_sampled: u8,
+    pub dim: i8,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderBufferBinding")]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::BufferBinding)]
+pub struct BufferBinding {
+    pub name_index: i32,
+    pub index: i32,
+    pub array_size: Option<i32>,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderConstantBuffer", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::ConstantBuffer)]
+pub struct ConstantBuffer {
+    pub name_index: i32,
+    pub matrix_params: Vec<MatrixParameter>,
+    pub vector_params: Vec<VectorParameter>,
+    pub struct_params: Vec<StructParameter>,
+    pub size: i32,
+    pub is_partial_cb: Option<u8>,
+}
+
+#[wasm_bindgen(js_class = "UnityShaderConstantBuffer")]
+impl ConstantBuffer {
+    pub fn get_vector_param_data(&self) -> Vec<i32> {
+        vector_param_data(&self.vector_params)
+    }
+
+    pub fn get_matrix_param_data(&self) -> Vec<i32> {
+        matrix_param_data(&self.matrix_params)
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityShaderStructParameter", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::StructParameter)]
+pub struct StructParameter {
+    pub name_index: i32,
+    pub index: i32,
+    pub array_size: i32,
+    pub struct_size: i32,
+    pub vector_params: Vec<VectorParameter>,
+    pub matrix_params: Vec<MatrixParameter>,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderUAVParameter")]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::UAVParameter)]
+pub struct UAVParameter {
+    pub name_index: i32,
+    pub index: i32,
+    pub original_index: i32,
+}
+
+#[wasm_bindgen(js_name = "UnityShaderSamplerParameter")]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::SamplerParameter)]
+pub struct SamplerParameter {
+    pub sampler: u32,
+    pub bind_point: i32,
+}
+
+#[wasm_bindgen(js_name = "UnityTexEnv")]
+#[derive(FromStructPerField, Serialize, Debug, Clone)]
+#[from(binary::TexEnv)]
+pub struct TexEnv {
+    pub texture: WasmFriendlyPPtr,
+    pub scale: Vec2,
+    pub offset: Vec2,
+}
+
+#[wasm_bindgen(js_name = "UnityMesh", getter_with_clone)]
+#[derive(Debug, Clone, FromStructPerField)]
+#[from(binary::Mesh)]
+pub struct Mesh {
+    pub name: String,
+    pub submeshes: Vec<SubMesh>,
+    // pub shapes: Vec<BlendShapeData>,
+    pub bind_pose: Vec<Matrix4x4>,
+    pub bone_name_hashes: Vec<u32>,
+    pub root_bone_name_hash: u32,
+    pub bones_aabb: Vec<AABB>,
+    pub variable_bone_count_weights: Vec<u32>,
+    pub mesh_compression: MeshCompression,
+    pub is_readable: u8,
+    pub keep_vertices: u8,
+    pub keep_indices: u8,
+    pub index_format: IndexFormat,
+    pub index_buffer: Vec<u8>,
+    pub vertex_data: VertexData,
+    pub compressed_mesh: CompressedMesh,
+    pub local_aabb: AABB,
+    pub mesh_usage_flags: i32,
+    pub baked_convex_collision_mesh: Vec<u8>,
+    pub baked_triangle_collision_mesh: Vec<u8>,
+    pub streaming_info: StreamingInfo,
+}
+
+#[wasm_bindgen(js_class = "UnityMesh")]
+impl Mesh {
+    pub fn get_submesh_info(&self) -> SubMeshInfoList {
+        SubMeshInfoList {
+            submeshes: self.submeshes.iter().map(|submesh| submesh.into()).collect(),
+        }
+    }
+
+    pub fn set_vertex_data(&mut self, data: Vec<u8>) {
+        self.vertex_data.data = data;
+    }
+
+    pub fn unpack_vertices(&self) -> Option<Vec<f32>> {
+        match self.mesh_compression {
+            MeshCompression::Off => None,
+            _ => Some(self.compressed_mesh.vertices.clone())
+        }
+    }
+
+    pub fn unpack_normals(&self) -> Option<Vec<f32>> {
+        match self.mesh_compression {
+            MeshCompression::Off => None,
+            _ => Some(self.compressed_mesh.unpack_normals())
+        }
+    }
+
+    pub fn unpack_indices(&self) -> Option<Vec<i32>> {
+        match self.mesh_compression {
+            MeshCompression::Off => None,
+            _ => Some(self.compressed_mesh.triangles.clone())
+        }
+    }
+
+    pub fn get_vertex_data(&self) -> Vec<u8> {
+        self.vertex_data.data.clone()
+    }
+
+    pub fn get_index_data(&self) -> Vec<u8> {
+        self.index_buffer.clone()
+    }
+
+    pub fn get_channel_count(&self) -> usize {
+        self.vertex_data.channels.len()
+    }
+
+    pub fn get_channels(&self) -> Vec<ChannelInfo> {
+        self.vertex_data.channels.clone()
+    }
+
+    pub fn get_streams(&self) -> Vec<VertexStreamInfo> {
+        self.vertex_data.get_streams()
+    }
+
+    // Decodes the PhysX-cooked collision geometry baked into the mesh,
+    // preferring the triangle mesh over the convex hull if both are present
+    pub fn get_baked_collision_mesh(&self) -> Result<Option<CollisionMesh>, String> {
+        if !self.baked_triangle_collision_mesh.is_empty() {
+            let (_, mesh) = physx::CookedTriangleMesh::from_bytes((&self.baked_triangle_collision_mesh, 0))
+                .map_err(|err| format!("failed to parse baked triangle mesh: {:?}", err))?;
+            return Ok(Some(CollisionMesh {
+                vertices: mesh.vertices,
+                indices: mesh.indices,
+                convex: false,
+            }));
+        }
+        if !self.baked_convex_collision_mesh.is_empty() {
+            let (_, mesh) = physx::CookedConvexMesh::from_bytes((&self.baked_convex_collision_mesh, 0))
+                .map_err(|err| format!("failed to parse baked convex mesh: {:?}", err))?;
+            return Ok(Some(CollisionMesh {
+                indices: mesh.hull.triangulate(),
+                vertices: mesh.hull.vertices,
+                convex: true,
+            }));
+        }
+        Ok(None)
+    }
+
+    // Exports the mesh's positions, normals and first UV set as OBJ, with a
+    // group per submesh. material_names optionally names each submesh's
+    // material. Streamed vertex data must have been set first.
+    pub fn export_obj(&self, material_names: Option<Vec<String>>) -> Result<ObjExport, String> {
+        let (positions, normals, uvs, indices) = match self.mesh_compression {
+            MeshCompression::Off => {
+                let positions = self.decode_channel(CHANNEL_POSITION, 3)?
+                    .ok_or("mesh has no positions")?;
+                let normals = self.decode_channel(CHANNEL_NORMAL, 3)?;
+                let uvs = self.decode_channel(CHANNEL_UV0, 2)?;
+                (positions, normals, uvs, self.decode_index_buffer())
+            },
+            _ => {
+                let compressed = &self.compressed_mesh;
+                let vertex_count = compressed.vertices.len() / 3;
+                let normals = Some(compressed.unpack_normals()).filter(|normals| normals.len() == vertex_count * 3);
+                let uvs = compressed.uv.get(..vertex_count * 2).map(|uvs| uvs.to_vec());
+                let indices = compressed.triangles.iter().map(|&index| index as u32).collect();
+                (compressed.vertices.clone(), normals, uvs, indices)
+            },
+        };
+        let groups = self.get_submesh_triangles(&indices, (positions.len() / 3) as u32)?
+            .into_iter()
+            .map(|(i, triangles)| ObjGroup {
+                name: material_names.as_ref()
+                    .and_then(|names| names.get(i).cloned())
+                    .unwrap_or_else(|| format!("{}_submesh{}", self.name, i)),
+                indices: triangles,
+            })
+            .collect::<Vec<_>>();
+        Ok(write_obj(&self.name, &positions, normals.as_deref(), uvs.as_deref(), &groups))
+    }
+}
+
+const CHANNEL_POSITION: usize = 0;
+const CHANNEL_NORMAL: usize = 1;
+const CHANNEL_UV0: usize = 4;
+
+const TOPOLOGY_TRIANGLES: i32 = 0;
+const TOPOLOGY_QUADS: i32 = 2;
+
+// a submesh's index and its triangle list
+type SubMeshTriangles = (usize, Vec<u32>);
+
+impl Mesh {
+    // Reads the first `components` components of a vertex channel as floats,
+    // or None if the mesh doesn't have the channel
+    fn decode_channel(&self, channel_index: usize, components: usize) -> Result<Option<Vec<f32>>, String> {
+        let Some(channel) = self.vertex_data.channels.get(channel_index) else {
+            return Ok(None);
+        };
+        if channel.dimension == 0 {
+            return Ok(None);
+        }
+        let vertex_count = self.vertex_data.vertex_count as usize;
+        let streams = self.vertex_data.get_streams();
+        let stream = &streams[channel.stream as usize];
+        let format_size = channel.get_format_size();
+        let data = &self.vertex_data.data;
+        let mut result = Vec::with_capacity(vertex_count * components);
+        for vertex in 0..vertex_count {
+            let vertex_start = stream.offset as usize + vertex * stream.stride as usize + channel.offset as usize;
+            for component in 0..components {
+                if component >= channel.dimension as usize {
+                    result.push(0.0);
+                    continue;
+                }
+                let start = vertex_start + component * format_size;
+                let bytes = data.get(start..start + format_size)
+                    .ok_or(format!("vertex {} is out of bounds of the vertex data (was it streamed?)", vertex))?;
+                result.push(decode_vertex_component(channel.format, bytes));
+            }
+        }
+        Ok(Some(result))
+    }
+
+    // Returns the vertex positions and each submesh's triangle list,
+    // skipping submeshes made of lines or points
+    pub(crate) fn get_triangles(&self) -> Result<(Vec<f32>, Vec<SubMeshTriangles>), String> {
+        let (positions, indices) = match self.mesh_compression {
+            MeshCompression::Off => {
+                let positions = self.decode_channel(CHANNEL_POSITION, 3)?
+                    .ok_or("mesh has no positions")?;
+                (positions, self.decode_index_buffer())
+            },
+            _ => {
+                let indices = self.compressed_mesh.triangles.iter().map(|&index| index as u32).collect();
+                (self.compressed_mesh.vertices.clone(), indices)
+            },
+        };
+        let triangles = self.get_submesh_triangles(&indices, (positions.len() / 3) as u32)?;
+        Ok((positions, triangles))
+    }
+
+    // Splits the index buffer into triangle lists per submesh, with quads
+    // split in two
+    fn get_submesh_triangles(&self, indices: &[u32], vertex_count: u32) -> Result<Vec<SubMeshTriangles>, String> {
+        let index_size = match self.index_format {
+            IndexFormat::UInt16 => 2,
+            IndexFormat::UInt32 => 4,
+        };
+        let mut result = Vec::with_capacity(self.submeshes.len());
+        for (i, submesh) in self.submeshes.iter().enumerate() {
+            let start = (submesh.first_byte / index_size) as usize;
+            let submesh_indices = indices.get(start..start + submesh.index_count as usize)
+                .ok_or(format!("submesh {} is out of bounds of the index buffer", i))?;
+            let submesh_indices: Vec<u32> = submesh_indices.iter().map(|&index| index + submesh.base_vertex).collect();
+            let triangles = match submesh.topology {
+                TOPOLOGY_TRIANGLES => submesh_indices,
+                TOPOLOGY_QUADS => submesh_indices.chunks_exact(4)
+                    .flat_map(|quad| [quad[0], quad[1], quad[2], quad[0], quad[2], quad[3]])
+                    .collect(),
+                // lines and points have no triangles
+                _ => continue,
+            };
+            if let Some(index) = triangles.iter().find(|&&index| index >= vertex_count) {
+                return Err(format!("submesh {} has index {}, but there are only {} vertices", i, index, vertex_count));
+            }
+            result.push((i, triangles));
+        }
+        Ok(result)
+    }
+
+    fn decode_index_buffer(&self) -> Vec<u32> {
+        match self.index_format {
+            IndexFormat::UInt16 => self.index_buffer.chunks_exact(2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]) as u32)
+                .collect(),
+            IndexFormat::UInt32 => self.index_buffer.chunks_exact(4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        }
+    }
+}
+
+fn decode_vertex_component(format: VertexFormat, bytes: &[u8]) -> f32 {
+    match format {
+        VertexFormat::Float => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        VertexFormat::Float16 => f16_to_f32(u16::from_le_bytes([bytes[0], bytes[1]])),
+        VertexFormat::UNorm8 => bytes[0] as f32 / 255.0,
+        VertexFormat::SNorm8 => (bytes[0] as i8 as f32 / 127.0).max(-1.0),
+        VertexFormat::UNorm16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 65535.0,
+        VertexFormat::SNorm16 => (i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32767.0).max(-1.0),
+        VertexFormat::UInt8 => bytes[0] as f32,
+        VertexFormat::SInt8 => bytes[0] as i8 as f32,
+        VertexFormat::UInt16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+        VertexFormat::SInt16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+        VertexFormat::UInt32 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32,
+        VertexFormat::SInt32 => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32,
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityCollisionMesh", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct CollisionMesh {
+    pub vertices: Vec<f32>,
+    pub indices: Vec<u32>,
+    pub convex: bool,
+}
+
+#[wasm_bindgen(js_name = "UnityIndexFormat")]
+#[derive(FromEnumPerVariant, Debug, Clone, Copy)]
+#[from(binary::IndexFormat)]
+pub enum IndexFormat {
+    UInt16,
+    UInt32,
+}
+
+#[wasm_bindgen(js_name = "UnityMeshCompression")]
+#[derive(FromEnumPerVariant, Debug, Clone, Copy)]
+#[from(binary::MeshCompression)]
+pub enum MeshCompression {
+    Off = 0,
+    Low = 1,
+    Medium = 2,
+    High = 3,
+}
+
+#[wasm_bindgen(js_name = "UnityCompressedMesh", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::CompressedMesh)]
+pub struct CompressedMesh {
+    pub vertices: Vec<f32>,
+    pub uv: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub tangents: Vec<f32>,
+    pub weights: Vec<i32>,
+    pub normal_signs: Vec<i32>,
+    pub tangent_signs: Vec<i32>,
+    pub float_colors: Vec<f32>,
+    pub bone_indices: Vec<i32>,
+    pub triangles: Vec<i32>,
+    pub uv_info: u32,
+}
+
+impl CompressedMesh {
+    pub fn unpack_normals(&self) -> Vec<f32> {
+        let n = self.normals.len() / 2;
+        let mut result = vec![0.0; 3 * n];
+        for i in 0..n {
+            let x = self.normals[2*i];
+            let y = self.normals[2*i + 1];
+            result[3*i + 0] = x;
+            result[3*i + 1] = y;
+            let zsqr = 1.0 - x*x - y*y;
+            if zsqr >= 0.0 {
+                result[3*i + 2] = zsqr.sqrt();
+            } else {
+                result[3*i + 2] = 0.0;
+            }
+
+            if self.normal_signs.len() > 0 && self.normal_signs[i] == 0 {
+                result[3*i + 2] *= -1.0;
+            }
+        }
+        result
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityVertexData", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::VertexData)]
+pub struct VertexData {
+    pub vertex_count: u32,
+    pub channels: Vec<ChannelInfo>,
+    streams: Vec<VertexStreamInfo>,
+    pub data: Vec<u8>,
+}
+
+impl VertexData {
+    // Before 5.0 the stream layout was stored explicitly; since then it's
+    // derived from the channels
+    pub fn get_streams(&self) -> Vec<VertexStreamInfo> {
+        if !self.streams.is_empty() {
+            return self.streams.clone();
+        }
+        VertexStreamInfo::from_channels(&self.channels, self.vertex_count as usize)
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct VertexStreamInfo {
+    pub channel_mask: u32,
+    pub offset: u32,
+    pub stride: u32,
+    pub divider_op: u8,
+    pub frequency: u32,
+}
+
+impl From<binary::StreamInfo> for VertexStreamInfo {
+    fn from(value: binary::StreamInfo) -> Self {
+        Self {
+            channel_mask: value.channel_mask,
+            offset: value.offset,
+            stride: value.stride as u32,
+            divider_op: value.divider_op,
+            frequency: value.frequency as u32,
+        }
+    }
+}
+
+impl VertexStreamInfo {
+    pub fn from_channels(channels: &[ChannelInfo], vertex_count: usize) -> Vec<VertexStreamInfo> {
+        let mut n_streams = 0;
+        for c in channels {
+            if c.stream > n_streams {
+                n_streams = c.stream;
+            }
+        }
+        n_streams += 1;
+        let mut result = Vec::with_capacity(n_streams as usize);
+        let mut offset = 0;
+        for s in 0..n_streams {
+            let mut channel_mask = 0;
+            let mut stride = 0;
+            for chn in 0..channels.len() {
+                let channel = &channels[chn];
+                if channel.stream == s {
+                    if channel.dimension > 0 {
+                        channel_mask |= 1 << chn;
+                        stride += channel.dimension as usize * channel.get_format_size();
+                    }
+                }
+            }
+
+            result.push(VertexStreamInfo {
+                channel_mask,
+                offset,
+                stride: stride as u32,
+                divider_op: 0,
+                frequency: 0,
+            });
+            offset += (vertex_count * stride) as u32;
+            offset = (offset + 0x0F) & !0x0F;
+        }
+        return result;
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityChannelInfo")]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::ChannelInfo)]
+pub struct ChannelInfo {
+    pub stream: u8,
+    pub offset: u8,
+    pub format: VertexFormat,
+    pub dimension: u8,
+}
+
+impl ChannelInfo {
+    pub fn get_format_size(&self) -> usize {
+        match self.format {
+            VertexFormat::Float |
+            VertexFormat::UInt32 |
+            VertexFormat::SInt32 => 4,
+
+            VertexFormat::Float16 |
+            VertexFormat::UNorm16 |
+            VertexFormat::SNorm16 |
             VertexFormat::UInt16 |
             VertexFormat::SInt16 => 2,
 
-            VertexFormat::UNorm8 |
-            VertexFormat::SNorm8 |
-            VertexFormat::UInt8 |
-            VertexFormat::SInt8 => 1,
+            VertexFormat::UNorm8 |
+            VertexFormat::SNorm8 |
+            VertexFormat::UInt8 |
+            VertexFormat::SInt8 => 1,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityVertexFormat")]
+#[derive(FromEnumPerVariant, Debug, Clone, Copy)]
+#[from(binary::VertexFormat)]
+pub enum VertexFormat {
+    Float,
+    Float16,
+    UNorm8,
+    SNorm8,
+    UNorm16,
+    SNorm16,
+    UInt8,
+    SInt8,
+    UInt16,
+    SInt16,
+    UInt32,
+    SInt32,
+}
+
+#[wasm_bindgen(js_name = "UnitySubMesh")]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::SubMesh)]
+pub struct SubMesh {
+    pub first_byte: u32,
+    pub index_count: u32,
+    pub topology: i32,
+    pub base_vertex: u32,
+    pub first_vertex: u32,
+    pub vertex_count: u32,
+    pub local_aabb: AABB,
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+pub struct SubMeshInfo {
+    pub first_byte: u32,
+    pub index_count: u32,
+    pub topology: i32,
+    pub base_vertex: u32,
+    pub first_vertex: u32,
+    pub vertex_count: u32,
+    pub aabb_center: [f32; 3],
+    pub aabb_extent: [f32; 3],
+}
+
+impl From<&SubMesh> for SubMeshInfo {
+    fn from(value: &SubMesh) -> Self {
+        let AABB { center, extent } = value.local_aabb;
+        Self {
+            first_byte: value.first_byte,
+            index_count: value.index_count,
+            topology: value.topology,
+            base_vertex: value.base_vertex,
+            first_vertex: value.first_vertex,
+            vertex_count: value.vertex_count,
+            aabb_center: [center.x, center.y, center.z],
+            aabb_extent: [extent.x, extent.y, extent.z],
+        }
+    }
+}
+
+#[derive(Tsify, Serialize, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct SubMeshInfoList {
+    pub submeshes: Vec<SubMeshInfo>,
+}
+
+#[wasm_bindgen(js_name = "UnityStreamingInfo", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::StreamingInfo)]
+pub struct StreamingInfo {
+    pub offset: u64,
+    pub size: u32,
+    pub path: String,
+}
+
+#[wasm_bindgen(js_name = "UnityTexture2D", getter_with_clone)]
+pub struct Texture2D {
+    pub name: String,
+    pub forced_fallback_format: i32,
+    pub downscale_fallback: u8,
+    pub width: i32,
+    pub height: i32,
+    pub complete_image_size: u32,
+    pub texture_format: TextureFormat,
+    pub mip_count: i32,
+    pub is_readable: u8,
+    pub ignore_master_texture_limit: u8,
+    pub is_preprocessed: u8,
+    pub streaming_mipmaps: u8,
+    pub streaming_mipmaps_priority: i32,
+    pub image_count: i32,
+    pub texture_dimension: i32,
+    pub texture_settings: GLTextureSettings,
+    pub lightmap_format: i32,
+    pub color_space: ColorSpace,
+    pub data: Vec<u8>,
+    pub streaming_info: StreamingInfo,
+}
+
+impl From<binary::Texture2D> for Texture2D {
+    fn from(value: binary::Texture2D) -> Self {
+        let s = value.settings;
+        // before 5.2 textures only said whether they had a full mip chain
+        let mip_count = match &s {
+            binary::TextureBooleanSettings::V4 { mip_map, .. } if *mip_map != 0 => {
+                32 - (value.width.max(value.height).max(1) as u32).leading_zeros() as i32
+            },
+            _ => value.mip_count,
+        };
+        let (is_readable,
+            ignore_master_texture_limit,
+            is_preprocessed,
+            streaming_mipmaps) = match s {
+                binary::TextureBooleanSettings::V4 { is_readable: a, .. } => (a, 0, 0, 0),
+                binary::TextureBooleanSettings::V5 { is_readable: a, .. } => (a, 0, 0, 0),
+                binary::TextureBooleanSettings::V2017 { is_readable: a, .. } => (a, 0, 0, 0),
+                binary::TextureBooleanSettings::V2018 { is_readable: a, streaming_mipmaps: d, .. } => (a, 0, 0, d),
+                binary::TextureBooleanSettings::V2019 { is_readable: a, ignore_master_texture_limit: b, is_preprocessed: c, streaming_mipmaps: d } => (a, b, c, d),
+                binary::TextureBooleanSettings::V2020 { is_readable: a, ignore_master_texture_limit: b, is_preprocessed: c, streaming_mipmaps: d } => (a, b, c, d),
+            };
+        Self {
+            name: value.name.into(),
+            forced_fallback_format: value.forced_fallback_format.into(),
+            downscale_fallback: value.downscale_fallback.into(),
+            width: value.width.into(),
+            height: value.height.into(),
+            complete_image_size: value.complete_image_size.into(),
+            texture_format: value.texture_format.into(),
+            mip_count,
+            is_readable,
+            ignore_master_texture_limit,
+            is_preprocessed,
+            streaming_mipmaps,
+            streaming_mipmaps_priority: value.streaming_mipmaps_priority.into(),
+            image_count: value.image_count.into(),
+            texture_dimension: value.texture_dimension.into(),
+            texture_settings: value.texture_settings.into(),
+            lightmap_format: value.lightmap_format.into(),
+            color_space: value.color_space.into(),
+            data: value.data.into(),
+            streaming_info: value.streaming_info.into(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityTexture2D")]
+impl Texture2D {
+    // data is the texture's streamed data, if it has any
+    pub fn export_dds(&self, data: Option<Vec<u8>>) -> Result<Vec<u8>, String> {
+        let format = self.get_block_format()?;
+        let data = data.as_deref().unwrap_or(&self.data);
+        write_dds(&format, self.width as u32, self.height as u32, self.mip_count.max(1) as u32, data)
+    }
+
+    pub fn export_ktx2(&self, data: Option<Vec<u8>>) -> Result<Vec<u8>, String> {
+        let format = self.get_block_format()?;
+        let data = data.as_deref().unwrap_or(&self.data);
+        let srgb = matches!(self.color_space, ColorSpace::SRGB);
+        write_ktx2(&format, srgb, self.width as u32, self.height as u32, self.mip_count.max(1) as u32, data)
+    }
+}
+
+impl Texture2D {
+    fn get_block_format(&self) -> Result<BlockFormat, String> {
+        if self.image_count > 1 {
+            return Err(format!("can't export texture arrays ({} images)", self.image_count));
+        }
+        get_block_format(self.texture_format, matches!(self.color_space, ColorSpace::SRGB))
+            .ok_or(format!("{:?} has no container equivalent", self.texture_format))
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityGLTextureSettings", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::GLTextureSettings)]
+pub struct GLTextureSettings {
+    pub filter_mode: TextureFilterMode,
+    pub aniso: i32,
+    pub mip_bias: f32,
+    pub wrap_u: TextureWrapMode,
+    pub wrap_v: TextureWrapMode,
+    pub wrap_w: TextureWrapMode,
+}
+
+#[wasm_bindgen(js_name = "UnityTextureFilterMode")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug)]
+#[from(binary::TextureFilterMode)]
+pub enum TextureFilterMode {
+    Nearest = 0,
+    Bilinear = 1,
+    Trilinear = 2,
+}
+
+#[wasm_bindgen(js_name = "UnityTextureWrapMode")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug)]
+#[from(binary::TextureWrapMode)]
+pub enum TextureWrapMode {
+    Repeat = 0,
+    Clamp = 1,
+    Mirror = 2,
+    MirrorOnce = 3,
+}
+
+#[wasm_bindgen(js_name = "UnityTextureFormat")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug)]
+#[from(binary::TextureFormat)]
+pub enum TextureFormat {
+    // Alpha 8 bit texture format.
+    Alpha8 = 1,
+    // RGBA 16 bit texture format.
+    ARGB16 = 2,
+    // RGB 24 bit texture format.
+    RGB24 = 3,
+    // RGBA 32 bit texture format.
+    RGBA32 = 4,
+    // ARGB 32 bit texture format.
+    ARGB32 = 5,
+    // RGB 16 bit texture format.
+    RGB16 = 7,
+    // Red 16 bit texture format.
+    R16 = 9,
+    // DXT1 compressed texture format.
+    DXT1 = 10,
+    // DXT5 compressed texture format.
+    DXT5 = 12,
+    // RGBA 16 bit (4444) texture format.
+    RGBA16 = 13,
+
+    // R 16 bit texture format.
+    RHalf = 15,
+    // RG 32 bit texture format.
+    RGHalf = 16,
+    // RGBA 64 bit texture format.
+    RGBAHalf = 17,
+
+    // R 32 bit texture format.
+    RFloat = 18,
+    // RG 64 bit texture format.
+    RGFloat = 19,
+    // RGBA 128 bit texture format.
+    RGBAFloat = 20,
+
+    // RGB 32 bit packed float format.
+    RGB9E5 = 22,
+
+    // R BC4 compressed texture format.
+    BC4 = 26,
+    // RG BC5 compressed texture format.
+    BC5 = 27,
+    // HDR RGB BC6 compressed texture format.
+    BC6H = 24,
+    // RGBA BC7 compressed texture format.
+    BC7 = 25,
+
+    // DXT1 crunched texture format.
+    DXT1Crunched = 28,
+    // DXT5 crunched texture format.
+    DXT5Crunched = 29,
+    // ETC (GLES2.0) 4 bits/pixel compressed RGB texture format.
+    EtcRGB4 = 34,
+    // EAC 4 bits/pixel compressed 16-bit R texture format
+    EacR = 41,
+    // EAC 4 bits/pixel compressed 16-bit signed R texture format
+    EacRSigned = 42,
+    // EAC 8 bits/pixel compressed 16-bit RG texture format
+    EacRG = 43,
+    // EAC 8 bits/pixel compressed 16-bit signed RG texture format
+    EacRGSigned = 44,
+
+    // ETC2 (GLES3.0) 4 bits/pixel compressed RGB texture format.
+    Etc2RGB4 = 45,
+    // ETC2 (GLES3.0) 4 bits/pixel compressed RGB + 1-bit alpha texture format.
+    Etc2RGB4PunchthroughAlpha = 46,
+    // ETC2 (GLES3.0) 8 bits/pixel compressed RGBA texture format.
+    Etc2RGBA8 = 47,
+
+    // ASTC uses 128bit block of varying sizes (we use only square blocks). It does not distinguish RGB/RGBA
+    Astc4x4 = 48,
+    Astc5x5 = 49,
+    Astc6x6 = 50,
+    Astc8x8 = 51,
+    Astc10x10 = 52,
+    Astc12x12 = 53,
+
+    // RG 16 bit texture format.
+    RG16 = 62,
+    // Red 8 bit texture format.
+    R8 = 63,
+    // ETC1 crunched texture format.
+    EtcRGB4Crunched = 64,
+    // ETC2_RGBA8 crunched texture format.
+    Etc2RGBA8Crunched = 65,
+
+    // ASTC (block size 4x4) compressed HDR RGB(A) texture format.
+    AstcHdr4x4 = 66,
+    // ASTC (block size 5x5) compressed HDR RGB(A)  texture format.
+    AstcHdr5x5 = 67,
+    // ASTC (block size 4x6x6) compressed HDR RGB(A) texture format.
+    AstcHdr6x6 = 68,
+    // ASTC (block size 8x8) compressed HDR RGB(A) texture format.
+    AstcHdr8x8 = 69,
+    // ASTC (block size 10x10) compressed HDR RGB(A) texture format.
+    AstcHdr10x10 = 70,
+    // ASTC (block size 12x12) compressed HDR RGB(A) texture format.
+    AstcHdr12x12 = 71,
+
+    RG32 = 72,
+    RGB48 = 73,
+    RGBA64 = 74,
+    R8Signed = 75,
+    RG16Signed = 76,
+    RGB24Signed = 77,
+    RGBA32Signed = 78,
+    R16Signed = 79,
+    RG32Signed = 80,
+    RGB48Signed = 81,
+    RGBA64Signed = 82,
+}
+
+#[wasm_bindgen(js_name = "UnityTextureColorSpace")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug)]
+#[from(binary::ColorSpace)]
+pub enum ColorSpace {
+    Linear = 0x00,
+    SRGB   = 0x01,
+}
+
+#[wasm_bindgen(js_name = "UnityMeshFilter", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::MeshFilter)]
+pub struct MeshFilter {
+    pub game_object: WasmFriendlyPPtr,
+    pub mesh: WasmFriendlyPPtr,
+}
+
+#[wasm_bindgen(js_name = "UnityMeshRenderer", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::MeshRenderer)]
+pub struct MeshRenderer {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub cast_shadows: u8,
+    pub receive_shadows: u8,
+    pub dynamic_occludee: u8,
+    pub motion_vectors: u8,
+    pub light_probe_usage: u8,
+    pub reflection_probe_usage: u8,
+    pub ray_tracing_mode: u8,
+    pub rendering_layer_mask: u32,
+    pub renderer_priority: i32,
+    pub lightmap_index: u16,
+    pub lightmap_index_dynamic: u16,
+    pub lightmap_tiling_offset: Vec4,
+    pub lightmap_tiling_offset_dynamic: Vec4,
+    pub materials: Vec<WasmFriendlyPPtr>,
+    pub static_batch_info: StaticBatchInfo,
+    pub static_batch_root: WasmFriendlyPPtr,
+    pub probe_anchor: WasmFriendlyPPtr,
+    pub light_probe_volume_override: WasmFriendlyPPtr,
+    pub sorting_layer_id: i32,
+    pub sorting_layer: i16,
+    pub sorting_order: i16,
+    pub additional_vertex_streams: WasmFriendlyPPtr,
+}
+
+#[wasm_bindgen(js_name = "UnityStaticBatchInfo", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::StaticBatchInfo)]
+pub struct StaticBatchInfo {
+    pub first_submesh: u16,
+    pub submesh_count: u16,
+}
+
+#[wasm_bindgen(js_name = "UnityScriptMapper")]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::ScriptMapper)]
+pub struct ScriptMapper {
+    shader_to_name_map: HashMap<WasmFriendlyPPtr, String>,
+}
+
+#[wasm_bindgen(js_class = "UnityScriptMapper")]
+impl ScriptMapper {
+    pub fn get_shader_pointers(&self) -> Vec<WasmFriendlyPPtr> {
+        self.shader_to_name_map.keys().cloned().collect()
+    }
+
+    pub fn get_shader_names(&self) -> Vec<String> {
+        self.shader_to_name_map.values().cloned().collect()
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityAnimator", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct Animator {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub avatar: WasmFriendlyPPtr,
+    pub controller: WasmFriendlyPPtr,
+    pub culling_mode: AnimatorCullingMode,
+    pub update_mode: AnimatorUpdateMode,
+    pub apply_root_motion: u8,
+    pub has_transform_hierarchy: u8,
+    pub keep_animator_controller_state_on_disable: u8,
+}
+
+impl From<binary::Animator> for Animator {
+    fn from(value: binary::Animator) -> Self {
+        Self {
+            game_object: value.game_object.into(),
+            enabled: value.enabled,
+            avatar: value.avatar.into(),
+            controller: value.controller.into(),
+            culling_mode: value.culling_mode.into(),
+            update_mode: value.update_mode.into(),
+            apply_root_motion: value.apply_root_motion,
+            has_transform_hierarchy: value.has_transform_hierarchy,
+            keep_animator_controller_state_on_disable: value.keep_animator_controller_state_on_disable,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityAnimatorCullingMode")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug)]
+#[from(binary::AnimatorCullingMode)]
+pub enum AnimatorCullingMode {
+    AlwaysAnimate = 0,
+    CullUpdateTransforms = 1,
+    CullCompletely = 2,
+}
+
+#[wasm_bindgen(js_name = "UnityAnimatorUpdateMode")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug)]
+#[from(binary::AnimatorUpdateMode)]
+pub enum AnimatorUpdateMode {
+    Normal = 0,
+    AnimatePhysics = 1,
+    UnscaledTime = 2,
+}
+
+#[wasm_bindgen(js_name = "UnityAnimatorController", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct AnimatorController {
+    pub name: String,
+    pub animation_clips: Vec<WasmFriendlyPPtr>,
+    // for each layer, indices into animation_clips played by its default state
+    default_state_clip_indices: Vec<Vec<u32>>,
+}
+
+impl From<binary::AnimatorController> for AnimatorController {
+    fn from(value: binary::AnimatorController) -> Self {
+        let controller = &value.controller;
+        let mut default_state_clip_indices = Vec::with_capacity(controller.layers.values.len());
+        for layer in &controller.layers.values {
+            let mut clip_indices = Vec::new();
+            let state = controller.state_machines.values.get(layer.state_machine_index as usize)
+                .and_then(|state_machine| state_machine.states.values.get(state_machine.default_state as usize));
+            if let Some(state) = state {
+                let blend_tree_index = state.blend_tree_constant_indices.values
+                    .get(layer.state_machine_motion_set_index as usize)
+                    .cloned()
+                    .unwrap_or(-1);
+                if blend_tree_index >= 0 {
+                    if let Some(blend_tree) = state.blend_trees.values.get(blend_tree_index as usize) {
+                        for node in &blend_tree.nodes.values {
+                            if node.clip_id != binary::BlendTreeNodeConstant::NO_CLIP && !clip_indices.contains(&node.clip_id) {
+                                clip_indices.push(node.clip_id);
+                            }
+                        }
+                    }
+                }
+            }
+            default_state_clip_indices.push(clip_indices);
+        }
+
+        Self {
+            name: value.name.into(),
+            animation_clips: value.animation_clips.into(),
+            default_state_clip_indices,
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityAnimatorController")]
+impl AnimatorController {
+    pub fn get_layer_count(&self) -> usize {
+        self.default_state_clip_indices.len()
+    }
+
+    pub fn get_default_state_clips(&self, layer_index: usize) -> Vec<WasmFriendlyPPtr> {
+        let Some(clip_indices) = self.default_state_clip_indices.get(layer_index) else {
+            return Vec::new();
+        };
+        clip_indices.iter()
+            .filter_map(|&i| self.animation_clips.get(i as usize).cloned())
+            .collect()
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityAnimatorOverrideController", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::AnimatorOverrideController)]
+pub struct AnimatorOverrideController {
+    pub name: String,
+    pub controller: WasmFriendlyPPtr,
+    pub clips: Vec<AnimationClipOverride>,
+}
+
+#[wasm_bindgen(js_class = "UnityAnimatorOverrideController")]
+impl AnimatorOverrideController {
+    // Maps a clip from the base controller to the clip that should be played
+    // in its place, if it's been overridden
+    pub fn resolve_clip(&self, clip: &WasmFriendlyPPtr) -> WasmFriendlyPPtr {
+        self.clips.iter()
+            .find(|o| o.original_clip == *clip && o.override_clip.path_id != 0)
+            .map(|o| o.override_clip)
+            .unwrap_or(*clip)
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityAnimationClipOverride")]
+#[derive(Clone, Copy, Debug, FromStructPerField)]
+#[from(binary::AnimationClipOverride)]
+pub struct AnimationClipOverride {
+    pub original_clip: WasmFriendlyPPtr,
+    pub override_clip: WasmFriendlyPPtr,
+}
+
+#[wasm_bindgen(js_name = "UnityAnimationClip", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    pub name: String,
+    pub sample_rate: f32,
+    pub start_time: f32,
+    pub stop_time: f32,
+    pub loop_time: bool,
+    pub bindings: Vec<CurveBinding>,
+    // by curve index, decoded from the muscle clip
+    curves: Vec<AnimationCurve>,
+}
+
+impl From<binary::AnimationClip> for AnimationClip {
+    fn from(value: binary::AnimationClip) -> Self {
+        let muscle_clip = value.muscle_clip;
+        Self {
+            name: value.name.into(),
+            sample_rate: value.sample_rate,
+            start_time: muscle_clip.start_time,
+            stop_time: muscle_clip.stop_time,
+            loop_time: muscle_clip.loop_time != 0,
+            bindings: resolve_bindings(&value.clip_binding_constant.generic_bindings.values),
+            curves: decode_curves(&muscle_clip.clip),
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityAnimationClip")]
+impl AnimationClip {
+    pub fn get_curve_count(&self) -> usize {
+        self.curves.len()
+    }
+
+    pub fn get_curve(&self, index: usize) -> Option<AnimationCurve> {
+        self.curves.get(index).cloned()
+    }
+
+    // Every curve's value at the given time, by curve index
+    pub fn sample_curves(&self, time: f32) -> Vec<f32> {
+        self.curves.iter().map(|curve| curve.evaluate(time)).collect()
+    }
+
+    // For humanoid clips, the avatar's skeleton at the given time
+    pub fn sample_human_pose(&self, avatar: &Avatar, time: f32) -> Result<HumanPose, String> {
+        let values = self.sample_curves(time);
+        let mut body = [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+        let mut muscles = [0.0; MUSCLE_COUNT];
+        for binding in self.bindings.iter().filter(|binding| binding.custom_type == BINDING_CUSTOM_TYPE_MUSCLE) {
+            let Some(&value) = values.get(binding.first_curve as usize) else {
+                continue;
+            };
+            let attribute = binding.attribute;
+            if let Some(i) = attribute.checked_sub(HUMANOID_ROOT_ATTRIBUTE).filter(|&i| i < 7) {
+                body[i as usize] = value;
+            } else if let Some(i) = attribute.checked_sub(HUMANOID_MUSCLE_ATTRIBUTE).filter(|&i| (i as usize) < MUSCLE_COUNT) {
+                muscles[i as usize] = value;
+            }
+        }
+        let position = Vec3 { x: body[0], y: body[1], z: body[2] };
+        let rotation = Quaternion { x: body[3], y: body[4], z: body[5], w: body[6] };
+        avatar.compute_human_pose(&position, &rotation, &muscles)
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityAvatar", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct Avatar {
+    pub name: String,
+    // parent index of each skeleton node, or -1 for the root
+    pub skeleton_parents: Vec<i32>,
+    // hash of each skeleton node's path, which is what SkinnedMeshRenderer's
+    // bone name hashes refer to
+    pub skeleton_name_ids: Vec<u32>,
+    pub default_pose: Vec<XForm>,
+    pub human_bones: Vec<HumanBone>,
+    pub skeleton_bones: Vec<SkeletonBone>,
+    pub root_motion_bone_name: String,
+    tos: HashMap<u32, String>,
+    // per human bone, empty if the avatar isn't humanoid
+    pub(crate) human_rig: Vec<Option<HumanBoneRig>>,
+    pub(crate) human_scale: f32,
+}
+
+impl From<binary::Avatar> for Avatar {
+    fn from(value: binary::Avatar) -> Self {
+        let constant = value.avatar;
+        let human_rig = build_human_rig(&constant);
+        Self {
+            name: value.name.into(),
+            skeleton_parents: constant.avatar_skeleton.nodes.values.iter()
+                .map(|node| node.parent_id)
+                .collect(),
+            skeleton_name_ids: constant.avatar_skeleton.ids.into(),
+            default_pose: constant.default_pose.x.into(),
+            human_bones: value.human_description.human.into(),
+            skeleton_bones: value.human_description.skeleton.into(),
+            root_motion_bone_name: value.human_description.root_motion_bone_name.into(),
+            tos: value.tos.into(),
+            human_rig,
+            human_scale: constant.human.scale,
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityAvatar")]
+impl Avatar {
+    // Returns the transform path (e.g. "Armature/Hips/Spine") for a path hash
+    pub fn get_path_for_hash(&self, hash: u32) -> Option<String> {
+        self.tos.get(&hash).cloned()
+    }
+
+    pub fn get_skeleton_node_paths(&self) -> Vec<String> {
+        self.skeleton_name_ids.iter()
+            .map(|hash| self.tos.get(hash).cloned().unwrap_or_default())
+            .collect()
+    }
+
+    // Maps a mesh's bone name hashes to indices into this avatar's skeleton,
+    // or -1 if the avatar doesn't know about that bone
+    pub fn get_skeleton_indices_for_hashes(&self, hashes: &[u32]) -> Vec<i32> {
+        hashes.iter()
+            .map(|hash| match self.skeleton_name_ids.iter().position(|id| id == hash) {
+                Some(i) => i as i32,
+                None => -1,
+            })
+            .collect()
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityXForm")]
+#[derive(Clone, Copy, Debug, FromStructPerField)]
+#[from(binary::XForm)]
+pub struct XForm {
+    pub t: Vec3,
+    pub q: Quaternion,
+    pub s: Vec3,
+}
+
+#[wasm_bindgen(js_name = "UnityHumanBone", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct HumanBone {
+    pub bone_name: String,
+    pub human_name: String,
+}
+
+impl From<binary::HumanBone> for HumanBone {
+    fn from(value: binary::HumanBone) -> Self {
+        Self {
+            bone_name: value.bone_name.into(),
+            human_name: value.human_name.into(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnitySkeletonBone", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::SkeletonBone)]
+pub struct SkeletonBone {
+    pub name: String,
+    pub parent_name: String,
+    pub position: Vec3,
+    pub rotation: Quaternion,
+    pub scale: Vec3,
+}
+
+#[wasm_bindgen(js_name = "UnityBoxCollider", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::BoxCollider)]
+pub struct BoxCollider {
+    pub game_object: WasmFriendlyPPtr,
+    pub is_trigger: u8,
+    pub enabled: u8,
+    pub size: Vec3,
+    pub center: Vec3,
+}
+
+#[wasm_bindgen(js_name = "UnitySphereCollider", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::SphereCollider)]
+pub struct SphereCollider {
+    pub game_object: WasmFriendlyPPtr,
+    pub is_trigger: u8,
+    pub enabled: u8,
+    pub radius: f32,
+    pub center: Vec3,
+}
+
+#[wasm_bindgen(js_name = "UnityCapsuleCollider", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::CapsuleCollider)]
+pub struct CapsuleCollider {
+    pub game_object: WasmFriendlyPPtr,
+    pub is_trigger: u8,
+    pub enabled: u8,
+    pub radius: f32,
+    pub height: f32,
+    pub direction: CapsuleDirection,
+    pub center: Vec3,
+}
+
+#[wasm_bindgen(js_name = "UnityCapsuleDirection")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug)]
+#[from(binary::CapsuleDirection)]
+pub enum CapsuleDirection {
+    X = 0,
+    Y = 1,
+    Z = 2,
+}
+
+#[wasm_bindgen(js_name = "UnityBoxCollider2D", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct BoxCollider2D {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub is_trigger: u8,
+    pub offset: Vec2,
+    pub size: Vec2,
+    pub edge_radius: f32,
+}
+
+impl From<binary::BoxCollider2D> for BoxCollider2D {
+    fn from(value: binary::BoxCollider2D) -> Self {
+        Self {
+            game_object: value.collider.game_object.into(),
+            enabled: value.collider.enabled,
+            is_trigger: value.collider.is_trigger,
+            offset: value.collider.offset,
+            size: value.size,
+            edge_radius: value.edge_radius,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityCircleCollider2D", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct CircleCollider2D {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub is_trigger: u8,
+    pub offset: Vec2,
+    pub radius: f32,
+}
+
+impl From<binary::CircleCollider2D> for CircleCollider2D {
+    fn from(value: binary::CircleCollider2D) -> Self {
+        Self {
+            game_object: value.collider.game_object.into(),
+            enabled: value.collider.enabled,
+            is_trigger: value.collider.is_trigger,
+            offset: value.collider.offset,
+            radius: value.radius,
+        }
+    }
+}
+
+// Paths are flattened into one list of (x, y) pairs, with path_lengths
+// saying how many points belong to each
+#[wasm_bindgen(js_name = "UnityPolygonCollider2D", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct PolygonCollider2D {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub is_trigger: u8,
+    pub offset: Vec2,
+    pub points: Vec<f32>,
+    pub path_lengths: Vec<u32>,
+}
+
+impl From<binary::PolygonCollider2D> for PolygonCollider2D {
+    fn from(value: binary::PolygonCollider2D) -> Self {
+        let mut points = Vec::new();
+        let mut path_lengths = Vec::new();
+        for path in &value.paths.values {
+            path_lengths.push(path.values.len() as u32);
+            points.extend(path.values.iter().flat_map(|p| [p.x, p.y]));
+        }
+        Self {
+            game_object: value.collider.game_object.into(),
+            enabled: value.collider.enabled,
+            is_trigger: value.collider.is_trigger,
+            offset: value.collider.offset,
+            points,
+            path_lengths,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityEdgeCollider2D", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct EdgeCollider2D {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub is_trigger: u8,
+    pub offset: Vec2,
+    pub edge_radius: f32,
+    pub points: Vec<Vec2>,
+}
+
+impl From<binary::EdgeCollider2D> for EdgeCollider2D {
+    fn from(value: binary::EdgeCollider2D) -> Self {
+        Self {
+            game_object: value.collider.game_object.into(),
+            enabled: value.collider.enabled,
+            is_trigger: value.collider.is_trigger,
+            offset: value.collider.offset,
+            edge_radius: value.edge_radius,
+            points: value.points.into(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityMeshCollider", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::MeshCollider)]
+pub struct MeshCollider {
+    pub game_object: WasmFriendlyPPtr,
+    pub is_trigger: u8,
+    pub enabled: u8,
+    pub convex: u8,
+    pub mesh: WasmFriendlyPPtr,
+}
+
+#[wasm_bindgen(js_name = "UnityNavMeshData", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct NavMeshData {
+    pub name: String,
+    pub source_bounds: AABB,
+    pub rotation: Quaternion,
+    pub position: Vec3,
+    pub agent_type_id: i32,
+    tiles: Vec<Vec<u8>>,
+}
+
+impl From<binary::NavMeshData> for NavMeshData {
+    fn from(value: binary::NavMeshData) -> Self {
+        Self {
+            name: value.name.into(),
+            source_bounds: value.source_bounds,
+            rotation: value.rotation,
+            position: value.position,
+            agent_type_id: value.agent_type_id,
+            tiles: value.tiles.values.into_iter()
+                .map(|tile| tile.mesh_data.data)
+                .collect(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityNavMeshData")]
+impl NavMeshData {
+    pub fn get_tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    // Merges every tile's polygons into a single triangle list. Vertices are
+    // in the navmesh's local space, i.e. before applying position/rotation.
+    pub fn get_geometry(&self) -> Result<NavMeshGeometry, String> {
+        let mut result = NavMeshGeometry {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            areas: Vec::new(),
+        };
+        for (i, data) in self.tiles.iter().enumerate() {
+            if data.is_empty() {
+                continue;
+            }
+            let (_, tile) = navmesh::NavMeshTile::from_bytes((data, 0))
+                .map_err(|err| format!("failed to parse navmesh tile {}: {:?}", i, err))?;
+            let base_vertex = (result.vertices.len() / 3) as u32;
+            let (indices, areas) = tile.triangulate();
+            result.indices.extend(indices.iter().map(|index| index + base_vertex));
+            result.areas.extend(areas);
+            result.vertices.extend(tile.vertices);
+        }
+        Ok(result)
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityNavMeshGeometry", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct NavMeshGeometry {
+    pub vertices: Vec<f32>,
+    pub indices: Vec<u32>,
+    // the navmesh area type of each triangle
+    pub areas: Vec<u8>,
+}
+
+#[wasm_bindgen(js_name = "UnityOcclusionCullingSettings", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct OcclusionCullingSettings {
+    // Umbra tome data, empty if this scene's PVS lives in occlusion_culling_data
+    pub pvs_data: Vec<u8>,
+    // renderers and portals referenced by index from the PVS data
+    pub static_renderers: Vec<WasmFriendlyPPtr>,
+    pub portals: Vec<WasmFriendlyPPtr>,
+    pub scene_guid: String,
+    pub occlusion_culling_data: WasmFriendlyPPtr,
+}
+
+impl From<binary::OcclusionCullingSettings> for OcclusionCullingSettings {
+    fn from(value: binary::OcclusionCullingSettings) -> Self {
+        Self {
+            pvs_data: value.pvs_data.into(),
+            static_renderers: value.static_renderers.into(),
+            portals: value.portals.into(),
+            scene_guid: value.scene_guid.to_hex_string(),
+            occlusion_culling_data: value.occlusion_culling_data.into(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityOcclusionCullingData", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct OcclusionCullingData {
+    pub name: String,
+    pub pvs_data: Vec<u8>,
+    pub scenes: Vec<OcclusionScene>,
+}
+
+impl From<binary::OcclusionCullingData> for OcclusionCullingData {
+    fn from(value: binary::OcclusionCullingData) -> Self {
+        Self {
+            name: value.name.into(),
+            pvs_data: value.pvs_data.into(),
+            scenes: value.scenes.into(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityOcclusionCullingData")]
+impl OcclusionCullingData {
+    // Finds where a scene's renderers and portals start in the shared PVS data
+    pub fn find_scene(&self, scene_guid: &str) -> Option<OcclusionScene> {
+        self.scenes.iter()
+            .find(|scene| scene.scene == scene_guid)
+            .cloned()
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityOcclusionScene", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct OcclusionScene {
+    pub index_renderers: i32,
+    pub size_renderers: i32,
+    pub index_portals: i32,
+    pub size_portals: i32,
+    pub scene: String,
+}
+
+impl From<binary::OcclusionScene> for OcclusionScene {
+    fn from(value: binary::OcclusionScene) -> Self {
+        Self {
+            index_renderers: value.index_renderers,
+            size_renderers: value.size_renderers,
+            index_portals: value.index_portals,
+            size_portals: value.size_portals,
+            scene: value.scene.to_hex_string(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityResourceManager")]
+#[derive(Clone, Debug)]
+pub struct ResourceManager {
+    // Not a HashMap, since a single path can refer to several objects (e.g. a
+    // Texture2D and the Sprite made from it)
+    paths: Vec<String>,
+    objects: Vec<WasmFriendlyPPtr>,
+}
+
+impl From<binary::ResourceManager> for ResourceManager {
+    fn from(value: binary::ResourceManager) -> Self {
+        Self {
+            paths: value.container.keys.into_iter().map(|path| path.into()).collect(),
+            objects: value.container.values.into_iter().map(|pptr| pptr.into()).collect(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityResourceManager")]
+impl ResourceManager {
+    pub fn get_paths(&self) -> Vec<String> {
+        self.paths.clone()
+    }
+
+    pub fn get_objects(&self) -> Vec<WasmFriendlyPPtr> {
+        self.objects.clone()
+    }
+
+    // Looks up the objects loadable via Resources.Load(path). Like Unity, the
+    // comparison is case insensitive, and paths have no file extension.
+    pub fn find_objects(&self, path: &str) -> Vec<WasmFriendlyPPtr> {
+        let path = path.to_lowercase();
+        self.paths.iter()
+            .zip(self.objects.iter())
+            .filter(|(p, _)| p.to_lowercase() == path)
+            .map(|(_, pptr)| *pptr)
+            .collect()
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityBuildSettings", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::BuildSettings)]
+pub struct BuildSettings {
+    pub scenes: Vec<String>,
+}
+
+#[wasm_bindgen(js_name = "UnityPreloadData", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::PreloadData)]
+pub struct PreloadData {
+    pub name: String,
+    pub assets: Vec<WasmFriendlyPPtr>,
+    pub dependencies: Vec<String>,
+}
+
+#[wasm_bindgen(js_name = "UnityAssetBundle")]
+#[derive(Clone, Debug)]
+pub struct AssetBundle {
+    name: String,
+    preload_table: Vec<WasmFriendlyPPtr>,
+    paths: Vec<String>,
+    asset_infos: Vec<binary::AssetBundleAssetInfo>,
+}
+
+impl From<binary::AssetBundle> for AssetBundle {
+    fn from(value: binary::AssetBundle) -> Self {
+        Self {
+            name: value.name.into(),
+            preload_table: value.preload_table.values.into_iter().map(|pptr| pptr.into()).collect(),
+            paths: value.container.keys.into_iter().map(|path| path.into()).collect(),
+            asset_infos: value.container.values,
         }
     }
 }
 
-#[wasm_bindgen(js_name = "UnityVertexFormat")]
-#[derive(FromEnumPerVariant, Debug, Clone, Copy)]
-#[from(binary::VertexFormat)]
-pub enum VertexFormat {
-    Float,
-    Float16,
-    UNorm8,
-    SNorm8,
-    UNorm16,
-    SNorm16,
-    UInt8,
-    SInt8,
-    UInt16,
-    SInt16,
-    UInt32,
-    SInt32,
+#[wasm_bindgen(js_class = "UnityAssetBundle")]
+impl AssetBundle {
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn get_paths(&self) -> Vec<String> {
+        self.paths.clone()
+    }
+
+    pub fn get_preload_table(&self) -> Vec<WasmFriendlyPPtr> {
+        self.preload_table.clone()
+    }
+
+    // The asset at this container path, along with everything that needs
+    // loading before it, per the preload table. Paths are lowercase.
+    pub fn get_preload_objects(&self, path: &str) -> Vec<WasmFriendlyPPtr> {
+        let mut result = Vec::new();
+        for (p, info) in self.paths.iter().zip(self.asset_infos.iter()) {
+            if p != path {
+                continue;
+            }
+            let start = info.preload_index.max(0) as usize;
+            let end = start + info.preload_size.max(0) as usize;
+            if let Some(preloads) = self.preload_table.get(start..end) {
+                result.extend_from_slice(preloads);
+            }
+            result.push(info.asset.into());
+        }
+        result
+    }
 }
 
-#[wasm_bindgen(js_name = "UnitySubMesh")]
+#[wasm_bindgen(js_name = "UnityCanvas")]
 #[derive(Clone, Debug, FromStructPerField)]
-#[from(binary::SubMesh)]
-pub struct SubMesh {
-    pub first_byte: u32,
-    pub index_count: u32,
-    pub topology: i32,
-    pub base_vertex: u32,
-    pub first_vertex: u32,
-    pub vertex_count: u32,
-    pub local_aabb: AABB,
+#[from(binary::Canvas)]
+pub struct Canvas {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub render_mode: CanvasRenderMode,
+    pub camera: WasmFriendlyPPtr,
+    pub plane_distance: f32,
+    pub pixel_perfect: u8,
+    pub override_sorting: u8,
+    pub sorting_layer_id: i32,
+    pub sorting_order: i16,
+    pub target_display: i8,
 }
 
-#[wasm_bindgen(js_name = "UnityStreamingInfo", getter_with_clone)]
+#[wasm_bindgen(js_name = "UnityCanvasRenderMode")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug)]
+#[from(binary::CanvasRenderMode)]
+pub enum CanvasRenderMode {
+    ScreenSpaceOverlay = 0,
+    ScreenSpaceCamera = 1,
+    WorldSpace = 2,
+}
+
+#[wasm_bindgen(js_name = "UnityCanvasRenderer")]
 #[derive(Clone, Debug, FromStructPerField)]
-#[from(binary::StreamingInfo)]
-pub struct StreamingInfo {
-    pub offset: u64,
-    pub size: u32,
-    pub path: String,
+#[from(binary::CanvasRenderer)]
+pub struct CanvasRenderer {
+    pub game_object: WasmFriendlyPPtr,
+    pub cull_transparent_mesh: u8,
 }
 
-#[wasm_bindgen(js_name = "UnityTexture2D", getter_with_clone)]
-pub struct Texture2D {
+#[wasm_bindgen(js_name = "UnityMonoScript", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct MonoScript {
     pub name: String,
-    pub forced_fallback_format: i32,
-    pub downscale_fallback: u8,
-    pub width: i32,
-    pub height: i32,
-    pub complete_image_size: u32,
-    pub texture_format: TextureFormat,
-    pub mip_count: i32,
-    pub is_readable: u8,
-    pub ignore_master_texture_limit: u8,
-    pub is_preprocessed: u8,
-    pub streaming_mipmaps: u8,
-    pub streaming_mipmaps_priority: i32,
-    pub image_count: i32,
-    pub texture_dimension: i32,
-    pub texture_settings: GLTextureSettings,
-    pub lightmap_format: i32,
-    pub color_space: ColorSpace,
-    pub data: Vec<u8>,
-    pub streaming_info: StreamingInfo,
+    pub class_name: String,
+    pub namespace: String,
+    pub assembly_name: String,
 }
 
-impl From<binary::Texture2D> for Texture2D {
-    fn from(value: binary::Texture2D) -> Self {
-        let s = value.settings;
-        let (is_readable,
-            ignore_master_texture_limit,
-            is_preprocessed,
-            streaming_mipmaps) = match s {
-                binary::TextureBooleanSettings::V2019 { is_readable: a, ignore_master_texture_limit: b, is_preprocessed: c, streaming_mipmaps: d } => (a, b, c, d),
-                binary::TextureBooleanSettings::V2020 { is_readable: a, ignore_master_texture_limit: b, is_preprocessed: c, streaming_mipmaps: d } => (a, b, c, d),
-            };
+impl From<binary::MonoScript> for MonoScript {
+    fn from(value: binary::MonoScript) -> Self {
         Self {
             name: value.name.into(),
-            forced_fallback_format: value.forced_fallback_format.into(),
-            downscale_fallback: value.downscale_fallback.into(),
-            width: value.width.into(),
-            height: value.height.into(),
-            complete_image_size: value.complete_image_size.into(),
-            texture_format: value.texture_format.into(),
-            mip_count: value.mip_count.into(),
-            is_readable,
-            ignore_master_texture_limit,
-            is_preprocessed,
-            streaming_mipmaps,
-            streaming_mipmaps_priority: value.streaming_mipmaps_priority.into(),
-            image_count: value.image_count.into(),
-            texture_dimension: value.texture_dimension.into(),
-            texture_settings: value.texture_settings.into(),
-            lightmap_format: value.lightmap_format.into(),
-            color_space: value.color_space.into(),
-            data: value.data.into(),
-            streaming_info: value.streaming_info.into(),
+            class_name: value.class_name.into(),
+            namespace: value.namespace.into(),
+            assembly_name: value.assembly_name.into(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityMonoScript")]
+impl MonoScript {
+    pub fn get_full_name(&self) -> String {
+        if self.namespace.is_empty() {
+            self.class_name.clone()
+        } else {
+            format!("{}.{}", self.namespace, self.class_name)
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityMonoBehavior", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::MonoBehavior)]
+pub struct MonoBehavior {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub script: WasmFriendlyPPtr,
+    pub name: String,
+}
+
+#[wasm_bindgen(js_name = "UnityImage")]
+#[derive(Clone, Debug)]
+pub struct Image {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub material: WasmFriendlyPPtr,
+    pub color: ColorRGBA,
+    pub sprite: WasmFriendlyPPtr,
+    pub image_type: ImageType,
+    pub preserve_aspect: u8,
+    pub fill_center: u8,
+    pub fill_method: i32,
+    pub fill_amount: f32,
+    pub fill_clockwise: u8,
+    pub fill_origin: i32,
+    pub pixels_per_unit_multiplier: f32,
+}
+
+impl From<binary::Image> for Image {
+    fn from(value: binary::Image) -> Self {
+        Self {
+            game_object: value.behavior.game_object.into(),
+            enabled: value.behavior.enabled,
+            material: value.graphic.material.into(),
+            color: value.graphic.color,
+            sprite: value.sprite.into(),
+            image_type: value.image_type.into(),
+            preserve_aspect: value.preserve_aspect,
+            fill_center: value.fill_center,
+            fill_method: value.fill_method,
+            fill_amount: value.fill_amount,
+            fill_clockwise: value.fill_clockwise,
+            fill_origin: value.fill_origin,
+            pixels_per_unit_multiplier: value.pixels_per_unit_multiplier,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityImageType")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug)]
+#[from(binary::ImageType)]
+pub enum ImageType {
+    Simple = 0,
+    Sliced = 1,
+    Tiled = 2,
+    Filled = 3,
+}
+
+#[wasm_bindgen(js_name = "UnityRawImage")]
+#[derive(Clone, Debug)]
+pub struct RawImage {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub material: WasmFriendlyPPtr,
+    pub color: ColorRGBA,
+    pub texture: WasmFriendlyPPtr,
+    pub uv_rect: Rect,
+}
+
+impl From<binary::RawImage> for RawImage {
+    fn from(value: binary::RawImage) -> Self {
+        Self {
+            game_object: value.behavior.game_object.into(),
+            enabled: value.behavior.enabled,
+            material: value.graphic.material.into(),
+            color: value.graphic.color,
+            texture: value.texture.into(),
+            uv_rect: value.uv_rect,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnitySkinnedMeshRenderer", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct SkinnedMeshRenderer {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub cast_shadows: u8,
+    pub receive_shadows: u8,
+    pub lightmap_index: u16,
+    pub lightmap_tiling_offset: Vec4,
+    pub materials: Vec<WasmFriendlyPPtr>,
+    pub mesh: WasmFriendlyPPtr,
+    pub bones: Vec<WasmFriendlyPPtr>,
+    pub blend_shape_weights: Vec<f32>,
+    pub root_bone: WasmFriendlyPPtr,
+    pub aabb: AABB,
+    pub update_when_offscreen: u8,
+}
+
+impl From<binary::SkinnedMeshRenderer> for SkinnedMeshRenderer {
+    fn from(value: binary::SkinnedMeshRenderer) -> Self {
+        Self {
+            game_object: value.renderer.game_object.into(),
+            enabled: value.renderer.enabled,
+            cast_shadows: value.renderer.cast_shadows,
+            receive_shadows: value.renderer.receive_shadows,
+            lightmap_index: value.renderer.lightmap_index,
+            lightmap_tiling_offset: value.renderer.lightmap_tiling_offset,
+            materials: value.renderer.materials.into(),
+            mesh: value.mesh.into(),
+            bones: value.bones.into(),
+            blend_shape_weights: value.blend_shape_weights.into(),
+            root_bone: value.root_bone.into(),
+            aabb: value.aabb,
+            update_when_offscreen: value.update_when_offscreen,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnitySpriteRenderer", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct SpriteRenderer {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub materials: Vec<WasmFriendlyPPtr>,
+    pub sorting_layer_id: i32,
+    pub sorting_layer: i16,
+    pub sorting_order: i16,
+    pub sprite: WasmFriendlyPPtr,
+    pub color: ColorRGBA,
+    pub flip_x: u8,
+    pub flip_y: u8,
+    pub draw_mode: SpriteDrawMode,
+    pub size: Vec2,
+    pub mask_interaction: i32,
+}
+
+impl From<binary::SpriteRenderer> for SpriteRenderer {
+    fn from(value: binary::SpriteRenderer) -> Self {
+        Self {
+            game_object: value.renderer.game_object.into(),
+            enabled: value.renderer.enabled,
+            materials: value.renderer.materials.into(),
+            sorting_layer_id: value.renderer.sorting_layer_id,
+            sorting_layer: value.renderer.sorting_layer,
+            sorting_order: value.renderer.sorting_order,
+            sprite: value.sprite.into(),
+            color: value.color,
+            flip_x: value.flip_x,
+            flip_y: value.flip_y,
+            draw_mode: value.draw_mode.into(),
+            size: value.size,
+            mask_interaction: value.mask_interaction,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnitySpriteDrawMode")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug)]
+#[from(binary::SpriteDrawMode)]
+pub enum SpriteDrawMode {
+    Simple = 0,
+    Sliced = 1,
+    Tiled = 2,
+}
+
+#[wasm_bindgen(js_name = "UnitySprite", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct Sprite {
+    pub name: String,
+    pub rect: Rect,
+    pub pivot: Vec2,
+    pub border: Vec4,
+    pub pixels_to_units: f32,
+    pub texture: WasmFriendlyPPtr,
+    pub alpha_texture: WasmFriendlyPPtr,
+    // where the sprite ended up in its texture, which differs from rect if
+    // it's been packed into an atlas
+    pub texture_rect: Rect,
+    pub texture_rect_offset: Vec2,
+    pub submeshes: Vec<SubMesh>,
+    pub index_buffer: Vec<u8>,
+    pub vertex_data: VertexData,
+    // set if the sprite was packed into a SpriteAtlas, in which case its
+    // texture is only known once resolved against that atlas
+    pub sprite_atlas: WasmFriendlyPPtr,
+    render_data_key: (Guid, i64),
+}
+
+impl From<binary::Sprite> for Sprite {
+    fn from(value: binary::Sprite) -> Self {
+        let render_data = value.render_data;
+        Self {
+            name: value.name.into(),
+            rect: value.rect,
+            pivot: value.pivot,
+            border: value.border,
+            pixels_to_units: value.pixels_to_units,
+            texture: render_data.texture.into(),
+            alpha_texture: render_data.alpha_texture.into(),
+            texture_rect: render_data.texture_rect,
+            texture_rect_offset: render_data.texture_rect_offset,
+            submeshes: render_data.submeshes.into(),
+            index_buffer: render_data.index_buffer.into(),
+            vertex_data: render_data.vertex_data.into(),
+            sprite_atlas: value.sprite_atlas.into(),
+            render_data_key: (value.render_data_key_guid, value.render_data_key_id),
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnitySprite")]
+impl Sprite {
+    pub fn get_streams(&self) -> Vec<VertexStreamInfo> {
+        self.vertex_data.get_streams()
+    }
+
+    pub fn is_packed(&self) -> bool {
+        self.sprite_atlas.path_id != 0
+    }
+}
+
+#[wasm_bindgen(js_name = "UnitySpriteAtlas", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct SpriteAtlas {
+    pub name: String,
+    pub packed_sprites: Vec<WasmFriendlyPPtr>,
+    pub packed_sprite_names: Vec<String>,
+    render_data: Vec<((Guid, i64), SpriteAtlasData)>,
+}
+
+#[derive(Clone, Debug)]
+struct SpriteAtlasData {
+    texture: WasmFriendlyPPtr,
+    alpha_texture: WasmFriendlyPPtr,
+    texture_rect: Rect,
+    texture_rect_offset: Vec2,
+}
+
+impl From<binary::SpriteAtlas> for SpriteAtlas {
+    fn from(value: binary::SpriteAtlas) -> Self {
+        let render_data_map = value.render_data_map;
+        let render_data = render_data_map.keys.into_iter()
+            .zip(render_data_map.values)
+            .map(|(key, data)| ((key.guid, key.id), SpriteAtlasData {
+                texture: data.texture.into(),
+                alpha_texture: data.alpha_texture.into(),
+                texture_rect: data.texture_rect,
+                texture_rect_offset: data.texture_rect_offset,
+            }))
+            .collect();
+        Self {
+            name: value.name.into(),
+            packed_sprites: value.packed_sprites.into(),
+            packed_sprite_names: value.packed_sprite_names_to_index.into(),
+            render_data,
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnitySpriteAtlas")]
+impl SpriteAtlas {
+    // Points a packed sprite at where it ended up in this atlas. Returns
+    // false if the atlas doesn't have it.
+    pub fn resolve_sprite(&self, sprite: &mut Sprite) -> bool {
+        let Some((_, data)) = self.render_data.iter().find(|(key, _)| *key == sprite.render_data_key) else {
+            return false;
+        };
+        sprite.texture = data.texture;
+        sprite.alpha_texture = data.alpha_texture;
+        sprite.texture_rect = data.texture_rect;
+        sprite.texture_rect_offset = data.texture_rect_offset;
+        true
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityTk2dSprite", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct Tk2dSprite {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub collection: WasmFriendlyPPtr,
+    pub color: ColorRGBA,
+    pub scale: Vec3,
+    pub sprite_id: i32,
+}
+
+impl From<binary::Tk2dSprite> for Tk2dSprite {
+    fn from(value: binary::Tk2dSprite) -> Self {
+        Self {
+            game_object: value.behavior.game_object.into(),
+            enabled: value.behavior.enabled,
+            collection: value.collection.into(),
+            color: value.color,
+            scale: value.scale,
+            sprite_id: value.sprite_id,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityTk2dSpriteCollectionData", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct Tk2dSpriteCollectionData {
+    pub sprite_definitions: Vec<Tk2dSpriteDefinition>,
+}
+
+impl From<binary::Tk2dSpriteCollectionData> for Tk2dSpriteCollectionData {
+    fn from(value: binary::Tk2dSpriteCollectionData) -> Self {
+        Self {
+            sprite_definitions: value.sprite_definitions.into(),
+        }
+    }
+}
+
+#[wasm_bindgen(js_class = "UnityTk2dSpriteCollectionData")]
+impl Tk2dSpriteCollectionData {
+    pub fn get_definition(&self, sprite_id: i32) -> Option<Tk2dSpriteDefinition> {
+        if sprite_id < 0 {
+            return None;
+        }
+        self.sprite_definitions.get(sprite_id as usize).cloned()
+    }
+}
+
+// Positions and UVs are flattened to 3 and 2 floats per vertex
+#[wasm_bindgen(js_name = "UnityTk2dSpriteDefinition", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct Tk2dSpriteDefinition {
+    pub name: String,
+    pub positions: Vec<f32>,
+    pub uvs: Vec<f32>,
+    pub indices: Vec<u32>,
+    pub material: WasmFriendlyPPtr,
+    pub material_id: i32,
+}
+
+impl From<binary::Tk2dSpriteDefinition> for Tk2dSpriteDefinition {
+    fn from(value: binary::Tk2dSpriteDefinition) -> Self {
+        Self {
+            name: value.name.into(),
+            positions: value.positions.values.iter().flat_map(|p| [p.x, p.y, p.z]).collect(),
+            uvs: value.uvs.values.iter().flat_map(|uv| [uv.x, uv.y]).collect(),
+            indices: value.indices.values.iter().map(|&i| i as u32).collect(),
+            material: value.material.into(),
+            material_id: value.material_id,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityLineRenderer", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct LineRenderer {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub materials: Vec<WasmFriendlyPPtr>,
+    pub positions: Vec<Vec3>,
+    pub parameters: LineParameters,
+    pub use_world_space: u8,
+    pub looping: u8,
+}
+
+impl From<binary::LineRenderer> for LineRenderer {
+    fn from(value: binary::LineRenderer) -> Self {
+        Self {
+            game_object: value.renderer.game_object.into(),
+            enabled: value.renderer.enabled,
+            materials: value.renderer.materials.into(),
+            positions: value.positions.into(),
+            parameters: value.parameters.into(),
+            use_world_space: value.use_world_space,
+            looping: value.looping,
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityTrailRenderer", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct TrailRenderer {
+    pub game_object: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub materials: Vec<WasmFriendlyPPtr>,
+    pub time: f32,
+    pub parameters: LineParameters,
+    pub min_vertex_distance: f32,
+    pub emitting: u8,
+}
+
+impl From<binary::TrailRenderer> for TrailRenderer {
+    fn from(value: binary::TrailRenderer) -> Self {
+        Self {
+            game_object: value.renderer.game_object.into(),
+            enabled: value.renderer.enabled,
+            materials: value.renderer.materials.into(),
+            time: value.time,
+            parameters: value.parameters.into(),
+            min_vertex_distance: value.min_vertex_distance,
+            emitting: value.emitting,
         }
     }
 }
 
-#[wasm_bindgen(js_name = "UnityGLTextureSettings", getter_with_clone)]
-#[derive(Clone, Debug, FromStructPerField)]
-#[from(binary::GLTextureSettings)]
-pub struct GLTextureSettings {
-    pub filter_mode: TextureFilterMode,
-    pub aniso: i32,
-    pub mip_bias: f32,
-    pub wrap_u: TextureWrapMode,
-    pub wrap_v: TextureWrapMode,
-    pub wrap_w: TextureWrapMode,
+#[wasm_bindgen(js_name = "UnityLineParameters", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct LineParameters {
+    pub width_multiplier: f32,
+    pub width_curve: AnimationCurve,
+    pub color_gradient: Gradient,
+    pub num_corner_vertices: i32,
+    pub num_cap_vertices: i32,
+    pub alignment: LineAlignment,
+    pub texture_mode: LineTextureMode,
 }
 
-#[wasm_bindgen(js_name = "UnityTextureFilterMode")]
-#[derive(FromEnumPerVariant, Clone, Copy, Debug)]
-#[from(binary::TextureFilterMode)]
-pub enum TextureFilterMode {
-    Nearest = 0,
-    Bilinear = 1,
-    Trilinear = 2,
+impl From<binary::LineParameters> for LineParameters {
+    fn from(value: binary::LineParameters) -> Self {
+        Self {
+            width_multiplier: value.width_multiplier,
+            width_curve: value.width_curve.into(),
+            color_gradient: value.color_gradient.into(),
+            num_corner_vertices: value.num_corner_vertices,
+            num_cap_vertices: value.num_cap_vertices,
+            alignment: value.alignment.into(),
+            texture_mode: value.texture_mode.into(),
+        }
+    }
 }
 
-#[wasm_bindgen(js_name = "UnityTextureWrapMode")]
+#[wasm_bindgen(js_class = "UnityLineParameters")]
+impl LineParameters {
+    // Width of the line at t, which goes from 0 at the start of the line to 1
+    // at its end
+    pub fn evaluate_width(&self, t: f32) -> f32 {
+        self.width_multiplier * self.width_curve.evaluate(t)
+    }
+
+    pub fn evaluate_color(&self, t: f32) -> ColorRGBA {
+        self.color_gradient.evaluate(t)
+    }
+}
+
+#[wasm_bindgen(js_name = "UnityLineAlignment")]
 #[derive(FromEnumPerVariant, Clone, Copy, Debug)]
-#[from(binary::TextureWrapMode)]
-pub enum TextureWrapMode {
-    Repeat = 0,
-    Clamp = 1,
-    Mirror = 2,
-    MirrorOnce = 3,
+#[from(binary::LineAlignment)]
+pub enum LineAlignment {
+    View = 0,
+    TransformZ = 1,
 }
 
-#[wasm_bindgen(js_name = "UnityTextureFormat")]
+#[wasm_bindgen(js_name = "UnityLineTextureMode")]
 #[derive(FromEnumPerVariant, Clone, Copy, Debug)]
-#[from(binary::TextureFormat)]
-pub enum TextureFormat {
-    // Alpha 8 bit texture format.
-    Alpha8 = 1,
-    // RGBA 16 bit texture format.
-    ARGB16 = 2,
-    // RGB 24 bit texture format.
-    RGB24 = 3,
-    // RGBA 32 bit texture format.
-    RGBA32 = 4,
-    // ARGB 32 bit texture format.
-    ARGB32 = 5,
-    // RGB 16 bit texture format.
-    RGB16 = 7,
-    // Red 16 bit texture format.
-    R16 = 9,
-    // DXT1 compressed texture format.
-    DXT1 = 10,
-    // DXT5 compressed texture format.
-    DXT5 = 12,
-    // RGBA 16 bit (4444) texture format.
-    RGBA16 = 13,
+#[from(binary::LineTextureMode)]
+pub enum LineTextureMode {
+    Stretch = 0,
+    Tile = 1,
+    DistributePerSegment = 2,
+    RepeatPerSegment = 3,
+}
 
-    // R 16 bit texture format.
-    RHalf = 15,
-    // RG 32 bit texture format.
-    RGHalf = 16,
-    // RGBA 64 bit texture format.
-    RGBAHalf = 17,
+#[wasm_bindgen(js_name = "UnityAnimationCurve", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct AnimationCurve {
+    pub keyframes: Vec<Keyframe>,
+    pub pre_infinity: CurveWrapMode,
+    pub post_infinity: CurveWrapMode,
+}
 
-    // R 32 bit texture format.
-    RFloat = 18,
-    // RG 64 bit texture format.
-    RGFloat = 19,
-    // RGBA 128 bit texture format.
-    RGBAFloat = 20,
+impl From<binary::AnimationCurve> for AnimationCurve {
+    fn from(value: binary::AnimationCurve) -> Self {
+        Self {
+            keyframes: value.curve.into(),
+            pre_infinity: CurveWrapMode::from_raw(value.pre_infinity),
+            post_infinity: CurveWrapMode::from_raw(value.post_infinity),
+        }
+    }
+}
 
-    // RGB 32 bit packed float format.
-    RGB9E5 = 22,
+#[wasm_bindgen(js_class = "UnityAnimationCurve")]
+impl AnimationCurve {
+    pub fn evaluate(&self, time: f32) -> f32 {
+        curve::evaluate(&self.keyframes, self.pre_infinity, self.post_infinity, time)
+    }
 
-    // R BC4 compressed texture format.
-    BC4 = 26,
-    // RG BC5 compressed texture format.
-    BC5 = 27,
-    // HDR RGB BC6 compressed texture format.
-    BC6H = 24,
-    // RGBA BC7 compressed texture format.
-    BC7 = 25,
+    // Fills out with samples evenly spaced from start_time to end_time,
+    // inclusive
+    pub fn sample_into(&self, start_time: f32, end_time: f32, out: &mut [f32]) {
+        let step = match out.len() {
+            0 | 1 => 0.0,
+            n => (end_time - start_time) / (n - 1) as f32,
+        };
+        for (i, value) in out.iter_mut().enumerate() {
+            *value = self.evaluate(start_time + step * i as f32);
+        }
+    }
+}
 
-    // DXT1 crunched texture format.
-    DXT1Crunched = 28,
-    // DXT5 crunched texture format.
-    DXT5Crunched = 29,
-    // ETC (GLES2.0) 4 bits/pixel compressed RGB texture format.
-    EtcRGB4 = 34,
-    // EAC 4 bits/pixel compressed 16-bit R texture format
-    EacR = 41,
-    // EAC 4 bits/pixel compressed 16-bit signed R texture format
-    EacRSigned = 42,
-    // EAC 8 bits/pixel compressed 16-bit RG texture format
-    EacRG = 43,
-    // EAC 8 bits/pixel compressed 16-bit signed RG texture format
-    EacRGSigned = 44,
+#[wasm_bindgen(js_name = "UnityKeyframe")]
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub time: f32,
+    pub value: f32,
+    pub in_slope: f32,
+    pub out_slope: f32,
+    // bit 0 for the in tangent, bit 1 for the out
+    pub weighted_mode: i32,
+    pub in_weight: f32,
+    pub out_weight: f32,
+}
 
-    // ETC2 (GLES3.0) 4 bits/pixel compressed RGB texture format.
-    Etc2RGB4 = 45,
-    // ETC2 (GLES3.0) 4 bits/pixel compressed RGB + 1-bit alpha texture format.
-    Etc2RGB4PunchthroughAlpha = 46,
-    // ETC2 (GLES3.0) 8 bits/pixel compressed RGBA texture format.
-    Etc2RGBA8 = 47,
+impl From<binary::Keyframe> for Keyframe {
+    fn from(value: binary::Keyframe) -> Self {
+        Self {
+            time: value.time,
+            value: value.value,
+            in_slope: value.in_slope,
+            out_slope: value.out_slope,
+            weighted_mode: value.weighted_mode,
+            in_weight: value.in_weight,
+            out_weight: value.out_weight,
+        }
+    }
+}
 
-    // ASTC uses 128bit block of varying sizes (we use only square blocks). It does not distinguish RGB/RGBA
-    Astc4x4 = 48,
-    Astc5x5 = 49,
-    Astc6x6 = 50,
-    Astc8x8 = 51,
-    Astc10x10 = 52,
-    Astc12x12 = 53,
+#[wasm_bindgen(js_name = "UnityGradient")]
+#[derive(Clone, Debug)]
+pub struct Gradient {
+    // RGB keys, with their times in the w component
+    color_keys: Vec<Vec4>,
+    // (time, alpha) pairs
+    alpha_keys: Vec<Vec2>,
+    pub mode: GradientMode,
+}
 
-    // RG 16 bit texture format.
-    RG16 = 62,
-    // Red 8 bit texture format.
-    R8 = 63,
-    // ETC1 crunched texture format.
-    EtcRGB4Crunched = 64,
-    // ETC2_RGBA8 crunched texture format.
-    Etc2RGBA8Crunched = 65,
+impl From<binary::Gradient> for Gradient {
+    fn from(value: binary::Gradient) -> Self {
+        // all 8 keys are always stored; the color keys' alpha channels hold
+        // the alpha keys' values
+        let color_keys = (0..value.num_color_keys.min(8) as usize)
+            .map(|i| {
+                let key = &value.color_keys[i];
+                Vec4 { x: key.r, y: key.g, z: key.b, w: value.color_times[i] as f32 / 65535.0 }
+            })
+            .collect();
+        let alpha_keys = (0..value.num_alpha_keys.min(8) as usize)
+            .map(|i| Vec2 { x: value.alpha_times[i] as f32 / 65535.0, y: value.color_keys[i].a })
+            .collect();
+        Self {
+            color_keys,
+            alpha_keys,
+            mode: value.mode.into(),
+        }
+    }
+}
 
-    // ASTC (block size 4x4) compressed HDR RGB(A) texture format.
-    AstcHdr4x4 = 66,
-    // ASTC (block size 5x5) compressed HDR RGB(A)  texture format.
-    AstcHdr5x5 = 67,
-    // ASTC (block size 4x6x6) compressed HDR RGB(A) texture format.
-    AstcHdr6x6 = 68,
-    // ASTC (block size 8x8) compressed HDR RGB(A) texture format.
-    AstcHdr8x8 = 69,
-    // ASTC (block size 10x10) compressed HDR RGB(A) texture format.
-    AstcHdr10x10 = 70,
-    // ASTC (block size 12x12) compressed HDR RGB(A) texture format.
-    AstcHdr12x12 = 71,
+#[wasm_bindgen(js_class = "UnityGradient")]
+impl Gradient {
+    pub fn evaluate(&self, t: f32) -> ColorRGBA {
+        let rgb = Self::evaluate_keys(&self.color_keys, t, self.mode, |k| k.w, |k| [k.x, k.y, k.z]);
+        let alpha = Self::evaluate_keys(&self.alpha_keys, t, self.mode, |k| k.x, |k| [k.y]);
+        ColorRGBA {
+            r: rgb.map_or(1.0, |c| c[0]),
+            g: rgb.map_or(1.0, |c| c[1]),
+            b: rgb.map_or(1.0, |c| c[2]),
+            a: alpha.map_or(1.0, |c| c[0]),
+        }
+    }
+}
 
-    RG32 = 72,
-    RGB48 = 73,
-    RGBA64 = 74,
-    R8Signed = 75,
-    RG16Signed = 76,
-    RGB24Signed = 77,
-    RGBA32Signed = 78,
-    R16Signed = 79,
-    RG32Signed = 80,
-    RGB48Signed = 81,
-    RGBA64Signed = 82,
+impl Gradient {
+    fn evaluate_keys<K, const N: usize>(keys: &[K], t: f32, mode: GradientMode, time: impl Fn(&K) -> f32, value: impl Fn(&K) -> [f32; N]) -> Option<[f32; N]> {
+        let first = keys.first()?;
+        let last = keys.last()?;
+        if t <= time(first) {
+            return Some(value(first));
+        }
+        if t >= time(last) {
+            return Some(value(last));
+        }
+        let i = keys.iter().position(|k| time(k) > t)?;
+        let (k0, k1) = (&keys[i - 1], &keys[i]);
+        if let GradientMode::Fixed = mode {
+            // fixed gradients hold each key's value up until its time
+            return Some(value(k1));
+        }
+        let dt = time(k1) - time(k0);
+        let f = if dt > 0.0 { (t - time(k0)) / dt } else { 0.0 };
+        let (v0, v1) = (value(k0), value(k1));
+        let mut result = [0.0; N];
+        for j in 0..N {
+            result[j] = v0[j] + (v1[j] - v0[j]) * f;
+        }
+        Some(result)
+    }
 }
 
-#[wasm_bindgen(js_name = "UnityTextureColorSpace")]
+#[wasm_bindgen(js_name = "UnityGradientMode")]
 #[derive(FromEnumPerVariant, Clone, Copy, Debug)]
-#[from(binary::ColorSpace)]
-pub enum ColorSpace {
-    Linear = 0x00,
-    SRGB   = 0x01,
+#[from(binary::GradientMode)]
+pub enum GradientMode {
+    Blend = 0,
+    Fixed = 1,
 }
 
-#[wasm_bindgen(js_name = "UnityMeshFilter", getter_with_clone)]
+#[wasm_bindgen(js_name = "UnityWindZone")]
 #[derive(Clone, Debug, FromStructPerField)]
-#[from(binary::MeshFilter)]
-pub struct MeshFilter {
+#[from(binary::WindZone)]
+pub struct WindZone {
     pub game_object: WasmFriendlyPPtr,
-    pub mesh: WasmFriendlyPPtr,
+    pub enabled: u8,
+    pub mode: WindZoneMode,
+    pub radius: f32,
+    pub wind_main: f32,
+    pub wind_turbulence: f32,
+    pub wind_pulse_magnitude: f32,
+    pub wind_pulse_frequency: f32,
 }
 
-#[wasm_bindgen(js_name = "UnityMeshRenderer", getter_with_clone)]
+#[wasm_bindgen(js_name = "UnityWindZoneMode")]
+#[derive(FromEnumPerVariant, Clone, Copy, Debug)]
+#[from(binary::WindZoneMode)]
+pub enum WindZoneMode {
+    Directional = 0,
+    Spherical = 1,
+}
+
+#[wasm_bindgen(js_name = "UnityTerrain", getter_with_clone)]
 #[derive(Clone, Debug, FromStructPerField)]
-#[from(binary::MeshRenderer)]
-pub struct MeshRenderer {
+#[from(binary::Terrain)]
+pub struct Terrain {
     pub game_object: WasmFriendlyPPtr,
     pub enabled: u8,
-    pub cast_shadows: u8,
-    pub receive_shadows: u8,
-    pub dynamic_occludee: u8,
-    pub motion_vectors: u8,
-    pub light_probe_usage: u8,
-    pub reflection_probe_usage: u8,
-    pub ray_tracing_mode: u8,
-    pub rendering_layer_mask: u32,
-    pub renderer_priority: i32,
-    pub lightmap_index: u16,
-    pub lightmap_index_dynamic: u16,
-    pub lightmap_tiling_offset: Vec4,
-    pub lightmap_tiling_offset_dynamic: Vec4,
-    pub materials: Vec<WasmFriendlyPPtr>,
-    pub static_batch_info: StaticBatchInfo,
-    pub static_batch_root: WasmFriendlyPPtr,
-    pub probe_anchor: WasmFriendlyPPtr,
-    pub light_probe_volume_override: WasmFriendlyPPtr,
-    pub sorting_layer_id: i32,
-    pub sorting_layer: i16,
-    pub sorting_order: i16,
-    pub additional_vertex_streams: WasmFriendlyPPtr,
+    pub terrain_data: WasmFriendlyPPtr,
+    pub heightmap_pixel_error: f32,
+    pub heightmap_maximum_lod: i32,
+    pub shadow_casting_mode: i32,
+    pub draw_heightmap: u8,
+    pub material_template: WasmFriendlyPPtr,
 }
 
-#[wasm_bindgen(js_name = "UnityStaticBatchInfo", getter_with_clone)]
-#[derive(Clone, Debug, FromStructPerField)]
-#[from(binary::StaticBatchInfo)]
-pub struct StaticBatchInfo {
-    pub first_submesh: u16,
-    pub submesh_count: u16,
+#[wasm_bindgen(js_name = "UnityTerrainData", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct TerrainData {
+    pub name: String,
+    pub alpha_textures: Vec<WasmFriendlyPPtr>,
+    pub terrain_layers: Vec<WasmFriendlyPPtr>,
+    heights: Vec<i16>,
+    pub resolution: usize,
+    pub scale: Vec3,
 }
 
-#[wasm_bindgen(js_name = "UnityScriptMapper")]
-#[derive(Clone, Debug, FromStructPerField)]
-#[from(binary::ScriptMapper)]
-pub struct ScriptMapper {
-    shader_to_name_map: HashMap<WasmFriendlyPPtr, String>,
+impl From<binary::TerrainData> for TerrainData {
+    fn from(value: binary::TerrainData) -> Self {
+        let heightmap = value.heightmap;
+        let resolution = heightmap.resolution.or(heightmap.width).unwrap_or(0).max(0) as usize;
+        Self {
+            name: value.name.into(),
+            alpha_textures: value.splat_database.alpha_textures.into(),
+            terrain_layers: value.splat_database.terrain_layers.into(),
+            heights: heightmap.heights.values,
+            resolution,
+            scale: heightmap.scale,
+        }
+    }
 }
 
-#[wasm_bindgen(js_class = "UnityScriptMapper")]
-impl ScriptMapper {
-    pub fn get_shader_pointers(&self) -> Vec<WasmFriendlyPPtr> {
-        self.shader_to_name_map.keys().cloned().collect()
+const TERRAIN_MAX_HEIGHT: f32 = 32766.0;
+
+#[wasm_bindgen(js_class = "UnityTerrainData")]
+impl TerrainData {
+    // Heights from 0 to 1, row by row along z
+    pub fn get_normalized_heights(&self) -> Vec<f32> {
+        self.heights.iter().map(|&h| h as f32 / TERRAIN_MAX_HEIGHT).collect()
     }
 
-    pub fn get_shader_names(&self) -> Vec<String> {
-        self.shader_to_name_map.values().cloned().collect()
+    // A grid mesh over the heightmap in the terrain's local space, as
+    // interleaved position, normal and UV (8 floats per vertex)
+    pub fn build_mesh(&self) -> TerrainMesh {
+        let res = self.resolution;
+        let mut vertices = Vec::with_capacity(res * res * 8);
+        let mut indices = Vec::new();
+        if res < 2 || self.heights.len() < res * res {
+            return TerrainMesh { vertices, indices };
+        }
+        let height_at = |x: usize, z: usize| {
+            self.heights[z * res + x] as f32 / TERRAIN_MAX_HEIGHT * self.scale.y
+        };
+        for z in 0..res {
+            for x in 0..res {
+                let left = height_at(x.saturating_sub(1), z);
+                let right = height_at((x + 1).min(res - 1), z);
+                let down = height_at(x, z.saturating_sub(1));
+                let up = height_at(x, (z + 1).min(res - 1));
+                let normal = nalgebra_glm::normalize(&nalgebra_glm::vec3(
+                    (left - right) / (2.0 * self.scale.x),
+                    1.0,
+                    (down - up) / (2.0 * self.scale.z),
+                ));
+                vertices.extend_from_slice(&[
+                    x as f32 * self.scale.x, height_at(x, z), z as f32 * self.scale.z,
+                    normal.x, normal.y, normal.z,
+                    x as f32 / (res - 1) as f32, z as f32 / (res - 1) as f32,
+                ]);
+            }
+        }
+        for z in 0..res as u32 - 1 {
+            for x in 0..res as u32 - 1 {
+                let i = z * res as u32 + x;
+                let above = i + res as u32;
+                indices.extend_from_slice(&[i, above, i + 1, i + 1, above, above + 1]);
+            }
+        }
+        TerrainMesh { vertices, indices }
     }
 }
 
+#[wasm_bindgen(js_name = "UnityTerrainMesh", getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct TerrainMesh {
+    pub vertices: Vec<f32>,
+    pub indices: Vec<u32>,
+}
+
+#[wasm_bindgen(js_name = "UnityRenderSettings")]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::RenderSettings)]
+pub struct RenderSettings {
+    pub fog: u8,
+    pub fog_color: ColorRGBA,
+    pub fog_mode: i32,
+    pub fog_density: f32,
+    pub linear_fog_start: f32,
+    pub linear_fog_end: f32,
+    pub ambient_light: Option<ColorRGBA>,
+    pub ambient_sky_color: Option<ColorRGBA>,
+    pub ambient_equator_color: Option<ColorRGBA>,
+    pub ambient_ground_color: Option<ColorRGBA>,
+    pub ambient_intensity: f32,
+    pub ambient_mode: i32,
+    pub subtractive_shadow_color: Option<ColorRGBA>,
+    pub skybox_material: WasmFriendlyPPtr,
+}
+
+#[wasm_bindgen(js_name = "UnityLightmapSettings", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::LightmapSettings)]
+pub struct LightmapSettings {
+    pub lightmaps: Vec<LightmapData>,
+    pub lightmaps_mode: i32,
+    pub use_shadowmask: u8,
+}
+
+#[wasm_bindgen(js_name = "UnityLightmapData", getter_with_clone)]
+#[derive(Clone, Debug, FromStructPerField)]
+#[from(binary::LightmapData)]
+pub struct LightmapData {
+    pub lightmap: WasmFriendlyPPtr,
+    pub dir_lightmap: WasmFriendlyPPtr,
+    pub shadow_mask: WasmFriendlyPPtr,
+}
+
 define_create!(GameObject, "UnityGameObject");
 define_create!(Transform, "UnityTransform");
 define_create!(Material, "UnityMaterial");
@@ -728,3 +4575,131 @@ define_create!(Texture2D, "UnityTexture2D");
 define_create!(MeshFilter, "UnityMeshFilter");
 define_create!(MeshRenderer, "UnityMeshRenderer");
 define_create!(ScriptMapper, "UnityScriptMapper");
+define_create!(Animator, "UnityAnimator");
+define_create!(AnimatorController, "UnityAnimatorController");
+define_create!(AnimatorOverrideController, "UnityAnimatorOverrideController");
+define_create!(AnimationClip, "UnityAnimationClip");
+define_create!(Avatar, "UnityAvatar");
+define_create!(BoxCollider, "UnityBoxCollider");
+define_create!(SphereCollider, "UnitySphereCollider");
+define_create!(CapsuleCollider, "UnityCapsuleCollider");
+define_create!(MeshCollider, "UnityMeshCollider");
+define_create!(NavMeshData, "UnityNavMeshData");
+define_create!(OcclusionCullingSettings, "UnityOcclusionCullingSettings");
+define_create!(OcclusionCullingData, "UnityOcclusionCullingData");
+define_create!(ResourceManager, "UnityResourceManager");
+define_create!(BuildSettings, "UnityBuildSettings");
+define_create!(PreloadData, "UnityPreloadData");
+define_create!(AssetBundle, "UnityAssetBundle");
+define_create!(Canvas, "UnityCanvas");
+define_create!(CanvasRenderer, "UnityCanvasRenderer");
+define_create!(MonoScript, "UnityMonoScript");
+define_create!(MonoBehavior, "UnityMonoBehavior");
+define_create!(Image, "UnityImage");
+define_create!(RawImage, "UnityRawImage");
+define_create!(SkinnedMeshRenderer, "UnitySkinnedMeshRenderer");
+define_create!(SpriteRenderer, "UnitySpriteRenderer");
+define_create!(Sprite, "UnitySprite");
+define_create!(LineRenderer, "UnityLineRenderer");
+define_create!(TrailRenderer, "UnityTrailRenderer");
+define_create!(WindZone, "UnityWindZone");
+define_create!(Terrain, "UnityTerrain");
+define_create!(TerrainData, "UnityTerrainData");
+define_create!(RenderSettings, "UnityRenderSettings");
+define_create!(LightmapSettings, "UnityLightmapSettings");
+define_create!(BoxCollider2D, "UnityBoxCollider2D");
+define_create!(CircleCollider2D, "UnityCircleCollider2D");
+define_create!(PolygonCollider2D, "UnityPolygonCollider2D");
+define_create!(EdgeCollider2D, "UnityEdgeCollider2D");
+define_create!(SpriteAtlas, "UnitySpriteAtlas");
+define_create!(Tk2dSprite, "UnityTk2dSprite");
+define_create!(Tk2dSpriteCollectionData, "UnityTk2dSpriteCollectionData");
+define_create!(Shader, "UnityShader", Some(Shader::create_fallback));
+define_create!(LegacyShader, "UnityLegacyShader");
+
+// Parses an object as its class without keeping the result, for validating
+// whole files. Returns None for classes we have no parser for, and on failure
+// the error along with how many bytes were read before it.
+pub fn validate_object(class_id: ClassID, version: UnityVersion, data: &[u8]) -> Option<Result<(), (String, u64)>> {
+    let result = match class_id {
+        ClassID::GameObject => parse_and_discard::<binary::GameObject>(version, data),
+        ClassID::Transform | ClassID::RectTransform => parse_and_discard::<binary::Transform>(version, data),
+        ClassID::Material => parse_and_discard::<binary::Material>(version, data),
+        ClassID::Mesh => parse_and_discard::<binary::Mesh>(version, data),
+        ClassID::Texture2D => parse_and_discard::<binary::Texture2D>(version, data),
+        ClassID::MeshFilter => parse_and_discard::<binary::MeshFilter>(version, data),
+        ClassID::MeshRenderer => parse_and_discard::<binary::MeshRenderer>(version, data),
+        ClassID::ScriptMapper => parse_and_discard::<binary::ScriptMapper>(version, data),
+        ClassID::Animator => parse_and_discard::<binary::Animator>(version, data),
+        ClassID::AnimatorController => parse_and_discard::<binary::AnimatorController>(version, data),
+        ClassID::AnimatorOverrideController => parse_and_discard::<binary::AnimatorOverrideController>(version, data),
+        ClassID::AnimationClip => parse_and_discard::<binary::AnimationClip>(version, data),
+        ClassID::Avatar => parse_and_discard::<binary::Avatar>(version, data),
+        ClassID::BoxCollider => parse_and_discard::<binary::BoxCollider>(version, data),
+        ClassID::SphereCollider => parse_and_discard::<binary::SphereCollider>(version, data),
+        ClassID::CapsuleCollider => parse_and_discard::<binary::CapsuleCollider>(version, data),
+        ClassID::MeshCollider => parse_and_discard::<binary::MeshCollider>(version, data),
+        ClassID::NavMeshData => parse_and_discard::<binary::NavMeshData>(version, data),
+        ClassID::OcclusionCullingSettings => parse_and_discard::<binary::OcclusionCullingSettings>(version, data),
+        ClassID::OcclusionCullingData => parse_and_discard::<binary::OcclusionCullingData>(version, data),
+        ClassID::ResourceManager => parse_and_discard::<binary::ResourceManager>(version, data),
+        ClassID::BuildSettings => parse_and_discard::<binary::BuildSettings>(version, data),
+        ClassID::PreloadData => parse_and_discard::<binary::PreloadData>(version, data),
+        ClassID::AssetBundle => parse_and_discard::<binary::AssetBundle>(version, data),
+        ClassID::Canvas => parse_and_discard::<binary::Canvas>(version, data),
+        ClassID::CanvasRenderer => parse_and_discard::<binary::CanvasRenderer>(version, data),
+        ClassID::MonoScript => parse_and_discard::<binary::MonoScript>(version, data),
+        ClassID::MonoBehavior => parse_and_discard::<binary::MonoBehavior>(version, data),
+        ClassID::SkinnedMeshRenderer => parse_and_discard::<binary::SkinnedMeshRenderer>(version, data),
+        ClassID::SpriteRenderer => parse_and_discard::<binary::SpriteRenderer>(version, data),
+        ClassID::Sprite => parse_and_discard::<binary::Sprite>(version, data),
+        ClassID::LineRenderer => parse_and_discard::<binary::LineRenderer>(version, data),
+        ClassID::TrailRenderer => parse_and_discard::<binary::TrailRenderer>(version, data),
+        ClassID::WindZone => parse_and_discard::<binary::WindZone>(version, data),
+        ClassID::Shader if version < UnityVersion::V2017_3_0f3 => parse_and_discard::<binary::LegacyShader>(version, data),
+        ClassID::Shader => parse_and_discard::<binary::Shader>(version, data),
+        ClassID::Terrain => parse_and_discard::<binary::Terrain>(version, data),
+        ClassID::TerrainData => parse_and_discard::<binary::TerrainData>(version, data),
+        ClassID::RenderSettings => parse_and_discard::<binary::RenderSettings>(version, data),
+        ClassID::LightmapSettings => parse_and_discard::<binary::LightmapSettings>(version, data),
+        ClassID::BoxCollider2D => parse_and_discard::<binary::BoxCollider2D>(version, data),
+        ClassID::CircleCollider2D => parse_and_discard::<binary::CircleCollider2D>(version, data),
+        ClassID::PolygonCollider2D => parse_and_discard::<binary::PolygonCollider2D>(version, data),
+        ClassID::EdgeCollider2D => parse_and_discard::<binary::EdgeCollider2D>(version, data),
+        ClassID::SpriteAtlas => parse_and_discard::<binary::SpriteAtlas>(version, data),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn parse_and_discard<T: for<'a> DekuReader<'a, UnityVersion>>(version: UnityVersion, data: &[u8]) -> Result<(), (String, u64)> {
+    reset_parse_budget();
+    set_object_version(version);
+    let mut cursor = Cursor::new(data);
+    let mut reader = Reader::new(&mut cursor);
+    match T::from_reader_with_ctx(&mut reader, version) {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            let offset = reader.bits_read / 8;
+            Err((describe_parse_error::<T>(&err, version, data, offset), offset as u64))
+        },
+    }
+}
+
+// Says where in data a parse failed, both as the path of fields and array
+// elements we were in and as a hex dump around the offset. The path comes
+// from parsing the object again with field tracing on.
+fn describe_parse_error<T: for<'a> DekuReader<'a, UnityVersion>>(err: &DekuError, version: UnityVersion, data: &[u8], offset: usize) -> String {
+    let path = trace_error_path(|| {
+        reset_parse_budget();
+        set_object_version(version);
+        let mut cursor = Cursor::new(data);
+        let _ = T::from_reader_with_ctx(&mut Reader::new(&mut cursor), version);
+    });
+    let location = if path.is_empty() {
+        format!("at offset {:#x}", offset)
+    } else {
+        format!("at offset {:#x} in {}", offset, path)
+    };
+    format!("{:?} {}\n{}", err, location, hex_dump_around(data, offset, 32))
+}
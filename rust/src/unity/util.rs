@@ -17,3 +17,28 @@ pub fn deku_peek<'a, T, R: std::io::Read + std::io::Seek>(reader: &mut Reader<R>
         }
     }
 }
+
+// Formats the bytes around offset as a hex dump, with a caret under the byte
+// at offset, for pointing at where a parse went wrong
+pub fn hex_dump_around(data: &[u8], offset: usize, radius: usize) -> String {
+    let start = offset.saturating_sub(radius) & !0xf;
+    let end = (offset + radius).min(data.len());
+    let mut result = String::new();
+    let mut line_start = start;
+    while line_start < end {
+        let line = &data[line_start..(line_start + 16).min(end)];
+        let hex: Vec<String> = line.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = line.iter()
+            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+            .collect();
+        result.push_str(&format!("{:08x}  {:<47}  {}\n", line_start, hex.join(" "), ascii));
+        if (line_start..line_start + 16).contains(&offset) {
+            result.push_str(&format!("{}^^\n", " ".repeat(10 + (offset - line_start) * 3)));
+        }
+        line_start += 16;
+    }
+    if offset >= data.len() {
+        result.push_str(&format!("(offset {:#x} is past the end of the {:#x} bytes of data)\n", offset, data.len()));
+    }
+    result
+}
@@ -1,5 +1,7 @@
 use std::convert::TryFrom;
 
+use crate::unity::types::common::UnityVersion;
+
 #[derive(Debug, PartialEq, PartialOrd, Default, Copy, Clone)]
 pub struct ParsedUnityVersion {
     pub major: usize,
@@ -59,6 +61,38 @@ impl TryFrom<&str> for ParsedUnityVersion {
     }
 }
 
+impl ParsedUnityVersion {
+    // The first build of major.minor.build
+    fn earliest(major: usize, minor: usize, build: usize) -> Self {
+        ParsedUnityVersion { major, minor, build, version_type: VersionType::Alpha, type_number: 0 }
+    }
+
+    // The closest layout we have parsers for, if any. Each layout is only
+    // right from the earliest version its gates assume, so versions from
+    // before that within its range (e.g. 2019.1, whose meshes predate
+    // ray_tracing_mode) are rejected rather than misparsed.
+    pub fn to_supported_version(self) -> Option<UnityVersion> {
+        let (version, earliest) = match (self.major, self.minor) {
+            (4, _) => (UnityVersion::V4_7_2f1, Self::earliest(4, 3, 0)),
+            (5, 4) => (UnityVersion::V5_4_6f1, Self::earliest(5, 4, 0)),
+            (2017, _) => (UnityVersion::V2017_3_0f3, Self::earliest(2017, 3, 0)),
+            (2018, 0..=1) => (UnityVersion::V2018_1_9f2, Self::earliest(2018, 1, 0)),
+            (2018, 2) => (UnityVersion::V2018_2_21f1, Self::earliest(2018, 2, 0)),
+            (2018, _) => (UnityVersion::V2018_4_36f1, Self::earliest(2018, 3, 0)),
+            (2019, _) => (UnityVersion::V2019_4_39f1, Self::earliest(2019, 3, 0)),
+            (2020, _) => (UnityVersion::V2020_3_16f1, Self::earliest(2020, 3, 2)),
+            (2021, _) => (UnityVersion::V2021_3_27f1, Self::earliest(2021, 3, 10)),
+            (v, _) if v >= 2022 => (UnityVersion::V2022_3_0f1, Self::earliest(2022, 1, 0)),
+            _ => return None,
+        };
+        if self >= earliest {
+            Some(version)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, PartialOrd, Copy, Clone)]
 pub enum VersionType {
     Alpha,
@@ -104,4 +138,34 @@ mod test {
         assert_eq!(v1 >= v3, true);
         assert_eq!(v2 >= v3, true);
     }
+
+    #[test]
+    fn test_supported_version() {
+        let v = ParsedUnityVersion::try_from("2017.3.0f3").unwrap();
+        assert_eq!(v.to_supported_version(), Some(UnityVersion::V2017_3_0f3));
+        let v = ParsedUnityVersion::try_from("2018.4.36f1").unwrap();
+        assert_eq!(v.to_supported_version(), Some(UnityVersion::V2018_4_36f1));
+        let v = ParsedUnityVersion::try_from("2018.1.0f2").unwrap();
+        assert_eq!(v.to_supported_version(), Some(UnityVersion::V2018_1_9f2));
+        let v = ParsedUnityVersion::try_from("2018.2.3f1").unwrap();
+        assert_eq!(v.to_supported_version(), Some(UnityVersion::V2018_2_21f1));
+        let v = ParsedUnityVersion::try_from("4.6.9f1").unwrap();
+        assert_eq!(v.to_supported_version(), Some(UnityVersion::V4_7_2f1));
+        let v = ParsedUnityVersion::try_from("5.4.1f1").unwrap();
+        assert_eq!(v.to_supported_version(), Some(UnityVersion::V5_4_6f1));
+        let v = ParsedUnityVersion::try_from("5.6.7f1").unwrap();
+        assert_eq!(v.to_supported_version(), None);
+        let v = ParsedUnityVersion::try_from("2023.2.1f1").unwrap();
+        assert_eq!(v.to_supported_version(), Some(UnityVersion::V2022_3_0f1));
+
+        // too early for the layout their year maps to
+        for version in ["4.2.2f1", "5.3.8f1", "2017.2.5f1", "2019.2.21f1", "2020.3.1f1", "2021.3.9f1"] {
+            let v = ParsedUnityVersion::try_from(version).unwrap();
+            assert_eq!(v.to_supported_version(), None, "{}", version);
+        }
+        let v = ParsedUnityVersion::try_from("2019.3.0f6").unwrap();
+        assert_eq!(v.to_supported_version(), Some(UnityVersion::V2019_4_39f1));
+        let v = ParsedUnityVersion::try_from("2021.3.10f1").unwrap();
+        assert_eq!(v.to_supported_version(), Some(UnityVersion::V2021_3_27f1));
+    }
 }
@@ -44,7 +44,9 @@ impl Adt {
             }
         }
 
-        let adt_base_pos = &map_chunks[0].header.position;
+        let adt_base_pos = &map_chunks.first()
+            .ok_or("ADT had no MCNK chunks".to_string())?
+            .header.position;
         let mut liquids: Vec<Option<LiquidData>> = Vec::new();
         if let Some((liquid_header, data)) = liquid_data {
             let mut instance_chunks: Vec<(Vec<LiquidInstance>, Vec<usize>)> = Vec::with_capacity(liquid_header.chunks.len());
@@ -97,8 +99,21 @@ impl Adt {
                 let liquid = LiquidData::parse(instances, vertex_data_sizes, x_coord, y_coord, data)?;
                 liquids.push(liquid);
             }
+        } else {
+            // pre-WotLK ADTs store their liquids inside each MCNK instead
+            for mcnk in &mut map_chunks {
+                let position = mcnk.header.position;
+                let layers: Vec<LiquidLayer> = mcnk.legacy_liquids.drain(..)
+                    .map(|liquid| liquid.into_layer(position.x, position.y))
+                    .collect();
+                if layers.is_empty() {
+                    liquids.push(None);
+                } else {
+                    liquids.push(Some(LiquidData { layers }));
+                }
+            }
         }
-        
+
         Ok(Adt {
             map_chunks,
             doodads: vec![],
@@ -219,7 +234,7 @@ impl Adt {
                     self.doodads = mddf.doodads;
                 },
                 b"KNCM" => {
-                    self.map_chunks[map_chunk_idx].append_obj_chunk(chunk, chunk_data)?;
+                    self.get_map_chunk_mut(map_chunk_idx)?.append_obj_chunk(chunk, chunk_data)?;
                     map_chunk_idx += 1;
                 }
                 b"FDOM" => self.map_object_defs = parse_array(chunk_data, 0x40)?,
@@ -235,7 +250,7 @@ impl Adt {
         for (chunk, chunk_data) in &mut chunked_data {
             match &chunk.magic {
                 b"KNCM" => {
-                    self.map_chunks[map_chunk_idx].append_tex_chunk(chunk, chunk_data)?;
+                    self.get_map_chunk_mut(map_chunk_idx)?.append_tex_chunk(chunk, chunk_data)?;
                     map_chunk_idx += 1;
                 }
                 b"DIHM" => self.height_tex_ids = Some(parse_with_byte_size(chunk_data)?),
@@ -246,6 +261,42 @@ impl Adt {
         Ok(())
     }
 
+    // Packs each placement as position (3), rotation in degrees (3), then
+    // scale, so a whole tile's worth can cross to JS in a single array
+    pub fn get_doodad_placements(&self, lod_level: usize) -> Vec<f32> {
+        let doodads = self.get_doodads(lod_level);
+        let mut result = Vec::with_capacity(doodads.len() * PLACEMENT_STRIDE);
+        for doodad in &doodads {
+            push_placement(&mut result, &doodad.position, &doodad.rotation, doodad.scale);
+        }
+        result
+    }
+
+    pub fn get_wmo_placements(&self, lod_level: usize) -> Vec<f32> {
+        let defs = self.get_wmo_defs(lod_level);
+        let mut result = Vec::with_capacity(defs.len() * PLACEMENT_STRIDE);
+        for def in &defs {
+            // WMO scale is only meaningful if flag 0x4 is set
+            let scale = if def.flags & 0x4 > 0 { def.scale } else { 1024 };
+            push_placement(&mut result, &def.position, &def.rotation, scale);
+        }
+        result
+    }
+
+    pub fn get_wmo_file_ids(&self, lod_level: usize) -> Vec<u32> {
+        self.get_wmo_defs(lod_level).iter().map(|def| def.name_id).collect()
+    }
+
+    pub fn get_placement_stride() -> usize {
+        PLACEMENT_STRIDE
+    }
+
+    fn get_map_chunk_mut(&mut self, index: usize) -> Result<&mut MapChunk, String> {
+        let num_chunks = self.map_chunks.len();
+        self.map_chunks.get_mut(index)
+            .ok_or(format!("split ADT has MCNK {}, but the root only has {}", index, num_chunks))
+    }
+
     fn chunk_index_to_coords(index: usize) -> (f32, f32) {
         let mut x = (index / 17) as f32;
         let mut y = (index as f32) % 17.0;
@@ -357,6 +408,14 @@ impl Adt {
     }
 }
 
+const PLACEMENT_STRIDE: usize = 3 + 3 + 1;
+
+fn push_placement(result: &mut Vec<f32>, position: &Vec3, rotation: &Vec3, scale: u16) {
+    result.extend([position.x, position.y, position.z]);
+    result.extend([rotation.x, rotation.y, rotation.z]);
+    result.push(scale as f32 / 1024.0);
+}
+
 #[derive(Debug, DekuRead, Clone)]
 pub struct LodLevels {
     pub m2_lod_offset: [u32; 3],
@@ -488,19 +547,28 @@ pub struct MapChunk {
     pub vertex_lighting: Option<VertexLighting>,
     pub texture_layers: Vec<MapChunkTextureLayer>,
     pub alpha_map: Option<Vec<u8>>,
+    legacy_liquids: Vec<LegacyLiquid>,
 }
 
 impl MapChunk {
     pub fn new(_chunk: Chunk, chunk_data: &[u8]) -> Result<Self, String> {
-        let header = parse(chunk_data)?;
+        let header: MapChunkHeader = parse(chunk_data)?;
 
         let mut mcvt: Option<HeightmapChunk> = None;
         let mut mcnr: Option<NormalChunk> = None;
-        let mut chunked_data = ChunkedData::new(&chunk_data[0x80..]);
+        let mut mccv: Option<VertexColors> = None;
+        let mut mclv: Option<VertexLighting> = None;
+        let legacy_liquids = LegacyLiquid::parse_all(&header, chunk_data)?;
+        let subchunks = chunk_data.get(0x80..)
+            .ok_or("MapChunk was too small for its header".to_string())?;
+        let mut chunked_data = ChunkedData::new(subchunks);
         for (subchunk, subchunk_data) in &mut chunked_data {
             match &subchunk.magic {
                 b"TVCM" => mcvt = Some(parse(subchunk_data)?),
                 b"RNCM" => mcnr = Some(parse(subchunk_data)?),
+                // split ADTs keep vertex shading in the root file
+                b"VCCM" => mccv = Some(parse(subchunk_data)?),
+                b"VLCM" => mclv = Some(parse(subchunk_data)?),
                 //_ => println!("skipping subchunk {}", subchunk.magic_str()),
                 _ => {},
             }
@@ -511,12 +579,14 @@ impl MapChunk {
             normals: mcnr.ok_or("MapChunk had no MCNR chunk".to_string())?,
             heightmap: mcvt.ok_or("MapChunk had no MCVT chunk".to_string())?,
 
+            vertex_colors: mccv,
+            vertex_lighting: mclv,
+
             // these will be appended in separate ADT files
             shadows: None,
-            vertex_colors: None,
-            vertex_lighting: None,
             alpha_map: None,
             texture_layers: vec![],
+            legacy_liquids,
         })
     }
 
@@ -935,6 +1005,121 @@ impl LiquidLayer {
     }
 }
 
+// MCLQ's vertices are 8 bytes, but what the first 4 mean depends on the
+// liquid: water and ocean start with a depth byte, magma and slime store UVs
+#[derive(DekuRead, Debug, Clone)]
+struct LegacyLiquidVertex {
+    pub depth: u8,
+    pub _data: [u8; 3],
+    pub height: f32,
+}
+
+#[derive(DekuRead, Debug, Clone)]
+struct LegacyLiquid {
+    #[deku(skip)] pub liquid_type: u16,
+    pub min_height: f32,
+    pub max_height: f32,
+    pub vertices: [LegacyLiquidVertex; 9 * 9],
+    pub tiles: [u8; 8 * 8],
+}
+
+// min/max heights, vertices, tiles, then n_flowvs and two flow volumes
+const LEGACY_LIQUID_SIZE: usize = 8 + 9 * 9 * 8 + 8 * 8 + 4 + 2 * 0x28;
+
+// MCNK flags for each liquid that can be present, and the LiquidType it maps to
+const LEGACY_LIQUID_FLAGS: [(u32, u16); 4] = [
+    (0x04, 1), // river
+    (0x08, 2), // ocean
+    (0x10, 3), // magma
+    (0x20, 4), // slime
+];
+
+impl LegacyLiquid {
+    fn parse_all(header: &MapChunkHeader, chunk_data: &[u8]) -> Result<Vec<LegacyLiquid>, String> {
+        let mut result = Vec::new();
+        if header._ofs_liquid == 0 || header._size_liquid <= 8 {
+            return Ok(result);
+        }
+        // the offset is relative to the start of the MCNK, including its chunk
+        // header, and points to the MCLQ header (whose size is often wrong)
+        let mut offset = header._ofs_liquid as usize;
+        for (flag, liquid_type) in LEGACY_LIQUID_FLAGS {
+            if header.flags & flag == 0 {
+                continue;
+            }
+            let liquid_data = chunk_data.get(offset..offset + LEGACY_LIQUID_SIZE)
+                .ok_or(format!("MCLQ at {} out of bounds", offset))?;
+            let mut liquid: LegacyLiquid = parse(liquid_data)?;
+            liquid.liquid_type = liquid_type;
+            result.push(liquid);
+            offset += LEGACY_LIQUID_SIZE;
+        }
+        Ok(result)
+    }
+
+    fn into_layer(self, chunk_x: f32, chunk_y: f32) -> LiquidLayer {
+        let has_depth = self.liquid_type <= 2;
+        let mut extents = AABBox::default();
+        let mut vertices: Vec<f32> = Vec::with_capacity(6 * 9 * 9);
+        for y in 0..9 {
+            for x in 0..9 {
+                let vertex = &self.vertices[y * 9 + x];
+                let x_pos = chunk_x - y as f32 * UNIT_SIZE;
+                let y_pos = chunk_y - x as f32 * UNIT_SIZE;
+                let depth = if has_depth { vertex.depth as f32 } else { 0.0 };
+                vertices.push(x_pos);
+                vertices.push(y_pos);
+                vertices.push(vertex.height);
+                vertices.push(y as f32 / 8.0);
+                vertices.push(x as f32 / 8.0);
+                vertices.push(depth);
+                extents.update(x_pos, y_pos, vertex.height);
+            }
+        }
+
+        let mut indices: Vec<u16> = Vec::new();
+        for y in 0..8 {
+            for x in 0..8 {
+                // a liquid type nibble of 0xf means this tile has no liquid
+                if self.tiles[y * 8 + x] & 0x0f == 0x0f {
+                    continue;
+                }
+                let vert_indices = [
+                    y * 9 + x,
+                    y * 9 + x + 1,
+                    (y + 1) * 9 + x,
+                    (y + 1) * 9 + x + 1,
+                ];
+                indices.push(vert_indices[0] as u16);
+                indices.push(vert_indices[1] as u16);
+                indices.push(vert_indices[2] as u16);
+
+                indices.push(vert_indices[1] as u16);
+                indices.push(vert_indices[3] as u16);
+                indices.push(vert_indices[2] as u16);
+            }
+        }
+
+        LiquidLayer {
+            instance: LiquidInstance {
+                liquid_type: self.liquid_type,
+                liquid_object_or_lvf: 0,
+                min_height_level: self.min_height,
+                _max_height_level: self.max_height,
+                x_offset: 0,
+                y_offset: 0,
+                width: 8,
+                height: 8,
+                bitmask_offset: 0,
+                vertex_data_offset: 0,
+            },
+            extents,
+            vertices: Some(vertices),
+            indices: Some(indices),
+        }
+    }
+}
+
 #[derive(DekuRead, Debug, Clone)]
 pub struct LiquidHeader {
     #[deku(count = "256")]
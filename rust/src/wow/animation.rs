@@ -65,6 +65,11 @@ impl M2Sequence {
     }
 }
 
+pub const INTERPOLATION_NONE: u16 = 0;
+pub const INTERPOLATION_LINEAR: u16 = 1;
+pub const INTERPOLATION_BEZIER: u16 = 2;
+pub const INTERPOLATION_HERMITE: u16 = 3;
+
 #[derive(DekuRead, Debug, Clone)]
 pub struct M2TrackPartial<T> {
     pub timestamps_unallocated: WowArray<u16>,
@@ -455,8 +460,37 @@ impl AnimationManager {
     pub fn get_num_texture_weights(&self) -> usize {
         self.texture_weights.len()
     }
+
+    // Returns a column-major 4x4 matrix per bone for the current (blended)
+    // animation state, with parent transforms already applied
+    pub fn get_bone_matrices(&self) -> Vec<f32> {
+        self.calculate_bone_matrices(|bone| (
+            self.get_current_value_with_blend(&bone.translation, Vec3::new(0.0)),
+            self.get_current_value_with_blend(bone.rotation.as_ref().unwrap(), IDENTITY_QUAT),
+            self.get_current_value_with_blend(&bone.scaling, Vec3::new(1.0)),
+        ))
+    }
+
+    // Like get_bone_matrices, but samples a single sequence at the given time
+    // (in milliseconds) without touching the playback state
+    pub fn sample_bone_matrices(&self, sequence_index: usize, time: f64) -> Result<Vec<f32>, String> {
+        if sequence_index >= self.sequences.len() {
+            return Err(format!("sequence {} out of bounds ({} sequences)", sequence_index, self.sequences.len()));
+        }
+        Ok(self.calculate_bone_matrices(|bone| (
+            self.get_current_value(time, sequence_index, &bone.translation, Vec3::new(0.0)),
+            self.get_current_value(time, sequence_index, bone.rotation.as_ref().unwrap(), IDENTITY_QUAT),
+            self.get_current_value(time, sequence_index, &bone.scaling, Vec3::new(1.0)),
+        )))
+    }
+
+    pub fn get_sequence_duration(&self, sequence_index: usize) -> Option<u32> {
+        self.sequences.get(sequence_index).map(|seq| seq.duration)
+    }
 }
 
+const IDENTITY_QUAT: Quat = Quat { x: 0.0, y: 0.0, z: 0.0, w: 1.0 };
+
 // rust-only interface
 impl AnimationManager {
     pub fn new(
@@ -494,6 +528,33 @@ impl AnimationManager {
         }
     }
 
+    fn calculate_bone_matrices<F>(&self, sample: F) -> Vec<f32>
+        where F: Fn(&M2CompBone) -> (Vec3, Quat, Vec3) {
+        let mut matrices: Vec<nalgebra_glm::Mat4> = Vec::with_capacity(self.bones.len());
+        for (i, bone) in self.bones.iter().enumerate() {
+            let (translation, rotation, scaling) = sample(bone);
+            let pivot: nalgebra_glm::Vec3 = bone.pivot.into();
+            let local = nalgebra_glm::translation(&(pivot + nalgebra_glm::Vec3::from(translation)))
+                * nalgebra_glm::quat_to_mat4(&nalgebra_glm::quat(rotation.x, rotation.y, rotation.z, rotation.w))
+                * nalgebra_glm::scaling(&scaling.into())
+                * nalgebra_glm::translation(&-pivot);
+
+            // parents always come before their children in the bone list
+            let parent = bone.parent_bone;
+            let matrix = if parent >= 0 && (parent as usize) < i {
+                matrices[parent as usize] * local
+            } else {
+                local
+            };
+            matrices.push(matrix);
+        }
+        let mut result = Vec::with_capacity(matrices.len() * 16);
+        for matrix in &matrices {
+            result.extend_from_slice(matrix.as_slice());
+        }
+        result
+    }
+
     fn get_current_value<U, V>(&self, mut curr_time: f64, mut animation_index: usize, animation: &M2Track<U>, default: V) -> V
         where V: Clone + Lerp, U: Into<V> + Clone
         {
@@ -525,13 +586,18 @@ impl AnimationManager {
                 let time1 = times[time_index];
                 let time2 = times[time_index + 1];
 
-                if animation.interpolation_type == 0 {
-                    return <U as Into<V>>::into(value1.clone());
-                } else if animation.interpolation_type == 1 {
-                    let t = (curr_time - time1 as f64) / (time2 as f64 - time1 as f64);
-                    return value1.clone().into().lerp(value2.clone().into(), t as f32);
-                } else {
-                    unreachable!("unknown interpolation type!")
+                match animation.interpolation_type {
+                    INTERPOLATION_NONE => <U as Into<V>>::into(value1.clone()),
+                    // Hermite and Bezier tracks are only used by particle
+                    // emitters in practice, so approximate them linearly
+                    INTERPOLATION_LINEAR | INTERPOLATION_HERMITE | INTERPOLATION_BEZIER => {
+                        if time2 <= time1 {
+                            return value1.clone().into();
+                        }
+                        let t = (curr_time - time1 as f64) / (time2 as f64 - time1 as f64);
+                        value1.clone().into().lerp(value2.clone().into(), t.clamp(0.0, 1.0) as f32)
+                    },
+                    _ => value1.clone().into(),
                 }
             }
         } else {
@@ -57,10 +57,48 @@ pub struct BlpHeader {
     palette: [u32; 256], // BGRA values
 }
 
+// BLP1 (Warcraft III, early WoW betas) has a different header, which we
+// convert to a BLP2 one. Its palettized images are laid out like BLP2's.
+#[derive(Debug, DekuRead)]
+#[deku(magic = b"BLP1")]
+struct Blp1Header {
+    compression: u32, // 0 for JPEG, 1 for palettized
+    alpha_bit_depth: u32,
+    width: u32,
+    height: u32,
+    _picture_type: u32,
+    has_mips: u32,
+    mip_offsets: [u32; 16],
+    mip_sizes: [u32; 16],
+    #[deku(cond = "*compression == 1")]
+    palette: Option<[u32; 256]>,
+}
+
+impl Blp1Header {
+    fn into_blp2(self) -> Result<BlpHeader, String> {
+        let palette = match (self.compression, self.palette) {
+            (1, Some(palette)) => palette,
+            _ => return Err("JPEG-compressed BLP1 textures aren't supported".to_string()),
+        };
+        Ok(BlpHeader {
+            _version: 1,
+            color_encoding: ColorEncoding::Uncompressed,
+            alpha_bit_depth: self.alpha_bit_depth as u8,
+            preferred_format: PixelFormat::Unspecified,
+            has_mips: self.has_mips as u8,
+            width: self.width,
+            height: self.height,
+            mip_offsets: self.mip_offsets,
+            mip_sizes: self.mip_sizes,
+            palette,
+        })
+    }
+}
+
 #[wasm_bindgen(js_name = "WowBlp")]
 #[derive(Debug)]
 pub struct Blp {
-    texture_data: Vec<u8>,
+    data: Vec<u8>,
     pub header: BlpHeader,
 }
 
@@ -73,52 +111,48 @@ pub struct BlpMipMetadata {
 #[wasm_bindgen(js_class = "WowBlp")]
 impl Blp {
     pub fn new(data: &[u8]) -> Result<Blp, String> {
-        let (_, header) = BlpHeader::from_bytes((data, 0))
-            .map_err(|e| format!("{:?}", e))?;
+        let header = if data.starts_with(b"BLP1") {
+            let (_, header) = Blp1Header::from_bytes((data, 0))
+                .map_err(|e| format!("{:?}", e))?;
+            header.into_blp2()?
+        } else {
+            let (_, header) = BlpHeader::from_bytes((data, 0))
+                .map_err(|e| format!("{:?}", e))?;
+            header
+        };
 
         Ok(Blp {
-            texture_data: data[1172..].to_vec(),
+            data: data.to_vec(),
             header,
         })
     }
 
-    pub fn get_texture_data(&self, start: usize, size: usize) -> Result<Vec<u8>, String> {
-        let data = &self.texture_data[start..start+size];
-        match (self.header.color_encoding, self.header.preferred_format) {
-            (ColorEncoding::Uncompressed, _) => {
-                let mut result = Vec::with_capacity(self.texture_data.len() * 4);
-                for &idx in data.iter() {
-                    let pixel: u32 = self.header.palette[idx as usize];
-                    let [b, g, r, a] = pixel.to_le_bytes();
-                    result.push(r);
-                    result.push(g);
-                    result.push(b);
-                    result.push(a);
-                }
-                Ok(result)
-            },
-            (ColorEncoding::Dxtc, _) => Ok(data.to_vec()),
-            x => Err(format!("unsupported texture format combination: {:?}", x)),
-        }
-    }
-
+    // Compressed formats are returned as is, everything else as RGBA8
     pub fn get_mip_data(&self, mip_level: usize) -> Result<Vec<u8>, String> {
-        if mip_level > self.header.mip_offsets.len() {
+        if mip_level >= self.header.mip_offsets.len() {
             return Err("invalid mip level".to_string());
         }
-        let w = (self.header.width >> mip_level).max(1);
-        let h = (self.header.height >> mip_level).max(1);
-        let offset = self.header.mip_offsets[mip_level] as usize - 1172;
-        let size = match self.header.preferred_format {
-            PixelFormat::Dxt5 | PixelFormat::Dxt3 => {
-                ((w + 3) / 4) * ((h + 3) / 4) * 16
-            },
-            PixelFormat::Dxt1 => {
-                ((w + 3) / 4) * ((h + 3) / 4) * 8
-            },
-            _ => self.header.mip_sizes[mip_level],
+        let w = (self.header.width >> mip_level).max(1) as usize;
+        let h = (self.header.height >> mip_level).max(1) as usize;
+        let pixel_count = w * h;
+        let size = match (self.header.color_encoding, self.header.preferred_format) {
+            (ColorEncoding::Dxtc, PixelFormat::Dxt5 | PixelFormat::Dxt3) => w.div_ceil(4) * h.div_ceil(4) * 16,
+            (ColorEncoding::Dxtc, PixelFormat::Dxt1) => w.div_ceil(4) * h.div_ceil(4) * 8,
+            (ColorEncoding::Uncompressed, _) => pixel_count + (pixel_count * self.header.alpha_bit_depth as usize).div_ceil(8),
+            (ColorEncoding::A8R8G8B8, _) => pixel_count * 4,
+            _ => self.header.mip_sizes[mip_level] as usize,
         };
-        self.get_texture_data(offset, size as usize)
+        let offset = self.header.mip_offsets[mip_level] as usize;
+        let data = self.data.get(offset..offset + size)
+            .ok_or(format!("mip {} is out of bounds of the file", mip_level))?;
+
+        match self.header.color_encoding {
+            ColorEncoding::Uncompressed => self.decode_palettized(data, pixel_count),
+            ColorEncoding::Dxtc => Ok(data.to_vec()),
+            ColorEncoding::A8R8G8B8 => Ok(data.chunks_exact(4)
+                .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]])
+                .collect()),
+        }
     }
 
     pub fn get_num_mips(&self) -> usize {
@@ -130,3 +164,24 @@ impl Blp {
         16
     }
 }
+
+impl Blp {
+    // Palette indices are followed by the alpha channel, packed at
+    // alpha_bit_depth bits per pixel from the low bits up
+    fn decode_palettized(&self, data: &[u8], pixel_count: usize) -> Result<Vec<u8>, String> {
+        let (indices, alpha) = data.split_at(pixel_count);
+        let mut result = Vec::with_capacity(pixel_count * 4);
+        for (i, &index) in indices.iter().enumerate() {
+            let [b, g, r, _] = self.header.palette[index as usize].to_le_bytes();
+            let a = match self.header.alpha_bit_depth {
+                0 => 0xFF,
+                1 => ((alpha[i / 8] >> (i % 8)) & 1) * 0xFF,
+                4 => ((alpha[i / 2] >> ((i % 2) * 4)) & 0xF) * 0x11,
+                8 => alpha[i],
+                depth => return Err(format!("unsupported alpha bit depth {}", depth)),
+            };
+            result.extend_from_slice(&[r, g, b, a]);
+        }
+        Ok(result)
+    }
+}
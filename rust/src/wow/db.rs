@@ -380,6 +380,216 @@ impl<T> DatabaseTable<T> {
         let index = self.ids.iter().position(|haystack| *haystack == needle)?;
         Some(&self.records[index])
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &T)> {
+        self.ids.iter().cloned().zip(self.records.iter())
+    }
+}
+
+// Legacy (pre-Cataclysm) client databases have no field metadata, so they're
+// read against a caller-provided schema, one entry per 4-byte column (or
+// byte, for U8). The first column is always the record's ID.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DbcFieldType {
+    U32,
+    I32,
+    F32,
+    U8,
+    String,
+}
+
+impl DbcFieldType {
+    // Schemas can be given as a format string, one character per column:
+    // u(nsigned), i(nteger), f(loat), b(yte) or s(tring)
+    pub fn parse_format(format: &str) -> Result<Vec<DbcFieldType>, String> {
+        format.chars()
+            .map(|c| match c {
+                'u' => Ok(DbcFieldType::U32),
+                'i' => Ok(DbcFieldType::I32),
+                'f' => Ok(DbcFieldType::F32),
+                'b' => Ok(DbcFieldType::U8),
+                's' => Ok(DbcFieldType::String),
+                _ => Err(format!("unknown DBC field type '{}'", c)),
+            })
+            .collect()
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            DbcFieldType::U8 => 1,
+            _ => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DbcValue {
+    U32(u32),
+    I32(i32),
+    F32(f32),
+    U8(u8),
+    String(String),
+}
+
+#[derive(DekuRead, Debug, Clone)]
+#[deku(magic = b"WDBC")]
+pub struct DbcHeader {
+    pub record_count: u32,
+    pub field_count: u32,
+    pub record_size: u32,
+    pub string_block_size: u32,
+}
+
+#[derive(Debug)]
+pub struct DbcTable {
+    rows: Vec<Vec<DbcValue>>,
+    ids: HashMap<u32, usize>,
+}
+
+impl DbcTable {
+    pub fn new(data: &[u8], schema: &[DbcFieldType]) -> Result<DbcTable, String> {
+        let header: DbcHeader = parse(data)?;
+        let schema_size: usize = schema.iter().map(|field| field.size()).sum();
+        if schema_size > header.record_size as usize {
+            return Err(format!("schema is {} bytes, but records are only {}", schema_size, header.record_size));
+        }
+        if schema.len() > header.field_count as usize {
+            return Err(format!("schema has {} columns, but the DBC only has {}", schema.len(), header.field_count));
+        }
+        if schema.first() != Some(&DbcFieldType::U32) && schema.first() != Some(&DbcFieldType::I32) {
+            return Err("DBC schemas must start with an integer ID column".to_string());
+        }
+
+        let records_start = 4 + 16;
+        let records_size = header.record_count.checked_mul(header.record_size)
+            .ok_or(format!("DBC has too many records ({} of {} bytes)", header.record_count, header.record_size))?;
+        let strings_start = records_start + records_size as usize;
+        let strings_end = strings_start.checked_add(header.string_block_size as usize)
+            .ok_or(format!("DBC string block size {} out of bounds", header.string_block_size))?;
+        let strings = data.get(strings_start..strings_end)
+            .ok_or(format!("DBC string block at {} out of bounds", strings_start))?;
+
+        let mut rows = Vec::with_capacity(header.record_count as usize);
+        let mut ids = HashMap::with_capacity(header.record_count as usize);
+        for i in 0..header.record_count as usize {
+            let mut offset = records_start + i * header.record_size as usize;
+            let mut row = Vec::with_capacity(schema.len());
+            for field in schema {
+                let bytes = &data[offset..offset + field.size()];
+                let word = || u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                row.push(match field {
+                    DbcFieldType::U32 => DbcValue::U32(word()),
+                    DbcFieldType::I32 => DbcValue::I32(word() as i32),
+                    DbcFieldType::F32 => DbcValue::F32(f32::from_bits(word())),
+                    DbcFieldType::U8 => DbcValue::U8(bytes[0]),
+                    DbcFieldType::String => DbcValue::String(read_dbc_string(strings, word() as usize)?),
+                });
+                offset += field.size();
+            }
+            let id = match row[0] {
+                DbcValue::U32(id) => id,
+                DbcValue::I32(id) => id as u32,
+                _ => unreachable!(),
+            };
+            ids.insert(id, rows.len());
+            rows.push(row);
+        }
+        Ok(DbcTable { rows, ids })
+    }
+
+    pub fn get_row(&self, id: u32) -> Option<&[DbcValue]> {
+        let index = *self.ids.get(&id)?;
+        Some(&self.rows[index])
+    }
+
+    pub fn get_u32(&self, id: u32, column: usize) -> Option<u32> {
+        match self.get_row(id)?.get(column)? {
+            DbcValue::U32(v) => Some(*v),
+            DbcValue::I32(v) => Some(*v as u32),
+            DbcValue::U8(v) => Some(*v as u32),
+            _ => None,
+        }
+    }
+
+    pub fn get_f32(&self, id: u32, column: usize) -> Option<f32> {
+        match self.get_row(id)?.get(column)? {
+            DbcValue::F32(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn get_string(&self, id: u32, column: usize) -> Option<&str> {
+        match self.get_row(id)?.get(column)? {
+            DbcValue::String(v) => Some(v.as_str()),
+            _ => None,
+        }
+    }
+}
+
+fn read_dbc_string(strings: &[u8], offset: usize) -> Result<String, String> {
+    let bytes = strings.get(offset..)
+        .ok_or(format!("DBC string offset {} out of bounds", offset))?;
+    let chars = bytes.split(|b| *b == 0).next().unwrap_or(&[]);
+    Ok(String::from_utf8_lossy(chars).to_string())
+}
+
+#[derive(DekuRead, Debug, Clone)]
+#[wasm_bindgen(js_name = "WowMapRecord", getter_with_clone)]
+#[deku(ctx = "db2: Wdc4Db2File")]
+pub struct MapRecord {
+    #[deku(reader = "db2.read_string(deku::reader, 0)")]
+    pub directory: String,
+    #[deku(reader = "db2.read_string(deku::reader, 1)")]
+    pub name: String,
+    #[deku(reader = "db2.read_field(deku::reader, 8)")]
+    pub instance_type: u8,
+    #[deku(reader = "db2.read_field(deku::reader, 13)")]
+    pub parent_map_id: i16,
+    #[deku(reader = "db2.read_field(deku::reader, 21)")]
+    pub wdt_file_data_id: u32,
+}
+
+#[derive(DekuRead, Debug, Clone)]
+#[deku(ctx = "db2: Wdc4Db2File")]
+pub struct GroundEffectTextureRecord {
+    #[deku(reader = "db2.read_vec(deku::reader, 0)")]
+    pub doodad_ids: Vec<u16>,
+    #[deku(reader = "db2.read_vec(deku::reader, 1)")]
+    pub doodad_weights: Vec<u8>,
+    #[deku(reader = "db2.read_field(deku::reader, 2)")]
+    pub density: u8,
+    #[deku(reader = "db2.read_field(deku::reader, 3)")]
+    pub _sound: u32,
+}
+
+#[derive(DekuRead, Debug, Clone)]
+#[deku(ctx = "db2: Wdc4Db2File")]
+pub struct GroundEffectDoodadRecord {
+    #[deku(reader = "db2.read_field(deku::reader, 0)")]
+    pub model_file_id: u32,
+    #[deku(reader = "db2.read_field(deku::reader, 1)")]
+    pub flags: u8,
+    #[deku(reader = "db2.read_field(deku::reader, 2)")]
+    pub animation_scale: f32,
+    #[deku(reader = "db2.read_field(deku::reader, 3)")]
+    pub push_scale: f32,
+}
+
+impl DatabaseTable<GroundEffectTextureRecord> {
+    // Resolves a terrain layer's effect_id into the doodads it scatters and
+    // their weights, skipping empty slots
+    pub fn get_ground_effect_doodads<'a>(&self, effect_id: u32, doodads: &'a DatabaseTable<GroundEffectDoodadRecord>) -> Vec<(&'a GroundEffectDoodadRecord, u8)> {
+        let Some(texture) = self.get_record(effect_id) else {
+            return Vec::new();
+        };
+        texture.doodad_ids.iter()
+            .zip(texture.doodad_weights.iter())
+            .filter_map(|(&doodad_id, &weight)| {
+                let doodad = doodads.get_record(doodad_id as u32)?;
+                Some((doodad, weight))
+            })
+            .collect()
+    }
 }
 
 #[derive(DekuRead, Debug, Clone)]
@@ -776,6 +986,11 @@ pub struct Database {
     light_params: DatabaseTable<LightParamsRecord>,
     light_skyboxes: DatabaseTable<LightSkyboxRecord>,
     liquid_types: DatabaseTable<LiquidType>,
+    // the rest are only loaded by the scenes that need them
+    maps: Option<DatabaseTable<MapRecord>>,
+    ground_effect_textures: Option<DatabaseTable<GroundEffectTextureRecord>>,
+    ground_effect_doodads: Option<DatabaseTable<GroundEffectDoodadRecord>>,
+    dbc_tables: HashMap<String, DbcTable>,
 }
 
 #[wasm_bindgen(js_class = "WowDatabase")]
@@ -798,9 +1013,73 @@ impl Database {
             light_params,
             liquid_types,
             light_skyboxes,
+            maps: None,
+            ground_effect_textures: None,
+            ground_effect_doodads: None,
+            dbc_tables: HashMap::new(),
         })
     }
 
+    pub fn load_maps(&mut self, map_db: &[u8]) -> Result<(), String> {
+        self.maps = Some(DatabaseTable::new(map_db)?);
+        Ok(())
+    }
+
+    pub fn load_ground_effects(&mut self, ground_effect_texture_db: &[u8], ground_effect_doodad_db: &[u8]) -> Result<(), String> {
+        self.ground_effect_textures = Some(DatabaseTable::new(ground_effect_texture_db)?);
+        self.ground_effect_doodads = Some(DatabaseTable::new(ground_effect_doodad_db)?);
+        Ok(())
+    }
+
+    // Loads a legacy WDBC database under the given name, with its schema as
+    // a DbcFieldType format string
+    pub fn load_dbc(&mut self, name: String, data: &[u8], format: &str) -> Result<(), String> {
+        let schema = DbcFieldType::parse_format(format)?;
+        self.dbc_tables.insert(name, DbcTable::new(data, &schema)?);
+        Ok(())
+    }
+
+    pub fn get_map(&self, map_id: u32) -> Option<MapRecord> {
+        self.maps.as_ref()?.get_record(map_id).cloned()
+    }
+
+    pub fn get_map_id_by_directory(&self, directory: &str) -> Option<u32> {
+        self.maps.as_ref()?.iter()
+            .find(|(_, map)| map.directory.eq_ignore_ascii_case(directory))
+            .map(|(id, _)| id)
+    }
+
+    pub fn get_ground_effect_doodads(&self, effect_id: u32) -> Vec<GroundEffectDoodad> {
+        let (Some(textures), Some(doodads)) = (&self.ground_effect_textures, &self.ground_effect_doodads) else {
+            return Vec::new();
+        };
+        textures.get_ground_effect_doodads(effect_id, doodads).into_iter()
+            .map(|(doodad, weight)| GroundEffectDoodad {
+                model_file_id: doodad.model_file_id,
+                weight,
+                flags: doodad.flags,
+                animation_scale: doodad.animation_scale,
+                push_scale: doodad.push_scale,
+            })
+            .collect()
+    }
+
+    pub fn get_ground_effect_density(&self, effect_id: u32) -> Option<u8> {
+        Some(self.ground_effect_textures.as_ref()?.get_record(effect_id)?.density)
+    }
+
+    pub fn get_dbc_u32(&self, name: &str, id: u32, column: usize) -> Option<u32> {
+        self.dbc_tables.get(name)?.get_u32(id, column)
+    }
+
+    pub fn get_dbc_f32(&self, name: &str, id: u32, column: usize) -> Option<f32> {
+        self.dbc_tables.get(name)?.get_f32(id, column)
+    }
+
+    pub fn get_dbc_string(&self, name: &str, id: u32, column: usize) -> Option<String> {
+        self.dbc_tables.get(name)?.get_string(id, column).map(|s| s.to_string())
+    }
+
     fn get_default_light(&self, map_id: u16, time: u32) -> LightResult {
         let origin = Vec3::new(0.0);
         let default_light = self.lights.records.iter()
@@ -949,6 +1228,15 @@ pub struct SkyboxMetadata {
     pub weight: f32,
 }
 
+#[wasm_bindgen(js_name = "WowGroundEffectDoodad")]
+pub struct GroundEffectDoodad {
+    pub model_file_id: u32,
+    pub weight: u8,
+    pub flags: u8,
+    pub animation_scale: f32,
+    pub push_scale: f32,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -980,6 +1268,39 @@ mod test {
         dbg!(&db.get_record(41).unwrap().name);
     }
 
+    #[test]
+    fn test_dbc() {
+        let mut data = Vec::new();
+        data.extend(b"WDBC");
+        for v in [2u32, 3, 12, 9] {
+            data.extend(v.to_le_bytes());
+        }
+        data.extend(1u32.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend(0.5f32.to_le_bytes());
+        data.extend(7u32.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(2.0f32.to_le_bytes());
+        data.extend(b"\0Azeroth\0");
+
+        let schema = [DbcFieldType::U32, DbcFieldType::String, DbcFieldType::F32];
+        let table = DbcTable::new(&data, &schema).unwrap();
+        assert_eq!(table.get_string(1, 1), Some("Azeroth"));
+        assert_eq!(table.get_f32(1, 2), Some(0.5));
+        assert_eq!(table.get_string(7, 1), Some(""));
+        assert_eq!(table.get_u32(7, 0), Some(7));
+        assert!(table.get_row(3).is_none());
+        assert_eq!(DbcFieldType::parse_format("usf").unwrap(), schema);
+
+        // a record count and size whose product overflows
+        let mut data = Vec::new();
+        data.extend(b"WDBC");
+        for v in [0x10000u32, 3, 0x10000, 0] {
+            data.extend(v.to_le_bytes());
+        }
+        assert!(DbcTable::new(&data, &schema).is_err());
+    }
+
     #[test]
     fn test_skybox() {
         let sheep_path = "../data/WorldOfWarcraft/sheep0";
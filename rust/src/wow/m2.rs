@@ -6,6 +6,7 @@ use deku::prelude::*;
 use wasm_bindgen::prelude::*;
 use crate::wow::{animation::*, common::parse, particles::Emitter};
 
+use super::skin::{Skin, SkinLookupTables, SkinRenderBatch};
 use super::common::{
     fixed_precision_6_9_to_f32, parse_array, AABBox, ChunkedData, Fixedi16, Quat, Vec2, Vec3, WowArray, WowCharArray
 };
@@ -37,7 +38,7 @@ pub struct M2Header {
     materials: WowArray<M2Material>,
     bone_lookup_table: WowArray<u16>,
     texture_lookup_table: WowArray<u16>,
-    _texture_unit_lookup_table: WowArray<u16>,
+    texture_coord_lookup_table: WowArray<u16>,
     transparency_lookup_table: WowArray<u16>,
     texture_transforms_lookup_table: WowArray<u16>,
     pub bounding_box: AABBox,
@@ -55,7 +56,8 @@ pub struct M2Header {
     _camera_lookup_table: WowArray<u16>,
     _ribbon_emitters: WowArray<()>,
     particle_emitters: WowArray<ParticleEmitter>,
-    _blend_map_overrides: WowArray<u16>,
+    #[deku(cond = "*flags & 0x08 != 0")]
+    blend_mode_overrides: Option<WowArray<u16>>,
 }
 
 impl M2Header {
@@ -136,7 +138,20 @@ impl M2Header {
         let vertex_data_start = self.vertices.offset as usize;
         let vertex_data_size = self.vertices.count as usize * M2::get_vertex_stride();
         let vertex_data_end = vertex_data_start + vertex_data_size;
-        Ok(m2_data[vertex_data_start..vertex_data_end].to_vec())
+        m2_data.get(vertex_data_start..vertex_data_end)
+            .map(|vertex_data| vertex_data.to_vec())
+            .ok_or(format!("{} vertices at {} are out of bounds", self.vertices.count, vertex_data_start))
+    }
+
+    fn get_texture_coord_lookup_table(&self, m2_data: &[u8]) -> Result<Vec<u16>, String> {
+        self.texture_coord_lookup_table.to_vec(m2_data)
+    }
+
+    fn get_blend_mode_overrides(&self, m2_data: &[u8]) -> Result<Vec<u16>, String> {
+        match &self.blend_mode_overrides {
+            Some(overrides) => overrides.to_vec(m2_data),
+            None => Ok(Vec::new()),
+        }
     }
 
     fn get_texture_lookup_table(&self, m2_data: &[u8]) -> Result<Vec<u16>, String> {
@@ -209,6 +224,8 @@ pub struct M2 {
     legacy_textures: Option<Vec<LegacyTexture>>,
     vertex_data: Option<Vec<u8>>,
     texture_lookup_table: Option<Vec<u16>>,
+    texture_coord_lookup_table: Option<Vec<u16>>,
+    blend_mode_overrides: Option<Vec<u16>>,
     bone_lookup_table: Option<Vec<u16>>,
     texture_transforms_lookup_table: Option<Vec<u16>>,
     transparency_lookup_table: Option<Vec<u16>>,
@@ -219,30 +236,39 @@ pub struct M2 {
 #[wasm_bindgen(js_class = "WowM2")]
 impl M2 {
     pub fn new(data: &[u8]) -> Result<M2, String> {
-        let mut chunked_data = ChunkedData::new(data);
-        let (header_chunk, chunk_data) = chunked_data.next()
-            .ok_or("no header chunk".to_string())?;
-        assert_eq!(&header_chunk.magic, b"MD21");
-        let (_, header) = M2Header::from_bytes((chunk_data, 0))
-            .map_err(|e| format!("{:?}", e))?;
-
         let mut txid: Option<Vec<u32>> = None;
         let mut sfid: Option<Vec<u32>> = None;
         let mut txac: Option<Vec<u16>> = None;
         let mut exp2_unallocated: Option<WowArray<Exp2Record>> = None;
-        for (chunk, chunk_data) in &mut chunked_data {
-            match &chunk.magic {
-                b"TXID" => txid = Some(parse_array(chunk_data, 4)?),
-                b"SFID" => sfid = Some(parse_array(chunk_data, 4)?),
-                b"TXAC" => txac = Some(parse_array(chunk_data, 2)?),
-                b"EXP2" => exp2_unallocated = Some(parse(chunk_data)?),
-                _ => {},
+
+        // Pre-Legion files are just the MD20 block, with pointers relative to
+        // the start of the file and skins found by filename
+        let m2_data = if data.starts_with(b"MD20") {
+            sfid = Some(Vec::new());
+            data
+        } else {
+            let mut chunked_data = ChunkedData::new(data);
+            let (header_chunk, _) = chunked_data.next()
+                .ok_or("no header chunk".to_string())?;
+            if &header_chunk.magic != b"MD21" {
+                return Err(format!("expected MD21 chunk, got {:?}", header_chunk.magic));
+            }
+            for (chunk, chunk_data) in &mut chunked_data {
+                match &chunk.magic {
+                    b"TXID" => txid = Some(parse_array(chunk_data, 4)?),
+                    b"SFID" => sfid = Some(parse_array(chunk_data, 4)?),
+                    b"TXAC" => txac = Some(parse_array(chunk_data, 2)?),
+                    b"EXP2" => exp2_unallocated = Some(parse(chunk_data)?),
+                    _ => {},
+                }
             }
-        }
 
-        // M2 pointers are relative to the end of the MD21 block, which seems to
-        // always be 16 bytes in
-        let m2_data = &data[8..];
+            // M2 pointers are relative to the end of the MD21 block, which seems to
+            // always be 16 bytes in
+            &data[8..]
+        };
+        let (_, header) = M2Header::from_bytes((m2_data, 0))
+            .map_err(|e| format!("{:?}", e))?;
 
         let mut exp2_allocated = None;
         if let Some(exp2_unallocated) = exp2_unallocated {
@@ -290,6 +316,8 @@ impl M2 {
             materials: header.get_materials(m2_data)?,
             vertex_data: Some(header.get_vertex_data(m2_data)?),
             texture_lookup_table: Some(header.get_texture_lookup_table(m2_data)?),
+            texture_coord_lookup_table: Some(header.get_texture_coord_lookup_table(m2_data)?),
+            blend_mode_overrides: Some(header.get_blend_mode_overrides(m2_data)?),
             bone_lookup_table: Some(header.get_bone_lookup_table(m2_data)?),
             particle_emitters: Some(particle_emitters),
             legacy_textures: Some(legacy_textures),
@@ -319,6 +347,20 @@ impl M2 {
         self.texture_lookup_table.take().expect("M2 texture lookup table already taken")
     }
 
+    pub fn take_texture_coord_lookup(&mut self) -> Vec<u16> {
+        self.texture_coord_lookup_table.take().expect("M2 texture coord lookup table already taken")
+    }
+
+    // Replaces the blend modes that M2ModelBatch shader ids imply, indexed by
+    // the batch's shader_id. Empty unless the model's flags ask for it.
+    pub fn take_blend_mode_overrides(&mut self) -> Vec<u16> {
+        self.blend_mode_overrides.take().expect("M2 blend mode overrides already taken")
+    }
+
+    pub fn get_flags(&self) -> M2Flags {
+        M2Flags::new(self.flags)
+    }
+
     pub fn take_bone_lookup(&mut self) -> Vec<u16> {
         self.bone_lookup_table.take().expect("M2 bone lookup table already taken")
     }
@@ -343,6 +385,25 @@ impl M2 {
     pub fn take_vertex_data(&mut self) -> Vec<u8> {
         self.vertex_data.take().expect("M2 vertex data already taken")
     }
+
+    // Merges a skin profile's batches with this M2's materials and lookup
+    // tables. Must be called before any of the lookup tables are taken.
+    pub fn get_skin_batches(&self, skin: &Skin) -> Result<Vec<SkinRenderBatch>, String> {
+        let materials: Vec<(M2BlendingMode, u16)> = self.materials.iter()
+            .map(|mat| (mat.blending_mode, mat.flags))
+            .collect();
+        let lookups = SkinLookupTables {
+            textures: self.texture_lookup_table.as_ref()
+                .ok_or("M2 texture lookup table already taken".to_string())?,
+            texture_coords: self.texture_coord_lookup_table.as_ref()
+                .ok_or("M2 texture coord lookup table already taken".to_string())?,
+            texture_weights: self.transparency_lookup_table.as_ref()
+                .ok_or("M2 transparency lookup table already taken".to_string())?,
+            texture_transforms: self.texture_transforms_lookup_table.as_ref()
+                .ok_or("M2 texture transform lookup table already taken".to_string())?,
+        };
+        skin.resolve_batches(&materials, &lookups)
+    }
 }
 
 #[derive(DekuRead)]
@@ -414,6 +475,28 @@ impl M2BoneFlags {
     }
 }
 
+#[wasm_bindgen(js_name = "WowM2Flags")]
+pub struct M2Flags {
+    pub tilt_x: bool,
+    pub tilt_y: bool,
+    pub use_texture_combiner_combos: bool,
+    pub load_phys_data: bool,
+    pub camera_related: bool,
+}
+
+#[wasm_bindgen(js_class = "WowM2Flags")]
+impl M2Flags {
+    pub fn new(x: u32) -> Self {
+        Self {
+            tilt_x:                      (x & 0x001) > 0,
+            tilt_y:                      (x & 0x002) > 0,
+            use_texture_combiner_combos: (x & 0x008) > 0,
+            load_phys_data:              (x & 0x020) > 0,
+            camera_related:              (x & 0x100) > 0,
+        }
+    }
+}
+
 #[wasm_bindgen(js_name = "WowM2MaterialFlags")]
 pub struct M2MaterialFlags {
     pub unlit: bool,
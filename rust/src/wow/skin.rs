@@ -4,6 +4,7 @@ use super::common::{
     WowArray,
     Vec3,
 };
+use super::m2::M2BlendingMode;
 
 #[wasm_bindgen(js_name = "WowSkinSubmesh")]
 #[derive(Debug, DekuRead, Clone)]
@@ -37,7 +38,7 @@ impl SkinSubmesh {
 pub struct SkinProfile {
     vertices: WowArray<u16>,
     indices: WowArray<u16>,
-    _bones: WowArray<[u8; 4]>,
+    bones: WowArray<[u8; 4]>,
     submeshes: WowArray<SkinSubmesh>,
     batches: WowArray<ModelBatch>,
     pub _bone_count_max: u32,
@@ -49,6 +50,7 @@ pub struct Skin {
     pub submeshes: Vec<SkinSubmesh>,
     pub batches: Vec<ModelBatch>,
     indices: Option<Vec<u16>>,
+    bone_indices: Option<Vec<u8>>,
     _profile: SkinProfile,
 }
 
@@ -69,7 +71,21 @@ impl Skin {
         let mut indices = Vec::with_capacity(local_vertex_indices.len());
 
         for local_idx in local_vertex_indices {
-            indices.push(global_vertex_indices[local_idx as usize]);
+            let global_idx = global_vertex_indices.get(local_idx as usize)
+                .ok_or(format!("skin index {} out of bounds ({} vertices)", local_idx, global_vertex_indices.len()))?;
+            indices.push(*global_idx);
+        }
+
+        // per-vertex indices into the M2's bone lookup table, in the same
+        // order as the skin's vertex list
+        let bone_indices = profile.bones.to_vec(data)
+            .map_err(|e| format!("{:?}", e))?
+            .concat();
+
+        for batch in &batches {
+            if batch.skin_submesh_index as usize >= submeshes.len() {
+                return Err(format!("batch references submesh {}, but skin only has {}", batch.skin_submesh_index, submeshes.len()));
+            }
         }
 
         Ok(Skin {
@@ -77,12 +93,117 @@ impl Skin {
             submeshes,
             _profile: profile,
             indices: Some(indices),
+            bone_indices: Some(bone_indices),
         })
     }
 
     pub fn take_indices(&mut self) -> Vec<u16> {
         self.indices.take().expect("Skin indices already taken")
     }
+
+    pub fn take_bone_indices(&mut self) -> Vec<u8> {
+        self.bone_indices.take().expect("Skin bone indices already taken")
+    }
+}
+
+// A ModelBatch with its submesh range, material and lookup table indirections
+// resolved against the owning M2
+#[wasm_bindgen(js_name = "WowSkinRenderBatch")]
+#[derive(Debug, Clone, Copy)]
+pub struct SkinRenderBatch {
+    pub batch_index: u32,
+    pub index_start: u32, // in bytes, like SkinSubmesh::get_index_start
+    pub index_count: u32,
+    pub vertex_start: u32,
+    pub vertex_count: u32,
+    pub pixel_shader: PixelShader,
+    pub vertex_shader: VertexShader,
+    pub blending_mode: M2BlendingMode,
+    pub material_flags: u16,
+    pub priority_plane: u8,
+    pub material_layer: u16,
+    pub color_index: u16,
+    pub texture_count: u16,
+    pub ignore_texture_weight: bool,
+    textures: [Option<u16>; 4],
+    texture_coords: [Option<u16>; 4],
+    texture_weights: [Option<u16>; 4],
+    texture_transforms: [Option<u16>; 4],
+}
+
+#[wasm_bindgen(js_class = "WowSkinRenderBatch")]
+impl SkinRenderBatch {
+    // index into the M2's texture list
+    pub fn get_texture_index(&self, n: usize) -> Option<u16> {
+        *self.textures.get(n)?
+    }
+
+    // which UV set (or 0xffff for environment mapping) texture n samples from
+    pub fn get_texture_coord_index(&self, n: usize) -> Option<u16> {
+        *self.texture_coords.get(n)?
+    }
+
+    // index into the animation manager's texture weights
+    pub fn get_texture_weight_index(&self, n: usize) -> Option<u16> {
+        *self.texture_weights.get(n)?
+    }
+
+    // index into the animation manager's texture transforms
+    pub fn get_texture_transform_index(&self, n: usize) -> Option<u16> {
+        *self.texture_transforms.get(n)?
+    }
+}
+
+pub(crate) struct SkinLookupTables<'a> {
+    pub textures: &'a [u16],
+    pub texture_coords: &'a [u16],
+    pub texture_weights: &'a [u16],
+    pub texture_transforms: &'a [u16],
+}
+
+impl Skin {
+    pub(crate) fn resolve_batches(&self, materials: &[(M2BlendingMode, u16)], lookups: &SkinLookupTables) -> Result<Vec<SkinRenderBatch>, String> {
+        let mut result = Vec::with_capacity(self.batches.len());
+        for (i, batch) in self.batches.iter().enumerate() {
+            let submesh = &self.submeshes[batch.skin_submesh_index as usize];
+            let &(blending_mode, material_flags) = materials.get(batch.material_index as usize)
+                .ok_or(format!("batch {} references material {}, but M2 only has {}", i, batch.material_index, materials.len()))?;
+
+            let resolve = |table: &[u16], combo_index: u16| -> [Option<u16>; 4] {
+                let mut values = [None; 4];
+                for (n, value) in values.iter_mut().enumerate().take(batch.texture_count as usize) {
+                    *value = table.get(combo_index as usize + n).cloned();
+                }
+                values
+            };
+
+            result.push(SkinRenderBatch {
+                batch_index: i as u32,
+                index_start: submesh.get_index_start(),
+                index_count: submesh.index_count as u32,
+                vertex_start: submesh.vertex_start as u32,
+                vertex_count: submesh.vertex_count as u32,
+                pixel_shader: batch.get_pixel_shader(),
+                vertex_shader: batch.get_vertex_shader(),
+                blending_mode,
+                material_flags,
+                priority_plane: batch.priority_plane,
+                material_layer: batch.material_layer,
+                color_index: batch.color_index,
+                texture_count: batch.texture_count,
+                ignore_texture_weight: batch.flags & 0x40 > 0,
+                textures: resolve(lookups.textures, batch.texture_combo_index),
+                texture_coords: resolve(lookups.texture_coords, batch.texture_coord_combo_index),
+                texture_weights: resolve(lookups.texture_weights, batch.texture_weight_combo_index),
+                texture_transforms: resolve(lookups.texture_transforms, batch.texture_transform_combo_index),
+            });
+        }
+
+        // draw in the same order as the client: by priority plane, then
+        // material layer, keeping file order otherwise
+        result.sort_by_key(|batch| (batch.priority_plane, batch.material_layer));
+        Ok(result)
+    }
 }
 
 #[wasm_bindgen(js_name = "WowVertexShader")]
@@ -1,8 +1,12 @@
 use deku::{prelude::*};
 use wasm_bindgen::prelude::*;
 
+use super::adt::TILE_SIZE;
 use super::common::{ChunkedData, AABBox, Vec3, parse};
 
+pub const MAP_TILES: usize = 64;
+pub const MAP_SIZE: f32 = TILE_SIZE * (MAP_TILES / 2) as f32;
+
 #[wasm_bindgen(js_name = "WowWdt", getter_with_clone)]
 pub struct Wdt {
     pub header: Mphd,
@@ -23,19 +27,25 @@ impl Wdt {
             match &chunk.magic {
                 b"NIAM" => {
                     let size = 2 * 4;
+                    if chunk_data.len() < 4096 * size {
+                        return Err(format!("WDT MAIN chunk too small ({} bytes)", chunk_data.len()));
+                    }
                     for i in 0..4096 {
                         area_infos.push(parse(&chunk_data[i*size..(i+1)*size])?)
                     }
                 },
                 b"DIAM" => {
                     let size = 8 * 4;
+                    if chunk_data.len() < 4096 * size {
+                        return Err(format!("WDT MAID chunk too small ({} bytes)", chunk_data.len()));
+                    }
                     for i in 0..4096 {
                         map_filedata_ids.push(parse(&chunk_data[i*size..(i+1)*size])?)
                     }
                 },
                 b"DHPM" => header = Some(parse(chunk_data)?),
                 b"FDOM" => global_wmo = Some(parse(chunk_data)?),
-                _ => println!("skipping {}", chunk.magic_str()),
+                _ => {},
             }
         }
         if area_infos.is_empty() || map_filedata_ids.is_empty() {
@@ -67,6 +77,52 @@ impl Wdt {
     pub fn get_all_map_data(&self) -> Vec<MapFileDataIDs> {
         self.map_filedata_ids.clone()
     }
+
+    pub fn get_area_info(&self, x: usize, y: usize) -> Option<AreaInfo> {
+        if x >= MAP_TILES || y >= MAP_TILES {
+            return None;
+        }
+        self.area_infos.get(y * MAP_TILES + x).cloned()
+    }
+
+    pub fn has_tile(&self, x: usize, y: usize) -> bool {
+        if x >= MAP_TILES || y >= MAP_TILES {
+            return false;
+        }
+        let i = y * MAP_TILES + x;
+        self.area_infos[i].has_adt() && self.map_filedata_ids[i].root_adt != 0
+    }
+
+    // Returns the (x, y) ADT coordinates containing a world-space position,
+    // as the inverse of how ADT chunk positions are laid out
+    pub fn get_tile_for_position(x: f32, y: f32) -> Option<Vec<usize>> {
+        let tile_x = ((MAP_SIZE - y) / TILE_SIZE).floor();
+        let tile_y = ((MAP_SIZE - x) / TILE_SIZE).floor();
+        let range = 0.0..MAP_TILES as f32;
+        if !range.contains(&tile_x) || !range.contains(&tile_y) {
+            return None;
+        }
+        Some(vec![tile_x as usize, tile_y as usize])
+    }
+
+    // Returns the (x, y) pairs, flattened, of every existing ADT within
+    // radius tiles of the given tile, nearest first, for streaming in
+    pub fn get_tiles_near(&self, center_x: usize, center_y: usize, radius: usize) -> Vec<usize> {
+        let mut tiles = Vec::new();
+        let min_x = center_x.saturating_sub(radius);
+        let min_y = center_y.saturating_sub(radius);
+        let max_x = (center_x + radius).min(MAP_TILES - 1);
+        let max_y = (center_y + radius).min(MAP_TILES - 1);
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                if self.has_tile(x, y) {
+                    tiles.push((x, y));
+                }
+            }
+        }
+        tiles.sort_by_key(|&(x, y)| x.abs_diff(center_x).max(y.abs_diff(center_y)));
+        tiles.iter().flat_map(|&(x, y)| [x, y]).collect()
+    }
 }
 
 #[wasm_bindgen(js_name = "WowGlobalWmoDefinition")]
@@ -96,8 +152,12 @@ impl AreaInfo {
         (self.flags & 0b01) == 1
     }
 
+    pub fn has_adt(&self) -> bool {
+        (self.flags & 0b01) > 0
+    }
+
     pub fn is_loaded(&self) -> bool {
-        (self.flags & 0b10) == 1
+        (self.flags & 0b10) > 0
     }
 }
 
@@ -136,4 +196,14 @@ mod tests {
         let data = std::fs::read("../data/wotlk/world/maps/tanarisinstance/tanarisinstance.wdt").unwrap();
         dbg!(Wdt::new(&data).unwrap().header.flags);
     }
+
+    #[test]
+    fn test_tile_for_position() {
+        // the center of the map is the corner between tiles 31 and 32
+        let eps = 1.0;
+        assert_eq!(Wdt::get_tile_for_position(eps, eps), Some(vec![31, 31]));
+        assert_eq!(Wdt::get_tile_for_position(-eps, -eps), Some(vec![32, 32]));
+        assert_eq!(Wdt::get_tile_for_position(MAP_SIZE - eps, MAP_SIZE - eps), Some(vec![0, 0]));
+        assert_eq!(Wdt::get_tile_for_position(MAP_SIZE + eps, 0.0), None);
+    }
 }
@@ -90,10 +90,14 @@ pub struct Wmo {
 impl Wmo {
     pub fn new(data: &[u8]) -> Result<Wmo, String> {
         let mut chunked_data = ChunkedData::new(data);
-        let (mver, _) = chunked_data.next().unwrap();
-        assert_eq!(mver.magic_str(), "REVM");
-        let (mhdr, mhdr_data) = chunked_data.next().unwrap();
-        assert_eq!(mhdr.magic_str(), "DHOM");
+        let (mver, _) = chunked_data.next().ok_or("WMO file was empty")?;
+        if &mver.magic != b"REVM" {
+            return Err(format!("expected MVER chunk, got {}", mver.magic_str()));
+        }
+        let (mhdr, mhdr_data) = chunked_data.next().ok_or("WMO file didn't have MOHD chunk")?;
+        if &mhdr.magic != b"DHOM" {
+            return Err(format!("expected MOHD chunk, got {}", mhdr.magic_str()));
+        }
         let header: WmoHeader = parse(mhdr_data)?;
         let mut momt: Option<Vec<WmoMaterial>> = None;
         let mut mogi: Option<Vec<GroupInfo>> = None;
@@ -125,11 +129,7 @@ impl Wmo {
                 b"TPOM" => maybe_portals = Some(parse_array(chunk_data, 20)?),
                 b"RPOM" => maybe_portal_refs = Some(parse_array(chunk_data, 8)?),
                 b"IDOM" => modi = Some(parse_array(chunk_data, 4)?),
-                b"DIFG" => {
-                    let ids: Vec<u32> = parse_array(chunk_data, 4)?;
-                    dbg!(ids.len(), header.get_flags().lod, header.num_lod);
-                    gfid = Some(ids);
-                }
+                b"DIFG" => gfid = Some(parse_array(chunk_data, 4)?),
                 b"DVAM" => mavd = parse_array(chunk_data, 0x30)?,
                 b"GVAM" => mavg = parse_array(chunk_data, 0x30)?,
                 b"ISOM" => mosi = Some(parse(chunk_data)?),
@@ -141,15 +141,15 @@ impl Wmo {
                     skybox_name = Some(String::from_utf8_lossy(chars).to_string());
                 }
                 b"SDOM" => mods = parse_array(chunk_data, 0x20)?,
-                _ => println!("skipping {} chunk", chunk.magic_str()),
+                _ => {},
             }
         }
-        let portal_vertices = maybe_portal_vertices.expect("WMO didn't have portal refs");
+        let portal_vertices = maybe_portal_vertices.ok_or("WMO file didn't have MOPV chunk")?;
         let portals = maybe_portals
-            .expect("WMO didn't have portals")
+            .ok_or("WMO file didn't have MOPT chunk")?
             .iter()
             .map(|portal| PortalData::new(&portal, &portal_vertices))
-            .collect();
+            .collect::<Result<Vec<PortalData>, String>>()?;
         Ok(Wmo {
             flags: WmoHeaderFlags::new(header.flags),
             header,
@@ -162,7 +162,7 @@ impl Wmo {
             skybox_file_id: mosi.map(|m| m.skybox_file_id),
             skybox_name,
             doodad_sets: mods,
-            portal_refs: maybe_portal_refs.expect("WMO didn't have portal refs"),
+            portal_refs: maybe_portal_refs.ok_or("WMO file didn't have MOPR chunk")?,
             portals,
             group_text,
             global_ambient_volumes: mavg,
@@ -398,6 +398,9 @@ impl Wmo {
             num_vertices: group.num_vertices,
             num_uv_bufs: group.num_uv_bufs,
             num_color_bufs: group.num_color_bufs,
+            trans_batch_count: group.header.trans_batch_count,
+            int_batch_count: group.header.int_batch_count,
+            ext_batch_count: group.header.ext_batch_count,
         }
     }
 
@@ -424,8 +427,18 @@ impl Wmo {
         ]
     }
 
+    pub fn get_num_doodad_sets(&self) -> usize {
+        self.doodad_sets.len()
+    }
+
+    pub fn get_doodad_set_name(&self, doodad_set_id: usize) -> Option<String> {
+        self.doodad_sets.get(doodad_set_id).map(|set| set.get_name())
+    }
+
     pub fn get_doodad_set_refs(&self, mut doodad_set_id: usize) -> Vec<u32> {
-        let default_set = &self.doodad_sets[0];
+        let Some(default_set) = self.doodad_sets.first() else {
+            return Vec::new();
+        };
         if doodad_set_id >= self.doodad_sets.len() {
             doodad_set_id = 0;
         }
@@ -481,12 +494,13 @@ pub struct PortalData {
 }
 
 impl PortalData {
-    pub fn new(portal: &Portal, portal_vertices: &[f32]) -> PortalData {
+    pub fn new(portal: &Portal, portal_vertices: &[f32]) -> Result<PortalData, String> {
         let plane: Plane = (&portal.plane).into();
 
         let verts_start = 3 * portal.start_vertex as usize;
         let verts_end = verts_start + 3 * portal.count as usize;
-        let verts = &portal_vertices[verts_start..verts_end];
+        let verts = portal_vertices.get(verts_start..verts_end)
+            .ok_or(format!("portal vertices {}..{} out of bounds", portal.start_vertex, portal.start_vertex + portal.count))?;
         let mut vertices = Vec::new();
         for i in 0..verts.len() / 3 {
             let v = Vec3::from_column_slice(&verts[i * 3..i * 3 + 3]);
@@ -513,11 +527,11 @@ impl PortalData {
         let mut aabb = AABB::default();
         aabb.set_from_points(&vertices);
 
-        PortalData {
+        Ok(PortalData {
             vertices,
             aabb,
             plane,
-        }
+        })
     }
 
     // projects the portal vertices onto a 2D plane determined by the portal's plane. returns
@@ -638,6 +652,10 @@ pub struct WmoGroupDescriptor {
     pub num_vertices: usize,
     pub num_uv_bufs: usize,
     pub num_color_bufs: usize,
+    // batches are stored transparent first, then interior, then exterior
+    pub trans_batch_count: u16,
+    pub int_batch_count: u16,
+    pub ext_batch_count: u16,
 }
 
 #[derive(Debug, Clone)]
@@ -664,9 +682,11 @@ pub struct WmoGroup {
 impl WmoGroup {
     pub fn new(data: &[u8]) -> Result<WmoGroup, String> {
         let mut chunked_data = ChunkedData::new(data);
-        let (mver, _) = chunked_data.next().unwrap();
-        assert_eq!(mver.magic_str(), "REVM");
-        let (_, mhdr_data) = chunked_data.next().unwrap();
+        let (mver, _) = chunked_data.next().ok_or("WMO group file was empty")?;
+        if &mver.magic != b"REVM" {
+            return Err(format!("expected MVER chunk, got {}", mver.magic_str()));
+        }
+        let (_, mhdr_data) = chunked_data.next().ok_or("WMO group file didn't have MOGP chunk")?;
         let header: WmoGroupHeader = parse(mhdr_data)?;
         let flags = WmoGroupFlags::new(header.flags);
         let mut maybe_indices: Option<Vec<u16>> = None;
@@ -707,7 +727,7 @@ impl WmoGroup {
                 }
                 b"ABOM" => batches = Some(parse_array(chunk_data, 24)?),
                 b"RDOM" => doodad_refs = Some(parse_array(chunk_data, 2)?),
-                _ => println!("skipping {}", chunk.magic_str()),
+                _ => {},
             }
         }
 
@@ -1106,12 +1126,19 @@ impl WmoLiquid {
 
 #[derive(DekuRead, Debug, Clone)]
 pub struct DoodadSet {
-    _name: [u8; 0x14],
+    name: [u8; 0x14],
     pub start_index: u32,
     #[deku(pad_bytes_after = "4")]
     pub count: u32,
 }
 
+impl DoodadSet {
+    pub fn get_name(&self) -> String {
+        let chars = self.name.split(|n| *n == 0).next().unwrap_or(&[]);
+        String::from_utf8_lossy(chars).to_string()
+    }
+}
+
 #[derive(DekuRead, Debug, Clone)]
 pub struct BspNode {
     pub flags: u16,
@@ -1269,6 +1296,17 @@ pub struct MaterialBatch {
     pub material_id: u8,
 }
 
+#[wasm_bindgen(js_class = "WowWmoMaterialBatch")]
+impl MaterialBatch {
+    pub fn get_material_id(&self) -> u16 {
+        if self.use_material_id_large > 0 {
+            self.material_id_large
+        } else {
+            self.material_id as u16
+        }
+    }
+}
+
 #[derive(DekuRead, Debug, Clone)]
 pub struct WmoGroupHeader {
     pub group_name: u32,             // offset to MOGN
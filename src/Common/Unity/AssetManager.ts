@@ -1,6 +1,6 @@
 
 import { vec2, vec3 } from 'gl-matrix';
-import { UnityAABB, UnityAssetFile, UnityAssetFileObject, UnityChannelInfo, UnityClassID, UnityGLTextureSettings, UnityMaterial, UnityMesh, UnityMeshCompression, UnityPPtr, UnityShader, UnityStreamingInfo, UnitySubMesh, UnityTexture2D, UnityTextureColorSpace, UnityTextureFormat, UnityVersion, UnityVertexFormat, CrunchTexture } from '../../../rust/pkg/noclip_support';
+import { UnityAABB, UnityAssetFile, UnityAssetFileObject, UnityChannelInfo, UnityClassID, UnityGLTextureSettings, UnityMaterial, UnityMesh, UnityMeshCompression, UnityPPtr, UnityShader, UnityStreamingInfo, SubMeshInfo, UnityTexture2D, UnityTextureColorSpace, UnityTextureFormat, UnityVersion, UnityVertexFormat, CrunchTexture, EtcFormat } from '../../../rust/pkg/noclip_support';
 import ArrayBufferSlice from '../../ArrayBufferSlice.js';
 import { Color, TransparentBlack, colorNewFromRGBA } from '../../Color.js';
 import { DataFetcher } from '../../DataFetcher.js';
@@ -293,15 +293,31 @@ export class AssetFile {
         return materialData;
     };
 
-    private fetchFromCache<T extends Destroyable>(assetSystem: UnityAssetSystem, pathID: BigInt, createFunc: CreateFunc<T>): Promise<T | null> {
+    private fetchFromCache<T extends Destroyable>(assetSystem: UnityAssetSystem, pathID: BigInt, createFunc: CreateFunc<T>, shareByContent = false): Promise<T | null> {
         if (this.promiseCache.has(pathID))
             return this.promiseCache.get(pathID)! as Promise<T>;
 
-        const promise = this.fetchObject(pathID).then(async objData => {
-            const v = await createFunc(assetSystem, objData);
-            this.dataCache.set(pathID, v);
-            return v;
-        });
+        let promise: Promise<T | null>;
+        if (shareByContent) {
+            // Objects that don't refer to anything by PPtr parse the same
+            // wherever their bytes are, so e.g. a texture duplicated into
+            // several sharedassets files is only loaded once. The object
+            // cache owns the result. A streamed object's StreamingInfo path
+            // and offset are part of its bytes, but the path is relative, so
+            // the key also has the directory it's resolved from.
+            promise = this.fetchObject(pathID).then((objData) => {
+                const hash = rust.xxhash64(objData.data, BigInt(0)).toString(16);
+                const directory = this.path.substring(0, this.path.lastIndexOf('/'));
+                const key = `${directory}/${objData.classID}_${objData.data.byteLength}_${hash}`;
+                return assetSystem.objectCache.fetchByKey(key, () => createFunc(assetSystem, objData));
+            });
+        } else {
+            promise = this.fetchObject(pathID).then(async objData => {
+                const v = await createFunc(assetSystem, objData);
+                this.dataCache.set(pathID, v);
+                return v;
+            });
+        }
         this.promiseCache.set(pathID, promise);
         return promise;
     }
@@ -311,9 +327,9 @@ export class AssetFile {
             return null;
 
         if (type === UnityAssetResourceType.Mesh)
-            return this.fetchFromCache(assetSystem, pathID, this.createMeshData) as Promise<ResType<T>>;
+            return this.fetchFromCache(assetSystem, pathID, this.createMeshData, true) as Promise<ResType<T>>;
         else if (type === UnityAssetResourceType.Texture2D)
-            return this.fetchFromCache(assetSystem, pathID, this.createTexture2DData) as Promise<ResType<T>>;
+            return this.fetchFromCache(assetSystem, pathID, this.createTexture2DData, true) as Promise<ResType<T>>;
         else if (type === UnityAssetResourceType.Material)
             return this.fetchFromCache(assetSystem, pathID, this.createMaterialData) as Promise<ResType<T>>;
         else if (type === UnityAssetResourceType.Shader)
@@ -333,6 +349,32 @@ export class AssetFile {
     }
 }
 
+// Parsed resources by key, which the cache owns
+export class UnityObjectCache {
+    private promiseCache = new Map<string, Promise<Destroyable | null>>();
+    private dataCache = new Map<string, Destroyable | null>();
+
+    public fetchByKey<T extends Destroyable>(key: string, create: () => Promise<T | null>): Promise<T | null> {
+        if (this.promiseCache.has(key))
+            return this.promiseCache.get(key)! as Promise<T | null>;
+
+        const promise = create().then((v) => {
+            this.dataCache.set(key, v);
+            return v;
+        });
+        this.promiseCache.set(key, promise);
+        return promise;
+    }
+
+    public destroy(device: GfxDevice): void {
+        for (const v of this.dataCache.values())
+            if (v !== null)
+                v.destroy(device);
+        this.dataCache.clear();
+        this.promiseCache.clear();
+    }
+}
+
 function pptrToKey(file: AssetFile, p: UnityPPtr): string {
     return JSON.stringify([file.path, Number(p.path_id)]);
 }
@@ -341,6 +383,9 @@ export class UnityAssetSystem {
     private assetFiles = new Map<string, AssetFile>();
     private shaderPPtrToName = new Map<string, string>();
     public renderCache: GfxRenderCache;
+    // Meshes and textures shared between files by a hash of their object's
+    // bytes, for games that duplicate them across many files
+    public objectCache = new UnityObjectCache();
 
     constructor(public device: GfxDevice, private dataFetcher: DataFetcher, private basePath: string, public version: UnityVersion) {
         this.renderCache = new GfxRenderCache(this.device);
@@ -439,6 +484,7 @@ export class UnityAssetSystem {
         this.renderCache.destroy();
         for (const v of this.assetFiles.values())
             v.destroy(device);
+        this.objectCache.destroy(device);
     }
 }
 
@@ -462,10 +508,10 @@ export enum UnityChannel {
 
 export class UnityMeshData {
     public bbox = new Geometry.AABB();
-    public submeshes: UnitySubMesh[];
+    public submeshes: SubMeshInfo[];
     public indexBufferStride: number;
 
-    constructor(public inputLayout: GfxInputLayout, public vertexBuffers: GfxVertexBufferDescriptor[], public indexBuffer: GfxIndexBufferDescriptor, bbox: UnityAABB, submeshes: UnitySubMesh[], public indexBufferFormat: GfxFormat) {
+    constructor(public inputLayout: GfxInputLayout, public vertexBuffers: GfxVertexBufferDescriptor[], public indexBuffer: GfxIndexBufferDescriptor, bbox: UnityAABB, submeshes: SubMeshInfo[], public indexBufferFormat: GfxFormat) {
         const center = vec3.fromValues(bbox.center.x, bbox.center.y, bbox.center.z);
         const extent = vec3.fromValues(bbox.extent.x, bbox.extent.y, bbox.extent.z);
         this.bbox.setFromCenterAndHalfExtents(center, extent);
@@ -497,7 +543,7 @@ function loadCompressedMesh(device: GfxDevice, mesh: UnityMesh): UnityMeshData {
     const indexData = makeStaticDataBuffer(device, GfxBufferUsage.Index, indices.buffer);
     const vertexBuffers = coalesceBuffer(device, GfxBufferUsage.Vertex, [new ArrayBufferSlice(vertices.buffer), new ArrayBufferSlice(normals.buffer)]);
     const indexBuffer = { buffer: indexData, byteOffset: 0 };
-    return new UnityMeshData(layout, vertexBuffers, indexBuffer, mesh.local_aabb, mesh.submeshes, indexBufferFormat);
+    return new UnityMeshData(layout, vertexBuffers, indexBuffer, mesh.local_aabb, mesh.get_submesh_info().submeshes, indexBufferFormat);
 }
 
 function vertexFormatToGfxFormatBase(vertexFormat: UnityVertexFormat): GfxFormat {
@@ -568,7 +614,37 @@ function loadMesh(device: GfxDevice, mesh: UnityMesh): UnityMeshData {
     const indexBufferFormat = (mesh.index_format === rust.UnityIndexFormat.UInt32) ? GfxFormat.U32_R : GfxFormat.U16_R;
     const layout = device.createInputLayout({ vertexAttributeDescriptors, vertexBufferDescriptors: layoutBufferDescriptors, indexBufferFormat });
     const indexBuffer = { buffer: indexData, byteOffset: 0 };
-    return new UnityMeshData(layout, stateBufferDescriptors, indexBuffer, mesh.local_aabb, mesh.submeshes, indexBufferFormat);
+    return new UnityMeshData(layout, stateBufferDescriptors, indexBuffer, mesh.local_aabb, mesh.get_submesh_info().submeshes, indexBufferFormat);
+}
+
+// ETC textures are decoded to RGBA8, since desktop GPUs can't sample them
+function getEtcFormat(fmt: UnityTextureFormat): EtcFormat | null {
+    if (fmt === rust.UnityTextureFormat.EtcRGB4)
+        return rust.EtcFormat.Etc1RGB;
+    else if (fmt === rust.UnityTextureFormat.Etc2RGB4)
+        return rust.EtcFormat.Etc2RGB;
+    else if (fmt === rust.UnityTextureFormat.Etc2RGB4PunchthroughAlpha)
+        return rust.EtcFormat.Etc2RGBA1;
+    else
+        return null;
+}
+
+// Block size of ASTC textures, which are also decoded in software; HDR ones
+// decode to half floats
+function getAstcBlockSize(fmt: UnityTextureFormat): [number, number, boolean] | null {
+    if (fmt === rust.UnityTextureFormat.Astc4x4) return [4, 4, false];
+    else if (fmt === rust.UnityTextureFormat.Astc5x5) return [5, 5, false];
+    else if (fmt === rust.UnityTextureFormat.Astc6x6) return [6, 6, false];
+    else if (fmt === rust.UnityTextureFormat.Astc8x8) return [8, 8, false];
+    else if (fmt === rust.UnityTextureFormat.Astc10x10) return [10, 10, false];
+    else if (fmt === rust.UnityTextureFormat.Astc12x12) return [12, 12, false];
+    else if (fmt === rust.UnityTextureFormat.AstcHdr4x4) return [4, 4, true];
+    else if (fmt === rust.UnityTextureFormat.AstcHdr5x5) return [5, 5, true];
+    else if (fmt === rust.UnityTextureFormat.AstcHdr6x6) return [6, 6, true];
+    else if (fmt === rust.UnityTextureFormat.AstcHdr8x8) return [8, 8, true];
+    else if (fmt === rust.UnityTextureFormat.AstcHdr10x10) return [10, 10, true];
+    else if (fmt === rust.UnityTextureFormat.AstcHdr12x12) return [12, 12, true];
+    else return null;
 }
 
 function translateTextureFormat(fmt: UnityTextureFormat, colorSpace: UnityTextureColorSpace): GfxFormat {
@@ -604,6 +680,16 @@ function translateTextureFormat(fmt: UnityTextureFormat, colorSpace: UnityTextur
         return GfxFormat.BC7;
     else if (fmt === rust.UnityTextureFormat.BC7 && colorSpace === rust.UnityTextureColorSpace.SRGB)
         return GfxFormat.BC7_SRGB;
+    else if (getAstcBlockSize(fmt) !== null && getAstcBlockSize(fmt)![2])
+        return GfxFormat.F16_RGBA;
+    else if (getAstcBlockSize(fmt) !== null && colorSpace === rust.UnityTextureColorSpace.Linear)
+        return GfxFormat.U8_RGBA_NORM;
+    else if (getAstcBlockSize(fmt) !== null && colorSpace === rust.UnityTextureColorSpace.SRGB)
+        return GfxFormat.U8_RGBA_SRGB;
+    else if (getEtcFormat(fmt) !== null && colorSpace === rust.UnityTextureColorSpace.Linear)
+        return GfxFormat.U8_RGBA_NORM;
+    else if (getEtcFormat(fmt) !== null && colorSpace === rust.UnityTextureColorSpace.SRGB)
+        return GfxFormat.U8_RGBA_SRGB;
     else
         throw new Error(`unknown texture format ${fmt} and colorspace ${colorSpace} combo`);
 }
@@ -654,6 +740,11 @@ function calcLevelSize(fmt: UnityTextureFormat, w: number, h: number): number {
             return count * 16;
         else
             throw "whoops";
+    } else if (getEtcFormat(fmt) !== null) {
+        return Math.ceil(w / 4) * Math.ceil(h / 4) * 8;
+    } else if (getAstcBlockSize(fmt) !== null) {
+        const [blockW, blockH] = getAstcBlockSize(fmt)!;
+        return Math.ceil(w / blockW) * Math.ceil(h / blockH) * 16;
     } else if (fmt === rust.UnityTextureFormat.Alpha8) {
         return w * h;
     } else if (fmt === rust.UnityTextureFormat.RGB24) {
@@ -724,6 +815,27 @@ export class UnityTexture2DData {
                 levels.push(crunched.decode_level(data, i));
             }
             device.uploadTextureData(this.gfxTexture, 0, levels);
+        } else if (getEtcFormat(header.texture_format) !== null) {
+            const etcFormat = getEtcFormat(header.texture_format)!;
+            const levels = calcLevels(data, header.texture_format, header.width, header.height, header.mip_count);
+            let w = header.width, h = header.height;
+            for (let i = 0; i < levels.length; i++) {
+                levels[i] = rust.decode_etc(etcFormat, levels[i] as Uint8Array, w, h);
+                w = Math.max(w >>> 1, 1);
+                h = Math.max(h >>> 1, 1);
+            }
+            device.uploadTextureData(this.gfxTexture, 0, levels);
+        } else if (getAstcBlockSize(header.texture_format) !== null) {
+            const [blockW, blockH, hdr] = getAstcBlockSize(header.texture_format)!;
+            const levels = calcLevels(data, header.texture_format, header.width, header.height, header.mip_count);
+            let w = header.width, h = header.height;
+            for (let i = 0; i < levels.length; i++) {
+                const level = levels[i] as Uint8Array;
+                levels[i] = hdr ? rust.decode_astc_hdr(level, w, h, blockW, blockH) : rust.decode_astc(level, w, h, blockW, blockH);
+                w = Math.max(w >>> 1, 1);
+                h = Math.max(h >>> 1, 1);
+            }
+            device.uploadTextureData(this.gfxTexture, 0, levels);
         } else {
             const oData = imageFormatConvertData(data, header.texture_format);
             const levels = calcLevels(oData, header.texture_format, header.width, header.height, header.mip_count);
@@ -812,15 +924,17 @@ export class UnityMaterialData {
             texEnv.free();
         }
 
-        for (const name of this.header.get_color_keys()) {
-            const color = this.header.get_color_by_key(name)!;
-            this.colorsByName.set(name, colorNewFromRGBA(color.r, color.g, color.b, color.a));
-            color.free();
-        }
-
-        for (const name of this.header.get_float_keys()) {
-            this.floatsByName.set(name, this.header.get_float_by_key(name)!);
-        }
+        const arrays = this.header.get_property_arrays();
+        const colorNames = arrays.color_names.length > 0 ? arrays.color_names.split('\n') : [];
+        const colorValues = arrays.color_values;
+        for (let i = 0; i < colorNames.length; i++)
+            this.colorsByName.set(colorNames[i], colorNewFromRGBA(colorValues[i*4+0], colorValues[i*4+1], colorValues[i*4+2], colorValues[i*4+3]));
+
+        const floatNames = arrays.float_names.length > 0 ? arrays.float_names.split('\n') : [];
+        const floatValues = arrays.float_values;
+        for (let i = 0; i < floatNames.length; i++)
+            this.floatsByName.set(floatNames[i], floatValues[i]);
+        arrays.free();
 
         const shaderPPtr = this.header.shader;
         this.shader = await assetSystem.fetchResource(UnityAssetResourceType.Shader, this.location, shaderPPtr);
@@ -10,7 +10,7 @@ import { assert, assertExists, fallbackUndefined, nArray } from "../../util.js";
 import { ViewerRenderInput } from "../../viewer.js";
 import { AssetFile, AssetLocation, AssetObjectData, UnityAssetResourceType, UnityAssetSystem, UnityChannel, UnityMaterialData, UnityMeshData, createUnityAssetSystem } from "./AssetManager.js";
 import { rust } from "../../rustlib.js";
-import { UnityMeshRenderer, UnityVec3, UnityQuaternion, UnityTransform, UnityPPtr, UnityGameObject, UnityMeshFilter, UnityAssetFileObject, UnityVersion } from "../../../rust/pkg/noclip_support.js";
+import { UnityMeshRenderer, UnityTransform, TransformInfo, PPtrInfo, UnityPPtr, UnityGameObject, UnityMeshFilter, UnityAssetFileObject, UnityVersion } from "../../../rust/pkg/noclip_support.js";
 import { AABB } from "../../Geometry.js";
 
 export abstract class UnityComponent {
@@ -24,14 +24,6 @@ export abstract class UnityComponent {
     }
 }
 
-function vec3FromVec3f(dst: vec3, src: UnityVec3): void {
-    vec3.set(dst, src.x, src.y, src.z);
-}
-
-function quatFromQuaternion(dst: quat, src: UnityQuaternion): void {
-    quat.set(dst, src.x, src.y, src.z, src.w);
-}
-
 const noclipSpaceFromUnitySpace = mat4.fromValues(
      -1, 0, 0, 0,
       0, 1, 0, 0,
@@ -48,21 +40,24 @@ export class Transform extends UnityComponent {
 
     public modelMatrix = mat4.create();
 
-    constructor(level: UnityLevel, public gameObject: GameObject, private wasmObj: UnityTransform) {
+    private info: TransformInfo;
+
+    constructor(level: UnityLevel, public gameObject: GameObject, wasmObj: UnityTransform) {
         super();
-        vec3FromVec3f(this.localPosition, wasmObj.local_position);
-        quatFromQuaternion(this.localRotation, wasmObj.local_rotation);
-        vec3FromVec3f(this.localScale, wasmObj.local_scale);
+        this.info = wasmObj.get_info();
+        wasmObj.free();
+        vec3.copy(this.localPosition, this.info.local_position);
+        quat.copy(this.localRotation, this.info.local_rotation);
+        vec3.copy(this.localScale, this.info.local_scale);
     }
 
     public override spawn(level: UnityLevel): void {
         super.spawn(level);
-        this.parent = level.findComponentByPPtr(this.wasmObj.parent);
-        this.children = this.wasmObj.children.map((pptr) => {
+        this.parent = level.findComponentByPPtr(this.info.parent);
+        this.children = this.info.children.map((pptr) => {
             return assertExists(level.findComponentByPPtr<Transform>(pptr));
         });
-        this.wasmObj.free();
-        this.wasmObj = null!;
+        this.info = null!;
     }
 
     public updateModelMatrix(): void {
@@ -313,7 +308,7 @@ class UnityLevel {
         return fallbackUndefined(this.gameObjects.find((obj) => obj.location.pathID === pptr.path_id), null);
     }
 
-    public findComponentByPPtr<T extends UnityComponent>(pptr: UnityPPtr): T | null {
+    public findComponentByPPtr<T extends UnityComponent>(pptr: UnityPPtr | PPtrInfo): T | null {
         assert(pptr.file_index === 0);
         if (Number(pptr.path_id) === 0)
             return null;
@@ -36,6 +36,9 @@ function getBitmapTextureFormat(format: BitmapFormat): GfxFormat {
         case rust.BitmapFormat.P8Bump:
         case rust.BitmapFormat.Y8:
         case rust.BitmapFormat.A8y8:
+        case rust.BitmapFormat.Ay8:
+        case rust.BitmapFormat.A1r5g5b5:
+        case rust.BitmapFormat.A4r4g4b4:
             return GfxFormat.U8_RGBA_NORM;
         default:
             throw new Error(`couldn't recognize bitmap format ${rust.BitmapFormat[format]}`);